@@ -0,0 +1,373 @@
+use std::sync::Arc;
+
+use axum::{Json, Router};
+use axum::extract::{DefaultBodyLimit, Path, Query, State};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::routing::{delete, get, post, put};
+use futures::{Stream, StreamExt};
+use hyper::StatusCode;
+use hyper::header::HeaderValue;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+use uuid::Uuid;
+
+#[cfg(feature = "fs-storage")]
+use crate::api::blob_storage_admin::blob_storage_routes;
+use crate::maven::advisories::ReplacementAdvisory;
+use crate::maven::coordinates::{MavenArtifactId, MavenGroupId};
+use crate::maven::metadata_backup::{MetadataBackup, RestoreReport};
+use crate::maven::plugin_prefix::PluginPrefixConflict;
+use crate::maven::prefetch_plan::PrefetchPlan;
+use crate::maven::remote_repo::{MavenPluginMetadata, TtlOverride};
+use crate::maven::version_blocking::VersionBlockingRule;
+use crate::repository_manager::{RepositoryManager, validate_repository_name};
+use crate::util::audit_log::{AuditEvent, AuditEventKind};
+use crate::util::change_kind::ChangeKind;
+use crate::util::deploy_metrics::DeployStats;
+use crate::util::latency_metrics::{LatencyReport, REQUEST_LATENCIES};
+use crate::util::tasks::{TaskState, TaskStatus};
+use crate::util::transfer_metrics::TransferMetricsSnapshot;
+
+/// backups contain all metadata, so they exceed the default request size limit
+const MAX_BACKUP_SIZE: usize = 1024*1024*1024;
+
+/// Routes for the admin API, to be nested below a common prefix like '/api/v1/admin'
+pub(crate) fn admin_routes() -> Router<Arc<RepositoryManager>> {
+    let router = Router::new()
+        .route("/blocked-versions", get(get_blocked_versions).put(put_blocked_version).delete(delete_blocked_version))
+        .route("/advisories", get(get_advisories).put(put_advisory).delete(delete_advisory))
+        .route("/plugins/:group_id", get(get_plugins).put(put_plugin))
+        .route("/plugins/:group_id/:artifact_id", delete(delete_plugin))
+        .route("/ttl-overrides", get(get_ttl_overrides).put(put_ttl_override))
+        .route("/ttl-overrides/:group_prefix", delete(delete_ttl_override))
+        .route("/clones", get(get_clones))
+        .route("/clones/:name", put(put_clone).delete(delete_clone))
+        .route("/metadata-backup", get(get_metadata_backup).put(put_metadata_backup).layer(DefaultBodyLimit::max(MAX_BACKUP_SIZE)))
+        .route("/deploy-metrics", get(get_deploy_metrics))
+        .route("/upstream-metrics", get(get_upstream_metrics))
+        .route("/transfer-metrics", get(get_transfer_metrics))
+        .route("/latency-metrics", get(get_latency_metrics))
+        .route("/warm-up", post(post_warm_up))
+        .route("/tasks", get(get_tasks))
+        .route("/tasks/:id", get(get_task).delete(delete_task))
+        .route("/tasks/:id/events", get(get_task_events))
+        .route("/audit-log", get(get_audit_log));
+
+    #[cfg(feature = "fs-storage")]
+    let router = router
+        .nest("/blob-storage", blob_storage_routes());
+
+    router
+}
+
+/// '201 Created' for inserts, '200 OK' for both updates and no-ops
+fn status_for_change(change_kind: ChangeKind) -> StatusCode {
+    match change_kind {
+        ChangeKind::Inserted => StatusCode::CREATED,
+        ChangeKind::Updated | ChangeKind::Unchanged => StatusCode::OK,
+    }
+}
+
+async fn get_blocked_versions(State(state): State<Arc<RepositoryManager>>) -> Json<Vec<VersionBlockingRule>> {
+    Json(state.blocked_versions.rules())
+}
+
+async fn put_blocked_version(State(state): State<Arc<RepositoryManager>>, Json(rule): Json<VersionBlockingRule>) -> StatusCode {
+    let subject = format!("{}:{}:{}", rule.group_id.0, rule.artifact_id.0, rule.version_pattern);
+    let details = rule.message.clone();
+
+    let change_kind = state.blocked_versions.add_rule(rule);
+    if change_kind.is_change() {
+        state.audit_log.record(AuditEventKind::BlockingRuleAdded, subject, details);
+    }
+    status_for_change(change_kind)
+}
+
+#[derive(Deserialize)]
+struct VersionPatternTarget {
+    group_id: MavenGroupId,
+    artifact_id: MavenArtifactId,
+    version_pattern: String,
+}
+
+async fn delete_blocked_version(State(state): State<Arc<RepositoryManager>>, Json(target): Json<VersionPatternTarget>) -> StatusCode {
+    if state.blocked_versions.remove_rule(&target.group_id, &target.artifact_id, &target.version_pattern) {
+        state.audit_log.record(
+            AuditEventKind::BlockingRuleRemoved,
+            format!("{}:{}:{}", target.group_id.0, target.artifact_id.0, target.version_pattern),
+            "",
+        );
+        StatusCode::NO_CONTENT
+    }
+    else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+async fn get_advisories(State(state): State<Arc<RepositoryManager>>) -> Json<Vec<ReplacementAdvisory>> {
+    Json(state.advisories.advisories())
+}
+
+/// The replacement version is sent as a header, so it must be a valid header value
+async fn put_advisory(State(state): State<Arc<RepositoryManager>>, Json(advisory): Json<ReplacementAdvisory>) -> StatusCode {
+    if HeaderValue::from_str(&advisory.replacement_version).is_err() {
+        return StatusCode::BAD_REQUEST;
+    }
+    let subject = format!("{}:{}:{}", advisory.group_id.0, advisory.artifact_id.0, advisory.version_pattern);
+    let details = advisory.as_text();
+
+    let change_kind = state.advisories.add_advisory(advisory);
+    if change_kind.is_change() {
+        state.audit_log.record(AuditEventKind::AdvisoryAdded, subject, details);
+    }
+    status_for_change(change_kind)
+}
+
+async fn delete_advisory(State(state): State<Arc<RepositoryManager>>, Json(target): Json<VersionPatternTarget>) -> StatusCode {
+    if state.advisories.remove_advisory(&target.group_id, &target.artifact_id, &target.version_pattern) {
+        state.audit_log.record(
+            AuditEventKind::AdvisoryRemoved,
+            format!("{}:{}:{}", target.group_id.0, target.artifact_id.0, target.version_pattern),
+            "",
+        );
+        StatusCode::NO_CONTENT
+    }
+    else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+async fn get_plugins(State(state): State<Arc<RepositoryManager>>, Path(group_id): Path<String>) -> Result<Json<Vec<MavenPluginMetadata>>, StatusCode> {
+    match state.repo.get_group_metadata(&MavenGroupId(group_id)).await {
+        Ok(group_metadata) => Ok(Json(group_metadata.plugins)),
+        Err(e) => {
+            error!("error getting plugins: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn put_plugin(State(state): State<Arc<RepositoryManager>>, Path(group_id): Path<String>, Json(plugin_metadata): Json<MavenPluginMetadata>) -> StatusCode {
+    let subject = format!("{}:{}", group_id, plugin_metadata.artifact_id.0);
+    let details = format!("prefix {}", plugin_metadata.prefix);
+
+    match state.repo.register_plugin(MavenGroupId(group_id), plugin_metadata).await {
+        Ok(change_kind) => {
+            if change_kind.is_change() {
+                state.audit_log.record(AuditEventKind::PluginRegistered, subject, details);
+            }
+            status_for_change(change_kind)
+        }
+        Err(e) if PluginPrefixConflict::of(&e).is_some() => {
+            info!("rejected plugin registration: {}", e);
+            StatusCode::CONFLICT
+        }
+        Err(e) => {
+            error!("error registering plugin: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+async fn delete_plugin(State(state): State<Arc<RepositoryManager>>, Path((group_id, artifact_id)): Path<(String, String)>) -> StatusCode {
+    let subject = format!("{}:{}", group_id, artifact_id);
+
+    match state.repo.unregister_plugin(&MavenGroupId(group_id), &MavenArtifactId(artifact_id)).await {
+        Ok(true) => {
+            state.audit_log.record(AuditEventKind::PluginUnregistered, subject, "");
+            StatusCode::NO_CONTENT
+        }
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(e) => {
+            error!("error unregistering plugin: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+async fn get_ttl_overrides(State(state): State<Arc<RepositoryManager>>) -> Result<Json<Vec<TtlOverride>>, StatusCode> {
+    match state.repo.get_ttl_overrides().await {
+        Ok(ttl_overrides) => Ok(Json(ttl_overrides)),
+        Err(e) => {
+            error!("error getting TTL overrides: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn put_ttl_override(State(state): State<Arc<RepositoryManager>>, Json(ttl_override): Json<TtlOverride>) -> StatusCode {
+    let subject = ttl_override.group_prefix.clone();
+    let details = format!("ttl {}s", ttl_override.ttl_secs);
+
+    match state.repo.set_ttl_override(ttl_override).await {
+        Ok(change_kind) => {
+            if change_kind.is_change() {
+                state.audit_log.record(AuditEventKind::TtlOverrideSet, subject, details);
+            }
+            status_for_change(change_kind)
+        }
+        Err(e) => {
+            error!("error setting TTL override: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+async fn delete_ttl_override(State(state): State<Arc<RepositoryManager>>, Path(group_prefix): Path<String>) -> StatusCode {
+    match state.repo.remove_ttl_override(&group_prefix).await {
+        Ok(true) => {
+            state.audit_log.record(AuditEventKind::TtlOverrideRemoved, group_prefix, "");
+            StatusCode::NO_CONTENT
+        }
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(e) => {
+            error!("error removing TTL override: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+async fn get_clones(State(state): State<Arc<RepositoryManager>>) -> Json<Vec<String>> {
+    Json(state.clone_names())
+}
+
+#[derive(Deserialize)]
+struct CloneQuery {
+    /// a remote or hosted repository, the default repository if omitted
+    source: Option<String>,
+}
+
+/// Freezes a repository's current state as a read-only clone, served below '/clones/{name}/'
+async fn put_clone(State(state): State<Arc<RepositoryManager>>, Path(name): Path<String>, Query(query): Query<CloneQuery>) -> StatusCode {
+    if validate_repository_name(&name).is_err() {
+        return StatusCode::BAD_REQUEST;
+    }
+    let source = query.source.unwrap_or_else(|| state.repo.name().to_string());
+    if !state.repository_names().contains(&source) {
+        return StatusCode::NOT_FOUND;
+    }
+
+    match state.clone_repository(&source, &name).await {
+        Ok(true) => {
+            state.audit_log.record(AuditEventKind::RepositoryCloned, name, format!("from {}", source));
+            StatusCode::CREATED
+        }
+        // clones are immutable, so an existing clone is never replaced
+        Ok(false) => StatusCode::CONFLICT,
+        Err(e) => {
+            error!("error cloning repository: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+async fn delete_clone(State(state): State<Arc<RepositoryManager>>, Path(name): Path<String>) -> StatusCode {
+    if state.remove_clone(&name) {
+        state.audit_log.record(AuditEventKind::RepositoryCloneRemoved, name, "");
+        StatusCode::NO_CONTENT
+    }
+    else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+async fn get_metadata_backup(State(state): State<Arc<RepositoryManager>>) -> Result<Json<MetadataBackup>, StatusCode> {
+    match state.create_metadata_backup().await {
+        Ok(backup) => Ok(Json(backup)),
+        Err(e) => {
+            error!("error creating metadata backup: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RestoreQuery {
+    #[serde(default)]
+    skip_missing: bool,
+}
+
+/// Restores a backup as returned by [get_metadata_backup], replacing all current metadata
+async fn put_metadata_backup(State(state): State<Arc<RepositoryManager>>, Query(query): Query<RestoreQuery>, Json(backup): Json<MetadataBackup>) -> Result<Json<RestoreReport>, (StatusCode, String)> {
+    match state.restore_metadata_backup(backup, query.skip_missing).await {
+        Ok(report) => Ok(Json(report)),
+        // typically failed validation against blob storage
+        Err(e) => Err((StatusCode::CONFLICT, e.to_string())),
+    }
+}
+
+async fn get_deploy_metrics(State(state): State<Arc<RepositoryManager>>) -> Json<Vec<DeployStats>> {
+    Json(state.deploy_metrics.stats())
+}
+
+#[derive(Serialize)]
+struct UpstreamMetrics {
+    /// transfers that were aborted (and retried) for being too slow
+    slow_transfers: u64,
+    /// upstream metadata files that did not match their checksum files
+    metadata_checksum_mismatches: u64,
+}
+
+async fn get_upstream_metrics(State(state): State<Arc<RepositoryManager>>) -> Json<UpstreamMetrics> {
+    Json(UpstreamMetrics {
+        slow_transfers: state.repo.slow_transfer_count(),
+        metadata_checksum_mismatches: state.repo.metadata_checksum_mismatch_count(),
+    })
+}
+
+async fn get_transfer_metrics(State(state): State<Arc<RepositoryManager>>) -> Json<TransferMetricsSnapshot> {
+    Json(state.transfer_metrics.snapshot())
+}
+
+/// Latency percentiles of cache hits, cache misses and metadata requests over the last few
+///  minutes, see [RequestLatencies](crate::util::latency_metrics::RequestLatencies)
+async fn get_latency_metrics() -> Json<LatencyReport> {
+    Json(REQUEST_LATENCIES.report())
+}
+
+/// Starts fetching a prefetch plan's artifacts in the background, see [RepositoryManager::warm_up]
+async fn post_warm_up(State(state): State<Arc<RepositoryManager>>, Json(plan): Json<PrefetchPlan>) -> (StatusCode, Json<TaskRef>) {
+    info!("starting warm-up of {} artifacts", plan.entries.len());
+    let manager = state.clone();
+    let id = state.tasks.spawn("warm-up", |task| async move {
+        Ok(manager.warm_up(&plan, &task).await)
+    });
+    (StatusCode::ACCEPTED, Json(TaskRef { id }))
+}
+
+/// The answer to starting a long-running operation, see [crate::util::tasks::TaskRegistry]
+#[derive(Serialize)]
+pub(crate) struct TaskRef {
+    pub id: Uuid,
+}
+
+async fn get_tasks(State(state): State<Arc<RepositoryManager>>) -> Json<Vec<TaskStatus>> {
+    Json(state.tasks.statuses())
+}
+
+async fn get_task(State(state): State<Arc<RepositoryManager>>, Path(id): Path<Uuid>) -> Result<Json<TaskStatus>, StatusCode> {
+    state.tasks.status(&id)
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Streams the task's status as server-sent events whenever it changes, until it has finished
+async fn get_task_events(State(state): State<Arc<RepositoryManager>>, Path(id): Path<Uuid>) -> Result<Sse<impl Stream<Item = Result<Event, serde_json::Error>>>, StatusCode> {
+    let statuses = state.tasks.watch(&id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Sse::new(statuses.map(|status| Event::default().json_data(status)))
+        .keep_alive(KeepAlive::default()))
+}
+
+/// Only running tasks can be cancelled, finished ones are answered with '409 Conflict'
+async fn delete_task(State(state): State<Arc<RepositoryManager>>, Path(id): Path<Uuid>) -> StatusCode {
+    match state.tasks.cancel(&id) {
+        Some(TaskState::Running) => StatusCode::NO_CONTENT,
+        Some(_) => StatusCode::CONFLICT,
+        None => StatusCode::NOT_FOUND,
+    }
+}
+
+async fn get_audit_log(State(state): State<Arc<RepositoryManager>>) -> Json<Vec<AuditEvent>> {
+    Json(state.audit_log.recent_events())
+}
@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use axum::Json;
+use axum::extract::{Query, State};
+use bytes::{Bytes, BytesMut};
+use futures::StreamExt;
+use hyper::StatusCode;
+use serde::Deserialize;
+use tracing::error;
+
+use crate::maven::coordinates::{MavenArtifactId, MavenArtifactRef, MavenClassifier, MavenCoordinates, MavenGroupId, MavenVersion};
+use crate::maven::jar_diff::JarDiff;
+use crate::maven::jar_index::{index_jar, JarEntry};
+use crate::maven::paths::as_maven_path;
+use crate::maven::version_resolution::is_snapshot;
+use crate::repository_manager::RepositoryManager;
+use crate::util::repo_error::RepoError;
+
+/// artifacts are compared in memory, so larger ones are refused
+const MAX_DIFF_ARTIFACT_SIZE: usize = 256*1024*1024;
+
+#[derive(Deserialize)]
+pub(crate) struct ArtifactDiffQuery {
+    g: String,
+    a: String,
+    from: String,
+    to: String,
+    classifier: Option<String>,
+    /// file extension of the artifacts, default '.jar'
+    extension: Option<String>,
+    /// compares classes by their SHA-256 in addition to their size and CRC-32
+    #[serde(default)]
+    hashes: bool,
+}
+
+/// Compares two versions of an artifact in the default repository by their jar entries, e.g. for
+///  reviewing a release or verifying that a rebuild did not change anything
+pub(crate) async fn artifact_diff(State(state): State<Arc<RepositoryManager>>, Query(query): Query<ArtifactDiffQuery>) -> Result<Json<JarDiff>, (StatusCode, String)> {
+    if query.g.is_empty() || query.a.is_empty() || query.from.is_empty() || query.to.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "'g', 'a', 'from' and 'to' must not be empty".to_string()));
+    }
+    let file_extension = match &query.extension {
+        None => ".jar".to_string(),
+        Some(e) if e.starts_with('.') => e.clone(),
+        Some(e) => format!(".{}", e),
+    };
+    let artifact_ref = |version: &str| MavenArtifactRef {
+        coordinates: MavenCoordinates {
+            group_id: MavenGroupId(query.g.clone()),
+            artifact_id: MavenArtifactId(query.a.clone()),
+            version: MavenVersion::Release(version.to_string()),
+        },
+        classifier: match &query.classifier {
+            Some(classifier) if !classifier.is_empty() => MavenClassifier::Classified(classifier.clone()),
+            _ => MavenClassifier::Unclassified,
+        },
+        file_extension: file_extension.clone(),
+    };
+
+    let from = read_jar_index(&state, artifact_ref(&query.from), query.hashes).await?;
+    let to = read_jar_index(&state, artifact_ref(&query.to), query.hashes).await?;
+    Ok(Json(JarDiff::new(from, to)))
+}
+
+async fn read_jar_index(state: &RepositoryManager, artifact_ref: MavenArtifactRef, hash_classes: bool) -> Result<Vec<JarEntry>, (StatusCode, String)> {
+    // snapshots are compared as their most recent timestamped build
+    let artifact_ref = match is_snapshot(artifact_ref.coordinates.version.unqualified()) {
+        false => artifact_ref,
+        true => match state.resolve_snapshot(&artifact_ref).await {
+            Ok(Some(artifact_ref)) => artifact_ref,
+            Ok(None) => return Err((StatusCode::NOT_FOUND, format!("{} does not exist", as_maven_path(&artifact_ref)))),
+            Err(e) => {
+                error!("error resolving snapshot: {}", e);
+                return Err((StatusCode::INTERNAL_SERVER_ERROR, String::new()));
+            }
+        },
+    };
+    let path = as_maven_path(&artifact_ref);
+
+    let data = match read_artifact(state, &artifact_ref).await {
+        Ok(data) => data,
+        Err(e) if RepoError::is_not_found(&e) => return Err((StatusCode::NOT_FOUND, format!("{} does not exist", path))),
+        Err(e) => {
+            error!("error reading {} for diffing: {}", path, e);
+            return Err((StatusCode::BAD_GATEWAY, format!("{} is not available", path)));
+        }
+    };
+    // parsing and decompressing is CPU bound
+    tokio::task::spawn_blocking(move || index_jar(&data, hash_classes)).await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, format!("{}: {}", path, e)))
+}
+
+async fn read_artifact(state: &RepositoryManager, artifact_ref: &MavenArtifactRef) -> anyhow::Result<Bytes> {
+    let mut blob = state.get_artifact(artifact_ref).await?;
+    let mut result = BytesMut::new();
+    while let Some(chunk) = blob.data.next().await {
+        result.extend_from_slice(&chunk?);
+        if result.len() > MAX_DIFF_ARTIFACT_SIZE {
+            return Err(anyhow!("the artifact is larger than {} bytes", MAX_DIFF_ARTIFACT_SIZE));
+        }
+    }
+    Ok(result.freeze())
+}
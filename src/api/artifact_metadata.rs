@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use axum::Json;
+use axum::extract::{Query, State};
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::maven::coordinates::{MavenArtifactId, MavenGroupId};
+use crate::maven::remote_repo::MavenArtifactMetadata;
+use crate::maven::timestamps::parse_maven_timestamp;
+use crate::maven::version_list::VersionListOptions;
+use crate::repository_manager::RepositoryManager;
+
+/// bounds the work a single request can cause
+const MAX_ARTIFACTS_PER_REQUEST: usize = 1000;
+
+#[derive(Deserialize)]
+pub(crate) struct ArtifactKey {
+    group_id: String,
+    artifact_id: String,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct AsOfQuery {
+    /// a Maven timestamp ('yyyyMMddHHmmss' in UTC) for metadata as it was back then
+    as_of: Option<String>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct ArtifactMetadataEntry {
+    group_id: String,
+    artifact_id: String,
+    /// None if no version of the artifact is known
+    metadata: Option<ArtifactMetadataJson>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct ArtifactMetadataJson {
+    latest_version: String,
+    release_version: Option<String>,
+    versions: Vec<String>,
+    /// number of versions before pagination, see [VersionListOptions]
+    total_versions: usize,
+    last_updated: String,
+}
+impl ArtifactMetadataJson {
+    fn new(metadata: MavenArtifactMetadata, version_list: &VersionListOptions) -> ArtifactMetadataJson {
+        let page = version_list.apply(&metadata.versions);
+        ArtifactMetadataJson {
+            latest_version: metadata.latest_version.unqualified().to_string(),
+            release_version: metadata.release_version.map(|v| v.unqualified().to_string()),
+            versions: page.versions.iter().map(|v| v.unqualified().to_string()).collect(),
+            total_versions: page.total,
+            last_updated: metadata.last_updated,
+        }
+    }
+}
+
+/// Returns the locally known metadata of many artifacts in one call, e.g. for browsing all
+///  artifacts of a group or building a dependency graph. Results are in the order of the request,
+///  and consistent with each other. The query string selects versions for each artifact, and
+///  optionally a point in time for reproducing past dependency resolution.
+pub(crate) async fn artifact_metadata(State(state): State<Arc<RepositoryManager>>, Query(version_list): Query<VersionListOptions>, Query(as_of): Query<AsOfQuery>, Json(keys): Json<Vec<ArtifactKey>>) -> Result<Json<Vec<ArtifactMetadataEntry>>, (StatusCode, String)> {
+    if keys.len() > MAX_ARTIFACTS_PER_REQUEST {
+        return Err((StatusCode::BAD_REQUEST, format!("at most {} artifacts can be requested at once", MAX_ARTIFACTS_PER_REQUEST)));
+    }
+    let as_of = match as_of.as_of {
+        None => None,
+        Some(s) => Some(parse_maven_timestamp(&s)
+            .ok_or_else(|| (StatusCode::BAD_REQUEST, format!("invalid timestamp '{}', must be 'yyyyMMddHHmmss'", s)))?),
+    };
+
+    let artifacts = keys.into_iter()
+        .map(|key| (MavenGroupId(key.group_id), MavenArtifactId(key.artifact_id)))
+        .collect::<Vec<_>>();
+    let metadata = match as_of {
+        None => state.repo.get_artifact_metadata_many(&artifacts).await,
+        Some(as_of) => state.repo.get_artifact_metadata_as_of(&artifacts, as_of).await,
+    };
+    let metadata = metadata
+        .map_err(|e| {
+            error!("error getting artifact metadata: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, String::new())
+        })?;
+
+    Ok(Json(artifacts.into_iter()
+        .zip(metadata)
+        .map(|((group_id, artifact_id), metadata)| ArtifactMetadataEntry {
+            group_id: group_id.0,
+            artifact_id: artifact_id.0,
+            metadata: metadata.map(|metadata| ArtifactMetadataJson::new(metadata, &version_list)),
+        })
+        .collect()))
+}
@@ -0,0 +1,159 @@
+use std::sync::Arc;
+
+use axum::{Json, Router};
+use axum::extract::{Query, State};
+use axum::routing::{get, post};
+use hyper::StatusCode;
+use serde::Deserialize;
+use tracing::{error, warn};
+
+use crate::api::admin::TaskRef;
+use crate::blob::fs_blob_storage::{FsBlobStorageMetricsSnapshot, FsckReport};
+use crate::blob::integrity_manifest::{create_manifest, IntegrityManifest, ManifestVerificationReport, verify_manifest};
+use crate::repository_manager::RepositoryManager;
+use crate::util::audit_log::AuditEventKind;
+
+/// Maintenance operations specific to file system blob storage, nested in the admin API
+pub(crate) fn blob_storage_routes() -> Router<Arc<RepositoryManager>> {
+    Router::new()
+        .route("/orphans", get(get_orphans).delete(delete_orphan))
+        .route("/metrics", get(get_metrics))
+        .route("/gc", post(post_gc))
+        .route("/integrity-manifest", get(get_integrity_manifest))
+        .route("/integrity-manifest/verify", post(post_verify_integrity_manifest))
+}
+
+#[derive(Deserialize)]
+struct OrphanQuery {
+    grace_period_secs: Option<u64>,
+}
+
+/// Lists orphaned data in blob storage without deleting anything
+async fn get_orphans(State(state): State<Arc<RepositoryManager>>, Query(query): Query<OrphanQuery>) -> Result<Json<FsckReport>, (StatusCode, String)> {
+    let fs_blob_storage = state.fs_blob_storage.as_ref()
+        .ok_or((StatusCode::NOT_FOUND, String::new()))?;
+
+    let grace_period = state.orphan_grace_period(query.grace_period_secs)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    match fs_blob_storage.fsck(&grace_period, true, &state.blob_reference_checker()).await {
+        Ok(report) => Ok(Json(report)),
+        Err(e) => {
+            error!("error checking blob storage for orphans: {}", e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, String::new()))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct GcQuery {
+    grace_period_secs: Option<u64>,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Deletes orphaned data now rather than waiting for the scheduled garbage collection, as a task
+///  whose result is the [FsckReport]
+async fn post_gc(State(state): State<Arc<RepositoryManager>>, Query(query): Query<GcQuery>) -> Result<(StatusCode, Json<TaskRef>), (StatusCode, String)> {
+    let fs_blob_storage = state.fs_blob_storage.clone()
+        .ok_or((StatusCode::NOT_FOUND, String::new()))?;
+
+    let grace_period = state.orphan_grace_period(query.grace_period_secs)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let manager = state.clone();
+    let id = state.tasks.spawn("blob gc", |_| async move {
+        fs_blob_storage.fsck(&grace_period, query.dry_run, &manager.blob_reference_checker()).await
+    });
+    Ok((StatusCode::ACCEPTED, Json(TaskRef { id })))
+}
+
+/// fsck and garbage collection counters
+async fn get_metrics(State(state): State<Arc<RepositoryManager>>) -> Result<Json<FsBlobStorageMetricsSnapshot>, StatusCode> {
+    let fs_blob_storage = state.fs_blob_storage.as_ref()
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(fs_blob_storage.metrics().snapshot()))
+}
+
+#[derive(Deserialize)]
+struct PurgeOrphanQuery {
+    /// as reported by the orphan listing
+    path: String,
+    grace_period_secs: Option<u64>,
+}
+
+async fn delete_orphan(State(state): State<Arc<RepositoryManager>>, Query(query): Query<PurgeOrphanQuery>) -> StatusCode {
+    let fs_blob_storage = match state.fs_blob_storage.as_ref() {
+        Some(s) => s,
+        None => return StatusCode::NOT_FOUND,
+    };
+
+    let grace_period = match state.orphan_grace_period(query.grace_period_secs) {
+        Ok(grace_period) => grace_period,
+        Err(e) => {
+            warn!("refusing to purge {}: {}", query.path, e);
+            return StatusCode::BAD_REQUEST;
+        }
+    };
+    match fs_blob_storage.purge_orphan(&query.path, &grace_period, &state.blob_reference_checker()).await {
+        Ok(true) => {
+            state.audit_log.record(AuditEventKind::OrphanPurged, query.path, "");
+            StatusCode::NO_CONTENT
+        }
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(e) => {
+            // the item is not orphaned (any longer) or the path is invalid
+            warn!("refusing to purge {}: {}", query.path, e);
+            StatusCode::CONFLICT
+        }
+    }
+}
+
+/// Hashes all blobs and returns a signed manifest of them, to be kept outside the server and
+///  verified in a later audit. This reads the entire storage, so it can take a while.
+async fn get_integrity_manifest(State(state): State<Arc<RepositoryManager>>) -> Result<Json<IntegrityManifest>, StatusCode> {
+    let fs_blob_storage = state.fs_blob_storage.as_ref()
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let signing_key = state.manifest_signing_key.as_ref()
+        .ok_or(StatusCode::NOT_IMPLEMENTED)?;
+
+    let result = match fs_blob_storage.keys().await {
+        Ok(keys) => create_manifest(fs_blob_storage.as_ref(), &keys, signing_key.as_bytes()).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(manifest) => Ok(Json(manifest)),
+        Err(e) => {
+            error!("error creating integrity manifest: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Compares the blobs in storage to a manifest created by [get_integrity_manifest]. A manifest with
+///  an invalid signature is rejected.
+async fn post_verify_integrity_manifest(State(state): State<Arc<RepositoryManager>>, Json(manifest): Json<IntegrityManifest>) -> Result<Json<ManifestVerificationReport>, StatusCode> {
+    let fs_blob_storage = state.fs_blob_storage.as_ref()
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let signing_key = state.manifest_signing_key.as_ref()
+        .ok_or(StatusCode::NOT_IMPLEMENTED)?;
+
+    if !manifest.has_valid_signature(signing_key.as_bytes()) {
+        warn!("rejecting integrity manifest with an invalid signature");
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let result = match fs_blob_storage.keys().await {
+        Ok(keys) => verify_manifest(fs_blob_storage.as_ref(), &manifest, &keys, signing_key.as_bytes()).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(report) => {
+            state.audit_log.record(AuditEventKind::IntegrityManifestVerified, format!("manifest created {}", manifest.created),
+                                   format!("{} verified, {} missing, {} modified", report.verified, report.missing.len(), report.modified.len()));
+            Ok(Json(report))
+        }
+        Err(e) => {
+            error!("error verifying integrity manifest: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use std::sync::Arc;
+
+use axum::Json;
+use axum::extract::{Query, State};
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::maven::coordinates::{MavenArtifactId, MavenCoordinates, MavenGroupId, MavenVersion};
+use crate::repository_manager::{DeployRefusal, RepositoryManager};
+
+#[derive(Deserialize)]
+pub(crate) struct CanDeployQuery {
+    g: String,
+    a: String,
+    v: String,
+    /// the hosted repository, can be omitted if there is only one
+    repo: Option<String>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct CanDeployAnswer {
+    can_deploy: bool,
+    refusals: Vec<DeployRefusal>,
+}
+
+/// Tells CI whether a deploy would be accepted before it uploads anything. Refused deploys are
+///  answered with '409 Conflict' and the reasons for refusing them.
+pub(crate) async fn can_deploy(State(state): State<Arc<RepositoryManager>>, Query(query): Query<CanDeployQuery>) -> Result<(StatusCode, Json<CanDeployAnswer>), (StatusCode, String)> {
+    if query.g.is_empty() || query.a.is_empty() || query.v.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "'g', 'a' and 'v' must not be empty".to_string()));
+    }
+
+    // a deploy's version is not qualified by a timestamp yet, even for snapshots
+    let coordinates = MavenCoordinates {
+        group_id: MavenGroupId(query.g),
+        artifact_id: MavenArtifactId(query.a),
+        version: MavenVersion::Release(query.v),
+    };
+
+    match state.check_deploy(query.repo.as_deref(), &coordinates).await {
+        Ok(refusals) if refusals.is_empty() => Ok((StatusCode::OK, Json(CanDeployAnswer { can_deploy: true, refusals }))),
+        Ok(refusals) => Ok((StatusCode::CONFLICT, Json(CanDeployAnswer { can_deploy: false, refusals }))),
+        Err(e) => {
+            error!("error checking deploy: {}", e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, String::new()))
+        }
+    }
+}
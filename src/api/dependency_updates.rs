@@ -0,0 +1,108 @@
+use std::sync::Arc;
+
+use axum::Json;
+use axum::extract::{Query, State};
+use futures::StreamExt;
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::maven::coordinates::{MavenArtifactId, MavenGroupId};
+use crate::maven::dependency_updates::{DependencyUpdates, find_updates};
+use crate::repository_manager::RepositoryManager;
+
+/// bounds the work a single request can cause - every dependency can require an upstream request
+const MAX_DEPENDENCIES_PER_REQUEST: usize = 1000;
+/// upstream metadata requests in parallel per report
+const CONCURRENT_LOOKUPS: usize = 8;
+
+#[derive(Deserialize)]
+pub(crate) struct Dependency {
+    group_id: String,
+    artifact_id: String,
+    version: String,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct DependencyUpdatesQuery {
+    /// only suggest updates within the current major version
+    #[serde(default)]
+    same_major: bool,
+}
+
+#[derive(Serialize)]
+pub(crate) struct DependencyUpdatesEntry {
+    group_id: String,
+    artifact_id: String,
+    version: String,
+    #[serde(flatten)]
+    updates: DependencyUpdates,
+}
+
+/// Reports which of a list of dependencies (e.g. a project's dependency list) have newer
+///  releases, based on local and (cached) upstream metadata. Results are in the order of the
+///  request.
+pub(crate) async fn dependency_updates(State(state): State<Arc<RepositoryManager>>, Query(query): Query<DependencyUpdatesQuery>, Json(dependencies): Json<Vec<Dependency>>) -> Result<Json<Vec<DependencyUpdatesEntry>>, (StatusCode, String)> {
+    if dependencies.len() > MAX_DEPENDENCIES_PER_REQUEST {
+        return Err((StatusCode::BAD_REQUEST, format!("at most {} dependencies can be requested at once", MAX_DEPENDENCIES_PER_REQUEST)));
+    }
+    if let Some(dependency) = dependencies.iter().find(|d| d.group_id.is_empty() || d.artifact_id.is_empty() || d.version.is_empty()) {
+        return Err((StatusCode::BAD_REQUEST, format!("incomplete dependency '{}:{}:{}'", dependency.group_id, dependency.artifact_id, dependency.version)));
+    }
+
+    let state = &state;
+    let result = futures::stream::iter(dependencies)
+        .map(|dependency| async move {
+            let group_id = MavenGroupId(dependency.group_id);
+            let artifact_id = MavenArtifactId(dependency.artifact_id);
+            let versions = state.available_versions(&group_id, &artifact_id).await?;
+            let updates = find_updates(&dependency.version, &versions, query.same_major);
+            Ok::<_, anyhow::Error>(DependencyUpdatesEntry {
+                group_id: group_id.0,
+                artifact_id: artifact_id.0,
+                version: dependency.version,
+                updates,
+            })
+        })
+        .buffered(CONCURRENT_LOOKUPS)
+        .collect::<Vec<_>>().await
+        .into_iter()
+        .collect::<anyhow::Result<Vec<_>>>()
+        .map_err(|e| {
+            error!("error finding dependency updates: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, String::new())
+        })?;
+    Ok(Json(result))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use axum::body::Body;
+    use hyper::{Method, Request, StatusCode};
+    use tower::ServiceExt;
+
+    use crate::api::routes;
+    use crate::repository_manager::{parse_repository_config, RepositoryManager, RepositoryManagerConfig};
+
+    #[tokio::test]
+    async fn test_too_many_dependencies() {
+        let manager = Arc::new(RepositoryManager::new(RepositoryManagerConfig {
+            repositories: vec![parse_repository_config("central=remote:http://127.0.0.1:1").unwrap()],
+            ..Default::default()
+        }).unwrap());
+        let dependency = r#"{"group_id":"g","artifact_id":"a","version":"1"}"#;
+        let body = format!("[{}]", vec![dependency; 1001].join(","));
+        let response = routes().with_state(manager)
+            .oneshot(Request::builder()
+                .method(Method::POST)
+                .uri("/api/v1/dependency-updates")
+                .header("content-type", "application/json")
+                .body(Body::from(body))
+                .unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+use hyper::{Body, Response, StatusCode};
+use hyper::header::{CACHE_CONTROL, CONTENT_TYPE};
+use prometheus::TEXT_FORMAT;
+use tracing::error;
+
+use crate::api::repo::status_response;
+use crate::repository_manager::RepositoryManager;
+use crate::util::prometheus_metrics::render;
+
+/// Metrics in the Prometheus text format, for scraping
+pub(crate) async fn metrics(State(state): State<Arc<RepositoryManager>>) -> Response<Body> {
+    if let Err(e) = state.sample_metrics().await {
+        error!("error sampling metrics: {}", e);
+        return status_response(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    Response::builder()
+        .header(CONTENT_TYPE, TEXT_FORMAT)
+        .header(CACHE_CONTROL, "no-store")
+        .body(Body::from(render()))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use hyper::Request;
+    use tower::ServiceExt;
+
+    use crate::api::routes;
+    use crate::repository_manager::{parse_repository_config, RepositoryManagerConfig};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_metrics() {
+        let manager = Arc::new(RepositoryManager::new(RepositoryManagerConfig {
+            repositories: vec![parse_repository_config("metrics-test=remote:http://127.0.0.1:1").unwrap()],
+            ..Default::default()
+        }).unwrap());
+
+        let request = Request::builder().uri("/metrics").body(Body::empty()).unwrap();
+        let response = routes().with_state(manager).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), TEXT_FORMAT);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains(r#"arti_vault_negative_cache_entries{repository="metrics-test"} 0"#), "{}", body);
+    }
+}
@@ -0,0 +1,107 @@
+#[cfg(feature = "admin-api")]
+pub mod admin;
+#[cfg(all(feature = "admin-api", feature = "fs-storage"))]
+pub mod blob_storage_admin;
+pub mod artifact_diff;
+pub mod artifact_metadata;
+pub mod can_deploy;
+pub mod dependency_updates;
+pub mod metrics;
+pub mod platforms;
+pub mod preview;
+pub mod project_info;
+pub mod repo;
+#[cfg(feature = "admin-api")]
+pub mod repository_admin;
+pub mod resolve;
+pub mod webdav;
+
+use std::sync::Arc;
+
+use axum::Router;
+#[cfg(feature = "admin-api")]
+use axum::middleware;
+use axum::routing::{any, get, post};
+
+#[cfg(feature = "admin-api")]
+use crate::api::admin::admin_routes;
+use crate::api::artifact_diff::artifact_diff;
+use crate::api::artifact_metadata::artifact_metadata;
+use crate::api::can_deploy::can_deploy;
+use crate::api::dependency_updates::dependency_updates;
+use crate::api::metrics::metrics;
+use crate::api::platforms::platforms;
+use crate::api::preview::preview;
+use crate::api::project_info::project_info;
+use crate::api::repo::{repo, repo_clone, repo_head, repo_put, repo_root, root};
+#[cfg(feature = "admin-api")]
+use crate::api::repository_admin::{quarantined_blob, repository_routes};
+use crate::api::resolve::resolve;
+use crate::api::webdav::webdav;
+use crate::repository_manager::RepositoryManager;
+#[cfg(feature = "admin-api")]
+use crate::util::cache_control::no_store;
+
+/// The server's HTTP routes, without authentication - see `main` for the layers around them
+pub fn routes() -> Router<Arc<RepositoryManager>> {
+    let app = Router::new()
+        // `GET /` goes to `root`
+        .route("/", get(root))
+        .route("/metrics", get(metrics))
+        .route("/repo/", get(repo_root))
+        .route("/repo/*path", get(repo).head(repo_head).put(repo_put))
+        .route("/clones/:clone_name/*path", get(repo_clone))
+        .route("/webdav/*path", any(webdav))
+        .route("/api/v1/resolve", get(resolve))
+        .route("/api/v1/can-deploy", get(can_deploy))
+        .route("/api/v1/platforms", get(platforms))
+        .route("/api/v1/artifact-metadata", post(artifact_metadata))
+        .route("/api/v1/artifact-diff", get(artifact_diff))
+        .route("/api/v1/dependency-updates", post(dependency_updates))
+        .route("/api/v1/preview/*path", get(preview))
+        .route("/api/v1/project-info/*path", get(project_info));
+
+    #[cfg(feature = "admin-api")]
+    let app = app
+        .nest("/api/v1/admin", admin_routes().layer(middleware::map_response(no_store)))
+        .nest("/api/v1/repositories", repository_routes())
+        .route("/api/v1/quarantined-blobs/:repo/:blob_id", get(quarantined_blob));
+
+    app
+}
+
+/// A repository manager for handler tests, with a remote repository 'central' that has no upstream
+///  and a hosted repository 'internal' to which version 1.0 of com.acme:lib is deployed
+#[cfg(test)]
+pub(crate) mod test_manager {
+    use std::sync::Arc;
+
+    use bytes::Bytes;
+    use sha1::{Digest as _, Sha1};
+
+    use crate::repository_manager::{parse_repository_config, RepositoryManager, RepositoryManagerConfig, RepositoryRef};
+    use crate::util::content_hooks::ContentStream;
+
+    pub(crate) async fn manager(jar: &[u8], pom: &[u8]) -> Arc<RepositoryManager> {
+        let manager = Arc::new(RepositoryManager::new(RepositoryManagerConfig {
+            repositories: vec![
+                parse_repository_config("central=remote:http://127.0.0.1:1").unwrap(),
+                parse_repository_config("internal=hosted").unwrap(),
+            ],
+            ..Default::default()
+        }).unwrap());
+
+        let Some((RepositoryRef::Hosted(hosted), _)) = manager.find_repository("internal/") else { panic!() };
+        for (path, content) in [
+            ("com/acme/lib/1.0/lib-1.0.jar", Bytes::copy_from_slice(jar)),
+            ("com/acme/lib/1.0/lib-1.0.jar.sha1", Bytes::from(hex::encode(Sha1::digest(jar)))),
+            ("com/acme/lib/1.0/lib-1.0.pom", Bytes::copy_from_slice(pom)),
+            ("com/acme/lib/1.0/lib-1.0.pom.sha1", Bytes::from(hex::encode(Sha1::digest(pom)))),
+            ("com/acme/lib/maven-metadata.xml", Bytes::from("<metadata/>")),
+        ] {
+            let data: ContentStream = Box::pin(futures::stream::iter(vec![Ok(content)]));
+            hosted.deploy(path, data).await.unwrap();
+        }
+        manager
+    }
+}
@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use axum::Json;
+use axum::extract::{Query, State};
+use axum::response::{IntoResponse, Response};
+use hyper::StatusCode;
+use hyper::header::{HeaderMap, LOCATION};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::api::resolve::download_url;
+use crate::maven::coordinates::{MavenArtifactId, MavenArtifactRef, MavenClassifier, MavenCoordinates, MavenGroupId, MavenVersion};
+use crate::maven::platform_classifiers::{Arch, Os, platform_of, select_classifiers};
+use crate::maven::version_resolution::is_snapshot;
+use crate::repository_manager::RepositoryManager;
+
+#[derive(Deserialize)]
+pub(crate) struct PlatformsQuery {
+    g: String,
+    a: String,
+    v: String,
+    /// file extension of the artifacts, default '.jar'
+    extension: Option<String>,
+    /// e.g. 'linux', 'osx' or 'windows'
+    os: Option<String>,
+    /// e.g. 'x86_64' or 'aarch_64'
+    arch: Option<String>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct PlatformArtifact {
+    classifier: String,
+    os: Option<Os>,
+    arch: Option<Arch>,
+    download_url: String,
+}
+
+/// Lists a version's platform specific artifacts (e.g. 'natives-linux', 'osx-aarch_64'). If 'os'
+///  and / or 'arch' are given, the request is redirected to the matching artifact instead, so
+///  that scripts can download native artifacts with 'curl -L' without knowing the classifier
+///  naming scheme. Ambiguous requests are answered with '300 Multiple Choices' and the candidates.
+pub(crate) async fn platforms(State(state): State<Arc<RepositoryManager>>, Query(query): Query<PlatformsQuery>, headers: HeaderMap) -> Result<Response, (StatusCode, String)> {
+    if query.g.is_empty() || query.a.is_empty() || query.v.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "'g', 'a' and 'v' must not be empty".to_string()));
+    }
+    let os = query.os.as_deref().map(str::parse::<Os>).transpose()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let arch = query.arch.as_deref().map(str::parse::<Arch>).transpose()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let file_extension = match query.extension {
+        None => ".jar".to_string(),
+        Some(e) if e.starts_with('.') => e,
+        Some(e) => format!(".{}", e),
+    };
+
+    let group_id = MavenGroupId(query.g);
+    let artifact_id = MavenArtifactId(query.a);
+    let classifiers = state.platform_classifiers(&group_id, &artifact_id, &query.v, &file_extension).await
+        .map_err(|e| {
+            error!("error listing platform classifiers: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, String::new())
+        })?;
+
+    let is_selection = os.is_some() || arch.is_some();
+    let selected = match is_selection {
+        true => select_classifiers(&classifiers, os, arch),
+        false => classifiers.iter().collect(),
+    };
+
+    let mut artifacts = vec![];
+    for classifier in selected {
+        let artifact_ref = MavenArtifactRef {
+            coordinates: MavenCoordinates {
+                group_id: group_id.clone(),
+                artifact_id: artifact_id.clone(),
+                version: MavenVersion::Release(query.v.clone()),
+            },
+            classifier: MavenClassifier::Classified(classifier.clone()),
+            file_extension: file_extension.clone(),
+        };
+        // snapshots are downloaded as their most recent timestamped build
+        let artifact_ref = match is_snapshot(&query.v) {
+            false => artifact_ref,
+            true => match state.resolve_snapshot(&artifact_ref).await {
+                Ok(Some(artifact_ref)) => artifact_ref,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!("error resolving snapshot: {}", e);
+                    return Err((StatusCode::INTERNAL_SERVER_ERROR, String::new()));
+                }
+            },
+        };
+
+        let platform = platform_of(classifier);
+        artifacts.push(PlatformArtifact {
+            classifier: classifier.clone(),
+            os: platform.and_then(|p| p.os),
+            arch: platform.and_then(|p| p.arch),
+            download_url: download_url(&headers, &artifact_ref),
+        });
+    }
+
+    if !is_selection {
+        return Ok(Json(artifacts).into_response());
+    }
+    match artifacts.len() {
+        0 => Err((StatusCode::NOT_FOUND, format!("no artifact of {}:{}:{} matches the requested platform", group_id.0, artifact_id.0, query.v))),
+        1 => Ok((StatusCode::TEMPORARY_REDIRECT, [(LOCATION, artifacts[0].download_url.clone())]).into_response()),
+        _ => Ok((StatusCode::MULTIPLE_CHOICES, Json(artifacts)).into_response()),
+    }
+}
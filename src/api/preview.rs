@@ -0,0 +1,185 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, State};
+use hyper::{Body, Response, StatusCode};
+use hyper::header::{CONTENT_TYPE, HeaderName};
+use hyper::http::response;
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::api::repo::{artifact_error_response, blocked_version_response, message_response, status_response};
+use crate::maven::paths::{checksum_target, parse_artifact_metadata_path, parse_maven_path};
+use crate::repository_manager::{RepositoryManager, RepositoryRef};
+use crate::util::blob::Blob;
+use crate::util::byte_range::{BlobRange, ByteRange, slice_stream};
+use crate::util::cache_control::CachePolicy;
+use crate::util::content_type::preview_syntax;
+
+const DEFAULT_PREVIEW_KB: u64 = 16;
+const MAX_PREVIEW_KB: u64 = 1024;
+
+/// 'xml', 'json' or 'text', for highlighting the preview
+const PREVIEW_SYNTAX: HeaderName = HeaderName::from_static("x-preview-syntax");
+/// whether the preview is shorter than the file, if the file's size is known
+const PREVIEW_TRUNCATED: HeaderName = HeaderName::from_static("x-preview-truncated");
+
+#[derive(Deserialize)]
+pub(crate) struct PreviewQuery {
+    /// the number of kilobytes to return at most, default 16
+    max_kb: Option<u64>,
+}
+
+/// The beginning of a text file (e.g. a pom, a Gradle module or 'maven-metadata.xml') in a
+///  repository, for looking at what the vault actually serves. The path is the same as below
+///  '/repo/'. Uncached artifacts of remote repositories are requested as a range if the
+///  repository passes ranges through (see [UncachedRangePolicy](crate::util::byte_range::UncachedRangePolicy)).
+///  Binary files are refused with '415 Unsupported Media Type'.
+pub(crate) async fn preview(State(state): State<Arc<RepositoryManager>>, Path(full_path): Path<String>, Query(query): Query<PreviewQuery>) -> Response<Body> {
+    let max_kb = query.max_kb.unwrap_or(DEFAULT_PREVIEW_KB);
+    if max_kb == 0 || max_kb > MAX_PREVIEW_KB {
+        return message_response(StatusCode::BAD_REQUEST, format!("'max_kb' must be between 1 and {}", MAX_PREVIEW_KB));
+    }
+    let max_len = max_kb * 1024;
+
+    let (repository, path) = match state.find_repository(&full_path) {
+        Some((repository, path)) => (repository, path),
+        None => (RepositoryRef::Remote(&state.repo), full_path.as_str()),
+    };
+
+    if let Some(metadata_path) = parse_artifact_metadata_path(path).filter(|p| p.checksum.is_none()) {
+        let xml = match repository {
+            RepositoryRef::Remote(remote) => remote.get_artifact_metadata_xml(&metadata_path, &Default::default()).await,
+            RepositoryRef::Hosted(hosted) => hosted.get_artifact_metadata_xml(&metadata_path, &Default::default()).await,
+        };
+        return match xml {
+            Ok(Some(xml)) => text_preview(xml, "xml", max_len),
+            Ok(None) => status_response(StatusCode::NOT_FOUND),
+            Err(e) => {
+                warn!("error generating artifact metadata for {}: {}", path, e);
+                status_response(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+        };
+    }
+
+    let artifact_ref = match parse_maven_path(path) {
+        Ok(artifact_ref) => artifact_ref,
+        Err(_) => return status_response(StatusCode::NOT_FOUND),
+    };
+    let syntax = match preview_syntax(&artifact_ref.file_extension) {
+        Some(syntax) => syntax,
+        None => return message_response(StatusCode::UNSUPPORTED_MEDIA_TYPE, format!("{} is not a text file", path)),
+    };
+    if let Some(response) = blocked_version_response(&state, &artifact_ref, path) {
+        return response;
+    }
+
+    if let Some((kind, target)) = checksum_target(&artifact_ref) {
+        let checksum = match repository {
+            RepositoryRef::Remote(remote) => remote.get_artifact_checksum(&target, kind).await.map(Some),
+            RepositoryRef::Hosted(hosted) => hosted.get_artifact_checksum(&target, kind).await,
+        };
+        return match checksum {
+            Ok(Some(checksum)) => text_preview(checksum, syntax, max_len),
+            Ok(None) => status_response(StatusCode::NOT_FOUND),
+            Err(e) => artifact_error_response(&e),
+        };
+    }
+
+    let blob = match repository {
+        RepositoryRef::Remote(remote) => match remote.get_artifact_range(&artifact_ref, &ByteRange::FromTo(0, max_len - 1)).await {
+            Ok(BlobRange::Partial { blob, total_size, .. }) => Ok(Some((blob, Some(total_size)))),
+            Ok(BlobRange::Unsatisfiable { .. }) => return text_preview(String::new(), syntax, max_len),
+            Err(e) => Err(e),
+        },
+        RepositoryRef::Hosted(hosted) => hosted.get_artifact(&artifact_ref).await
+            .map(|blob| blob.map(|blob| (blob, None))),
+    };
+    match blob {
+        Ok(Some((blob, total_size))) => blob_preview(blob, total_size, syntax, max_len),
+        Ok(None) => status_response(StatusCode::NOT_FOUND),
+        Err(e) => {
+            warn!("error previewing {}: {}", path, e);
+            artifact_error_response(&e)
+        }
+    }
+}
+
+fn preview_response(syntax: &str, truncated: Option<bool>) -> response::Builder {
+    // plain text, so that browsers never interpret the content
+    let mut response_builder = CachePolicy::NoStore.apply(Response::builder())
+        .header(CONTENT_TYPE, "text/plain; charset=utf-8")
+        .header(PREVIEW_SYNTAX, syntax);
+    if let Some(truncated) = truncated {
+        response_builder = response_builder.header(PREVIEW_TRUNCATED, truncated.to_string());
+    }
+    response_builder
+}
+
+fn text_preview(text: String, syntax: &str, max_len: u64) -> Response<Body> {
+    let truncated = text.len() as u64 > max_len;
+    let mut text = text.into_bytes();
+    text.truncate(max_len as usize);
+    preview_response(syntax, Some(truncated))
+        .body(Body::from(text))
+        .unwrap()
+}
+
+/// 'total_size' is that of the entire file if the blob is a range of it
+fn blob_preview(blob: Blob, total_size: Option<u64>, syntax: &str, max_len: u64) -> Response<Body> {
+    preview_response(syntax, total_size.or(blob.size).map(|size| size > max_len))
+        .body(Body::wrap_stream(slice_stream(blob.data, 0..max_len)))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use hyper::Request;
+    use sha1::{Digest as _, Sha1};
+    use tower::ServiceExt;
+
+    use crate::api::routes;
+    use crate::api::test_manager::manager;
+
+    use super::*;
+
+    async fn get(manager: &Arc<RepositoryManager>, uri: &str) -> (StatusCode, Option<String>, Option<String>, String) {
+        let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let response = routes().with_state(manager.clone()).oneshot(request).await.unwrap();
+        let status = response.status();
+        let header = |name| response.headers().get(name).map(|h| h.to_str().unwrap().to_string());
+        let (syntax, truncated) = (header(PREVIEW_SYNTAX), header(PREVIEW_TRUNCATED));
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, syntax, truncated, String::from_utf8_lossy(&body).to_string())
+    }
+
+    #[tokio::test]
+    async fn test_preview() {
+        let pom = format!("<project>{}</project>", " ".repeat(2000));
+        let manager = manager(b"jar", pom.as_bytes()).await;
+
+        let (status, syntax, truncated, body) = get(&manager, "/api/v1/preview/internal/com/acme/lib/1.0/lib-1.0.pom").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!((syntax.as_deref(), truncated.as_deref()), (Some("xml"), Some("false")));
+        assert_eq!(body, pom);
+
+        let (_, _, truncated, body) = get(&manager, "/api/v1/preview/internal/com/acme/lib/1.0/lib-1.0.pom?max_kb=1").await;
+        assert_eq!(truncated.as_deref(), Some("true"));
+        assert_eq!(body, pom[..1024]);
+
+        let (status, syntax, _, body) = get(&manager, "/api/v1/preview/internal/com/acme/lib/1.0/lib-1.0.jar.sha1").await;
+        assert_eq!((status, syntax.as_deref()), (StatusCode::OK, Some("text")));
+        assert_eq!(body, hex::encode(Sha1::digest(b"jar")));
+
+        let (status, syntax, _, body) = get(&manager, "/api/v1/preview/internal/com/acme/lib/maven-metadata.xml").await;
+        assert_eq!((status, syntax.as_deref()), (StatusCode::OK, Some("xml")));
+        assert!(body.contains("<version>1.0</version>"), "{}", body);
+    }
+
+    #[tokio::test]
+    async fn test_refused() {
+        let manager = manager(b"jar", b"<project/>").await;
+        assert_eq!(get(&manager, "/api/v1/preview/internal/com/acme/lib/1.0/lib-1.0.jar").await.0, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(get(&manager, "/api/v1/preview/internal/com/acme/lib/1.0/lib-1.0.module").await.0, StatusCode::NOT_FOUND);
+        assert_eq!(get(&manager, "/api/v1/preview/internal/com/acme/lib/1.0/lib-1.0.pom?max_kb=0").await.0, StatusCode::BAD_REQUEST);
+    }
+}
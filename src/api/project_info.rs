@@ -0,0 +1,186 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use axum::Json;
+use axum::extract::{Path, State};
+use bytes::{Bytes, BytesMut};
+use futures::StreamExt;
+use hyper::StatusCode;
+use tracing::{error, warn};
+
+use crate::maven::coordinates::{MavenArtifactRef, MavenClassifier};
+use crate::maven::paths::parse_maven_path;
+use crate::maven::project_info::{notice_files, parse_pom, ProjectInfo};
+use crate::maven::quarantine::ArtifactStatus;
+use crate::repository_manager::{RepositoryManager, RepositoryRef};
+use crate::util::repo_error::RepoError;
+
+/// poms are a few kilobytes, anything this large is not a pom
+const MAX_POM_SIZE: usize = 4*1024*1024;
+/// jars are searched for notice files in memory, larger ones are described by their pom only
+const MAX_JAR_SIZE: usize = 256*1024*1024;
+
+/// The essentials of an artifact's pom (name, description, licenses, SCM URL, developers) and,
+///  for jars, the license and notice files they contain, e.g. for developer portals. The path is
+///  the same as below '/repo/' and must refer to an artifact that is available locally - remote
+///  repositories do not download artifacts for this. The result is cached in the repository's
+///  metadata store.
+pub(crate) async fn project_info(State(state): State<Arc<RepositoryManager>>, Path(full_path): Path<String>) -> Result<Json<ProjectInfo>, (StatusCode, String)> {
+    let (repository, path) = match state.find_repository(&full_path) {
+        Some((repository, path)) => (repository, path),
+        None => (RepositoryRef::Remote(&state.repo), full_path.as_str()),
+    };
+    let artifact_ref = parse_maven_path(path)
+        .map_err(|_| (StatusCode::NOT_FOUND, format!("{} is not an artifact", path)))?;
+
+    let cached = match &repository {
+        RepositoryRef::Remote(remote) => remote.get_project_info(&artifact_ref).await,
+        RepositoryRef::Hosted(hosted) => hosted.get_project_info(&artifact_ref).await,
+    };
+    match cached {
+        Ok(Some(project_info)) => return Ok(Json(project_info)),
+        Ok(None) => {}
+        Err(e) => error!("error reading cached project info for {}: {}", path, e),
+    }
+
+    let available = match &repository {
+        RepositoryRef::Remote(remote) => remote.artifact_status(&artifact_ref).await
+            .map(|status| status == Some(ArtifactStatus::Cached)),
+        RepositoryRef::Hosted(hosted) => hosted.get_artifact_stat(&artifact_ref).await
+            .map(|stat| stat.is_some()),
+    };
+    match available {
+        Ok(true) => {}
+        Ok(false) => return Err((StatusCode::NOT_FOUND, format!("{} is not available locally", path))),
+        Err(e) => {
+            error!("error checking availability of {}: {}", path, e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, String::new()));
+        }
+    }
+
+    let pom_ref = MavenArtifactRef {
+        coordinates: artifact_ref.coordinates.clone(),
+        classifier: MavenClassifier::Unclassified,
+        file_extension: ".pom".to_string(),
+    };
+    let pom = match read_artifact(&repository, &pom_ref, MAX_POM_SIZE).await {
+        Ok(Some(pom)) => pom,
+        Ok(None) => return Err((StatusCode::NOT_FOUND, format!("{} has no pom", path))),
+        Err(e) => {
+            warn!("error reading the pom of {}: {}", path, e);
+            return Err((StatusCode::BAD_GATEWAY, format!("the pom of {} is not available", path)));
+        }
+    };
+    let mut project_info = ProjectInfo {
+        pom: parse_pom(&String::from_utf8_lossy(&pom)),
+        notice_files: vec![],
+    };
+
+    if artifact_ref.file_extension == ".jar" {
+        match read_artifact(&repository, &artifact_ref, MAX_JAR_SIZE).await {
+            Ok(Some(jar)) => {
+                // decompressing is CPU bound
+                match tokio::task::spawn_blocking(move || notice_files(&jar)).await {
+                    Ok(Ok(files)) => project_info.notice_files = files,
+                    Ok(Err(e)) => warn!("error reading notice files of {}: {}", path, e),
+                    Err(e) => return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+                }
+            }
+            Ok(None) => {}
+            Err(e) => warn!("not reading notice files of {}: {}", path, e),
+        }
+    }
+
+    let stored = match &repository {
+        RepositoryRef::Remote(remote) => remote.cache_project_info(&artifact_ref, project_info.clone()).await,
+        RepositoryRef::Hosted(hosted) => hosted.cache_project_info(&artifact_ref, project_info.clone()).await,
+    };
+    if let Err(e) = stored {
+        warn!("error caching project info for {}: {}", path, e);
+    }
+    Ok(Json(project_info))
+}
+
+/// None if the artifact does not exist, an error if it is larger than 'max_len'
+async fn read_artifact(repository: &RepositoryRef<'_>, artifact_ref: &MavenArtifactRef, max_len: usize) -> anyhow::Result<Option<Bytes>> {
+    let blob = match repository {
+        RepositoryRef::Remote(remote) => match remote.get_artifact(artifact_ref).await {
+            Ok(blob) => blob,
+            Err(e) if RepoError::is_not_found(&e) => return Ok(None),
+            Err(e) => return Err(e),
+        },
+        RepositoryRef::Hosted(hosted) => match hosted.get_artifact(artifact_ref).await? {
+            Some(blob) => blob,
+            None => return Ok(None),
+        },
+    };
+    let mut data = blob.data;
+    let mut result = BytesMut::new();
+    while let Some(chunk) = data.next().await {
+        result.extend_from_slice(&chunk?);
+        if result.len() > max_len {
+            return Err(anyhow!("the artifact is larger than {} bytes", max_len));
+        }
+    }
+    Ok(Some(result.freeze()))
+}
+
+#[cfg(test)]
+mod test {
+    use hyper::{Body, Request};
+    use tower::ServiceExt;
+
+    use crate::api::{routes, test_manager};
+    use crate::maven::jar_index::test_jar::jar;
+    use crate::maven::project_info::{NoticeFile, PomLicense};
+
+    use super::*;
+
+    const POM: &str = "<project><artifactId>lib</artifactId><name>Acme Lib</name>\
+        <licenses><license><name>Apache-2.0</name></license></licenses></project>";
+
+    async fn manager() -> Arc<RepositoryManager> {
+        let jar = jar(&[("META-INF/LICENSE", b"Apache License"), ("com/acme/Lib.class", b"class")]);
+        test_manager::manager(&jar, POM.as_bytes()).await
+    }
+
+    async fn get(manager: &Arc<RepositoryManager>, uri: &str) -> (StatusCode, serde_json::Value) {
+        let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let response = routes().with_state(manager.clone()).oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn test_project_info() {
+        let manager = manager().await;
+
+        let (status, json) = get(&manager, "/api/v1/project-info/internal/com/acme/lib/1.0/lib-1.0.jar").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["name"], "Acme Lib");
+        assert_eq!(json["licenses"][0]["name"], "Apache-2.0");
+        assert_eq!(json["notice_files"][0]["name"], "META-INF/LICENSE");
+        assert_eq!(json["notice_files"][0]["content"], "Apache License");
+
+        // the pom itself has no notice files
+        let (status, json) = get(&manager, "/api/v1/project-info/internal/com/acme/lib/1.0/lib-1.0.pom").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["notice_files"], serde_json::json!([]));
+
+        let Some((RepositoryRef::Hosted(hosted), _)) = manager.find_repository("internal/") else { panic!() };
+        let artifact_ref = parse_maven_path("com/acme/lib/1.0/lib-1.0.jar").unwrap();
+        let cached = hosted.get_project_info(&artifact_ref).await.unwrap().unwrap();
+        assert_eq!(cached.pom.licenses, vec![PomLicense { name: Some("Apache-2.0".to_string()), url: None }]);
+        assert_eq!(cached.notice_files, vec![NoticeFile { name: "META-INF/LICENSE".to_string(), content: "Apache License".to_string() }]);
+    }
+
+    #[tokio::test]
+    async fn test_not_available() {
+        let manager = manager().await;
+        assert_eq!(get(&manager, "/api/v1/project-info/internal/com/acme/lib/2.0/lib-2.0.jar").await.0, StatusCode::NOT_FOUND);
+        assert_eq!(get(&manager, "/api/v1/project-info/internal/com/acme/lib/maven-metadata.xml").await.0, StatusCode::NOT_FOUND);
+        // uncached artifacts of remote repositories are not downloaded
+        assert_eq!(get(&manager, "/api/v1/project-info/central/com/acme/lib/1.0/lib-1.0.jar").await.0, StatusCode::NOT_FOUND);
+    }
+}
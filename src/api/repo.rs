@@ -0,0 +1,836 @@
+use std::sync::Arc;
+
+use axum::Extension;
+use axum::extract::{BodyStream, Path, Query, State};
+use hyper::{Body, Response, StatusCode};
+use hyper::header::{ACCEPT, ACCEPT_RANGES, CONTENT_DISPOSITION, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG, HeaderMap, HeaderValue, LAST_MODIFIED, RANGE, RETRY_AFTER, VARY};
+use hyper::http::response;
+use tracing::{Instrument, span, trace, warn};
+use tracing::Level;
+use futures::TryStreamExt;
+
+use crate::auth::credential_store::{ANONYMOUS, Principal};
+use crate::maven::advisories::ReplacementAdvisory;
+use crate::maven::checksums::RepositoryChecksums;
+use crate::maven::coordinates::MavenArtifactRef;
+use crate::maven::directory_listing::DirectoryListing;
+use crate::maven::paths::{ArtifactMetadataPath, as_maven_path, checksum_target, ChecksumKind, maven_file_name, parse_artifact_metadata_path, parse_maven_path, parse_snapshot_metadata_path, SnapshotMetadataPath};
+use crate::maven::hosted_repo::{DeployOutcome, HostedMavenRepo};
+use crate::maven::naming_policy::NamingPolicyError;
+use crate::maven::remote_repo::RemoteMavenRepo;
+use crate::maven::version_list::VersionListOptions;
+use crate::repository_manager::{BlockedVersion, RepositoryManager, RepositoryRef};
+use crate::util::blob::{Blob, BlobStat};
+use crate::util::byte_range::{BlobRange, ByteRange};
+use crate::util::cache_control::CachePolicy;
+use crate::util::conditional_request::{etag_for_sha1, is_conditional, is_not_modified};
+use crate::util::content_hooks::ContentStream;
+use crate::util::content_type::{content_disposition, content_type_for_extension, DispositionPolicy};
+use crate::util::repo_error::RepoError;
+use crate::util::retry_later::{RetryLaterError, RetryLaterReason};
+
+// basic handler that responds with a static string
+pub(crate) async fn root() -> &'static str {
+    "Hello, World!" //TODO
+}
+
+pub(crate) async fn repo_root(State(state): State<Arc<RepositoryManager>>, headers: HeaderMap) -> Response<Body> {
+    remote_directory_listing(&state.repo, "", "", &headers).await
+}
+
+/// Query strings that are not valid [VersionListOptions] are ignored rather than rejected, since
+///  they are meaningless for anything but artifact metadata
+fn version_list_options(query: Option<Query<VersionListOptions>>) -> VersionListOptions {
+    query.map(|Query(options)| options).unwrap_or_default()
+}
+
+pub(crate) async fn repo(State(state): State<Arc<RepositoryManager>>, Path(full_path): Path<String>, version_list: Option<Query<VersionListOptions>>, headers: HeaderMap) -> Response<Body> {
+    let version_list = version_list_options(version_list);
+    let (remote, repo_path) = match state.find_repository(&full_path) {
+        Some((RepositoryRef::Hosted(hosted), path)) if is_directory_path(path) => {
+            let span = span!(Level::TRACE, "hosted directory listing", repo = hosted.name(), path);
+            return directory_listing_response(hosted.get_directory_listing(path).instrument(span).await, &full_path, &headers);
+        }
+        Some((RepositoryRef::Hosted(hosted), path)) => return hosted_repo_response(&state, hosted, path, &version_list, &headers, false).await,
+        Some((RepositoryRef::Remote(remote), path)) => (remote, path),
+        // paths that do not start with a repository name go to the default repository
+        None => (&state.repo, full_path.as_str()),
+    };
+    if is_directory_path(repo_path) {
+        return remote_directory_listing(remote, repo_path, &full_path, &headers).await;
+    }
+    if let Some(metadata_path) = parse_snapshot_metadata_path(repo_path) {
+        return snapshot_metadata_response(remote, &metadata_path).await;
+    }
+    if let Some(metadata_path) = parse_artifact_metadata_path(repo_path) {
+        return artifact_metadata_response(remote.get_artifact_metadata_xml(&metadata_path, &version_list).await, &metadata_path, repo_path);
+    }
+
+    let span = span!(Level::TRACE, "repo get", repo_path);
+
+    let artifact_ref = match span.in_scope(|| {
+        trace!("getting from repo: {}", repo_path);
+        parse_maven_path(repo_path)
+    }) {
+        Ok(artifact_ref) => artifact_ref,
+        Err(_) => return status_response(StatusCode::BAD_REQUEST),
+    };
+
+    if let Some(response) = span.in_scope(|| blocked_version_response(&state, &artifact_ref, repo_path)) {
+        return response;
+    }
+
+    // checksum files the repository does not serve are passed through from upstream
+    if let Some((kind, target)) = checksum_target(&artifact_ref).filter(|(kind, _)| remote.checksums().is_served(*kind)) {
+        return checksum_response(remote, &target, kind).instrument(span).await;
+    }
+
+    let advisory = state.find_advisory(&artifact_ref);
+
+    if is_conditional(&headers) {
+        match remote.get_artifact_stat(&artifact_ref).instrument(span.clone()).await {
+            Ok(stat) => if let Some(response) = conditional_response(&headers, &artifact_ref, &stat) {
+                return response;
+            }
+            Err(e) => {
+                warn!("error getting {}: {}", repo_path, e);
+                return artifact_error_response(&e);
+            }
+        }
+    }
+
+    let range = headers.get(RANGE)
+        .and_then(|h| h.to_str().ok())
+        .and_then(ByteRange::parse);
+    if let Some(range) = range {
+        return match remote.get_artifact_range(&artifact_ref, &range).instrument(span).await {
+            Ok(BlobRange::Partial { blob, range, total_size }) => {
+                let mut response = blob_response(&artifact_ref, advisory.as_ref(), &state, remote.checksums(), blob);
+                *response.status_mut() = StatusCode::PARTIAL_CONTENT;
+                response.headers_mut().insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+                response.headers_mut().insert(CONTENT_RANGE, HeaderValue::from_str(&format!("bytes {}-{}/{}", range.start, range.end - 1, total_size)).unwrap());
+                response
+            }
+            Ok(BlobRange::Unsatisfiable { total_size }) => Response::builder()
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(CONTENT_RANGE, format!("bytes */{}", total_size))
+                .body(Body::empty())
+                .unwrap(),
+            Err(e) => {
+                warn!("error getting {}: {}", repo_path, e);
+                artifact_error_response(&e)
+            }
+        };
+    }
+
+    let blob = match remote.get_artifact(&artifact_ref).instrument(span).await {
+        Ok(blob) => blob,
+        Err(e) => {
+            warn!("error getting {}: {}", repo_path, e);
+            return artifact_error_response(&e);
+        }
+    };
+
+    // resuming downloads is supported for remote repositories only
+    let mut response = blob_response(&artifact_ref, advisory.as_ref(), &state, remote.checksums(), blob);
+    response.headers_mut().insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    response
+}
+
+/// Serves artifacts from a frozen clone of the repository
+pub(crate) async fn repo_clone(State(state): State<Arc<RepositoryManager>>, Path((clone_name, repo_path)): Path<(String, String)>, headers: HeaderMap) -> Response<Body> {
+    let span = span!(Level::TRACE, "repo clone get", clone_name, repo_path);
+
+    let clone = match state.get_clone(&clone_name) {
+        Some(clone) => clone,
+        None => return status_response(StatusCode::NOT_FOUND),
+    };
+    let artifact_ref = match parse_maven_path(&repo_path) {
+        Ok(artifact_ref) => artifact_ref,
+        Err(_) => return status_response(StatusCode::BAD_REQUEST),
+    };
+
+    if let Some(response) = span.in_scope(|| blocked_version_response(&state, &artifact_ref, &repo_path)) {
+        return response;
+    }
+
+    if let Some((kind, target)) = checksum_target(&artifact_ref).filter(|(kind, _)| clone.checksums().is_served(*kind)) {
+        return match clone.get_artifact_checksum(&target, kind).instrument(span).await {
+            Ok(checksum) => text_response(CachePolicy::for_artifact(&target), kind.suffix(), checksum),
+            Err(e) => artifact_error_response(&e),
+        };
+    }
+
+    let advisory = state.find_advisory(&artifact_ref);
+
+    if is_conditional(&headers) {
+        match clone.get_artifact_stat(&artifact_ref).instrument(span.clone()).await {
+            Ok(stat) => if let Some(response) = conditional_response(&headers, &artifact_ref, &stat) {
+                return response;
+            }
+            Err(e) => return artifact_error_response(&e),
+        }
+    }
+
+    match clone.get_artifact(&artifact_ref).instrument(span).await {
+        Ok(blob) => blob_response(&artifact_ref, advisory.as_ref(), &state, clone.checksums(), blob),
+        // clones are offline, so anything that is not stored locally is 'not found'
+        Err(e) => artifact_error_response(&e),
+    }
+}
+
+/// Serves GET and HEAD requests for a hosted repository. Only artifacts from completed deploys
+///  are visible.
+pub(crate) async fn hosted_repo_response(state: &RepositoryManager, hosted: &HostedMavenRepo, path: &str, version_list: &VersionListOptions, headers: &HeaderMap, is_head: bool) -> Response<Body> {
+    let span = span!(Level::TRACE, "hosted repo get", repo = hosted.name(), path);
+
+    if let Some(metadata_path) = parse_snapshot_metadata_path(path) {
+        return match hosted.get_snapshot_metadata(&metadata_path.group_id, &metadata_path.artifact_id, &metadata_path.version).instrument(span).await {
+            Ok(Some(xml)) => match metadata_path.checksum {
+                None => text_response(CachePolicy::Revalidate, ".xml", xml),
+                Some(kind) => text_response(CachePolicy::Revalidate, kind.suffix(), kind.checksum_of(xml.as_bytes())),
+            },
+            Ok(None) => status_response(StatusCode::NOT_FOUND),
+            Err(e) => {
+                warn!("error generating snapshot metadata for {}: {}", path, e);
+                status_response(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+        };
+    }
+
+    if let Some(metadata_path) = parse_artifact_metadata_path(path) {
+        return artifact_metadata_response(hosted.get_artifact_metadata_xml(&metadata_path, version_list).instrument(span).await, &metadata_path, path);
+    }
+
+    let artifact_ref = match parse_maven_path(path) {
+        Ok(artifact_ref) => artifact_ref,
+        Err(_) => return status_response(StatusCode::NOT_FOUND),
+    };
+
+    if let Some(response) = span.in_scope(|| blocked_version_response(state, &artifact_ref, path)) {
+        return response;
+    }
+
+    if let Some((kind, target)) = checksum_target(&artifact_ref) {
+        return match hosted.get_artifact_checksum(&target, kind).instrument(span).await {
+            Ok(Some(checksum)) => text_response(CachePolicy::for_artifact(&target), kind.suffix(), checksum),
+            Ok(None) => status_response(StatusCode::NOT_FOUND),
+            Err(e) => {
+                warn!("error getting checksum for {}: {}", path, e);
+                status_response(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+        };
+    }
+
+    let advisory = state.find_advisory(&artifact_ref);
+    if is_head || is_conditional(headers) {
+        let stat = match hosted.get_artifact_stat(&artifact_ref).instrument(span.clone()).await {
+            Ok(Some(stat)) => stat,
+            Ok(None) => return status_response(StatusCode::NOT_FOUND),
+            Err(e) => {
+                warn!("error getting metadata for {}: {}", path, e);
+                return status_response(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        };
+        if let Some(response) = conditional_response(headers, &artifact_ref, &stat) {
+            return response;
+        }
+        if is_head {
+            return stat_response(&artifact_ref, advisory.as_ref(), state.content_disposition, hosted.checksums(), stat);
+        }
+    }
+
+    match hosted.get_artifact(&artifact_ref).instrument(span).await {
+        Ok(Some(blob)) => blob_response(&artifact_ref, advisory.as_ref(), state, hosted.checksums(), blob),
+        Ok(None) => status_response(StatusCode::NOT_FOUND),
+        Err(e) => {
+            warn!("error getting {}: {}", path, e);
+            status_response(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// `mvn deploy` to a hosted repository, see [HostedMavenRepo::deploy]
+pub(crate) async fn repo_put(State(state): State<Arc<RepositoryManager>>, Path(repo_path): Path<String>, principal: Option<Extension<Principal>>, headers: HeaderMap, body: BodyStream) -> Response<Body> {
+    deploy_response(&state, &repo_path, principal_name(&principal), &headers, Box::pin(body.map_err(anyhow::Error::from))).await
+}
+
+/// Shared by all deploy paths, so that they validate uploads the same way
+pub(crate) async fn deploy_response(state: &RepositoryManager, repo_path: &str, principal: &str, headers: &HeaderMap, data: ContentStream) -> Response<Body> {
+    let span = span!(Level::TRACE, "repo put", repo_path);
+
+    let (repo_name, path) = match repo_path.split_once('/') {
+        Some(split) => split,
+        None => return status_response(StatusCode::NOT_FOUND),
+    };
+
+    let expected_size = headers.get(CONTENT_LENGTH)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|s| s.parse().ok());
+
+    match state.deploy(repo_name, path, principal, data, expected_size).instrument(span).await {
+        Ok(None) => status_response(StatusCode::NOT_FOUND),
+        Ok(Some(DeployOutcome::Conflict(message))) => message_response(StatusCode::CONFLICT, message),
+        Ok(Some(DeployOutcome::Invalid(message))) => message_response(StatusCode::BAD_REQUEST, message),
+        Ok(Some(_)) => status_response(StatusCode::CREATED),
+        Err(e) => match e.downcast_ref::<NamingPolicyError>() {
+            Some(naming_policy_error) => Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(naming_policy_error.as_json()))
+                .unwrap(),
+            None => {
+                warn!("error deploying {}: {}", repo_path, e);
+                status_response(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+        }
+    }
+}
+
+/// the authenticated user, see [authenticate]
+pub(crate) fn principal_name(principal: &Option<Extension<Principal>>) -> &str {
+    principal.as_ref()
+        .map(|Extension(principal)| principal.name.as_str())
+        .unwrap_or(ANONYMOUS)
+}
+
+pub(crate) fn message_response(status: StatusCode, message: String) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(Body::from(message))
+        .unwrap()
+}
+
+pub(crate) fn status_response(status: StatusCode) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(Body::empty())
+        .unwrap()
+}
+
+/// Artifacts that can not be provided are answered with '404 Not Found'. If the repository refused
+///  to contact upstream (see [RetryLaterError]), the response says when to try again: a recently
+///  failed download stays a '404', an unavailable upstream repository is a '503'.
+pub(crate) fn artifact_error_response(e: &anyhow::Error) -> Response<Body> {
+    let retry_later = match e.downcast_ref::<RetryLaterError>() {
+        Some(retry_later) => retry_later,
+        None => return status_response(status_for_repo_error(RepoError::of(e))),
+    };
+    let status = match retry_later.reason {
+        RetryLaterReason::DownloadFailedRecently => StatusCode::NOT_FOUND,
+        RetryLaterReason::UpstreamUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .header(RETRY_AFTER, retry_later.retry_after_secs())
+        .body(Body::from(retry_later.as_json()))
+        .unwrap()
+}
+
+/// Errors that are not classified are unexpected, i.e. internal
+fn status_for_repo_error(e: Option<&RepoError>) -> StatusCode {
+    match e {
+        Some(RepoError::NotFound(_)) => StatusCode::NOT_FOUND,
+        Some(RepoError::UpstreamUnavailable(_)) | Some(RepoError::ChecksumMismatch(_)) | Some(RepoError::InvalidSignature(_)) => StatusCode::BAD_GATEWAY,
+        Some(RepoError::Internal(_)) | None => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// The body is checked against the blob's size, see [crate::util::transfer_metrics::TransferMetrics]
+fn blob_response(artifact_ref: &MavenArtifactRef, advisory: Option<&ReplacementAdvisory>, state: &RepositoryManager, checksums: &RepositoryChecksums, blob: Blob) -> Response<Body> {
+    let data = state.transfer_metrics.measure(as_maven_path(artifact_ref), blob.size, blob.data);
+    let response_body = Body::wrap_stream(data);
+    let response_builder = CachePolicy::for_artifact(artifact_ref).apply(Response::builder())
+        .header(CONTENT_TYPE, content_type_for_extension(&artifact_ref.file_extension))
+        .header(CONTENT_DISPOSITION, content_disposition(&artifact_ref.file_extension, &maven_file_name(artifact_ref), state.content_disposition));
+    let mut response_builder = with_advisory_headers(response_builder, advisory);
+    if let Some(size) = blob.size {
+        response_builder = response_builder.header(CONTENT_LENGTH, size);
+    }
+    if let Some(sha1) = blob.sha1 {
+        response_builder = response_builder.header(ETAG, etag_for_sha1(&sha1));
+    }
+    response_builder = with_checksum_headers(response_builder, checksums, blob.md5, blob.sha1);
+    if let Some(last_modified) = blob.last_modified {
+        response_builder = response_builder.header(LAST_MODIFIED, httpdate::fmt_http_date(last_modified));
+    }
+    for (name, value) in &blob.upstream_headers {
+        response_builder = response_builder.header(name, value);
+    }
+    response_builder.body(response_body)
+        .unwrap()
+}
+
+/// A repository's root ('/repo/internal/') or a directory inside it
+fn is_directory_path(path: &str) -> bool {
+    path.is_empty() || path.ends_with('/')
+}
+
+async fn remote_directory_listing(remote: &RemoteMavenRepo, directory_path: &str, request_path: &str, headers: &HeaderMap) -> Response<Body> {
+    let span = span!(Level::TRACE, "repo directory listing", directory_path);
+    directory_listing_response(remote.get_directory_listing(directory_path).instrument(span).await, request_path, headers)
+}
+
+/// Renders a directory listing as HTML, or as JSON if the client asks for it. The request path
+///  includes the repository name if there is one.
+fn directory_listing_response(listing: anyhow::Result<DirectoryListing>, request_path: &str, headers: &HeaderMap) -> Response<Body> {
+    let listing = match listing {
+        Ok(listing) => listing,
+        Err(e) => {
+            warn!("error listing /repo/{}: {}", request_path, e);
+            return status_response(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let wants_json = headers.get(ACCEPT)
+        .and_then(|h| h.to_str().ok())
+        .map(|accept| accept.contains("application/json"))
+        .unwrap_or(false);
+
+    let (content_type, body) = if wants_json {
+        ("application/json", serde_json::to_string(&listing).unwrap())
+    }
+    else {
+        ("text/html; charset=utf-8", listing.as_html(&format!("/repo/{}", request_path)))
+    };
+
+    CachePolicy::Revalidate.apply(Response::builder())
+        .header(CONTENT_TYPE, content_type)
+        .header(VARY, "Accept")
+        .body(Body::from(body))
+        .unwrap()
+}
+
+/// Serves checksum files from the checksums stored with the artifact's blob rather than fetching
+///  them from upstream separately
+async fn checksum_response(remote: &RemoteMavenRepo, artifact_ref: &MavenArtifactRef, kind: ChecksumKind) -> Response<Body> {
+    match remote.get_artifact_checksum(artifact_ref, kind).await {
+        Ok(checksum) => text_response(CachePolicy::for_artifact(artifact_ref), kind.suffix(), checksum),
+        Err(e) => {
+            warn!("error getting checksum for {}: {}", as_maven_path(artifact_ref), e);
+            artifact_error_response(&e)
+        }
+    }
+}
+
+/// The version level 'maven-metadata.xml' of a snapshot version is generated from local and
+///  upstream builds, so clients resolve the most recent timestamped build for each classifier
+async fn snapshot_metadata_response(remote: &RemoteMavenRepo, metadata_path: &SnapshotMetadataPath) -> Response<Body> {
+    let span = span!(Level::TRACE, "snapshot metadata", version = metadata_path.version);
+
+    let xml = match remote.get_snapshot_metadata(&metadata_path.group_id, &metadata_path.artifact_id, &metadata_path.version).instrument(span).await {
+        Ok(Some(xml)) => xml,
+        Ok(None) => return status_response(StatusCode::NOT_FOUND),
+        Err(e) => {
+            warn!("error generating snapshot metadata for {:?}: {}", metadata_path, e);
+            return status_response(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    match metadata_path.checksum {
+        None => text_response(CachePolicy::Revalidate, ".xml", xml),
+        Some(kind) => text_response(CachePolicy::Revalidate, kind.suffix(), kind.checksum_of(xml.as_bytes())),
+    }
+}
+
+/// The artifact level 'maven-metadata.xml' is generated from the metadata store, see
+///  [ArtifactMetadataPath]
+fn artifact_metadata_response(xml: anyhow::Result<Option<String>>, metadata_path: &ArtifactMetadataPath, path: &str) -> Response<Body> {
+    match xml {
+        Ok(Some(xml)) => match metadata_path.checksum {
+            None => text_response(CachePolicy::Revalidate, ".xml", xml),
+            Some(kind) => text_response(CachePolicy::Revalidate, kind.suffix(), kind.checksum_of(xml.as_bytes())),
+        },
+        Ok(None) => status_response(StatusCode::NOT_FOUND),
+        Err(e) => {
+            warn!("error generating artifact metadata for {}: {}", path, e);
+            status_response(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+fn text_response(cache_policy: CachePolicy, file_extension: &str, body: String) -> Response<Body> {
+    cache_policy.apply(Response::builder())
+        .header(CONTENT_TYPE, content_type_for_extension(file_extension))
+        .header(CONTENT_LENGTH, body.len())
+        .body(Body::from(body))
+        .unwrap()
+}
+
+/// HEAD requests are answered from the blob's metadata without opening its data
+pub(crate) async fn repo_head(State(state): State<Arc<RepositoryManager>>, Path(full_path): Path<String>, version_list: Option<Query<VersionListOptions>>, headers: HeaderMap) -> Response<Body> {
+    let version_list = version_list_options(version_list);
+    let (remote, repo_path) = match state.find_repository(&full_path) {
+        Some((RepositoryRef::Hosted(hosted), path)) => return hosted_repo_response(&state, hosted, path, &version_list, &headers, true).await,
+        Some((RepositoryRef::Remote(remote), path)) => (remote, path),
+        None => (&state.repo, full_path.as_str()),
+    };
+    if let Some(metadata_path) = parse_snapshot_metadata_path(repo_path) {
+        return snapshot_metadata_response(remote, &metadata_path).await;
+    }
+    if let Some(metadata_path) = parse_artifact_metadata_path(repo_path) {
+        return artifact_metadata_response(remote.get_artifact_metadata_xml(&metadata_path, &version_list).await, &metadata_path, repo_path);
+    }
+
+    let span = span!(Level::TRACE, "repo head", repo_path);
+
+    let artifact_ref = match span.in_scope(|| {
+        trace!("getting metadata from repo: {}", repo_path);
+        parse_maven_path(repo_path)
+    }) {
+        Ok(artifact_ref) => artifact_ref,
+        Err(_) => return status_response(StatusCode::BAD_REQUEST),
+    };
+
+    if let Some(response) = span.in_scope(|| blocked_version_response(&state, &artifact_ref, repo_path)) {
+        return response;
+    }
+
+    // checksum files the repository does not serve are passed through from upstream
+    if let Some((kind, target)) = checksum_target(&artifact_ref).filter(|(kind, _)| remote.checksums().is_served(*kind)) {
+        return checksum_response(remote, &target, kind).instrument(span).await;
+    }
+
+    let advisory = state.find_advisory(&artifact_ref);
+
+    let stat = match remote.get_artifact_stat(&artifact_ref).instrument(span).await {
+        Ok(stat) => stat,
+        Err(e) => {
+            warn!("error getting metadata for {}: {}", repo_path, e);
+            return artifact_error_response(&e);
+        }
+    };
+    if let Some(response) = conditional_response(&headers, &artifact_ref, &stat) {
+        return response;
+    }
+
+    stat_response(&artifact_ref, advisory.as_ref(), state.content_disposition, remote.checksums(), stat)
+}
+
+fn stat_response(artifact_ref: &MavenArtifactRef, advisory: Option<&ReplacementAdvisory>, disposition: DispositionPolicy, checksums: &RepositoryChecksums, stat: BlobStat) -> Response<Body> {
+    let response_builder = CachePolicy::for_artifact(artifact_ref).apply(Response::builder());
+    let mut response_builder = with_advisory_headers(response_builder, advisory)
+        .header(CONTENT_TYPE, content_type_for_extension(&artifact_ref.file_extension))
+        .header(CONTENT_DISPOSITION, content_disposition(&artifact_ref.file_extension, &maven_file_name(artifact_ref), disposition))
+        .header(CONTENT_LENGTH, stat.size);
+    if let Some(sha1) = stat.sha1 {
+        response_builder = response_builder.header(ETAG, etag_for_sha1(&sha1));
+    }
+    response_builder = with_checksum_headers(response_builder, checksums, stat.md5, stat.sha1);
+    if let Some(last_modified) = stat.last_modified {
+        response_builder = response_builder.header(LAST_MODIFIED, httpdate::fmt_http_date(last_modified));
+    }
+    for (name, value) in &stat.upstream_headers {
+        response_builder = response_builder.header(name, value);
+    }
+    response_builder.body(Body::empty())
+        .unwrap()
+}
+
+/// Carries the validators and caching headers a '200 OK' would have, so that clients can
+///  refresh their cached copy
+/// '304 Not Modified' if the request's validators match the artifact, for GET and HEAD alike
+fn conditional_response(headers: &HeaderMap, artifact_ref: &MavenArtifactRef, stat: &BlobStat) -> Option<Response<Body>> {
+    is_not_modified(headers, stat.sha1.map(|sha1| etag_for_sha1(&sha1)).as_deref(), stat.last_modified)
+        .then(|| not_modified_response(artifact_ref, stat))
+}
+
+fn not_modified_response(artifact_ref: &MavenArtifactRef, stat: &BlobStat) -> Response<Body> {
+    let mut response_builder = CachePolicy::for_artifact(artifact_ref).apply(Response::builder())
+        .status(StatusCode::NOT_MODIFIED);
+    if let Some(sha1) = stat.sha1 {
+        response_builder = response_builder.header(ETAG, etag_for_sha1(&sha1));
+    }
+    if let Some(last_modified) = stat.last_modified {
+        response_builder = response_builder.header(LAST_MODIFIED, httpdate::fmt_http_date(last_modified));
+    }
+    response_builder.body(Body::empty())
+        .unwrap()
+}
+
+pub(crate) fn blocked_version_response(state: &RepositoryManager, artifact_ref: &MavenArtifactRef, repo_path: &str) -> Option<Response<Body>> {
+    let BlockedVersion { rule, advisory } = state.check_blocked(artifact_ref, repo_path)?;
+
+    let response_body = match &advisory {
+        None => rule.message,
+        Some(advisory) => format!("{}\n{}", rule.message, advisory.as_text()),
+    };
+
+    // blocking rules can be lifted at any time, so caches must not hold on to this response
+    Some(with_advisory_headers(CachePolicy::NoStore.apply(Response::builder()), advisory.as_ref())
+        .status(StatusCode::GONE)
+        .body(Body::from(response_body))
+        .unwrap())
+}
+
+/// Advertises the checksums the repository serves as 'x-checksum-<kind>' headers, strongest
+///  first, so that clients need not request the checksum files separately
+fn with_checksum_headers(response_builder: response::Builder, checksums: &RepositoryChecksums, md5: Option<[u8;16]>, sha1: Option<[u8;20]>) -> response::Builder {
+    checksums.available(md5, sha1).into_iter()
+        .fold(response_builder, |builder, (kind, checksum)| builder.header(format!("x-checksum-{}", kind.name()), checksum))
+}
+
+fn with_advisory_headers(response_builder: response::Builder, advisory: Option<&ReplacementAdvisory>) -> response::Builder {
+    let mut response_builder = response_builder;
+    if let Some(advisory) = advisory {
+        // header values must be visible ASCII - skip values that don't qualify rather than failing the request
+        if let Ok(replacement_version) = HeaderValue::from_str(&advisory.replacement_version) {
+            response_builder = response_builder.header("x-advisory-replacement-version", replacement_version);
+        }
+        if let Some(message) = &advisory.message {
+            if let Ok(message) = HeaderValue::from_str(message) {
+                response_builder = response_builder.header("x-advisory-message", message);
+            }
+        }
+    }
+    response_builder
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use bytes::Bytes;
+    use hyper::Request;
+    use hyper::header::{CACHE_CONTROL, HeaderName, IF_MODIFIED_SINCE, IF_NONE_MATCH};
+    use rstest::rstest;
+    use sha1::{Digest as _, Sha1};
+    use tower::ServiceExt;
+
+    use crate::api::routes;
+    use crate::api::test_manager::manager;
+    use crate::maven::coordinates::{MavenArtifactId, MavenGroupId};
+    use crate::maven::version_blocking::VersionBlockingRule;
+
+    use super::*;
+
+    async fn get(manager: &Arc<RepositoryManager>, uri: &str, accept: &str) -> (StatusCode, String) {
+        let request = Request::builder()
+            .uri(uri)
+            .header(ACCEPT, accept)
+            .body(Body::empty())
+            .unwrap();
+        let response = routes().with_state(manager.clone()).oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, String::from_utf8_lossy(&body).to_string())
+    }
+
+    async fn get_headers(manager: &Arc<RepositoryManager>, uri: &str) -> (StatusCode, HeaderMap) {
+        let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let response = routes().with_state(manager.clone()).oneshot(request).await.unwrap();
+        (response.status(), response.headers().clone())
+    }
+
+    /// A conditional request whose validator either matches the deployed 'jar' or not
+    async fn conditional(manager: &Arc<RepositoryManager>, method: &str, uri: &str, header: HeaderName, matching: bool) -> (StatusCode, HeaderMap, Bytes) {
+        let value = match (header == IF_NONE_MATCH, matching) {
+            (true, true) => etag_for_sha1(&Sha1::digest(b"jar").into()),
+            (true, false) => "\"other\"".to_string(),
+            (false, true) => httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(3600)),
+            (false, false) => httpdate::fmt_http_date(UNIX_EPOCH),
+        };
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header, value)
+            .body(Body::empty())
+            .unwrap();
+        let response = routes().with_state(manager.clone()).oneshot(request).await.unwrap();
+        let (parts, body) = response.into_parts();
+        (parts.status, parts.headers, hyper::body::to_bytes(body).await.unwrap())
+    }
+
+    #[cfg(feature = "admin-api")]
+    async fn send_json(manager: &Arc<RepositoryManager>, method: &str, uri: &str, json: &str) -> StatusCode {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(json.to_string()))
+            .unwrap();
+        routes().with_state(manager.clone()).oneshot(request).await.unwrap().status()
+    }
+
+    #[cfg(feature = "admin-api")]
+    async fn send(manager: &Arc<RepositoryManager>, method: &str, uri: &str) -> StatusCode {
+        let request = Request::builder().method(method).uri(uri).body(Body::empty()).unwrap();
+        routes().with_state(manager.clone()).oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_cache_control() {
+        let manager = manager(b"jar", b"pom").await;
+        let Some((RepositoryRef::Hosted(hosted), _)) = manager.find_repository("internal/") else { panic!() };
+        let jar_path = "com/acme/lib/1.0-SNAPSHOT/lib-1.0-SNAPSHOT-20231114.221320-1.jar";
+        let pom_path = "com/acme/lib/1.0-SNAPSHOT/lib-1.0-SNAPSHOT-20231114.221320-1.pom";
+        for (path, content) in [
+            (jar_path.to_string(), "jar".to_string()),
+            (format!("{}.sha1", jar_path), hex::encode(Sha1::digest(b"jar"))),
+            (pom_path.to_string(), "pom".to_string()),
+            (format!("{}.sha1", pom_path), hex::encode(Sha1::digest(b"pom"))),
+            ("com/acme/lib/1.0-SNAPSHOT/maven-metadata.xml".to_string(), "<metadata/>".to_string()),
+        ] {
+            let data: ContentStream = Box::pin(futures::stream::iter(vec![Ok(Bytes::from(content))]));
+            hosted.deploy(&path, data).await.unwrap();
+        }
+
+        let (status, headers) = get_headers(&manager, "/repo/internal/com/acme/lib/1.0/lib-1.0.jar").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers[CACHE_CONTROL], "public, max-age=3600, s-maxage=86400");
+        assert_eq!(headers[VARY], "Authorization");
+
+        let (status, headers) = get_headers(&manager, &format!("/repo/internal/{}", jar_path)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers[CACHE_CONTROL], "public, no-cache");
+        assert_eq!(headers[VARY], "Authorization");
+
+        #[cfg(feature = "admin-api")]
+        {
+            let (status, headers) = get_headers(&manager, "/api/v1/admin/audit-log").await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(headers[CACHE_CONTROL], "no-store");
+            assert!(!headers.contains_key(VARY));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_advisory_headers() {
+        let manager = manager(b"jar", b"pom").await;
+        let uri = "/repo/internal/com/acme/lib/1.0/lib-1.0.jar";
+        let advisory = |message: &str| ReplacementAdvisory {
+            group_id: MavenGroupId("com.acme".to_string()),
+            artifact_id: MavenArtifactId("lib".to_string()),
+            version_pattern: "1.*".to_string(),
+            replacement_version: "2.0".to_string(),
+            message: Some(message.to_string()),
+        };
+
+        let (_, headers) = get_headers(&manager, uri).await;
+        assert!(!headers.contains_key("x-advisory-replacement-version"));
+
+        // deprecated, i.e. still served
+        manager.advisories.add_advisory(advisory("use 2.0"));
+        let (status, headers) = get_headers(&manager, uri).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers["x-advisory-replacement-version"], "2.0");
+        assert_eq!(headers["x-advisory-message"], "use 2.0");
+
+        // messages that are no valid header values are left out
+        manager.advisories.add_advisory(advisory("use 2.0\nnow"));
+        let (status, headers) = get_headers(&manager, uri).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers["x-advisory-replacement-version"], "2.0");
+        assert!(!headers.contains_key("x-advisory-message"));
+
+        // so are versions
+        manager.advisories.add_advisory(ReplacementAdvisory { replacement_version: "2.0\n".to_string(), ..advisory("use 2.0") });
+        let (status, headers) = get_headers(&manager, uri).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(!headers.contains_key("x-advisory-replacement-version"));
+        assert_eq!(headers["x-advisory-message"], "use 2.0");
+
+        manager.advisories.add_advisory(advisory("use 2.0"));
+        manager.blocked_versions.add_rule(VersionBlockingRule {
+            group_id: MavenGroupId("com.acme".to_string()),
+            artifact_id: MavenArtifactId("lib".to_string()),
+            version_pattern: "1.0".to_string(),
+            message: "blocked".to_string(),
+        });
+        let (status, headers) = get_headers(&manager, uri).await;
+        assert_eq!(status, StatusCode::GONE);
+        assert_eq!(headers["x-advisory-replacement-version"], "2.0");
+        assert_eq!(headers["x-advisory-message"], "use 2.0");
+        assert_eq!(get(&manager, uri, "*/*").await.1, "blocked\nuse 2.0 - recommended replacement: com.acme:lib:2.0");
+    }
+
+    #[cfg(feature = "admin-api")]
+    #[tokio::test]
+    async fn test_advisory_admin() {
+        let manager = manager(b"jar", b"pom").await;
+        let advisory = r#"{"group_id":"com.acme","artifact_id":"lib","version_pattern":"1.*","replacement_version":"2.0","message":null}"#;
+        let target = r#"{"group_id":"com.acme","artifact_id":"lib","version_pattern":"1.*"}"#;
+
+        assert_eq!(send_json(&manager, "PUT", "/api/v1/admin/advisories", advisory).await, StatusCode::CREATED);
+        assert_eq!(send_json(&manager, "PUT", "/api/v1/admin/advisories", advisory).await, StatusCode::OK);
+        let invalid_version = r#"{"group_id":"com.acme","artifact_id":"lib","version_pattern":"1.*","replacement_version":"2.0\n","message":null}"#;
+        assert_eq!(send_json(&manager, "PUT", "/api/v1/admin/advisories", invalid_version).await, StatusCode::BAD_REQUEST);
+        assert_eq!(get(&manager, "/api/v1/admin/advisories", "application/json").await, (StatusCode::OK, format!("[{}]", advisory)));
+
+        let (_, headers) = get_headers(&manager, "/repo/internal/com/acme/lib/1.0/lib-1.0.jar").await;
+        assert_eq!(headers["x-advisory-replacement-version"], "2.0");
+
+        assert_eq!(send_json(&manager, "DELETE", "/api/v1/admin/advisories", target).await, StatusCode::NO_CONTENT);
+        assert_eq!(send_json(&manager, "DELETE", "/api/v1/admin/advisories", target).await, StatusCode::NOT_FOUND);
+        assert_eq!(get(&manager, "/api/v1/admin/advisories", "application/json").await, (StatusCode::OK, "[]".to_string()));
+
+        let (_, headers) = get_headers(&manager, "/repo/internal/com/acme/lib/1.0/lib-1.0.jar").await;
+        assert!(!headers.contains_key("x-advisory-replacement-version"));
+    }
+
+    #[cfg(feature = "admin-api")]
+    #[tokio::test]
+    async fn test_hosted_clone() {
+        let manager = manager(b"jar", b"pom").await;
+
+        assert_eq!(send(&manager, "PUT", "/api/v1/admin/clones/frozen?source=unknown").await, StatusCode::NOT_FOUND);
+        assert_eq!(send(&manager, "PUT", "/api/v1/admin/clones/frozen?source=internal").await, StatusCode::CREATED);
+        assert_eq!(send(&manager, "PUT", "/api/v1/admin/clones/frozen?source=internal").await, StatusCode::CONFLICT);
+
+        assert_eq!(get(&manager, "/clones/frozen/com/acme/lib/1.0/lib-1.0.jar", "*/*").await, (StatusCode::OK, "jar".to_string()));
+        assert_eq!(get(&manager, "/clones/frozen/com/acme/lib/1.0/lib-1.0.jar.sha1", "*/*").await, (StatusCode::OK, hex::encode(Sha1::digest(b"jar"))));
+        assert_eq!(get(&manager, "/clones/frozen/com/acme/lib/2.0/lib-2.0.jar", "*/*").await.0, StatusCode::NOT_FOUND);
+
+        let uri = "/clones/frozen/com/acme/lib/1.0/lib-1.0.jar";
+        assert_eq!(conditional(&manager, "GET", uri, IF_NONE_MATCH, true).await.0, StatusCode::NOT_MODIFIED);
+        assert_eq!(conditional(&manager, "GET", uri, IF_NONE_MATCH, false).await.0, StatusCode::OK);
+
+        assert_eq!(send(&manager, "DELETE", "/api/v1/admin/clones/frozen").await, StatusCode::NO_CONTENT);
+        assert_eq!(get(&manager, "/clones/frozen/com/acme/lib/1.0/lib-1.0.jar", "*/*").await.0, StatusCode::NOT_FOUND);
+        assert_eq!(send(&manager, "DELETE", "/api/v1/admin/clones/frozen").await, StatusCode::NOT_FOUND);
+    }
+
+    #[rstest]
+    #[case::get_etag_matching("GET", "/repo/internal/com/acme/lib/1.0/lib-1.0.jar", IF_NONE_MATCH, true, StatusCode::NOT_MODIFIED)]
+    #[case::get_etag_changed("GET", "/repo/internal/com/acme/lib/1.0/lib-1.0.jar", IF_NONE_MATCH, false, StatusCode::OK)]
+    #[case::get_not_modified_since("GET", "/repo/internal/com/acme/lib/1.0/lib-1.0.jar", IF_MODIFIED_SINCE, true, StatusCode::NOT_MODIFIED)]
+    #[case::get_modified_since("GET", "/repo/internal/com/acme/lib/1.0/lib-1.0.jar", IF_MODIFIED_SINCE, false, StatusCode::OK)]
+    #[case::head_etag_matching("HEAD", "/repo/internal/com/acme/lib/1.0/lib-1.0.jar", IF_NONE_MATCH, true, StatusCode::NOT_MODIFIED)]
+    #[case::head_etag_changed("HEAD", "/repo/internal/com/acme/lib/1.0/lib-1.0.jar", IF_NONE_MATCH, false, StatusCode::OK)]
+    #[case::head_not_modified_since("HEAD", "/repo/internal/com/acme/lib/1.0/lib-1.0.jar", IF_MODIFIED_SINCE, true, StatusCode::NOT_MODIFIED)]
+    #[case::webdav_get("GET", "/webdav/internal/com/acme/lib/1.0/lib-1.0.jar", IF_NONE_MATCH, true, StatusCode::NOT_MODIFIED)]
+    #[case::webdav_head("HEAD", "/webdav/internal/com/acme/lib/1.0/lib-1.0.jar", IF_MODIFIED_SINCE, true, StatusCode::NOT_MODIFIED)]
+    #[tokio::test]
+    async fn test_hosted_conditional(#[case] method: &str, #[case] uri: &str, #[case] header: HeaderName, #[case] matching: bool, #[case] expected: StatusCode) {
+        let manager = manager(b"jar", b"pom").await;
+
+        let (status, headers, body) = conditional(&manager, method, uri, header, matching).await;
+        assert_eq!(status, expected);
+        assert_eq!(headers[ETAG], etag_for_sha1(&Sha1::digest(b"jar").into()));
+        assert!(headers.contains_key(LAST_MODIFIED));
+        let expected_body: &[u8] = match (method, status) {
+            ("GET", StatusCode::OK) => b"jar",
+            _ => b"",
+        };
+        assert_eq!(body, expected_body);
+    }
+
+    #[tokio::test]
+    async fn test_hosted_directory_listing() {
+        let manager = manager(b"jar", b"pom").await;
+
+        let (status, body) = get(&manager, "/repo/internal/", "application/json").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, r#"{"entries":[{"name":"com","is_directory":true,"kind":"group"}]}"#);
+
+        let (_, body) = get(&manager, "/repo/internal/com/acme/lib/", "application/json").await;
+        assert_eq!(body, r#"{"entries":[{"name":"1.0","is_directory":true,"kind":"version"}]}"#);
+
+        let (status, body) = get(&manager, "/repo/internal/com/acme/lib/1.0/", "text/html").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains(r#"<title>/repo/internal/com/acme/lib/1.0/</title>"#));
+        assert!(body.contains(r#"<li class="file"><a href="lib-1.0.jar">lib-1.0.jar</a></li>"#));
+    }
+}
@@ -0,0 +1,323 @@
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+use axum::{Extension, Json, Router};
+use axum::extract::{Path, State};
+use axum::routing::{delete, get, post};
+use hyper::{Body, Response, StatusCode};
+use hyper::header::{CONTENT_DISPOSITION, CONTENT_LENGTH, CONTENT_TYPE};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use uuid::Uuid;
+
+use crate::api::repo::{message_response, principal_name, status_response};
+use crate::auth::credential_store::Principal;
+use crate::maven::hosted_repo::DeleteOutcome;
+use crate::maven::paths::{as_maven_path, maven_file_name, parse_maven_path};
+use crate::maven::quarantine::ArtifactStatus;
+use crate::repository_manager::RepositoryManager;
+use crate::util::cache_control::CachePolicy;
+use crate::util::content_type::{content_disposition, DispositionPolicy};
+
+/// Maintenance of individual repositories' content, nested below '/api/v1/repositories'. Unlike
+///  the rest of the admin API, these requests are subject to the repositories' access rules for
+///  deleting.
+pub(crate) fn repository_routes() -> Router<Arc<RepositoryManager>> {
+    Router::new()
+        .route("/:repo/artifacts/*path", delete(delete_artifact))
+        .route("/:repo/invalidate", post(post_invalidate))
+        .route("/:repo/quarantine", get(get_quarantine).delete(delete_quarantine))
+        .route("/:repo/quarantine/:blob_key", delete(delete_quarantined_artifact))
+}
+
+/// Deletes an artifact from a hosted repository, or evicts it from a remote repository's cache
+///  together with any remembered failed download, so that the next request downloads it again
+async fn delete_artifact(State(state): State<Arc<RepositoryManager>>, Path((repo, path)): Path<(String, String)>, principal: Option<Extension<Principal>>) -> Response<Body> {
+    let principal = principal_name(&principal);
+
+    match state.delete(&repo, &path, principal).await {
+        Ok(Some(DeleteOutcome::Deleted(_))) => return status_response(StatusCode::NO_CONTENT),
+        Ok(Some(DeleteOutcome::Conflict(message))) => return message_response(StatusCode::CONFLICT, message),
+        Ok(Some(DeleteOutcome::NotFound)) => return status_response(StatusCode::NOT_FOUND),
+        Ok(None) => {}
+        Err(e) => {
+            error!("error deleting {}/{}: {}", repo, path, e);
+            return status_response(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    let artifact_ref = match parse_maven_path(&path) {
+        Ok(artifact_ref) => artifact_ref,
+        Err(e) => return message_response(StatusCode::BAD_REQUEST, e.to_string()),
+    };
+    match state.evict_artifact(&repo, &artifact_ref, principal).await {
+        Ok(Some(eviction)) if !eviction.is_empty() => status_response(StatusCode::NO_CONTENT),
+        Ok(_) => status_response(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("error evicting {}/{}: {}", repo, path, e);
+            status_response(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct InvalidationTarget {
+    /// e.g. 'com.acme', covering 'com.acme' and its subgroups like 'com.acme.a'
+    group_prefix: String,
+}
+
+#[derive(Serialize)]
+struct InvalidationReport {
+    /// the repository paths of the evicted artifacts
+    artifacts: Vec<String>,
+    failed_downloads: usize,
+}
+
+/// Evicts all cached artifacts in a group and its subgroups from a remote repository, e.g. when
+///  upstream published corrected artifacts
+async fn post_invalidate(State(state): State<Arc<RepositoryManager>>, Path(repo): Path<String>, principal: Option<Extension<Principal>>, Json(target): Json<InvalidationTarget>) -> Result<Json<InvalidationReport>, (StatusCode, String)> {
+    let group_prefix = target.group_prefix.trim_matches('.');
+    if group_prefix.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "a group prefix is required".to_string()));
+    }
+
+    match state.evict_group(&repo, group_prefix, principal_name(&principal)).await {
+        Ok(Some(eviction)) => Ok(Json(InvalidationReport {
+            artifacts: eviction.artifacts.iter()
+                .map(|(artifact_ref, _)| as_maven_path(artifact_ref))
+                .collect(),
+            failed_downloads: eviction.failed_downloads,
+        })),
+        Ok(None) => Err((StatusCode::NOT_FOUND, format!("there is no remote repository '{}'", repo))),
+        Err(e) => {
+            error!("error invalidating {} in {}: {}", group_prefix, repo, e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, String::new()))
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct QuarantineEntry {
+    path: String,
+    blob_key: Uuid,
+    reason: String,
+    /// seconds since the UNIX epoch
+    quarantined_at: u64,
+    /// e.g. 'cached' if a later download succeeded
+    artifact_status: Option<ArtifactStatus>,
+    /// for fetching the download without admin credentials, if a blob id secret is configured
+    download_path: Option<String>,
+}
+
+/// Downloads of a remote repository that failed validation and are kept for inspection
+async fn get_quarantine(State(state): State<Arc<RepositoryManager>>, Path(repo): Path<String>) -> Result<Json<Vec<QuarantineEntry>>, (StatusCode, String)> {
+    match state.get_quarantined_artifacts(&repo).await {
+        Ok(Some(quarantined)) => Ok(Json(quarantined.into_iter()
+            .map(|(quarantined, artifact_status)| QuarantineEntry {
+                path: as_maven_path(&quarantined.artifact_ref),
+                blob_key: quarantined.blob_key,
+                reason: quarantined.reason,
+                quarantined_at: quarantined.quarantined_at.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default(),
+                artifact_status,
+                download_path: state.quarantined_blob_path(&repo, &quarantined.blob_key),
+            })
+            .collect())),
+        Ok(None) => Err((StatusCode::NOT_FOUND, format!("there is no remote repository '{}'", repo))),
+        Err(e) => {
+            error!("error listing quarantined artifacts of {}: {}", repo, e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, String::new()))
+        }
+    }
+}
+
+/// Serves a quarantined download by the opaque id in its 'download_path', see
+///  [RepositoryManager::quarantined_blob_path]. The id is the credential, so that the download can
+///  be handed to someone without access to the admin API, e.g. for a security analysis, and purging
+///  the download revokes it.
+pub(crate) async fn quarantined_blob(State(state): State<Arc<RepositoryManager>>, Path((repo, blob_id)): Path<(String, String)>) -> Response<Body> {
+    match state.get_quarantined_blob(&repo, &blob_id).await {
+        Ok(Some((quarantined, blob))) => {
+            let file_name = maven_file_name(&quarantined.artifact_ref);
+            let mut response_builder = CachePolicy::NoStore.apply(Response::builder())
+                .header(CONTENT_TYPE, "application/octet-stream")
+                .header(CONTENT_DISPOSITION, content_disposition(&quarantined.artifact_ref.file_extension, &file_name, DispositionPolicy::Attachment));
+            if let Some(size) = blob.size {
+                response_builder = response_builder.header(CONTENT_LENGTH, size);
+            }
+            response_builder
+                .body(Body::wrap_stream(blob.data))
+                .unwrap()
+        }
+        Ok(None) => status_response(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("error reading quarantined blob {} of {}: {}", blob_id, repo, e);
+            status_response(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct PurgeReport {
+    /// the repository paths of the purged downloads
+    artifacts: Vec<String>,
+}
+
+/// Purges all of a remote repository's quarantined downloads, deleting their blobs
+async fn delete_quarantine(State(state): State<Arc<RepositoryManager>>, Path(repo): Path<String>, principal: Option<Extension<Principal>>) -> Result<Json<PurgeReport>, (StatusCode, String)> {
+    match state.purge_quarantined_artifacts(&repo, None, principal_name(&principal)).await {
+        Ok(Some(purged)) => Ok(Json(PurgeReport {
+            artifacts: purged.iter()
+                .map(|quarantined| as_maven_path(&quarantined.artifact_ref))
+                .collect(),
+        })),
+        Ok(None) => Err((StatusCode::NOT_FOUND, format!("there is no remote repository '{}'", repo))),
+        Err(e) => {
+            error!("error purging quarantined artifacts of {}: {}", repo, e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, String::new()))
+        }
+    }
+}
+
+async fn delete_quarantined_artifact(State(state): State<Arc<RepositoryManager>>, Path((repo, blob_key)): Path<(String, Uuid)>, principal: Option<Extension<Principal>>) -> StatusCode {
+    match state.purge_quarantined_artifacts(&repo, Some(blob_key), principal_name(&principal)).await {
+        Ok(Some(purged)) if !purged.is_empty() => StatusCode::NO_CONTENT,
+        Ok(_) => StatusCode::NOT_FOUND,
+        Err(e) => {
+            error!("error purging quarantined blob {} of {}: {}", blob_key, repo, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hyper::{Request, Server};
+    use hyper::service::{make_service_fn, service_fn};
+    use tower::ServiceExt;
+
+    use crate::api::routes;
+    use crate::repository_manager::{parse_repository_config, RepositoryManagerConfig};
+    use crate::util::audit_log::AuditEventKind;
+
+    use super::*;
+
+    fn manager() -> Arc<RepositoryManager> {
+        Arc::new(RepositoryManager::new(RepositoryManagerConfig {
+            repositories: vec![
+                parse_repository_config("central=remote:http://127.0.0.1:1").unwrap(),
+                parse_repository_config("internal=hosted").unwrap(),
+            ],
+            ..Default::default()
+        }).unwrap())
+    }
+
+    async fn send(manager: &Arc<RepositoryManager>, method: &str, uri: &str, body: &str) -> (StatusCode, String) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = repository_routes().with_state(manager.clone()).oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, String::from_utf8_lossy(&body).to_string())
+    }
+
+    #[tokio::test]
+    async fn test_delete_artifact() {
+        let manager = manager();
+        assert_eq!(send(&manager, "DELETE", "/central/artifacts/com/acme/a/1.0/a-1.0.jar", "").await.0, StatusCode::NOT_FOUND);
+        assert_eq!(send(&manager, "DELETE", "/central/artifacts/com/acme/a/maven-metadata.xml", "").await.0, StatusCode::BAD_REQUEST);
+        assert_eq!(send(&manager, "DELETE", "/internal/artifacts/com/acme/a/1.0/a-1.0.jar", "").await.0, StatusCode::NOT_FOUND);
+        assert_eq!(send(&manager, "DELETE", "/other/artifacts/com/acme/a/1.0/a-1.0.jar", "").await.0, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_invalidate() {
+        let manager = manager();
+        let (status, body) = send(&manager, "POST", "/central/invalidate", r#"{"group_prefix": "com.acme"}"#).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, r#"{"artifacts":[],"failed_downloads":0}"#);
+        assert_eq!(manager.audit_log.recent_events().last().unwrap().kind, AuditEventKind::CacheInvalidated);
+
+        assert_eq!(send(&manager, "POST", "/central/invalidate", r#"{"group_prefix": "."}"#).await.0, StatusCode::BAD_REQUEST);
+        assert_eq!(send(&manager, "POST", "/internal/invalidate", r#"{"group_prefix": "com.acme"}"#).await.0, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_quarantine() {
+        let manager = manager();
+        assert_eq!(send(&manager, "GET", "/central/quarantine", "").await, (StatusCode::OK, "[]".to_string()));
+        assert_eq!(send(&manager, "DELETE", "/central/quarantine", "").await, (StatusCode::OK, r#"{"artifacts":[]}"#.to_string()));
+        assert_eq!(send(&manager, "DELETE", &format!("/central/quarantine/{}", Uuid::from_u128(1)), "").await.0, StatusCode::NOT_FOUND);
+        assert_eq!(send(&manager, "DELETE", "/central/quarantine/not-a-blob", "").await.0, StatusCode::BAD_REQUEST);
+        assert_eq!(send(&manager, "GET", "/internal/quarantine", "").await.0, StatusCode::NOT_FOUND);
+    }
+
+    /// serves a jar with a checksum file that does not match it, and 404 for everything else
+    fn serve_corrupt_upstream() -> String {
+        let make_service = make_service_fn(|_| async {
+            Ok::<_, hyper::Error>(service_fn(|request: Request<Body>| async move {
+                let body = match request.uri().path() {
+                    "/com/acme/a/1.0/a-1.0.jar" => Some("jar"),
+                    "/com/acme/a/1.0/a-1.0.jar.sha1" => Some("0123456789012345678901234567890123456789"),
+                    _ => None,
+                };
+                Ok::<_, hyper::Error>(match body {
+                    Some(body) => Response::new(Body::from(body)),
+                    None => Response::builder().status(StatusCode::NOT_FOUND).body(Body::empty()).unwrap(),
+                })
+            }))
+        });
+        let server = Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(make_service);
+        let uri = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+        uri
+    }
+
+    #[tokio::test]
+    async fn test_quarantined_blob() {
+        assert_eq!(manager().quarantined_blob_path("central", &Uuid::from_u128(1)), None);
+
+        let manager = Arc::new(RepositoryManager::new(RepositoryManagerConfig {
+            repositories: vec![
+                parse_repository_config(&format!("central=remote:{};checksum_files=verify_if_present", serve_corrupt_upstream())).unwrap(),
+                parse_repository_config("other=remote:http://127.0.0.1:1").unwrap(),
+            ],
+            blob_id_secret: Some("0123456789abcdef".to_string()),
+            ..Default::default()
+        }).unwrap());
+        assert!(manager.repo.get_artifact(&parse_maven_path("com/acme/a/1.0/a-1.0.jar").unwrap()).await.is_err());
+
+        let (status, body) = send(&manager, "GET", "/central/quarantine", "").await;
+        assert_eq!(status, StatusCode::OK);
+        let entries: serde_json::Value = serde_json::from_str(&body).unwrap();
+        let blob_key = entries[0]["blob_key"].as_str().unwrap().to_string();
+        let download_path = entries[0]["download_path"].as_str().unwrap().to_string();
+        let blob_id = download_path.strip_prefix("/api/v1/quarantined-blobs/central/").unwrap().to_string();
+        assert!(!blob_id.contains(&blob_key));
+
+        let get = |uri: String| {
+            let manager = manager.clone();
+            async move {
+                let response = routes().with_state(manager).oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap()).await.unwrap();
+                let (parts, body) = response.into_parts();
+                (parts.status, parts.headers, hyper::body::to_bytes(body).await.unwrap())
+            }
+        };
+        let (status, headers, body) = get(download_path.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "jar");
+        assert_eq!(headers[CONTENT_DISPOSITION], "attachment; filename=\"a-1.0.jar\"");
+        assert_eq!(headers[hyper::header::CACHE_CONTROL], "no-store");
+
+        assert_eq!(get(format!("/api/v1/quarantined-blobs/central/{}", blob_key)).await.0, StatusCode::NOT_FOUND);
+        assert_eq!(get(format!("/api/v1/quarantined-blobs/other/{}", blob_id)).await.0, StatusCode::NOT_FOUND);
+        assert_eq!(get(format!("{}x", download_path)).await.0, StatusCode::NOT_FOUND);
+
+        // purging revokes the download
+        assert_eq!(send(&manager, "DELETE", "/central/quarantine", "").await.0, StatusCode::OK);
+        assert_eq!(get(download_path).await.0, StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,97 @@
+use std::sync::Arc;
+
+use axum::Json;
+use axum::extract::{Query, State};
+use hyper::StatusCode;
+use hyper::header::{HeaderMap, HOST};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::maven::coordinates::{MavenArtifactId, MavenArtifactRef, MavenClassifier, MavenCoordinates, MavenGroupId, MavenVersion};
+use crate::maven::paths::as_maven_path;
+use crate::maven::version_resolution::{is_snapshot, VersionConstraint};
+use crate::repository_manager::RepositoryManager;
+
+#[derive(Deserialize)]
+pub(crate) struct ResolveQuery {
+    g: String,
+    a: String,
+    /// a version, a range like '[1.0,2.0)' or 'LATEST' / 'RELEASE'
+    constraint: String,
+    /// file extension of the artifact to download, default '.jar'
+    extension: Option<String>,
+    classifier: Option<String>,
+    #[serde(default)]
+    snapshots: bool,
+}
+
+#[derive(Serialize)]
+pub(crate) struct Resolution {
+    group_id: String,
+    artifact_id: String,
+    version: String,
+    download_url: String,
+}
+
+/// Resolves a version constraint over local and upstream metadata, for scripts that need an
+///  artifact without running a full Maven resolver
+pub(crate) async fn resolve(State(state): State<Arc<RepositoryManager>>, Query(query): Query<ResolveQuery>, headers: HeaderMap) -> Result<Json<Resolution>, (StatusCode, String)> {
+    let constraint = VersionConstraint::parse(&query.constraint)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    let group_id = MavenGroupId(query.g);
+    let artifact_id = MavenArtifactId(query.a);
+    let version = match state.resolve_version(&group_id, &artifact_id, &constraint, query.snapshots).await {
+        Ok(Some(version)) => version,
+        Ok(None) => return Err((StatusCode::NOT_FOUND, format!("no version of {}:{} matches {}", group_id.0, artifact_id.0, query.constraint))),
+        Err(e) => {
+            error!("error resolving version: {}", e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, String::new()));
+        }
+    };
+
+    let artifact_ref = MavenArtifactRef {
+        coordinates: MavenCoordinates {
+            group_id,
+            artifact_id,
+            version: MavenVersion::Release(version.clone()),
+        },
+        classifier: query.classifier.map(MavenClassifier::Classified).unwrap_or(MavenClassifier::Unclassified),
+        file_extension: match query.extension {
+            None => ".jar".to_string(),
+            Some(e) if e.starts_with('.') => e,
+            Some(e) => format!(".{}", e),
+        },
+    };
+
+    // snapshots are downloaded as their most recent timestamped build
+    let artifact_ref = if is_snapshot(&version) {
+        match state.resolve_snapshot(&artifact_ref).await {
+            Ok(Some(artifact_ref)) => artifact_ref,
+            Ok(None) => return Err((StatusCode::NOT_FOUND, format!("no build of {} for the requested classifier and extension", version))),
+            Err(e) => {
+                error!("error resolving snapshot: {}", e);
+                return Err((StatusCode::INTERNAL_SERVER_ERROR, String::new()));
+            }
+        }
+    }
+    else {
+        artifact_ref
+    };
+
+    let download_url = download_url(&headers, &artifact_ref);
+    Ok(Json(Resolution {
+        group_id: artifact_ref.coordinates.group_id.0,
+        artifact_id: artifact_ref.coordinates.artifact_id.0,
+        version,
+        download_url,
+    }))
+}
+
+/// The URL for downloading an artifact, relative to the request's host if it is known
+pub(crate) fn download_url(headers: &HeaderMap, artifact_ref: &MavenArtifactRef) -> String {
+    match headers.get(HOST).and_then(|h| h.to_str().ok()) {
+        Some(host) => format!("http://{}/repo/{}", host, as_maven_path(artifact_ref)),
+        None => format!("/repo/{}", as_maven_path(artifact_ref)),
+    }
+}
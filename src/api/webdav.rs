@@ -0,0 +1,219 @@
+use std::sync::Arc;
+
+use axum::Extension;
+use axum::extract::{BodyStream, Path, State};
+use futures::TryStreamExt;
+use hyper::{Body, Method, Response, StatusCode};
+use hyper::header::{ALLOW, CONTENT_TYPE, HeaderMap};
+use tracing::warn;
+
+use crate::auth::credential_store::Principal;
+use crate::maven::hosted_repo::{DeleteOutcome, HostedMavenRepo};
+use crate::maven::paths::parse_maven_path;
+use crate::repository_manager::{RepositoryManager, RepositoryRef};
+use crate::util::blob::BlobStat;
+use crate::util::conditional_request::etag_for_sha1;
+use crate::api::repo::{deploy_response, hosted_repo_response, message_response, principal_name, status_response};
+
+const ALLOWED_METHODS: &str = "OPTIONS, GET, HEAD, PUT, DELETE, PROPFIND, MKCOL";
+
+/// A file or directory in a PROPFIND response
+struct DavResource {
+    href: String,
+    /// None for directories
+    stat: Option<BlobStat>,
+}
+
+/// WebDAV access to hosted repositories for deployment tools that do not speak plain HTTP PUT.
+///  Uploads go through the same validation as `PUT /repo/...`, and directories exist implicitly
+///  as long as there are artifacts in them, so MKCOL has no effect.
+pub(crate) async fn webdav(State(state): State<Arc<RepositoryManager>>, Path(repo_path): Path<String>, principal: Option<Extension<Principal>>, method: Method, headers: HeaderMap, body: BodyStream) -> Response<Body> {
+    // the repository root may be addressed without a trailing '/'
+    let repo_path = match repo_path.contains('/') {
+        true => repo_path,
+        false => format!("{}/", repo_path),
+    };
+    let (hosted, path) = match state.find_repository(&repo_path) {
+        Some((RepositoryRef::Hosted(hosted), path)) => (hosted, path),
+        _ => return status_response(StatusCode::NOT_FOUND),
+    };
+
+    match method.as_str() {
+        "OPTIONS" => Response::builder()
+            .header("DAV", "1")
+            .header(ALLOW, ALLOWED_METHODS)
+            .body(Body::empty())
+            .unwrap(),
+        "GET" => hosted_repo_response(&state, hosted, path, &Default::default(), &headers, false).await,
+        "HEAD" => hosted_repo_response(&state, hosted, path, &Default::default(), &headers, true).await,
+        "PUT" => deploy_response(&state, &repo_path, principal_name(&principal), &headers, Box::pin(body.map_err(anyhow::Error::from))).await,
+        "PROPFIND" => propfind(hosted, path, &headers).await,
+        "MKCOL" => mkcol(hosted, path).await,
+        "DELETE" => {
+            match state.delete(hosted.name(), path, principal_name(&principal)).await {
+                Ok(Some(DeleteOutcome::Deleted(_))) => status_response(StatusCode::NO_CONTENT),
+                Ok(Some(DeleteOutcome::Conflict(message))) => message_response(StatusCode::CONFLICT, message),
+                Ok(Some(DeleteOutcome::NotFound)) | Ok(None) => status_response(StatusCode::NOT_FOUND),
+                Err(e) => {
+                    warn!("error deleting {}: {}", repo_path, e);
+                    status_response(StatusCode::INTERNAL_SERVER_ERROR)
+                }
+            }
+        }
+        _ => Response::builder()
+            .status(StatusCode::METHOD_NOT_ALLOWED)
+            .header(ALLOW, ALLOWED_METHODS)
+            .body(Body::empty())
+            .unwrap(),
+    }
+}
+
+/// All properties are returned regardless of the request body. 'Depth: infinity' is treated like
+///  'Depth: 1', since listing an entire repository is expensive.
+async fn propfind(hosted: &HostedMavenRepo, path: &str, headers: &HeaderMap) -> Response<Body> {
+    let include_children = headers.get("Depth")
+        .map(|h| h.as_bytes() != b"0")
+        .unwrap_or(true);
+    let href_prefix = format!("/webdav/{}/", hosted.name());
+
+    let resources = match dav_resources(hosted, path, &href_prefix, include_children).await {
+        Ok(Some(resources)) => resources,
+        Ok(None) => return status_response(StatusCode::NOT_FOUND),
+        Err(e) => {
+            warn!("error listing {}{}: {}", href_prefix, path, e);
+            return status_response(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    Response::builder()
+        .status(StatusCode::MULTI_STATUS)
+        .header(CONTENT_TYPE, "application/xml; charset=utf-8")
+        .body(Body::from(render_multistatus(&resources)))
+        .unwrap()
+}
+
+/// None if there is neither an artifact nor a directory at the path
+async fn dav_resources(hosted: &HostedMavenRepo, path: &str, href_prefix: &str, include_children: bool) -> anyhow::Result<Option<Vec<DavResource>>> {
+    if !path.is_empty() && !path.ends_with('/') {
+        if let Some(stat) = artifact_stat(hosted, path).await? {
+            return Ok(Some(vec![DavResource { href: format!("{}{}", href_prefix, path), stat: Some(stat) }]));
+        }
+    }
+
+    let directory_path = path.trim_end_matches('/');
+    let listing = hosted.get_directory_listing(directory_path).await?;
+    if listing.entries.is_empty() && !directory_path.is_empty() {
+        return Ok(None);
+    }
+
+    let directory_href = match directory_path.is_empty() {
+        true => href_prefix.to_string(),
+        false => format!("{}{}/", href_prefix, directory_path),
+    };
+    let mut resources = vec![DavResource { href: directory_href.clone(), stat: None }];
+    if include_children {
+        for entry in listing.entries {
+            if entry.is_directory {
+                resources.push(DavResource { href: format!("{}{}/", directory_href, entry.name), stat: None });
+                continue;
+            }
+            let child_path = match directory_path.is_empty() {
+                true => entry.name.clone(),
+                false => format!("{}/{}", directory_path, entry.name),
+            };
+            if let Some(stat) = artifact_stat(hosted, &child_path).await? {
+                resources.push(DavResource { href: format!("{}{}", directory_href, entry.name), stat: Some(stat) });
+            }
+        }
+    }
+    Ok(Some(resources))
+}
+
+async fn artifact_stat(hosted: &HostedMavenRepo, path: &str) -> anyhow::Result<Option<BlobStat>> {
+    match parse_maven_path(path) {
+        Ok(artifact_ref) => hosted.get_artifact_stat(&artifact_ref).await,
+        Err(_) => Ok(None),
+    }
+}
+
+/// Directories are created implicitly by deploying into them, so only existing resources are
+///  refused as required by RFC 4918
+async fn mkcol(hosted: &HostedMavenRepo, path: &str) -> Response<Body> {
+    let path = path.trim_end_matches('/');
+    let exists = match artifact_stat(hosted, path).await {
+        Ok(Some(_)) => true,
+        Ok(None) => match hosted.get_directory_listing(path).await {
+            Ok(listing) => path.is_empty() || !listing.entries.is_empty(),
+            Err(e) => {
+                warn!("error listing {}: {}", path, e);
+                return status_response(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        },
+        Err(e) => {
+            warn!("error getting {}: {}", path, e);
+            return status_response(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    match exists {
+        true => status_response(StatusCode::METHOD_NOT_ALLOWED),
+        false => status_response(StatusCode::CREATED),
+    }
+}
+
+fn render_multistatus(resources: &[DavResource]) -> String {
+    let mut xml = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<D:multistatus xmlns:D=\"DAV:\">\n".to_string();
+    for resource in resources {
+        xml.push_str(&format!("  <D:response>\n    <D:href>{}</D:href>\n    <D:propstat>\n      <D:prop>\n", escape(&resource.href)));
+        match &resource.stat {
+            None => xml.push_str("        <D:resourcetype><D:collection/></D:resourcetype>\n"),
+            Some(stat) => {
+                let last_modified = stat.last_modified.unwrap_or(stat.created);
+                xml.push_str("        <D:resourcetype/>\n");
+                xml.push_str(&format!("        <D:getcontentlength>{}</D:getcontentlength>\n", stat.size));
+                xml.push_str(&format!("        <D:getlastmodified>{}</D:getlastmodified>\n", httpdate::fmt_http_date(last_modified)));
+                if let Some(sha1) = &stat.sha1 {
+                    xml.push_str(&format!("        <D:getetag>{}</D:getetag>\n", escape(&etag_for_sha1(sha1))));
+                }
+            }
+        }
+        xml.push_str("      </D:prop>\n      <D:status>HTTP/1.1 200 OK</D:status>\n    </D:propstat>\n  </D:response>\n");
+    }
+    xml.push_str("</D:multistatus>\n");
+    xml
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, SystemTime};
+
+    use super::*;
+
+    #[test]
+    fn test_render_multistatus() {
+        let stat = BlobStat {
+            size: 3,
+            md5: None,
+            sha1: Some([0; 20]),
+            created: SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+            last_modified: None,
+            upstream_headers: vec![],
+        };
+        let xml = render_multistatus(&[
+            DavResource { href: "/webdav/internal/com/".to_string(), stat: None },
+            DavResource { href: "/webdav/internal/com/a.jar".to_string(), stat: Some(stat) },
+        ]);
+
+        assert!(xml.contains("<D:href>/webdav/internal/com/</D:href>\n    <D:propstat>\n      <D:prop>\n        <D:resourcetype><D:collection/></D:resourcetype>"));
+        assert!(xml.contains("<D:getcontentlength>3</D:getcontentlength>"));
+        assert!(xml.contains("<D:getlastmodified>Tue, 14 Nov 2023 22:13:20 GMT</D:getlastmodified>"));
+        assert!(xml.contains(&format!("<D:getetag>&quot;{}&quot;</D:getetag>", "0".repeat(40))));
+        assert_eq!(xml.matches("<D:response>").count(), 2);
+    }
+}
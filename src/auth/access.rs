@@ -0,0 +1,71 @@
+use serde::Deserialize;
+
+use crate::auth::credential_store::Principal;
+
+/// matches every authenticated user in an [AccessPolicy]'s user lists
+pub const ANY_USER: &str = "*";
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Permission {
+    /// downloads, directory listings and metadata
+    Read,
+    /// uploads
+    Deploy,
+    Delete,
+}
+
+/// Who may access a repository. Access is unrestricted (including anonymous access) if there is
+///  no list for it, otherwise it requires an authenticated user who is on the list, or '*' for
+///  any authenticated user. Deleting requires deploy access.
+#[derive(Clone, Eq, PartialEq, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccessPolicy {
+    #[serde(default)]
+    pub read: Option<Vec<String>>,
+    #[serde(default)]
+    pub deploy: Option<Vec<String>>,
+}
+impl AccessPolicy {
+    pub fn is_restricted(&self) -> bool {
+        self.read.is_some() || self.deploy.is_some()
+    }
+
+    pub fn allows(&self, permission: Permission, principal: Option<&Principal>) -> bool {
+        let users = match permission {
+            Permission::Read => &self.read,
+            Permission::Deploy | Permission::Delete => &self.deploy,
+        };
+        match (users, principal) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(users), Some(principal)) => users.iter().any(|user| user == ANY_USER || user == &principal.name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::unrestricted(None, None, true)]
+    #[case::unrestricted_authenticated(None, Some("alice"), true)]
+    #[case::anonymous(Some(&["alice"][..]), None, false)]
+    #[case::listed(Some(&["alice"][..]), Some("alice"), true)]
+    #[case::not_listed(Some(&["alice"][..]), Some("bob"), false)]
+    #[case::any_user(Some(&["*"][..]), Some("bob"), true)]
+    #[case::nobody(Some(&[][..]), Some("alice"), false)]
+    fn test_allows(#[case] deploy: Option<&[&str]>, #[case] principal: Option<&str>, #[case] expected: bool) {
+        let policy = AccessPolicy {
+            read: None,
+            deploy: deploy.map(|users| users.iter().map(|s| s.to_string()).collect()),
+        };
+        let principal = principal.map(|name| Principal { name: name.to_string(), roles: vec![] });
+        assert_eq!(policy.allows(Permission::Deploy, principal.as_ref()), expected);
+        assert_eq!(policy.allows(Permission::Delete, principal.as_ref()), expected);
+        assert!(policy.allows(Permission::Read, None));
+    }
+}
@@ -0,0 +1,439 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use hyper::{Body, Method, Request, StatusCode};
+use hyper::header::{AUTHORIZATION, HeaderMap, WWW_AUTHENTICATE};
+use percent_encoding::percent_decode_str;
+use tracing::{debug, warn};
+
+use crate::auth::access::{AccessPolicy, Permission};
+use crate::auth::authorization::Authorization;
+use crate::auth::credential_store::{CredentialStore, Principal};
+use crate::repository_manager::{RepositoryConfig, RepositoryKind};
+
+/// API endpoints that serve data of the default repository
+const DEFAULT_REPOSITORY_API: &[&str] = &["/api/v1/resolve", "/api/v1/platforms", "/api/v1/artifact-metadata", "/api/v1/artifact-diff", "/api/v1/dependency-updates"];
+
+/// the role that grants access to the admin API unless [Authenticator::with_admin_roles] configures others
+pub const DEFAULT_ADMIN_ROLE: &str = "admin";
+
+/// The repository a frozen clone was created from, by the clone's name, see
+///  [RepositoryManager::clone_source](crate::repository_manager::RepositoryManager::clone_source)
+pub type CloneSources = Arc<dyn Fn(&str) -> Option<String> + Send + Sync>;
+
+/// what a request accesses
+#[derive(Debug, Eq, PartialEq)]
+enum Target {
+    Repository {
+        repository: String,
+        /// relative to the repository's root, None for API requests
+        path: Option<String>,
+        permission: Permission,
+    },
+    /// the admin API, which requires one of the admin roles if requests are authenticated
+    Admin,
+}
+
+/// Why [Authenticator::authorize] refuses a request
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Refusal {
+    /// missing or invalid credentials
+    Unauthenticated,
+    /// the principal is not allowed to access the target
+    Forbidden,
+    /// authenticating the request failed, e.g. because a directory service is not available
+    Error,
+}
+
+/// what a request's Authorization header amounts to
+#[derive(Debug, Eq, PartialEq)]
+enum Credentials {
+    Missing,
+    Valid(Principal),
+    Invalid,
+}
+
+/// Checks requests against the repositories' [AccessPolicy]s and the roles' [Authorization], see
+///  [authenticate]
+pub struct Authenticator {
+    /// None disables authentication, i.e. all requests are anonymous
+    credential_store: Option<Arc<dyn CredentialStore>>,
+    /// by repository name
+    access: BTreeMap<String, AccessPolicy>,
+    /// serves repository paths that do not start with a repository name
+    default_repository: Option<String>,
+    /// the only hosted repository, which API requests for deploys refer to if they do not name one
+    default_hosted_repository: Option<String>,
+    authorization: Authorization,
+    admin_roles: Vec<String>,
+    /// clones are governed by their source repository's access, the default repository's if this
+    ///  is not set
+    clone_sources: Option<CloneSources>,
+}
+impl Authenticator {
+    pub fn new(credential_store: Option<Arc<dyn CredentialStore>>, repositories: &[RepositoryConfig], authorization: Authorization) -> Authenticator {
+        Authenticator {
+            credential_store,
+            authorization,
+            access: repositories.iter()
+                .map(|r| (r.name.clone(), r.access.clone()))
+                .collect(),
+            default_repository: repositories.iter()
+                .find(|r| matches!(r.kind, RepositoryKind::Remote { .. }))
+                .map(|r| r.name.clone()),
+            default_hosted_repository: match repositories.iter().filter(|r| r.kind == RepositoryKind::Hosted).collect::<Vec<_>>()[..] {
+                [hosted] => Some(hosted.name.clone()),
+                _ => None,
+            },
+            admin_roles: vec![DEFAULT_ADMIN_ROLE.to_string()],
+            clone_sources: None,
+        }
+    }
+
+    /// the roles that grant access to the admin API, see [DEFAULT_ADMIN_ROLE]
+    pub fn with_admin_roles(self, admin_roles: Vec<String>) -> Authenticator {
+        Authenticator {
+            admin_roles,
+            ..self
+        }
+    }
+
+    pub fn with_clone_sources(self, clone_sources: CloneSources) -> Authenticator {
+        Authenticator {
+            clone_sources: Some(clone_sources),
+            ..self
+        }
+    }
+
+    /// The repository a request accesses and how, or the admin API. None for requests that do not
+    ///  access a repository's content.
+    fn target(&self, path: &str, query: Option<&str>, method: &Method) -> Option<Target> {
+        let permission = match method.as_str() {
+            "GET" | "HEAD" | "OPTIONS" | "PROPFIND" => Permission::Read,
+            "DELETE" => Permission::Delete,
+            _ => Permission::Deploy,
+        };
+        // handlers see the decoded path, so repository names must be matched against it as well
+        let path = percent_decode_str(path).decode_utf8_lossy();
+
+        // previews and project info address files the same way as the repository paths
+        let (repository, path) = if let Some(repo_path) = path.strip_prefix("/repo/")
+            .or_else(|| path.strip_prefix("/api/v1/preview/"))
+            .or_else(|| path.strip_prefix("/api/v1/project-info/"))
+        {
+            match repo_path.split_once('/') {
+                Some((name, path)) if self.access.contains_key(name) => (name.to_string(), path),
+                _ => (self.default_repository.clone()?, repo_path),
+            }
+        }
+        else if let Some(repo_path) = path.strip_prefix("/webdav/") {
+            let (name, path) = repo_path.split_once('/').unwrap_or((repo_path, ""));
+            (name.to_string(), path)
+        }
+        else if let Some(clone_path) = path.strip_prefix("/clones/") {
+            let (clone_name, path) = clone_path.split_once('/').unwrap_or((clone_path, ""));
+            let source = self.clone_sources.as_ref().and_then(|clone_sources| clone_sources(clone_name));
+            (source.or_else(|| self.default_repository.clone())?, path)
+        }
+        else if let Some(api_path) = path.strip_prefix("/api/v1/repositories/") {
+            // evicting and invalidating cached artifacts is deleting as far as access is concerned, and
+            //  so is inspecting quarantined downloads
+            let (name, api_path) = api_path.split_once('/').unwrap_or((api_path, ""));
+            return Some(Target::Repository {
+                repository: name.to_string(),
+                path: api_path.strip_prefix("artifacts/").map(|path| path.to_string()),
+                permission: Permission::Delete,
+            });
+        }
+        else if path == "/api/v1/admin" || path.starts_with("/api/v1/admin/") {
+            return Some(Target::Admin);
+        }
+        else if path == "/api/v1/can-deploy" {
+            return self.can_deploy_target(query);
+        }
+        else if DEFAULT_REPOSITORY_API.contains(&path.as_ref()) {
+            return Some(Target::Repository {
+                repository: self.default_repository.clone()?,
+                path: None,
+                permission: Permission::Read,
+            });
+        }
+        else {
+            return None;
+        };
+        Some(Target::Repository {
+            repository,
+            path: Some(path.to_string()),
+            permission,
+        })
+    }
+
+    /// Asking whether a deploy would be accepted requires the access the deploy requires, i.e.
+    ///  deploy access to the version's directory. None if the query does not determine the
+    ///  repository, which the API refuses anyway.
+    fn can_deploy_target(&self, query: Option<&str>) -> Option<Target> {
+        let params = query.unwrap_or_default()
+            .split('&')
+            .filter_map(|param| param.split_once('='))
+            .map(|(name, value)| (name, percent_decode_str(&value.replace('+', " ")).decode_utf8_lossy().to_string()))
+            .collect::<BTreeMap<_, _>>();
+        let repository = match params.get("repo") {
+            Some(repository) => repository.to_string(),
+            None => self.default_hosted_repository.clone()?,
+        };
+        let path = match (params.get("g"), params.get("a"), params.get("v")) {
+            (Some(g), Some(a), Some(v)) => Some(format!("{}/{}/{}/", g.replace('.', "/"), a, v)),
+            _ => None,
+        };
+        Some(Target::Repository {
+            repository,
+            path,
+            permission: Permission::Deploy,
+        })
+    }
+
+    async fn credentials(&self, headers: &HeaderMap) -> anyhow::Result<Credentials> {
+        let credential_store = match &self.credential_store {
+            Some(credential_store) => credential_store,
+            None => return Ok(Credentials::Missing),
+        };
+        let authorization = match headers.get(AUTHORIZATION) {
+            Some(authorization) => authorization.to_str().unwrap_or_default(),
+            None => return Ok(Credentials::Missing),
+        };
+
+        let principal = match authorization.split_once(' ') {
+            Some((scheme, encoded)) if scheme.eq_ignore_ascii_case("basic") => {
+                let decoded = STANDARD.decode(encoded.trim()).ok()
+                    .and_then(|decoded| String::from_utf8(decoded).ok());
+                match decoded.as_ref().and_then(|decoded| decoded.split_once(':')) {
+                    Some((user, password)) => credential_store.authenticate_password(user, password).await?,
+                    None => None,
+                }
+            }
+            Some((scheme, token)) if scheme.eq_ignore_ascii_case("bearer") => credential_store.authenticate_token(token.trim()).await?,
+            _ => None,
+        };
+        Ok(match principal {
+            Some(principal) => Credentials::Valid(principal),
+            None => Credentials::Invalid,
+        })
+    }
+
+    /// Authenticates a request for 'path' (and 'query') of the HTTP routes and checks that its
+    ///  principal may access what the path refers to. Returns the principal, None for anonymous
+    ///  requests and requests that do not access a repository or the admin API. Other frontends
+    ///  like gRPC pass the HTTP path that is equivalent to their request.
+    pub async fn authorize(&self, path: &str, query: Option<&str>, method: &Method, headers: &HeaderMap) -> Result<Option<Principal>, Refusal> {
+        let target = match self.target(path, query, method) {
+            Some(target) => target,
+            None => return Ok(None),
+        };
+
+        let principal = match self.credentials(headers).await {
+            Ok(Credentials::Missing) => None,
+            Ok(Credentials::Valid(principal)) => Some(principal),
+            Ok(Credentials::Invalid) => {
+                debug!("invalid credentials for {}", path);
+                return Err(Refusal::Unauthenticated);
+            }
+            Err(e) => {
+                warn!("error authenticating request for {}: {}", path, e);
+                return Err(Refusal::Error);
+            }
+        };
+
+        if !self.allows(&target, principal.as_ref()) {
+            return match principal {
+                None => Err(Refusal::Unauthenticated),
+                Some(principal) => {
+                    debug!("{} has no access to {:?}", principal.name, target);
+                    Err(Refusal::Forbidden)
+                }
+            };
+        }
+        Ok(principal)
+    }
+
+    fn allows(&self, target: &Target, principal: Option<&Principal>) -> bool {
+        match target {
+            Target::Repository { repository, path, permission } => {
+                let is_allowed_by_policy = self.access.get(repository)
+                    .map(|policy| policy.allows(*permission, principal))
+                    .unwrap_or(true);
+                is_allowed_by_policy && self.authorization.allows(repository, path.as_deref(), *permission, principal)
+            }
+            // without a source of credentials, everybody is anonymous and the admin API is open
+            Target::Admin => self.credential_store.is_none() || principal
+                .map(|principal| principal.roles.iter().any(|role| self.admin_roles.contains(role)))
+                .unwrap_or(false),
+        }
+    }
+}
+
+/// Middleware authenticating requests with HTTP Basic or bearer credentials and enforcing the
+///  repositories' access policies, the roles' authorization rules and the admin roles for the
+///  admin API. The authenticated [Principal] is added to the request's
+///  extensions for handlers, e.g. to record who deployed an artifact. Invalid credentials are
+///  refused even for repositories that allow anonymous access, so that clients notice.
+pub async fn authenticate(State(authenticator): State<Arc<Authenticator>>, mut request: Request<Body>, next: Next<Body>) -> Response {
+    let authorized = authenticator.authorize(request.uri().path(), request.uri().query(), request.method(), request.headers()).await;
+    match authorized {
+        Ok(Some(principal)) => {
+            request.extensions_mut().insert(principal);
+            next.run(request).await
+        }
+        Ok(None) => next.run(request).await,
+        Err(Refusal::Unauthenticated) => unauthorized_response(),
+        Err(Refusal::Forbidden) => StatusCode::FORBIDDEN.into_response(),
+        Err(Refusal::Error) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+fn unauthorized_response() -> Response {
+    (StatusCode::UNAUTHORIZED, [(WWW_AUTHENTICATE, "Basic realm=\"arti-vault\"")]).into_response()
+}
+
+#[cfg(test)]
+mod test {
+    use axum::{Extension, middleware, Router};
+    use axum::routing::any;
+    use rstest::rstest;
+    use tower::ServiceExt;
+
+    use crate::auth::authorization::AuthorizationRule;
+    use crate::auth::credential_store::{hash_password, hash_token};
+    use crate::auth::file_credential_store::FileCredentialStore;
+    use crate::repository_manager::parse_repository_config;
+
+    use super::*;
+
+    fn authenticator() -> Authenticator {
+        let mut internal = parse_repository_config("internal=hosted").unwrap();
+        internal.access = AccessPolicy {
+            read: Some(vec!["*".to_string()]),
+            deploy: Some(vec!["ci".to_string()]),
+        };
+        let credential_store = FileCredentialStore::parse(&format!(r#"
+            [[users]]
+            name = "alice"
+            password = "{}"
+
+            [[users]]
+            name = "ci"
+            tokens = ["{}"]
+            roles = ["team-x"]
+
+            [[users]]
+            name = "ops"
+            tokens = ["{}"]
+            roles = ["admin"]
+        "#, hash_password("salt", "secret"), hash_token("ci-token"), hash_token("ops-token"))).unwrap();
+
+        let authorization = Authorization::new(vec![AuthorizationRule {
+            role: "team-x".to_string(),
+            repositories: vec!["*".to_string()],
+            group_ids: vec!["com.acme.teamx".to_string()],
+            permissions: vec![Permission::Deploy],
+        }]);
+        Authenticator::new(Some(Arc::new(credential_store)), &[
+            parse_repository_config("central=remote:https://repo1.maven.org/maven2").unwrap(),
+            internal,
+        ], authorization)
+            .with_clone_sources(Arc::new(|name| (name == "internal-frozen").then(|| "internal".to_string())))
+    }
+
+    fn repository_target(repository: &str, path: Option<&str>, permission: Permission) -> Target {
+        Target::Repository {
+            repository: repository.to_string(),
+            path: path.map(|s| s.to_string()),
+            permission,
+        }
+    }
+
+    #[rstest]
+    #[case::default_repository("/repo/org/a/a/1.0/a-1.0.jar", "GET", Some(repository_target("central", Some("org/a/a/1.0/a-1.0.jar"), Permission::Read)))]
+    #[case::named_repository("/repo/internal/org/a/a/1.0/a-1.0.jar", "PUT", Some(repository_target("internal", Some("org/a/a/1.0/a-1.0.jar"), Permission::Deploy)))]
+    #[case::encoded_name("/repo/intern%61l/org/a/a/1.0/a-1.0.jar", "GET", Some(repository_target("internal", Some("org/a/a/1.0/a-1.0.jar"), Permission::Read)))]
+    #[case::webdav("/webdav/internal", "PROPFIND", Some(repository_target("internal", Some(""), Permission::Read)))]
+    #[case::webdav_delete("/webdav/internal/org/", "DELETE", Some(repository_target("internal", Some("org/"), Permission::Delete)))]
+    #[case::clone("/clones/c1/org/a/a/1.0/a-1.0.jar", "GET", Some(repository_target("central", Some("org/a/a/1.0/a-1.0.jar"), Permission::Read)))]
+    #[case::clone_of_hosted("/clones/internal-frozen/org/a/a/1.0/a-1.0.jar", "GET", Some(repository_target("internal", Some("org/a/a/1.0/a-1.0.jar"), Permission::Read)))]
+    #[case::api("/api/v1/artifact-metadata", "POST", Some(repository_target("central", None, Permission::Read)))]
+    #[case::preview("/api/v1/preview/internal/org/a/a/1.0/a-1.0.pom", "GET", Some(repository_target("internal", Some("org/a/a/1.0/a-1.0.pom"), Permission::Read)))]
+    #[case::project_info("/api/v1/project-info/internal/org/a/a/1.0/a-1.0.jar", "GET", Some(repository_target("internal", Some("org/a/a/1.0/a-1.0.jar"), Permission::Read)))]
+    #[case::admin("/api/v1/admin/tasks", "GET", Some(Target::Admin))]
+    #[case::admin_root("/api/v1/admin", "GET", Some(Target::Admin))]
+    #[case::admin_prefix("/api/v1/administrators", "GET", None)]
+    #[case::can_deploy("/api/v1/can-deploy?g=com.acme&a=lib&v=1.0", "GET", Some(repository_target("internal", Some("com/acme/lib/1.0/"), Permission::Deploy)))]
+    #[case::can_deploy_named("/api/v1/can-deploy?g=com.acme&a=lib&v=1.0&repo=other", "GET", Some(repository_target("other", Some("com/acme/lib/1.0/"), Permission::Deploy)))]
+    #[case::delete_artifact("/api/v1/repositories/central/artifacts/org/a/a/1.0/a-1.0.jar", "DELETE", Some(repository_target("central", Some("org/a/a/1.0/a-1.0.jar"), Permission::Delete)))]
+    #[case::invalidate("/api/v1/repositories/central/invalidate", "POST", Some(repository_target("central", None, Permission::Delete)))]
+    #[case::quarantined_blob("/api/v1/quarantined-blobs/central/AAAA", "GET", None)]
+    fn test_target(#[case] uri: &str, #[case] method: &str, #[case] expected: Option<Target>) {
+        let (path, query) = match uri.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (uri, None),
+        };
+        let method = Method::from_bytes(method.as_bytes()).unwrap();
+        assert_eq!(authenticator().target(path, query, &method), expected);
+    }
+
+    async fn principal_name(principal: Option<Extension<Principal>>) -> String {
+        principal.map(|Extension(p)| p.name).unwrap_or_default()
+    }
+
+    #[rstest]
+    #[case::anonymous_unrestricted("GET", "/repo/a.jar", None, StatusCode::OK, "")]
+    #[case::anonymous_restricted("GET", "/repo/internal/a.jar", None, StatusCode::UNAUTHORIZED, "")]
+    #[case::basic("GET", "/repo/internal/a.jar", Some("Basic YWxpY2U6c2VjcmV0"), StatusCode::OK, "alice")]
+    #[case::wrong_password("GET", "/repo/a.jar", Some("Basic YWxpY2U6d3Jvbmc="), StatusCode::UNAUTHORIZED, "")]
+    #[case::not_allowed("PUT", "/repo/internal/a.jar", Some("Basic YWxpY2U6c2VjcmV0"), StatusCode::FORBIDDEN, "")]
+    #[case::bearer("PUT", "/repo/internal/com/acme/teamx/a/1.0/a-1.0.jar", Some("Bearer ci-token"), StatusCode::OK, "ci")]
+    #[case::principal_for_unrestricted("GET", "/repo/a.jar", Some("Bearer ci-token"), StatusCode::OK, "ci")]
+    #[case::uncovered_deploy("PUT", "/repo/internal/a.jar", Some("Bearer ci-token"), StatusCode::FORBIDDEN, "")]
+    #[case::role("PUT", "/repo/com/acme/teamx/a/1.0/a-1.0.jar", Some("Bearer ci-token"), StatusCode::OK, "ci")]
+    #[case::missing_role("PUT", "/repo/com/acme/teamx/a/1.0/a-1.0.jar", Some("Basic YWxpY2U6c2VjcmV0"), StatusCode::FORBIDDEN, "")]
+    #[case::anonymous_missing_role("PUT", "/repo/com/acme/teamx/a/1.0/a-1.0.jar", None, StatusCode::UNAUTHORIZED, "")]
+    #[case::admin("POST", "/api/v1/admin/blob-storage/gc", Some("Bearer ops-token"), StatusCode::OK, "ops")]
+    #[case::admin_anonymous("GET", "/api/v1/admin/audit-log", None, StatusCode::UNAUTHORIZED, "")]
+    #[case::admin_missing_role("GET", "/api/v1/admin/audit-log", Some("Bearer ci-token"), StatusCode::FORBIDDEN, "")]
+    #[tokio::test]
+    async fn test_authenticate(#[case] method: &str, #[case] path: &str, #[case] authorization: Option<&str>, #[case] expected_status: StatusCode, #[case] expected_principal: &str) {
+        let app = Router::new()
+            .route("/repo/*path", any(principal_name))
+            .route("/api/v1/admin/*path", any(principal_name))
+            .layer(middleware::from_fn_with_state(Arc::new(authenticator()), authenticate));
+
+        let mut request = Request::builder()
+            .method(method)
+            .uri(path);
+        if let Some(authorization) = authorization {
+            request = request.header(AUTHORIZATION, authorization);
+        }
+        let response = app.oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+
+        assert_eq!(response.status(), expected_status);
+        if expected_status == StatusCode::UNAUTHORIZED {
+            assert!(response.headers().contains_key(WWW_AUTHENTICATE));
+        }
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert_eq!(body, expected_principal.as_bytes());
+    }
+
+    #[test]
+    fn test_admin_without_credential_store() {
+        let unauthenticated = Authenticator::new(None, &[parse_repository_config("central=remote:https://repo1.maven.org/maven2").unwrap()], Default::default());
+        assert!(unauthenticated.allows(&Target::Admin, None));
+
+        let admin = Principal { name: "ops".to_string(), roles: vec!["ops".to_string()] };
+        let authenticated = authenticator().with_admin_roles(vec!["ops".to_string()]);
+        assert!(authenticated.allows(&Target::Admin, Some(&admin)));
+        assert!(!authenticated.allows(&Target::Admin, None));
+    }
+}
@@ -0,0 +1,197 @@
+use anyhow::anyhow;
+use serde::Deserialize;
+
+use crate::auth::access::Permission;
+use crate::auth::credential_store::Principal;
+
+/// Grants holders of a role permissions on repositories and groupIds, e.g. only 'team-x' may
+///  deploy 'com.acme.teamx.*':
+///
+/// ```toml
+/// [[authorization]]
+/// role = "team-x"
+/// repositories = ["internal*"]
+/// group_ids = ["com.acme.teamx"]
+/// permissions = ["deploy", "delete"]
+/// ```
+///
+/// A rule covers requests for the permissions it lists on matching repositories and groupIds.
+///  Such requests require one of the roles of the rules covering them, in addition to what the
+///  repository's [AccessPolicy](crate::auth::access::AccessPolicy) requires. Once there are rules,
+///  deploys and deletes that no rule covers are refused, while reads no rule covers are decided by
+///  the access policy alone.
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuthorizationRule {
+    pub role: String,
+    /// repository names, '*' matching any sequence of characters
+    #[serde(default = "all_repositories")]
+    pub repositories: Vec<String>,
+    /// groupId prefixes, 'com.acme' covering 'com.acme' and 'com.acme.*' - empty for all groupIds
+    #[serde(default)]
+    pub group_ids: Vec<String>,
+    pub permissions: Vec<Permission>,
+}
+
+fn all_repositories() -> Vec<String> {
+    vec!["*".to_string()]
+}
+
+impl AuthorizationRule {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.role.is_empty() {
+            return Err(anyhow!("authorization rule without a role"));
+        }
+        if self.repositories.is_empty() || self.permissions.is_empty() {
+            return Err(anyhow!("authorization rule for role '{}' must list repositories and permissions", self.role));
+        }
+        if let Some(group_id) = self.group_ids.iter().find(|g| group_path(g).is_empty()) {
+            return Err(anyhow!("authorization rule for role '{}' has an invalid groupId '{}'", self.role, group_id));
+        }
+        Ok(())
+    }
+
+    /// 'path' is relative to the repository's root, None if the request does not refer to a path
+    ///  (e.g. API requests) - that is covered by all rules for the repository to be on the safe side
+    fn covers(&self, repository: &str, path: Option<&str>, permission: Permission) -> bool {
+        if !self.permissions.contains(&permission) {
+            return false;
+        }
+        if !self.repositories.iter().any(|pattern| matches_pattern(pattern, repository)) {
+            return false;
+        }
+        if self.group_ids.is_empty() {
+            return true;
+        }
+        let path = match path {
+            Some(path) => path.trim_matches('/'),
+            None => return true,
+        };
+        self.group_ids.iter()
+            .map(|group_id| group_path(group_id))
+            .any(|group_path| {
+                is_in_directory(path, &group_path)
+                    // deleting a parent directory deletes the group's artifacts as well
+                    || (permission == Permission::Delete && is_in_directory(&group_path, path))
+            })
+    }
+}
+
+/// The roles' rules, see [AuthorizationRule]
+#[derive(Clone, Debug, Default)]
+pub struct Authorization {
+    rules: Vec<AuthorizationRule>,
+}
+impl Authorization {
+    pub fn new(rules: Vec<AuthorizationRule>) -> Authorization {
+        Authorization { rules }
+    }
+
+    pub fn allows(&self, repository: &str, path: Option<&str>, permission: Permission, principal: Option<&Principal>) -> bool {
+        let mut covering_rules = self.rules.iter()
+            .filter(|rule| rule.covers(repository, path, permission))
+            .peekable();
+        if covering_rules.peek().is_none() {
+            return self.rules.is_empty() || permission == Permission::Read;
+        }
+        match principal {
+            None => false,
+            Some(principal) => covering_rules.any(|rule| principal.roles.contains(&rule.role)),
+        }
+    }
+}
+
+/// 'com.acme.*' -> 'com/acme'
+fn group_path(group_id: &str) -> String {
+    group_id.trim_end_matches('*')
+        .trim_matches('.')
+        .replace('.', "/")
+}
+
+/// true if 'path' is 'dir' or inside it, both without leading or trailing '/'
+fn is_in_directory(path: &str, dir: &str) -> bool {
+    dir.is_empty() || path == dir || path.strip_prefix(dir).map(|rest| rest.starts_with('/')).unwrap_or(false)
+}
+
+fn matches_pattern(pattern: &str, s: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == s,
+        Some((prefix, rest)) => match s.strip_prefix(prefix) {
+            None => false,
+            Some(s) => (0..=s.len())
+                .filter(|&i| s.is_char_boundary(i))
+                .any(|i| matches_pattern(rest, &s[i..])),
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn authorization() -> Authorization {
+        Authorization::new(vec![
+            AuthorizationRule {
+                role: "team-x".to_string(),
+                repositories: vec!["internal*".to_string()],
+                group_ids: vec!["com.acme.teamx.*".to_string()],
+                permissions: vec![Permission::Deploy, Permission::Delete],
+            },
+            AuthorizationRule {
+                role: "release-manager".to_string(),
+                repositories: vec!["releases".to_string()],
+                group_ids: vec![],
+                permissions: vec![Permission::Deploy],
+            },
+        ])
+    }
+
+    #[rstest]
+    #[case::exact("internal", true)]
+    #[case::prefix("internal*", true)]
+    #[case::suffix("*nal", true)]
+    #[case::infix("in*al", true)]
+    #[case::any("*", true)]
+    #[case::mismatch("intern", false)]
+    #[case::mismatched_suffix("*x", false)]
+    fn test_matches_pattern(#[case] pattern: &str, #[case] expected: bool) {
+        assert_eq!(matches_pattern(pattern, "internal"), expected);
+    }
+
+    #[rstest]
+    #[case::in_group("internal-snapshots", Some("com/acme/teamx/a/1.0/a-1.0.jar"), Permission::Deploy, None, false)]
+    #[case::role("internal-snapshots", Some("com/acme/teamx/a/1.0/a-1.0.jar"), Permission::Deploy, Some("team-x"), true)]
+    #[case::other_role("internal", Some("/com/acme/teamx/sub/a/1.0/a-1.0.jar"), Permission::Deploy, Some("team-y"), false)]
+    #[case::other_group("internal", Some("com/acme/teamxy/a/1.0/a-1.0.jar"), Permission::Deploy, Some("team-x"), false)]
+    #[case::read("internal", Some("com/acme/teamx/a/1.0/a-1.0.jar"), Permission::Read, None, true)]
+    #[case::uncovered_read("central", Some("org/a/1.0/a-1.0.jar"), Permission::Read, None, true)]
+    #[case::other_repository("central", Some("com/acme/teamx/a/1.0/a-1.0.jar"), Permission::Deploy, Some("team-x"), false)]
+    #[case::uncovered_delete("releases", Some("org/a/1.0/"), Permission::Delete, Some("release-manager"), false)]
+    #[case::delete_parent("internal", Some("com/acme/"), Permission::Delete, Some("team-y"), false)]
+    #[case::deploy_parent("internal", Some("com/acme/maven-metadata.xml"), Permission::Deploy, Some("team-x"), false)]
+    #[case::unknown_path("internal", None, Permission::Delete, Some("team-y"), false)]
+    #[case::all_groups("releases", Some("org/a/1.0/a-1.0.jar"), Permission::Deploy, Some("team-x"), false)]
+    #[case::all_groups_role("releases", Some("org/a/1.0/a-1.0.jar"), Permission::Deploy, Some("release-manager"), true)]
+    fn test_allows(#[case] repository: &str, #[case] path: Option<&str>, #[case] permission: Permission, #[case] role: Option<&str>, #[case] expected: bool) {
+        let principal = role.map(|role| Principal { name: "alice".to_string(), roles: vec![role.to_string()] });
+        assert_eq!(authorization().allows(repository, path, permission, principal.as_ref()), expected);
+    }
+
+    #[rstest]
+    #[case::read(Permission::Read)]
+    #[case::deploy(Permission::Deploy)]
+    #[case::delete(Permission::Delete)]
+    fn test_allows_without_rules(#[case] permission: Permission) {
+        assert!(Authorization::default().allows("internal", Some("org/a/1.0/a-1.0.jar"), permission, None));
+    }
+
+    #[test]
+    fn test_validate() {
+        let rule = authorization().rules[0].clone();
+        assert!(rule.validate().is_ok());
+        assert!(AuthorizationRule { group_ids: vec!["*".to_string()], ..rule.clone() }.validate().is_err());
+        assert!(AuthorizationRule { permissions: vec![], ..rule }.validate().is_err());
+    }
+}
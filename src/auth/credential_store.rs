@@ -0,0 +1,177 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use hmac::{Hmac, KeyInit, Mac};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// the principal recorded for requests without credentials
+pub const ANONYMOUS: &str = "anonymous";
+
+/// An authenticated user
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Principal {
+    pub name: String,
+    /// see [AuthorizationRule](crate::auth::authorization::AuthorizationRule)
+    pub roles: Vec<String>,
+}
+
+//TODO a database backed store once there is a database for metadata
+/// Users with their passwords and API tokens. Secrets are stored as hashes, so implementations
+///  hash the presented secret and compare the result. Lookups are async so that stores can be
+///  backed by a database or a directory service.
+#[async_trait]
+pub trait CredentialStore: Send + Sync {
+    /// HTTP Basic authentication, which is what Maven and Gradle send. The password may also be one
+    ///  of the user's tokens, for clients that support only Basic authentication.
+    async fn authenticate_password(&self, user: &str, password: &str) -> anyhow::Result<Option<Principal>>;
+
+    /// bearer tokens
+    async fn authenticate_token(&self, token: &str) -> anyhow::Result<Option<Principal>>;
+}
+
+/// Asks several stores in turn, e.g. the users file for service accounts and LDAP for people. The
+///  first store that knows the credentials authenticates them. A store that fails (e.g. because
+///  the directory is down) is skipped, and its error is returned only if no other store knows the
+///  credentials.
+pub struct ChainedCredentialStore {
+    stores: Vec<Arc<dyn CredentialStore>>,
+}
+impl ChainedCredentialStore {
+    pub fn new(stores: Vec<Arc<dyn CredentialStore>>) -> ChainedCredentialStore {
+        ChainedCredentialStore { stores }
+    }
+}
+
+#[async_trait]
+impl CredentialStore for ChainedCredentialStore {
+    async fn authenticate_password(&self, user: &str, password: &str) -> anyhow::Result<Option<Principal>> {
+        let mut error = None;
+        for store in &self.stores {
+            match store.authenticate_password(user, password).await {
+                Ok(Some(principal)) => return Ok(Some(principal)),
+                Ok(None) => {}
+                Err(e) => {
+                    warn!("error authenticating {}, trying the next credential store: {}", user, e);
+                    error.get_or_insert(e);
+                }
+            }
+        }
+        error.map_or(Ok(None), Err)
+    }
+
+    async fn authenticate_token(&self, token: &str) -> anyhow::Result<Option<Principal>> {
+        let mut error = None;
+        for store in &self.stores {
+            match store.authenticate_token(token).await {
+                Ok(Some(principal)) => return Ok(Some(principal)),
+                Ok(None) => {}
+                Err(e) => {
+                    warn!("error authenticating a token, trying the next credential store: {}", e);
+                    error.get_or_insert(e);
+                }
+            }
+        }
+        error.map_or(Ok(None), Err)
+    }
+}
+
+/// Hashes a password as 'hmac-sha256:<salt>:<hex encoded HMAC of the password, keyed with the salt>'
+pub fn hash_password(salt: &str, password: &str) -> String {
+    format!("hmac-sha256:{}:{}", salt, hex::encode(password_mac(salt, password).finalize().into_bytes()))
+}
+
+/// Checks a password against a hash created by [hash_password], in constant time
+pub fn verify_password(password_hash: &str, password: &str) -> anyhow::Result<bool> {
+    let (salt, hash) = match password_hash.split_once(':') {
+        Some(("hmac-sha256", rest)) => rest.split_once(':')
+            .ok_or_else(|| anyhow!("password hash must be 'hmac-sha256:<salt>:<hash>'"))?,
+        _ => return Err(anyhow!("unsupported password hash, must be 'hmac-sha256:<salt>:<hash>'")),
+    };
+    let hash = hex::decode(hash)?;
+    Ok(password_mac(salt, password).verify_slice(&hash).is_ok())
+}
+
+fn password_mac(salt: &str, password: &str) -> HmacSha256 {
+    let mut mac = <HmacSha256 as KeyInit>::new_from_slice(salt.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(password.as_bytes());
+    mac
+}
+
+/// Tokens are random, so they are stored as plain SHA-256 hashes, hex encoded. This allows
+///  looking them up without knowing the user.
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::valid("secret", true)]
+    #[case::wrong_password("Secret", false)]
+    fn test_verify_password(#[case] password: &str, #[case] expected: bool) {
+        let password_hash = hash_password("salt", "secret");
+        assert!(password_hash.starts_with("hmac-sha256:salt:"));
+        assert_eq!(verify_password(&password_hash, password).unwrap(), expected);
+        assert!(verify_password("plain:secret", "secret").is_err());
+    }
+
+    /// knows the user 'alice' with password and token 'secret', or fails for everything
+    struct StubStore {
+        available: bool,
+    }
+    #[async_trait]
+    impl CredentialStore for StubStore {
+        async fn authenticate_password(&self, user: &str, password: &str) -> anyhow::Result<Option<Principal>> {
+            match (self.available, user, password) {
+                (false, _, _) => Err(anyhow!("unavailable")),
+                (true, "alice", "secret") => self.authenticate_token(password).await,
+                (true, _, _) => Ok(None),
+            }
+        }
+
+        async fn authenticate_token(&self, token: &str) -> anyhow::Result<Option<Principal>> {
+            match (self.available, token) {
+                (false, _) => Err(anyhow!("unavailable")),
+                (true, "secret") => Ok(Some(Principal { name: "alice".to_string(), roles: vec![] })),
+                (true, _) => Ok(None),
+            }
+        }
+    }
+
+    fn chained(available: &[bool]) -> ChainedCredentialStore {
+        ChainedCredentialStore::new(available.iter()
+            .map(|&available| Arc::new(StubStore { available }) as Arc<dyn CredentialStore>)
+            .collect())
+    }
+
+    #[rstest]
+    #[case::first(vec![true, false], Some(Some("alice")))]
+    #[case::after_failing_store(vec![false, true], Some(Some("alice")))]
+    #[case::all_failing(vec![false, false], None)]
+    #[case::none(vec![], Some(None))]
+    #[tokio::test]
+    async fn test_chained(#[case] available: Vec<bool>, #[case] expected: Option<Option<&str>>) {
+        let store = chained(&available);
+
+        let principal = store.authenticate_password("alice", "secret").await.ok();
+        assert_eq!(principal.map(|p| p.map(|p| p.name)), expected.map(|p| p.map(|name| name.to_string())));
+        let principal = store.authenticate_token("secret").await.ok();
+        assert_eq!(principal.map(|p| p.map(|p| p.name)), expected.map(|p| p.map(|name| name.to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_chained_unknown_credentials_with_failing_store() {
+        // the failing store might have known them
+        assert!(chained(&[true, false]).authenticate_password("alice", "wrong").await.is_err());
+        assert!(chained(&[false, true]).authenticate_token("wrong").await.is_err());
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use crate::auth::credential_store::{CredentialStore, hash_token, Principal, verify_password};
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct UsersFile {
+    #[serde(default)]
+    users: Vec<UserEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct UserEntry {
+    name: String,
+    /// see [hash_password](crate::auth::credential_store::hash_password), users without a
+    ///  password can only authenticate with a token
+    #[serde(default)]
+    password: Option<String>,
+    /// see [hash_token]
+    #[serde(default)]
+    tokens: Vec<String>,
+    /// see [AuthorizationRule](crate::auth::authorization::AuthorizationRule)
+    #[serde(default)]
+    roles: Vec<String>,
+}
+
+struct StoredUser {
+    password_hash: Option<String>,
+    roles: Vec<String>,
+}
+
+/// Users read from a TOML file at startup:
+///
+/// ```toml
+/// [[users]]
+/// name = "ci"
+/// password = "hmac-sha256:<salt>:<hash>"
+/// tokens = ["<SHA-256 of the token, hex encoded>"]
+/// roles = ["team-x"]
+/// ```
+pub struct FileCredentialStore {
+    /// by name
+    users: HashMap<String, StoredUser>,
+    /// token hash -> user name
+    tokens: HashMap<String, String>,
+}
+impl FileCredentialStore {
+    pub fn load(path: &Path) -> anyhow::Result<FileCredentialStore> {
+        let s = std::fs::read_to_string(path)
+            .with_context(|| format!("error reading users file {}", path.display()))?;
+        FileCredentialStore::parse(&s)
+            .with_context(|| format!("error in users file {}", path.display()))
+    }
+
+    pub fn parse(s: &str) -> anyhow::Result<FileCredentialStore> {
+        let users_file: UsersFile = toml::from_str(s)?;
+
+        let mut users = HashMap::new();
+        let mut tokens = HashMap::new();
+        for user in users_file.users {
+            if users.contains_key(&user.name) {
+                return Err(anyhow!("user '{}' is listed twice", user.name));
+            }
+            for token in user.tokens {
+                let token = token.to_ascii_lowercase();
+                if token.len() != 64 || hex::decode(&token).is_err() {
+                    return Err(anyhow!("token of user '{}' must be a hex encoded SHA-256 hash", user.name));
+                }
+                if tokens.insert(token, user.name.clone()).is_some() {
+                    return Err(anyhow!("a token of user '{}' is listed twice", user.name));
+                }
+            }
+            users.insert(user.name, StoredUser {
+                password_hash: user.password,
+                roles: user.roles,
+            });
+        }
+        Ok(FileCredentialStore { users, tokens })
+    }
+
+    fn principal(&self, name: &str) -> Option<Principal> {
+        self.users.get(name).map(|user| Principal {
+            name: name.to_string(),
+            roles: user.roles.clone(),
+        })
+    }
+
+    fn has_token(&self, user: &str, token: &str) -> bool {
+        self.tokens.get(&hash_token(token))
+            .map(|name| name == user)
+            .unwrap_or(false)
+    }
+}
+
+#[async_trait]
+impl CredentialStore for FileCredentialStore {
+    async fn authenticate_password(&self, user: &str, password: &str) -> anyhow::Result<Option<Principal>> {
+        let is_valid = match self.users.get(user).map(|u| &u.password_hash) {
+            None => false,
+            Some(Some(password_hash)) => verify_password(password_hash, password)? || self.has_token(user, password),
+            Some(None) => self.has_token(user, password),
+        };
+        Ok(if is_valid { self.principal(user) } else { None })
+    }
+
+    async fn authenticate_token(&self, token: &str) -> anyhow::Result<Option<Principal>> {
+        Ok(self.tokens.get(&hash_token(token))
+            .and_then(|name| self.principal(name)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use crate::auth::credential_store::hash_password;
+
+    use super::*;
+
+    fn store() -> FileCredentialStore {
+        FileCredentialStore::parse(&format!(r#"
+            [[users]]
+            name = "alice"
+            password = "{}"
+            tokens = ["{}"]
+
+            [[users]]
+            name = "ci"
+            tokens = ["{}"]
+            roles = ["deployer"]
+        "#, hash_password("pepper", "secret"), hash_token("alice-token"), hash_token("ci-token"))).unwrap()
+    }
+
+    #[rstest]
+    #[case::password("alice", "secret", Some("alice"))]
+    #[case::wrong_password("alice", "wrong", None)]
+    #[case::token_as_password("alice", "alice-token", Some("alice"))]
+    #[case::other_users_token("alice", "ci-token", None)]
+    #[case::without_password("ci", "ci-token", Some("ci"))]
+    #[case::unknown_user("bob", "secret", None)]
+    #[tokio::test]
+    async fn test_authenticate_password(#[case] user: &str, #[case] password: &str, #[case] expected: Option<&str>) {
+        let principal = store().authenticate_password(user, password).await.unwrap();
+        assert_eq!(principal.map(|p| p.name), expected.map(|s| s.to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_token() {
+        let store = store();
+        assert_eq!(store.authenticate_token("ci-token").await.unwrap(), Some(Principal { name: "ci".to_string(), roles: vec!["deployer".to_string()] }));
+        assert_eq!(store.authenticate_token("secret").await.unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(FileCredentialStore::parse("[[users]]\nname = \"a\"\n[[users]]\nname = \"a\"").is_err());
+        assert!(FileCredentialStore::parse("[[users]]\nname = \"a\"\ntokens = [\"secret\"]").is_err());
+    }
+}
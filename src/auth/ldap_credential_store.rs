@@ -0,0 +1,505 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use serde::Deserialize;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tracing::debug;
+
+use crate::auth::credential_store::{CredentialStore, Principal};
+
+/// result codes, see RFC 4511
+const SUCCESS: u32 = 0;
+const INVALID_CREDENTIALS: u32 = 49;
+
+const TAG_BOOLEAN: u8 = 0x01;
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_ENUMERATED: u8 = 0x0a;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+const TAG_SIMPLE_AUTHENTICATION: u8 = 0x80;
+const TAG_PRESENT_FILTER: u8 = 0x87;
+const TAG_BIND_REQUEST: u8 = 0x60;
+const TAG_BIND_RESPONSE: u8 = 0x61;
+const TAG_UNBIND_REQUEST: u8 = 0x42;
+const TAG_SEARCH_REQUEST: u8 = 0x63;
+const TAG_SEARCH_RESULT_ENTRY: u8 = 0x64;
+const TAG_SEARCH_RESULT_DONE: u8 = 0x65;
+
+/// responses are small, this protects against servers (or something else listening on the port)
+///  sending garbage
+const MAX_MESSAGE_SIZE: usize = 1024 * 1024;
+
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+/// ```toml
+/// [ldap]
+/// url = "ldaps://ldap.example.com"
+/// user_dn = "uid={user},ou=people,dc=example,dc=com"
+/// group_attribute = "memberOf"
+/// ```
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LdapConfig {
+    /// 'ldaps://<host>[:<port>]', or 'ldap://<host>[:<port>]' with 'allow_insecure'
+    pub url: String,
+    /// the DN users bind as, '{user}' is replaced with the (escaped) user name
+    pub user_dn: String,
+    /// attribute of a user's entry with the DNs of their groups. The value of a group DN's first
+    ///  component becomes a role, e.g. 'team-x' for 'cn=team-x,ou=groups,dc=example,dc=com'.
+    #[serde(default)]
+    pub group_attribute: Option<String>,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// 'ldap://' sends passwords in clear text, so it must be enabled explicitly, e.g. for a
+    ///  directory on the same host
+    #[serde(default)]
+    pub allow_insecure: bool,
+}
+impl LdapConfig {
+    /// host, port and whether to use TLS
+    fn server(&self) -> anyhow::Result<(String, u16, bool)> {
+        let (address, tls, default_port) = if let Some(address) = self.url.strip_prefix("ldaps://") {
+            (address, true, 636)
+        }
+        else if let Some(address) = self.url.strip_prefix("ldap://") {
+            if !self.allow_insecure {
+                return Err(anyhow!("LDAP URL '{}' would send passwords unencrypted, use 'ldaps://' or set allow_insecure", self.url));
+            }
+            (address, false, 389)
+        }
+        else {
+            return Err(anyhow!("LDAP URL must start with 'ldap://' or 'ldaps://': '{}'", self.url));
+        };
+
+        let address = address.trim_end_matches('/');
+        let (host, port) = match address.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().with_context(|| format!("invalid port in LDAP URL '{}'", self.url))?),
+            None => (address, default_port),
+        };
+        if host.is_empty() {
+            return Err(anyhow!("LDAP URL without a host: '{}'", self.url));
+        }
+        Ok((host.to_string(), port, tls))
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        self.server()?;
+        if !self.user_dn.contains("{user}") {
+            return Err(anyhow!("LDAP user_dn must contain '{{user}}': '{}'", self.user_dn));
+        }
+        Ok(())
+    }
+}
+
+trait Connection: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Connection for T {}
+
+/// Authenticates users by binding to an LDAP directory with their DN and password. Tokens are
+///  not supported, since the directory has none.
+pub struct LdapCredentialStore {
+    config: LdapConfig,
+    host: String,
+    port: u16,
+    tls: bool,
+}
+impl LdapCredentialStore {
+    pub fn new(config: LdapConfig) -> anyhow::Result<LdapCredentialStore> {
+        let (host, port, tls) = config.server()?;
+        Ok(LdapCredentialStore { config, host, port, tls })
+    }
+
+    async fn connect(&self) -> anyhow::Result<Box<dyn Connection>> {
+        let tcp = TcpStream::connect((self.host.as_str(), self.port)).await
+            .with_context(|| format!("error connecting to LDAP server {}", self.config.url))?;
+        if !self.tls {
+            return Ok(Box::new(tcp));
+        }
+        let connector = tokio_native_tls::TlsConnector::from(native_tls::TlsConnector::new()?);
+        let tls = connector.connect(&self.host, tcp).await
+            .with_context(|| format!("TLS error connecting to LDAP server {}", self.config.url))?;
+        Ok(Box::new(tls))
+    }
+
+    async fn bind(&self, user: &str, password: &str) -> anyhow::Result<Option<Principal>> {
+        let dn = self.config.user_dn.replace("{user}", &escape_dn_value(user));
+        let mut connection = self.connect().await?;
+
+        send(&mut connection, 1, &bind_request(&dn, password)).await?;
+        let (tag, response) = receive(&mut connection).await?;
+        if tag != TAG_BIND_RESPONSE {
+            return Err(anyhow!("unexpected LDAP response to a bind request: tag {:#x}", tag));
+        }
+        match result_code(&response)? {
+            SUCCESS => {}
+            INVALID_CREDENTIALS => {
+                debug!("LDAP bind as {} failed", dn);
+                return Ok(None);
+            }
+            code => return Err(anyhow!("LDAP bind as {} failed with result code {}", dn, code)),
+        }
+
+        let roles = match &self.config.group_attribute {
+            Some(group_attribute) => groups(&mut connection, &dn, group_attribute).await?,
+            None => vec![],
+        };
+        // the server closes the connection, so there is nothing to wait for
+        let _ = send(&mut connection, 3, &tlv(TAG_UNBIND_REQUEST, &[])).await;
+
+        Ok(Some(Principal {
+            name: user.to_string(),
+            roles,
+        }))
+    }
+}
+
+#[async_trait]
+impl CredentialStore for LdapCredentialStore {
+    async fn authenticate_password(&self, user: &str, password: &str) -> anyhow::Result<Option<Principal>> {
+        // a bind without a password is an anonymous bind, which succeeds for any DN
+        if user.is_empty() || password.is_empty() {
+            return Ok(None);
+        }
+        tokio::time::timeout(Duration::from_secs(self.config.timeout_secs), self.bind(user, password)).await
+            .map_err(|_| anyhow!("timeout authenticating with LDAP server {}", self.config.url))?
+    }
+
+    async fn authenticate_token(&self, _token: &str) -> anyhow::Result<Option<Principal>> {
+        Ok(None)
+    }
+}
+
+/// The roles of a user, see [LdapConfig::group_attribute]
+async fn groups(connection: &mut Box<dyn Connection>, dn: &str, group_attribute: &str) -> anyhow::Result<Vec<String>> {
+    send(connection, 2, &search_request(dn, group_attribute)).await?;
+
+    let mut roles = vec![];
+    loop {
+        let (tag, response) = receive(connection).await?;
+        match tag {
+            TAG_SEARCH_RESULT_ENTRY => {
+                roles.extend(attribute_values(&response, group_attribute)?.iter()
+                    .filter_map(|group_dn| group_name(group_dn)));
+            }
+            TAG_SEARCH_RESULT_DONE => return match result_code(&response)? {
+                SUCCESS => Ok(roles),
+                code => Err(anyhow!("LDAP search for the groups of {} failed with result code {}", dn, code)),
+            },
+            // e.g. search result references
+            _ => {}
+        }
+    }
+}
+
+/// 'cn=team-x,ou=groups,dc=example,dc=com' -> 'team-x'
+fn group_name(group_dn: &str) -> Option<String> {
+    let (_, value) = group_dn.split(',').next()?.split_once('=')?;
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// Escapes a value for use in a DN, see RFC 4514
+fn escape_dn_value(value: &str) -> String {
+    let mut escaped = String::new();
+    let last = value.chars().count().saturating_sub(1);
+    for (i, c) in value.chars().enumerate() {
+        match c {
+            ',' | '+' | '"' | '\\' | '<' | '>' | ';' | '=' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '#' if i == 0 => escaped.push_str("\\#"),
+            ' ' if i == 0 || i == last => escaped.push_str("\\ "),
+            '\0' => escaped.push_str("\\00"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn bind_request(dn: &str, password: &str) -> Vec<u8> {
+    tlv(TAG_BIND_REQUEST, &[
+        integer(3), // LDAP v3
+        tlv(TAG_OCTET_STRING, dn.as_bytes()),
+        tlv(TAG_SIMPLE_AUTHENTICATION, password.as_bytes()),
+    ].concat())
+}
+
+/// reads one attribute of the entry with the given DN
+fn search_request(dn: &str, attribute: &str) -> Vec<u8> {
+    tlv(TAG_SEARCH_REQUEST, &[
+        tlv(TAG_OCTET_STRING, dn.as_bytes()),
+        tlv(TAG_ENUMERATED, &[0]), // scope: base object
+        tlv(TAG_ENUMERATED, &[0]), // never dereference aliases
+        integer(0), // no size limit
+        integer(0), // no time limit
+        tlv(TAG_BOOLEAN, &[0]), // values, not only attribute types
+        tlv(TAG_PRESENT_FILTER, b"objectClass"),
+        tlv(TAG_SEQUENCE, &tlv(TAG_OCTET_STRING, attribute.as_bytes())),
+    ].concat())
+}
+
+/// The values of an attribute in a search result entry
+fn attribute_values(entry: &[u8], attribute: &str) -> anyhow::Result<Vec<String>> {
+    let mut entry = BerReader::new(entry);
+    entry.expect(TAG_OCTET_STRING)?; // the entry's DN
+    let mut attributes = BerReader::new(entry.expect(TAG_SEQUENCE)?);
+
+    let mut values = vec![];
+    while !attributes.is_empty() {
+        let mut partial_attribute = BerReader::new(attributes.expect(TAG_SEQUENCE)?);
+        let name = partial_attribute.expect(TAG_OCTET_STRING)?;
+        let mut attribute_values = BerReader::new(partial_attribute.expect(TAG_SET)?);
+        if !name.eq_ignore_ascii_case(attribute.as_bytes()) {
+            continue;
+        }
+        while !attribute_values.is_empty() {
+            values.push(String::from_utf8_lossy(attribute_values.expect(TAG_OCTET_STRING)?).into_owned());
+        }
+    }
+    Ok(values)
+}
+
+/// the result code of a response, which is its first element
+fn result_code(response: &[u8]) -> anyhow::Result<u32> {
+    let code = BerReader::new(response).expect(TAG_ENUMERATED)?;
+    if code.is_empty() || code.len() > 4 {
+        return Err(anyhow!("invalid LDAP result code"));
+    }
+    Ok(code.iter().fold(0, |acc, &b| acc << 8 | b as u32))
+}
+
+async fn send(connection: &mut Box<dyn Connection>, message_id: u32, operation: &[u8]) -> anyhow::Result<()> {
+    let message = tlv(TAG_SEQUENCE, &[integer(message_id).as_slice(), operation].concat());
+    connection.write_all(&message).await?;
+    connection.flush().await?;
+    Ok(())
+}
+
+/// Reads a message, returning the tag and content of its protocol operation
+async fn receive(connection: &mut Box<dyn Connection>) -> anyhow::Result<(u8, Vec<u8>)> {
+    if connection.read_u8().await? != TAG_SEQUENCE {
+        return Err(anyhow!("invalid LDAP message"));
+    }
+    let len = match connection.read_u8().await? {
+        len if len < 0x80 => len as usize,
+        num_bytes if (0x81..=0x84).contains(&num_bytes) => {
+            let mut len = 0;
+            for _ in 0..num_bytes & 0x7f {
+                len = len << 8 | connection.read_u8().await? as usize;
+            }
+            len
+        }
+        _ => return Err(anyhow!("invalid length of an LDAP message")),
+    };
+    if len > MAX_MESSAGE_SIZE {
+        return Err(anyhow!("LDAP message of {} bytes exceeds the maximum size", len));
+    }
+    let mut message = vec![0; len];
+    connection.read_exact(&mut message).await?;
+
+    let mut message = BerReader::new(&message);
+    message.expect(TAG_INTEGER)?; // message id - there is only one request at a time
+    let (tag, operation) = message.next()?;
+    Ok((tag, operation.to_vec()))
+}
+
+/// BER encodes a tag, length and content
+fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut result = vec![tag];
+    if content.len() < 0x80 {
+        result.push(content.len() as u8);
+    }
+    else {
+        let len_bytes = (content.len() as u32).to_be_bytes();
+        let len_bytes = &len_bytes[len_bytes.iter().take_while(|&&b| b == 0).count()..];
+        result.push(0x80 | len_bytes.len() as u8);
+        result.extend_from_slice(len_bytes);
+    }
+    result.extend_from_slice(content);
+    result
+}
+
+fn integer(value: u32) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let mut content = bytes[bytes.iter().take_while(|&&b| b == 0).count().min(3)..].to_vec();
+    // the value is signed
+    if content[0] & 0x80 != 0 {
+        content.insert(0, 0);
+    }
+    tlv(TAG_INTEGER, &content)
+}
+
+/// Reads the BER encoded elements of a constructed element's content one after the other
+struct BerReader<'a> {
+    data: &'a [u8],
+}
+impl<'a> BerReader<'a> {
+    fn new(data: &'a [u8]) -> BerReader<'a> {
+        BerReader { data }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn next(&mut self) -> anyhow::Result<(u8, &'a [u8])> {
+        let truncated = || anyhow!("truncated LDAP message");
+        let (&tag, rest) = self.data.split_first().ok_or_else(truncated)?;
+        let (&first_len_byte, mut rest) = rest.split_first().ok_or_else(truncated)?;
+        let len = if first_len_byte < 0x80 {
+            first_len_byte as usize
+        }
+        else {
+            let num_bytes = (first_len_byte & 0x7f) as usize;
+            if num_bytes == 0 || num_bytes > 4 || rest.len() < num_bytes {
+                return Err(anyhow!("invalid length in LDAP message"));
+            }
+            let len = rest[..num_bytes].iter().fold(0, |acc, &b| acc << 8 | b as usize);
+            rest = &rest[num_bytes..];
+            len
+        };
+        if rest.len() < len {
+            return Err(truncated());
+        }
+        let (content, rest) = rest.split_at(len);
+        self.data = rest;
+        Ok((tag, content))
+    }
+
+    fn expect(&mut self, expected_tag: u8) -> anyhow::Result<&'a [u8]> {
+        let (tag, content) = self.next()?;
+        if tag != expected_tag {
+            return Err(anyhow!("unexpected element in LDAP message: tag {:#x} instead of {:#x}", tag, expected_tag));
+        }
+        Ok(content)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    #[rstest]
+    #[case::plain("alice", "alice")]
+    #[case::special_chars("a,b+c=d", "a\\,b\\+c\\=d")]
+    #[case::injection("x,ou=admins", "x\\,ou\\=admins")]
+    #[case::leading_hash("#a", "\\#a")]
+    #[case::surrounding_spaces(" a b ", "\\ a b\\ ")]
+    fn test_escape_dn_value(#[case] value: &str, #[case] expected: &str) {
+        assert_eq!(escape_dn_value(value), expected);
+    }
+
+    #[rstest]
+    #[case::short(3, vec![0x02, 0x01, 0x03])]
+    #[case::sign_bit(200, vec![0x02, 0x02, 0x00, 0xc8])]
+    #[case::zero(0, vec![0x02, 0x01, 0x00])]
+    fn test_integer(#[case] value: u32, #[case] expected: Vec<u8>) {
+        assert_eq!(integer(value), expected);
+    }
+
+    #[test]
+    fn test_tlv_long_form() {
+        let content = vec![7; 300];
+        let encoded = tlv(TAG_OCTET_STRING, &content);
+        assert_eq!(encoded[..4], [0x04, 0x82, 0x01, 0x2c]);
+
+        let mut reader = BerReader::new(&encoded);
+        assert_eq!(reader.expect(TAG_OCTET_STRING).unwrap(), content.as_slice());
+        assert!(reader.is_empty());
+        assert!(BerReader::new(&encoded[..100]).next().is_err());
+    }
+
+    /// Answers one connection's bind request, and the search for groups if the bind succeeds
+    async fn serve_directory(listener: TcpListener) {
+        let (tcp, _) = listener.accept().await.unwrap();
+        let mut connection: Box<dyn Connection> = Box::new(tcp);
+
+        let (tag, request) = receive(&mut connection).await.unwrap();
+        assert_eq!(tag, TAG_BIND_REQUEST);
+        let mut request = BerReader::new(&request);
+        request.expect(TAG_INTEGER).unwrap();
+        let dn = request.expect(TAG_OCTET_STRING).unwrap();
+        let password = request.expect(TAG_SIMPLE_AUTHENTICATION).unwrap();
+        let is_valid = dn == b"uid=alice,ou=people,dc=example,dc=com" && password == b"secret";
+        let result_code = if is_valid { SUCCESS } else { INVALID_CREDENTIALS };
+        let response = [vec![TAG_ENUMERATED, 1, result_code as u8], tlv(TAG_OCTET_STRING, b""), tlv(TAG_OCTET_STRING, b"")].concat();
+        send(&mut connection, 1, &tlv(TAG_BIND_RESPONSE, &response)).await.unwrap();
+        if !is_valid {
+            return;
+        }
+
+        let (tag, _) = receive(&mut connection).await.unwrap();
+        assert_eq!(tag, TAG_SEARCH_REQUEST);
+        let groups = [
+            tlv(TAG_OCTET_STRING, b"cn=team-x,ou=groups,dc=example,dc=com"),
+            tlv(TAG_OCTET_STRING, b"cn=admins,ou=groups,dc=example,dc=com"),
+        ].concat();
+        let attribute = tlv(TAG_SEQUENCE, &[tlv(TAG_OCTET_STRING, b"memberOf"), tlv(TAG_SET, &groups)].concat());
+        let entry = [tlv(TAG_OCTET_STRING, b"uid=alice,ou=people,dc=example,dc=com"), tlv(TAG_SEQUENCE, &attribute)].concat();
+        send(&mut connection, 2, &tlv(TAG_SEARCH_RESULT_ENTRY, &entry)).await.unwrap();
+        let done = [vec![TAG_ENUMERATED, 1, 0], tlv(TAG_OCTET_STRING, b""), tlv(TAG_OCTET_STRING, b"")].concat();
+        send(&mut connection, 2, &tlv(TAG_SEARCH_RESULT_DONE, &done)).await.unwrap();
+    }
+
+    #[rstest]
+    #[case::valid("alice", "secret", Some(vec!["team-x", "admins"]))]
+    #[case::wrong_password("alice", "wrong", None)]
+    #[case::unknown_user("bob", "secret", None)]
+    #[tokio::test]
+    async fn test_authenticate_password(#[case] user: &str, #[case] password: &str, #[case] expected_roles: Option<Vec<&str>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let store = LdapCredentialStore::new(LdapConfig {
+            url: format!("ldap://{}", listener.local_addr().unwrap()),
+            user_dn: "uid={user},ou=people,dc=example,dc=com".to_string(),
+            group_attribute: Some("memberOf".to_string()),
+            timeout_secs: 5,
+            allow_insecure: true,
+        }).unwrap();
+        let server = tokio::spawn(serve_directory(listener));
+
+        let principal = store.authenticate_password(user, password).await.unwrap();
+        assert_eq!(principal, expected_roles.map(|roles| Principal {
+            name: user.to_string(),
+            roles: roles.iter().map(|s| s.to_string()).collect(),
+        }));
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_empty_password_is_rejected() {
+        let store = LdapCredentialStore::new(LdapConfig {
+            url: "ldap://127.0.0.1:1".to_string(),
+            user_dn: "uid={user},dc=example,dc=com".to_string(),
+            group_attribute: None,
+            timeout_secs: 5,
+            allow_insecure: true,
+        }).unwrap();
+        assert_eq!(store.authenticate_password("alice", "").await.unwrap(), None);
+    }
+
+    #[rstest]
+    #[case::ldap("ldap://ldap.example.com", true, Some(("ldap.example.com", 389, false)))]
+    #[case::ldap_not_allowed("ldap://ldap.example.com", false, None)]
+    #[case::ldaps("ldaps://ldap.example.com", false, Some(("ldap.example.com", 636, true)))]
+    #[case::ldaps_with_port("ldaps://ldap.example.com:1636/", false, Some(("ldap.example.com", 1636, true)))]
+    #[case::other_scheme("https://ldap.example.com", true, None)]
+    #[case::invalid_port("ldap://ldap.example.com:x", true, None)]
+    fn test_server(#[case] url: &str, #[case] allow_insecure: bool, #[case] expected: Option<(&str, u16, bool)>) {
+        let config = LdapConfig {
+            url: url.to_string(),
+            user_dn: "uid={user}".to_string(),
+            group_attribute: None,
+            timeout_secs: 5,
+            allow_insecure,
+        };
+        assert_eq!(config.server().ok(), expected.map(|(host, port, tls)| (host.to_string(), port, tls)));
+    }
+}
@@ -0,0 +1,7 @@
+pub mod access;
+pub mod authenticator;
+pub mod authorization;
+pub mod credential_store;
+pub mod file_credential_store;
+pub mod ldap_credential_store;
+pub mod oidc_credential_store;
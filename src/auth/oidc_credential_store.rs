@@ -0,0 +1,379 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hyper::Client;
+use hyper_tls::HttpsConnector;
+use ring::signature::{ECDSA_P256_SHA256_FIXED, ECDSA_P384_SHA384_FIXED, RSA_PKCS1_2048_8192_SHA256, RSA_PKCS1_2048_8192_SHA384, RSA_PKCS1_2048_8192_SHA512, RsaPublicKeyComponents, UnparsedPublicKey};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::{debug, warn};
+
+use crate::auth::credential_store::{CredentialStore, Principal};
+use crate::util::upstream_client::HttpsClient;
+
+/// the identity provider's keys are fetched again after this time...
+const KEYS_MAX_AGE: Duration = Duration::from_secs(3600);
+/// ... or when a token is signed with an unknown key, but not more often than this
+const KEYS_MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+/// tolerated clock skew when checking a token's expiry
+const LEEWAY_SECS: u64 = 60;
+
+fn default_username_claim() -> String {
+    "sub".to_string()
+}
+
+/// ```toml
+/// [oidc]
+/// issuer = "https://login.example.com/realms/dev"
+/// audience = "arti-vault"
+/// username_claim = "preferred_username"
+/// roles_claim = "groups"
+/// ```
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OidcConfig {
+    /// must match the tokens' 'iss' claim exactly
+    pub issuer: String,
+    /// must be one of the tokens' 'aud' claim
+    pub audience: String,
+    /// discovered through the issuer's '/.well-known/openid-configuration' if this is not set
+    #[serde(default)]
+    pub jwks_uri: Option<String>,
+    /// the claim with the user's name
+    #[serde(default = "default_username_claim")]
+    pub username_claim: String,
+    /// the claim with the user's roles, a list or a space separated string
+    #[serde(default)]
+    pub roles_claim: Option<String>,
+}
+impl OidcConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for uri in Some(&self.issuer).into_iter().chain(&self.jwks_uri) {
+            let parsed = uri.parse::<hyper::Uri>()
+                .map_err(|_| anyhow!("invalid OIDC URI '{}'", uri))?;
+            if !matches!(parsed.scheme_str(), Some("http") | Some("https")) {
+                return Err(anyhow!("OIDC URI must be http or https: '{}'", uri));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct Discovery {
+    jwks_uri: String,
+}
+
+#[derive(Deserialize)]
+struct JwkSet {
+    keys: Vec<Jwk>,
+}
+
+#[derive(Deserialize)]
+struct Jwk {
+    kty: String,
+    #[serde(default)]
+    kid: Option<String>,
+    #[serde(default)]
+    crv: Option<String>,
+    #[serde(default)]
+    n: Option<String>,
+    #[serde(default)]
+    e: Option<String>,
+    #[serde(default)]
+    x: Option<String>,
+    #[serde(default)]
+    y: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct JwtHeader {
+    alg: String,
+    #[serde(default)]
+    kid: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+enum VerificationKey {
+    Rsa { n: Vec<u8>, e: Vec<u8> },
+    /// uncompressed points
+    EcP256(Vec<u8>),
+    EcP384(Vec<u8>),
+}
+impl VerificationKey {
+    /// None for unsupported keys, e.g. encryption keys
+    fn from_jwk(jwk: &Jwk) -> Option<VerificationKey> {
+        let decode = |s: &Option<String>| URL_SAFE_NO_PAD.decode(s.as_ref()?).ok();
+        match (jwk.kty.as_str(), jwk.crv.as_deref()) {
+            ("RSA", _) => Some(VerificationKey::Rsa { n: decode(&jwk.n)?, e: decode(&jwk.e)? }),
+            ("EC", Some("P-256")) => Some(VerificationKey::EcP256([vec![4], decode(&jwk.x)?, decode(&jwk.y)?].concat())),
+            ("EC", Some("P-384")) => Some(VerificationKey::EcP384([vec![4], decode(&jwk.x)?, decode(&jwk.y)?].concat())),
+            _ => None,
+        }
+    }
+
+    fn verify(&self, alg: &str, message: &[u8], signature: &[u8]) -> bool {
+        match (self, alg) {
+            (VerificationKey::Rsa { n, e }, "RS256") => RsaPublicKeyComponents { n, e }.verify(&RSA_PKCS1_2048_8192_SHA256, message, signature).is_ok(),
+            (VerificationKey::Rsa { n, e }, "RS384") => RsaPublicKeyComponents { n, e }.verify(&RSA_PKCS1_2048_8192_SHA384, message, signature).is_ok(),
+            (VerificationKey::Rsa { n, e }, "RS512") => RsaPublicKeyComponents { n, e }.verify(&RSA_PKCS1_2048_8192_SHA512, message, signature).is_ok(),
+            (VerificationKey::EcP256(point), "ES256") => UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, point).verify(message, signature).is_ok(),
+            (VerificationKey::EcP384(point), "ES384") => UnparsedPublicKey::new(&ECDSA_P384_SHA384_FIXED, point).verify(message, signature).is_ok(),
+            // in particular 'none'
+            _ => false,
+        }
+    }
+}
+
+/// by key id
+type Keys = Arc<Vec<(Option<String>, VerificationKey)>>;
+
+/// Accepts access tokens (JWTs) of an OpenID Connect identity provider as bearer tokens, and as
+///  the password of HTTP Basic authentication for clients that support nothing else. Tokens are
+///  verified with the provider's published keys, which are cached.
+pub struct OidcCredentialStore {
+    config: OidcConfig,
+    client: HttpsClient,
+    keys: Mutex<Option<(Keys, Instant)>>,
+}
+impl OidcCredentialStore {
+    pub fn new(config: OidcConfig) -> OidcCredentialStore {
+        OidcCredentialStore {
+            config,
+            client: Client::builder().build(HttpsConnector::new()),
+            keys: Default::default(),
+        }
+    }
+
+    /// The cached keys, fetched again if they are outdated or do not contain the key id
+    async fn keys(&self, kid: Option<&str>) -> anyhow::Result<Keys> {
+        let cached = self.keys.lock().unwrap().clone();
+        if let Some((keys, fetched)) = &cached {
+            let has_key = kid.is_none() || keys.iter().any(|(k, _)| k.as_deref() == kid);
+            if fetched.elapsed() < KEYS_MAX_AGE && (has_key || fetched.elapsed() < KEYS_MIN_REFRESH_INTERVAL) {
+                return Ok(keys.clone());
+            }
+        }
+
+        match self.fetch_keys().await {
+            Ok(keys) => {
+                *self.keys.lock().unwrap() = Some((keys.clone(), Instant::now()));
+                Ok(keys)
+            }
+            Err(e) => match cached {
+                Some((keys, _)) => {
+                    warn!("error fetching the keys of OIDC issuer {}, using cached keys: {:#}", self.config.issuer, e);
+                    Ok(keys)
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    async fn fetch_keys(&self) -> anyhow::Result<Keys> {
+        let jwks_uri = match &self.config.jwks_uri {
+            Some(jwks_uri) => jwks_uri.clone(),
+            None => {
+                let discovery_uri = format!("{}/.well-known/openid-configuration", self.config.issuer.trim_end_matches('/'));
+                self.get_json::<Discovery>(&discovery_uri).await?.jwks_uri
+            }
+        };
+        let jwk_set: JwkSet = self.get_json(&jwks_uri).await?;
+        Ok(Arc::new(jwk_set.keys.iter()
+            .filter_map(|jwk| VerificationKey::from_jwk(jwk).map(|key| (jwk.kid.clone(), key)))
+            .collect()))
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, uri: &str) -> anyhow::Result<T> {
+        let fetch = async {
+            let response = self.client.get(uri.parse()?).await?;
+            if !response.status().is_success() {
+                return Err(anyhow!("GET {} failed with status {}", uri, response.status()));
+            }
+            let body = hyper::body::to_bytes(response.into_body()).await?;
+            Ok(serde_json::from_slice(&body)?)
+        };
+        tokio::time::timeout(FETCH_TIMEOUT, fetch).await
+            .map_err(|_| anyhow!("timeout fetching {}", uri))?
+    }
+
+    /// None if the token is invalid, expired or not meant for this server
+    fn verify(&self, token: &str, header: &JwtHeader, keys: &[(Option<String>, VerificationKey)], now: u64) -> Option<Principal> {
+        let (signed, signature) = token.rsplit_once('.')?;
+        let (_, payload) = signed.split_once('.')?;
+        let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+
+        let is_signed = keys.iter()
+            .filter(|(kid, _)| header.kid.is_none() || kid == &header.kid)
+            .any(|(_, key)| key.verify(&header.alg, signed.as_bytes(), &signature));
+        if !is_signed {
+            debug!("OIDC token with invalid signature or unknown key {:?}", header.kid);
+            return None;
+        }
+
+        let claims: serde_json::Map<String, Value> = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).ok()?).ok()?;
+        if claims.get("iss").and_then(Value::as_str) != Some(&self.config.issuer) {
+            debug!("OIDC token of another issuer: {:?}", claims.get("iss"));
+            return None;
+        }
+        let is_audience = match claims.get("aud") {
+            Some(Value::String(aud)) => aud == &self.config.audience,
+            Some(Value::Array(aud)) => aud.iter().any(|aud| aud.as_str() == Some(&self.config.audience)),
+            _ => false,
+        };
+        if !is_audience {
+            debug!("OIDC token for another audience: {:?}", claims.get("aud"));
+            return None;
+        }
+        let expires = claims.get("exp").and_then(Value::as_u64)?;
+        let not_before = claims.get("nbf").and_then(Value::as_u64).unwrap_or(0);
+        if now > expires + LEEWAY_SECS || now + LEEWAY_SECS < not_before {
+            debug!("OIDC token outside its validity period");
+            return None;
+        }
+
+        let name = claims.get(&self.config.username_claim).and_then(Value::as_str)?;
+        let roles = match self.config.roles_claim.as_ref().and_then(|claim| claims.get(claim)) {
+            Some(Value::Array(roles)) => roles.iter().filter_map(Value::as_str).map(|s| s.to_string()).collect(),
+            Some(Value::String(roles)) => roles.split_whitespace().map(|s| s.to_string()).collect(),
+            _ => vec![],
+        };
+        Some(Principal {
+            name: name.to_string(),
+            roles,
+        })
+    }
+}
+
+/// None if the token is not a JWT, e.g. a token of another credential store
+fn parse_header(token: &str) -> Option<JwtHeader> {
+    if token.split('.').count() != 3 {
+        return None;
+    }
+    let (header, _) = token.split_once('.')?;
+    serde_json::from_slice(&URL_SAFE_NO_PAD.decode(header).ok()?).ok()
+}
+
+fn unix_time_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[async_trait]
+impl CredentialStore for OidcCredentialStore {
+    async fn authenticate_password(&self, user: &str, password: &str) -> anyhow::Result<Option<Principal>> {
+        Ok(self.authenticate_token(password).await?
+            .filter(|principal| principal.name == user))
+    }
+
+    async fn authenticate_token(&self, token: &str) -> anyhow::Result<Option<Principal>> {
+        let header = match parse_header(token) {
+            Some(header) => header,
+            None => return Ok(None),
+        };
+        let keys = self.keys(header.kid.as_deref()).await?;
+        Ok(self.verify(token, &header, &keys, unix_time_secs()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ring::rand::SystemRandom;
+    use ring::signature::{ECDSA_P256_SHA256_FIXED_SIGNING, EcdsaKeyPair, KeyPair};
+    use rstest::rstest;
+    use serde_json::json;
+
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn store() -> OidcCredentialStore {
+        OidcCredentialStore::new(OidcConfig {
+            issuer: "https://login.example.com".to_string(),
+            audience: "arti-vault".to_string(),
+            jwks_uri: None,
+            username_claim: "preferred_username".to_string(),
+            roles_claim: Some("groups".to_string()),
+        })
+    }
+
+    fn key_pair() -> EcdsaKeyPair {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng).unwrap();
+        EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref(), &rng).unwrap()
+    }
+
+    fn token(key_pair: &EcdsaKeyPair, header: Value, claims: Value) -> String {
+        let signed = format!("{}.{}", URL_SAFE_NO_PAD.encode(header.to_string()), URL_SAFE_NO_PAD.encode(claims.to_string()));
+        let signature = key_pair.sign(&SystemRandom::new(), signed.as_bytes()).unwrap();
+        format!("{}.{}", signed, URL_SAFE_NO_PAD.encode(signature.as_ref()))
+    }
+
+    fn claims() -> Value {
+        json!({
+            "iss": "https://login.example.com",
+            "aud": ["account", "arti-vault"],
+            "exp": NOW + 300,
+            "preferred_username": "alice",
+            "groups": ["team-x"],
+        })
+    }
+
+    fn with(claims: Value, name: &str, value: Value) -> Value {
+        let mut claims = claims;
+        claims[name] = value;
+        claims
+    }
+
+    #[rstest]
+    #[case::valid(json!({"alg": "ES256", "kid": "k1"}), claims(), true)]
+    #[case::without_kid(json!({"alg": "ES256"}), claims(), true)]
+    #[case::single_audience(json!({"alg": "ES256", "kid": "k1"}), with(claims(), "aud", json!("arti-vault")), true)]
+    #[case::within_leeway(json!({"alg": "ES256", "kid": "k1"}), with(claims(), "exp", json!(NOW - 30)), true)]
+    #[case::expired(json!({"alg": "ES256", "kid": "k1"}), with(claims(), "exp", json!(NOW - 300)), false)]
+    #[case::not_yet_valid(json!({"alg": "ES256", "kid": "k1"}), with(claims(), "nbf", json!(NOW + 300)), false)]
+    #[case::other_audience(json!({"alg": "ES256", "kid": "k1"}), with(claims(), "aud", json!("other")), false)]
+    #[case::other_issuer(json!({"alg": "ES256", "kid": "k1"}), with(claims(), "iss", json!("https://evil.example.com")), false)]
+    #[case::unknown_key(json!({"alg": "ES256", "kid": "k2"}), claims(), false)]
+    #[case::alg_none(json!({"alg": "none", "kid": "k1"}), claims(), false)]
+    #[case::wrong_alg(json!({"alg": "RS256", "kid": "k1"}), claims(), false)]
+    fn test_verify(#[case] header: Value, #[case] claims: Value, #[case] expected_valid: bool) {
+        let key_pair = key_pair();
+        let keys = vec![(Some("k1".to_string()), VerificationKey::EcP256(key_pair.public_key().as_ref().to_vec()))];
+        let token = token(&key_pair, header, claims);
+
+        let principal = store().verify(&token, &parse_header(&token).unwrap(), &keys, NOW);
+        let expected = expected_valid.then(|| Principal {
+            name: "alice".to_string(),
+            roles: vec!["team-x".to_string()],
+        });
+        assert_eq!(principal, expected);
+    }
+
+    #[test]
+    fn test_verify_tampered() {
+        let key_pair = key_pair();
+        let keys = vec![(Some("k1".to_string()), VerificationKey::EcP256(key_pair.public_key().as_ref().to_vec()))];
+        let token = token(&key_pair, json!({"alg": "ES256", "kid": "k1"}), claims());
+
+        let (header, rest) = token.split_once('.').unwrap();
+        let (_, signature) = rest.split_once('.').unwrap();
+        let forged_claims = with(claims(), "preferred_username", json!("admin"));
+        let forged = format!("{}.{}.{}", header, URL_SAFE_NO_PAD.encode(forged_claims.to_string()), signature);
+        assert_eq!(store().verify(&forged, &parse_header(&forged).unwrap(), &keys, NOW), None);
+    }
+
+    #[tokio::test]
+    async fn test_ignores_other_tokens() {
+        assert_eq!(store().authenticate_token("not-a-jwt").await.unwrap(), None);
+        assert_eq!(store().authenticate_password("alice", "secret").await.unwrap(), None);
+    }
+}
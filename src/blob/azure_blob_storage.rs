@@ -0,0 +1,89 @@
+use async_trait::async_trait;
+use hyper::{Body, Client, Method, Request};
+use hyper::header::CONTENT_LENGTH;
+use hyper_tls::HttpsConnector;
+
+use crate::blob::object_storage::{delete_object, get_object_stream, ObjectStore, ObjectStream, send, UploadPart};
+use crate::util::upstream_client::HttpsClient;
+
+/// REST API version, see https://learn.microsoft.com/en-us/rest/api/storageservices/versioning-for-the-azure-storage-services
+const API_VERSION: &str = "2021-08-06";
+
+/// Azure Blob Storage, using one container. Objects are uploaded as block blobs with one block per
+///  part, and committed with a block list.
+///
+/// Requests are authorized with a SAS token for the container, which needs read, write, create
+///  and delete permissions.
+pub struct AzureObjectStore {
+    client: HttpsClient,
+    /// e.g. 'https://account.blob.core.windows.net/container', without trailing '/'
+    container_url: String,
+    /// without leading '?'
+    sas_token: String,
+}
+impl AzureObjectStore {
+    pub fn new(container_url: &str, sas_token: &str) -> AzureObjectStore {
+        AzureObjectStore {
+            client: Client::builder().build(HttpsConnector::new()),
+            container_url: container_url.trim_end_matches('/').to_string(),
+            sas_token: sas_token.trim_start_matches('?').to_string(),
+        }
+    }
+
+    /// 'query' is appended before the SAS token, e.g. 'comp=block&'
+    fn request(&self, method: Method, name: &str, query: &str) -> hyper::http::request::Builder {
+        Request::builder()
+            .method(method)
+            .uri(format!("{}/{}?{}{}", self.container_url, name, query, self.sas_token))
+            .header("x-ms-version", API_VERSION)
+    }
+
+    /// Block ids must be valid Base64 of the same length for all blocks of a blob. Eight hex
+    ///  digits are that already, so no encoding is needed.
+    fn block_id(index: usize) -> String {
+        format!("{:08x}", index)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for AzureObjectStore {
+    async fn begin_upload(&self, _name: &str) -> anyhow::Result<String> {
+        // uncommitted blocks are implicitly part of the blob's next block list
+        Ok(String::new())
+    }
+
+    async fn upload_part(&self, name: &str, _upload: &str, part: UploadPart) -> anyhow::Result<()> {
+        // empty blocks are not allowed - an empty blob is committed with an empty block list
+        if part.data.is_empty() {
+            return Ok(());
+        }
+        let request = self.request(Method::PUT, name, &format!("comp=block&blockid={}&", Self::block_id(part.index)))
+            .header(CONTENT_LENGTH, part.data.len())
+            .body(Body::from(part.data))?;
+        send(&self.client, request, &[]).await?;
+        Ok(())
+    }
+
+    async fn finish_upload(&self, name: &str, _upload: &str, num_parts: usize, total_size: u64) -> anyhow::Result<()> {
+        let num_blocks = if total_size == 0 { 0 } else { num_parts };
+        let mut block_list = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?><BlockList>");
+        for index in 0..num_blocks {
+            block_list.push_str(&format!("<Latest>{}</Latest>", Self::block_id(index)));
+        }
+        block_list.push_str("</BlockList>");
+
+        let request = self.request(Method::PUT, name, "comp=blocklist&")
+            .header(CONTENT_LENGTH, block_list.len())
+            .body(Body::from(block_list))?;
+        send(&self.client, request, &[]).await?;
+        Ok(())
+    }
+
+    async fn get(&self, name: &str) -> anyhow::Result<Option<ObjectStream>> {
+        get_object_stream(&self.client, self.request(Method::GET, name, "").body(Body::empty())?).await
+    }
+
+    async fn delete(&self, name: &str) -> anyhow::Result<bool> {
+        delete_object(&self.client, self.request(Method::DELETE, name, "").body(Body::empty())?).await
+    }
+}
@@ -0,0 +1,157 @@
+use anyhow::anyhow;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::{Hmac, KeyInit, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// the number of bytes of the HMAC that are part of an id
+const TAG_LEN: usize = 16;
+
+/// Maps blob keys to identifiers for use in external URLs (pre-signed URLs, replication between
+///  instances) and back. Keys are internal: identifiers must neither reveal them nor allow
+///  guessing or enumerating other blobs' identifiers.
+pub trait BlobIdCodec: Send + Sync {
+    fn encode(&self, key: &Uuid) -> String;
+    /// None if the id was not issued by this codec, i.e. it is malformed, tampered with or signed
+    ///  with a different secret
+    fn decode(&self, id: &str) -> Option<Uuid>;
+}
+
+/// Opaque, signed blob ids derived from a server secret: the id is the key encrypted with a
+///  keystream derived from its HMAC, followed by that HMAC (truncated to 128 bits). Ids are
+///  deterministic, so the same blob always has the same URL, and they are only valid for
+///  instances sharing the secret.
+pub struct SignedBlobIds {
+    tag_key: Vec<u8>,
+    mask_key: Vec<u8>,
+}
+impl SignedBlobIds {
+    pub fn new(secret: &[u8]) -> anyhow::Result<SignedBlobIds> {
+        if secret.len() < 16 {
+            return Err(anyhow!("the blob id secret must have at least 16 bytes"));
+        }
+        // separate keys for signing and for encrypting
+        Ok(SignedBlobIds {
+            tag_key: mac(secret, b"arti-vault blob id tag")?.to_vec(),
+            mask_key: mac(secret, b"arti-vault blob id mask")?.to_vec(),
+        })
+    }
+
+    fn tag(&self, key: &Uuid) -> [u8; TAG_LEN] {
+        truncated(&mac(&self.tag_key, key.as_bytes()).expect("HMAC accepts keys of any length"))
+    }
+
+    fn mask(&self, tag: &[u8]) -> [u8; TAG_LEN] {
+        truncated(&mac(&self.mask_key, tag).expect("HMAC accepts keys of any length"))
+    }
+}
+
+impl BlobIdCodec for SignedBlobIds {
+    fn encode(&self, key: &Uuid) -> String {
+        let tag = self.tag(key);
+        let mask = self.mask(&tag);
+
+        let mut id = key.into_bytes().iter().zip(mask).map(|(b, m)| b ^ m).collect::<Vec<_>>();
+        id.extend_from_slice(&tag);
+        URL_SAFE_NO_PAD.encode(id)
+    }
+
+    fn decode(&self, id: &str) -> Option<Uuid> {
+        let id = URL_SAFE_NO_PAD.decode(id).ok()?;
+        if id.len() != 16 + TAG_LEN {
+            return None;
+        }
+        let (encrypted, tag) = id.split_at(16);
+        let mask = self.mask(tag);
+
+        let mut key = [0u8; 16];
+        for (i, (b, m)) in encrypted.iter().zip(mask).enumerate() {
+            key[i] = b ^ m;
+        }
+        let key = Uuid::from_bytes(key);
+
+        // constant time comparison of the tag
+        let mut expected = HmacSha256::new_from_slice(&self.tag_key).ok()?;
+        expected.update(key.as_bytes());
+        expected.verify_truncated_left(tag).ok()?;
+        Some(key)
+    }
+}
+
+fn mac(key: &[u8], data: &[u8]) -> anyhow::Result<[u8; 32]> {
+    let mut mac = <HmacSha256 as KeyInit>::new_from_slice(key)
+        .map_err(|_| anyhow!("invalid HMAC key"))?;
+    mac.update(data);
+    Ok(mac.finalize().into_bytes().into())
+}
+
+fn truncated(mac: &[u8; 32]) -> [u8; TAG_LEN] {
+    let mut result = [0u8; TAG_LEN];
+    result.copy_from_slice(&mac[..TAG_LEN]);
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::*;
+
+    use super::*;
+
+    const SECRET: &[u8] = b"0123456789abcdef";
+
+    #[test]
+    fn test_round_trip() {
+        let ids = SignedBlobIds::new(SECRET).unwrap();
+        let key = Uuid::new_v4();
+
+        let id = ids.encode(&key);
+        assert_eq!(ids.decode(&id), Some(key));
+        assert_eq!(ids.encode(&key), id);
+        assert_eq!(SignedBlobIds::new(SECRET).unwrap().decode(&id), Some(key));
+    }
+
+    #[test]
+    fn test_opaque() {
+        let ids = SignedBlobIds::new(SECRET).unwrap();
+        let key = Uuid::new_v4();
+        let id = URL_SAFE_NO_PAD.decode(ids.encode(&key)).unwrap();
+
+        assert!(!id.windows(4).any(|w| key.as_bytes().windows(4).any(|k| k == w)));
+        // consecutive keys do not have related ids
+        let next = Uuid::from_u128(key.as_u128() + 1);
+        let next_id = URL_SAFE_NO_PAD.decode(ids.encode(&next)).unwrap();
+        assert_ne!(id[16..], next_id[16..]);
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::not_base64("not base64!")]
+    #[case::too_short("AAAA")]
+    #[case::unsigned("AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA")]
+    fn test_invalid(#[case] id: &str) {
+        assert_eq!(SignedBlobIds::new(SECRET).unwrap().decode(id), None);
+    }
+
+    #[test]
+    fn test_tampered() {
+        let ids = SignedBlobIds::new(SECRET).unwrap();
+        let id = URL_SAFE_NO_PAD.decode(ids.encode(&Uuid::new_v4())).unwrap();
+
+        for i in 0..id.len() {
+            let mut tampered = id.clone();
+            tampered[i] ^= 1;
+            assert_eq!(ids.decode(&URL_SAFE_NO_PAD.encode(tampered)), None);
+        }
+    }
+
+    #[test]
+    fn test_other_secret() {
+        let key = Uuid::new_v4();
+        let id = SignedBlobIds::new(SECRET).unwrap().encode(&key);
+        assert_eq!(SignedBlobIds::new(b"fedcba9876543210").unwrap().decode(&id), None);
+        assert!(SignedBlobIds::new(b"too short").is_err());
+    }
+}
@@ -1,18 +1,151 @@
 use std::fmt::Debug;
 use std::hash::Hash;
+use std::ops::Range;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
 
+use anyhow::anyhow;
 use async_trait::async_trait;
 use bytes::Bytes;
+use futures::StreamExt;
 use futures_core::Stream;
-use crate::util::blob::Blob;
+use serde::{Deserialize, Serialize};
 
+use crate::util::blob::{Blob, BlobStat};
+use crate::util::byte_range::slice_stream;
+
+/// Data to be stored as a blob. It is boxed rather than generic so that [BlobStorage] is object
+///  safe, allowing repositories to hold storage as `Arc<dyn BlobStorage<Uuid>>`.
+pub type BlobStream<'a> = Pin<Box<dyn Stream<Item=anyhow::Result<Bytes>> + Send + 'a>>;
+
+/// What a blob was stored as. Backends that keep metadata with their blobs record it, so that a
+///  repository's metadata can be rebuilt from its blob storage if the metadata store is lost.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct BlobOrigin {
+    /// the repository path, e.g. 'org/a/a/1.0/a-1.0.jar'
+    pub path: String,
+    pub fetched: SystemTime,
+    pub last_modified: SystemTime,
+}
+
+/// Results of fetching several blobs, see [BlobStorage::get_many]
+pub type GetManyStream<'a, Key> = Pin<Box<dyn Stream<Item=(Key, anyhow::Result<Option<Blob>>)> + Send + 'a>>;
+
+/// Stores blobs by key. Keys are internal: clients only ever see artifact paths and SHA1 based
+///  ETags, and URLs addressing blobs directly (e.g. download URLs of quarantined blobs) use ids
+///  from a [crate::blob::blob_id::BlobIdCodec].
 #[async_trait]
-pub trait BlobStorage<Key: Clone + Debug + Eq + PartialEq + Hash>: Send + Sync {
+pub trait BlobStorage<Key: Clone + Debug + Eq + PartialEq + Hash + Send + Sync + 'static>: Send + Sync {
     /// The key for looking up blobs
-    async fn insert(&self, data: impl Stream<Item=anyhow::Result<Bytes>> + Send)-> anyhow::Result<Key>;
+    async fn insert(&self, data: BlobStream<'_>) -> anyhow::Result<Key>;
 
-    async fn get(&self, key: &Key, ) -> anyhow::Result<Option<Blob>>;
+    /// Like [BlobStorage::insert], with the data's length if it is known in advance, e.g. from a
+    ///  'Content-Length' header. Backends can use it to preallocate or to reject data exceeding
+    ///  their limits before reading it. The insert fails if the data has a different length, e.g.
+    ///  because a transfer was truncated.
+    async fn insert_with_size(&self, data: BlobStream<'_>, expected_size: Option<u64>) -> anyhow::Result<Key> {
+        match expected_size {
+            None => self.insert(data).await,
+            Some(expected_size) => self.insert(Box::pin(expect_size(data, expected_size))).await,
+        }
+    }
+
+    /// None if there is no blob for the key. Blobs returned by storage backends always have their
+    ///  size and checksums.
+    async fn get(&self, key: &Key) -> anyhow::Result<Option<Blob>>;
+
+    /// Like [BlobStorage::get] for part of a blob, e.g. to resume an interrupted download. The
+    ///  range must be within the blob. The returned blob's size is the range's length, while its
+    ///  checksums are the entire blob's. The default implementation reads and discards the data
+    ///  before the range, backends that can seek should override it.
+    async fn get_range(&self, key: &Key, range: Range<u64>) -> anyhow::Result<Option<Blob>> {
+        Ok(self.get(key).await?.map(|blob| Blob {
+            data: Box::pin(slice_stream(blob.data, range.clone())),
+            size: Some(range.end - range.start),
+            ..blob
+        }))
+    }
+
+    /// Returns a blob's metadata without opening its data, e.g. for HEAD requests
+    async fn stat(&self, key: &Key) -> anyhow::Result<Option<BlobStat>>;
 
     async fn delete(&self, key: &Key) -> anyhow::Result<bool>;
+
+    /// Records what a blob was stored as, see [BlobOrigin]. The default implementation does
+    ///  nothing, for backends that keep no metadata of their own.
+    async fn record_origin(&self, _key: &Key, _origin: &BlobOrigin) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// All blobs with a recorded [BlobOrigin], in no particular order - none for backends that do
+    ///  not record origins
+    async fn origins(&self) -> anyhow::Result<Vec<(Key, BlobOrigin)>> {
+        Ok(vec![])
+    }
+
+    /// When a blob was last read as far as the backend knows, for initializing access tracking of
+    ///  blobs stored before it was tracked. The default implementation returns the time the blob
+    ///  was created. None if there is no blob for the key.
+    async fn last_access(&self, key: &Key) -> anyhow::Result<Option<SystemTime>> {
+        Ok(self.stat(key).await?.map(|stat| stat.created))
+    }
+
+    /// Fetches several blobs, returning results in the order of the given keys. The default
+    ///  implementation fetches them one at a time, backends can override it to batch or parallelize
+    ///  lookups.
+    fn get_many<'a>(&'a self, keys: &[Key]) -> GetManyStream<'a, Key> {
+        Box::pin(futures::stream::iter(keys.to_vec())
+            .then(move |key| async move {
+                let blob = self.get(&key).await;
+                (key, blob)
+            }))
+    }
 }
 
+/// Passes data through, failing as soon as it exceeds 'expected_size', or at its end if it falls
+///  short of it
+pub fn expect_size(data: impl Stream<Item=anyhow::Result<Bytes>> + Send, expected_size: u64) -> impl Stream<Item=anyhow::Result<Bytes>> + Send {
+    let num_bytes = Arc::new(AtomicU64::new(0));
+    let counted_bytes = num_bytes.clone();
+
+    data
+        .map(move |chunk| {
+            let chunk = chunk?;
+            let total = counted_bytes.fetch_add(chunk.len() as u64, Ordering::Relaxed) + chunk.len() as u64;
+            if total > expected_size {
+                return Err(anyhow!("data exceeds the expected size of {} bytes", expected_size));
+            }
+            Ok(chunk)
+        })
+        .chain(futures::stream::once(async move {
+            let total = num_bytes.load(Ordering::Relaxed);
+            match total < expected_size {
+                true => Some(Err(anyhow!("data ended after {} of the expected {} bytes", total, expected_size))),
+                false => None,
+            }
+        }).filter_map(|result| async move { result }))
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::exact(3, true)]
+    #[case::too_long(2, false)]
+    #[case::truncated(4, false)]
+    #[tokio::test]
+    async fn test_expect_size(#[case] expected_size: u64, #[case] expected_ok: bool) {
+        let data = futures::stream::iter(vec![Ok(Bytes::from_static(b"ab")), Ok(Bytes::from_static(b"c"))]);
+        let result = expect_size(data, expected_size)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<anyhow::Result<Vec<_>>>();
+        assert_eq!(result.is_ok(), expected_ok);
+    }
+}
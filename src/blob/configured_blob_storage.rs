@@ -0,0 +1,209 @@
+use std::ops::Range;
+#[cfg(feature = "fs-storage")]
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[cfg(feature = "azure-storage")]
+use crate::blob::azure_blob_storage::AzureObjectStore;
+use crate::blob::blob_storage::{BlobOrigin, BlobStorage, BlobStream, GetManyStream};
+#[cfg(feature = "fs-storage")]
+use crate::blob::fs_blob_storage::FsBlobStorage;
+#[cfg(feature = "gcs-storage")]
+use crate::blob::gcs_blob_storage::GcsObjectStore;
+#[cfg(any(feature = "azure-storage", feature = "gcs-storage"))]
+use crate::blob::object_storage::ObjectBlobStorage;
+use crate::blob::transient_blob_storage::TransientBlobStorage;
+use crate::util::blob::{Blob, BlobStat};
+use crate::util::uuid_generator::UuidGenerator;
+
+/// The blob storage backend for a repository, selected at startup
+#[derive(Clone, Eq, PartialEq, Debug, Default, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BlobStorageConfig {
+    /// in memory, i.e. lost on restart
+    #[default]
+    Transient,
+    /// 'verify_reads' checks blobs against their checksums when they are read, see
+    ///  [FsBlobStorage::with_read_verification]
+    #[cfg(feature = "fs-storage")]
+    Fs {
+        root: PathBuf,
+        #[serde(default)]
+        verify_reads: bool,
+    },
+    /// e.g. 'https://account.blob.core.windows.net/container', see [AzureObjectStore]
+    #[cfg(feature = "azure-storage")]
+    Azure { container_url: String, sas_token: String },
+    /// without 'access_token', tokens are requested from the instance metadata server, see [GcsObjectStore]
+    #[cfg(feature = "gcs-storage")]
+    Gcs {
+        bucket: String,
+        #[serde(default)]
+        access_token: Option<String>,
+    },
+}
+impl BlobStorageConfig {
+    pub fn create(&self, key_generator: Arc<dyn UuidGenerator>) -> ConfiguredBlobStorage {
+        match self {
+            BlobStorageConfig::Transient => ConfiguredBlobStorage::Transient(TransientBlobStorage::new().with_key_generator(key_generator)),
+            #[cfg(feature = "fs-storage")]
+            BlobStorageConfig::Fs { root, verify_reads } => ConfiguredBlobStorage::Fs(Arc::new(FsBlobStorage::new(root.clone())
+                .with_key_generator(key_generator)
+                .with_read_verification(*verify_reads))),
+            #[cfg(feature = "azure-storage")]
+            BlobStorageConfig::Azure { container_url, sas_token } => ConfiguredBlobStorage::Azure(ObjectBlobStorage::new(AzureObjectStore::new(container_url, sas_token)).with_key_generator(key_generator)),
+            #[cfg(feature = "gcs-storage")]
+            BlobStorageConfig::Gcs { bucket, access_token } => ConfiguredBlobStorage::Gcs(ObjectBlobStorage::new(GcsObjectStore::new(bucket, access_token.clone())).with_key_generator(key_generator)),
+        }
+    }
+}
+
+/// Delegates to one of the available backends, so that repositories with different backends
+///  have the same type
+pub enum ConfiguredBlobStorage {
+    Transient(TransientBlobStorage),
+    #[cfg(feature = "fs-storage")]
+    Fs(Arc<FsBlobStorage>),
+    #[cfg(feature = "azure-storage")]
+    Azure(ObjectBlobStorage<AzureObjectStore>),
+    #[cfg(feature = "gcs-storage")]
+    Gcs(ObjectBlobStorage<GcsObjectStore>),
+}
+impl ConfiguredBlobStorage {
+    /// for maintenance operations that are specific to file system storage
+    #[cfg(feature = "fs-storage")]
+    pub fn as_fs(&self) -> Option<&Arc<FsBlobStorage>> {
+        match self {
+            ConfiguredBlobStorage::Fs(fs) => Some(fs),
+            _ => None,
+        }
+    }
+}
+
+#[async_trait]
+impl BlobStorage<Uuid> for ConfiguredBlobStorage {
+    async fn insert(&self, data: BlobStream<'_>) -> anyhow::Result<Uuid> {
+        match self {
+            ConfiguredBlobStorage::Transient(s) => s.insert(data).await,
+            #[cfg(feature = "fs-storage")]
+            ConfiguredBlobStorage::Fs(s) => s.insert(data).await,
+            #[cfg(feature = "azure-storage")]
+            ConfiguredBlobStorage::Azure(s) => s.insert(data).await,
+            #[cfg(feature = "gcs-storage")]
+            ConfiguredBlobStorage::Gcs(s) => s.insert(data).await,
+        }
+    }
+
+    async fn insert_with_size(&self, data: BlobStream<'_>, expected_size: Option<u64>) -> anyhow::Result<Uuid> {
+        match self {
+            ConfiguredBlobStorage::Transient(s) => s.insert_with_size(data, expected_size).await,
+            #[cfg(feature = "fs-storage")]
+            ConfiguredBlobStorage::Fs(s) => s.insert_with_size(data, expected_size).await,
+            #[cfg(feature = "azure-storage")]
+            ConfiguredBlobStorage::Azure(s) => s.insert_with_size(data, expected_size).await,
+            #[cfg(feature = "gcs-storage")]
+            ConfiguredBlobStorage::Gcs(s) => s.insert_with_size(data, expected_size).await,
+        }
+    }
+
+    async fn get(&self, key: &Uuid) -> anyhow::Result<Option<Blob>> {
+        match self {
+            ConfiguredBlobStorage::Transient(s) => s.get(key).await,
+            #[cfg(feature = "fs-storage")]
+            ConfiguredBlobStorage::Fs(s) => s.get(key).await,
+            #[cfg(feature = "azure-storage")]
+            ConfiguredBlobStorage::Azure(s) => s.get(key).await,
+            #[cfg(feature = "gcs-storage")]
+            ConfiguredBlobStorage::Gcs(s) => s.get(key).await,
+        }
+    }
+
+    async fn get_range(&self, key: &Uuid, range: Range<u64>) -> anyhow::Result<Option<Blob>> {
+        match self {
+            ConfiguredBlobStorage::Transient(s) => s.get_range(key, range).await,
+            #[cfg(feature = "fs-storage")]
+            ConfiguredBlobStorage::Fs(s) => s.get_range(key, range).await,
+            #[cfg(feature = "azure-storage")]
+            ConfiguredBlobStorage::Azure(s) => s.get_range(key, range).await,
+            #[cfg(feature = "gcs-storage")]
+            ConfiguredBlobStorage::Gcs(s) => s.get_range(key, range).await,
+        }
+    }
+
+    async fn stat(&self, key: &Uuid) -> anyhow::Result<Option<BlobStat>> {
+        match self {
+            ConfiguredBlobStorage::Transient(s) => s.stat(key).await,
+            #[cfg(feature = "fs-storage")]
+            ConfiguredBlobStorage::Fs(s) => s.stat(key).await,
+            #[cfg(feature = "azure-storage")]
+            ConfiguredBlobStorage::Azure(s) => s.stat(key).await,
+            #[cfg(feature = "gcs-storage")]
+            ConfiguredBlobStorage::Gcs(s) => s.stat(key).await,
+        }
+    }
+
+    async fn delete(&self, key: &Uuid) -> anyhow::Result<bool> {
+        match self {
+            ConfiguredBlobStorage::Transient(s) => s.delete(key).await,
+            #[cfg(feature = "fs-storage")]
+            ConfiguredBlobStorage::Fs(s) => s.delete(key).await,
+            #[cfg(feature = "azure-storage")]
+            ConfiguredBlobStorage::Azure(s) => s.delete(key).await,
+            #[cfg(feature = "gcs-storage")]
+            ConfiguredBlobStorage::Gcs(s) => s.delete(key).await,
+        }
+    }
+
+    async fn record_origin(&self, key: &Uuid, origin: &BlobOrigin) -> anyhow::Result<()> {
+        match self {
+            ConfiguredBlobStorage::Transient(s) => s.record_origin(key, origin).await,
+            #[cfg(feature = "fs-storage")]
+            ConfiguredBlobStorage::Fs(s) => s.record_origin(key, origin).await,
+            #[cfg(feature = "azure-storage")]
+            ConfiguredBlobStorage::Azure(s) => s.record_origin(key, origin).await,
+            #[cfg(feature = "gcs-storage")]
+            ConfiguredBlobStorage::Gcs(s) => s.record_origin(key, origin).await,
+        }
+    }
+
+    async fn origins(&self) -> anyhow::Result<Vec<(Uuid, BlobOrigin)>> {
+        match self {
+            ConfiguredBlobStorage::Transient(s) => s.origins().await,
+            #[cfg(feature = "fs-storage")]
+            ConfiguredBlobStorage::Fs(s) => s.origins().await,
+            #[cfg(feature = "azure-storage")]
+            ConfiguredBlobStorage::Azure(s) => s.origins().await,
+            #[cfg(feature = "gcs-storage")]
+            ConfiguredBlobStorage::Gcs(s) => s.origins().await,
+        }
+    }
+
+    async fn last_access(&self, key: &Uuid) -> anyhow::Result<Option<SystemTime>> {
+        match self {
+            ConfiguredBlobStorage::Transient(s) => s.last_access(key).await,
+            #[cfg(feature = "fs-storage")]
+            ConfiguredBlobStorage::Fs(s) => s.last_access(key).await,
+            #[cfg(feature = "azure-storage")]
+            ConfiguredBlobStorage::Azure(s) => s.last_access(key).await,
+            #[cfg(feature = "gcs-storage")]
+            ConfiguredBlobStorage::Gcs(s) => s.last_access(key).await,
+        }
+    }
+
+    fn get_many<'a>(&'a self, keys: &[Uuid]) -> GetManyStream<'a, Uuid> {
+        match self {
+            ConfiguredBlobStorage::Transient(s) => s.get_many(keys),
+            #[cfg(feature = "fs-storage")]
+            ConfiguredBlobStorage::Fs(s) => s.get_many(keys),
+            #[cfg(feature = "azure-storage")]
+            ConfiguredBlobStorage::Azure(s) => s.get_many(keys),
+            #[cfg(feature = "gcs-storage")]
+            ConfiguredBlobStorage::Gcs(s) => s.get_many(keys),
+        }
+    }
+}
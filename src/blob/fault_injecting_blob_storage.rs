@@ -0,0 +1,94 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+
+use crate::blob::blob_storage::{BlobOrigin, BlobStorage, BlobStream};
+use crate::util::blob::{Blob, BlobStat};
+use crate::util::fault_injection::{FaultInjector, FaultOperation};
+
+/// Wraps another blob storage, injecting faults before delegating to it - for testing only
+pub struct FaultInjectingBlobStorage<S> {
+    inner: S,
+    injector: Arc<FaultInjector>,
+}
+impl<S> FaultInjectingBlobStorage<S> {
+    pub fn new(inner: S, injector: Arc<FaultInjector>) -> FaultInjectingBlobStorage<S> {
+        FaultInjectingBlobStorage {
+            inner,
+            injector,
+        }
+    }
+}
+
+#[async_trait]
+impl<Key, S> BlobStorage<Key> for FaultInjectingBlobStorage<S>
+where
+    Key: Clone + Debug + Eq + PartialEq + Hash + Send + Sync + 'static,
+    S: BlobStorage<Key>,
+{
+    async fn insert(&self, data: BlobStream<'_>) -> anyhow::Result<Key> {
+        self.injector.inject(FaultOperation::BlobInsert, None).await?;
+        self.inner.insert(data).await
+    }
+
+    async fn insert_with_size(&self, data: BlobStream<'_>, expected_size: Option<u64>) -> anyhow::Result<Key> {
+        self.injector.inject(FaultOperation::BlobInsert, None).await?;
+        self.inner.insert_with_size(data, expected_size).await
+    }
+
+    async fn get(&self, key: &Key) -> anyhow::Result<Option<Blob>> {
+        self.injector.inject(FaultOperation::BlobGet, None).await?;
+        self.inner.get(key).await
+    }
+
+    async fn get_range(&self, key: &Key, range: Range<u64>) -> anyhow::Result<Option<Blob>> {
+        self.injector.inject(FaultOperation::BlobGet, None).await?;
+        self.inner.get_range(key, range).await
+    }
+
+    async fn stat(&self, key: &Key) -> anyhow::Result<Option<BlobStat>> {
+        self.injector.inject(FaultOperation::BlobStat, None).await?;
+        self.inner.stat(key).await
+    }
+
+    async fn delete(&self, key: &Key) -> anyhow::Result<bool> {
+        self.injector.inject(FaultOperation::BlobDelete, None).await?;
+        self.inner.delete(key).await
+    }
+
+    async fn record_origin(&self, key: &Key, origin: &BlobOrigin) -> anyhow::Result<()> {
+        self.inner.record_origin(key, origin).await
+    }
+
+    async fn origins(&self) -> anyhow::Result<Vec<(Key, BlobOrigin)>> {
+        self.inner.origins().await
+    }
+
+    async fn last_access(&self, key: &Key) -> anyhow::Result<Option<SystemTime>> {
+        self.inner.last_access(key).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bytes::Bytes;
+    use crate::blob::transient_blob_storage::TransientBlobStorage;
+    use crate::util::fault_injection::{Fault, FaultRule};
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fails_only_injected_operation() {
+        let injector = Arc::new(FaultInjector::new());
+        let storage = FaultInjectingBlobStorage::new(TransientBlobStorage::new(), injector.clone());
+
+        let key = storage.insert(Box::pin(futures::stream::iter(vec![Ok(Bytes::from_static(b"abc"))]))).await.unwrap();
+
+        injector.add_rule(FaultRule::always(FaultOperation::BlobGet, Fault::Fail));
+        assert!(storage.get(&key).await.is_err());
+        assert_eq!(storage.stat(&key).await.unwrap().unwrap().size, 3);
+    }
+}
@@ -1,7 +1,12 @@
-use std::fmt::Debug;
-use std::path::PathBuf;
-use std::time::Duration;
+use std::fmt::{Debug, Display, Formatter};
+use std::io::{ErrorKind, SeekFrom};
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
 
+use anyhow::anyhow;
 use async_recursion::async_recursion;
 use async_trait::async_trait;
 use bytes::Bytes;
@@ -10,119 +15,568 @@ use futures_core::Stream;
 use serde::{Deserialize, Serialize};
 use sha1::{Digest, Sha1};
 use tokio::fs::{create_dir_all, metadata, OpenOptions, read_dir, remove_dir, remove_dir_all, remove_file, rename, try_exists};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::OnceCell;
 use tokio_util::io::ReaderStream;
-use tracing::{debug, error, trace, warn};
+use tracing::{debug, error, Span, trace, warn};
 use uuid::Uuid;
 
-use crate::blob::blob_storage::BlobStorage;
-use crate::util::blob::Blob;
+use crate::blob::blob_storage::{BlobOrigin, BlobStorage, BlobStream, GetManyStream};
+use crate::blob::insert_journal::{InsertJournal, InsertKind};
+use crate::util::blob::{Blob, BlobStat};
+use crate::util::prometheus_metrics::BLOB_STORAGE_BYTES;
+use crate::util::uuid_generator::{RandomUuidGenerator, UuidGenerator};
+use crate::util::validating_http_body::{Sha1HttpBodyValidator, ValidatingStream};
+
+/// the maximum number of blobs opened concurrently by 'get_many'
+const NUM_PARALLEL_OPENS: usize = 16;
 
 #[derive(Serialize, Deserialize)]
 struct BlobMetaData {
     sha1: [u8;20],
     md5: [u8;16],
+    /// recorded after the insert, and missing for blobs stored before origins were recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    origin: Option<BlobOrigin>,
 }
 
 
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Serialize)]
+pub enum OrphanKind {
+    /// a '.inserting' or '.deleting' folder left behind by an interrupted operation
+    TempFolder,
+    /// a blob that is not referenced by any metadata
+    UnreferencedBlob,
+}
+impl Display for OrphanKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrphanKind::TempFolder => write!(f, "temp folder"),
+            OrphanKind::UnreferencedBlob => write!(f, "blob"),
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Default, Serialize)]
+pub struct FsckReport {
+    pub orphans: Vec<FsckFinding>,
+    /// paths (relative to the storage root) of files or directories that do not match the
+    ///  storage layout and were therefore skipped
+    pub foreign_entries: Vec<String>,
+}
+impl FsckReport {
+    /// the size of the orphans that were deleted
+    pub fn reclaimed_bytes(&self) -> u64 {
+        self.orphans.iter()
+            .filter(|o| o.deleted)
+            .map(|o| o.size)
+            .sum()
+    }
+}
+
+/// Orphaned data found by 'fsck'
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+pub struct FsckFinding {
+    /// relative to the storage root
+    pub path: String,
+    pub kind: OrphanKind,
+    /// bytes in the orphaned directory, i.e. the space deleting it reclaims
+    pub size: u64,
+    /// false in 'log_only' mode
+    pub deleted: bool,
+}
+
 #[async_trait]
 pub trait IsReferencedChecker: Send + Sync + Debug {
     async fn is_referenced(&self, key: &Uuid) -> anyhow::Result<bool>;
 }
 
 
+/// Blobs are stored in directories named after their keys, nested in shard directories named
+///  after prefixes of the key to avoid huge directories. The scheme defines the lengths of these
+///  prefixes, e.g. [1, 3, 2, 2] stores the blob with key '12345678-...' in '1/234/56/78/12345678-...'.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ShardingScheme {
+    segment_lengths: Vec<usize>,
+}
+impl ShardingScheme {
+    /// Shard directory names are taken from the first (hex) group of the key, so the segment
+    ///  lengths must add up to no more than 8
+    pub fn new(segment_lengths: Vec<usize>) -> anyhow::Result<ShardingScheme> {
+        if segment_lengths.contains(&0) {
+            return Err(anyhow!("sharding segments must not be empty: {:?}", segment_lengths));
+        }
+        if segment_lengths.iter().sum::<usize>() > 8 {
+            return Err(anyhow!("sharding segments must not exceed 8 characters in total: {:?}", segment_lengths));
+        }
+        Ok(ShardingScheme { segment_lengths })
+    }
+
+    fn shard_names(&self, key: &Uuid) -> Vec<String> {
+        let key_string = key.as_hyphenated().to_string();
+        let mut offset = 0;
+        self.segment_lengths.iter()
+            .map(|len| {
+                let segment = key_string[offset..offset+len].to_string();
+                offset += len;
+                segment
+            })
+            .collect()
+    }
+
+    fn is_valid_shard_name(&self, level: usize, name: &str) -> bool {
+        name.len() == self.segment_lengths[level] && name.chars().all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase())
+    }
+}
+impl Default for ShardingScheme {
+    fn default() -> ShardingScheme {
+        // first level only single character to facilitate sharding
+        ShardingScheme { segment_lengths: vec![1, 3, 2, 2] }
+    }
+}
+
+/// Counters for monitoring, accumulated over the storage's lifetime
+#[derive(Default, Debug)]
+pub struct FsBlobStorageMetrics {
+    pub fsck_runs: AtomicU64,
+    /// files or directories in the storage root that do not match the expected layout
+    pub fsck_foreign_entries: AtomicU64,
+    pub fsck_orphans: AtomicU64,
+    /// bytes of orphaned data that fsck deleted
+    pub fsck_reclaimed_bytes: AtomicU64,
+    /// temp folders of inserts that were interrupted by a crash, removed on startup
+    pub recovered_inserts: AtomicU64,
+}
+impl FsBlobStorageMetrics {
+    pub fn snapshot(&self) -> FsBlobStorageMetricsSnapshot {
+        FsBlobStorageMetricsSnapshot {
+            fsck_runs: self.fsck_runs.load(Ordering::Relaxed),
+            fsck_foreign_entries: self.fsck_foreign_entries.load(Ordering::Relaxed),
+            fsck_orphans: self.fsck_orphans.load(Ordering::Relaxed),
+            fsck_reclaimed_bytes: self.fsck_reclaimed_bytes.load(Ordering::Relaxed),
+            recovered_inserts: self.recovered_inserts.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// see [FsBlobStorageMetrics]
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Serialize)]
+pub struct FsBlobStorageMetricsSnapshot {
+    pub fsck_runs: u64,
+    pub fsck_foreign_entries: u64,
+    pub fsck_orphans: u64,
+    pub fsck_reclaimed_bytes: u64,
+    pub recovered_inserts: u64,
+}
+
+/// name of the [InsertJournal] file in the storage root
+const INSERT_JOURNAL_FILE: &str = "insert-journal";
+
 #[derive(Debug)]
 pub struct FsBlobStorage {
     root: PathBuf,
+    key_generator: Arc<dyn UuidGenerator>,
+    sharding_scheme: ShardingScheme,
+    metrics: FsBlobStorageMetrics,
+    /// opened on first use, see [FsBlobStorage::recover]
+    journal: OnceCell<InsertJournal>,
+    verify_reads: bool,
 }
 impl FsBlobStorage {
+    pub fn new(root: PathBuf) -> FsBlobStorage {
+        FsBlobStorage {
+            root,
+            key_generator: Arc::new(RandomUuidGenerator::default()),
+            sharding_scheme: Default::default(),
+            metrics: Default::default(),
+            journal: OnceCell::new(),
+            verify_reads: false,
+        }
+    }
+
+    /// NB: changing the sharding scheme of existing storage makes all existing blobs inaccessible
+    pub fn with_sharding_scheme(self, sharding_scheme: ShardingScheme) -> FsBlobStorage {
+        FsBlobStorage {
+            sharding_scheme,
+            ..self
+        }
+    }
+
+    pub fn metrics(&self) -> &FsBlobStorageMetrics {
+        &self.metrics
+    }
+
+    pub fn with_key_generator(self, key_generator: Arc<dyn UuidGenerator>) -> FsBlobStorage {
+        FsBlobStorage {
+            key_generator,
+            ..self
+        }
+    }
+
+    /// Checks the data of blobs that are read in their entirety against the SHA1 checksum that was
+    ///  stored with them, failing the stream at its end if the data changed on disk (e.g. bit rot
+    ///  or a partial write). This costs hashing every read, and ranges are not checked.
+    pub fn with_read_verification(self, verify_reads: bool) -> FsBlobStorage {
+        FsBlobStorage {
+            verify_reads,
+            ..self
+        }
+    }
+
+    /// Starts a resumable upload, i.e. an insert whose data arrives in several chunks (possibly over
+    ///  several client connections). The returned session id is the key the blob will have once
+    ///  the upload is complete.
+    ///
+    /// Chunks are assembled in the blob's '.inserting' temp directory. Uploads survive restarts,
+    ///  but abandoned uploads are cleaned up by [FsBlobStorage::fsck] after its grace period, which
+    ///  limits how long an upload can be resumed.
+    pub async fn start_upload(&self) -> anyhow::Result<Uuid> {
+        let key = self.key_generator.new_uuid();
+        self.journal().await?.started(&key, InsertKind::Upload).await?;
+        let temp_directory_path = self.temp_directory_path_for_key(&key);
+        create_dir_all(&temp_directory_path).await?;
+
+        OpenOptions::new()
+            .create_new(true)
+            .write(true)
+            .open(temp_directory_path.join("data"))
+            .await?;
+
+        debug!("started upload {}", key.as_hyphenated());
+        Ok(key)
+    }
+
+    /// The number of bytes received so far, i.e. the offset at which an interrupted upload is resumed
+    pub async fn upload_offset(&self, session_id: &Uuid) -> anyhow::Result<u64> {
+        let data_path = self.temp_directory_path_for_key(session_id).join("data");
+        match metadata(&data_path).await {
+            Ok(m) => Ok(m.len()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Err(anyhow!("no upload in progress for {}", session_id.as_hyphenated())),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Appends a chunk at the given offset, which must be the number of bytes received so far
+    ///  (e.g. from a 'Content-Range' header). Returns the new offset.
+    pub async fn append_chunk(&self, session_id: &Uuid, offset: u64, data: impl Stream<Item=anyhow::Result<Bytes>> + Send) -> anyhow::Result<u64> {
+        let current_offset = self.upload_offset(session_id).await?;
+        if offset != current_offset {
+            return Err(anyhow!("chunk for upload {} starts at offset {}, expected {}", session_id.as_hyphenated(), offset, current_offset));
+        }
+
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(self.temp_directory_path_for_key(session_id).join("data"))
+            .await?;
+
+        let mut data = Box::pin(data);
+        let mut num_bytes = current_offset;
+        while let Some(bytes) = data.next().await {
+            let bytes = bytes?;
+            file.write_all(&bytes).await?;
+            num_bytes += bytes.len() as u64;
+        }
+        file.flush().await?;
+        Ok(num_bytes)
+    }
+
+    /// Completes an upload, making the blob available under the session id. If a checksum is
+    ///  given, the upload is only completed if the data matches it; otherwise it stays in progress
+    ///  so that the client can abort or retry.
+    pub async fn complete_upload(&self, session_id: &Uuid, expected_sha1: Option<[u8;20]>) -> anyhow::Result<Uuid> {
+        let temp_directory_path = self.temp_directory_path_for_key(session_id);
+
+        let file = OpenOptions::new()
+            .read(true)
+            .open(temp_directory_path.join("data"))
+            .await?;
+
+        // hash state is not persisted between chunks, so checksums are calculated from the
+        //  assembled file
+        let mut sha1_hasher: Sha1 = Default::default();
+        let mut md5_hasher = md5::Context::new();
+        let mut chunks = ReaderStream::new(file);
+        while let Some(bytes) = chunks.next().await {
+            let bytes = bytes?;
+            sha1_hasher.update(&bytes);
+            md5_hasher.consume(&bytes);
+        }
+
+        let metadata = BlobMetaData {
+            sha1: sha1_hasher.finalize().into(),
+            md5: md5_hasher.finalize().into(),
+            origin: None,
+        };
+        if let Some(expected_sha1) = expected_sha1 {
+            if expected_sha1 != metadata.sha1 {
+                return Err(anyhow!("checksum mismatch for upload {}", session_id.as_hyphenated()));
+            }
+        }
+
+        Self::write_blob_metadata(temp_directory_path.clone(), &metadata).await?;
+        rename(temp_directory_path, self.directory_path_for_key(session_id)).await?;
+        self.journal().await?.finished(session_id, true).await?;
+        debug!("completed upload {}", session_id.as_hyphenated());
+        Ok(*session_id)
+    }
+
+    pub async fn abort_upload(&self, session_id: &Uuid) -> anyhow::Result<()> {
+        remove_dir_all(self.temp_directory_path_for_key(session_id)).await?;
+        self.journal().await?.finished(session_id, false).await?;
+        Ok(())
+    }
+
+    /// Removes the temp folders of inserts that were interrupted by a crash, as recorded in the
+    ///  storage's [InsertJournal], and returns their number. Resumable uploads are kept.
+    ///
+    /// This happens on first use of the storage anyway, calling it on startup just makes sure
+    ///  that leftovers are cleaned up right away.
+    pub async fn recover(&self) -> anyhow::Result<u64> {
+        self.journal().await?;
+        Ok(self.metrics.recovered_inserts.load(Ordering::Relaxed))
+    }
+
+    async fn journal(&self) -> anyhow::Result<&InsertJournal> {
+        self.journal.get_or_try_init(|| async {
+            create_dir_all(&self.root).await?;
+            let (journal, interrupted) = InsertJournal::open(self.root.join(INSERT_JOURNAL_FILE)).await?;
+            for key in interrupted {
+                let temp_directory_path = self.temp_directory_path_for_key(&key);
+                match remove_dir_all(&temp_directory_path).await {
+                    Ok(()) => {
+                        warn!("removed interrupted insert {}", temp_directory_path.display());
+                        self.metrics.recovered_inserts.fetch_add(1, Ordering::Relaxed);
+                    }
+                    // the crash happened before the temp folder was created
+                    Err(e) if e.kind() == ErrorKind::NotFound => {}
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            Ok(journal)
+        }).await
+    }
 
     /// Check for (and optionally repair) orphaned data left by interrupted / crashed operations.
-    ///  'grace_period' is the minimum duration after which temporary temporary data is assumed
-    ///       to be orphaned.
+    ///  'grace_period' is the minimum duration after which unreferenced blobs, abandoned uploads
+    ///       and '.deleting' folders are assumed to be orphaned. '.inserting' folders that are
+    ///       not in flight according to the [InsertJournal] are orphans regardless of their age.
     ///  'log_only' determines whether the operation actually repairs (i.e. typically deletes)
     ///       data structures it considers orphaned, or just logs them
+    ///
+    /// Files and directories that do not match the sharding scheme's layout (e.g. 'lost+found')
+    ///  are never touched, but they are reported as foreign entries.
+    ///
+    /// Returns the orphaned and foreign data that was found, allowing callers to report it or to
+    ///  purge items individually (see [FsBlobStorage::purge_orphan]).
     #[tracing::instrument]
-    pub async fn fsck(&self, grace_period: &Duration, log_only: bool, is_referenced_checker: &impl IsReferencedChecker) -> anyhow::Result<()> {
-        Self::fsck_rec(0, &self.root, grace_period, log_only, is_referenced_checker).await?;
-        Ok(())
+    pub async fn fsck(&self, grace_period: &Duration, log_only: bool, is_referenced_checker: &impl IsReferencedChecker) -> anyhow::Result<FsckReport> {
+        self.metrics.fsck_runs.fetch_add(1, Ordering::Relaxed);
+
+        let mut report = FsckReport::default();
+        self.fsck_rec(0, &self.root, grace_period, log_only, is_referenced_checker, &mut report).await?;
+
+        self.metrics.fsck_foreign_entries.fetch_add(report.foreign_entries.len() as u64, Ordering::Relaxed);
+        self.metrics.fsck_orphans.fetch_add(report.orphans.len() as u64, Ordering::Relaxed);
+        self.metrics.fsck_reclaimed_bytes.fetch_add(report.reclaimed_bytes(), Ordering::Relaxed);
+        Ok(report)
     }
 
+    /// 'level' is the number of shard directories above 'directory': on the level equal to the
+    ///  number of sharding segments, there are blob directories.
     #[async_recursion]
-    async fn fsck_rec(level: usize, directory: &PathBuf, grace_period: &Duration, log_only: bool, is_referenced_checker: &impl IsReferencedChecker) -> anyhow::Result<bool> {
+    async fn fsck_rec(&self, level: usize, directory: &PathBuf, grace_period: &Duration, log_only: bool, is_referenced_checker: &impl IsReferencedChecker, report: &mut FsckReport) -> anyhow::Result<bool> {
         trace!("fsck'ing directory {}", directory.display());
 
-        if level > 7 {
-            warn!("more nested directories than expected in fsck - skipping {}", directory.display());
-            return Ok(true); // assume non-empty to be on the safe side
-        }
+        let is_blob_level = level == self.sharding_scheme.segment_lengths.len();
 
         let mut non_empty = false;
         let mut entries = read_dir(directory).await?;
-        loop {
-            if let Some(entry) = entries.next_entry().await? {
-                let path = entry.path();
-
-                let mut this_entry_remains = true;
-
-                if path.is_dir() {
-                    // completely ignore all folders that don't have an expired grace period -
-                    //  they may have initialization 'in flight'
-
-                    let expired_grace_period = Self::has_expired_grace_period(&path, grace_period).await;
-
-                    if expired_grace_period && Self::is_temp_folder(&path) {
-                        if log_only {
-                            warn!("fsck found orphaned temp folder - skipping because of 'log_only' mode: {}", path.display());
-                        }
-                        else {
-                            warn!("fsck found orphaned temp folder - deleting: {}", path.display());
-                            remove_dir_all(&path).await?;
-                            this_entry_remains = false;
-                        }
-                    }
-                    else {
-                        if let Some(file_name) = path.file_name() {
-                            if let Some(file_name) = file_name.to_str() {
-                                if let Ok(uuid) = Uuid::parse_str(file_name) {
-                                    if expired_grace_period && !is_referenced_checker.is_referenced(&uuid).await? {
-                                        if log_only {
-                                            warn!("fsck found orphaned blob - skipping because of 'log_only' mode: {}", path.display());
-                                        }
-                                        else {
-                                            warn!("fsck found orphaned blob - deleting: {}", path.display());
-                                            remove_dir_all(&path).await?;
-                                            this_entry_remains = false;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let file_type = entry.file_type().await?;
 
-                    if this_entry_remains {
-                        let has_content = Self::fsck_rec(level+1, &path, grace_period, log_only, is_referenced_checker).await?;
-                        if !has_content {
-                            debug!("fsck: removing empty folder {}", path.display());
-                            remove_dir(&path).await?;
-                            this_entry_remains = false;
-                        }
-                    }
+            if level == 0 && path.file_name().and_then(|n| n.to_str()) == Some(INSERT_JOURNAL_FILE) {
+                non_empty = true;
+                continue;
+            }
+
+            let expected_layout = file_type.is_dir() && match path.file_name().and_then(|n| n.to_str()) {
+                None => false,
+                Some(name) if is_blob_level => self.is_valid_blob_directory_name(directory, name),
+                Some(name) => self.sharding_scheme.is_valid_shard_name(level, name),
+            };
+
+            if !expected_layout {
+                // we don't know what this is, so we leave it alone
+                warn!("fsck found entry that does not match the storage layout - skipping: {}", path.display());
+                report.foreign_entries.push(self.relative_path(&path)?);
+                non_empty = true;
+                continue;
+            }
+
+            let mut this_entry_remains = true;
 
-                    non_empty = non_empty || this_entry_remains;
+            let orphan_kind = if is_blob_level {
+                self.orphan_kind(&path, grace_period, is_referenced_checker).await?
+            }
+            else {
+                None
+            };
+
+            if let Some(kind) = orphan_kind {
+                let size = Self::directory_size(&path).await?;
+                if log_only {
+                    warn!("fsck found orphaned {} - skipping because of 'log_only' mode: {}", kind, path.display());
                 }
                 else {
-                    non_empty = true;
+                    warn!("fsck found orphaned {} - deleting: {}", kind, path.display());
+                    remove_dir_all(&path).await?;
+                    this_entry_remains = false;
                 }
+
+                report.orphans.push(FsckFinding {
+                    path: self.relative_path(&path)?,
+                    kind,
+                    size,
+                    deleted: !this_entry_remains,
+                });
             }
-            else {
-                break;
+
+            if this_entry_remains && !is_blob_level {
+                let has_content = self.fsck_rec(level+1, &path, grace_period, log_only, is_referenced_checker, report).await?;
+                if !has_content && !log_only {
+                    debug!("fsck: removing empty folder {}", path.display());
+                    remove_dir(&path).await?;
+                    this_entry_remains = false;
+                }
             }
+
+            non_empty = non_empty || this_entry_remains;
         }
 
         Ok(non_empty)
     }
 
+    /// The keys of all completely inserted blobs, e.g. for creating an integrity manifest. Temp
+    ///  folders and entries that do not match the storage layout are skipped.
+    pub async fn keys(&self) -> anyhow::Result<Vec<Uuid>> {
+        let mut result = vec![];
+        self.keys_rec(0, &self.root, &mut result).await?;
+        result.sort();
+        Ok(result)
+    }
+
+    #[async_recursion]
+    async fn keys_rec(&self, level: usize, directory: &PathBuf, result: &mut Vec<Uuid>) -> anyhow::Result<()> {
+        let is_blob_level = level == self.sharding_scheme.segment_lengths.len();
+
+        let mut entries = read_dir(directory).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if !entry.file_type().await?.is_dir() {
+                continue;
+            }
+            let name = match path.file_name().and_then(|n| n.to_str()) {
+                Some(name) => name,
+                None => continue,
+            };
+
+            if is_blob_level {
+                if let Ok(key) = Uuid::parse_str(name) {
+                    if self.is_valid_blob_directory_name(directory, name) {
+                        result.push(key);
+                    }
+                }
+            }
+            else if self.sharding_scheme.is_valid_shard_name(level, name) {
+                self.keys_rec(level+1, &path, result).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Blob directories are named after the blob's key, with a suffix for temp folders. They must
+    ///  be located in the shard directory corresponding to their key.
+    fn is_valid_blob_directory_name(&self, directory: &Path, name: &str) -> bool {
+        let key_string = name.strip_suffix(".inserting")
+            .or_else(|| name.strip_suffix(".deleting"))
+            .unwrap_or(name);
+
+        match Uuid::parse_str(key_string) {
+            Ok(key) => key_string == key.as_hyphenated().to_string() && self.directory_path_for_key(&key).parent() == Some(directory),
+            Err(_) => false,
+        }
+    }
+
+    fn relative_path(&self, path: &Path) -> anyhow::Result<String> {
+        Ok(path.strip_prefix(&self.root)?.to_string_lossy().to_string())
+    }
+
+    /// Deletes a single item reported by 'fsck', re-checking that it is (still) orphaned.
+    ///  'relative_path' is the path as reported by fsck, i.e. relative to the storage root.
+    ///
+    /// Returns false if the item does not exist (any longer).
+    pub async fn purge_orphan(&self, relative_path: &str, grace_period: &Duration, is_referenced_checker: &impl IsReferencedChecker) -> anyhow::Result<bool> {
+        // reject anything that might escape the storage root
+        let relative_path = Path::new(relative_path);
+        if !relative_path.components().all(|c| matches!(c, Component::Normal(_))) {
+            return Err(anyhow!("not a valid relative path in blob storage: {}", relative_path.display()));
+        }
+
+        let path = self.root.join(relative_path);
+        if !try_exists(&path).await? {
+            return Ok(false);
+        }
+
+        let matches_layout = match (path.parent(), path.file_name().and_then(|n| n.to_str())) {
+            (Some(parent), Some(name)) => self.is_valid_blob_directory_name(parent, name),
+            _ => false,
+        };
+        if !matches_layout {
+            return Err(anyhow!("{} is not a blob directory", relative_path.display()));
+        }
+
+        match self.orphan_kind(&path, grace_period, is_referenced_checker).await? {
+            None => Err(anyhow!("{} is not orphaned (or within the grace period)", relative_path.display())),
+            Some(kind) => {
+                warn!("purging orphaned {}: {}", kind, path.display());
+                remove_dir_all(&path).await?;
+                Ok(true)
+            }
+        }
+    }
+
+    async fn orphan_kind(&self, path: &PathBuf, grace_period: &Duration, is_referenced_checker: &impl IsReferencedChecker) -> anyhow::Result<Option<OrphanKind>> {
+        if let Some(key) = path.file_name().and_then(|n| n.to_str()).and_then(|n| n.strip_suffix(".inserting")) {
+            let in_flight = match Uuid::parse_str(key) {
+                Ok(key) => self.journal().await?.in_flight(&key).await,
+                Err(_) => None,
+            };
+            return Ok(match in_flight {
+                Some(InsertKind::Insert) => None,
+                Some(InsertKind::Upload) if !Self::has_expired_grace_period(path, grace_period).await => None,
+                _ => Some(OrphanKind::TempFolder),
+            });
+        }
+
+        // '.deleting' folders and blobs may have a delete or a reference 'in flight'
+        if !Self::has_expired_grace_period(path, grace_period).await {
+            return Ok(None);
+        }
+        if Self::is_temp_folder(path) {
+            return Ok(Some(OrphanKind::TempFolder));
+        }
+
+        if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
+            if let Ok(uuid) = Uuid::parse_str(file_name) {
+                if !is_referenced_checker.is_referenced(&uuid).await? {
+                    return Ok(Some(OrphanKind::UnreferencedBlob));
+                }
+            }
+        }
+        Ok(None)
+    }
+
     fn is_temp_folder(path: &PathBuf) -> bool { //TODO unit test
         if let Some(file_name) = path.file_name() {
             if let Some(file_name) = file_name.to_str() {
@@ -153,29 +607,41 @@ impl FsBlobStorage {
         }
     }
 
-    fn directory_path_for_key(&self, key: &Uuid) -> PathBuf { //TODO unit test
-        let mut result = self.root.clone();
+    /// the total size of the files in a blob directory
+    async fn directory_size(path: &Path) -> anyhow::Result<u64> {
+        let mut result = 0;
+        let mut entries = read_dir(path).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let metadata = entry.metadata().await?;
+            if metadata.is_file() {
+                result += metadata.len();
+            }
+        }
+        Ok(result)
+    }
 
-        let key_string = key.as_hyphenated().to_string();
-        // first level only single character to facilitate sharding
-        result.push(&key_string[0..1]);
-        result.push(&key_string[1..4]);
-        result.push(&key_string[4..6]);
-        result.push(&key_string[6..8]);
-        result.push(key_string);
+    fn directory_path_for_key(&self, key: &Uuid) -> PathBuf {
+        let mut result = self.root.clone();
+        for shard_name in self.sharding_scheme.shard_names(key) {
+            result.push(shard_name);
+        }
+        result.push(key.as_hyphenated().to_string());
+        result
+    }
 
+    pub(crate) fn temp_directory_path_for_key(&self, key: &Uuid) -> PathBuf {
+        let mut result = self.directory_path_for_key(key);
+        result.pop();
+        result.push(format!("{}.inserting", key.as_hyphenated()));
         result
     }
 
     async fn do_insert(
         directory_path: PathBuf,
         data: impl Stream<Item=anyhow::Result<Bytes>> + Send
-    ) -> anyhow::Result<PathBuf> {
+    ) -> anyhow::Result<u64> {
         let mut data = Box::pin(data);
 
-        //TODO trace
-        //TODO performance / monitoring
-
         let mut data_path = directory_path.clone();
         data_path.push("data");
 
@@ -187,26 +653,28 @@ impl FsBlobStorage {
 
         let mut sha1_hasher: Sha1 = Default::default();
         let mut md5_hasher = md5::Context::new();
+        let mut num_bytes = 0u64;
 
-        loop {
-            match data.next().await {
-                Some(bytes) => {
-                    let bytes = bytes?;
-                    sha1_hasher.update(&bytes);
-                    md5_hasher.consume(&bytes);
-                    file.write(&bytes).await?;
-                }
-                None =>
-                    break,
-            }
+        while let Some(bytes) = data.next().await {
+            let bytes = bytes?;
+            sha1_hasher.update(&bytes);
+            md5_hasher.consume(&bytes);
+            file.write_all(&bytes).await?;
+            num_bytes += bytes.len() as u64;
         }
 
         let metadata = BlobMetaData {
             sha1: sha1_hasher.finalize().into(),
             md5: md5_hasher.compute().into(),
+            origin: None,
         };
+        Self::write_blob_metadata(directory_path, &metadata).await?;
+
+        Ok(num_bytes)
+    }
 
-        let metadata_json = serde_json::to_string(&metadata)?;
+    async fn write_blob_metadata(directory_path: PathBuf, metadata: &BlobMetaData) -> anyhow::Result<()> {
+        let metadata_json = serde_json::to_string(metadata)?;
 
         let mut metadata_file = directory_path;
         metadata_file.push("metadata.json");
@@ -220,39 +688,89 @@ impl FsBlobStorage {
         metadata_file.write_all(metadata_json.as_bytes())
             .await?;
 
-        Ok(data_path)
+        Ok(())
+    }
+
+    /// replaces the metadata file atomically, so that readers never see a partial file
+    async fn replace_blob_metadata(directory_path: PathBuf, metadata: &BlobMetaData) -> anyhow::Result<()> {
+        let metadata_json = serde_json::to_string(metadata)?;
+
+        let mut temp_path = directory_path.clone();
+        temp_path.push("metadata.json.tmp");
+        let mut metadata_path = directory_path;
+        metadata_path.push("metadata.json");
+
+        let mut temp_file = OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .open(&temp_path)
+            .await?;
+        temp_file.write_all(metadata_json.as_bytes())
+            .await?;
+        temp_file.sync_all()
+            .await?;
+        rename(&temp_path, &metadata_path)
+            .await?;
+        Ok(())
+    }
+
+    async fn read_blob_metadata(directory_path: PathBuf) -> anyhow::Result<BlobMetaData> {
+        let mut metadata_path = directory_path;
+        metadata_path.push("metadata.json");
+        let mut metadata_file = OpenOptions::new()
+            .read(true)
+            .open(metadata_path)
+            .await?;
+
+        let mut metadata_json = String::new();
+        metadata_file.read_to_string(&mut metadata_json)
+            .await?;
+
+        Ok(serde_json::from_str(&metadata_json)?)
     }
 }
 
 //TODO PathBuf.is_dir() etc. -> metadata -> async; leave sym links alone
 
 
+fn count_read_bytes(chunk: &Bytes) {
+    BLOB_STORAGE_BYTES.with_label_values(&["read"]).inc_by(chunk.len() as u64);
+}
+
 #[async_trait]
 impl BlobStorage<Uuid> for FsBlobStorage {
-    async fn insert(&self, data: impl Stream<Item=anyhow::Result<Bytes>> + Send) -> anyhow::Result<Uuid> {
-        //TODO performance / monitoring
-        let key = Uuid::new_v4();
+    #[tracing::instrument(level = "debug", skip_all, fields(key, bytes, duration_ms))]
+    async fn insert(&self, data: BlobStream<'_>) -> anyhow::Result<Uuid> {
+        let start = Instant::now();
+        let key = self.key_generator.new_uuid();
+        Span::current().record("key", key.as_hyphenated().to_string());
         let directory_path = self.directory_path_for_key(&key);
 
         trace!("inserting file blob - synthetic key is {}, directory is {}", key.as_hyphenated(), directory_path.display());
 
-        let mut temp_directory_path = directory_path.clone();
-        temp_directory_path.pop();
-        temp_directory_path.push(format!("{}.inserting", key.as_hyphenated()));
-
+        let journal = self.journal().await?;
+        journal.started(&key, InsertKind::Insert).await?;
+        let temp_directory_path = self.temp_directory_path_for_key(&key);
         create_dir_all(&temp_directory_path).await?;
 
-        let result = match Self::do_insert(directory_path.clone(), data).await {
-            Ok(_) => {
+        // data is written to the temp directory, which is then renamed atomically
+        let result = match Self::do_insert(temp_directory_path.clone(), data).await {
+            Ok(num_bytes) => {
                 rename(temp_directory_path, directory_path).await?;
+                BLOB_STORAGE_BYTES.with_label_values(&["written"]).inc_by(num_bytes);
+                journal.finished(&key, true).await?;
+                Span::current().record("bytes", num_bytes);
+                Span::current().record("duration_ms", start.elapsed().as_millis() as u64);
+                debug!("inserted file system blob");
                 Ok(key)
             }
             Err(e) => {
-                match self.delete(&key).await {
-                    Ok(_) => {}
-                    Err(e) => {
-                        error!("error cleaning up directory for key {} after failed attempt to insert: {}", &key, e);
-                    }
+                if let Err(e) = remove_dir_all(&temp_directory_path).await {
+                    error!("error cleaning up directory for key {} after failed attempt to insert: {}", &key, e);
+                }
+                else if let Err(e) = journal.finished(&key, false).await {
+                    error!("error journaling failed attempt to insert {}: {}", &key, e);
                 }
                 Err(e)
             }
@@ -260,6 +778,7 @@ impl BlobStorage<Uuid> for FsBlobStorage {
         result
     }
 
+    #[tracing::instrument(level = "debug", skip(self), fields(key = %key.as_hyphenated()))]
     async fn get(&self, key: &Uuid) -> anyhow::Result<Option<Blob>> {
         let directory_path = self.directory_path_for_key(key);
         trace!("getting file system blob {} from directory {}", key.as_hyphenated(), directory_path.display());
@@ -275,30 +794,152 @@ impl BlobStorage<Uuid> for FsBlobStorage {
             .read(true)
             .open(data_path)
             .await?;
+        let size = file.metadata().await?.len();
 
         let stream = ReaderStream::new(file)
-            .map_err(|e| e.into());
+            .inspect_ok(count_read_bytes);
 
-        let mut metadata_path = directory_path;
-        metadata_path.push("metadata.json");
-        let mut metadata_file = OpenOptions::new()
-            .read(true)
-            .open(metadata_path)
-            .await?;
+        let metadata = Self::read_blob_metadata(directory_path).await?;
 
-        let mut metadata_json = String::new();
-        metadata_file.read_to_string(&mut metadata_json)
-            .await?;
+        let data: BlobStream = if self.verify_reads {
+            Box::pin(ValidatingStream::new(stream, vec![Box::new(Sha1HttpBodyValidator::new(metadata.sha1))]))
+        }
+        else {
+            Box::pin(stream.map_err(|e| e.into()))
+        };
+
+        Ok(Some(Blob {
+            data,
+            md5: Some(metadata.md5),
+            sha1: Some(metadata.sha1),
+            size: Some(size),
+            last_modified: None,
+            upstream_headers: vec![],
+        }))
+    }
 
-        let metadata: BlobMetaData = serde_json::from_str(&&metadata_json)?;
+    #[tracing::instrument(level = "debug", skip(self), fields(key = %key.as_hyphenated()))]
+    async fn get_range(&self, key: &Uuid, range: Range<u64>) -> anyhow::Result<Option<Blob>> {
+        let directory_path = self.directory_path_for_key(key);
+
+        let mut data_path = directory_path.clone();
+        data_path.push("data");
+
+        let mut file = match OpenOptions::new().read(true).open(data_path).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        file.seek(SeekFrom::Start(range.start)).await?;
+        let len = range.end - range.start;
+
+        let stream = ReaderStream::new(file.take(len))
+            .inspect_ok(count_read_bytes)
+            .map_err(|e| e.into());
+
+        let metadata = Self::read_blob_metadata(directory_path).await?;
 
         Ok(Some(Blob {
             data: Box::pin(stream),
             md5: Some(metadata.md5),
             sha1: Some(metadata.sha1),
+            size: Some(len),
+            last_modified: None,
+            upstream_headers: vec![],
+        }))
+    }
+
+    #[tracing::instrument(level = "debug", skip(self), fields(key = %key.as_hyphenated()))]
+    async fn stat(&self, key: &Uuid) -> anyhow::Result<Option<BlobStat>> {
+        let directory_path = self.directory_path_for_key(key);
+        trace!("stat'ing file system blob {} in directory {}", key.as_hyphenated(), directory_path.display());
+
+        let mut data_path = directory_path.clone();
+        data_path.push("data");
+
+        let data_file_metadata = match metadata(&data_path).await {
+            Ok(m) => m,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let metadata = Self::read_blob_metadata(directory_path).await?;
+
+        Ok(Some(BlobStat {
+            size: data_file_metadata.len(),
+            md5: Some(metadata.md5),
+            sha1: Some(metadata.sha1),
+            // not all file systems support creation timestamps, but data files are never modified after insert
+            created: data_file_metadata.created()
+                .or_else(|_| data_file_metadata.modified())?,
+            last_modified: None,
+            upstream_headers: vec![],
         }))
     }
 
+    /// The data file's access time, or its modification time if that is later (e.g. on file systems
+    ///  mounted with 'noatime', where the access time stays at creation)
+    async fn last_access(&self, key: &Uuid) -> anyhow::Result<Option<SystemTime>> {
+        let mut data_path = self.directory_path_for_key(key);
+        data_path.push("data");
+
+        let data_file_metadata = match metadata(&data_path).await {
+            Ok(m) => m,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let last_access = match (data_file_metadata.accessed(), data_file_metadata.modified()) {
+            (Ok(accessed), Ok(modified)) => accessed.max(modified),
+            (Ok(t), Err(_)) | (Err(_), Ok(t)) => t,
+            (Err(_), Err(_)) => data_file_metadata.created()?,
+        };
+        Ok(Some(last_access))
+    }
+
+    #[tracing::instrument(level = "debug", skip(self, origin), fields(key = %key.as_hyphenated()))]
+    async fn record_origin(&self, key: &Uuid, origin: &BlobOrigin) -> anyhow::Result<()> {
+        let directory_path = self.directory_path_for_key(key);
+        let mut metadata = Self::read_blob_metadata(directory_path.clone()).await?;
+        metadata.origin = Some(origin.clone());
+        Self::replace_blob_metadata(directory_path, &metadata).await
+    }
+
+    /// Reads the metadata of all blobs, which is slow for large storage - this is meant for
+    ///  rebuilding a lost metadata store at startup
+    async fn origins(&self) -> anyhow::Result<Vec<(Uuid, BlobOrigin)>> {
+        let keys = self.keys().await?;
+        let origins = futures::stream::iter(keys)
+            .map(|key| async move {
+                let metadata = Self::read_blob_metadata(self.directory_path_for_key(&key)).await;
+                (key, metadata)
+            })
+            .buffer_unordered(NUM_PARALLEL_OPENS)
+            .collect::<Vec<_>>().await;
+
+        let mut result = vec![];
+        for (key, metadata) in origins {
+            match metadata {
+                Ok(BlobMetaData { origin: Some(origin), .. }) => result.push((key, origin)),
+                Ok(_) => {}
+                // e.g. deleted concurrently
+                Err(e) => warn!("skipping blob {} with unreadable metadata: {}", key, e),
+            }
+        }
+        Ok(result)
+    }
+
+    /// Opens files in parallel to avoid serial file system latency, returning results in the order
+    ///  of the given keys
+    fn get_many<'a>(&'a self, keys: &[Uuid]) -> GetManyStream<'a, Uuid> {
+        Box::pin(futures::stream::iter(keys.to_vec())
+            .map(move |key| async move {
+                let blob = self.get(&key).await;
+                (key, blob)
+            })
+            .buffered(NUM_PARALLEL_OPENS))
+    }
+
+    #[tracing::instrument(level = "debug", skip(self), fields(key = %key.as_hyphenated()))]
     async fn delete(&self, key: &Uuid) -> anyhow::Result<bool> {
         let directory_path = self.directory_path_for_key(key);
         trace!("deleting file system blob {} from directory {}", key.as_hyphenated(), directory_path.display());
@@ -336,3 +977,217 @@ impl BlobStorage<Uuid> for FsBlobStorage {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::time::SystemTime;
+
+    use rstest::*;
+
+    use crate::util::repo_error::RepoError;
+
+    use super::*;
+
+    fn temp_storage() -> FsBlobStorage {
+        let root = std::env::temp_dir().join(format!("arti-vault-test-{}", Uuid::new_v4().as_hyphenated()));
+        FsBlobStorage::new(root)
+    }
+
+    fn chunk(data: &'static [u8]) -> BlobStream<'static> {
+        Box::pin(futures::stream::iter(vec![Ok(Bytes::from_static(data))]))
+    }
+
+    #[tokio::test]
+    async fn test_insert() {
+        let storage = temp_storage();
+        let key = storage.insert(chunk(b"abc")).await.unwrap();
+
+        let stat = storage.stat(&key).await.unwrap().unwrap();
+        assert_eq!(stat.size, 3);
+        assert_eq!(stat.sha1, Some(<[u8;20]>::from(Sha1::digest(b"abc"))));
+
+        let _ = remove_dir_all(&storage.root).await;
+    }
+
+    #[tokio::test]
+    async fn test_read_verification() {
+        let storage = temp_storage().with_read_verification(true);
+        let key = storage.insert(chunk(b"abc")).await.unwrap();
+        let data = storage.get(&key).await.unwrap().unwrap().data.try_collect::<Vec<_>>().await.unwrap().concat();
+        assert_eq!(data, b"abc");
+
+        // bit rot
+        tokio::fs::write(storage.directory_path_for_key(&key).join("data"), b"abd").await.unwrap();
+        let result = storage.get(&key).await.unwrap().unwrap().data.try_collect::<Vec<_>>().await;
+        assert!(matches!(RepoError::of(&result.unwrap_err()), Some(RepoError::ChecksumMismatch(_))));
+
+        let _ = remove_dir_all(&storage.root).await;
+    }
+
+    #[tokio::test]
+    async fn test_get_range() {
+        let storage = temp_storage();
+        let key = storage.insert(chunk(b"abcdef")).await.unwrap();
+
+        let blob = storage.get_range(&key, 2..5).await.unwrap().unwrap();
+        assert_eq!(blob.size, Some(3));
+        assert_eq!(blob.sha1, Some(<[u8;20]>::from(Sha1::digest(b"abcdef"))));
+        let data = blob.data.try_collect::<Vec<_>>().await.unwrap().concat();
+        assert_eq!(data, b"cde");
+
+        assert!(storage.get_range(&Uuid::from_u64_pair(0, 1), 0..1).await.unwrap().is_none());
+
+        let _ = remove_dir_all(&storage.root).await;
+    }
+
+    #[tokio::test]
+    async fn test_resumable_upload() {
+        let storage = temp_storage();
+        let session_id = storage.start_upload().await.unwrap();
+
+        assert_eq!(storage.append_chunk(&session_id, 0, chunk(b"ab")).await.unwrap(), 2);
+        // e.g. a retry of a chunk that was already received
+        assert!(storage.append_chunk(&session_id, 0, chunk(b"ab")).await.is_err());
+        assert_eq!(storage.upload_offset(&session_id).await.unwrap(), 2);
+        assert_eq!(storage.append_chunk(&session_id, 2, chunk(b"c")).await.unwrap(), 3);
+
+        assert!(storage.stat(&session_id).await.unwrap().is_none());
+        assert!(storage.complete_upload(&session_id, Some([0u8;20])).await.is_err());
+
+        let expected_sha1 = Sha1::digest(b"abc").into();
+        assert_eq!(storage.complete_upload(&session_id, Some(expected_sha1)).await.unwrap(), session_id);
+        assert_eq!(storage.stat(&session_id).await.unwrap().unwrap().size, 3);
+        assert!(storage.upload_offset(&session_id).await.is_err());
+
+        let _ = remove_dir_all(&storage.root).await;
+    }
+
+    #[tokio::test]
+    async fn test_keys() {
+        let storage = temp_storage();
+        let a = storage.insert(chunk(b"a")).await.unwrap();
+        let b = storage.insert(chunk(b"b")).await.unwrap();
+        let inserting = storage.start_upload().await.unwrap();
+        create_dir_all(storage.root.join("lost+found")).await.unwrap();
+
+        let mut expected = vec![a, b];
+        expected.sort();
+        assert_eq!(storage.keys().await.unwrap(), expected);
+        assert!(!expected.contains(&inserting));
+
+        let _ = remove_dir_all(&storage.root).await;
+    }
+
+    #[tokio::test]
+    async fn test_origins() {
+        let storage = temp_storage();
+        let a = storage.insert(chunk(b"a")).await.unwrap();
+        let _b = storage.insert(chunk(b"b")).await.unwrap();
+        let origin = BlobOrigin {
+            path: "com/acme/a/1.0/a-1.0.jar".to_string(),
+            fetched: SystemTime::UNIX_EPOCH + Duration::from_secs(1000),
+            last_modified: SystemTime::UNIX_EPOCH + Duration::from_secs(500),
+        };
+        storage.record_origin(&a, &origin).await.unwrap();
+
+        assert_eq!(storage.origins().await.unwrap(), vec![(a, origin)]);
+        // recording the origin keeps the blob's checksums
+        assert_eq!(storage.stat(&a).await.unwrap().unwrap().sha1, Some(<[u8;20]>::from(Sha1::digest(b"a"))));
+
+        let _ = remove_dir_all(&storage.root).await;
+    }
+
+    #[derive(Debug)]
+    struct AllReferenced;
+    #[async_trait]
+    impl IsReferencedChecker for AllReferenced {
+        async fn is_referenced(&self, _key: &Uuid) -> anyhow::Result<bool> {
+            Ok(true)
+        }
+    }
+
+    #[derive(Debug)]
+    struct NoneReferenced;
+    #[async_trait]
+    impl IsReferencedChecker for NoneReferenced {
+        async fn is_referenced(&self, _key: &Uuid) -> anyhow::Result<bool> {
+            Ok(false)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fsck_reclaimed_bytes() {
+        let storage = temp_storage();
+        let key = storage.insert(chunk(b"abc")).await.unwrap();
+
+        let report = storage.fsck(&Duration::ZERO, true, &NoneReferenced).await.unwrap();
+        assert_eq!(report.orphans.len(), 1);
+        assert!(report.orphans[0].size >= 3);
+        assert_eq!(report.reclaimed_bytes(), 0);
+
+        let report = storage.fsck(&Duration::ZERO, false, &NoneReferenced).await.unwrap();
+        assert_eq!(storage.metrics().snapshot().fsck_reclaimed_bytes, report.orphans[0].size);
+        assert!(storage.stat(&key).await.unwrap().is_none());
+
+        let _ = remove_dir_all(&storage.root).await;
+    }
+
+    #[tokio::test]
+    async fn test_recover_interrupted_inserts() {
+        let storage = temp_storage();
+        let upload = storage.start_upload().await.unwrap();
+        let interrupted = Uuid::from_u64_pair(0, 1);
+        storage.journal().await.unwrap().started(&interrupted, InsertKind::Insert).await.unwrap();
+        create_dir_all(storage.temp_directory_path_for_key(&interrupted)).await.unwrap();
+
+        // simulate a restart
+        let storage = FsBlobStorage::new(storage.root.clone());
+        assert_eq!(storage.recover().await.unwrap(), 1);
+        assert!(!try_exists(storage.temp_directory_path_for_key(&interrupted)).await.unwrap());
+        assert_eq!(storage.upload_offset(&upload).await.unwrap(), 0);
+
+        // not in the journal, so it is an orphan without waiting for the grace period
+        let stray = Uuid::from_u64_pair(0, 2);
+        create_dir_all(storage.temp_directory_path_for_key(&stray)).await.unwrap();
+        let report = storage.fsck(&Duration::from_secs(3600), false, &AllReferenced).await.unwrap();
+        assert!(report.foreign_entries.is_empty());
+        assert_eq!(report.orphans.iter().map(|o| o.path.as_str()).collect::<Vec<_>>(), vec![storage.relative_path(&storage.temp_directory_path_for_key(&stray)).unwrap()]);
+        assert_eq!(storage.upload_offset(&upload).await.unwrap(), 0);
+
+        let _ = remove_dir_all(&storage.root).await;
+    }
+
+    #[test]
+    fn test_directory_path_for_key() {
+        let key = Uuid::parse_str("12345678-9abc-def0-1234-56789abcdef0").unwrap();
+
+        let storage = FsBlobStorage::new(PathBuf::from("/root"));
+        assert_eq!(storage.directory_path_for_key(&key), PathBuf::from("/root/1/234/56/78/12345678-9abc-def0-1234-56789abcdef0"));
+
+        let storage = FsBlobStorage::new(PathBuf::from("/root"))
+            .with_sharding_scheme(ShardingScheme::new(vec![2, 2]).unwrap());
+        assert_eq!(storage.directory_path_for_key(&key), PathBuf::from("/root/12/34/12345678-9abc-def0-1234-56789abcdef0"));
+    }
+
+    #[rstest]
+    #[case::blob("/root/1/234/56/78", "12345678-9abc-def0-1234-56789abcdef0", true)]
+    #[case::inserting("/root/1/234/56/78", "12345678-9abc-def0-1234-56789abcdef0.inserting", true)]
+    #[case::deleting("/root/1/234/56/78", "12345678-9abc-def0-1234-56789abcdef0.deleting", true)]
+    #[case::wrong_shard("/root/1/234/56/79", "12345678-9abc-def0-1234-56789abcdef0", false)]
+    #[case::unknown_suffix("/root/1/234/56/78", "12345678-9abc-def0-1234-56789abcdef0.bak", false)]
+    #[case::not_hyphenated("/root/1/234/56/78", "123456789abcdef0123456789abcdef0", false)]
+    #[case::foreign("/root/1/234/56/78", "lost+found", false)]
+    fn test_is_valid_blob_directory_name(#[case] directory: &str, #[case] name: &str, #[case] expected: bool) {
+        let storage = FsBlobStorage::new(PathBuf::from("/root"));
+        assert_eq!(storage.is_valid_blob_directory_name(Path::new(directory), name), expected);
+    }
+
+    #[rstest]
+    #[case::valid(vec![1, 3, 2, 2], true)]
+    #[case::no_sharding(vec![], true)]
+    #[case::too_long(vec![4, 5], false)]
+    #[case::empty_segment(vec![1, 0, 2], false)]
+    fn test_sharding_scheme_validation(#[case] segment_lengths: Vec<usize>, #[case] expected_valid: bool) {
+        assert_eq!(ShardingScheme::new(segment_lengths).is_ok(), expected_valid);
+    }
+}
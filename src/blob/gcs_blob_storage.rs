@@ -0,0 +1,139 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use hyper::{Body, Client, Method, Request, StatusCode};
+use hyper::header::{AUTHORIZATION, CONTENT_LENGTH, CONTENT_RANGE, LOCATION};
+use hyper_tls::HttpsConnector;
+use serde::Deserialize;
+
+use crate::blob::object_storage::{delete_object, get_object_stream, ObjectStore, ObjectStream, send, UploadPart};
+use crate::util::upstream_client::HttpsClient;
+
+const API_BASE: &str = "https://storage.googleapis.com";
+/// provides tokens for the service account of the GCE / GKE instance the server runs on
+const METADATA_TOKEN_URI: &str = "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+/// tokens from the metadata server are refreshed this long before they expire
+const TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+#[derive(Deserialize)]
+struct MetadataToken {
+    access_token: String,
+    expires_in: u64,
+}
+
+/// Google Cloud Storage, using one bucket and the JSON API. Objects are uploaded with resumable
+///  uploads, one request per part.
+///
+/// Requests are authorized with a fixed OAuth access token if one is configured (e.g. for tests),
+///  and with tokens from the instance metadata server otherwise.
+pub struct GcsObjectStore {
+    client: HttpsClient,
+    bucket: String,
+    access_token: Option<String>,
+    metadata_token: Mutex<Option<(String, Instant)>>,
+}
+impl GcsObjectStore {
+    pub fn new(bucket: &str, access_token: Option<String>) -> GcsObjectStore {
+        GcsObjectStore {
+            client: Client::builder().build(HttpsConnector::new()),
+            bucket: bucket.to_string(),
+            access_token,
+            metadata_token: Default::default(),
+        }
+    }
+
+    async fn authorization(&self) -> anyhow::Result<String> {
+        if let Some(token) = &self.access_token {
+            return Ok(format!("Bearer {}", token));
+        }
+        if let Some((token, valid_until)) = self.metadata_token.lock().unwrap().as_ref() {
+            if Instant::now() < *valid_until {
+                return Ok(format!("Bearer {}", token));
+            }
+        }
+
+        let request = Request::builder()
+            .uri(METADATA_TOKEN_URI)
+            .header("Metadata-Flavor", "Google")
+            .body(Body::empty())?;
+        let response = send(&self.client, request, &[]).await
+            .map_err(|e| anyhow!("error getting an access token from the instance metadata server: {}", e))?;
+        let token: MetadataToken = serde_json::from_slice(&hyper::body::to_bytes(response.into_body()).await?)?;
+
+        let valid_until = Instant::now() + Duration::from_secs(token.expires_in).saturating_sub(TOKEN_EXPIRY_MARGIN);
+        *self.metadata_token.lock().unwrap() = Some((token.access_token.clone(), valid_until));
+        Ok(format!("Bearer {}", token.access_token))
+    }
+
+    /// Object names are UUIDs with a suffix, so they need no percent encoding
+    fn object_uri(&self, name: &str) -> String {
+        format!("{}/storage/v1/b/{}/o/{}", API_BASE, self.bucket, name)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for GcsObjectStore {
+    async fn begin_upload(&self, name: &str) -> anyhow::Result<String> {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(format!("{}/upload/storage/v1/b/{}/o?uploadType=resumable&name={}", API_BASE, self.bucket, name))
+            .header(AUTHORIZATION, self.authorization().await?)
+            .header(CONTENT_LENGTH, 0)
+            .body(Body::empty())?;
+        let response = send(&self.client, request, &[]).await?;
+
+        // the session URI identifies the upload, and authorizes it as well
+        Ok(response.headers().get(LOCATION)
+            .and_then(|h| h.to_str().ok())
+            .ok_or_else(|| anyhow!("GCS returned no session URI for uploading {}", name))?
+            .to_string())
+    }
+
+    async fn upload_part(&self, name: &str, upload: &str, part: UploadPart) -> anyhow::Result<()> {
+        let total = match part.total_size {
+            Some(total_size) => total_size.to_string(),
+            None => "*".to_string(),
+        };
+        let content_range = match part.data.len() as u64 {
+            0 => format!("bytes */{}", total),
+            len => format!("bytes {}-{}/{}", part.offset, part.offset + len - 1, total),
+        };
+
+        let request = Request::builder()
+            .method(Method::PUT)
+            .uri(upload)
+            .header(CONTENT_LENGTH, part.data.len())
+            .header(CONTENT_RANGE, content_range)
+            .body(Body::from(part.data))?;
+        // 308 'Resume Incomplete' acknowledges a part that is not the last one
+        let response = send(&self.client, request, &[StatusCode::PERMANENT_REDIRECT]).await?;
+        if part.total_size.is_some() && !response.status().is_success() {
+            return Err(anyhow!("GCS did not complete the upload of {}: status {}", name, response.status()));
+        }
+        Ok(())
+    }
+
+    async fn finish_upload(&self, _name: &str, _upload: &str, _num_parts: usize, _total_size: u64) -> anyhow::Result<()> {
+        // the object is complete with its last part
+        Ok(())
+    }
+
+    async fn get(&self, name: &str) -> anyhow::Result<Option<ObjectStream>> {
+        let request = Request::builder()
+            .uri(format!("{}?alt=media", self.object_uri(name)))
+            .header(AUTHORIZATION, self.authorization().await?)
+            .body(Body::empty())?;
+        get_object_stream(&self.client, request).await
+    }
+
+    async fn delete(&self, name: &str) -> anyhow::Result<bool> {
+        let request = Request::builder()
+            .method(Method::DELETE)
+            .uri(self.object_uri(name))
+            .header(AUTHORIZATION, self.authorization().await?)
+            .body(Body::empty())?;
+        delete_object(&self.client, request).await
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+use tokio::fs::{File, OpenOptions, read_to_string, rename, write};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// the journal is rewritten with only the entries in flight once it has this many entries
+const COMPACTION_THRESHOLD: usize = 10_000;
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum InsertKind {
+    /// a regular insert, which completes or fails within a single call
+    Insert,
+    /// a resumable upload, which remains in flight across restarts until it is completed or aborted
+    Upload,
+}
+impl InsertKind {
+    fn tag(&self) -> &'static str {
+        match self {
+            InsertKind::Insert => "insert",
+            InsertKind::Upload => "upload",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct JournalState {
+    file: File,
+    in_flight: HashMap<Uuid, InsertKind>,
+    num_entries: usize,
+}
+
+/// Append-only record of the inserts into a blob storage, with a line per started and finished
+///  insert. After a crash, the inserts that were started but never finished are exactly the ones
+///  whose temp folders are left over.
+#[derive(Debug)]
+pub struct InsertJournal {
+    path: PathBuf,
+    state: Mutex<JournalState>,
+}
+impl InsertJournal {
+    /// Opens the journal a previous process left behind (if any). Returns the journal, with
+    ///  resumable uploads still in flight, and the regular inserts that were interrupted.
+    pub async fn open(path: PathBuf) -> anyhow::Result<(InsertJournal, Vec<Uuid>)> {
+        let mut in_flight = match read_to_string(&path).await {
+            Ok(s) => parse(&s)?,
+            Err(e) if e.kind() == ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut interrupted = in_flight.iter()
+            .filter(|(_, kind)| **kind == InsertKind::Insert)
+            .map(|(key, _)| *key)
+            .collect::<Vec<_>>();
+        interrupted.sort();
+        in_flight.retain(|_, kind| *kind == InsertKind::Upload);
+
+        let file = compact(&path, &in_flight).await?;
+        let journal = InsertJournal {
+            path,
+            state: Mutex::new(JournalState {
+                file,
+                num_entries: in_flight.len(),
+                in_flight,
+            }),
+        };
+        Ok((journal, interrupted))
+    }
+
+    /// NB: this must be called before the insert's temp folder is created
+    pub async fn started(&self, key: &Uuid, kind: InsertKind) -> anyhow::Result<()> {
+        let mut state = self.state.lock().await;
+        self.append(&mut state, &format!("{} {}\n", kind.tag(), key.as_hyphenated())).await?;
+        state.in_flight.insert(*key, kind);
+        Ok(())
+    }
+
+    /// Called when an insert was committed or aborted, i.e. its temp folder no longer exists
+    pub async fn finished(&self, key: &Uuid, committed: bool) -> anyhow::Result<()> {
+        let mut state = self.state.lock().await;
+        let tag = if committed { "commit" } else { "abort" };
+        self.append(&mut state, &format!("{} {}\n", tag, key.as_hyphenated())).await?;
+        state.in_flight.remove(key);
+        Ok(())
+    }
+
+    pub async fn in_flight(&self, key: &Uuid) -> Option<InsertKind> {
+        self.state.lock().await.in_flight.get(key).copied()
+    }
+
+    async fn append(&self, state: &mut JournalState, line: &str) -> anyhow::Result<()> {
+        if state.num_entries >= COMPACTION_THRESHOLD && state.num_entries >= 2 * state.in_flight.len() {
+            state.file = compact(&self.path, &state.in_flight).await?;
+            state.num_entries = state.in_flight.len();
+        }
+        state.file.write_all(line.as_bytes()).await?;
+        state.file.sync_data().await?;
+        state.num_entries += 1;
+        Ok(())
+    }
+}
+
+/// Atomically replaces the journal with one that has only the entries in flight, and opens it
+///  for appending
+async fn compact(path: &Path, in_flight: &HashMap<Uuid, InsertKind>) -> anyhow::Result<File> {
+    let content = in_flight.iter()
+        .map(|(key, kind)| format!("{} {}\n", kind.tag(), key.as_hyphenated()))
+        .collect::<String>();
+
+    let temp_path = path.with_extension("compacting");
+    write(&temp_path, content).await?;
+    rename(&temp_path, path).await?;
+
+    Ok(OpenOptions::new()
+        .append(true)
+        .open(path)
+        .await?)
+}
+
+/// A torn last line (from a crash while appending) is ignored, anything else that is malformed
+///  is an error
+fn parse(journal: &str) -> anyhow::Result<HashMap<Uuid, InsertKind>> {
+    let mut result = HashMap::new();
+    let lines = journal.lines().collect::<Vec<_>>();
+    for (i, line) in lines.iter().enumerate() {
+        let parsed = line.split_once(' ')
+            .and_then(|(tag, key)| Some((tag, Uuid::parse_str(key).ok()?)));
+        match parsed {
+            Some(("insert", key)) => { result.insert(key, InsertKind::Insert); }
+            Some(("upload", key)) => { result.insert(key, InsertKind::Upload); }
+            Some(("commit", key)) | Some(("abort", key)) => { result.remove(&key); }
+            _ if i == lines.len() - 1 && !journal.ends_with('\n') => {}
+            _ => return Err(anyhow!("malformed insert journal entry in line {}: {}", i+1, line)),
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn key(n: u64) -> Uuid {
+        Uuid::from_u64_pair(0, n)
+    }
+
+    #[test]
+    fn test_parse() {
+        let journal = format!("insert {}\nupload {}\ninsert {}\ncommit {}\nupload {}\nabort {}\ninsert 00000000-",
+            key(1), key(2), key(3), key(3), key(4), key(4));
+        let in_flight = parse(&journal).unwrap();
+        assert_eq!(in_flight.len(), 2);
+        assert_eq!(in_flight.get(&key(1)), Some(&InsertKind::Insert));
+        assert_eq!(in_flight.get(&key(2)), Some(&InsertKind::Upload));
+
+        assert!(parse("insert x\ncommit y\n").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reopen() {
+        let path = std::env::temp_dir().join(format!("arti-vault-journal-{}", Uuid::new_v4().as_hyphenated()));
+        {
+            let (journal, interrupted) = InsertJournal::open(path.clone()).await.unwrap();
+            assert!(interrupted.is_empty());
+            journal.started(&key(1), InsertKind::Insert).await.unwrap();
+            journal.started(&key(2), InsertKind::Upload).await.unwrap();
+            journal.started(&key(3), InsertKind::Insert).await.unwrap();
+            journal.finished(&key(3), true).await.unwrap();
+            assert_eq!(journal.in_flight(&key(1)).await, Some(InsertKind::Insert));
+            assert_eq!(journal.in_flight(&key(3)).await, None);
+        }
+
+        let (journal, interrupted) = InsertJournal::open(path.clone()).await.unwrap();
+        assert_eq!(interrupted, vec![key(1)]);
+        assert_eq!(journal.in_flight(&key(1)).await, None);
+        assert_eq!(journal.in_flight(&key(2)).await, Some(InsertKind::Upload));
+        assert_eq!(read_to_string(&path).await.unwrap(), format!("upload {}\n", key(2)));
+
+        let _ = tokio::fs::remove_file(&path).await;
+    }
+}
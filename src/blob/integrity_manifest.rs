@@ -0,0 +1,216 @@
+use std::collections::BTreeSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::anyhow;
+use futures::StreamExt;
+use hmac::{Hmac, KeyInit, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::blob::blob_storage::BlobStorage;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// A blob's size and content hash at the time a manifest was created
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub key: Uuid,
+    pub size: u64,
+    /// hex encoded
+    pub sha256: String,
+}
+
+/// A signed snapshot of all blobs in storage. Verifying it against the storage later detects bit
+///  rot or tampering between audits, while the signature detects tampering with the manifest itself.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct IntegrityManifest {
+    /// seconds since the epoch
+    pub created: u64,
+    pub entries: Vec<ManifestEntry>,
+    /// hex encoded HMAC-SHA256 of the other fields
+    pub signature: String,
+}
+
+/// the signed part of a manifest
+#[derive(Serialize)]
+struct SignedContent<'a> {
+    created: u64,
+    entries: &'a [ManifestEntry],
+}
+
+impl IntegrityManifest {
+    fn mac(created: u64, entries: &[ManifestEntry], signing_key: &[u8]) -> anyhow::Result<HmacSha256> {
+        let mut mac = <HmacSha256 as KeyInit>::new_from_slice(signing_key)
+            .map_err(|_| anyhow!("invalid manifest signing key"))?;
+        mac.update(&serde_json::to_vec(&SignedContent { created, entries })?);
+        Ok(mac)
+    }
+
+    fn sign(created: u64, entries: Vec<ManifestEntry>, signing_key: &[u8]) -> anyhow::Result<IntegrityManifest> {
+        let signature = hex::encode(Self::mac(created, &entries, signing_key)?.finalize().into_bytes());
+        Ok(IntegrityManifest {
+            created,
+            entries,
+            signature,
+        })
+    }
+
+    pub fn has_valid_signature(&self, signing_key: &[u8]) -> bool {
+        let signature = match hex::decode(&self.signature) {
+            Ok(s) => s,
+            Err(_) => return false,
+        };
+        match Self::mac(self.created, &self.entries, signing_key) {
+            Ok(mac) => mac.verify_slice(&signature).is_ok(),
+            Err(_) => false,
+        }
+    }
+}
+
+/// Differences between a manifest and the current contents of blob storage
+#[derive(Clone, Eq, PartialEq, Debug, Default, Serialize)]
+pub struct ManifestVerificationReport {
+    /// the number of blobs that match the manifest
+    pub verified: usize,
+    /// blobs in the manifest that no longer exist
+    pub missing: Vec<Uuid>,
+    /// blobs whose size or content differ from the manifest
+    pub modified: Vec<Uuid>,
+    /// blobs that were added since the manifest was created - this is expected in a live
+    ///  repository and listed for information only
+    pub added: Vec<Uuid>,
+}
+impl ManifestVerificationReport {
+    pub fn is_intact(&self) -> bool {
+        self.missing.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// Hashes the contents of each blob, returning None if a blob does not exist (any longer)
+async fn hash_blob(storage: &impl BlobStorage<Uuid>, key: &Uuid) -> anyhow::Result<Option<ManifestEntry>> {
+    let mut blob = match storage.get(key).await? {
+        Some(blob) => blob,
+        None => return Ok(None),
+    };
+
+    let mut hasher = Sha256::new();
+    let mut size = 0u64;
+    while let Some(chunk) = blob.data.next().await {
+        let chunk = chunk?;
+        size += chunk.len() as u64;
+        hasher.update(&chunk);
+    }
+
+    Ok(Some(ManifestEntry {
+        key: *key,
+        size,
+        sha256: hex::encode(hasher.finalize()),
+    }))
+}
+
+/// Reads all blobs in 'keys' and creates a signed manifest of them. Blobs that are deleted while
+///  the manifest is created are skipped.
+pub async fn create_manifest(storage: &impl BlobStorage<Uuid>, keys: &[Uuid], signing_key: &[u8]) -> anyhow::Result<IntegrityManifest> {
+    let created = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+    let mut entries = vec![];
+    for key in keys {
+        if let Some(entry) = hash_blob(storage, key).await? {
+            entries.push(entry);
+        }
+    }
+
+    info!("created integrity manifest for {} blobs", entries.len());
+    IntegrityManifest::sign(created, entries, signing_key)
+}
+
+/// Re-reads all blobs listed in a manifest and compares them to it. 'current_keys' are the keys
+///  of all blobs currently in storage, for reporting blobs that were added since.
+///
+/// Fails without reading any blobs if the manifest's signature is invalid.
+pub async fn verify_manifest(storage: &impl BlobStorage<Uuid>, manifest: &IntegrityManifest, current_keys: &[Uuid], signing_key: &[u8]) -> anyhow::Result<ManifestVerificationReport> {
+    if !manifest.has_valid_signature(signing_key) {
+        return Err(anyhow!("invalid manifest signature"));
+    }
+
+    let mut report = ManifestVerificationReport::default();
+    for expected in &manifest.entries {
+        match hash_blob(storage, &expected.key).await? {
+            None => {
+                warn!("blob {} from the integrity manifest is missing", expected.key.as_hyphenated());
+                report.missing.push(expected.key);
+            }
+            Some(actual) if actual != *expected => {
+                warn!("blob {} differs from the integrity manifest", expected.key.as_hyphenated());
+                report.modified.push(expected.key);
+            }
+            Some(_) => report.verified += 1,
+        }
+    }
+
+    let manifest_keys = manifest.entries.iter()
+        .map(|e| e.key)
+        .collect::<BTreeSet<_>>();
+    report.added = current_keys.iter()
+        .filter(|k| !manifest_keys.contains(k))
+        .cloned()
+        .collect();
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod test {
+    use bytes::Bytes;
+    use futures::stream;
+
+    use crate::blob::transient_blob_storage::TransientBlobStorage;
+
+    use super::*;
+
+    const SIGNING_KEY: &[u8] = b"secret";
+
+    async fn insert(storage: &TransientBlobStorage, data: &'static [u8]) -> Uuid {
+        storage.insert(Box::pin(stream::iter(vec![Ok(Bytes::from_static(data))]))).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_create_and_verify() {
+        let storage = TransientBlobStorage::new();
+        let a = insert(&storage, b"abc").await;
+        let b = insert(&storage, b"defg").await;
+
+        let manifest = create_manifest(&storage, &[a, b], SIGNING_KEY).await.unwrap();
+        assert_eq!(manifest.entries.len(), 2);
+        assert_eq!(manifest.entries[0].size, 3);
+        assert_eq!(manifest.entries[0].sha256, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+
+        let c = insert(&storage, b"h").await;
+        storage.delete(&b).await.unwrap();
+
+        let report = verify_manifest(&storage, &manifest, &[a, c], SIGNING_KEY).await.unwrap();
+        assert_eq!(report, ManifestVerificationReport {
+            verified: 1,
+            missing: vec![b],
+            modified: vec![],
+            added: vec![c],
+        });
+        assert!(!report.is_intact());
+    }
+
+    #[tokio::test]
+    async fn test_tampered_manifest() {
+        let storage = TransientBlobStorage::new();
+        let a = insert(&storage, b"abc").await;
+
+        let mut manifest = create_manifest(&storage, &[a], SIGNING_KEY).await.unwrap();
+        assert!(manifest.has_valid_signature(SIGNING_KEY));
+        assert!(!manifest.has_valid_signature(b"other key"));
+
+        manifest.entries[0].size = 4;
+        assert!(!manifest.has_valid_signature(SIGNING_KEY));
+        assert!(verify_manifest(&storage, &manifest, &[a], SIGNING_KEY).await.is_err());
+    }
+}
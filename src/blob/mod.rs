@@ -1,3 +1,18 @@
+#[cfg(feature = "azure-storage")]
+pub mod azure_blob_storage;
+pub mod blob_id;
 pub mod blob_storage;
+pub mod configured_blob_storage;
+#[cfg(feature = "fault-injection")]
+pub mod fault_injecting_blob_storage;
+#[cfg(feature = "fs-storage")]
 pub mod fs_blob_storage;
+#[cfg(feature = "gcs-storage")]
+pub mod gcs_blob_storage;
+#[cfg(feature = "fs-storage")]
+pub mod insert_journal;
+#[cfg(feature = "fs-storage")]
+pub mod integrity_manifest;
+#[cfg(any(feature = "azure-storage", feature = "gcs-storage"))]
+pub mod object_storage;
 pub mod transient_blob_storage;
@@ -0,0 +1,356 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use bytes::{Bytes, BytesMut};
+use futures::StreamExt;
+use futures_core::Stream;
+use hyper::{Body, Request, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use tracing::{debug, Span};
+use uuid::Uuid;
+
+use crate::blob::blob_storage::{BlobStorage, BlobStream};
+use crate::util::blob::{Blob, BlobStat};
+use crate::util::upstream_client::HttpsClient;
+use crate::util::uuid_generator::{RandomUuidGenerator, UuidGenerator};
+
+/// Data is uploaded in parts of this size, so that memory usage per upload is bounded regardless
+///  of the blob's size. Cloud APIs have requirements for part sizes (e.g. multiples of 256 KiB for
+///  GCS), which this satisfies.
+pub const PART_SIZE: usize = 8*1024*1024;
+
+pub type ObjectStream = Pin<Box<dyn Stream<Item=anyhow::Result<Bytes>> + Send + 'static>>;
+
+/// A chunk of an object's data, see [ObjectStore::upload_part]
+pub struct UploadPart {
+    /// parts are numbered consecutively starting at 0
+    pub index: usize,
+    /// the position of the part's first byte in the object
+    pub offset: u64,
+    pub data: Bytes,
+    /// the object's total size if this is the last part, None otherwise
+    pub total_size: Option<u64>,
+}
+
+/// The operations a cloud object store (Azure Blob Storage, Google Cloud Storage, ...) needs to
+///  provide for [ObjectBlobStorage]. Objects are uploaded in parts so that neither the store nor
+///  this server need to know an object's size in advance.
+#[async_trait]
+pub trait ObjectStore: Send + Sync {
+    /// Starts an upload, returning a handle that is passed to the other upload operations
+    async fn begin_upload(&self, name: &str) -> anyhow::Result<String>;
+
+    /// Parts are uploaded in order, and there is always at least one part. Only the last part
+    ///  can be shorter than [PART_SIZE], and it is empty only for empty objects.
+    async fn upload_part(&self, name: &str, upload: &str, part: UploadPart) -> anyhow::Result<()>;
+
+    /// Makes the object visible after all parts were uploaded. Uploads that fail before this are
+    ///  left for the store's own cleanup of incomplete uploads.
+    async fn finish_upload(&self, name: &str, upload: &str, num_parts: usize, total_size: u64) -> anyhow::Result<()>;
+
+    /// None if there is no such object
+    async fn get(&self, name: &str) -> anyhow::Result<Option<ObjectStream>>;
+
+    /// true if there was an object to delete
+    async fn delete(&self, name: &str) -> anyhow::Result<bool>;
+}
+
+/// Checksums are calculated while uploading, so they are stored in a separate small object
+///  written after the blob's data
+#[derive(Serialize, Deserialize)]
+struct ObjectMetadata {
+    size: u64,
+    sha1: [u8;20],
+    md5: [u8;16],
+    /// seconds since the epoch
+    created: u64,
+}
+
+/// Adapts an [ObjectStore] to [BlobStorage], streaming data in both directions. Each blob is
+///  stored as an object named after its key, and a '<key>.meta.json' object with its checksums.
+pub struct ObjectBlobStorage<O: ObjectStore> {
+    store: O,
+    key_generator: Arc<dyn UuidGenerator>,
+}
+impl<O: ObjectStore> ObjectBlobStorage<O> {
+    pub fn new(store: O) -> ObjectBlobStorage<O> {
+        ObjectBlobStorage {
+            store,
+            key_generator: Arc::new(RandomUuidGenerator::default()),
+        }
+    }
+
+    pub fn with_key_generator(self, key_generator: Arc<dyn UuidGenerator>) -> ObjectBlobStorage<O> {
+        ObjectBlobStorage {
+            key_generator,
+            ..self
+        }
+    }
+
+    fn data_name(key: &Uuid) -> String {
+        key.as_hyphenated().to_string()
+    }
+
+    fn metadata_name(key: &Uuid) -> String {
+        format!("{}.meta.json", key.as_hyphenated())
+    }
+
+    async fn upload(&self, name: &str, data: impl Stream<Item=anyhow::Result<Bytes>> + Send, mut on_chunk: impl FnMut(&Bytes) + Send) -> anyhow::Result<u64> {
+        let mut data = Box::pin(data);
+        let upload = self.store.begin_upload(name).await?;
+
+        let mut buffer = BytesMut::new();
+        let mut index = 0;
+        let mut offset = 0u64;
+        while let Some(chunk) = data.next().await {
+            let chunk = chunk?;
+            on_chunk(&chunk);
+            buffer.extend_from_slice(&chunk);
+
+            // a full part is held back until there is more data, so that the last part is never
+            //  empty unless the entire object is
+            while buffer.len() > PART_SIZE {
+                let part = buffer.split_to(PART_SIZE).freeze();
+                let len = part.len() as u64;
+                self.store.upload_part(name, &upload, UploadPart { index, offset, data: part, total_size: None }).await?;
+                index += 1;
+                offset += len;
+            }
+        }
+
+        let total_size = offset + buffer.len() as u64;
+        self.store.upload_part(name, &upload, UploadPart { index, offset, data: buffer.freeze(), total_size: Some(total_size) }).await?;
+        self.store.finish_upload(name, &upload, index + 1, total_size).await?;
+        Ok(total_size)
+    }
+
+    async fn get_metadata(&self, key: &Uuid) -> anyhow::Result<Option<ObjectMetadata>> {
+        let mut stream = match self.store.get(&Self::metadata_name(key)).await? {
+            None => return Ok(None),
+            Some(stream) => stream,
+        };
+        let mut json = BytesMut::new();
+        while let Some(chunk) = stream.next().await {
+            json.extend_from_slice(&chunk?);
+        }
+        Ok(Some(serde_json::from_slice(&json)?))
+    }
+}
+
+#[async_trait]
+impl<O: ObjectStore> BlobStorage<Uuid> for ObjectBlobStorage<O> {
+    #[tracing::instrument(level = "debug", skip_all, fields(key, bytes))]
+    async fn insert(&self, data: BlobStream<'_>) -> anyhow::Result<Uuid> {
+        let key = self.key_generator.new_uuid();
+        Span::current().record("key", key.as_hyphenated().to_string());
+
+        let mut sha1_hasher: Sha1 = Default::default();
+        let mut md5_hasher = md5::Context::new();
+        let size = self.upload(&Self::data_name(&key), data, |chunk| {
+            sha1_hasher.update(chunk);
+            md5_hasher.consume(chunk);
+        }).await?;
+        Span::current().record("bytes", size);
+
+        let metadata = ObjectMetadata {
+            size,
+            sha1: sha1_hasher.finalize().into(),
+            md5: md5_hasher.finalize().into(),
+            created: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        };
+        let json = Bytes::from(serde_json::to_vec(&metadata)?);
+        self.upload(&Self::metadata_name(&key), futures::stream::once(async { Ok(json) }), |_| {}).await?;
+        Ok(key)
+    }
+
+    #[tracing::instrument(level = "debug", skip(self), fields(key = %key.as_hyphenated()))]
+    async fn get(&self, key: &Uuid) -> anyhow::Result<Option<Blob>> {
+        // a blob without metadata is incomplete, e.g. from an interrupted insert
+        let metadata = match self.get_metadata(key).await? {
+            None => return Ok(None),
+            Some(metadata) => metadata,
+        };
+        match self.store.get(&Self::data_name(key)).await? {
+            None => Ok(None),
+            Some(data) => Ok(Some(Blob {
+                data,
+                md5: Some(metadata.md5),
+                sha1: Some(metadata.sha1),
+                size: Some(metadata.size),
+                last_modified: None,
+                upstream_headers: vec![],
+            })),
+        }
+    }
+
+    async fn stat(&self, key: &Uuid) -> anyhow::Result<Option<BlobStat>> {
+        Ok(self.get_metadata(key).await?.map(|metadata| BlobStat {
+            size: metadata.size,
+            md5: Some(metadata.md5),
+            sha1: Some(metadata.sha1),
+            created: UNIX_EPOCH + Duration::from_secs(metadata.created),
+            last_modified: None,
+            upstream_headers: vec![],
+        }))
+    }
+
+    #[tracing::instrument(level = "debug", skip(self), fields(key = %key.as_hyphenated()))]
+    async fn delete(&self, key: &Uuid) -> anyhow::Result<bool> {
+        // metadata first, so that an interrupted delete leaves an incomplete blob rather than
+        //  metadata without data
+        let existed = self.store.delete(&Self::metadata_name(key)).await?;
+        self.store.delete(&Self::data_name(key)).await?;
+        debug!("deleted blob: {}", existed);
+        Ok(existed)
+    }
+}
+
+/// Sends a request to a cloud store, failing for responses that are neither successful nor
+///  one of the 'accepted' status codes
+pub async fn send(client: &HttpsClient, request: Request<Body>, accepted: &[StatusCode]) -> anyhow::Result<Response<Body>> {
+    let description = format!("{} {}", request.method(), request.uri().path());
+    let response = client.request(request).await?;
+    let status = response.status();
+    if status.is_success() || accepted.contains(&status) {
+        return Ok(response);
+    }
+
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap_or_default();
+    Err(anyhow!("{} failed with status {}: {}", description, status, String::from_utf8_lossy(&body)))
+}
+
+/// The response body as an [ObjectStream], or None for a 404 response
+pub async fn get_object_stream(client: &HttpsClient, request: Request<Body>) -> anyhow::Result<Option<ObjectStream>> {
+    let response = send(client, request, &[StatusCode::NOT_FOUND]).await?;
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    Ok(Some(Box::pin(response.into_body().map(|chunk| chunk.map_err(anyhow::Error::from)))))
+}
+
+/// true if there was an object to delete
+pub async fn delete_object(client: &HttpsClient, request: Request<Body>) -> anyhow::Result<bool> {
+    let response = send(client, request, &[StatusCode::NOT_FOUND]).await?;
+    Ok(response.status() != StatusCode::NOT_FOUND)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// keeps uploaded parts in memory, checking that they follow the [ObjectStore] contract
+    #[derive(Default)]
+    struct InMemoryObjectStore {
+        uploads: Mutex<HashMap<String, Vec<u8>>>,
+        objects: Mutex<HashMap<String, Bytes>>,
+        part_sizes: Mutex<Vec<usize>>,
+    }
+
+    #[async_trait]
+    impl ObjectStore for InMemoryObjectStore {
+        async fn begin_upload(&self, name: &str) -> anyhow::Result<String> {
+            self.uploads.lock().unwrap().insert(name.to_string(), vec![]);
+            Ok(format!("upload-{}", name))
+        }
+
+        async fn upload_part(&self, name: &str, upload: &str, part: UploadPart) -> anyhow::Result<()> {
+            assert_eq!(upload, format!("upload-{}", name));
+            let mut uploads = self.uploads.lock().unwrap();
+            let data = uploads.get_mut(name).unwrap();
+            assert_eq!(part.offset, data.len() as u64);
+            match part.total_size {
+                None => assert_eq!(part.data.len(), PART_SIZE),
+                Some(total_size) => {
+                    assert!(!part.data.is_empty() || total_size == 0);
+                    assert_eq!(total_size, part.offset + part.data.len() as u64);
+                }
+            }
+            data.extend_from_slice(&part.data);
+            self.part_sizes.lock().unwrap().push(part.data.len());
+            Ok(())
+        }
+
+        async fn finish_upload(&self, name: &str, _upload: &str, _num_parts: usize, total_size: u64) -> anyhow::Result<()> {
+            let data = self.uploads.lock().unwrap().remove(name).unwrap();
+            assert_eq!(data.len() as u64, total_size);
+            self.objects.lock().unwrap().insert(name.to_string(), Bytes::from(data));
+            Ok(())
+        }
+
+        async fn get(&self, name: &str) -> anyhow::Result<Option<ObjectStream>> {
+            Ok(self.objects.lock().unwrap().get(name).cloned().map(|data| {
+                let stream: ObjectStream = Box::pin(futures::stream::once(async { Ok(data) }));
+                stream
+            }))
+        }
+
+        async fn delete(&self, name: &str) -> anyhow::Result<bool> {
+            Ok(self.objects.lock().unwrap().remove(name).is_some())
+        }
+    }
+
+    async fn read_all(blob: Blob) -> Vec<u8> {
+        let mut result = vec![];
+        let mut data = blob.data;
+        while let Some(chunk) = data.next().await {
+            result.extend_from_slice(&chunk.unwrap());
+        }
+        result
+    }
+
+    #[tokio::test]
+    async fn test_insert_get_delete() {
+        let storage = ObjectBlobStorage::new(InMemoryObjectStore::default());
+        let key = storage.insert(Box::pin(futures::stream::iter(vec![Ok(Bytes::from_static(b"ab")), Ok(Bytes::from_static(b"c"))]))).await.unwrap();
+
+        let stat = storage.stat(&key).await.unwrap().unwrap();
+        assert_eq!(stat.size, 3);
+        assert_eq!(stat.sha1, Some(<[u8;20]>::from(Sha1::digest(b"abc"))));
+        assert_eq!(stat.md5, Some(md5::compute(b"abc").0));
+
+        let blob = storage.get(&key).await.unwrap().unwrap();
+        assert_eq!(blob.size, Some(3));
+        assert_eq!(read_all(blob).await, b"abc");
+
+        assert!(storage.delete(&key).await.unwrap());
+        assert!(storage.get(&key).await.unwrap().is_none());
+        assert!(!storage.delete(&key).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_multipart_upload() {
+        let storage = ObjectBlobStorage::new(InMemoryObjectStore::default());
+        // exactly two parts, so the last one must not be empty
+        let data = vec![7u8; 2*PART_SIZE];
+        let chunks = data.chunks(100_000)
+            .map(|c| Ok(Bytes::copy_from_slice(c)))
+            .collect::<Vec<_>>();
+        let key = storage.insert(Box::pin(futures::stream::iter(chunks))).await.unwrap();
+
+        assert_eq!(storage.store.part_sizes.lock().unwrap()[..2], [PART_SIZE, PART_SIZE]);
+        assert_eq!(read_all(storage.get(&key).await.unwrap().unwrap()).await, data);
+    }
+
+    #[tokio::test]
+    async fn test_empty_blob() {
+        let storage = ObjectBlobStorage::new(InMemoryObjectStore::default());
+        let key = storage.insert(Box::pin(futures::stream::empty())).await.unwrap();
+        assert_eq!(storage.stat(&key).await.unwrap().unwrap().size, 0);
+        assert!(read_all(storage.get(&key).await.unwrap().unwrap()).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_insert_leaves_no_blob() {
+        let storage = ObjectBlobStorage::new(InMemoryObjectStore::default());
+        let data = futures::stream::iter(vec![Ok(Bytes::from_static(b"ab")), Err(anyhow!("connection reset"))]);
+        assert!(storage.insert(Box::pin(data)).await.is_err());
+        assert!(storage.store.objects.lock().unwrap().is_empty());
+    }
+}
@@ -1,78 +1,204 @@
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 
+use anyhow::anyhow;
 use async_trait::async_trait;
 use bytes::Bytes;
 use futures::StreamExt;
 use futures_core::Stream;
 use sha1::{Digest, Sha1};
+use tracing::{debug, Span};
 use uuid::Uuid;
 
-use crate::blob::blob_storage::BlobStorage;
-use crate::util::blob::Blob;
+use crate::blob::blob_storage::{BlobStorage, BlobStream, expect_size};
+use crate::util::blob::{Blob, BlobStat};
+use crate::util::uuid_generator::{RandomUuidGenerator, UuidGenerator};
+
+/// blobs with a known size are preallocated up to this size
+const MAX_PREALLOCATION: usize = 16*1024*1024;
 
 /// in-memory blob storage, neither optimized nor particularly robust - for testing purposes
+///
+/// Memory usage can be bounded by limiting the total number of bytes and / or the number of
+///  entries. When a limit is exceeded, the least recently used blobs are evicted. Inserting a
+///  single blob that exceeds the byte limit on its own fails.
 pub struct TransientBlobStorage {
-    data: Arc<Mutex<HashMap<Uuid, (Vec<u8>, [u8;16], [u8;20])>>>,
+    max_total_bytes: Option<usize>,
+    max_num_entries: Option<usize>,
+    key_generator: Arc<dyn UuidGenerator>,
+    data: Arc<Mutex<TransientData>>,
 }
-impl TransientBlobStorage {
-    pub fn new() -> TransientBlobStorage {
+
+impl Default for TransientBlobStorage {
+    fn default() -> TransientBlobStorage {
         TransientBlobStorage {
+            max_total_bytes: None,
+            max_num_entries: None,
+            key_generator: Arc::new(RandomUuidGenerator::default()),
             data: Default::default(),
         }
     }
 }
 
-#[async_trait]
-impl BlobStorage<Uuid> for TransientBlobStorage {
-    async fn insert(&self, data: impl Stream<Item=anyhow::Result<Bytes>> + Send) -> anyhow::Result<Uuid> {
+#[derive(Default)]
+struct TransientData {
+    blobs: HashMap<Uuid, TransientBlob>,
+    total_bytes: usize,
+    /// logical clock for tracking the order of accesses to blobs
+    access_counter: u64,
+}
+impl TransientData {
+    fn next_access(&mut self) -> u64 {
+        self.access_counter += 1;
+        self.access_counter
+    }
+
+    fn evict_least_recently_used(&mut self) {
+        let lru_key = self.blobs.iter()
+            .min_by_key(|(_, blob)| blob.last_access)
+            .map(|(key, _)| *key);
+
+        if let Some(key) = lru_key {
+            debug!("evicting transient blob {}", key.as_hyphenated());
+            if let Some(blob) = self.blobs.remove(&key) {
+                self.total_bytes -= blob.data.len();
+            }
+        }
+    }
+}
+
+struct TransientBlob {
+    data: Bytes,
+    md5: [u8;16],
+    sha1: [u8;20],
+    created: SystemTime,
+    last_access: u64,
+}
+
+impl TransientBlobStorage {
+    pub fn new() -> TransientBlobStorage {
+        Default::default()
+    }
+
+    /// 'None' means 'unlimited' for either limit
+    pub fn with_limits(max_total_bytes: Option<usize>, max_num_entries: Option<usize>) -> TransientBlobStorage {
+        TransientBlobStorage {
+            max_total_bytes,
+            max_num_entries,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_key_generator(self, key_generator: Arc<dyn UuidGenerator>) -> TransientBlobStorage {
+        TransientBlobStorage {
+            key_generator,
+            ..self
+        }
+    }
+
+    fn exceeds_limits(&self, data: &TransientData, additional_bytes: usize) -> bool {
+        if let Some(max_total_bytes) = self.max_total_bytes {
+            if data.total_bytes + additional_bytes > max_total_bytes {
+                return true;
+            }
+        }
+        if let Some(max_num_entries) = self.max_num_entries {
+            if data.blobs.len() + 1 > max_num_entries {
+                return true;
+            }
+        }
+        false
+    }
+
+    #[tracing::instrument(level = "debug", skip_all, fields(key, bytes))]
+    async fn do_insert(&self, data: impl Stream<Item=anyhow::Result<Bytes>> + Send, capacity: usize) -> anyhow::Result<Uuid> {
         let mut data = Box::pin(data);
 
-        let key = Uuid::new_v4();
+        let key = self.key_generator.new_uuid();
+        Span::current().record("key", key.as_hyphenated().to_string());
 
-        let mut data_vec = Vec::new();
+        let mut data_vec = Vec::with_capacity(capacity);
         let mut sha1_hasher: Sha1 = Default::default();
         let mut md5_hasher = md5::Context::new();
 
-        loop {
-            match data.next().await {
-                Some(bytes) => {
-                    let bytes = bytes?;
-                    sha1_hasher.update(&bytes);
-                    md5_hasher.consume(&bytes);
-                    data_vec.extend_from_slice(&bytes);
+        while let Some(bytes) = data.next().await {
+            let bytes = bytes?;
+            sha1_hasher.update(&bytes);
+            md5_hasher.consume(&bytes);
+            data_vec.extend_from_slice(&bytes);
+
+            // fail early rather than materializing a blob we can not store anyway
+            if let Some(max_total_bytes) = self.max_total_bytes {
+                if data_vec.len() > max_total_bytes {
+                    return Err(anyhow!("blob exceeds the transient storage's limit of {} bytes", max_total_bytes));
                 }
-                None =>
-                    break,
             }
         }
 
-        self.data.lock()
-            .unwrap()
-            .insert(
-                key.clone(),
-                (
-                    data_vec,
-                    md5_hasher.compute().into(),
-                    sha1_hasher.finalize().into(),
-                )
-            );
+        let mut lock = self.data.lock().unwrap();
+        while !lock.blobs.is_empty() && self.exceeds_limits(&lock, data_vec.len()) {
+            lock.evict_least_recently_used();
+        }
+
+        Span::current().record("bytes", data_vec.len());
+        let last_access = lock.next_access();
+        lock.total_bytes += data_vec.len();
+        lock.blobs.insert(
+            key,
+            TransientBlob {
+                data: Bytes::from(data_vec),
+                md5: md5_hasher.compute().into(),
+                sha1: sha1_hasher.finalize().into(),
+                created: SystemTime::now(),
+                last_access,
+            }
+        );
 
         Ok(key)
     }
+}
+
+#[async_trait]
+impl BlobStorage<Uuid> for TransientBlobStorage {
+    async fn insert(&self, data: BlobStream<'_>) -> anyhow::Result<Uuid> {
+        self.do_insert(data, 0).await
+    }
 
+    async fn insert_with_size(&self, data: BlobStream<'_>, expected_size: Option<u64>) -> anyhow::Result<Uuid> {
+        let expected_size = match expected_size {
+            None => return self.do_insert(data, 0).await,
+            Some(expected_size) => expected_size,
+        };
+        if let Some(max_total_bytes) = self.max_total_bytes {
+            if expected_size > max_total_bytes as u64 {
+                return Err(anyhow!("blob of {} bytes exceeds the transient storage's limit of {} bytes", expected_size, max_total_bytes));
+            }
+        }
+        // the expected size may come from a client, so it is not trusted with unbounded allocations
+        let capacity = expected_size.min(MAX_PREALLOCATION as u64) as usize;
+        self.do_insert(expect_size(data, expected_size), capacity).await
+    }
+
+    #[tracing::instrument(level = "debug", skip(self), fields(key = %key.as_hyphenated()))]
     async fn get(&self, key: &Uuid) -> anyhow::Result<Option<Blob>> {
-        let lock = self.data.lock().unwrap();
+        let mut lock = self.data.lock().unwrap();
+        let access = lock.next_access();
+
+        if let Some(blob) = lock.blobs.get_mut(key) {
+            blob.last_access = access;
 
-        if let Some((data, md5, sha1)) = lock.get(key) {
-            let data: Vec<u8> = data.clone();
-            let bytes = Bytes::from(data);
+            let bytes = blob.data.clone();
+            let size = bytes.len() as u64;
             let stream = futures::stream::once(async move { Ok::<_, anyhow::Error>(bytes) });
 
             Ok(Some(Blob {
                 data: Box::pin(stream),
-                md5: Some(md5.clone()),
-                sha1: Some(sha1.clone()),
+                md5: Some(blob.md5),
+                sha1: Some(blob.sha1),
+                size: Some(size),
+                last_modified: None,
+                upstream_headers: vec![],
             }))
         }
         else {
@@ -80,10 +206,92 @@ impl BlobStorage<Uuid> for TransientBlobStorage {
         }
     }
 
-    async fn delete(&self, key: &Uuid) -> anyhow::Result<bool> {
+    #[tracing::instrument(level = "debug", skip(self), fields(key = %key.as_hyphenated()))]
+    async fn stat(&self, key: &Uuid) -> anyhow::Result<Option<BlobStat>> {
         Ok(self.data.lock().unwrap()
-            .remove(key)
-            .is_some()
+            .blobs
+            .get(key)
+            .map(|blob| BlobStat {
+                size: blob.data.len() as u64,
+                md5: Some(blob.md5),
+                sha1: Some(blob.sha1),
+                created: blob.created,
+                last_modified: None,
+                upstream_headers: vec![],
+            })
         )
     }
-}
\ No newline at end of file
+
+    #[tracing::instrument(level = "debug", skip(self), fields(key = %key.as_hyphenated()))]
+    async fn delete(&self, key: &Uuid) -> anyhow::Result<bool> {
+        let mut lock = self.data.lock().unwrap();
+        match lock.blobs.remove(key) {
+            Some(blob) => {
+                lock.total_bytes -= blob.data.len();
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn data(len: usize) -> BlobStream<'static> {
+        Box::pin(futures::stream::once(async move { Ok(Bytes::from(vec![0u8; len])) }))
+    }
+
+    #[tokio::test]
+    async fn test_entry_limit_evicts_least_recently_used() {
+        let storage = TransientBlobStorage::with_limits(None, Some(2));
+
+        let key1 = storage.insert(data(1)).await.unwrap();
+        let key2 = storage.insert(data(1)).await.unwrap();
+        // accessing key1 makes key2 the least recently used
+        assert!(storage.get(&key1).await.unwrap().is_some());
+
+        let key3 = storage.insert(data(1)).await.unwrap();
+
+        assert!(storage.get(&key1).await.unwrap().is_some());
+        assert!(storage.get(&key2).await.unwrap().is_none());
+        assert!(storage.get(&key3).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_byte_limit_evicts_until_blob_fits() {
+        let storage = TransientBlobStorage::with_limits(Some(10), None);
+
+        let key1 = storage.insert(data(4)).await.unwrap();
+        let key2 = storage.insert(data(4)).await.unwrap();
+        let key3 = storage.insert(data(8)).await.unwrap();
+
+        assert!(storage.get(&key1).await.unwrap().is_none());
+        assert!(storage.get(&key2).await.unwrap().is_none());
+        assert!(storage.get(&key3).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_blob_exceeding_byte_limit_fails() {
+        let storage = TransientBlobStorage::with_limits(Some(10), None);
+
+        let key1 = storage.insert(data(4)).await.unwrap();
+        assert!(storage.insert(data(11)).await.is_err());
+
+        // a failed insert leaves existing blobs alone
+        assert!(storage.get(&key1).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_insert_with_size() {
+        let storage = TransientBlobStorage::with_limits(Some(10), None);
+
+        let key = storage.insert_with_size(data(4), Some(4)).await.unwrap();
+        assert_eq!(storage.stat(&key).await.unwrap().unwrap().size, 4);
+        assert!(storage.insert_with_size(data(4), Some(5)).await.is_err());
+
+        // rejected without waiting for data
+        assert!(storage.insert_with_size(Box::pin(futures::stream::pending()), Some(11)).await.is_err());
+    }
+}
@@ -0,0 +1,218 @@
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+use hyper::{Body, Client, Method, Request, StatusCode, Uri};
+use hyper::body::to_bytes;
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE};
+use hyper::http::request;
+use hyper_tls::HttpsConnector;
+
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+use crate::maven::metadata_backup::{MetadataBackup, RestoreReport};
+use crate::maven::prefetch_plan::{AccessLogFormat, PrefetchPlanBuilder};
+
+const DEFAULT_SERVER: &str = "http://127.0.0.1:3000";
+const DEFAULT_WARM_UP_LIMIT: usize = 1000;
+
+/// Subcommands operating on a running server through its admin API
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum CliCommand {
+    /// writes a metadata backup to a file
+    Backup {
+        server: String,
+        file: PathBuf,
+    },
+    /// restores a metadata backup from a file
+    Restore {
+        server: String,
+        file: PathBuf,
+        skip_missing: bool,
+    },
+    /// builds a prefetch plan from an access log, and has the server fetch its artifacts
+    WarmUp {
+        server: String,
+        file: PathBuf,
+        format: AccessLogFormat,
+        /// number of artifacts in the plan
+        limit: usize,
+        /// print the plan instead of sending it to the server
+        dry_run: bool,
+    },
+}
+
+pub const USAGE: &str = "usage:
+  arti-vault                                                 run the server
+  arti-vault --dev                                           run with throwaway storage and example artifacts
+  arti-vault backup <file> [--server <url>]                  write a metadata backup to <file>
+  arti-vault restore <file> [--skip-missing] [--server <url>] restore a metadata backup from <file>
+  arti-vault warm-up <access log> [--format combined|nexus|artifactory] [--limit <n>] [--dry-run] [--server <url>]
+                                                             prefetch the most requested artifacts of <access log>
+
+commands authenticate with the token in ARTI_VAULT_TOKEN if it is set";
+
+/// Returns None if the arguments (without the program name) do not start with a subcommand
+pub fn parse_command(args: &[String]) -> anyhow::Result<Option<CliCommand>> {
+    let (command, args) = match args.split_first() {
+        None => return Ok(None),
+        Some((command, args)) => (command.as_str(), args),
+    };
+
+    let mut file = None;
+    let mut server = DEFAULT_SERVER.to_string();
+    let mut skip_missing = false;
+    let mut format = AccessLogFormat::Combined;
+    let mut limit = DEFAULT_WARM_UP_LIMIT;
+    let mut dry_run = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--server" => server = args.next().ok_or_else(|| anyhow!("--server requires a URL"))?.clone(),
+            "--skip-missing" if command == "restore" => skip_missing = true,
+            "--format" if command == "warm-up" => format = args.next().ok_or_else(|| anyhow!("--format requires a log format"))?.parse()?,
+            "--limit" if command == "warm-up" => limit = args.next().ok_or_else(|| anyhow!("--limit requires a number"))?.parse()?,
+            "--dry-run" if command == "warm-up" => dry_run = true,
+            a if a.starts_with("--") => return Err(anyhow!("unknown option {}", a)),
+            a if file.is_none() => file = Some(PathBuf::from(a)),
+            a => return Err(anyhow!("unexpected argument {}", a)),
+        }
+    }
+    let server = server.trim_end_matches('/').to_string();
+
+    match command {
+        "backup" => Ok(Some(CliCommand::Backup {
+            server,
+            file: file.ok_or_else(|| anyhow!("backup requires a file name"))?,
+        })),
+        "restore" => Ok(Some(CliCommand::Restore {
+            server,
+            file: file.ok_or_else(|| anyhow!("restore requires a file name"))?,
+            skip_missing,
+        })),
+        "warm-up" => Ok(Some(CliCommand::WarmUp {
+            server,
+            file: file.ok_or_else(|| anyhow!("warm-up requires an access log file"))?,
+            format,
+            limit,
+            dry_run,
+        })),
+        other => Err(anyhow!("unknown command {}", other)),
+    }
+}
+
+/// A request to the admin API, with the token from 'ARTI_VAULT_TOKEN' if the server authenticates
+///  requests
+fn admin_request(method: Method, uri: String) -> anyhow::Result<request::Builder> {
+    let mut request = Request::builder()
+        .method(method)
+        .uri(Uri::try_from(uri)?);
+    if let Ok(token) = std::env::var("ARTI_VAULT_TOKEN") {
+        request = request.header(AUTHORIZATION, format!("Bearer {}", token));
+    }
+    Ok(request)
+}
+
+pub async fn run_command(command: CliCommand) -> anyhow::Result<()> {
+    let client = Client::builder().build::<_, Body>(HttpsConnector::new());
+
+    match command {
+        CliCommand::Backup { server, file } => {
+            let request = admin_request(Method::GET, format!("{}/api/v1/admin/metadata-backup", server))?
+                .body(Body::empty())?;
+            let response = client.request(request).await?;
+            let status = response.status();
+            let body = to_bytes(response.into_body()).await?;
+            if status != StatusCode::OK {
+                return Err(anyhow!("server returned {}: {}", status, String::from_utf8_lossy(&body)));
+            }
+
+            // parsing before writing ensures that only complete backups are written
+            let backup: MetadataBackup = serde_json::from_slice(&body)?;
+            tokio::fs::write(&file, serde_json::to_vec_pretty(&backup)?).await?;
+            println!("wrote backup of {} artifacts to {:?}", backup.metadata.artifacts.len(), file);
+        }
+        CliCommand::Restore { server, file, skip_missing } => {
+            let backup: MetadataBackup = serde_json::from_slice(&tokio::fs::read(&file).await?)?;
+
+            let request = admin_request(Method::PUT, format!("{}/api/v1/admin/metadata-backup?skip_missing={}", server, skip_missing))?
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(serde_json::to_vec(&backup)?))?;
+            let response = client.request(request).await?;
+            let status = response.status();
+            let body = to_bytes(response.into_body()).await?;
+            if status != StatusCode::OK {
+                return Err(anyhow!("restore failed with {}: {}", status, String::from_utf8_lossy(&body)));
+            }
+
+            let report: RestoreReport = serde_json::from_slice(&body)?;
+            println!("restored {} artifacts", report.restored_artifacts);
+            for (path, reason) in &report.skipped_artifacts {
+                println!("skipped {}: {}", path, reason);
+            }
+        }
+        CliCommand::WarmUp { server, file, format, limit, dry_run } => {
+            // logs are read line by line since they can be much larger than memory
+            let mut builder = PrefetchPlanBuilder::new(format);
+            let mut lines = BufReader::new(tokio::fs::File::open(&file).await?).lines();
+            while let Some(line) = lines.next_line().await? {
+                builder.add_line(&line);
+            }
+            if builder.num_unparseable_lines > 0 {
+                eprintln!("{} of {} lines are not requests in the {:?} format", builder.num_unparseable_lines, builder.num_lines, format);
+            }
+            let plan = builder.build(limit);
+
+            if dry_run {
+                println!("{}", serde_json::to_string_pretty(&plan)?);
+                return Ok(());
+            }
+
+            let request = admin_request(Method::POST, format!("{}/api/v1/admin/warm-up", server))?
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(serde_json::to_vec(&plan)?))?;
+            let response = client.request(request).await?;
+            let status = response.status();
+            let body = to_bytes(response.into_body()).await?;
+            if status != StatusCode::ACCEPTED {
+                return Err(anyhow!("warm-up failed with {}: {}", status, String::from_utf8_lossy(&body)));
+            }
+            let task: serde_json::Value = serde_json::from_slice(&body)?;
+            println!("server is fetching {} artifacts, see {}/api/v1/admin/tasks/{} for progress", plan.entries.len(), server, task["id"].as_str().unwrap_or_default());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(|a| a.to_string()).collect()
+    }
+
+    #[rstest]
+    #[case("", None)]
+    #[case("backup b.json", Some(CliCommand::Backup { server: DEFAULT_SERVER.to_string(), file: "b.json".into() }))]
+    #[case("backup b.json --server http://vault:8080/", Some(CliCommand::Backup { server: "http://vault:8080".to_string(), file: "b.json".into() }))]
+    #[case("restore --skip-missing b.json", Some(CliCommand::Restore { server: DEFAULT_SERVER.to_string(), file: "b.json".into(), skip_missing: true }))]
+    #[case("warm-up request.log --format nexus --limit 50 --dry-run", Some(CliCommand::WarmUp { server: DEFAULT_SERVER.to_string(), file: "request.log".into(), format: AccessLogFormat::Nexus, limit: 50, dry_run: true }))]
+    fn test_parse_command(#[case] command_line: &str, #[case] expected: Option<CliCommand>) {
+        assert_eq!(parse_command(&args(command_line)).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case("backup")]
+    #[case("backup a.json b.json")]
+    #[case("backup --skip-missing b.json")]
+    #[case("backup b.json --server")]
+    #[case("warm-up request.log --format apache")]
+    #[case("backup b.json --dry-run")]
+    #[case("frobnicate")]
+    fn test_parse_invalid_command(#[case] command_line: &str) {
+        assert!(parse_command(&args(command_line)).is_err());
+    }
+}
@@ -0,0 +1,776 @@
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use hyper::header::HeaderName;
+use hyper::Uri;
+use serde::Deserialize;
+use tracing::Level;
+
+use crate::auth::authenticator::{Authenticator, DEFAULT_ADMIN_ROLE};
+use crate::auth::authorization::{Authorization, AuthorizationRule};
+use crate::auth::credential_store::{ChainedCredentialStore, CredentialStore};
+use crate::auth::file_credential_store::FileCredentialStore;
+use crate::auth::ldap_credential_store::{LdapConfig, LdapCredentialStore};
+use crate::auth::oidc_credential_store::{OidcConfig, OidcCredentialStore};
+use crate::blob::blob_id::SignedBlobIds;
+#[cfg(any(test, feature = "fs-storage"))]
+use crate::blob::configured_blob_storage::BlobStorageConfig;
+#[cfg(feature = "http3")]
+use crate::http3::Http3Config;
+use crate::maven::blocking_rules_file::BlockingRulesFileConfig;
+use crate::maven::metadata_export::MetadataExportConfig;
+use crate::maven::negative_cache::NegativeCachePolicy;
+use crate::maven::plugin_prefix::PluginPrefixPolicy;
+#[cfg(feature = "fs-storage")]
+use crate::repository_manager::BlobGcConfig;
+use crate::repository_manager::{parse_repository_config, RepositoryConfig, RepositoryKind, RepositoryManagerConfig, validate_repository_name};
+use crate::util::anonymization::{AnonymizationMode, AnonymizationPolicy};
+use crate::util::byte_range::UncachedRangePolicy;
+use crate::util::content_type::DispositionPolicy;
+use crate::util::slow_transfer::SlowTransferPolicy;
+
+/// Server settings, read from a TOML file (see [Config::load]). Environment variables override
+///  individual settings, e.g. for containers that share a config file.
+///
+/// ```toml
+/// listen_addr = "0.0.0.0:3000"
+/// log_level = "info"
+/// users_file = "/etc/arti-vault/users.toml"
+///
+/// [[repositories]]
+/// name = "central"
+/// type = "remote"
+/// upstream_uri = "https://repo1.maven.org/maven2"
+/// blob_storage = { type = "fs", root = "/var/lib/arti-vault/central" }
+///
+/// [[repositories]]
+/// name = "internal"
+/// type = "hosted"
+/// access = { read = ["*"], deploy = ["ci"] }
+/// ```
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub listen_addr: SocketAddr,
+    /// address of the gRPC API, if the server is built with the 'grpc' feature
+    pub grpc_addr: SocketAddr,
+    /// UDP address of the HTTP/3 listener, if the server is built with the 'http3' feature. HTTP/3
+    ///  is only enabled if this is set, and it requires 'tls_cert' and 'tls_key'.
+    pub http3_addr: Option<SocketAddr>,
+    /// see [Http3Config](crate::http3::Http3Config)
+    pub tls_cert: Option<PathBuf>,
+    pub tls_key: Option<PathBuf>,
+    /// 'error', 'warn', 'info', 'debug' or 'trace'
+    pub log_level: String,
+    /// see [RepositoryManagerConfig::repositories]
+    pub repositories: Vec<RepositoryConfig>,
+    /// how long an artifact that upstream does not have is remembered before it is requested again
+    pub failed_download_retry_secs: u64,
+    /// the same for upstream transport errors, 0 disables remembering them
+    pub failed_download_error_retry_secs: u64,
+    /// max number of remembered failed downloads per repository
+    pub failed_download_max_entries: usize,
+    /// up to this much is added to each failed download's retry time
+    pub failed_download_jitter_secs: u64,
+    /// 'reject' (default) or 'overwrite' registering a plugin with a prefix another plugin in its group has
+    pub plugin_prefix_policy: PluginPrefixPolicy,
+    /// deterministic mode for reproducible storage layout and logs, e.g. for bug reports
+    pub uuid_seed: Option<u64>,
+    /// allowlist of upstream response headers to store with each artifact
+    pub persisted_headers: Vec<String>,
+    /// return persisted upstream headers to clients
+    pub replay_upstream_headers: bool,
+    pub deploy_alert_webhook: Option<String>,
+    /// see [RepositoryManagerConfig::manifest_signing_key]
+    pub manifest_signing_key: Option<String>,
+    /// see [RepositoryManagerConfig::blob_id_secret]
+    pub blob_id_secret: Option<String>,
+    /// 'inline_text' (default) or 'attachment' to make browsers download text artifacts like poms as well
+    pub content_disposition: DispositionPolicy,
+    /// 'download' (default), 'passthrough' or 'passthrough_and_fill', see [UncachedRangePolicy]
+    pub uncached_range_policy: UncachedRangePolicy,
+    /// public keys that repositories with a 'signatures' policy verify artifacts against
+    pub signature_keyring: Option<PathBuf>,
+    /// HKP keyserver for signatures by keys that are not in the keyring
+    pub signature_keyserver: Option<String>,
+    /// upstream transfers below this rate (after a grace period) are aborted and retried, 0 disables this
+    pub min_transfer_rate: u64,
+    pub slow_transfer_grace_secs: u64,
+    /// the metadata store is exported periodically if this is set
+    pub metadata_export_path: Option<PathBuf>,
+    pub metadata_export_interval_secs: u64,
+    /// blocking rules that are reloaded when the file changes, see [BlockingRulesFileConfig]
+    pub blocking_rules_file: Option<PathBuf>,
+    pub blocking_rules_check_secs: u64,
+    /// orphaned blobs in the default repository's file system storage are deleted periodically
+    ///  if this is positive
+    pub blob_gc_interval_secs: u64,
+    /// minimum age of orphaned data before it is deleted
+    pub blob_gc_grace_secs: u64,
+    /// orphans are only logged
+    pub blob_gc_dry_run: bool,
+    /// registers the artifacts found in remote repositories' file system storage at startup, see
+    ///  [RepositoryManager::rebuild_cache_metadata](crate::repository_manager::RepositoryManager::rebuild_cache_metadata)
+    pub rebuild_metadata_on_startup: bool,
+    /// 'hash' or 'drop' principals in the audit log and deploy metrics after 'anonymize_after_secs'
+    pub anonymize_principals: Option<AnonymizationMode>,
+    pub anonymize_after_secs: u64,
+    /// users with their password and token hashes, see [FileCredentialStore] - requests are not
+    ///  authenticated if neither this nor an identity provider is configured
+    pub users_file: Option<PathBuf>,
+    /// directory users authenticate against with their password, after the users file
+    pub ldap: Option<LdapConfig>,
+    /// identity provider whose access tokens are accepted
+    pub oidc: Option<OidcConfig>,
+    /// what the users' roles grant, see [AuthorizationRule]
+    pub authorization: Vec<AuthorizationRule>,
+    /// users with one of these roles may use the admin API if requests are authenticated
+    pub admin_roles: Vec<String>,
+}
+impl Default for Config {
+    fn default() -> Config {
+        let manager_defaults = RepositoryManagerConfig::default();
+        let slow_transfer_defaults = SlowTransferPolicy::default();
+        Config {
+            listen_addr: SocketAddr::from(([127, 0, 0, 1], 3000)),
+            grpc_addr: SocketAddr::from(([127, 0, 0, 1], 3001)),
+            http3_addr: None,
+            tls_cert: None,
+            tls_key: None,
+            log_level: "trace".to_string(),
+            repositories: manager_defaults.repositories,
+            failed_download_retry_secs: manager_defaults.negative_cache.not_found_ttl.as_secs(),
+            failed_download_error_retry_secs: manager_defaults.negative_cache.transport_error_ttl.as_secs(),
+            failed_download_max_entries: manager_defaults.negative_cache.max_entries,
+            failed_download_jitter_secs: manager_defaults.negative_cache.jitter.as_secs(),
+            plugin_prefix_policy: manager_defaults.plugin_prefix_policy,
+            uuid_seed: None,
+            persisted_headers: vec![],
+            replay_upstream_headers: false,
+            deploy_alert_webhook: None,
+            manifest_signing_key: None,
+            blob_id_secret: None,
+            content_disposition: Default::default(),
+            uncached_range_policy: manager_defaults.uncached_range_policy,
+            signature_keyring: None,
+            signature_keyserver: None,
+            min_transfer_rate: slow_transfer_defaults.min_bytes_per_sec,
+            slow_transfer_grace_secs: slow_transfer_defaults.grace_period.as_secs(),
+            metadata_export_path: None,
+            metadata_export_interval_secs: 3600,
+            blocking_rules_file: None,
+            blocking_rules_check_secs: 10,
+            blob_gc_interval_secs: 0,
+            blob_gc_grace_secs: 3600,
+            blob_gc_dry_run: false,
+            rebuild_metadata_on_startup: false,
+            anonymize_principals: None,
+            anonymize_after_secs: 7*24*3600,
+            users_file: None,
+            ldap: None,
+            oidc: None,
+            authorization: vec![],
+            admin_roles: vec![DEFAULT_ADMIN_ROLE.to_string()],
+        }
+    }
+}
+
+impl Config {
+    /// Reads the config file (if any), applies overrides from environment variables and validates
+    ///  the result. `env` looks up an environment variable, e.g. `|name| std::env::var(name).ok()`.
+    pub fn load(path: Option<&Path>, env: impl Fn(&str) -> Option<String>) -> anyhow::Result<Config> {
+        let mut config = match path {
+            Some(path) => {
+                let s = std::fs::read_to_string(path)
+                    .with_context(|| format!("error reading config file {}", path.display()))?;
+                Config::parse(&s)
+                    .with_context(|| format!("error in config file {}", path.display()))?
+            }
+            None => Config::default(),
+        };
+        config.apply_env_overrides(env)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn parse(s: &str) -> anyhow::Result<Config> {
+        Ok(toml::from_str(s)?)
+    }
+
+    fn apply_env_overrides(&mut self, env: impl Fn(&str) -> Option<String>) -> anyhow::Result<()> {
+        if let Some(listen_addr) = parse_env(&env, "ARTI_VAULT_LISTEN_ADDR", "a socket address")? {
+            self.listen_addr = listen_addr;
+        }
+        if let Some(grpc_addr) = parse_env(&env, "ARTI_VAULT_GRPC_ADDR", "a socket address")? {
+            self.grpc_addr = grpc_addr;
+        }
+        if let Some(http3_addr) = parse_env(&env, "ARTI_VAULT_HTTP3_ADDR", "a socket address")? {
+            self.http3_addr = Some(http3_addr);
+        }
+        if let Some(path) = env("ARTI_VAULT_TLS_CERT") {
+            self.tls_cert = Some(path.into());
+        }
+        if let Some(path) = env("ARTI_VAULT_TLS_KEY") {
+            self.tls_key = Some(path.into());
+        }
+        if let Some(log_level) = env("ARTI_VAULT_LOG_LEVEL") {
+            self.log_level = log_level;
+        }
+        // comma separated, e.g. 'central=remote:https://repo1.maven.org/maven2,internal=hosted;fs=/data/internal'
+        if let Some(repositories) = env("ARTI_VAULT_REPOSITORIES") {
+            self.repositories = split_list(&repositories)
+                .map(|s| parse_repository_config(s).context("invalid ARTI_VAULT_REPOSITORIES"))
+                .collect::<anyhow::Result<_>>()?;
+        }
+        if let Some(secs) = parse_env(&env, "ARTI_VAULT_FAILED_DOWNLOAD_RETRY_SECS", "a number of seconds")? {
+            self.failed_download_retry_secs = secs;
+        }
+        if let Some(secs) = parse_env(&env, "ARTI_VAULT_FAILED_DOWNLOAD_ERROR_RETRY_SECS", "a number of seconds")? {
+            self.failed_download_error_retry_secs = secs;
+        }
+        if let Some(max_entries) = parse_env(&env, "ARTI_VAULT_FAILED_DOWNLOAD_MAX_ENTRIES", "an unsigned integer")? {
+            self.failed_download_max_entries = max_entries;
+        }
+        if let Some(secs) = parse_env(&env, "ARTI_VAULT_FAILED_DOWNLOAD_JITTER_SECS", "a number of seconds")? {
+            self.failed_download_jitter_secs = secs;
+        }
+        if let Some(policy) = parse_env(&env, "ARTI_VAULT_PLUGIN_PREFIX_POLICY", "'reject' or 'overwrite'")? {
+            self.plugin_prefix_policy = policy;
+        }
+        if let Some(seed) = parse_env(&env, "ARTI_VAULT_UUID_SEED", "an unsigned integer")? {
+            self.uuid_seed = Some(seed);
+        }
+        if let Some(headers) = env("ARTI_VAULT_PERSISTED_HEADERS") {
+            self.persisted_headers = split_list(&headers).map(|s| s.to_string()).collect();
+        }
+        if let Some(replay) = env("ARTI_VAULT_REPLAY_HEADERS") {
+            self.replay_upstream_headers = replay == "true";
+        }
+        if let Some(webhook) = env("ARTI_VAULT_DEPLOY_ALERT_WEBHOOK") {
+            self.deploy_alert_webhook = Some(webhook);
+        }
+        if let Some(key) = env("ARTI_VAULT_MANIFEST_SIGNING_KEY") {
+            self.manifest_signing_key = Some(key);
+        }
+        if let Some(secret) = env("ARTI_VAULT_BLOB_ID_SECRET") {
+            self.blob_id_secret = Some(secret);
+        }
+        if let Some(policy) = parse_env(&env, "ARTI_VAULT_CONTENT_DISPOSITION", "'inline_text' or 'attachment'")? {
+            self.content_disposition = policy;
+        }
+        if let Some(policy) = parse_env(&env, "ARTI_VAULT_UNCACHED_RANGE_POLICY", "'download', 'passthrough' or 'passthrough_and_fill'")? {
+            self.uncached_range_policy = policy;
+        }
+        if let Some(path) = env("ARTI_VAULT_SIGNATURE_KEYRING") {
+            self.signature_keyring = Some(path.into());
+        }
+        if let Some(uri) = env("ARTI_VAULT_SIGNATURE_KEYSERVER") {
+            self.signature_keyserver = Some(uri);
+        }
+        if let Some(rate) = parse_env(&env, "ARTI_VAULT_MIN_TRANSFER_RATE", "a number of bytes per second")? {
+            self.min_transfer_rate = rate;
+        }
+        if let Some(secs) = parse_env(&env, "ARTI_VAULT_SLOW_TRANSFER_GRACE_SECS", "a number of seconds")? {
+            self.slow_transfer_grace_secs = secs;
+        }
+        if let Some(path) = env("ARTI_VAULT_BLOCKING_RULES_FILE") {
+            self.blocking_rules_file = Some(path.into());
+        }
+        if let Some(secs) = parse_env(&env, "ARTI_VAULT_BLOCKING_RULES_CHECK_SECS", "a number of seconds")? {
+            self.blocking_rules_check_secs = secs;
+        }
+        if let Some(path) = env("ARTI_VAULT_METADATA_EXPORT_PATH") {
+            self.metadata_export_path = Some(path.into());
+        }
+        if let Some(secs) = parse_env(&env, "ARTI_VAULT_METADATA_EXPORT_INTERVAL_SECS", "a number of seconds")? {
+            self.metadata_export_interval_secs = secs;
+        }
+        if let Some(secs) = parse_env(&env, "ARTI_VAULT_BLOB_GC_INTERVAL_SECS", "a number of seconds")? {
+            self.blob_gc_interval_secs = secs;
+        }
+        if let Some(secs) = parse_env(&env, "ARTI_VAULT_BLOB_GC_GRACE_SECS", "a number of seconds")? {
+            self.blob_gc_grace_secs = secs;
+        }
+        if let Some(dry_run) = env("ARTI_VAULT_BLOB_GC_DRY_RUN") {
+            self.blob_gc_dry_run = dry_run == "true";
+        }
+        if let Some(rebuild) = env("ARTI_VAULT_REBUILD_METADATA_ON_STARTUP") {
+            self.rebuild_metadata_on_startup = rebuild == "true";
+        }
+        if let Some(mode) = parse_env(&env, "ARTI_VAULT_ANONYMIZE_PRINCIPALS", "'hash' or 'drop'")? {
+            self.anonymize_principals = Some(mode);
+        }
+        if let Some(secs) = parse_env(&env, "ARTI_VAULT_ANONYMIZE_AFTER_SECS", "a number of seconds")? {
+            self.anonymize_after_secs = secs;
+        }
+        if let Some(path) = env("ARTI_VAULT_USERS_FILE") {
+            self.users_file = Some(path.into());
+        }
+        Ok(())
+    }
+
+    /// Checks everything that can be checked before starting the server, so that configuration
+    ///  errors are reported at startup with the setting they refer to
+    pub fn validate(&self) -> anyhow::Result<()> {
+        self.log_level()?;
+
+        let mut has_remote = false;
+        for (i, repository) in self.repositories.iter().enumerate() {
+            validate_repository_name(&repository.name)?;
+            if self.repositories[..i].iter().any(|r| r.name == repository.name) {
+                return Err(anyhow!("repository '{}' is configured twice", repository.name));
+            }
+            if let RepositoryKind::Remote { upstream_uri, mirror_uri, .. } = &repository.kind {
+                has_remote = true;
+                validate_upstream_uri(&repository.name, "upstream", upstream_uri)?;
+                if let Some(mirror_uri) = mirror_uri {
+                    validate_upstream_uri(&repository.name, "mirror", mirror_uri)?;
+                }
+            }
+            if repository.checksums.is_empty() {
+                return Err(anyhow!("repository '{}' must serve at least one kind of checksum", repository.name));
+            }
+            if repository.access.is_restricted() && !self.authenticates() {
+                return Err(anyhow!("repository '{}' restricts access, which requires a users_file, ldap or oidc", repository.name));
+            }
+            #[cfg(feature = "fs-storage")]
+            if let BlobStorageConfig::Fs { root, .. } = &repository.blob_storage {
+                if root.as_os_str().is_empty() {
+                    return Err(anyhow!("blob storage root for repository '{}' must not be empty", repository.name));
+                }
+            }
+            #[cfg(feature = "azure-storage")]
+            if let BlobStorageConfig::Azure { container_url, .. } = &repository.blob_storage {
+                let uri = Uri::try_from(container_url.as_str())
+                    .with_context(|| format!("invalid Azure container URL for repository '{}': '{}'", repository.name, container_url))?;
+                if uri.scheme_str() != Some("https") || uri.path().trim_matches('/').is_empty() {
+                    return Err(anyhow!("Azure container URL for repository '{}' must be https and include the container: '{}'", repository.name, container_url));
+                }
+            }
+            #[cfg(feature = "gcs-storage")]
+            if let BlobStorageConfig::Gcs { bucket, .. } = &repository.blob_storage {
+                if bucket.is_empty() || bucket.contains('/') {
+                    return Err(anyhow!("invalid GCS bucket name for repository '{}': '{}'", repository.name, bucket));
+                }
+            }
+        }
+        if !has_remote {
+            return Err(anyhow!("at least one remote repository must be configured"));
+        }
+
+        for rule in &self.authorization {
+            rule.validate()?;
+        }
+        if !self.authorization.is_empty() && !self.authenticates() {
+            return Err(anyhow!("authorization rules require a users_file, ldap or oidc"));
+        }
+        if let Some(ldap) = &self.ldap {
+            ldap.validate()?;
+        }
+        if let Some(oidc) = &self.oidc {
+            oidc.validate()?;
+        }
+
+        for header in &self.persisted_headers {
+            HeaderName::from_str(header)
+                .with_context(|| format!("invalid persisted header name '{}'", header))?;
+        }
+        if let Some(secret) = &self.blob_id_secret {
+            SignedBlobIds::new(secret.as_bytes())?;
+        }
+        if self.http3_addr.is_some() && (self.tls_cert.is_none() || self.tls_key.is_none()) {
+            return Err(anyhow!("HTTP/3 requires tls_cert and tls_key"));
+        }
+        if self.metadata_export_path.is_some() && self.metadata_export_interval_secs == 0 {
+            return Err(anyhow!("metadata_export_interval_secs must be positive"));
+        }
+        if self.blocking_rules_file.is_some() && self.blocking_rules_check_secs == 0 {
+            return Err(anyhow!("blocking_rules_check_secs must be positive"));
+        }
+        Ok(())
+    }
+
+    /// whether there is a source of credentials
+    fn authenticates(&self) -> bool {
+        self.users_file.is_some() || self.ldap.is_some() || self.oidc.is_some()
+    }
+
+    pub fn log_level(&self) -> anyhow::Result<Level> {
+        Level::from_str(&self.log_level)
+            .map_err(|_| anyhow!("invalid log level '{}', must be one of 'error', 'warn', 'info', 'debug' or 'trace'", self.log_level))
+    }
+
+    pub fn repository_manager_config(&self) -> anyhow::Result<RepositoryManagerConfig> {
+        let slow_transfer_policy = match self.min_transfer_rate {
+            0 => None,
+            min_bytes_per_sec => Some(SlowTransferPolicy {
+                min_bytes_per_sec,
+                grace_period: Duration::from_secs(self.slow_transfer_grace_secs),
+            }),
+        };
+
+        Ok(RepositoryManagerConfig {
+            repositories: self.repositories.clone(),
+            uuid_seed: self.uuid_seed,
+            persisted_headers: self.persisted_headers.iter()
+                .map(|s| HeaderName::from_str(s))
+                .collect::<Result<_, _>>()?,
+            replay_upstream_headers: self.replay_upstream_headers,
+            deploy_alert_webhook: self.deploy_alert_webhook.clone(),
+            manifest_signing_key: self.manifest_signing_key.clone(),
+            blob_id_secret: self.blob_id_secret.clone(),
+            content_disposition: self.content_disposition,
+            slow_transfer_policy,
+            negative_cache: NegativeCachePolicy {
+                not_found_ttl: Duration::from_secs(self.failed_download_retry_secs),
+                transport_error_ttl: Duration::from_secs(self.failed_download_error_retry_secs),
+                max_entries: self.failed_download_max_entries,
+                jitter: Duration::from_secs(self.failed_download_jitter_secs),
+            },
+            plugin_prefix_policy: self.plugin_prefix_policy,
+            uncached_range_policy: self.uncached_range_policy,
+            signature_keyring: self.signature_keyring.clone(),
+            signature_keyserver: self.signature_keyserver.clone(),
+            ..Default::default()
+        })
+    }
+
+    #[cfg(feature = "fs-storage")]
+    pub fn blob_gc_config(&self) -> Option<BlobGcConfig> {
+        if self.blob_gc_interval_secs == 0 {
+            return None;
+        }
+        Some(BlobGcConfig {
+            interval: Duration::from_secs(self.blob_gc_interval_secs),
+            grace_period: Duration::from_secs(self.blob_gc_grace_secs),
+            dry_run: self.blob_gc_dry_run,
+        })
+    }
+
+    #[cfg(feature = "http3")]
+    pub fn http3_config(&self) -> Option<Http3Config> {
+        Some(Http3Config {
+            addr: self.http3_addr?,
+            cert_path: self.tls_cert.clone()?,
+            key_path: self.tls_key.clone()?,
+        })
+    }
+
+    /// Reads the users file, so this can fail even if the configuration is valid
+    pub fn authenticator(&self) -> anyhow::Result<Authenticator> {
+        let mut credential_stores: Vec<Arc<dyn CredentialStore>> = vec![];
+        if let Some(path) = &self.users_file {
+            credential_stores.push(Arc::new(FileCredentialStore::load(path)?));
+        }
+        if let Some(ldap) = &self.ldap {
+            credential_stores.push(Arc::new(LdapCredentialStore::new(ldap.clone())?));
+        }
+        if let Some(oidc) = &self.oidc {
+            credential_stores.push(Arc::new(OidcCredentialStore::new(oidc.clone())));
+        }
+        let credential_store: Option<Arc<dyn CredentialStore>> = match credential_stores.len() {
+            0 => None,
+            1 => credential_stores.pop(),
+            _ => Some(Arc::new(ChainedCredentialStore::new(credential_stores))),
+        };
+        Ok(Authenticator::new(credential_store, &self.repositories, Authorization::new(self.authorization.clone()))
+            .with_admin_roles(self.admin_roles.clone()))
+    }
+
+    pub fn anonymization_policy(&self) -> Option<AnonymizationPolicy> {
+        self.anonymize_principals
+            .map(|mode| AnonymizationPolicy::new(mode, Duration::from_secs(self.anonymize_after_secs)))
+    }
+
+    pub fn blocking_rules_file_config(&self) -> Option<BlockingRulesFileConfig> {
+        self.blocking_rules_file.as_ref().map(|path| BlockingRulesFileConfig {
+            path: path.clone(),
+            check_interval: Duration::from_secs(self.blocking_rules_check_secs),
+        })
+    }
+
+    pub fn metadata_export_config(&self) -> Option<MetadataExportConfig> {
+        self.metadata_export_path.as_ref().map(|path| MetadataExportConfig {
+            path: path.clone(),
+            interval: Duration::from_secs(self.metadata_export_interval_secs),
+        })
+    }
+}
+
+fn validate_upstream_uri(repository_name: &str, description: &str, uri: &str) -> anyhow::Result<()> {
+    let parsed = Uri::try_from(uri)
+        .with_context(|| format!("invalid {} URI for repository '{}': '{}'", description, repository_name, uri))?;
+    if !matches!(parsed.scheme_str(), Some("http") | Some("https")) {
+        return Err(anyhow!("{} URI for repository '{}' must be http or https: '{}'", description, repository_name, uri));
+    }
+    Ok(())
+}
+
+fn split_list(s: &str) -> impl Iterator<Item=&str> {
+    s.split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+}
+
+fn parse_env<T: FromStr>(env: &impl Fn(&str) -> Option<String>, name: &str, description: &str) -> anyhow::Result<Option<T>> {
+    match env(name) {
+        None => Ok(None),
+        Some(value) => value.trim().parse()
+            .map(Some)
+            .map_err(|_| anyhow!("{} must be {}, was '{}'", name, description, value)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use rstest::rstest;
+
+    use crate::auth::access::{AccessPolicy, Permission};
+    use crate::maven::checksums::default_checksum_kinds;
+    use crate::maven::paths::ChecksumKind;
+    use crate::maven::snapshot_retention::SnapshotRetentionPolicy;
+    use crate::util::checksum_headers::default_checksum_headers;
+    use crate::util::upstream_client::{ClientCertificate, UpstreamTlsConfig};
+    use super::*;
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars = vars.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<HashMap<_, _>>();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_parse() {
+        let config = Config::parse(r#"
+            listen_addr = "0.0.0.0:8080"
+            grpc_addr = "0.0.0.0:8081"
+            log_level = "info"
+            failed_download_retry_secs = 60
+            failed_download_error_retry_secs = 0
+            users_file = "/etc/arti-vault/users.toml"
+            admin_roles = ["ops"]
+
+            [[repositories]]
+            name = "central"
+            type = "remote"
+            upstream_uri = "https://repo1.maven.org/maven2"
+
+            [[repositories]]
+            name = "internal"
+            type = "hosted"
+            snapshot_retention = { keep_builds = 5 }
+            access = { deploy = ["ci"] }
+            checksums = ["sha512", "sha256", "sha1"]
+
+            [[authorization]]
+            role = "team-x"
+            group_ids = ["com.acme.teamx"]
+            permissions = ["deploy", "delete"]
+        "#).unwrap();
+
+        assert_eq!(config.listen_addr, SocketAddr::from(([0, 0, 0, 0], 8080)));
+        assert_eq!(config.grpc_addr, SocketAddr::from(([0, 0, 0, 0], 8081)));
+        assert_eq!(config.log_level().unwrap(), Level::INFO);
+        assert_eq!(config.failed_download_retry_secs, 60);
+        assert_eq!(config.repository_manager_config().unwrap().negative_cache.transport_error_ttl, Duration::ZERO);
+        assert_eq!(config.repositories, vec![
+            RepositoryConfig {
+                name: "central".to_string(),
+                kind: RepositoryKind::Remote {
+                    upstream_uri: "https://repo1.maven.org/maven2".to_string(),
+                    mirror_uri: None,
+                    tls: Default::default(),
+                    checksum_headers: default_checksum_headers(),
+                    checksum_files: Default::default(),
+                    signatures: Default::default(),
+                },
+                blob_storage: BlobStorageConfig::Transient,
+                snapshot_retention: Default::default(),
+                access: Default::default(),
+                checksums: default_checksum_kinds(),
+                naming_policy: Default::default(),
+            },
+            RepositoryConfig {
+                name: "internal".to_string(),
+                kind: RepositoryKind::Hosted,
+                blob_storage: BlobStorageConfig::Transient,
+                snapshot_retention: SnapshotRetentionPolicy { keep_builds: Some(5), max_age_days: None },
+                access: AccessPolicy {
+                    read: None,
+                    deploy: Some(vec!["ci".to_string()]),
+                },
+                checksums: vec![ChecksumKind::Sha512, ChecksumKind::Sha256, ChecksumKind::Sha1],
+                naming_policy: Default::default(),
+            },
+        ]);
+        assert_eq!(config.authorization, vec![AuthorizationRule {
+            role: "team-x".to_string(),
+            repositories: vec!["*".to_string()],
+            group_ids: vec!["com.acme.teamx".to_string()],
+            permissions: vec![Permission::Deploy, Permission::Delete],
+        }]);
+        assert_eq!(config.admin_roles, vec!["ops".to_string()]);
+        // unspecified settings have their defaults
+        assert_eq!(config.metadata_export_interval_secs, 3600);
+        assert!(config.blocking_rules_file_config().is_none());
+        config.validate().unwrap();
+    }
+
+    #[cfg(feature = "fs-storage")]
+    #[test]
+    fn test_parse_fs_blob_storage() {
+        let config = Config::parse(r#"
+            [[repositories]]
+            name = "central"
+            type = "remote"
+            upstream_uri = "https://repo1.maven.org/maven2"
+            blob_storage = { type = "fs", root = "/data/central" }
+        "#).unwrap();
+        assert_eq!(config.repositories[0].blob_storage, BlobStorageConfig::Fs { root: "/data/central".into(), verify_reads: false });
+    }
+
+    #[cfg(feature = "azure-storage")]
+    #[test]
+    fn test_parse_azure_blob_storage() {
+        let config = Config::parse(r#"
+            [[repositories]]
+            name = "central"
+            type = "remote"
+            upstream_uri = "https://repo1.maven.org/maven2"
+            blob_storage = { type = "azure", container_url = "https://account.blob.core.windows.net/central", sas_token = "sv=2021-08-06&sig=x" }
+        "#).unwrap();
+        assert_eq!(config.repositories[0].blob_storage, BlobStorageConfig::Azure {
+            container_url: "https://account.blob.core.windows.net/central".to_string(),
+            sas_token: "sv=2021-08-06&sig=x".to_string(),
+        });
+        config.validate().unwrap();
+    }
+
+    #[cfg(feature = "gcs-storage")]
+    #[test]
+    fn test_parse_gcs_blob_storage() {
+        let config = Config::parse(r#"
+            [[repositories]]
+            name = "central"
+            type = "remote"
+            upstream_uri = "https://repo1.maven.org/maven2"
+            blob_storage = { type = "gcs", bucket = "arti-vault-central" }
+        "#).unwrap();
+        assert_eq!(config.repositories[0].blob_storage, BlobStorageConfig::Gcs {
+            bucket: "arti-vault-central".to_string(),
+            access_token: None,
+        });
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn test_parse_tls_config() {
+        let config = Config::parse(r#"
+            [[repositories]]
+            name = "mirror"
+            type = "remote"
+            upstream_uri = "https://mirror.example.com"
+            client_certificate = { cert_path = "/tls/client.pem", key_path = "/tls/client.key" }
+            ca_certificates = ["/tls/internal-ca.pem"]
+        "#).unwrap();
+        assert_eq!(config.repositories[0].kind, RepositoryKind::Remote {
+            upstream_uri: "https://mirror.example.com".to_string(),
+            mirror_uri: None,
+            tls: UpstreamTlsConfig {
+                client_certificate: Some(ClientCertificate {
+                    cert_path: "/tls/client.pem".into(),
+                    key_path: "/tls/client.key".into(),
+                }),
+                ca_certificates: vec!["/tls/internal-ca.pem".into()],
+                ..Default::default()
+            },
+            checksum_headers: default_checksum_headers(),
+            checksum_files: Default::default(),
+            signatures: Default::default(),
+        });
+    }
+
+    #[test]
+    fn test_unknown_setting_is_rejected() {
+        let e = Config::parse("listen_address = \"0.0.0.0:8080\"").unwrap_err();
+        assert!(e.to_string().contains("listen_address"));
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let config = Config::load(None, env(&[
+            ("ARTI_VAULT_LISTEN_ADDR", "0.0.0.0:9000"),
+            ("ARTI_VAULT_GRPC_ADDR", "0.0.0.0:9001"),
+            ("ARTI_VAULT_HTTP3_ADDR", "0.0.0.0:4433"),
+            ("ARTI_VAULT_TLS_CERT", "cert.pem"),
+            ("ARTI_VAULT_TLS_KEY", "key.pem"),
+            ("ARTI_VAULT_REPOSITORIES", "mirror=remote:https://example.com/maven, internal=hosted"),
+            ("ARTI_VAULT_MIN_TRANSFER_RATE", "0"),
+            ("ARTI_VAULT_BLOB_GC_INTERVAL_SECS", "86400"),
+            ("ARTI_VAULT_BLOB_GC_DRY_RUN", "true"),
+            ("ARTI_VAULT_REBUILD_METADATA_ON_STARTUP", "true"),
+            ("ARTI_VAULT_ANONYMIZE_PRINCIPALS", "hash"),
+            ("ARTI_VAULT_ANONYMIZE_AFTER_SECS", "3600"),
+        ])).unwrap();
+
+        assert_eq!(config.listen_addr, SocketAddr::from(([0, 0, 0, 0], 9000)));
+        assert_eq!(config.grpc_addr, SocketAddr::from(([0, 0, 0, 0], 9001)));
+        assert_eq!(config.http3_addr, Some(SocketAddr::from(([0, 0, 0, 0], 4433))));
+        assert_eq!(config.tls_cert, Some(PathBuf::from("cert.pem")));
+        assert_eq!(config.tls_key, Some(PathBuf::from("key.pem")));
+        assert_eq!(config.repositories.len(), 2);
+        assert_eq!(config.repositories[0].name, "mirror");
+        assert_eq!(config.repository_manager_config().unwrap().slow_transfer_policy, None);
+        #[cfg(feature = "fs-storage")]
+        assert!(config.blob_gc_config().unwrap().dry_run);
+        assert!(config.rebuild_metadata_on_startup);
+        let anonymization_policy = config.anonymization_policy().unwrap();
+        assert_eq!(anonymization_policy.mode, AnonymizationMode::Hash);
+        assert_eq!(anonymization_policy.retention, Duration::from_secs(3600));
+    }
+
+    #[cfg(feature = "http3")]
+    #[test]
+    fn test_http3_config() {
+        assert!(Config::load(None, env(&[("ARTI_VAULT_TLS_CERT", "cert.pem"), ("ARTI_VAULT_TLS_KEY", "key.pem")])).unwrap().http3_config().is_none());
+
+        let config = Config::parse(r#"
+            http3_addr = "0.0.0.0:4433"
+            tls_cert = "cert.pem"
+            tls_key = "key.pem"
+        "#).unwrap().http3_config().unwrap();
+        assert_eq!(config.addr, SocketAddr::from(([0, 0, 0, 0], 4433)));
+        assert_eq!(config.cert_path, PathBuf::from("cert.pem"));
+        assert_eq!(config.key_path, PathBuf::from("key.pem"));
+    }
+
+    #[rstest]
+    #[case(&[("ARTI_VAULT_LISTEN_ADDR", "localhost")], "ARTI_VAULT_LISTEN_ADDR")]
+    #[case(&[("ARTI_VAULT_GRPC_ADDR", "3001")], "ARTI_VAULT_GRPC_ADDR")]
+    #[case(&[("ARTI_VAULT_HTTP3_ADDR", "4433"), ("ARTI_VAULT_TLS_CERT", "cert.pem"), ("ARTI_VAULT_TLS_KEY", "key.pem")], "ARTI_VAULT_HTTP3_ADDR")]
+    #[case(&[("ARTI_VAULT_HTTP3_ADDR", "0.0.0.0:4433"), ("ARTI_VAULT_TLS_KEY", "key.pem")], "tls_cert")]
+    #[case(&[("ARTI_VAULT_HTTP3_ADDR", "0.0.0.0:4433"), ("ARTI_VAULT_TLS_CERT", "cert.pem")], "tls_key")]
+    #[case(&[("ARTI_VAULT_LOG_LEVEL", "verbose")], "log level")]
+    #[case(&[("ARTI_VAULT_REPOSITORIES", "internal=hosted")], "remote repository")]
+    #[case(&[("ARTI_VAULT_REPOSITORIES", "a=hosted,a=remote:https://example.com")], "configured twice")]
+    #[case(&[("ARTI_VAULT_REPOSITORIES", "a=remote:ftp://example.com")], "http or https")]
+    #[case(&[("ARTI_VAULT_REPOSITORIES", "a=remote:https://example.com;mirror=ftp://example.com")], "mirror URI")]
+    #[case(&[("ARTI_VAULT_REPOSITORIES", "a=remote:https://example.com;read=alice")], "users_file")]
+    #[case(&[("ARTI_VAULT_PERSISTED_HEADERS", "x y")], "header name")]
+    #[case(&[("ARTI_VAULT_CONTENT_DISPOSITION", "download")], "ARTI_VAULT_CONTENT_DISPOSITION")]
+    #[case(&[("ARTI_VAULT_PLUGIN_PREFIX_POLICY", "ignore")], "ARTI_VAULT_PLUGIN_PREFIX_POLICY")]
+    #[case(&[("ARTI_VAULT_UNCACHED_RANGE_POLICY", "fill")], "ARTI_VAULT_UNCACHED_RANGE_POLICY")]
+    #[case(&[("ARTI_VAULT_ANONYMIZE_PRINCIPALS", "yes")], "ARTI_VAULT_ANONYMIZE_PRINCIPALS")]
+    #[case(&[("ARTI_VAULT_BLOB_ID_SECRET", "short")], "blob id secret")]
+    fn test_invalid_config(#[case] vars: &[(&str, &str)], #[case] expected_message: &str) {
+        let e = Config::load(None, env(vars)).unwrap_err();
+        assert!(format!("{:#}", e).contains(expected_message), "{:#}", e);
+    }
+}
@@ -0,0 +1,160 @@
+use std::net::SocketAddr;
+
+use anyhow::anyhow;
+use bytes::Bytes;
+use sha1::{Digest, Sha1};
+
+use crate::blob::configured_blob_storage::BlobStorageConfig;
+use crate::config::Config;
+use crate::maven::checksums::default_checksum_kinds;
+use crate::maven::hosted_repo::DeployOutcome;
+use crate::repository_manager::{RepositoryConfig, RepositoryKind, RepositoryManager};
+
+/// the hosted repository that example artifacts are deployed to
+pub const DEV_REPO_NAME: &str = "dev";
+
+/// (group path, artifact id, version) of the seeded example artifacts
+const EXAMPLE_ARTIFACTS: [(&str, &str, &str); 2] = [
+    ("com/example", "hello", "1.0"),
+    ("com/example", "hello", "1.1-SNAPSHOT"),
+];
+
+/// Whether the (command line) arguments request development mode
+pub fn is_dev_mode(args: &[String]) -> bool {
+    args.iter().any(|a| a == "--dev")
+}
+
+/// Throwaway settings for trying out the server locally: all storage is transient, and the server
+///  listens on a random free port on the loopback interface. The config file and environment
+///  variables are ignored so that they can not point development mode at real data.
+pub fn dev_config() -> Config {
+    let mut config = Config {
+        listen_addr: SocketAddr::from(([127, 0, 0, 1], 0)),
+        log_level: "info".to_string(),
+        ..Default::default()
+    };
+    for repository in &mut config.repositories {
+        repository.blob_storage = BlobStorageConfig::Transient;
+    }
+    config.repositories.push(RepositoryConfig {
+        name: DEV_REPO_NAME.to_string(),
+        kind: RepositoryKind::Hosted,
+        blob_storage: BlobStorageConfig::Transient,
+        snapshot_retention: Default::default(),
+        access: Default::default(),
+        checksums: default_checksum_kinds(),
+        naming_policy: Default::default(),
+    });
+    config
+}
+
+/// Deploys the example artifacts to the development repository the way Maven does, i.e. with
+///  checksums and a concluding metadata update
+pub async fn seed_example_artifacts(repository_manager: &RepositoryManager) -> anyhow::Result<()> {
+    for (group_path, artifact_id, version) in EXAMPLE_ARTIFACTS {
+        let directory = format!("{}/{}/{}", group_path, artifact_id, version);
+        let file_version = match version.ends_with("-SNAPSHOT") {
+            true => format!("{}-20240101.120000-1", version),
+            false => version.to_string(),
+        };
+
+        for extension in ["pom", "jar"] {
+            let path = format!("{}/{}-{}.{}", directory, artifact_id, file_version, extension);
+            let content = if extension == "pom" {
+                example_pom(group_path, artifact_id, version).into_bytes()
+            }
+            else {
+                format!("example content of {}", path).into_bytes()
+            };
+            let sha1 = hex::encode(Sha1::digest(&content));
+
+            deploy(repository_manager, &path, content).await?;
+            deploy(repository_manager, &format!("{}.sha1", path), sha1.into_bytes()).await?;
+        }
+
+        let metadata_path = match version.ends_with("-SNAPSHOT") {
+            true => format!("{}/maven-metadata.xml", directory),
+            false => format!("{}/{}/maven-metadata.xml", group_path, artifact_id),
+        };
+        deploy(repository_manager, &metadata_path, b"<metadata/>".to_vec()).await?;
+    }
+    Ok(())
+}
+
+async fn deploy(repository_manager: &RepositoryManager, path: &str, content: Vec<u8>) -> anyhow::Result<()> {
+    let size = content.len() as u64;
+    let data = Box::pin(futures::stream::iter(vec![Ok(Bytes::from(content))]));
+    match repository_manager.deploy(DEV_REPO_NAME, path, "dev-mode", data, Some(size)).await? {
+        Some(DeployOutcome::Conflict(msg)) | Some(DeployOutcome::Invalid(msg)) => Err(anyhow!("error seeding {}: {}", path, msg)),
+        Some(_) => Ok(()),
+        None => Err(anyhow!("repository {} does not exist", DEV_REPO_NAME)),
+    }
+}
+
+fn example_pom(group_path: &str, artifact_id: &str, version: &str) -> String {
+    format!(r#"<?xml version="1.0" encoding="UTF-8"?>
+<project xmlns="http://maven.apache.org/POM/4.0.0">
+  <modelVersion>4.0.0</modelVersion>
+  <groupId>{}</groupId>
+  <artifactId>{}</artifactId>
+  <version>{}</version>
+</project>
+"#, group_path.replace('/', "."), artifact_id, version)
+}
+
+/// Repository configuration for Maven and Gradle, ready to paste into a project using the server
+///  at 'addr'
+pub fn client_configuration(addr: &SocketAddr, repository_names: &[String]) -> String {
+    let mut result = String::new();
+
+    result.push_str("Maven (settings.xml, inside <profile><repositories>):\n");
+    for name in repository_names {
+        result.push_str(&format!("  <repository>\n    <id>arti-vault-{}</id>\n    <url>http://{}/repo/{}/</url>\n    <snapshots><enabled>true</enabled></snapshots>\n  </repository>\n", name, addr, name));
+    }
+    result.push_str(&format!("Maven (pom.xml, deploying to the development repository):\n  <distributionManagement>\n    <repository>\n      <id>arti-vault-{0}</id>\n      <url>http://{1}/repo/{0}/</url>\n    </repository>\n    <snapshotRepository>\n      <id>arti-vault-{0}</id>\n      <url>http://{1}/repo/{0}/</url>\n    </snapshotRepository>\n  </distributionManagement>\n", DEV_REPO_NAME, addr));
+
+    result.push_str("\nGradle (build.gradle.kts):\n  repositories {\n");
+    for name in repository_names {
+        result.push_str(&format!("      maven {{\n          url = uri(\"http://{}/repo/{}/\")\n          isAllowInsecureProtocol = true\n      }}\n", addr, name));
+    }
+    result.push_str("  }\n");
+    result.push_str(&format!("\nExample artifact: http://{}/repo/{}/com/example/hello/1.0/hello-1.0.jar\n", addr, DEV_REPO_NAME));
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use crate::maven::paths::parse_artifact_metadata_path;
+    use crate::repository_manager::RepositoryRef;
+
+    use super::*;
+
+    #[test]
+    fn test_is_dev_mode() {
+        assert!(is_dev_mode(&["--dev".to_string()]));
+        assert!(!is_dev_mode(&["backup".to_string(), "dev".to_string()]));
+    }
+
+    #[tokio::test]
+    async fn test_seed_example_artifacts() {
+        let config = dev_config();
+        let repository_manager = config.repository_manager_config().and_then(RepositoryManager::new).unwrap();
+        seed_example_artifacts(&repository_manager).await.unwrap();
+
+        let hosted = match repository_manager.find_repository("dev/com/example/hello/maven-metadata.xml") {
+            Some((RepositoryRef::Hosted(hosted), _)) => hosted,
+            _ => panic!("development repository not found"),
+        };
+        let xml = hosted.get_artifact_metadata_xml(&parse_artifact_metadata_path("com/example/hello/maven-metadata.xml").unwrap(), &Default::default()).await.unwrap().unwrap();
+        assert!(xml.contains("<release>1.0</release>"));
+        assert!(xml.contains("<version>1.1-SNAPSHOT</version>"));
+    }
+
+    #[test]
+    fn test_client_configuration() {
+        let addr = SocketAddr::from(([127, 0, 0, 1], 12345));
+        let configuration = client_configuration(&addr, &["central".to_string(), "dev".to_string()]);
+        assert!(configuration.contains("<url>http://127.0.0.1:12345/repo/central/</url>"));
+        assert!(configuration.contains("url = uri(\"http://127.0.0.1:12345/repo/dev/\")"));
+    }
+}
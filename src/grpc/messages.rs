@@ -0,0 +1,63 @@
+//TODO generate these from proto/arti_vault.proto once protoc is available in the build environment
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ArtifactRequest {
+    #[prost(string, tag = "1")]
+    pub path: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ArtifactInfo {
+    #[prost(uint64, tag = "1")]
+    pub size: u64,
+    #[prost(string, tag = "2")]
+    pub sha1: String,
+    #[prost(string, tag = "3")]
+    pub md5: String,
+    #[prost(uint64, tag = "4")]
+    pub last_modified: u64,
+    #[prost(string, tag = "5")]
+    pub advisory: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct DataChunk {
+    #[prost(bytes = "bytes", tag = "1")]
+    pub data: bytes::Bytes,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GcRequest {
+    #[prost(uint64, tag = "1")]
+    pub grace_period_secs: u64,
+    #[prost(bool, tag = "3")]
+    pub delete_orphans: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GcReply {
+    #[prost(string, repeated, tag = "1")]
+    pub orphans: Vec<String>,
+    #[prost(string, repeated, tag = "2")]
+    pub foreign_entries: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ArtifactMetadataRequest {
+    #[prost(string, tag = "1")]
+    pub group_id: String,
+    #[prost(string, tag = "2")]
+    pub artifact_id: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ArtifactMetadataReply {
+    #[prost(string, repeated, tag = "1")]
+    pub versions: Vec<String>,
+    #[prost(string, tag = "2")]
+    pub latest_version: String,
+    #[prost(string, tag = "3")]
+    pub release_version: String,
+    #[prost(string, tag = "4")]
+    pub last_updated: String,
+}
@@ -0,0 +1,426 @@
+// tonic::Status is the error type of every gRPC handler, large or not
+#![allow(clippy::result_large_err)]
+
+use std::convert::Infallible;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::UNIX_EPOCH;
+
+use futures::{Stream, StreamExt};
+use hex::ToHex;
+use tonic::{Request, Response, Status};
+use tonic::body::BoxBody;
+use tonic::codec::ProstCodec;
+use tonic::codegen::{Body, BoxFuture, empty_body, http, StdError};
+use tonic::server::{Grpc, NamedService, ServerStreamingService, UnaryService};
+
+use crate::auth::authenticator::{Authenticator, Refusal};
+use crate::grpc::messages::*;
+use crate::maven::coordinates::{MavenArtifactId, MavenArtifactRef, MavenGroupId};
+use crate::maven::paths::parse_maven_path;
+use crate::repository_manager::RepositoryManager;
+use crate::util::repo_error::RepoError;
+
+type DataChunkStream = Pin<Box<dyn Stream<Item = Result<DataChunk, Status>> + Send>>;
+
+/// The 'ArtiVault' gRPC service (see proto/arti_vault.proto), operating on the same
+///  [RepositoryManager] as the REST frontend. Requests are authenticated and authorized by the
+///  same [Authenticator] as the equivalent HTTP requests, with credentials in the 'authorization'
+///  metadata.
+///
+/// This is what tonic's code generator would produce, written by hand to avoid a build time
+///  dependency on protoc.
+#[derive(Clone)]
+pub struct ArtiVaultGrpcService {
+    manager: Arc<RepositoryManager>,
+    authenticator: Arc<Authenticator>,
+}
+impl ArtiVaultGrpcService {
+    pub fn new(manager: Arc<RepositoryManager>, authenticator: Arc<Authenticator>) -> ArtiVaultGrpcService {
+        ArtiVaultGrpcService {
+            manager,
+            authenticator,
+        }
+    }
+}
+
+impl NamedService for ArtiVaultGrpcService {
+    const NAME: &'static str = "arti_vault.ArtiVault";
+}
+
+impl<B> tonic::codegen::Service<http::Request<B>> for ArtiVaultGrpcService
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let manager = self.manager.clone();
+        let authenticator = self.authenticator.clone();
+        match request.uri().path() {
+            "/arti_vault.ArtiVault/ResolveArtifact" => Box::pin(async move {
+                Ok(Grpc::new(ProstCodec::default())
+                    .unary(Unary(move |r| resolve_artifact(manager.clone(), authenticator.clone(), r)), request)
+                    .await)
+            }),
+            "/arti_vault.ArtiVault/Download" => Box::pin(async move {
+                Ok(Grpc::new(ProstCodec::default())
+                    .server_streaming(ServerStreaming(move |r| download(manager.clone(), authenticator.clone(), r)), request)
+                    .await)
+            }),
+            "/arti_vault.ArtiVault/TriggerGc" => Box::pin(async move {
+                Ok(Grpc::new(ProstCodec::default())
+                    .unary(Unary(move |r| trigger_gc(manager.clone(), authenticator.clone(), r)), request)
+                    .await)
+            }),
+            "/arti_vault.ArtiVault/GetArtifactMetadata" => Box::pin(async move {
+                Ok(Grpc::new(ProstCodec::default())
+                    .unary(Unary(move |r| get_artifact_metadata(manager.clone(), authenticator.clone(), r)), request)
+                    .await)
+            }),
+            _ => Box::pin(async move {
+                Ok(http::Response::builder()
+                    .status(200)
+                    .header("grpc-status", "12") // UNIMPLEMENTED
+                    .header("content-type", "application/grpc")
+                    .body(empty_body())
+                    .unwrap())
+            }),
+        }
+    }
+}
+
+/// Adapts an async fn to tonic's [UnaryService]
+struct Unary<F>(F);
+impl<F, Fut, Req, Resp> UnaryService<Req> for Unary<F>
+where
+    F: FnMut(Request<Req>) -> Fut,
+    Fut: Future<Output = Result<Response<Resp>, Status>> + Send + 'static,
+{
+    type Response = Resp;
+    type Future = BoxFuture<Response<Resp>, Status>;
+
+    fn call(&mut self, request: Request<Req>) -> Self::Future {
+        Box::pin((self.0)(request))
+    }
+}
+
+/// Adapts an async fn to tonic's [ServerStreamingService]
+struct ServerStreaming<F>(F);
+impl<F, Fut, Req> ServerStreamingService<Req> for ServerStreaming<F>
+where
+    F: FnMut(Request<Req>) -> Fut,
+    Fut: Future<Output = Result<Response<DataChunkStream>, Status>> + Send + 'static,
+{
+    type Response = DataChunk;
+    type ResponseStream = DataChunkStream;
+    type Future = BoxFuture<Response<DataChunkStream>, Status>;
+
+    fn call(&mut self, request: Request<Req>) -> Self::Future {
+        Box::pin((self.0)(request))
+    }
+}
+
+/// Authenticates and authorizes a request like the HTTP request 'method' for 'path', see
+///  [Authenticator::authorize]
+async fn authorize<T>(authenticator: &Authenticator, request: &Request<T>, method: http::Method, path: &str) -> Result<(), Status> {
+    let headers = request.metadata().clone().into_headers();
+    match authenticator.authorize(path, None, &method, &headers).await {
+        Ok(_) => Ok(()),
+        Err(Refusal::Unauthenticated) => Err(Status::unauthenticated("missing or invalid credentials")),
+        Err(Refusal::Forbidden) => Err(Status::permission_denied("access denied")),
+        Err(Refusal::Error) => Err(Status::internal("error authenticating the request")),
+    }
+}
+
+/// The HTTP path of a file in the default repository, for authorizing requests for it
+fn repo_path(manager: &RepositoryManager, path: &str) -> String {
+    format!("/repo/{}/{}", manager.repo.name(), path.trim_start_matches('/'))
+}
+
+/// Parses the requested path, rejecting blocked versions
+fn requested_artifact(manager: &RepositoryManager, request: &ArtifactRequest) -> Result<MavenArtifactRef, Status> {
+    let artifact_ref = parse_maven_path(&request.path)
+        .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+    if let Some(blocked) = manager.check_blocked(&artifact_ref, &request.path) {
+        let message = match &blocked.advisory {
+            None => blocked.rule.message,
+            Some(advisory) => format!("{}\n{}", blocked.rule.message, advisory.as_text()),
+        };
+        return Err(Status::failed_precondition(message));
+    }
+    Ok(artifact_ref)
+}
+
+async fn resolve_artifact(manager: Arc<RepositoryManager>, authenticator: Arc<Authenticator>, request: Request<ArtifactRequest>) -> Result<Response<ArtifactInfo>, Status> {
+    authorize(&authenticator, &request, http::Method::GET, &repo_path(&manager, &request.get_ref().path)).await?;
+    let artifact_ref = requested_artifact(&manager, request.get_ref())?;
+    let stat = manager.get_artifact_stat(&artifact_ref).await
+        .map_err(|e| Status::internal(e.to_string()))?;
+
+    Ok(Response::new(ArtifactInfo {
+        size: stat.size,
+        sha1: stat.sha1.map(|h| h.encode_hex()).unwrap_or_default(),
+        md5: stat.md5.map(|h| h.encode_hex()).unwrap_or_default(),
+        last_modified: stat.last_modified
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        advisory: manager.find_advisory(&artifact_ref)
+            .map(|a| a.as_text())
+            .unwrap_or_default(),
+    }))
+}
+
+async fn download(manager: Arc<RepositoryManager>, authenticator: Arc<Authenticator>, request: Request<ArtifactRequest>) -> Result<Response<DataChunkStream>, Status> {
+    authorize(&authenticator, &request, http::Method::GET, &repo_path(&manager, &request.get_ref().path)).await?;
+    let artifact_ref = requested_artifact(&manager, request.get_ref())?;
+    let blob = manager.get_artifact(&artifact_ref).await
+        .map_err(|e| match RepoError::of(&e) {
+            Some(RepoError::NotFound(_)) => Status::not_found(e.to_string()),
+            Some(RepoError::UpstreamUnavailable(_)) => Status::unavailable(e.to_string()),
+            Some(RepoError::ChecksumMismatch(_)) | Some(RepoError::InvalidSignature(_)) => Status::data_loss(e.to_string()),
+            Some(RepoError::Internal(_)) | None => Status::internal(e.to_string()),
+        })?;
+
+    let chunks = blob.data
+        .map(|chunk| chunk
+            .map(|data| DataChunk { data })
+            .map_err(|e| Status::data_loss(e.to_string())));
+    Ok(Response::new(Box::pin(chunks)))
+}
+
+/// the admin API's equivalent of TriggerGc
+const GC_PATH: &str = "/api/v1/admin/blob-storage/gc";
+
+/// Only reports orphans unless the request asks to delete them
+#[cfg(feature = "fs-storage")]
+async fn trigger_gc(manager: Arc<RepositoryManager>, authenticator: Arc<Authenticator>, request: Request<GcRequest>) -> Result<Response<GcReply>, Status> {
+    authorize(&authenticator, &request, http::Method::POST, GC_PATH).await?;
+    let fs_blob_storage = manager.fs_blob_storage.as_ref()
+        .ok_or_else(|| Status::unimplemented("not supported by the configured blob storage"))?;
+
+    let grace_period_secs = match request.get_ref().grace_period_secs {
+        0 => None,
+        secs => Some(secs),
+    };
+    let grace_period = manager.orphan_grace_period(grace_period_secs)
+        .map_err(|e| Status::invalid_argument(e.to_string()))?;
+    let report = fs_blob_storage.fsck(&grace_period, !request.get_ref().delete_orphans, &manager.blob_reference_checker()).await
+        .map_err(|e| Status::internal(e.to_string()))?;
+
+    Ok(Response::new(GcReply {
+        orphans: report.orphans.into_iter().map(|o| o.path).collect(),
+        foreign_entries: report.foreign_entries,
+    }))
+}
+
+#[cfg(not(feature = "fs-storage"))]
+async fn trigger_gc(_manager: Arc<RepositoryManager>, authenticator: Arc<Authenticator>, request: Request<GcRequest>) -> Result<Response<GcReply>, Status> {
+    authorize(&authenticator, &request, http::Method::POST, GC_PATH).await?;
+    Err(Status::unimplemented("not supported by the configured blob storage"))
+}
+
+async fn get_artifact_metadata(manager: Arc<RepositoryManager>, authenticator: Arc<Authenticator>, request: Request<ArtifactMetadataRequest>) -> Result<Response<ArtifactMetadataReply>, Status> {
+    let metadata_path = format!("{}/{}/maven-metadata.xml", request.get_ref().group_id.replace('.', "/"), request.get_ref().artifact_id);
+    authorize(&authenticator, &request, http::Method::GET, &repo_path(&manager, &metadata_path)).await?;
+    let request = request.into_inner();
+    let metadata = manager.repo.get_artifact_metadata(&MavenGroupId(request.group_id), &MavenArtifactId(request.artifact_id)).await
+        .map_err(|e| Status::internal(e.to_string()))?
+        .ok_or_else(|| Status::not_found("no versions known for this artifact"))?;
+
+    Ok(Response::new(ArtifactMetadataReply {
+        versions: metadata.versions.iter().map(|v| v.unqualified().to_string()).collect(),
+        latest_version: metadata.latest_version.unqualified().to_string(),
+        release_version: metadata.release_version
+            .map(|v| v.unqualified().to_string())
+            .unwrap_or_default(),
+        last_updated: metadata.last_updated,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use bytes::Bytes;
+    use hyper::Server;
+    use hyper::service::{make_service_fn, service_fn};
+    use sha1::{Digest as _, Sha1};
+    use tokio::net::TcpListener;
+    use tonic::Code;
+    use tonic::codegen::http::uri::PathAndQuery;
+    use tonic::transport::Channel;
+
+    use crate::auth::authorization::Authorization;
+    use crate::auth::credential_store::hash_token;
+    use crate::auth::file_credential_store::FileCredentialStore;
+    use crate::repository_manager::{parse_repository_config, RepositoryManagerConfig};
+
+    use super::*;
+
+    const JAR_PATH: &str = "com/acme/a/1.0/a-1.0.jar";
+
+    /// serves the jar and its checksum, and 404 for everything else
+    fn serve_upstream() -> String {
+        let make_service = make_service_fn(|_| async {
+            Ok::<_, hyper::Error>(service_fn(|request: http::Request<hyper::Body>| async move {
+                let body = match request.uri().path().strip_prefix('/') {
+                    Some(JAR_PATH) => Some("jar".to_string()),
+                    Some(path) if path == format!("{}.sha1", JAR_PATH) => Some(hex::encode(Sha1::digest(b"jar"))),
+                    _ => None,
+                };
+                Ok::<_, hyper::Error>(match body {
+                    Some(body) => http::Response::new(hyper::Body::from(body)),
+                    None => http::Response::builder().status(404).body(hyper::Body::empty()).unwrap(),
+                })
+            }))
+        });
+        let server = Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(make_service);
+        let uri = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+        uri
+    }
+
+    /// The service for a repository that any authenticated user may read, with the users 'dev'
+    ///  and 'ops' (an admin)
+    async fn connect(repository_config: &str) -> (tonic::client::Grpc<Channel>, Arc<RepositoryManager>) {
+        let repository = parse_repository_config(&format!("central=remote:{};read=*{}", serve_upstream(), repository_config)).unwrap();
+        let manager = Arc::new(RepositoryManager::new(RepositoryManagerConfig {
+            repositories: vec![repository.clone()],
+            ..Default::default()
+        }).unwrap());
+        let credential_store = FileCredentialStore::parse(&format!(r#"
+            [[users]]
+            name = "dev"
+            tokens = ["{}"]
+
+            [[users]]
+            name = "ops"
+            tokens = ["{}"]
+            roles = ["admin"]
+        "#, hash_token("dev-token"), hash_token("ops-token"))).unwrap();
+        let authenticator = Authenticator::new(Some(Arc::new(credential_store)), &[repository], Authorization::default());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let incoming = futures::stream::unfold(listener, |listener| async move {
+            Some((listener.accept().await.map(|(stream, _)| stream), listener))
+        });
+        tokio::spawn(tonic::transport::Server::builder()
+            .add_service(ArtiVaultGrpcService::new(manager.clone(), Arc::new(authenticator)))
+            .serve_with_incoming(incoming));
+
+        let channel = Channel::from_shared(format!("http://{}", addr)).unwrap().connect().await.unwrap();
+        (tonic::client::Grpc::new(channel), manager)
+    }
+
+    fn request<T>(message: T, token: Option<&str>) -> Request<T> {
+        let mut request = Request::new(message);
+        if let Some(token) = token {
+            request.metadata_mut().insert("authorization", format!("Bearer {}", token).parse().unwrap());
+        }
+        request
+    }
+
+    async fn unary<Req, Resp>(client: &mut tonic::client::Grpc<Channel>, method: &'static str, message: Req, token: Option<&str>) -> Result<Resp, Status>
+    where
+        Req: prost::Message + Send + Sync + 'static,
+        Resp: prost::Message + Default + Send + Sync + 'static,
+    {
+        client.ready().await.unwrap();
+        client.unary(request(message, token), PathAndQuery::from_static(method), ProstCodec::default()).await
+            .map(|response| response.into_inner())
+    }
+
+    async fn download(client: &mut tonic::client::Grpc<Channel>, path: &str, token: Option<&str>) -> Result<Bytes, Status> {
+        client.ready().await.unwrap();
+        let message = ArtifactRequest { path: path.to_string() };
+        let mut chunks = client.server_streaming::<_, DataChunk, _>(request(message, token), PathAndQuery::from_static("/arti_vault.ArtiVault/Download"), ProstCodec::default()).await?
+            .into_inner();
+        let mut data = vec![];
+        while let Some(chunk) = chunks.message().await? {
+            data.extend_from_slice(&chunk.data);
+        }
+        Ok(data.into())
+    }
+
+    #[tokio::test]
+    async fn test_resolve_artifact() {
+        let (mut client, _) = connect("").await;
+        let resolve = |path: &str| ArtifactRequest { path: path.to_string() };
+
+        let info: ArtifactInfo = unary(&mut client, "/arti_vault.ArtiVault/ResolveArtifact", resolve(JAR_PATH), Some("dev-token")).await.unwrap();
+        assert_eq!(info.size, 3);
+        assert_eq!(info.sha1, hex::encode(Sha1::digest(b"jar")));
+        assert_eq!(info.advisory, "");
+
+        let e = unary::<_, ArtifactInfo>(&mut client, "/arti_vault.ArtiVault/ResolveArtifact", resolve(JAR_PATH), None).await.unwrap_err();
+        assert_eq!(e.code(), Code::Unauthenticated);
+        let e = unary::<_, ArtifactInfo>(&mut client, "/arti_vault.ArtiVault/ResolveArtifact", resolve("not a path"), Some("dev-token")).await.unwrap_err();
+        assert_eq!(e.code(), Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_download() {
+        let (mut client, _) = connect("").await;
+
+        assert_eq!(download(&mut client, JAR_PATH, Some("dev-token")).await.unwrap(), Bytes::from_static(b"jar"));
+        assert_eq!(download(&mut client, JAR_PATH, Some("wrong-token")).await.unwrap_err().code(), Code::Unauthenticated);
+        assert_eq!(download(&mut client, "com/acme/a/2.0/a-2.0.jar", Some("dev-token")).await.unwrap_err().code(), Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_get_artifact_metadata() {
+        let (mut client, _) = connect("").await;
+        download(&mut client, JAR_PATH, Some("dev-token")).await.unwrap();
+        let metadata_request = |artifact_id: &str| ArtifactMetadataRequest {
+            group_id: "com.acme".to_string(),
+            artifact_id: artifact_id.to_string(),
+        };
+
+        let metadata: ArtifactMetadataReply = unary(&mut client, "/arti_vault.ArtiVault/GetArtifactMetadata", metadata_request("a"), Some("dev-token")).await.unwrap();
+        assert_eq!(metadata.versions, vec!["1.0".to_string()]);
+        assert_eq!(metadata.latest_version, "1.0");
+
+        let e = unary::<_, ArtifactMetadataReply>(&mut client, "/arti_vault.ArtiVault/GetArtifactMetadata", metadata_request("b"), Some("dev-token")).await.unwrap_err();
+        assert_eq!(e.code(), Code::NotFound);
+        let e = unary::<_, ArtifactMetadataReply>(&mut client, "/arti_vault.ArtiVault/GetArtifactMetadata", metadata_request("a"), None).await.unwrap_err();
+        assert_eq!(e.code(), Code::Unauthenticated);
+    }
+
+    #[cfg(feature = "fs-storage")]
+    #[tokio::test]
+    async fn test_trigger_gc() {
+        let root = std::env::temp_dir().join(format!("arti-vault-test-{}", uuid::Uuid::new_v4().as_hyphenated()));
+        let (mut client, manager) = connect(&format!(";fs={}", root.display())).await;
+        // an interrupted insert, which is orphaned regardless of the grace period
+        let interrupted = manager.fs_blob_storage.as_ref().unwrap().temp_directory_path_for_key(&uuid::Uuid::new_v4());
+        tokio::fs::create_dir_all(&interrupted).await.unwrap();
+
+        let e = unary::<_, GcReply>(&mut client, "/arti_vault.ArtiVault/TriggerGc", GcRequest::default(), Some("dev-token")).await.unwrap_err();
+        assert_eq!(e.code(), Code::PermissionDenied);
+        let e = unary::<_, GcReply>(&mut client, "/arti_vault.ArtiVault/TriggerGc", GcRequest::default(), None).await.unwrap_err();
+        assert_eq!(e.code(), Code::Unauthenticated);
+
+        // only reporting is the default
+        let reply: GcReply = unary(&mut client, "/arti_vault.ArtiVault/TriggerGc", GcRequest::default(), Some("ops-token")).await.unwrap();
+        assert_eq!(reply.orphans.len(), 1);
+        assert!(interrupted.exists());
+
+        let gc_request = GcRequest { delete_orphans: true, ..Default::default() };
+        let reply: GcReply = unary(&mut client, "/arti_vault.ArtiVault/TriggerGc", gc_request, Some("ops-token")).await.unwrap();
+        assert_eq!(reply.orphans.len(), 1);
+        assert!(!interrupted.exists());
+
+        let _ = tokio::fs::remove_dir_all(&root).await;
+    }
+}
@@ -0,0 +1,168 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use axum::{middleware, Router};
+use axum::body::BoxBody;
+use bytes::{Buf, BytesMut};
+use h3::server::RequestStream;
+use hyper::{Body, Request, Response};
+use hyper::body::HttpBody;
+use hyper::header::{ALT_SVC, HeaderValue};
+use tower::ServiceExt;
+use tracing::{debug, info, warn};
+
+/// requests are buffered before they are passed to the router, so their size is limited
+const MAX_REQUEST_BODY_SIZE: usize = 16*1024*1024;
+
+/// Settings for the optional HTTP/3 (QUIC) listener, which serves the same routes as the
+///  HTTP/1.1 / HTTP/2 listener. HTTP/3 mandates TLS, so a certificate is required. See
+///  [Config::http3_config](crate::config::Config::http3_config).
+#[derive(Clone, Debug)]
+pub struct Http3Config {
+    /// UDP address
+    pub addr: SocketAddr,
+    /// PEM file with the certificate chain
+    pub cert_path: PathBuf,
+    /// PEM file with the private key (PKCS #8 or RSA)
+    pub key_path: PathBuf,
+}
+impl Http3Config {
+    /// The 'Alt-Svc' header value advertising this listener to HTTP/1.1 and HTTP/2 clients
+    pub fn alt_svc(&self) -> HeaderValue {
+        HeaderValue::from_str(&format!("h3=\":{}\"; ma=86400", self.addr.port()))
+            .expect("a port number is a valid header value")
+    }
+
+    fn tls_config(&self) -> anyhow::Result<rustls::ServerConfig> {
+        let certs = rustls_pemfile::certs(&mut BufReader::new(File::open(&self.cert_path)?))?
+            .into_iter()
+            .map(rustls::Certificate)
+            .collect::<Vec<_>>();
+
+        let mut key_reader = BufReader::new(File::open(&self.key_path)?);
+        let key = match rustls_pemfile::read_one(&mut key_reader)? {
+            Some(rustls_pemfile::Item::PKCS8Key(key)) | Some(rustls_pemfile::Item::RSAKey(key)) => rustls::PrivateKey(key),
+            _ => return Err(anyhow!("no private key in {:?}", self.key_path)),
+        };
+
+        let mut tls_config = rustls::ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(certs, key)?;
+        tls_config.alpn_protocols = vec![b"h3".to_vec()];
+        Ok(tls_config)
+    }
+}
+
+/// Advertises the HTTP/3 listener on all of the router's responses, leaving the router unchanged
+///  if HTTP/3 is not enabled
+pub fn with_alt_svc(app: Router, config: Option<&Http3Config>) -> Router {
+    let Some(config) = config else { return app };
+    let alt_svc = config.alt_svc();
+    app.layer(middleware::map_response(move |mut response: Response<BoxBody>| {
+        let alt_svc = alt_svc.clone();
+        async move {
+            response.headers_mut().insert(ALT_SVC, alt_svc);
+            response
+        }
+    }))
+}
+
+/// Serves the router's routes over HTTP/3 until the endpoint is closed
+pub async fn serve_http3(config: Http3Config, app: Router) -> anyhow::Result<()> {
+    let server_config = quinn::ServerConfig::with_crypto(Arc::new(config.tls_config()?));
+    let endpoint = quinn::Endpoint::server(server_config, config.addr)?;
+    info!("HTTP/3 listening on {}", config.addr);
+
+    while let Some(connecting) = endpoint.accept().await {
+        let app = app.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(connecting, app).await {
+                debug!("HTTP/3 connection failed: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+async fn handle_connection(connecting: quinn::Connecting, app: Router) -> anyhow::Result<()> {
+    let connection = connecting.await?;
+    let mut h3_connection = h3::server::Connection::new(h3_quinn::Connection::new(connection)).await?;
+
+    while let Some((request, stream)) = h3_connection.accept().await? {
+        let app = app.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_request(request, stream, app).await {
+                warn!("error handling HTTP/3 request: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+async fn handle_request<S>(request: Request<()>, mut stream: RequestStream<S, bytes::Bytes>, app: Router) -> anyhow::Result<()>
+where
+    S: h3::quic::BidiStream<bytes::Bytes>,
+{
+    let mut body = BytesMut::new();
+    while let Some(mut chunk) = stream.recv_data().await? {
+        if body.len() + chunk.remaining() > MAX_REQUEST_BODY_SIZE {
+            stream.send_response(Response::builder().status(413).body(())?).await?;
+            stream.finish().await?;
+            return Ok(());
+        }
+        body.extend_from_slice(&chunk.copy_to_bytes(chunk.remaining()));
+    }
+
+    let (parts, _) = request.into_parts();
+    let response = app.oneshot(Request::from_parts(parts, Body::from(body.freeze()))).await?;
+
+    let (parts, mut body) = response.into_parts();
+    stream.send_response(Response::from_parts(parts, ())).await?;
+    while let Some(chunk) = body.data().await {
+        stream.send_data(chunk?).await?;
+    }
+    stream.finish().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use axum::routing::get;
+    use rstest::*;
+
+    use super::*;
+
+    fn config(addr: &str) -> Http3Config {
+        Http3Config {
+            addr: SocketAddr::from_str(addr).unwrap(),
+            cert_path: PathBuf::from("cert.pem"),
+            key_path: PathBuf::from("key.pem"),
+        }
+    }
+
+    #[rstest]
+    #[case::v4("0.0.0.0:4433", "h3=\":4433\"; ma=86400")]
+    #[case::v6("[::1]:443", "h3=\":443\"; ma=86400")]
+    fn test_alt_svc(#[case] addr: &str, #[case] expected: &str) {
+        assert_eq!(config(addr).alt_svc(), expected);
+    }
+
+    #[rstest]
+    #[case::enabled(Some("0.0.0.0:4433"), Some("h3=\":4433\"; ma=86400"))]
+    #[case::disabled(None, None)]
+    #[tokio::test]
+    async fn test_with_alt_svc(#[case] addr: Option<&str>, #[case] expected: Option<&str>) {
+        let config = addr.map(config);
+        let app = with_alt_svc(Router::new().route("/", get(|| async { "" })), config.as_ref());
+
+        let response = app.oneshot(Request::builder().uri("/").body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.headers().get(ALT_SVC).map(|v| v.to_str().unwrap()), expected);
+    }
+}
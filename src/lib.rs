@@ -0,0 +1,26 @@
+//! arti-vault as a library, for embedding its repositories in other servers. [prelude] has the
+//!  types that stay compatible across minor versions; modules marked as hidden in the
+//!  documentation are internal to the server and may change at any time.
+
+pub mod prelude;
+
+#[doc(hidden)]
+pub mod api;
+pub mod auth;
+pub mod blob;
+#[cfg(feature = "admin-api")]
+#[doc(hidden)]
+pub mod cli;
+pub mod config;
+#[doc(hidden)]
+pub mod dev_mode;
+#[cfg(feature = "grpc")]
+#[doc(hidden)]
+pub mod grpc;
+#[cfg(feature = "http3")]
+#[doc(hidden)]
+pub mod http3;
+pub mod maven;
+pub mod repository_manager;
+#[doc(hidden)]
+pub mod util;
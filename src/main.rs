@@ -1,98 +1,191 @@
-use std::net::SocketAddr;
-use std::str::FromStr;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use axum::*;
-use axum::extract::{Path, State};
-use axum::routing::get;
-use hyper::{Body, Response};
-use tracing::{info, Instrument, span, trace};
-use tracing::Level;
+use tracing::info;
 use tracing_subscriber::FmtSubscriber;
-use uuid::Uuid;
-use hex::ToHex;
 
-use crate::blob::transient_blob_storage::TransientBlobStorage;
-use crate::maven::paths::parse_maven_path;
-use crate::maven::remote_repo::{DummyRemoteRepoMetadataStore, RemoteMavenRepo};
-
-pub mod blob;
-pub mod maven;
-pub mod util;
+use arti_vault::api;
+use arti_vault::auth::authenticator::authenticate;
+#[cfg(feature = "admin-api")]
+use arti_vault::cli;
+use arti_vault::config::Config;
+use arti_vault::dev_mode;
+use arti_vault::maven::blocking_rules_file::load_blocking_rules;
+#[cfg(feature = "grpc")]
+use arti_vault::grpc::service::ArtiVaultGrpcService;
+#[cfg(feature = "http3")]
+use arti_vault::http3::{serve_http3, with_alt_svc};
+use arti_vault::repository_manager::RepositoryManager;
+use arti_vault::util::access_log::access_log;
 
 #[tokio::main]
 async fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let dev_mode = dev_mode::is_dev_mode(&args);
+
+    #[cfg(feature = "admin-api")]
+    if !dev_mode {
+        match cli::parse_command(&args) {
+            Ok(None) => {}
+            Ok(Some(command)) => {
+                if let Err(e) = cli::run_command(command).await {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+                return;
+            }
+            Err(e) => {
+                eprintln!("{}\n{}", e, cli::USAGE);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    // the config file is optional, environment variables override its settings
+    let config_path = std::env::var("ARTI_VAULT_CONFIG").ok().map(PathBuf::from);
+    let config = match dev_mode {
+        true => Ok(dev_mode::dev_config()),
+        false => Config::load(config_path.as_deref(), |name| std::env::var(name).ok()),
+    };
+    let config = match config {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("invalid configuration: {:#}", e);
+            std::process::exit(2);
+        }
+    };
 
     let subscriber = FmtSubscriber::builder()
-        .with_max_level(Level::TRACE)
+        .with_max_level(config.log_level().unwrap())
         .with_ansi(true)
         .with_thread_ids(true)
         .with_thread_names(false)
         .finish();
 
-    //TODO log level filtering
-
     tracing::subscriber::set_global_default(subscriber)
         .expect("setting default subscriber failed");
 
-    // build our application with a route
-    let app = Router::new()
-        // .with_state(AppData{})
-        // `GET /` goes to `root`
-        .route("/", get(root))
-        .route("/repo/*path", get(repo))
-        .with_state(Arc::new(AppData{
-            repo: RemoteMavenRepo::new(
-                "https://repo1.maven.org/maven2".to_string(),
-                Arc::new(TransientBlobStorage::new()),
-                DummyRemoteRepoMetadataStore::new(),
-            ).unwrap(),
-        }))
-        //TODO HTTP trace layer
+    let authenticator = match config.authenticator() {
+        Ok(authenticator) => authenticator,
+        Err(e) => {
+            eprintln!("error setting up authentication: {:#}", e);
+            std::process::exit(2);
+        }
+    };
+
+    let repository_manager = match config.repository_manager_config().and_then(RepositoryManager::new) {
+        Ok(repository_manager) => repository_manager,
+        Err(e) => {
+            eprintln!("error setting up repositories: {:#}", e);
+            std::process::exit(2);
+        }
+    };
+    #[cfg(feature = "fs-storage")]
+    if let Some(fs_blob_storage) = &repository_manager.fs_blob_storage {
+        match fs_blob_storage.recover().await {
+            Ok(0) => {}
+            Ok(n) => info!("removed {} interrupted inserts from blob storage", n),
+            Err(e) => {
+                eprintln!("error recovering blob storage: {:#}", e);
+                std::process::exit(2);
+            }
+        }
+    }
+    if config.rebuild_metadata_on_startup {
+        if let Err(e) = repository_manager.rebuild_cache_metadata().await {
+            eprintln!("error rebuilding metadata from blob storage: {:#}", e);
+            std::process::exit(2);
+        }
+    }
+    if let Some(blocking_rules_file_config) = config.blocking_rules_file_config() {
+        match load_blocking_rules(&blocking_rules_file_config.path).await {
+            Ok(rules) => {
+                info!("loaded {} blocking rules from {:?}", rules.len(), blocking_rules_file_config.path);
+                repository_manager.blocked_versions.replace_file_rules(rules);
+            }
+            Err(e) => {
+                eprintln!("error loading blocking rules: {:#}", e);
+                std::process::exit(2);
+            }
+        }
+    }
+    info!("serving repositories {:?}", repository_manager.repository_names());
+    if dev_mode {
+        if let Err(e) = dev_mode::seed_example_artifacts(&repository_manager).await {
+            eprintln!("error seeding example artifacts: {:#}", e);
+            std::process::exit(2);
+        }
+    }
+    let repository_manager_names = repository_manager.repository_names();
+    let repository_manager = Arc::new(repository_manager);
+    let clone_sources = Arc::downgrade(&repository_manager);
+    let authenticator = Arc::new(authenticator.with_clone_sources(Arc::new(move |name| clone_sources.upgrade()?.clone_source(name))));
+    repository_manager.schedule_deploy_cleanup(Duration::from_secs(60));
+    repository_manager.schedule_snapshot_purge(Duration::from_secs(3600));
+    // artifacts cached by versions that did not track access times get one from blob storage
+    repository_manager.start_access_time_backfill();
+
+    if let Some(anonymization_policy) = config.anonymization_policy() {
+        repository_manager.schedule_anonymization(anonymization_policy);
+    }
+    if let Some(metadata_export_config) = config.metadata_export_config() {
+        repository_manager.schedule_metadata_export(metadata_export_config);
+    }
+    if let Some(blocking_rules_file_config) = config.blocking_rules_file_config() {
+        repository_manager.schedule_blocking_rules_reload(blocking_rules_file_config);
+    }
+    #[cfg(feature = "fs-storage")]
+    if let Some(blob_gc_config) = config.blob_gc_config() {
+        repository_manager.schedule_blob_gc(blob_gc_config);
+    }
 
-        ;
+    #[cfg(feature = "grpc")]
+    {
+        let grpc_addr = config.grpc_addr;
+        let grpc_service = ArtiVaultGrpcService::new(repository_manager.clone(), authenticator.clone());
+        info!("gRPC listening on {}", grpc_addr);
+        tokio::spawn(async move {
+            if let Err(e) = tonic::transport::Server::builder()
+                .add_service(grpc_service)
+                .serve(grpc_addr)
+                .await
+            {
+                tracing::error!("gRPC server failed: {}", e);
+            }
+        });
+    }
 
-    let addr = SocketAddr::from_str("127.0.0.1:3000").unwrap();
+    let app = api::routes()
+        .with_state(repository_manager)
+        .layer(middleware::from_fn_with_state(authenticator, authenticate))
+        .layer(middleware::from_fn(access_log));
+
+    #[cfg(feature = "http3")]
+    let app = {
+        let http3_config = config.http3_config();
+        let alt_svc_app = with_alt_svc(app.clone(), http3_config.as_ref());
+        if let Some(http3_config) = http3_config {
+            tokio::spawn(async move {
+                if let Err(e) = serve_http3(http3_config, app).await {
+                    tracing::error!("HTTP/3 server failed: {}", e);
+                }
+            });
+        }
+        alt_svc_app
+    };
+
+    let repository_names = repository_manager_names;
+    let server = Server::bind(&config.listen_addr)
+        .serve(app.into_make_service());
+    // the actual address, since a port of 0 binds a random free port
+    let addr = server.local_addr();
     info!("listening on {}", addr);
-    Server::bind(&addr)
-        .serve(app.into_make_service())
-        .await
-        .unwrap();
-}
-
-struct AppData {
-    repo: RemoteMavenRepo<TransientBlobStorage, DummyRemoteRepoMetadataStore>,
-}
-
-
-// basic handler that responds with a static string
-async fn root() -> &'static str {
-    "Hello, World!" //TODO
-}
-
-async fn repo(State(state): State<Arc<AppData>>, Path(repo_path): Path<String>, ) -> Response<Body> {
-    let span = span!(Level::TRACE, "repo get", repo_path, correlation_id = Uuid::new_v4().to_string());
-
-    let artifact_ref = span.in_scope(|| {
-        trace!("getting from repo: {}", repo_path);
-        parse_maven_path(&repo_path).unwrap()
-    });
-
-    let blob = state.repo.get_artifact(&artifact_ref)
-        .instrument(span)
+    if dev_mode {
+        println!("\narti-vault development mode - all data is lost on exit\n\n{}", dev_mode::client_configuration(&addr, &repository_names));
+    }
+    server
         .await
         .unwrap();
-
-    let response_body = Body::wrap_stream(blob.data);
-    let mut response_builder = Response::builder();
-    if let Some(sha1) = blob.sha1 {
-        response_builder = response_builder.header("x-checksum-sha1", sha1.encode_hex::<String>());
-    }
-    if let Some(md5) = blob.md5 {
-        response_builder = response_builder.header("x-checksum-md5", md5.encode_hex::<String>());
-    }
-    response_builder.body(response_body)
-        .unwrap()
 }
-
-
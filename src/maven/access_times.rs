@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::SystemTime;
+
+use serde::Serialize;
+
+use crate::maven::artifact_key::ArtifactKey;
+use crate::maven::coordinates::MavenArtifactRef;
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessTimeSource {
+    /// the artifact was served since access times are tracked
+    Tracked,
+    /// initialized from blob storage for an artifact that was cached before, see
+    ///  [AccessTimes::backfill]
+    Backfilled,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub struct AccessTime {
+    pub at: SystemTime,
+    pub source: AccessTimeSource,
+}
+
+/// When a repository's cached artifacts were last served, for evicting those that are no longer
+///  used. Artifacts cached before access times were tracked have no entry until they are served
+///  or backfilled - treating those as unused would evict recently used artifacts first.
+#[derive(Default)]
+pub struct AccessTimes {
+    entries: RwLock<HashMap<ArtifactKey, AccessTime>>,
+}
+impl AccessTimes {
+    pub fn new() -> AccessTimes {
+        Default::default()
+    }
+
+    pub fn record(&self, artifact_ref: &MavenArtifactRef, at: SystemTime) {
+        self.entries.write().unwrap().insert(ArtifactKey::for_artifact(artifact_ref), AccessTime {
+            at,
+            source: AccessTimeSource::Tracked,
+        });
+    }
+
+    /// Initializes an artifact's access time unless it has one - an access that was tracked while
+    ///  the backfill was running is more accurate than anything blob storage knows. Returns whether
+    ///  the access time was initialized.
+    pub fn backfill(&self, artifact_ref: &MavenArtifactRef, at: SystemTime) -> bool {
+        let mut entries = self.entries.write().unwrap();
+        let key = ArtifactKey::for_artifact(artifact_ref);
+        if entries.contains_key(&key) {
+            return false;
+        }
+        entries.insert(key, AccessTime {
+            at,
+            source: AccessTimeSource::Backfilled,
+        });
+        true
+    }
+
+    pub fn get(&self, artifact_ref: &MavenArtifactRef) -> Option<AccessTime> {
+        self.entries.read().unwrap()
+            .get(&ArtifactKey::for_artifact(artifact_ref))
+            .copied()
+    }
+
+    pub fn remove(&self, artifact_ref: &MavenArtifactRef) {
+        self.entries.write().unwrap().remove(&ArtifactKey::for_artifact(artifact_ref));
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::maven::paths::parse_maven_path;
+
+    use super::*;
+
+    #[test]
+    fn test_tracked_access_wins_over_backfill() {
+        let access_times = AccessTimes::new();
+        let jar = parse_maven_path("a/b/1.0/b-1.0.jar").unwrap();
+        let pom = parse_maven_path("a/b/1.0/b-1.0.pom").unwrap();
+        let now = SystemTime::now();
+        let earlier = now - Duration::from_secs(3600);
+
+        access_times.record(&jar, now);
+        assert!(!access_times.backfill(&jar, earlier));
+        assert_eq!(access_times.get(&jar), Some(AccessTime { at: now, source: AccessTimeSource::Tracked }));
+
+        assert!(access_times.backfill(&pom, earlier));
+        assert!(!access_times.backfill(&pom, now));
+        assert_eq!(access_times.get(&pom), Some(AccessTime { at: earlier, source: AccessTimeSource::Backfilled }));
+
+        // the backfilled time is replaced by real tracking
+        access_times.record(&pom, now);
+        assert_eq!(access_times.get(&pom).unwrap().source, AccessTimeSource::Tracked);
+
+        access_times.remove(&jar);
+        assert_eq!(access_times.get(&jar), None);
+        assert_eq!(access_times.len(), 1);
+    }
+}
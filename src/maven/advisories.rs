@@ -0,0 +1,138 @@
+use std::sync::RwLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::maven::coordinates::{MavenArtifactId, MavenArtifactRef, MavenGroupId};
+use crate::maven::version_blocking::matches_version_pattern;
+use crate::util::change_kind::ChangeKind;
+
+/// Points clients requesting a blocked or deprecated version to the recommended replacement.
+///  Advisories are independent of blocking: an advisory for a version that is not blocked marks
+///  it as deprecated, i.e. it is still served but with the advisory attached.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ReplacementAdvisory {
+    pub group_id: MavenGroupId,
+    pub artifact_id: MavenArtifactId,
+    /// same format as for blocking rules, see [crate::maven::version_blocking::VersionBlockingRule]
+    pub version_pattern: String,
+    pub replacement_version: String,
+    pub message: Option<String>,
+}
+impl ReplacementAdvisory {
+    pub fn matches(&self, artifact_ref: &MavenArtifactRef) -> bool {
+        matches_version_pattern(&self.group_id, &self.artifact_id, &self.version_pattern, artifact_ref)
+    }
+
+    /// renders the advisory as human readable text, e.g. for inclusion in a response body
+    pub fn as_text(&self) -> String {
+        match &self.message {
+            None => format!("recommended replacement: {}:{}:{}", self.group_id.0, self.artifact_id.0, self.replacement_version),
+            Some(msg) => format!("{} - recommended replacement: {}:{}:{}", msg, self.group_id.0, self.artifact_id.0, self.replacement_version),
+        }
+    }
+}
+
+/// The administrator-maintained table of replacement advisories
+#[derive(Default)]
+pub struct AdvisoryTable {
+    advisories: RwLock<Vec<ReplacementAdvisory>>,
+}
+impl AdvisoryTable {
+    pub fn new() -> AdvisoryTable {
+        Default::default()
+    }
+
+    /// Adds an advisory, replacing an existing advisory for the same group, artifact and version pattern
+    pub fn add_advisory(&self, advisory: ReplacementAdvisory) -> ChangeKind {
+        let mut advisories = self.advisories.write().unwrap();
+        match advisories.iter_mut().find(|a| a.group_id == advisory.group_id && a.artifact_id == advisory.artifact_id && a.version_pattern == advisory.version_pattern) {
+            Some(existing) if *existing == advisory => ChangeKind::Unchanged,
+            Some(existing) => {
+                *existing = advisory;
+                ChangeKind::Updated
+            }
+            None => {
+                advisories.push(advisory);
+                ChangeKind::Inserted
+            }
+        }
+    }
+
+    /// Returns true iff an advisory with the given target existed and was removed
+    pub fn remove_advisory(&self, group_id: &MavenGroupId, artifact_id: &MavenArtifactId, version_pattern: &str) -> bool {
+        let mut advisories = self.advisories.write().unwrap();
+        let len_before = advisories.len();
+        advisories.retain(|a| !(&a.group_id == group_id && &a.artifact_id == artifact_id && a.version_pattern == version_pattern));
+        advisories.len() != len_before
+    }
+
+    pub fn advisories(&self) -> Vec<ReplacementAdvisory> {
+        self.advisories.read().unwrap().clone()
+    }
+
+    /// Returns the first advisory applying to the given artifact, if any
+    pub fn find_advisory(&self, artifact_ref: &MavenArtifactRef) -> Option<ReplacementAdvisory> {
+        self.advisories.read().unwrap()
+            .iter()
+            .find(|a| a.matches(artifact_ref))
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::maven::coordinates::{MavenClassifier, MavenCoordinates, MavenVersion};
+
+    use super::*;
+
+    fn advisory(version_pattern: &str, replacement_version: &str) -> ReplacementAdvisory {
+        ReplacementAdvisory {
+            group_id: MavenGroupId("a.b".to_string()),
+            artifact_id: MavenArtifactId("c".to_string()),
+            version_pattern: version_pattern.to_string(),
+            replacement_version: replacement_version.to_string(),
+            message: None,
+        }
+    }
+
+    fn artifact_ref(version: &str) -> MavenArtifactRef {
+        MavenArtifactRef {
+            coordinates: MavenCoordinates {
+                group_id: MavenGroupId("a.b".to_string()),
+                artifact_id: MavenArtifactId("c".to_string()),
+                version: MavenVersion::Release(version.to_string()),
+            },
+            classifier: MavenClassifier::Unclassified,
+            file_extension: ".jar".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_crud() {
+        let table = AdvisoryTable::new();
+        assert_eq!(table.add_advisory(advisory("1.*", "2.0")), ChangeKind::Inserted);
+        assert_eq!(table.add_advisory(advisory("1.*", "2.0")), ChangeKind::Unchanged);
+        assert_eq!(table.add_advisory(advisory("1.*", "2.1")), ChangeKind::Updated);
+        assert_eq!(table.add_advisory(advisory("0.9", "2.1")), ChangeKind::Inserted);
+        assert_eq!(table.advisories(), vec![advisory("1.*", "2.1"), advisory("0.9", "2.1")]);
+
+        assert_eq!(table.find_advisory(&artifact_ref("1.5")), Some(advisory("1.*", "2.1")));
+        assert_eq!(table.find_advisory(&artifact_ref("0.9")), Some(advisory("0.9", "2.1")));
+        assert_eq!(table.find_advisory(&artifact_ref("2.1")), None);
+
+        let group_id = MavenGroupId("a.b".to_string());
+        let artifact_id = MavenArtifactId("c".to_string());
+        assert!(table.remove_advisory(&group_id, &artifact_id, "1.*"));
+        assert!(!table.remove_advisory(&group_id, &artifact_id, "1.*"));
+        assert_eq!(table.find_advisory(&artifact_ref("1.5")), None);
+        assert_eq!(table.advisories(), vec![advisory("0.9", "2.1")]);
+    }
+
+    #[test]
+    fn test_as_text() {
+        let mut advisory = advisory("1.*", "2.0");
+        assert_eq!(advisory.as_text(), "recommended replacement: a.b:c:2.0");
+        advisory.message = Some("CVE-2021-44228".to_string());
+        assert_eq!(advisory.as_text(), "CVE-2021-44228 - recommended replacement: a.b:c:2.0");
+    }
+}
@@ -0,0 +1,102 @@
+use percent_encoding::percent_decode_str;
+use sha1::{Digest, Sha1};
+
+use crate::maven::coordinates::{is_in_group, MavenArtifactRef, MavenClassifier, MavenVersion};
+
+/// Bumped whenever the canonical format changes, so that keys from different formats can not
+///  collide in shared (e.g. distributed) data structures
+const CANONICAL_FORMAT_VERSION: u32 = 1;
+
+/// A stable, canonical representation of a [MavenArtifactRef] for use as a key in caches, lock
+///  tables and for sharding. Different representations of the same artifact (case, percent encoded
+///  characters) map to the same key, and the key does not change between releases or processes
+///  (unlike e.g. Rust's default hasher).
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct ArtifactKey(String);
+impl ArtifactKey {
+    pub fn for_artifact(artifact_ref: &MavenArtifactRef) -> ArtifactKey {
+        let coordinates = &artifact_ref.coordinates;
+        let version = match &coordinates.version {
+            MavenVersion::Release(v) => canonical(v),
+            MavenVersion::Snapshot { version, timestamp, build_number } => format!(
+                "{}:{}:{}",
+                canonical(version),
+                canonical(timestamp),
+                build_number.map(|n| n.to_string()).unwrap_or_default(),
+            ),
+        };
+        let classifier = match &artifact_ref.classifier {
+            MavenClassifier::Unclassified => "".to_string(),
+            MavenClassifier::Classified(c) => canonical(c),
+        };
+
+        ArtifactKey(format!(
+            "v{}|{}|{}|{}|{}|{}",
+            CANONICAL_FORMAT_VERSION,
+            canonical(&coordinates.group_id.0),
+            canonical(&coordinates.artifact_id.0),
+            version,
+            classifier,
+            canonical(&artifact_ref.file_extension),
+        ))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// see [MavenGroupId::is_in](crate::maven::coordinates::MavenGroupId::is_in), ignoring case
+    ///  and percent encoding like the key itself
+    pub fn is_in_group(&self, group_prefix: &str) -> bool {
+        let group_id = self.0.split('|').nth(1).unwrap_or_default();
+        is_in_group(group_id, &canonical(group_prefix))
+    }
+
+    /// A hash of the canonical representation that is stable across processes and platforms
+    pub fn stable_hash(&self) -> u64 {
+        let digest = Sha1::digest(self.0.as_bytes());
+        let mut prefix = [0u8; 8];
+        prefix.copy_from_slice(&digest[..8]);
+        u64::from_be_bytes(prefix)
+    }
+
+    /// The shard this key belongs to for the given number of shards
+    pub fn shard(&self, num_shards: u64) -> u64 {
+        self.stable_hash() % num_shards.max(1)
+    }
+}
+
+fn canonical(s: &str) -> String {
+    percent_decode_str(s)
+        .decode_utf8_lossy()
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::*;
+    use crate::maven::paths::parse_maven_path;
+    use super::*;
+
+    #[rstest]
+    #[case("com/example/lib/1.0/lib-1.0.jar", "com/example/lib/1.0/lib-1.0.jar", true)]
+    #[case("com/example/lib/1.0/lib-1.0.jar", "com/Example/lib/1.0/lib-1.0.jar", true)]
+    #[case("com/example/lib/1.0/lib-1.0.jar", "com/example/l%69b/1.0/l%69b-1.0.jar", true)]
+    #[case("com/example/lib/1.0/lib-1.0.jar", "com/example/lib/1.0/lib-1.0.pom", false)]
+    #[case("com/example/lib/1.0/lib-1.0.jar", "com/example/lib/1.0/lib-1.0-sources.jar", false)]
+    #[case("com/example/lib/1.0-SNAPSHOT/lib-1.0-SNAPSHOT-20230102.030405-1.jar", "com/example/lib/1.0-SNAPSHOT/lib-1.0-SNAPSHOT-20230102.030405-2.jar", false)]
+    fn test_equivalence(#[case] path_a: &str, #[case] path_b: &str, #[case] expected_equal: bool) {
+        let a = ArtifactKey::for_artifact(&parse_maven_path(path_a).unwrap());
+        let b = ArtifactKey::for_artifact(&parse_maven_path(path_b).unwrap());
+        assert_eq!(a == b, expected_equal);
+        assert_eq!(a.stable_hash() == b.stable_hash(), expected_equal);
+    }
+
+    #[test]
+    fn test_stable() {
+        let key = ArtifactKey::for_artifact(&parse_maven_path("com/example/lib/1.0/lib-1.0.jar").unwrap());
+        assert_eq!(key.as_str(), "v1|com.example|lib|1.0||.jar");
+        assert_eq!(key.stable_hash(), ArtifactKey::for_artifact(&parse_maven_path("com/example/lib/1.0/lib-1.0.jar").unwrap()).stable_hash());
+        assert!(key.shard(16) < 16);
+    }
+}
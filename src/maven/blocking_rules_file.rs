@@ -0,0 +1,111 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use serde::Deserialize;
+
+use crate::maven::version_blocking::VersionBlockingRule;
+
+/// Blocking rules maintained in a file of their own rather than through the admin API, e.g. by a
+///  security team's pipeline. The file is checked for changes periodically, and a changed file
+///  replaces the rules of its previous version at once - a file that can not be read or parsed
+///  leaves the previous rules in place.
+///
+/// ```toml
+/// [[rules]]
+/// group_id = "org.apache.logging.log4j"
+/// artifact_id = "log4j-core"
+/// version_pattern = "2.14.*"
+/// message = "CVE-2021-44228, use 2.17.1 or later"
+/// ```
+#[derive(Clone, Debug)]
+pub struct BlockingRulesFileConfig {
+    pub path: PathBuf,
+    pub check_interval: Duration,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BlockingRulesFile {
+    #[serde(default)]
+    rules: Vec<VersionBlockingRule>,
+}
+
+/// Parses and validates the content of a blocking rules file. Rules with the same target are
+///  rejected rather than one of them winning silently.
+pub fn parse_blocking_rules(s: &str) -> anyhow::Result<Vec<VersionBlockingRule>> {
+    let file: BlockingRulesFile = toml::from_str(s)?;
+    for (i, rule) in file.rules.iter().enumerate() {
+        let target = format!("{}:{}:{}", rule.group_id.0, rule.artifact_id.0, rule.version_pattern);
+        if rule.group_id.0.is_empty() || rule.artifact_id.0.is_empty() || rule.version_pattern.is_empty() {
+            return Err(anyhow!("blocking rule '{}' must have a group id, an artifact id and a version pattern", target));
+        }
+        if rule.version_pattern.trim_end_matches('*').contains('*') {
+            return Err(anyhow!("blocking rule '{}' can only have a trailing '*'", target));
+        }
+        if file.rules[..i].iter().any(|r| r.group_id == rule.group_id && r.artifact_id == rule.artifact_id && r.version_pattern == rule.version_pattern) {
+            return Err(anyhow!("blocking rule '{}' is defined twice", target));
+        }
+    }
+    Ok(file.rules)
+}
+
+pub async fn load_blocking_rules(path: &Path) -> anyhow::Result<Vec<VersionBlockingRule>> {
+    let content = tokio::fs::read_to_string(path).await
+        .with_context(|| format!("error reading blocking rules file {:?}", path))?;
+    parse_blocking_rules(&content)
+        .with_context(|| format!("invalid blocking rules file {:?}", path))
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let rules = parse_blocking_rules(r#"
+            [[rules]]
+            group_id = "org.apache.logging.log4j"
+            artifact_id = "log4j-core"
+            version_pattern = "2.14.*"
+            message = "CVE-2021-44228"
+        "#).unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].artifact_id.0, "log4j-core");
+        assert_eq!(rules[0].version_pattern, "2.14.*");
+
+        assert!(parse_blocking_rules("").unwrap().is_empty());
+    }
+
+    #[rstest]
+    #[case::empty_version(r#"[[rules]]
+        group_id = "a"
+        artifact_id = "b"
+        version_pattern = ""
+        message = """#)]
+    #[case::inner_wildcard(r#"[[rules]]
+        group_id = "a"
+        artifact_id = "b"
+        version_pattern = "1.*.1"
+        message = """#)]
+    #[case::duplicate(r#"[[rules]]
+        group_id = "a"
+        artifact_id = "b"
+        version_pattern = "1.0"
+        message = "x"
+        [[rules]]
+        group_id = "a"
+        artifact_id = "b"
+        version_pattern = "1.0"
+        message = "y""#)]
+    #[case::missing_version_pattern(r#"[[rules]]
+        group_id = "a"
+        artifact_id = "b"
+        version = "1.0"
+        message = """#)]
+    fn test_parse_invalid(#[case] s: &str) {
+        assert!(parse_blocking_rules(s).is_err());
+    }
+}
@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use hex::ToHex;
+use sha1::{Digest, Sha1};
+use sha2::{Digest as _, Sha256, Sha512};
+
+use crate::maven::paths::ChecksumKind;
+
+type DataStream = Pin<Box<dyn Stream<Item = anyhow::Result<Bytes>> + Send + 'static>>;
+
+/// computed checksums are forgotten when there are more than this, and computed again on demand
+const MAX_COMPUTED_CHECKSUMS: usize = 100_000;
+
+/// What repositories serve unless configured otherwise: the checksums Maven uploads and requests
+///  by default
+pub fn default_checksum_kinds() -> Vec<ChecksumKind> {
+    vec![ChecksumKind::Sha1, ChecksumKind::Md5]
+}
+
+/// The checksums a repository serves, strongest (i.e. most preferred) first. Checksums that are
+///  not stored with blobs (see [ChecksumKind::is_stored]) are computed from an artifact's data when
+///  they are first needed, and remembered by the artifact's SHA-1 since stored data never changes.
+pub struct RepositoryChecksums {
+    kinds: Vec<ChecksumKind>,
+    computed: Mutex<HashMap<([u8;20], ChecksumKind), String>>,
+}
+impl Default for RepositoryChecksums {
+    fn default() -> RepositoryChecksums {
+        RepositoryChecksums::new(default_checksum_kinds())
+    }
+}
+impl RepositoryChecksums {
+    pub fn new(kinds: Vec<ChecksumKind>) -> RepositoryChecksums {
+        RepositoryChecksums {
+            kinds,
+            computed: Default::default(),
+        }
+    }
+
+    pub fn kinds(&self) -> &[ChecksumKind] {
+        &self.kinds
+    }
+
+    pub fn is_served(&self, kind: ChecksumKind) -> bool {
+        self.kinds.contains(&kind)
+    }
+
+    /// The served checksums that are available without reading the artifact's data, strongest
+    ///  first, for advertising them with the artifact
+    pub fn available(&self, md5: Option<[u8;16]>, sha1: Option<[u8;20]>) -> Vec<(ChecksumKind, String)> {
+        let computed = self.computed.lock().unwrap();
+        self.kinds.iter()
+            .filter_map(|&kind| {
+                let checksum = match kind {
+                    ChecksumKind::Sha1 => sha1.map(hex::encode),
+                    ChecksumKind::Md5 => md5.map(hex::encode),
+                    ChecksumKind::Sha256 | ChecksumKind::Sha512 => computed.get(&(sha1?, kind)).cloned(),
+                };
+                checksum.map(|checksum| (kind, checksum))
+            })
+            .collect()
+    }
+
+    /// The hex encoded checksum of an artifact with the given stored checksums, or None if the
+    ///  repository does not serve this kind of checksum. 'data' is only called if the checksum
+    ///  needs to be computed.
+    pub async fn served<F, Fut>(&self, md5: Option<[u8;16]>, sha1: Option<[u8;20]>, kind: ChecksumKind, data: F) -> anyhow::Result<Option<String>>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = anyhow::Result<DataStream>>,
+    {
+        if !self.is_served(kind) {
+            return Ok(None);
+        }
+        match kind {
+            ChecksumKind::Sha1 => Ok(sha1.map(hex::encode)),
+            ChecksumKind::Md5 => Ok(md5.map(hex::encode)),
+            ChecksumKind::Sha256 | ChecksumKind::Sha512 => match sha1 {
+                Some(sha1) => self.computed(sha1, kind, data).await.map(Some),
+                None => Ok(None),
+            },
+        }
+    }
+
+    /// Computes a checksum that is not stored with blobs, regardless of whether it is served, e.g.
+    ///  for verifying an uploaded checksum file
+    pub async fn computed<F, Fut>(&self, sha1: [u8;20], kind: ChecksumKind, data: F) -> anyhow::Result<String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = anyhow::Result<DataStream>>,
+    {
+        if let Some(checksum) = self.computed.lock().unwrap().get(&(sha1, kind)) {
+            return Ok(checksum.clone());
+        }
+
+        let checksum = compute_checksum(kind, data().await?).await?;
+
+        let mut computed = self.computed.lock().unwrap();
+        if computed.len() >= MAX_COMPUTED_CHECKSUMS {
+            computed.clear();
+        }
+        computed.insert((sha1, kind), checksum.clone());
+        Ok(checksum)
+    }
+}
+
+enum Hasher {
+    Sha1(Sha1),
+    Md5(md5::Context),
+    Sha256(Sha256),
+    Sha512(Sha512),
+}
+
+async fn compute_checksum(kind: ChecksumKind, mut data: DataStream) -> anyhow::Result<String> {
+    let mut hasher = match kind {
+        ChecksumKind::Sha1 => Hasher::Sha1(Sha1::new()),
+        ChecksumKind::Md5 => Hasher::Md5(md5::Context::new()),
+        ChecksumKind::Sha256 => Hasher::Sha256(Sha256::new()),
+        ChecksumKind::Sha512 => Hasher::Sha512(Sha512::new()),
+    };
+    while let Some(chunk) = data.next().await {
+        let chunk = chunk?;
+        match &mut hasher {
+            Hasher::Sha1(h) => h.update(&chunk),
+            Hasher::Md5(h) => h.consume(&chunk),
+            Hasher::Sha256(h) => h.update(&chunk),
+            Hasher::Sha512(h) => h.update(&chunk),
+        }
+    }
+    Ok(match hasher {
+        Hasher::Sha1(h) => h.finalize().encode_hex(),
+        Hasher::Md5(h) => h.finalize().encode_hex(),
+        Hasher::Sha256(h) => h.finalize().encode_hex(),
+        Hasher::Sha512(h) => h.finalize().encode_hex(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    fn data(chunks: &[&'static [u8]]) -> DataStream {
+        Box::pin(futures::stream::iter(chunks.iter().map(|c| Ok(Bytes::from_static(c))).collect::<Vec<_>>()))
+    }
+
+    #[tokio::test]
+    async fn test_compute_checksum() {
+        for kind in ChecksumKind::ALL {
+            assert_eq!(compute_checksum(kind, data(&[b"ab", b"c"])).await.unwrap(), kind.checksum_of(b"abc"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_served() {
+        let checksums = RepositoryChecksums::new(vec![ChecksumKind::Sha256, ChecksumKind::Sha1]);
+        let sha1 = [1; 20];
+        let num_reads = AtomicUsize::new(0);
+        let read = || async {
+            num_reads.fetch_add(1, Ordering::SeqCst);
+            Ok(data(&[b"abc"]))
+        };
+
+        assert_eq!(checksums.available(Some([2; 16]), Some(sha1)), vec![(ChecksumKind::Sha1, hex::encode(sha1))]);
+        assert_eq!(checksums.served(Some([2; 16]), Some(sha1), ChecksumKind::Md5, read).await.unwrap(), None);
+        let sha256 = checksums.served(None, Some(sha1), ChecksumKind::Sha256, read).await.unwrap();
+        assert_eq!(sha256, Some(ChecksumKind::Sha256.checksum_of(b"abc")));
+        assert_eq!(checksums.served(None, Some(sha1), ChecksumKind::Sha256, read).await.unwrap(), sha256);
+        assert_eq!(num_reads.load(Ordering::SeqCst), 1);
+
+        assert_eq!(checksums.available(None, Some(sha1)), vec![
+            (ChecksumKind::Sha256, sha256.unwrap()),
+            (ChecksumKind::Sha1, hex::encode(sha1)),
+        ]);
+    }
+}
@@ -1,5 +1,6 @@
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+#[derive(Debug, Eq, PartialEq, Clone, Hash, Serialize, Deserialize)]
 pub enum MavenVersion {
     Release(String),
     Snapshot {
@@ -8,27 +9,51 @@ pub enum MavenVersion {
         build_number: Option<u32>,
     }
 }
+impl MavenVersion {
+    /// The version string without snapshot qualifiers, e.g. '1.0-SNAPSHOT'
+    pub fn unqualified(&self) -> &str {
+        match self {
+            MavenVersion::Release(v) => v,
+            MavenVersion::Snapshot { version, .. } => version,
+        }
+    }
+}
 
-#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash, Serialize, Deserialize)]
 pub struct MavenArtifactId(pub String);
 
-#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash, Serialize, Deserialize)]
 pub struct MavenGroupId(pub String);
+impl MavenGroupId {
+    /// true for the group itself and its subgroups, e.g. 'com.acme.a' is in 'com.acme' but
+    ///  'com.acmex' is not
+    pub fn is_in(&self, group_prefix: &str) -> bool {
+        is_in_group(&self.0, group_prefix)
+    }
+}
+
+/// see [MavenGroupId::is_in]
+pub fn is_in_group(group_id: &str, group_prefix: &str) -> bool {
+    match group_id.strip_prefix(group_prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with('.') || group_prefix.is_empty(),
+        None => false,
+    }
+}
 
-#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash, Serialize, Deserialize)]
 pub struct MavenCoordinates {
     pub group_id: MavenGroupId,
     pub artifact_id: MavenArtifactId,
     pub version: MavenVersion,
 }
 
-#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash, Serialize, Deserialize)]
 pub enum MavenClassifier {
     Unclassified,
     Classified(String),
 }
 
-#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash, Serialize, Deserialize)]
 pub struct MavenArtifactRef {
     pub coordinates: MavenCoordinates,
     // pub file_name: String,
@@ -36,3 +61,20 @@ pub struct MavenArtifactRef {
     pub file_extension: String,
 }
 
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::same("com.acme", "com.acme", true)]
+    #[case::subgroup("com.acme.a", "com.acme", true)]
+    #[case::other_group("com.acmex", "com.acme", false)]
+    #[case::parent("com", "com.acme", false)]
+    #[case::all("com.acme", "", true)]
+    fn test_is_in(#[case] group_id: &str, #[case] group_prefix: &str, #[case] expected: bool) {
+        assert_eq!(MavenGroupId(group_id.to_string()).is_in(group_prefix), expected);
+    }
+}
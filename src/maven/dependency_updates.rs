@@ -0,0 +1,82 @@
+use std::cmp::Ordering;
+
+use serde::Serialize;
+
+use crate::maven::version_resolution::{compare_versions, is_snapshot};
+
+/// Newer releases of a dependency, as found by [find_updates]
+#[derive(Clone, Eq, PartialEq, Debug, Default, Serialize)]
+pub struct DependencyUpdates {
+    /// the highest release overall, None if no release is known
+    pub latest_release: Option<String>,
+    /// the highest release above the current version, within the same major version if
+    ///  requested - None if the dependency is up to date
+    pub update: Option<String>,
+}
+
+/// Finds newer releases of a dependency among the available versions. Snapshots are never
+///  suggested as updates.
+pub fn find_updates(current: &str, available: &[String], same_major: bool) -> DependencyUpdates {
+    let releases = || available.iter()
+        .filter(|v| !is_snapshot(v));
+
+    let latest_release = releases()
+        .max_by(|a, b| compare_versions(a, b))
+        .cloned();
+    let update = releases()
+        .filter(|v| compare_versions(v, current) == Ordering::Greater)
+        .filter(|v| !same_major || major_version(v) == major_version(current))
+        .max_by(|a, b| compare_versions(a, b))
+        .cloned();
+    DependencyUpdates { latest_release, update }
+}
+
+/// the leading number of a version without leading zeros, None if it does not start with a digit
+fn major_version(version: &str) -> Option<&str> {
+    let digits = version.find(|c: char| !c.is_ascii_digit()).unwrap_or(version.len());
+    match &version[..digits] {
+        "" => None,
+        major => Some(major.trim_start_matches('0')),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn available() -> Vec<String> {
+        ["1.0", "1.1", "1.10", "1.11-SNAPSHOT", "2.0-rc1", "2.0", "3.0-SNAPSHOT"]
+            .iter().map(|s| s.to_string()).collect()
+    }
+
+    #[rstest]
+    #[case::minor("1.0", false, Some("2.0"))]
+    #[case::same_major("1.0", true, Some("1.10"))]
+    #[case::up_to_date_major("1.10", true, None)]
+    #[case::up_to_date("2.0", false, None)]
+    #[case::pre_release("2.0-rc1", true, Some("2.0"))]
+    #[case::snapshot("1.11-SNAPSHOT", true, None)]
+    #[case::unknown("0.9", true, None)]
+    #[case::leading_zeros("01.0", true, Some("1.10"))]
+    fn test_find_updates(#[case] current: &str, #[case] same_major: bool, #[case] expected: Option<&str>) {
+        let updates = find_updates(current, &available(), same_major);
+        assert_eq!(updates.update.as_deref(), expected);
+        assert_eq!(updates.latest_release.as_deref(), Some("2.0"));
+    }
+
+    #[test]
+    fn test_no_releases() {
+        assert_eq!(find_updates("1.0", &["1.1-SNAPSHOT".to_string()], false), DependencyUpdates::default());
+    }
+
+    #[rstest]
+    #[case("1.2.3", Some("1"))]
+    #[case("12-beta", Some("12"))]
+    #[case("007", Some("7"))]
+    #[case("v1", None)]
+    fn test_major_version(#[case] version: &str, #[case] expected: Option<&str>) {
+        assert_eq!(major_version(version), expected);
+    }
+}
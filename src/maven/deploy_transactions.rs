@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+use uuid::Uuid;
+
+use crate::maven::coordinates::{MavenArtifactRef, MavenCoordinates};
+
+struct DeployTransaction {
+    files: Vec<(MavenArtifactRef, Uuid)>,
+    started: Instant,
+}
+
+/// Groups the files of a single `mvn deploy` invocation (jar, pom, sources etc.) so that none of
+///  them become visible to consumers before the set is complete. Transactions are keyed by
+///  coordinates, which include the timestamp for snapshots. Maven uploads the metadata update last,
+///  so that is the point where a deploy's transaction is completed.
+pub struct DeployTransactions {
+    timeout: Duration,
+    open: Mutex<HashMap<MavenCoordinates, DeployTransaction>>,
+}
+impl DeployTransactions {
+    pub fn new(timeout: Duration) -> DeployTransactions {
+        DeployTransactions {
+            timeout,
+            open: Default::default(),
+        }
+    }
+
+    /// Adds a file (with validated checksum) to the transaction for its coordinates, opening the
+    ///  transaction if necessary. If the same file was uploaded before in this transaction, it is
+    ///  replaced and the previous blob key is returned for cleanup.
+    pub fn add_file(&self, artifact_ref: &MavenArtifactRef, blob_key: Uuid) -> Option<Uuid> {
+        let mut open = self.open.lock().unwrap();
+        let transaction = open.entry(artifact_ref.coordinates.clone())
+            .or_insert_with(|| DeployTransaction {
+                files: vec![],
+                started: Instant::now(),
+            });
+
+        match transaction.files.iter_mut().find(|(r, _)| r == artifact_ref) {
+            Some(existing) => Some(std::mem::replace(&mut existing.1, blob_key)),
+            None => {
+                transaction.files.push((artifact_ref.clone(), blob_key));
+                None
+            }
+        }
+    }
+
+    /// The blob key of a file that was added to an open transaction
+    pub fn staged_blob(&self, artifact_ref: &MavenArtifactRef) -> Option<Uuid> {
+        self.open.lock().unwrap()
+            .get(&artifact_ref.coordinates)?
+            .files.iter()
+            .find(|(r, _)| r == artifact_ref)
+            .map(|(_, blob_key)| *blob_key)
+    }
+
+    /// Whether the blob belongs to a file in any open transaction
+    pub fn contains_blob(&self, blob_key: &Uuid) -> bool {
+        self.open.lock().unwrap()
+            .values()
+            .any(|transaction| transaction.files.iter().any(|(_, key)| key == blob_key))
+    }
+
+    /// The coordinates of all open transactions
+    pub fn open_coordinates(&self) -> Vec<MavenCoordinates> {
+        self.open.lock().unwrap()
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    /// Closes the transaction for the given coordinates, returning its files for registration.
+    ///  Transactions without a '.pom' file are incomplete and stay open.
+    pub fn complete(&self, coordinates: &MavenCoordinates) -> anyhow::Result<Vec<(MavenArtifactRef, Uuid)>> {
+        let mut open = self.open.lock().unwrap();
+        let transaction = open.get(coordinates)
+            .ok_or_else(|| anyhow!("no deploy in progress for {:?}", coordinates))?;
+
+        if !transaction.files.iter().any(|(r, _)| r.file_extension == ".pom") {
+            return Err(anyhow!("incomplete deploy for {:?}: no pom was uploaded", coordinates));
+        }
+
+        Ok(open.remove(coordinates)
+            .map(|t| t.files)
+            .unwrap_or_default())
+    }
+
+    /// Aborts transactions that were not completed within the timeout, returning their files so
+    ///  that the blobs can be deleted
+    pub fn remove_timed_out(&self) -> Vec<(MavenArtifactRef, Uuid)> {
+        let now = Instant::now();
+        let mut result = Vec::new();
+        self.open.lock().unwrap()
+            .retain(|_, transaction| {
+                if now.duration_since(transaction.started) < self.timeout {
+                    true
+                }
+                else {
+                    result.append(&mut transaction.files);
+                    false
+                }
+            });
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::maven::paths::parse_maven_path;
+    use super::*;
+
+    #[test]
+    fn test_complete_requires_pom() {
+        let transactions = DeployTransactions::new(Duration::from_secs(60));
+        let jar = parse_maven_path("com/example/lib/1.0/lib-1.0.jar").unwrap();
+        let pom = parse_maven_path("com/example/lib/1.0/lib-1.0.pom").unwrap();
+        let other_version = parse_maven_path("com/example/lib/1.1/lib-1.1.pom").unwrap();
+
+        assert_eq!(transactions.add_file(&jar, Uuid::from_u64_pair(0, 1)), None);
+        transactions.add_file(&other_version, Uuid::from_u64_pair(0, 2));
+        assert!(transactions.complete(&jar.coordinates).is_err());
+
+        assert_eq!(transactions.add_file(&jar, Uuid::from_u64_pair(0, 3)), Some(Uuid::from_u64_pair(0, 1)));
+        transactions.add_file(&pom, Uuid::from_u64_pair(0, 4));
+        assert_eq!(transactions.complete(&jar.coordinates).unwrap(), vec![
+            (jar.clone(), Uuid::from_u64_pair(0, 3)),
+            (pom, Uuid::from_u64_pair(0, 4)),
+        ]);
+        assert!(transactions.complete(&jar.coordinates).is_err());
+
+        // other transactions are unaffected
+        assert!(transactions.complete(&other_version.coordinates).is_ok());
+    }
+
+    #[test]
+    fn test_time_out() {
+        let transactions = DeployTransactions::new(Duration::ZERO);
+        let jar = parse_maven_path("com/example/lib/1.0/lib-1.0.jar").unwrap();
+        transactions.add_file(&jar, Uuid::from_u64_pair(0, 1));
+        assert!(transactions.contains_blob(&Uuid::from_u64_pair(0, 1)));
+        assert!(!transactions.contains_blob(&Uuid::from_u64_pair(0, 2)));
+
+        assert_eq!(transactions.remove_timed_out(), vec![(jar.clone(), Uuid::from_u64_pair(0, 1))]);
+        assert!(!transactions.contains_blob(&Uuid::from_u64_pair(0, 1)));
+        assert!(transactions.complete(&jar.coordinates).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+
+lazy_static! {
+    static ref HREF_REGEX: Regex = Regex::new(r#"(?i)<a\s[^>]*href\s*=\s*"([^"]*)""#).unwrap();
+}
+
+/// What a directory entry is in terms of Maven's repository layout
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DirectoryEntryKind {
+    Group,
+    Artifact,
+    Version,
+    File,
+}
+impl DirectoryEntryKind {
+    /// for CSS classes in HTML listings
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DirectoryEntryKind::Group => "group",
+            DirectoryEntryKind::Artifact => "artifact",
+            DirectoryEntryKind::Version => "version",
+            DirectoryEntryKind::File => "file",
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+pub struct DirectoryEntry {
+    pub name: String,
+    pub is_directory: bool,
+    /// None for entries of upstream listings, which are not interpreted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<DirectoryEntryKind>,
+}
+impl DirectoryEntry {
+    pub fn of_kind(name: impl Into<String>, kind: DirectoryEntryKind) -> DirectoryEntry {
+        DirectoryEntry {
+            name: name.into(),
+            is_directory: kind != DirectoryEntryKind::File,
+            kind: Some(kind),
+        }
+    }
+}
+
+/// The children of a directory inside a Maven repository, e.g. 'org/apache/'
+#[derive(Clone, Eq, PartialEq, Debug, Default, Serialize)]
+pub struct DirectoryListing {
+    pub entries: Vec<DirectoryEntry>,
+}
+impl DirectoryListing {
+    /// Merges two listings, removing duplicates and sorting entries by name
+    pub fn merge(&self, other: &DirectoryListing) -> DirectoryListing {
+        let mut by_name = BTreeMap::new();
+        for e in self.entries.iter().chain(other.entries.iter()) {
+            by_name.entry(e.name.clone()).or_insert_with(|| e.clone());
+        }
+        DirectoryListing {
+            entries: by_name.into_values().collect(),
+        }
+    }
+
+    pub fn as_html(&self, directory_path: &str) -> String {
+        let mut result = format!("<!DOCTYPE html>\n<html>\n<head><title>{0}</title></head>\n<body>\n<h1>{0}</h1>\n<ul>\n", escape_html(directory_path));
+        for e in &self.entries {
+            let href = if e.is_directory { format!("{}/", e.name) } else { e.name.clone() };
+            match e.kind {
+                Some(kind) => result.push_str(&format!("<li class=\"{}\"><a href=\"{1}\">{1}</a></li>\n", kind.as_str(), escape_html(&href))),
+                None => result.push_str(&format!("<li><a href=\"{0}\">{0}</a></li>\n", escape_html(&href))),
+            }
+        }
+        result.push_str("</ul>\n</body>\n</html>\n");
+        result
+    }
+}
+
+/// Extracts the entries of a directory listing from an upstream repository's HTML index page.
+///  This is necessarily heuristic since there is no standard format for these pages: all relative
+///  links to direct children are treated as entries, with a trailing '/' marking directories.
+pub fn parse_html_index(html: &str) -> DirectoryListing {
+    let mut entries = Vec::new();
+    for captures in HREF_REGEX.captures_iter(html) {
+        let href = &captures[1];
+
+        // skip parent links, absolute links, queries and anchors (e.g. column sorting links)
+        if href.is_empty() || href.starts_with('.') || href.starts_with('/') || href.starts_with('?') || href.starts_with('#') || href.contains("://") {
+            continue;
+        }
+
+        let (name, is_directory) = match href.strip_suffix('/') {
+            Some(name) => (name, true),
+            None => (href, false),
+        };
+
+        // only direct children
+        if name.is_empty() || name.contains('/') {
+            continue;
+        }
+
+        entries.push(DirectoryEntry {
+            name: name.to_string(),
+            is_directory,
+            kind: None,
+        });
+    }
+
+    DirectoryListing::default().merge(&DirectoryListing { entries })
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_html_index() {
+        let html = r#"<html><body>
+            <a href="../">../</a>
+            <a href="?C=N;O=D">Name</a>
+            <a href="commons-lang3/" title="commons-lang3/">commons-lang3/</a>
+            <A HREF="maven-metadata.xml">maven-metadata.xml</A>
+            <a href="https://example.com/">elsewhere</a>
+            <a href="/absolute/">absolute</a>
+            <a href="nested/child/">nested</a>
+            <a href="commons-io/">commons-io/</a>
+        </body></html>"#;
+
+        assert_eq!(parse_html_index(html), DirectoryListing {
+            entries: vec![
+                DirectoryEntry { name: "commons-io".to_string(), is_directory: true, kind: None },
+                DirectoryEntry { name: "commons-lang3".to_string(), is_directory: true, kind: None },
+                DirectoryEntry { name: "maven-metadata.xml".to_string(), is_directory: false, kind: None },
+            ]
+        });
+    }
+}
@@ -0,0 +1,780 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
+
+use anyhow::anyhow;
+use bytes::{Bytes, BytesMut};
+use futures::StreamExt;
+use hex::FromHex;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::blob::blob_storage::BlobStorage;
+use crate::maven::checksums::RepositoryChecksums;
+use crate::maven::coordinates::{MavenArtifactId, MavenArtifactRef, MavenCoordinates, MavenGroupId, MavenVersion};
+use crate::maven::deploy_transactions::DeployTransactions;
+use crate::maven::directory_listing::DirectoryListing;
+use crate::maven::metadata_consistency::{check_artifact_metadata, check_snapshot_metadata, MetadataInconsistency};
+use crate::maven::metadata_xml::{render_snapshot_metadata, SnapshotVersion};
+use crate::maven::paths::{ArtifactMetadataPath, as_maven_path, checksum_target, ChecksumKind, parse_maven_path, parse_snapshot_metadata_path};
+use crate::maven::pending_deploys::{ChecksumOutcome, PendingDeploys};
+use crate::maven::project_info::ProjectInfo;
+use crate::maven::remote_repo::{ArtifactProvenance, directory_prefix, DummyRemoteRepoMetadataStore, GetArtifactDecision, local_directory_listing, local_snapshot_versions, RemoteMavenRepo, RemoteRepoMetadataStore, render_local_artifact_metadata};
+use crate::maven::snapshot_retention::{purge_snapshot_builds, SnapshotRetentionPolicy};
+use crate::maven::version_list::VersionListOptions;
+use crate::maven::version_resolution::is_snapshot;
+use crate::util::blob::{Blob, BlobStat};
+use crate::util::content_hooks::{ContentHooks, ContentStream};
+
+/// checksum files and metadata updates are read into memory
+const MAX_SMALL_FILE_SIZE: usize = 1024*1024;
+
+/// How long an upload waits for its checksum, and a deploy for its metadata update
+const DEFAULT_DEPLOY_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// frozen clones are offline, so their upstream is never contacted
+const FROZEN_CLONE_UPSTREAM: &str = "http://frozen-clone.invalid/";
+
+/// The result of a successful deploy request
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum DeployOutcome {
+    /// the data is stored, but held until its checksum arrives, see [PendingDeploys]
+    Pending,
+    /// the checksum matched, the file waits for the rest of its deploy, see [DeployTransactions]
+    Staged,
+    /// a metadata update completed one or more deploys, and these artifacts are visible now
+    Committed(Vec<MavenArtifactRef>),
+    /// the request was valid but had no effect, e.g. a second checksum for a staged file
+    Ignored,
+    /// the deploy is refused, e.g. because it would overwrite a release
+    Conflict(String),
+    /// the request is invalid, e.g. a checksum does not match the uploaded data
+    Invalid(String),
+}
+
+/// The result of a delete request
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum DeleteOutcome {
+    /// these artifacts were removed
+    Deleted(Vec<MavenArtifactRef>),
+    NotFound,
+    /// the delete is refused, e.g. because it would remove a release
+    Conflict(String),
+}
+
+/// A repository that artifacts are deployed to (e.g. by `mvn deploy`) rather than fetched from
+///  upstream. A deploy becomes visible only when it is complete: each file is held until its
+///  checksum arrives, and the files of a deploy are registered together when Maven uploads the
+///  metadata update.
+pub struct HostedMavenRepo {
+    name: String,
+    blob_storage: Arc<dyn BlobStorage<Uuid>>,
+    metadata_store: Arc<dyn RemoteRepoMetadataStore>,
+    /// releases are immutable by default, so that a published version always refers to the same content
+    allow_release_redeploy: bool,
+    content_hooks: ContentHooks,
+    deploy_timeout: Duration,
+    pending_deploys: PendingDeploys,
+    deploy_transactions: DeployTransactions,
+    snapshot_retention: SnapshotRetentionPolicy,
+    checksums: RepositoryChecksums,
+    /// set once a frozen clone shares the blobs of deployed artifacts, which are left to garbage
+    ///  collection from then on rather than deleted with their artifacts
+    blobs_shared: AtomicBool,
+}
+impl HostedMavenRepo {
+    pub fn new(name: String, blob_storage: Arc<dyn BlobStorage<Uuid>>, metadata_store: Arc<dyn RemoteRepoMetadataStore>) -> HostedMavenRepo {
+        HostedMavenRepo {
+            name,
+            blob_storage,
+            metadata_store,
+            allow_release_redeploy: false,
+            content_hooks: ContentHooks::new(),
+            deploy_timeout: DEFAULT_DEPLOY_TIMEOUT,
+            pending_deploys: PendingDeploys::new(DEFAULT_DEPLOY_TIMEOUT),
+            deploy_transactions: DeployTransactions::new(DEFAULT_DEPLOY_TIMEOUT),
+            snapshot_retention: Default::default(),
+            checksums: Default::default(),
+            blobs_shared: AtomicBool::new(false),
+        }
+    }
+
+    pub fn with_release_redeploy(self, allow_release_redeploy: bool) -> HostedMavenRepo {
+        HostedMavenRepo {
+            allow_release_redeploy,
+            ..self
+        }
+    }
+
+    /// Hooks are applied to deployed data before it is stored
+    pub fn with_content_hooks(self, content_hooks: ContentHooks) -> HostedMavenRepo {
+        HostedMavenRepo {
+            content_hooks,
+            ..self
+        }
+    }
+
+    pub fn with_deploy_timeout(self, timeout: Duration) -> HostedMavenRepo {
+        HostedMavenRepo {
+            deploy_timeout: timeout,
+            pending_deploys: PendingDeploys::new(timeout),
+            deploy_transactions: DeployTransactions::new(timeout),
+            ..self
+        }
+    }
+
+    pub fn with_snapshot_retention(self, snapshot_retention: SnapshotRetentionPolicy) -> HostedMavenRepo {
+        HostedMavenRepo {
+            snapshot_retention,
+            ..self
+        }
+    }
+
+    /// The checksum files the repository serves, strongest first. Uploaded checksum files are
+    ///  verified regardless.
+    pub fn with_checksums(self, checksum_kinds: Vec<ChecksumKind>) -> HostedMavenRepo {
+        HostedMavenRepo {
+            checksums: RepositoryChecksums::new(checksum_kinds),
+            ..self
+        }
+    }
+
+    pub fn checksums(&self) -> &RepositoryChecksums {
+        &self.checksums
+    }
+
+    /// How long a deploy may take from the first upload to the metadata update
+    pub fn deploy_timeout(&self) -> Duration {
+        self.deploy_timeout
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn find_local(&self, artifact_ref: &MavenArtifactRef) -> anyhow::Result<Option<(Uuid, ArtifactProvenance)>> {
+        match self.metadata_store.decide_get_artifact(artifact_ref).await? {
+            GetArtifactDecision::Local { blob_key, provenance } => Ok(Some((blob_key, provenance))),
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns None if the artifact was not deployed (or its deploy is not complete)
+    pub async fn get_artifact(&self, artifact_ref: &MavenArtifactRef) -> anyhow::Result<Option<Blob>> {
+        let (blob_key, provenance) = match self.find_local(artifact_ref).await? {
+            Some(local) => local,
+            None => return Ok(None),
+        };
+        match self.blob_storage.get(&blob_key).await? {
+            Some(blob) => Ok(Some(Blob {
+                last_modified: Some(provenance.last_modified),
+                ..blob
+            })),
+            None => Err(anyhow!("blob {} for {} not found", blob_key, as_maven_path(artifact_ref))),
+        }
+    }
+
+    pub async fn get_artifact_stat(&self, artifact_ref: &MavenArtifactRef) -> anyhow::Result<Option<BlobStat>> {
+        let (blob_key, provenance) = match self.find_local(artifact_ref).await? {
+            Some(local) => local,
+            None => return Ok(None),
+        };
+        match self.blob_storage.stat(&blob_key).await? {
+            Some(stat) => Ok(Some(BlobStat {
+                last_modified: Some(provenance.last_modified),
+                ..stat
+            })),
+            None => Err(anyhow!("blob {} for {} not found", blob_key, as_maven_path(artifact_ref))),
+        }
+    }
+
+    /// The content of an artifact's checksum file, i.e. its hex encoded checksum. Returns None if
+    ///  the artifact was not deployed, or if the repository does not serve this kind of checksum.
+    pub async fn get_artifact_checksum(&self, artifact_ref: &MavenArtifactRef, kind: ChecksumKind) -> anyhow::Result<Option<String>> {
+        let (blob_key, _) = match self.find_local(artifact_ref).await? {
+            Some(local) => local,
+            None => return Ok(None),
+        };
+        let stat = self.blob_storage.stat(&blob_key).await?
+            .ok_or_else(|| anyhow!("blob {} for {} not found", blob_key, as_maven_path(artifact_ref)))?;
+        self.checksums.served(stat.md5, stat.sha1, kind, || self.blob_data(blob_key)).await
+    }
+
+    async fn blob_data(&self, blob_key: Uuid) -> anyhow::Result<ContentStream> {
+        match self.blob_storage.get(&blob_key).await? {
+            Some(blob) => Ok(blob.data),
+            None => Err(anyhow!("blob {} not found", blob_key)),
+        }
+    }
+
+    /// The version level 'maven-metadata.xml' for a snapshot version, generated from deployed
+    ///  builds. Returns None if there is no deployed build.
+    pub async fn get_snapshot_metadata(&self, group_id: &MavenGroupId, artifact_id: &MavenArtifactId, version: &str) -> anyhow::Result<Option<String>> {
+        let candidates = local_snapshot_versions(self.metadata_store.as_ref(), group_id, artifact_id, version).await?;
+        let snapshot_versions = SnapshotVersion::latest_per_file(candidates, version);
+        if snapshot_versions.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(render_snapshot_metadata(&group_id.0, &artifact_id.0, version, &snapshot_versions)))
+    }
+
+    /// The artifact level 'maven-metadata.xml', generated from deployed versions. Returns None if
+    ///  nothing was deployed for the artifact.
+    pub async fn get_artifact_metadata_xml(&self, metadata_path: &ArtifactMetadataPath, version_list: &VersionListOptions) -> anyhow::Result<Option<String>> {
+        render_local_artifact_metadata(self.metadata_store.as_ref(), metadata_path, Default::default(), version_list).await
+    }
+
+    /// Handles an uploaded file, which can be an artifact, a checksum file or a metadata update.
+    ///  'path' is relative to the repository root.
+    pub async fn deploy(&self, path: &str, data: ContentStream) -> anyhow::Result<DeployOutcome> {
+        self.deploy_with_size(path, data, None).await
+    }
+
+    /// Like [HostedMavenRepo::deploy], with the upload's length if the client sent it, see
+    ///  [BlobStorage::insert_with_size]
+    pub async fn deploy_with_size(&self, path: &str, data: ContentStream, expected_size: Option<u64>) -> anyhow::Result<DeployOutcome> {
+        Ok(self.deploy_checked(path, data, expected_size).await?.0)
+    }
+
+    /// Like [HostedMavenRepo::deploy_with_size], also returning how the generated metadata fails
+    ///  to reflect a deploy that was committed, see [MetadataInconsistency]
+    pub async fn deploy_checked(&self, path: &str, data: ContentStream, expected_size: Option<u64>) -> anyhow::Result<(DeployOutcome, Vec<MetadataInconsistency>)> {
+        if path.ends_with("/maven-metadata.xml") {
+            // the metadata is generated from deployed artifacts, so the upload only marks the end of a deploy
+            read_bounded(data, MAX_SMALL_FILE_SIZE).await?;
+            return self.complete_deploys(path).await;
+        }
+        self.deploy_file(path, data, expected_size).await
+            .map(|outcome| (outcome, vec![]))
+    }
+
+    async fn deploy_file(&self, path: &str, data: ContentStream, expected_size: Option<u64>) -> anyhow::Result<DeployOutcome> {
+        if path.contains("/maven-metadata.xml.") {
+            return Ok(DeployOutcome::Ignored);
+        }
+
+        let artifact_ref = match parse_maven_path(path) {
+            Ok(artifact_ref) => artifact_ref,
+            Err(e) => return Ok(DeployOutcome::Invalid(e.to_string())),
+        };
+
+        if let Some((kind, target)) = checksum_target(&artifact_ref) {
+            let checksum = read_bounded(data, MAX_SMALL_FILE_SIZE).await?;
+            return self.add_checksum(&target, kind, &String::from_utf8_lossy(&checksum)).await;
+        }
+
+        if let MavenVersion::Release(_) = artifact_ref.coordinates.version {
+            if !self.allow_release_redeploy && self.exists(&artifact_ref).await? {
+                return Ok(DeployOutcome::Conflict(format!("{} exists already, and releases can not be redeployed", path)));
+            }
+        }
+
+        let expected_size = expected_size.filter(|_| self.content_hooks.is_empty());
+        let data = self.content_hooks.apply(path, data);
+        let blob_key = self.blob_storage.insert_with_size(data, expected_size).await?;
+        let stat = self.blob_storage.stat(&blob_key).await?
+            .ok_or_else(|| anyhow!("blob {} was stored but not found", blob_key))?;
+        let (md5, sha1) = match (stat.md5, stat.sha1) {
+            (Some(md5), Some(sha1)) => (md5, sha1),
+            _ => return Err(anyhow!("blob storage did not provide checksums for {}", blob_key)),
+        };
+
+        if let Some(replaced) = self.pending_deploys.add_upload(&artifact_ref, blob_key, md5, sha1) {
+            self.delete_blob(&replaced).await;
+        }
+        Ok(DeployOutcome::Pending)
+    }
+
+    /// Whether a deploy of the given version would be refused because it is a release that was
+    ///  deployed already or is being deployed. This is stricter than [HostedMavenRepo::deploy], which
+    ///  refuses only files that exist already.
+    pub async fn is_release_deployed(&self, coordinates: &MavenCoordinates) -> anyhow::Result<bool> {
+        if self.allow_release_redeploy || is_snapshot(coordinates.version.unqualified()) {
+            return Ok(false);
+        }
+        if self.deploy_transactions.open_coordinates().contains(coordinates) {
+            return Ok(true);
+        }
+        Ok(self.metadata_store.get_local_artifacts().await?
+            .iter()
+            .any(|artifact_ref| &artifact_ref.coordinates == coordinates))
+    }
+
+    /// Whether the artifact was deployed, or is part of a deploy in progress
+    async fn exists(&self, artifact_ref: &MavenArtifactRef) -> anyhow::Result<bool> {
+        Ok(self.deploy_transactions.staged_blob(artifact_ref).is_some()
+            || self.find_local(artifact_ref).await?.is_some())
+    }
+
+    async fn add_checksum(&self, artifact_ref: &MavenArtifactRef, kind: ChecksumKind, checksum: &str) -> anyhow::Result<DeployOutcome> {
+        let outcome = match kind.is_stored() {
+            true => self.pending_deploys.add_checksum(artifact_ref, kind, checksum),
+            false => self.verify_pending_upload(artifact_ref, kind, checksum).await?,
+        };
+        match outcome {
+            Ok(ChecksumOutcome::Committed(blob_key)) => {
+                if let Some(replaced) = self.deploy_transactions.add_file(artifact_ref, blob_key) {
+                    self.delete_blob(&replaced).await;
+                }
+                Ok(DeployOutcome::Staged)
+            }
+            Ok(ChecksumOutcome::Rejected(blob_key)) => {
+                self.delete_blob(&blob_key).await;
+                Ok(DeployOutcome::Invalid(format!("{} checksum does not match the uploaded data for {}", kind.suffix(), as_maven_path(artifact_ref))))
+            }
+            Err(_) => {
+                // Maven sends several checksums per file, only the first one resolves the pending upload
+                let blob_key = match self.deploy_transactions.staged_blob(artifact_ref) {
+                    Some(blob_key) => Some(blob_key),
+                    None => self.find_local(artifact_ref).await?.map(|(blob_key, _)| blob_key),
+                };
+                let stat = match blob_key {
+                    Some(blob_key) => self.blob_storage.stat(&blob_key).await?.map(|stat| (blob_key, stat)),
+                    None => None,
+                };
+                match stat {
+                    None => Ok(DeployOutcome::Invalid(format!("no upload for checksum of {}", as_maven_path(artifact_ref)))),
+                    Some((blob_key, stat)) if self.checksum_matches(blob_key, &stat, kind, checksum).await? => Ok(DeployOutcome::Ignored),
+                    Some(_) => Ok(DeployOutcome::Invalid(format!("{} checksum does not match the uploaded data for {}", kind.suffix(), as_maven_path(artifact_ref)))),
+                }
+            }
+        }
+    }
+
+    /// Checksums that are not stored with blobs (e.g. from Maven configured to upload only SHA-256
+    ///  and SHA-512) are verified by computing them from the pending upload's data. The inner
+    ///  result fails if there is no pending upload.
+    async fn verify_pending_upload(&self, artifact_ref: &MavenArtifactRef, kind: ChecksumKind, checksum: &str) -> anyhow::Result<anyhow::Result<ChecksumOutcome>> {
+        let (blob_key, sha1) = match self.pending_deploys.pending_upload(artifact_ref) {
+            Some(upload) => upload,
+            None => return Ok(Err(anyhow!("no pending upload for checksum"))),
+        };
+        let actual = self.checksums.computed(sha1, kind, || self.blob_data(blob_key)).await?;
+        let checksum = checksum.split_whitespace().next().unwrap_or("");
+        Ok(self.pending_deploys.resolve(artifact_ref, blob_key, actual.eq_ignore_ascii_case(checksum)))
+    }
+
+    /// 'checksum' is in the format of a Maven checksum file, i.e. hex optionally followed by a file name
+    async fn checksum_matches(&self, blob_key: Uuid, stat: &BlobStat, kind: ChecksumKind, checksum: &str) -> anyhow::Result<bool> {
+        let checksum = checksum.split_whitespace().next().unwrap_or("");
+        Ok(match kind {
+            ChecksumKind::Sha1 => <[u8;20]>::from_hex(checksum).ok().is_some_and(|c| Some(c) == stat.sha1),
+            ChecksumKind::Md5 => <[u8;16]>::from_hex(checksum).ok().is_some_and(|c| Some(c) == stat.md5),
+            ChecksumKind::Sha256 | ChecksumKind::Sha512 => match stat.sha1 {
+                Some(sha1) => self.checksums.computed(sha1, kind, || self.blob_data(blob_key)).await?.eq_ignore_ascii_case(checksum),
+                None => false,
+            },
+        })
+    }
+
+    /// Completes the open deploys a metadata update refers to: a version level update completes
+    ///  deploys of that (snapshot) version, an artifact level update completes all of the artifact's
+    ///  deploys.
+    async fn complete_deploys(&self, metadata_path: &str) -> anyhow::Result<(DeployOutcome, Vec<MetadataInconsistency>)> {
+        let (group_id, artifact_id, version) = match parse_snapshot_metadata_path(metadata_path) {
+            Some(p) => (p.group_id, p.artifact_id, Some(p.version)),
+            None => {
+                let directory = &metadata_path[..metadata_path.len() - "/maven-metadata.xml".len()];
+                match directory.rsplit_once('/') {
+                    Some((group_id, artifact_id)) if !group_id.is_empty() => (MavenGroupId(group_id.replace('/', ".")), MavenArtifactId(artifact_id.to_string()), None),
+                    _ => return Ok((DeployOutcome::Invalid(format!("not a valid metadata path: {}", metadata_path)), vec![])),
+                }
+            }
+        };
+
+        let open_coordinates = self.deploy_transactions.open_coordinates().into_iter()
+            .filter(|coordinates| coordinates.group_id == group_id
+                && coordinates.artifact_id == artifact_id
+                && version.as_ref().map(|v| v == coordinates.version.unqualified()).unwrap_or(true))
+            .collect::<Vec<_>>();
+        if open_coordinates.is_empty() {
+            return Ok((DeployOutcome::Committed(vec![]), vec![]));
+        }
+        let snapshot_versions = open_coordinates.iter()
+            .map(|coordinates| coordinates.version.unqualified().to_string())
+            .filter(|version| is_snapshot(version))
+            .collect::<BTreeSet<_>>();
+        let before = self.generated_metadata(&group_id, &artifact_id, &snapshot_versions).await?;
+
+        let mut committed = Vec::new();
+        for coordinates in open_coordinates {
+            let files = match self.deploy_transactions.complete(&coordinates) {
+                Ok(files) => files,
+                Err(e) => {
+                    // stays open until its pom arrives or it times out
+                    debug!("not completing deploy: {}", e);
+                    continue;
+                }
+            };
+
+            let provenance = provenance_now();
+            for (artifact_ref, blob_key) in files {
+                if let Some((replaced, _)) = self.find_local(&artifact_ref).await? {
+                    if replaced != blob_key {
+                        self.release_blob(&replaced).await;
+                    }
+                }
+                self.metadata_store.register_artifact(&artifact_ref, &blob_key, &provenance).await?;
+                committed.push(artifact_ref);
+            }
+        }
+        if committed.is_empty() {
+            return Ok((DeployOutcome::Committed(committed), vec![]));
+        }
+
+        let inconsistencies = self.check_committed_metadata(&group_id, &artifact_id, &snapshot_versions, &before, &committed).await?;
+        Ok((DeployOutcome::Committed(committed), inconsistencies))
+    }
+
+    /// The artifact level metadata and the version level metadata of the given snapshot versions,
+    ///  as they are served
+    async fn generated_metadata(&self, group_id: &MavenGroupId, artifact_id: &MavenArtifactId, snapshot_versions: &BTreeSet<String>) -> anyhow::Result<GeneratedMetadata> {
+        let metadata_path = ArtifactMetadataPath {
+            group_id: group_id.clone(),
+            artifact_id: artifact_id.clone(),
+            checksum: None,
+        };
+        let artifact = self.get_artifact_metadata_xml(&metadata_path, &Default::default()).await?;
+        let mut snapshots = BTreeMap::new();
+        for version in snapshot_versions {
+            snapshots.insert(version.clone(), self.get_snapshot_metadata(group_id, artifact_id, version).await?);
+        }
+        Ok(GeneratedMetadata { artifact, snapshots })
+    }
+
+    /// Checks that the metadata reflects a committed deploy, see [MetadataInconsistency]. Versions
+    ///  missing from the artifact level metadata are registered again, other inconsistencies can
+    ///  not be corrected automatically and are returned.
+    async fn check_committed_metadata(&self, group_id: &MavenGroupId, artifact_id: &MavenArtifactId, snapshot_versions: &BTreeSet<String>, before: &GeneratedMetadata, committed: &[MavenArtifactRef]) -> anyhow::Result<Vec<MetadataInconsistency>> {
+        let versions = committed.iter()
+            .map(|artifact_ref| artifact_ref.coordinates.version.unqualified().to_string())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        let mut after = self.generated_metadata(group_id, artifact_id, snapshot_versions).await?;
+        let missing_versions = check_artifact_metadata(before.artifact.as_deref(), after.artifact.as_deref(), &versions).into_iter()
+            .filter_map(|inconsistency| match inconsistency {
+                MetadataInconsistency::VersionNotListed { version } => Some(version),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if !missing_versions.is_empty() {
+            for artifact_ref in committed.iter().filter(|a| missing_versions.iter().any(|v| v == a.coordinates.version.unqualified())) {
+                if let Some((blob_key, _)) = self.find_local(artifact_ref).await? {
+                    warn!("re-registering {} in {}, its version was missing from the metadata", as_maven_path(artifact_ref), self.name);
+                    self.metadata_store.register_artifact(artifact_ref, &blob_key, &provenance_now()).await?;
+                }
+            }
+            after = self.generated_metadata(group_id, artifact_id, snapshot_versions).await?;
+        }
+
+        let mut inconsistencies = check_artifact_metadata(before.artifact.as_deref(), after.artifact.as_deref(), &versions);
+        for version in snapshot_versions {
+            let deployed = committed.iter()
+                .filter(|artifact_ref| artifact_ref.coordinates.version.unqualified() == version
+                    // checksums and signatures are not listed separately
+                    && !artifact_ref.file_extension.trim_start_matches('.').contains('.'))
+                .filter_map(|artifact_ref| SnapshotVersion::for_artifact(artifact_ref, String::new()))
+                .collect::<Vec<_>>();
+            let snapshot_before = before.snapshots.get(version).cloned().flatten();
+            let snapshot_after = after.snapshots.get(version).cloned().flatten();
+            inconsistencies.extend(check_snapshot_metadata(version, snapshot_before.as_deref(), snapshot_after.as_deref(), &deployed));
+        }
+        for inconsistency in &inconsistencies {
+            warn!("inconsistent metadata for {}:{} in {} after deploy: {}", group_id.0, artifact_id.0, self.name, inconsistency);
+        }
+        Ok(inconsistencies)
+    }
+
+    /// Removes a deployed artifact, or all deployed artifacts in a directory. Releases are
+    ///  immutable unless they can be redeployed, so deleting them is refused.
+    pub async fn delete(&self, path: &str) -> anyhow::Result<DeleteOutcome> {
+        let path = path.trim_end_matches('/');
+        if path.is_empty() {
+            return Ok(DeleteOutcome::Conflict("the repository root can not be deleted".to_string()));
+        }
+        let prefix = directory_prefix(path);
+        let artifacts = self.metadata_store.get_local_artifacts().await?
+            .into_iter()
+            .filter(|artifact_ref| {
+                let artifact_path = as_maven_path(artifact_ref);
+                artifact_path == path || artifact_path.starts_with(&prefix)
+            })
+            .collect::<Vec<_>>();
+        if artifacts.is_empty() {
+            return Ok(DeleteOutcome::NotFound);
+        }
+
+        if !self.allow_release_redeploy {
+            if let Some(release) = artifacts.iter().find(|artifact_ref| !is_snapshot(artifact_ref.coordinates.version.unqualified())) {
+                return Ok(DeleteOutcome::Conflict(format!("{} is a release, and releases can not be deleted", as_maven_path(release))));
+            }
+        }
+
+        for artifact_ref in &artifacts {
+            if let Some(blob_key) = self.metadata_store.unregister_artifact(artifact_ref).await? {
+                self.release_blob(&blob_key).await;
+            }
+        }
+        Ok(DeleteOutcome::Deleted(artifacts))
+    }
+
+    /// 'directory_path' is relative to the repository root, see [RemoteMavenRepo::get_directory_listing](crate::maven::remote_repo::RemoteMavenRepo::get_directory_listing)
+    pub async fn get_directory_listing(&self, directory_path: &str) -> anyhow::Result<DirectoryListing> {
+        local_directory_listing(self.metadata_store.as_ref(), &directory_prefix(directory_path)).await
+    }
+
+    /// Discards uploads that did not receive their checksum, and deploys that did not receive
+    ///  their metadata update, within the timeout. Returns the number of discarded files.
+    pub async fn remove_timed_out_deploys(&self) -> usize {
+        let mut timed_out = self.pending_deploys.remove_timed_out();
+        timed_out.append(&mut self.deploy_transactions.remove_timed_out());
+        for (artifact_ref, blob_key) in &timed_out {
+            warn!("discarding incomplete deploy of {} to {}", as_maven_path(artifact_ref), self.name);
+            self.delete_blob(blob_key).await;
+        }
+        timed_out.len()
+    }
+
+    /// Removes snapshot builds that the repository's [SnapshotRetentionPolicy] does not keep,
+    ///  returning the removed artifacts
+    pub async fn purge_snapshots(&self) -> anyhow::Result<Vec<MavenArtifactRef>> {
+        let mut result = Vec::new();
+        for (artifact_ref, blob_key) in purge_snapshot_builds(self.metadata_store.as_ref(), &self.snapshot_retention).await? {
+            self.release_blob(&blob_key).await;
+            result.push(artifact_ref);
+        }
+        Ok(result)
+    }
+
+    async fn delete_blob(&self, blob_key: &Uuid) {
+        if let Err(e) = self.blob_storage.delete(blob_key).await {
+            // orphans are cleaned up eventually by fsck
+            warn!("failed to delete blob {}: {}", blob_key, e);
+        }
+    }
+
+    /// Blobs of deploys that are in progress (waiting for a checksum or for the deploy to complete)
+    ///  are referenced as well, they are not orphans
+    /// Deletes the blob of an artifact that is no longer deployed, unless frozen clones may refer
+    ///  to it
+    async fn release_blob(&self, blob_key: &Uuid) {
+        if !self.blobs_shared.load(Ordering::Acquire) {
+            self.delete_blob(blob_key).await;
+        }
+    }
+
+    /// Creates a read-only copy of the completed deploys, served like a frozen clone of a remote
+    ///  repository (see [RemoteMavenRepo::frozen_clone]). Blobs are shared rather than copied.
+    pub async fn frozen_clone(&self) -> anyhow::Result<RemoteMavenRepo> {
+        // before the snapshot, so that no blob in it is deleted in the meantime
+        self.blobs_shared.store(true, Ordering::Release);
+        let snapshot = self.metadata_store.snapshot().await?;
+
+        Ok(RemoteMavenRepo::new(FROZEN_CLONE_UPSTREAM.to_string(), self.blob_storage.clone(), Arc::new(DummyRemoteRepoMetadataStore::from_snapshot(snapshot)))?
+            .with_name(self.name.clone())
+            .with_checksums(self.checksums.kinds().to_vec())
+            .with_offline(true))
+    }
+
+    pub async fn is_blob_referenced(&self, blob_key: &Uuid) -> anyhow::Result<bool> {
+        if self.pending_deploys.contains_blob(blob_key) || self.deploy_transactions.contains_blob(blob_key) {
+            return Ok(true);
+        }
+        self.metadata_store.is_blob_referenced(blob_key).await
+    }
+
+    pub async fn get_project_info(&self, artifact_ref: &MavenArtifactRef) -> anyhow::Result<Option<ProjectInfo>> {
+        self.metadata_store.get_project_info(artifact_ref).await
+    }
+
+    pub async fn cache_project_info(&self, artifact_ref: &MavenArtifactRef, project_info: ProjectInfo) -> anyhow::Result<()> {
+        self.metadata_store.cache_project_info(artifact_ref, project_info).await
+    }
+}
+
+async fn read_bounded(mut data: ContentStream, max_len: usize) -> anyhow::Result<Bytes> {
+    let mut result = BytesMut::new();
+    while let Some(chunk) = data.next().await {
+        result.extend_from_slice(&chunk?);
+        if result.len() > max_len {
+            return Err(anyhow!("request body exceeds {} bytes", max_len));
+        }
+    }
+    Ok(result.freeze())
+}
+
+/// The generated 'maven-metadata.xml' files of an artifact at some point
+struct GeneratedMetadata {
+    artifact: Option<String>,
+    /// by snapshot version
+    snapshots: BTreeMap<String, Option<String>>,
+}
+
+/// deployed files are fetched and modified when they are committed
+fn provenance_now() -> ArtifactProvenance {
+    let now = SystemTime::now();
+    ArtifactProvenance {
+        fetched: now,
+        last_modified: now,
+        upstream_headers: vec![],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use sha1::{Digest, Sha1};
+
+    use crate::blob::transient_blob_storage::TransientBlobStorage;
+    use crate::maven::paths::parse_artifact_metadata_path;
+    use crate::maven::remote_repo::DummyRemoteRepoMetadataStore;
+    use super::*;
+
+    fn data(bytes: impl AsRef<[u8]>) -> ContentStream {
+        Box::pin(futures::stream::iter(vec![Ok(Bytes::copy_from_slice(bytes.as_ref()))]))
+    }
+
+    fn sha1(bytes: &[u8]) -> String {
+        hex::encode(Sha1::digest(bytes))
+    }
+
+    fn repo() -> HostedMavenRepo {
+        HostedMavenRepo::new("internal".to_string(), Arc::new(TransientBlobStorage::new()), Arc::new(DummyRemoteRepoMetadataStore::new()))
+    }
+
+    #[tokio::test]
+    async fn test_deploy_becomes_visible_with_metadata_update() {
+        let repo = repo();
+        let jar = parse_maven_path("com/example/lib/1.0/lib-1.0.jar").unwrap();
+
+        assert_eq!(repo.deploy("com/example/lib/1.0/lib-1.0.jar", data(b"jar")).await.unwrap(), DeployOutcome::Pending);
+        assert_eq!(repo.deploy("com/example/lib/1.0/lib-1.0.jar.sha1", data(sha1(b"jar"))).await.unwrap(), DeployOutcome::Staged);
+        // further checksums are checked against the staged file
+        assert_eq!(repo.deploy("com/example/lib/1.0/lib-1.0.jar.sha1", data(sha1(b"jar"))).await.unwrap(), DeployOutcome::Ignored);
+        assert!(matches!(repo.deploy("com/example/lib/1.0/lib-1.0.jar.md5", data("0".repeat(32))).await.unwrap(), DeployOutcome::Invalid(_)));
+        assert_eq!(repo.deploy("com/example/lib/1.0/lib-1.0.pom", data(b"pom")).await.unwrap(), DeployOutcome::Pending);
+        assert_eq!(repo.deploy("com/example/lib/1.0/lib-1.0.pom.sha1", data(sha1(b"pom"))).await.unwrap(), DeployOutcome::Staged);
+        assert!(repo.get_artifact(&jar).await.unwrap().is_none());
+
+        match repo.deploy("com/example/lib/maven-metadata.xml", data(b"<metadata/>")).await.unwrap() {
+            DeployOutcome::Committed(artifacts) => assert_eq!(artifacts.len(), 2),
+            other => panic!("unexpected outcome {:?}", other),
+        }
+        assert!(repo.get_artifact(&jar).await.unwrap().is_some());
+        assert_eq!(repo.get_artifact_checksum(&jar, ChecksumKind::Sha1).await.unwrap(), Some(sha1(b"jar")));
+
+        let metadata_path = parse_artifact_metadata_path("com/example/lib/maven-metadata.xml").unwrap();
+        let xml = repo.get_artifact_metadata_xml(&metadata_path, &Default::default()).await.unwrap().unwrap();
+        assert!(xml.contains("<release>1.0</release>"));
+        assert!(xml.contains("<version>1.0</version>"));
+        assert!(repo.get_artifact_metadata_xml(&parse_artifact_metadata_path("com/example/other/maven-metadata.xml").unwrap(), &Default::default()).await.unwrap().is_none());
+
+        // releases are immutable
+        assert!(matches!(repo.deploy("com/example/lib/1.0/lib-1.0.jar", data(b"other")).await.unwrap(), DeployOutcome::Conflict(_)));
+        assert!(repo.is_release_deployed(&jar.coordinates).await.unwrap());
+        assert!(!repo.is_release_deployed(&parse_maven_path("com/example/lib/1.1/lib-1.1.jar").unwrap().coordinates).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_mismatching_checksum_discards_upload() {
+        let repo = repo();
+        repo.deploy("com/example/lib/1.0/lib-1.0.jar", data(b"jar")).await.unwrap();
+        assert!(matches!(repo.deploy("com/example/lib/1.0/lib-1.0.jar.sha1", data(sha1(b"other"))).await.unwrap(), DeployOutcome::Invalid(_)));
+
+        // nothing is pending any longer, so the release can be deployed again
+        assert_eq!(repo.deploy("com/example/lib/1.0/lib-1.0.jar", data(b"jar")).await.unwrap(), DeployOutcome::Pending);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_metadata_for_deployed_builds() {
+        let repo = repo();
+        for file in ["lib-1.0-SNAPSHOT-20231114.221320-1.pom", "lib-1.0-SNAPSHOT-sources-20231114.221320-1.jar"] {
+            let path = format!("com/example/lib/1.0-SNAPSHOT/{}", file);
+            repo.deploy(&path, data(b"x")).await.unwrap();
+            repo.deploy(&format!("{}.sha1", path), data(sha1(b"x"))).await.unwrap();
+        }
+        repo.deploy("com/example/lib/1.0-SNAPSHOT/maven-metadata.xml", data(b"<metadata/>")).await.unwrap();
+
+        let xml = repo.get_snapshot_metadata(&MavenGroupId("com.example".to_string()), &MavenArtifactId("lib".to_string()), "1.0-SNAPSHOT").await.unwrap().unwrap();
+        assert!(xml.contains("<classifier>sources</classifier>"));
+        assert!(xml.contains("<value>1.0-20231114.221320-1</value>"));
+    }
+
+    #[tokio::test]
+    async fn test_inconsistent_snapshot_metadata() {
+        let repo = repo();
+        let mut inconsistencies = Vec::new();
+        for file in ["lib-1.0-SNAPSHOT-20231114.221320-2.pom", "lib-1.0-SNAPSHOT-20231115.080000-1.pom"] {
+            let path = format!("com/example/lib/1.0-SNAPSHOT/{}", file);
+            repo.deploy(&path, data(b"x")).await.unwrap();
+            repo.deploy(&format!("{}.sha1", path), data(sha1(b"x"))).await.unwrap();
+            let (outcome, found) = repo.deploy_checked("com/example/lib/1.0-SNAPSHOT/maven-metadata.xml", data(b"<metadata/>"), None).await.unwrap();
+            assert!(matches!(outcome, DeployOutcome::Committed(artifacts) if artifacts.len() == 1));
+            inconsistencies.push(found);
+        }
+
+        // the second deploy restarted the build numbers, e.g. because its client ignored the metadata
+        assert!(inconsistencies[0].is_empty());
+        assert_eq!(inconsistencies[1], vec![MetadataInconsistency::BuildNumberDecreased { file: "pom".to_string(), before: 2, after: 1 }]);
+    }
+
+    #[tokio::test]
+    async fn test_purge_snapshots() {
+        let repo = repo().with_snapshot_retention(SnapshotRetentionPolicy { keep_builds: Some(1), max_age_days: None });
+        for path in ["com/example/lib/1.0-SNAPSHOT/lib-1.0-SNAPSHOT-20231114.221320-1.pom", "com/example/lib/1.0-SNAPSHOT/lib-1.0-SNAPSHOT-20231115.080000-2.pom"] {
+            repo.deploy(path, data(b"x")).await.unwrap();
+            repo.deploy(&format!("{}.sha1", path), data(sha1(b"x"))).await.unwrap();
+        }
+        repo.deploy("com/example/lib/1.0-SNAPSHOT/maven-metadata.xml", data(b"<metadata/>")).await.unwrap();
+
+        assert_eq!(repo.purge_snapshots().await.unwrap(), vec![parse_maven_path("com/example/lib/1.0-SNAPSHOT/lib-1.0-SNAPSHOT-20231114.221320-1.pom").unwrap()]);
+        let xml = repo.get_snapshot_metadata(&MavenGroupId("com.example".to_string()), &MavenArtifactId("lib".to_string()), "1.0-SNAPSHOT").await.unwrap().unwrap();
+        assert!(xml.contains("<value>1.0-20231115.080000-2</value>"));
+        assert!(!xml.contains("20231114.221320"));
+    }
+
+    #[tokio::test]
+    async fn test_blobs_of_deploys_in_progress_are_referenced() {
+        let repo = repo();
+        let jar = parse_maven_path("com/example/lib/1.0/lib-1.0.jar").unwrap();
+
+        repo.deploy("com/example/lib/1.0/lib-1.0.jar", data(b"jar")).await.unwrap();
+        let (blob_key, _) = repo.pending_deploys.pending_upload(&jar).unwrap();
+        assert!(repo.is_blob_referenced(&blob_key).await.unwrap());
+
+        repo.deploy("com/example/lib/1.0/lib-1.0.jar.sha1", data(sha1(b"jar"))).await.unwrap();
+        assert_eq!(repo.deploy_transactions.staged_blob(&jar), Some(blob_key));
+        assert!(repo.is_blob_referenced(&blob_key).await.unwrap());
+
+        repo.deploy("com/example/lib/1.0/lib-1.0.pom", data(b"pom")).await.unwrap();
+        repo.deploy("com/example/lib/1.0/lib-1.0.pom.sha1", data(sha1(b"pom"))).await.unwrap();
+        repo.deploy("com/example/lib/maven-metadata.xml", data(b"<metadata/>")).await.unwrap();
+        assert!(repo.is_blob_referenced(&blob_key).await.unwrap());
+
+        assert!(!repo.is_blob_referenced(&Uuid::new_v4()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_delete() {
+        let repo = repo();
+        for path in ["com/example/lib/1.0-SNAPSHOT/lib-1.0-SNAPSHOT-20231114.221320-1.pom", "com/example/lib/1.0-SNAPSHOT/lib-1.0-SNAPSHOT-20231114.221320-1.jar", "com/example/lib/1.0/lib-1.0.pom"] {
+            repo.deploy(path, data(b"x")).await.unwrap();
+            repo.deploy(&format!("{}.sha1", path), data(sha1(b"x"))).await.unwrap();
+        }
+        repo.deploy("com/example/lib/maven-metadata.xml", data(b"<metadata/>")).await.unwrap();
+        assert_eq!(repo.get_directory_listing("com/example/lib").await.unwrap().entries.len(), 2);
+
+        let pom = parse_maven_path("com/example/lib/1.0-SNAPSHOT/lib-1.0-SNAPSHOT-20231114.221320-1.pom").unwrap();
+        assert_eq!(repo.delete("com/example/lib/1.0-SNAPSHOT/lib-1.0-SNAPSHOT-20231114.221320-1.pom").await.unwrap(), DeleteOutcome::Deleted(vec![pom.clone()]));
+        assert!(repo.get_artifact(&pom).await.unwrap().is_none());
+        assert_eq!(repo.delete("com/example/lib/1.0-SNAPSHOT/lib-1.0-SNAPSHOT-20231114.221320-1.pom").await.unwrap(), DeleteOutcome::NotFound);
+        assert!(matches!(repo.delete("com/example/lib/1.0-SNAPSHOT/").await.unwrap(), DeleteOutcome::Deleted(artifacts) if artifacts.len() == 1));
+
+        // directories are deleted only as a whole, so a release in them prevents the delete
+        assert!(matches!(repo.delete("com/example").await.unwrap(), DeleteOutcome::Conflict(_)));
+        assert!(matches!(repo.delete("").await.unwrap(), DeleteOutcome::Conflict(_)));
+        assert_eq!(repo.get_directory_listing("com/example/lib/").await.unwrap().entries.len(), 1);
+    }
+}
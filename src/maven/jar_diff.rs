@@ -0,0 +1,112 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::maven::jar_index::JarEntry;
+
+/// An entry that is in both jars, but with different content
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+pub struct ChangedJarEntry {
+    pub name: String,
+    pub from_size: u64,
+    pub to_size: u64,
+}
+
+/// How two jars differ by their entries' content, ignoring e.g. timestamps and entry order so
+///  that reproducible rebuilds compare as identical. Entries are sorted by name.
+#[derive(Clone, Eq, PartialEq, Debug, Default, Serialize)]
+pub struct JarDiff {
+    pub added: Vec<JarEntry>,
+    pub removed: Vec<JarEntry>,
+    pub changed: Vec<ChangedJarEntry>,
+    /// the number of entries with the same content in both jars
+    pub unchanged: usize,
+}
+impl JarDiff {
+    pub fn new(from: Vec<JarEntry>, to: Vec<JarEntry>) -> JarDiff {
+        let mut from = from.into_iter()
+            .map(|entry| (entry.name.clone(), entry))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut result = JarDiff::default();
+        let to = to.into_iter()
+            .map(|entry| (entry.name.clone(), entry))
+            .collect::<BTreeMap<_, _>>();
+        for (name, to_entry) in to {
+            match from.remove(&name) {
+                None => result.added.push(to_entry),
+                Some(from_entry) if has_same_content(&from_entry, &to_entry) => result.unchanged += 1,
+                Some(from_entry) => result.changed.push(ChangedJarEntry {
+                    name,
+                    from_size: from_entry.size,
+                    to_size: to_entry.size,
+                }),
+            }
+        }
+        result.removed = from.into_values().collect();
+        result
+    }
+
+    pub fn is_identical(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// hashes are compared if both entries have them, so that CRC-32 collisions are detected
+fn has_same_content(a: &JarEntry, b: &JarEntry) -> bool {
+    let same_hash = match (&a.sha256, &b.sha256) {
+        (Some(a), Some(b)) => a == b,
+        _ => true,
+    };
+    a.size == b.size && a.crc32 == b.crc32 && same_hash
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entry(name: &str, size: u64, crc32: u32, sha256: Option<&str>) -> JarEntry {
+        JarEntry {
+            name: name.to_string(),
+            size,
+            crc32,
+            sha256: sha256.map(|s| s.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let diff = JarDiff::new(
+            vec![
+                entry("b/B.class", 10, 1, None),
+                entry("a/A.class", 10, 1, Some("aa")),
+                entry("c/C.class", 10, 1, None),
+                entry("d/D.class", 10, 1, None),
+            ],
+            vec![
+                entry("a/A.class", 10, 1, Some("ab")),
+                entry("b/B.class", 10, 1, None),
+                entry("d/D.class", 12, 2, None),
+                entry("e/E.class", 10, 1, None),
+            ],
+        );
+        assert_eq!(diff.added, vec![entry("e/E.class", 10, 1, None)]);
+        assert_eq!(diff.removed, vec![entry("c/C.class", 10, 1, None)]);
+        assert_eq!(diff.changed, vec![
+            ChangedJarEntry { name: "a/A.class".to_string(), from_size: 10, to_size: 10 },
+            ChangedJarEntry { name: "d/D.class".to_string(), from_size: 10, to_size: 12 },
+        ]);
+        assert_eq!(diff.unchanged, 1);
+        assert!(!diff.is_identical());
+    }
+
+    #[test]
+    fn test_identical() {
+        let entries = vec![entry("a/A.class", 10, 1, Some("aa")), entry("b/B.class", 10, 1, None)];
+        let mut reordered = entries.clone();
+        reordered.reverse();
+        let diff = JarDiff::new(entries, reordered);
+        assert!(diff.is_identical());
+        assert_eq!(diff.unchanged, 2);
+    }
+}
@@ -0,0 +1,296 @@
+use std::io::Read;
+
+use anyhow::anyhow;
+use flate2::read::DeflateDecoder;
+use serde::Serialize;
+use sha2::{Digest as _, Sha256};
+
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x06054b50;
+const CENTRAL_DIRECTORY_HEADER: u32 = 0x02014b50;
+const LOCAL_FILE_HEADER: u32 = 0x04034b50;
+const ZIP64_EXTRA_FIELD: u16 = 0x0001;
+
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATED: u16 = 8;
+
+/// the end of central directory record is followed by a comment of at most this length
+const MAX_COMMENT_LEN: usize = 0xffff;
+
+/// entries are not hashed if they are larger than this uncompressed, guarding against zip bombs
+const MAX_HASHED_ENTRY_SIZE: u64 = 64*1024*1024;
+
+/// A file in a jar (or any other zip archive), as listed in its central directory
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+pub struct JarEntry {
+    pub name: String,
+    /// uncompressed
+    pub size: u64,
+    pub crc32: u32,
+    /// hex encoded SHA-256 of the uncompressed content, for classes if requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+/// Lists a jar's entries in the order of its central directory, without directories. Only
+///  the central directory is read unless 'hash_classes' is set, in which case '.class' entries
+///  are decompressed for their SHA-256.
+pub fn index_jar(data: &[u8], hash_classes: bool) -> anyhow::Result<Vec<JarEntry>> {
+    let mut result = Vec::new();
+    for entry in central_directory(data)? {
+        let sha256 = match hash_classes && entry.name.ends_with(".class") {
+            true => {
+                if entry.size > MAX_HASHED_ENTRY_SIZE {
+                    return Err(anyhow!("error reading {}: entry is too large to hash", entry.name));
+                }
+                let content = read_content(data, &entry)
+                    .map_err(|e| anyhow!("error reading {}: {}", entry.name, e))?;
+                Some(hex::encode(Sha256::digest(&content)))
+            }
+            false => None,
+        };
+        result.push(JarEntry { name: entry.name, size: entry.size, crc32: entry.crc32, sha256 });
+    }
+    Ok(result)
+}
+
+/// The uncompressed content of the entries whose names match, in the order of the central
+///  directory. Entries larger than 'max_size' are skipped.
+pub fn read_entries(data: &[u8], matches: impl Fn(&str) -> bool, max_size: u64) -> anyhow::Result<Vec<(String, Vec<u8>)>> {
+    let mut result = Vec::new();
+    for entry in central_directory(data)? {
+        if !matches(&entry.name) || entry.size > max_size {
+            continue;
+        }
+        let content = read_content(data, &entry)
+            .map_err(|e| anyhow!("error reading {}: {}", entry.name, e))?;
+        result.push((entry.name, content));
+    }
+    Ok(result)
+}
+
+struct CentralDirectoryEntry {
+    name: String,
+    method: u16,
+    crc32: u32,
+    compressed_size: u64,
+    size: u64,
+    local_header_offset: u64,
+}
+
+/// the central directory's file entries, without directories
+fn central_directory(data: &[u8]) -> anyhow::Result<Vec<CentralDirectoryEntry>> {
+    let eocd = find_end_of_central_directory(data)?;
+    let num_entries = read_u16(data, eocd + 10)? as usize;
+    let mut offset = read_u32(data, eocd + 16)? as usize;
+    if num_entries == 0xffff || offset == 0xffffffff {
+        return Err(anyhow!("zip64 archives with more than 65535 entries are not supported"));
+    }
+
+    let mut result = Vec::with_capacity(num_entries);
+    for _ in 0..num_entries {
+        if read_u32(data, offset)? != CENTRAL_DIRECTORY_HEADER {
+            return Err(anyhow!("corrupt central directory at offset {}", offset));
+        }
+        let method = read_u16(data, offset + 10)?;
+        let crc32 = read_u32(data, offset + 16)?;
+        let mut compressed_size = read_u32(data, offset + 20)? as u64;
+        let mut size = read_u32(data, offset + 24)? as u64;
+        let name_len = read_u16(data, offset + 28)? as usize;
+        let extra_len = read_u16(data, offset + 30)? as usize;
+        let comment_len = read_u16(data, offset + 32)? as usize;
+        let mut local_header_offset = read_u32(data, offset + 42)? as u64;
+
+        let name = String::from_utf8_lossy(slice(data, offset + 46, name_len)?).to_string();
+        let extra = slice(data, offset + 46 + name_len, extra_len)?;
+        apply_zip64_extra(extra, &mut size, &mut compressed_size, &mut local_header_offset)?;
+        offset += 46 + name_len + extra_len + comment_len;
+
+        if name.ends_with('/') {
+            continue;
+        }
+        result.push(CentralDirectoryEntry { name, method, crc32, compressed_size, size, local_header_offset });
+    }
+    Ok(result)
+}
+
+fn find_end_of_central_directory(data: &[u8]) -> anyhow::Result<usize> {
+    let min_start = data.len().saturating_sub(22 + MAX_COMMENT_LEN);
+    (min_start..=data.len().saturating_sub(22)).rev()
+        .find(|&offset| read_u32(data, offset).ok() == Some(END_OF_CENTRAL_DIRECTORY))
+        .ok_or_else(|| anyhow!("not a zip archive"))
+}
+
+/// replaces values that do not fit 32 bits with those of the zip64 extra field, in the field's
+///  order
+fn apply_zip64_extra(mut extra: &[u8], size: &mut u64, compressed_size: &mut u64, local_header_offset: &mut u64) -> anyhow::Result<()> {
+    while extra.len() >= 4 {
+        let id = read_u16(extra, 0)?;
+        let len = read_u16(extra, 2)? as usize;
+        let field = slice(extra, 4, len)?;
+        if id == ZIP64_EXTRA_FIELD {
+            let mut values = field.chunks_exact(8).map(|v| u64::from_le_bytes(v.try_into().unwrap()));
+            for value in [size, compressed_size, local_header_offset] {
+                if *value == 0xffffffff {
+                    *value = values.next().ok_or_else(|| anyhow!("truncated zip64 extra field"))?;
+                }
+            }
+            return Ok(());
+        }
+        extra = &extra[4 + len..];
+    }
+    Ok(())
+}
+
+fn read_content(data: &[u8], entry: &CentralDirectoryEntry) -> anyhow::Result<Vec<u8>> {
+    let offset = usize::try_from(entry.local_header_offset)?;
+    if read_u32(data, offset)? != LOCAL_FILE_HEADER {
+        return Err(anyhow!("corrupt local file header"));
+    }
+    let name_len = read_u16(data, offset + 26)? as usize;
+    let extra_len = read_u16(data, offset + 28)? as usize;
+    let compressed = slice(data, offset + 30 + name_len + extra_len, usize::try_from(entry.compressed_size)?)?;
+
+    let mut content = Vec::with_capacity(entry.size as usize);
+    match entry.method {
+        METHOD_STORED => content.extend_from_slice(compressed),
+        METHOD_DEFLATED => {
+            // one more byte than expected, so that inconsistent sizes are detected
+            DeflateDecoder::new(compressed).take(entry.size + 1).read_to_end(&mut content)?;
+        }
+        method => return Err(anyhow!("unsupported compression method {}", method)),
+    }
+    if content.len() as u64 != entry.size {
+        return Err(anyhow!("size does not match the central directory"));
+    }
+    Ok(content)
+}
+
+fn slice(data: &[u8], offset: usize, len: usize) -> anyhow::Result<&[u8]> {
+    data.get(offset..offset.saturating_add(len))
+        .ok_or_else(|| anyhow!("truncated zip archive"))
+}
+
+fn read_u16(data: &[u8], offset: usize) -> anyhow::Result<u16> {
+    Ok(u16::from_le_bytes(slice(data, offset, 2)?.try_into().unwrap()))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> anyhow::Result<u32> {
+    Ok(u32::from_le_bytes(slice(data, offset, 4)?.try_into().unwrap()))
+}
+
+/// Builds zip archives for tests
+#[cfg(test)]
+pub(crate) mod test_jar {
+    use std::io::Write;
+
+    use flate2::Compression;
+    use flate2::write::DeflateEncoder;
+
+    /// (name, content) - directories end in '/' and are stored, files are deflated
+    pub(crate) fn jar(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut central_directory = Vec::new();
+        for (name, content) in entries {
+            let (method, compressed) = match name.ends_with('/') {
+                true => (0u16, content.to_vec()),
+                false => {
+                    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+                    encoder.write_all(content).unwrap();
+                    (8u16, encoder.finish().unwrap())
+                }
+            };
+            let crc = crc32(content);
+            let offset = data.len() as u32;
+
+            data.extend_from_slice(&0x04034b50u32.to_le_bytes());
+            data.extend_from_slice(&[20, 0, 0, 0]);
+            data.extend_from_slice(&method.to_le_bytes());
+            data.extend_from_slice(&[0; 4]);
+            data.extend_from_slice(&crc.to_le_bytes());
+            data.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+            data.extend_from_slice(&(content.len() as u32).to_le_bytes());
+            data.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            data.extend_from_slice(&[0; 2]);
+            data.extend_from_slice(name.as_bytes());
+            data.extend_from_slice(&compressed);
+
+            central_directory.extend_from_slice(&0x02014b50u32.to_le_bytes());
+            central_directory.extend_from_slice(&[20, 0, 20, 0, 0, 0]);
+            central_directory.extend_from_slice(&method.to_le_bytes());
+            central_directory.extend_from_slice(&[0; 4]);
+            central_directory.extend_from_slice(&crc.to_le_bytes());
+            central_directory.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+            central_directory.extend_from_slice(&(content.len() as u32).to_le_bytes());
+            central_directory.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            central_directory.extend_from_slice(&[0; 12]);
+            central_directory.extend_from_slice(&offset.to_le_bytes());
+            central_directory.extend_from_slice(name.as_bytes());
+        }
+        let central_directory_offset = data.len() as u32;
+        data.extend_from_slice(&central_directory);
+
+        data.extend_from_slice(&0x06054b50u32.to_le_bytes());
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        data.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        data.extend_from_slice(&(central_directory.len() as u32).to_le_bytes());
+        data.extend_from_slice(&central_directory_offset.to_le_bytes());
+        data.extend_from_slice(&[0; 2]);
+        data
+    }
+
+    fn crc32(data: &[u8]) -> u32 {
+        let mut crc = !0u32;
+        for &b in data {
+            crc ^= b as u32;
+            for _ in 0..8 {
+                crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb88320 } else { crc >> 1 };
+            }
+        }
+        !crc
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::test_jar::jar;
+
+    #[test]
+    fn test_index_jar() {
+        let data = jar(&[
+            ("META-INF/", b""),
+            ("META-INF/MANIFEST.MF", b"Manifest-Version: 1.0\n"),
+            ("a/A.class", b"\xca\xfe\xba\xbe class A"),
+        ]);
+
+        let entries = index_jar(&data, false).unwrap();
+        assert_eq!(entries.iter().map(|e| e.name.as_str()).collect::<Vec<_>>(), vec!["META-INF/MANIFEST.MF", "a/A.class"]);
+        assert_eq!(entries[0].size, 22);
+        assert_eq!(entries[0].crc32, 0x205adf7a);
+        assert!(entries.iter().all(|e| e.sha256.is_none()));
+
+        let entries = index_jar(&data, true).unwrap();
+        assert_eq!(entries[0].sha256, None);
+        assert_eq!(entries[1].sha256, Some(hex::encode(Sha256::digest(b"\xca\xfe\xba\xbe class A"))));
+    }
+
+    #[test]
+    fn test_read_entries() {
+        let data = jar(&[
+            ("META-INF/", b""),
+            ("META-INF/LICENSE", b"Apache License"),
+            ("META-INF/NOTICE", b"a notice that is too long"),
+            ("a/A.class", b"class A"),
+        ]);
+        let entries = read_entries(&data, |name| name.starts_with("META-INF/"), 20).unwrap();
+        assert_eq!(entries, vec![("META-INF/LICENSE".to_string(), b"Apache License".to_vec())]);
+    }
+
+    #[test]
+    fn test_not_a_jar() {
+        assert!(index_jar(b"<project/>", false).is_err());
+        let data = jar(&[("a/A.class", b"class A")]);
+        assert!(index_jar(&data[..data.len() - 30], false).is_err());
+    }
+}
@@ -1,4 +1,8 @@
-use crate::maven::coordinates::{MavenArtifactId, MavenClassifier, MavenCoordinates, MavenGroupId};
+use std::collections::BTreeSet;
+
+use crate::maven::coordinates::{MavenArtifactId, MavenArtifactRef, MavenClassifier, MavenCoordinates, MavenGroupId, MavenVersion};
+use crate::maven::directory_listing::{DirectoryEntry, DirectoryEntryKind, DirectoryListing};
+use crate::maven::paths::maven_file_name;
 
 
 // pub enum ArtifactStatus {
@@ -33,3 +37,156 @@ pub trait MavenRepoMetaDataProvider {
     //TODO plugins
 }
 
+/// [MavenRepoMetaDataProvider] for a set of artifacts, e.g. those that are available locally
+pub struct ArtifactIndex {
+    artifacts: Vec<MavenArtifactRef>,
+}
+impl ArtifactIndex {
+    pub fn new(artifacts: Vec<MavenArtifactRef>) -> ArtifactIndex {
+        ArtifactIndex { artifacts }
+    }
+
+    /// the file names of a version's artifacts, with snapshot versions unqualified
+    pub fn get_file_names(&self, coordinates: &MavenCoordinates) -> Vec<String> {
+        self.artifacts_of(coordinates)
+            .map(maven_file_name)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Lists a directory in Maven's repository layout, e.g. 'org/apache/' - child groups and
+    ///  artifacts of a group, versions of an artifact, or the files of a version. Names that are
+    ///  both (e.g. a group 'org.apache.maven' and an artifact 'org.apache:maven') are listed once.
+    pub fn directory_listing(&self, prefix: &str) -> DirectoryListing {
+        let segments = prefix.split('/')
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>();
+        let group_of = |segments: &[&str]| MavenGroupId(segments.join("."));
+
+        let mut entries = Vec::new();
+        if segments.len() >= 3 {
+            let n = segments.len();
+            let coordinates = MavenCoordinates {
+                group_id: group_of(&segments[..n-2]),
+                artifact_id: MavenArtifactId(segments[n-2].to_string()),
+                version: MavenVersion::Release(segments[n-1].to_string()),
+            };
+            entries.extend(self.get_file_names(&coordinates).into_iter()
+                .map(|name| DirectoryEntry::of_kind(name, DirectoryEntryKind::File)));
+        }
+        if segments.len() >= 2 {
+            let n = segments.len();
+            entries.extend(self.get_versions(&group_of(&segments[..n-1]), &MavenArtifactId(segments[n-1].to_string())).into_iter()
+                .map(|coordinates| DirectoryEntry::of_kind(coordinates.version.unqualified(), DirectoryEntryKind::Version)));
+        }
+        let group_id = group_of(&segments);
+        if !segments.is_empty() {
+            entries.extend(self.get_artifacts(&group_id).into_iter()
+                .map(|artifact_id| DirectoryEntry::of_kind(artifact_id.0, DirectoryEntryKind::Artifact)));
+        }
+        entries.extend(self.get_child_groups(&group_id).into_iter()
+            .map(|child| DirectoryEntry::of_kind(child.0.rsplit('.').next().unwrap_or_default(), DirectoryEntryKind::Group)));
+
+        DirectoryListing::default().merge(&DirectoryListing { entries })
+    }
+
+    fn artifacts_of<'a>(&'a self, coordinates: &'a MavenCoordinates) -> impl Iterator<Item = &'a MavenArtifactRef> + 'a {
+        self.artifacts.iter()
+            .filter(move |a| a.coordinates.group_id == coordinates.group_id
+                && a.coordinates.artifact_id == coordinates.artifact_id
+                && a.coordinates.version.unqualified() == coordinates.version.unqualified())
+    }
+}
+
+impl MavenRepoMetaDataProvider for ArtifactIndex {
+    fn get_child_groups(&self, group_id: &MavenGroupId) -> Vec<MavenGroupId> {
+        let prefix = match group_id.0.is_empty() {
+            true => String::new(),
+            false => format!("{}.", group_id.0),
+        };
+        self.artifacts.iter()
+            .filter_map(|a| a.coordinates.group_id.0.strip_prefix(&prefix))
+            .filter(|rest| !rest.is_empty())
+            .map(|rest| rest.split('.').next().unwrap_or_default())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|child| MavenGroupId(format!("{}{}", prefix, child)))
+            .collect()
+    }
+
+    fn get_artifacts(&self, group_id: &MavenGroupId) -> Vec<MavenArtifactId> {
+        self.artifacts.iter()
+            .filter(|a| &a.coordinates.group_id == group_id)
+            .map(|a| &a.coordinates.artifact_id.0)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|artifact_id| MavenArtifactId(artifact_id.clone()))
+            .collect()
+    }
+
+    /// one entry per version, i.e. snapshot versions are listed once with one of their builds
+    fn get_versions(&self, group_id: &MavenGroupId, artifact_id: &MavenArtifactId) -> Vec<MavenCoordinates> {
+        let mut seen = BTreeSet::new();
+        self.artifacts.iter()
+            .filter(|a| &a.coordinates.group_id == group_id && &a.coordinates.artifact_id == artifact_id)
+            .filter(|a| seen.insert(a.coordinates.version.unqualified()))
+            .map(|a| a.coordinates.clone())
+            .collect()
+    }
+
+    fn get_classifiers(&self, coordinates: &MavenCoordinates) -> Vec<MavenClassifier> {
+        let mut result = Vec::new();
+        for artifact_ref in self.artifacts_of(coordinates) {
+            if !result.contains(&artifact_ref.classifier) {
+                result.push(artifact_ref.classifier.clone());
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use crate::maven::paths::parse_maven_path;
+
+    use super::*;
+
+    fn index() -> ArtifactIndex {
+        ArtifactIndex::new([
+            "org/apache/maven/maven-core/3.9.5/maven-core-3.9.5.jar",
+            "org/apache/maven/maven-core/3.9.5/maven-core-3.9.5.pom",
+            "org/apache/maven/maven-core/3.9.6/maven-core-3.9.6.jar",
+            "org/apache/maven/plugins/maven-jar-plugin/3.3.0/maven-jar-plugin-3.3.0.jar",
+            "org/apache/maven/1.0/maven-1.0.pom",
+            "org/example/a/1.0-SNAPSHOT/a-1.0-SNAPSHOT-20231114.221320-1.jar",
+            "org/example/a/1.0-SNAPSHOT/a-1.0-SNAPSHOT-20231115.221320-2.jar",
+            "com/example/a/1.0/a-1.0-sources.jar",
+        ].iter().map(|path| parse_maven_path(path).unwrap()).collect())
+    }
+
+    #[rstest]
+    #[case::root("", vec![("com", DirectoryEntryKind::Group), ("org", DirectoryEntryKind::Group)])]
+    #[case::group("org/", vec![("apache", DirectoryEntryKind::Group), ("example", DirectoryEntryKind::Group)])]
+    #[case::group_and_artifact("org/apache/", vec![("maven", DirectoryEntryKind::Artifact)])]
+    #[case::artifacts_and_groups("org/apache/maven/", vec![("1.0", DirectoryEntryKind::Version), ("maven-core", DirectoryEntryKind::Artifact), ("plugins", DirectoryEntryKind::Group)])]
+    #[case::versions("org/apache/maven/maven-core/", vec![("3.9.5", DirectoryEntryKind::Version), ("3.9.6", DirectoryEntryKind::Version)])]
+    #[case::files("org/apache/maven/maven-core/3.9.5/", vec![("maven-core-3.9.5.jar", DirectoryEntryKind::File), ("maven-core-3.9.5.pom", DirectoryEntryKind::File)])]
+    #[case::snapshot_versions("org/example/a/", vec![("1.0-SNAPSHOT", DirectoryEntryKind::Version)])]
+    #[case::snapshot_files("org/example/a/1.0-SNAPSHOT/", vec![("a-1.0-SNAPSHOT-20231114.221320-1.jar", DirectoryEntryKind::File), ("a-1.0-SNAPSHOT-20231115.221320-2.jar", DirectoryEntryKind::File)])]
+    #[case::unknown("net/", vec![])]
+    fn test_directory_listing(#[case] prefix: &str, #[case] expected: Vec<(&str, DirectoryEntryKind)>) {
+        let expected = expected.into_iter()
+            .map(|(name, kind)| DirectoryEntry::of_kind(name, kind))
+            .collect::<Vec<_>>();
+        assert_eq!(index().directory_listing(prefix).entries, expected);
+    }
+
+    #[test]
+    fn test_get_classifiers() {
+        let coordinates = parse_maven_path("com/example/a/1.0/a-1.0.jar").unwrap().coordinates;
+        assert_eq!(index().get_classifiers(&coordinates), vec![MavenClassifier::Classified("sources".to_string())]);
+    }
+}
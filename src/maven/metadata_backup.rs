@@ -0,0 +1,177 @@
+use std::collections::{BTreeMap, HashMap};
+use std::time::SystemTime;
+
+use anyhow::anyhow;
+use hex::ToHex;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::maven::paths::as_maven_path;
+use crate::maven::remote_repo::{RemoteMavenRepo, RepoMetadataSnapshot};
+
+const FORMAT_VERSION: u32 = 1;
+
+/// What a blob looked like when a backup was taken
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct BlobManifestEntry {
+    pub key: Uuid,
+    pub size: u64,
+    /// hex encoded
+    pub sha1: Option<String>,
+}
+
+/// A consistent copy of a repository's metadata, together with a manifest of the blobs it refers
+///  to. Blob data is not part of the backup: blob storage is backed up separately (or is durable
+///  anyway), and the manifest allows checking that a restore target has the expected blobs.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct MetadataBackup {
+    pub format_version: u32,
+    pub created: SystemTime,
+    pub metadata: RepoMetadataSnapshot,
+    pub blob_manifest: Vec<BlobManifestEntry>,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct RestoreReport {
+    pub restored_artifacts: usize,
+    /// artifacts that were skipped because their blobs are missing or differ from the manifest,
+    ///  as (repository path, reason)
+    pub skipped_artifacts: Vec<(String, String)>,
+}
+
+pub async fn create_backup(repo: &RemoteMavenRepo) -> anyhow::Result<MetadataBackup> {
+    let created = SystemTime::now();
+    let metadata = repo.get_metadata_snapshot().await?;
+
+    let mut blob_manifest = BTreeMap::new();
+    for (artifact_ref, blob_key, _) in &metadata.artifacts {
+        if blob_manifest.contains_key(blob_key) {
+            continue;
+        }
+        match repo.get_blob_stat(blob_key).await? {
+            Some(stat) => {
+                blob_manifest.insert(*blob_key, BlobManifestEntry {
+                    key: *blob_key,
+                    size: stat.size,
+                    sha1: stat.sha1.map(|h| h.encode_hex()),
+                });
+            }
+            None => {
+                // backed up as is, restoring it will fail validation
+                warn!("blob {} for {} is missing while taking a backup", blob_key, as_maven_path(artifact_ref));
+            }
+        }
+    }
+
+    Ok(MetadataBackup {
+        format_version: FORMAT_VERSION,
+        created,
+        metadata,
+        blob_manifest: blob_manifest.into_values().collect(),
+    })
+}
+
+/// Checks that all blobs referenced by the backup are present in the repository's blob storage
+///  and match the manifest. Returns the artifacts that fail the check as (repository path, reason).
+pub async fn validate_backup(repo: &RemoteMavenRepo, backup: &MetadataBackup) -> anyhow::Result<Vec<(String, String)>> {
+    if backup.format_version != FORMAT_VERSION {
+        return Err(anyhow!("unsupported backup format version {}", backup.format_version));
+    }
+
+    let manifest = backup.blob_manifest.iter()
+        .map(|e| (e.key, e))
+        .collect::<HashMap<_, _>>();
+
+    let mut problems = Vec::new();
+    for (artifact_ref, blob_key, _) in &backup.metadata.artifacts {
+        let path = as_maven_path(artifact_ref);
+        let expected = match manifest.get(blob_key) {
+            Some(expected) => expected,
+            None => {
+                problems.push((path, format!("blob {} is not in the manifest", blob_key)));
+                continue;
+            }
+        };
+
+        match repo.get_blob_stat(blob_key).await? {
+            None => problems.push((path, format!("blob {} is missing", blob_key))),
+            Some(stat) if stat.size != expected.size => problems.push((path, format!("blob {} has size {} instead of {}", blob_key, stat.size, expected.size))),
+            Some(stat) if expected.sha1.is_some() && stat.sha1.map(|h| h.encode_hex::<String>()) != expected.sha1 => problems.push((path, format!("blob {} has a different sha1 checksum", blob_key))),
+            Some(_) => {}
+        }
+    }
+    Ok(problems)
+}
+
+/// Replaces the repository's metadata with a backup after validating it against blob storage.
+///  Unless `skip_missing` is set, the restore is refused if any blob is missing or modified;
+///  otherwise the affected artifacts are left out.
+pub async fn restore_backup(repo: &RemoteMavenRepo, backup: MetadataBackup, skip_missing: bool) -> anyhow::Result<RestoreReport> {
+    let problems = validate_backup(repo, &backup).await?;
+    if !problems.is_empty() && !skip_missing {
+        return Err(anyhow!("{} artifact(s) refer to missing or modified blobs, e.g. {}: {}", problems.len(), problems[0].0, problems[0].1));
+    }
+
+    let mut metadata = backup.metadata;
+    metadata.artifacts.retain(|(artifact_ref, _, _)| {
+        let path = as_maven_path(artifact_ref);
+        !problems.iter().any(|(p, _)| p == &path)
+    });
+
+    let restored_artifacts = metadata.artifacts.len();
+    repo.restore_metadata_snapshot(metadata).await?;
+
+    Ok(RestoreReport {
+        restored_artifacts,
+        skipped_artifacts: problems,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use bytes::Bytes;
+
+    use crate::blob::blob_storage::BlobStorage;
+    use crate::blob::transient_blob_storage::TransientBlobStorage;
+    use crate::maven::paths::parse_maven_path;
+    use crate::maven::remote_repo::{ArtifactProvenance, DummyRemoteRepoMetadataStore};
+    use super::*;
+
+    fn provenance() -> ArtifactProvenance {
+        ArtifactProvenance {
+            fetched: SystemTime::UNIX_EPOCH,
+            last_modified: SystemTime::UNIX_EPOCH,
+            upstream_headers: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_restore_validates_blobs() {
+        let blob_storage = Arc::new(TransientBlobStorage::new());
+        let present = blob_storage.insert(Box::pin(futures::stream::iter(vec![Ok(Bytes::from_static(b"abc"))]))).await.unwrap();
+        let missing = Uuid::from_u64_pair(0, 1);
+
+        let metadata = RepoMetadataSnapshot {
+            artifacts: vec![
+                (parse_maven_path("com/example/a/1.0/a-1.0.jar").unwrap(), present, provenance()),
+                (parse_maven_path("com/example/b/1.0/b-1.0.jar").unwrap(), missing, provenance()),
+            ],
+            ..Default::default()
+        };
+        let repo = RemoteMavenRepo::new("http://localhost".to_string(), blob_storage, Arc::new(DummyRemoteRepoMetadataStore::from_snapshot(metadata))).unwrap();
+
+        let backup = create_backup(&repo).await.unwrap();
+        assert_eq!(backup.blob_manifest.len(), 1);
+
+        assert!(restore_backup(&repo, backup.clone(), false).await.is_err());
+
+        let report = restore_backup(&repo, backup, true).await.unwrap();
+        assert_eq!(report.restored_artifacts, 1);
+        assert_eq!(report.skipped_artifacts.len(), 1);
+        assert_eq!(report.skipped_artifacts[0].0, "com/example/b/1.0/b-1.0.jar");
+        assert_eq!(repo.get_metadata_snapshot().await.unwrap().artifacts.len(), 1);
+    }
+}
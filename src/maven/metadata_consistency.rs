@@ -0,0 +1,166 @@
+use std::fmt::{Display, Formatter};
+
+use crate::maven::coordinates::MavenVersion;
+use crate::maven::metadata_xml::{parse_last_updated, parse_snapshot_versions, parse_versions, SnapshotVersion};
+
+/// A way in which the generated 'maven-metadata.xml' of a hosted repository does not reflect a
+///  deploy that was just committed, i.e. drift between the metadata store and the deployed files
+///  (e.g. after concurrent deploys of the same snapshot)
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum MetadataInconsistency {
+    /// the artifact level metadata does not list a deployed version
+    VersionNotListed { version: String },
+    /// the version level metadata lists another build of a file than the deployed one
+    BuildNotListed { file: String, deployed: String, listed: Option<String> },
+    /// the build number listed for a file is lower than before the deploy
+    BuildNumberDecreased { file: String, before: u32, after: u32 },
+    /// '<lastUpdated>' is earlier than before the deploy. It has a resolution of seconds, so it
+    ///  may remain unchanged for deploys within the same second.
+    LastUpdatedNotAdvanced { before: String, after: String },
+}
+impl Display for MetadataInconsistency {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MetadataInconsistency::VersionNotListed { version } =>
+                write!(f, "deployed version {} is not listed", version),
+            MetadataInconsistency::BuildNotListed { file, deployed, listed: Some(listed) } =>
+                write!(f, "{} lists build {} rather than the deployed build {}", file, listed, deployed),
+            MetadataInconsistency::BuildNotListed { file, deployed, listed: None } =>
+                write!(f, "{} is not listed, the deployed build is {}", file, deployed),
+            MetadataInconsistency::BuildNumberDecreased { file, before, after } =>
+                write!(f, "build number of {} went from {} down to {}", file, before, after),
+            MetadataInconsistency::LastUpdatedNotAdvanced { before, after } =>
+                write!(f, "lastUpdated went from {} back to {}", before, after),
+        }
+    }
+}
+
+/// Checks the artifact level metadata before and after a deploy of the given (unqualified)
+///  versions was committed
+pub fn check_artifact_metadata(before: Option<&str>, after: Option<&str>, versions: &[String]) -> Vec<MetadataInconsistency> {
+    let listed = after.map(parse_versions).unwrap_or_default();
+    let mut inconsistencies = versions.iter()
+        .filter(|version| !listed.contains(version))
+        .map(|version| MetadataInconsistency::VersionNotListed { version: version.clone() })
+        .collect::<Vec<_>>();
+    inconsistencies.extend(check_last_updated(before, after));
+    inconsistencies
+}
+
+/// Checks the version level metadata of a snapshot version before and after the given builds
+///  were committed: each deployed file must be listed with its deployed build, and no file's
+///  build number may go down
+pub fn check_snapshot_metadata(version: &str, before: Option<&str>, after: Option<&str>, deployed: &[SnapshotVersion]) -> Vec<MetadataInconsistency> {
+    let listed_before = before.map(parse_snapshot_versions).unwrap_or_default();
+    let listed_after = after.map(parse_snapshot_versions).unwrap_or_default();
+    let same_file = |a: &SnapshotVersion, b: &SnapshotVersion| a.classifier == b.classifier && a.extension == b.extension;
+
+    let mut inconsistencies = Vec::new();
+    for sv in deployed {
+        let listed = listed_after.iter().find(|l| same_file(l, sv));
+        if listed.map(|l| &l.value) != Some(&sv.value) {
+            inconsistencies.push(MetadataInconsistency::BuildNotListed {
+                file: file_name(sv),
+                deployed: sv.value.clone(),
+                listed: listed.map(|l| l.value.clone()),
+            });
+        }
+    }
+    for sv in &listed_after {
+        let previous = listed_before.iter().find(|l| same_file(l, sv));
+        if let (Some(before), Some(after)) = (previous.and_then(|p| build_number(p, version)), build_number(sv, version)) {
+            if after < before {
+                inconsistencies.push(MetadataInconsistency::BuildNumberDecreased { file: file_name(sv), before, after });
+            }
+        }
+    }
+    inconsistencies.extend(check_last_updated(before, after));
+    inconsistencies
+}
+
+fn check_last_updated(before: Option<&str>, after: Option<&str>) -> Option<MetadataInconsistency> {
+    let before = parse_last_updated(before?)?;
+    let after = after.and_then(parse_last_updated).unwrap_or_default();
+    (after < before).then_some(MetadataInconsistency::LastUpdatedNotAdvanced { before, after })
+}
+
+fn build_number(sv: &SnapshotVersion, version: &str) -> Option<u32> {
+    match sv.version(version) {
+        Some(MavenVersion::Snapshot { build_number, .. }) => build_number,
+        _ => None,
+    }
+}
+
+/// e.g. 'sources.jar' or 'pom'
+fn file_name(sv: &SnapshotVersion) -> String {
+    match &sv.classifier {
+        Some(classifier) => format!("{}.{}", classifier, sv.extension),
+        None => sv.extension.clone(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::maven::metadata_xml::render_snapshot_metadata;
+
+    use super::*;
+
+    fn artifact_xml(versions: &[&str], last_updated: &str) -> String {
+        let versions = versions.iter()
+            .map(|v| format!("<version>{}</version>", v))
+            .collect::<String>();
+        format!("<metadata><versioning><versions>{}</versions><lastUpdated>{}</lastUpdated></versioning></metadata>", versions, last_updated)
+    }
+
+    fn snapshot_version(classifier: Option<&str>, value: &str, updated: &str) -> SnapshotVersion {
+        SnapshotVersion {
+            classifier: classifier.map(|c| c.to_string()),
+            extension: "jar".to_string(),
+            value: value.to_string(),
+            updated: updated.to_string(),
+        }
+    }
+
+    fn snapshot_xml(snapshot_versions: &[SnapshotVersion]) -> String {
+        render_snapshot_metadata("com.example", "lib", "1.0-SNAPSHOT", snapshot_versions)
+    }
+
+    #[test]
+    fn test_artifact_metadata() {
+        let before = artifact_xml(&["1.0"], "20231114221320");
+        let versions = vec!["1.1".to_string()];
+        assert!(check_artifact_metadata(Some(&before), Some(&artifact_xml(&["1.0", "1.1"], "20231114221320")), &versions).is_empty());
+        assert!(check_artifact_metadata(None, Some(&artifact_xml(&["1.1"], "20231114221320")), &versions).is_empty());
+
+        assert_eq!(check_artifact_metadata(Some(&before), Some(&artifact_xml(&["1.0"], "20231114221319")), &versions), vec![
+            MetadataInconsistency::VersionNotListed { version: "1.1".to_string() },
+            MetadataInconsistency::LastUpdatedNotAdvanced { before: "20231114221320".to_string(), after: "20231114221319".to_string() },
+        ]);
+        assert_eq!(check_artifact_metadata(None, None, &versions), vec![MetadataInconsistency::VersionNotListed { version: "1.1".to_string() }]);
+    }
+
+    #[test]
+    fn test_snapshot_metadata() {
+        let build_2 = snapshot_version(None, "1.0-20231114.221320-2", "20231114221320");
+        let build_3 = snapshot_version(None, "1.0-20231115.080000-3", "20231115080000");
+        let before = snapshot_xml(&[build_2.clone()]);
+        assert!(check_snapshot_metadata("1.0-SNAPSHOT", Some(&before), Some(&snapshot_xml(&[build_3.clone()])), &[build_3.clone()]).is_empty());
+        assert!(check_snapshot_metadata("1.0-SNAPSHOT", None, Some(&snapshot_xml(&[build_2.clone()])), &[build_2.clone()]).is_empty());
+
+        let sources = snapshot_version(Some("sources"), "1.0-20231115.080000-3", "20231115080000");
+        assert_eq!(check_snapshot_metadata("1.0-SNAPSHOT", Some(&before), Some(&snapshot_xml(&[build_3.clone()])), &[build_3.clone(), sources]), vec![
+            MetadataInconsistency::BuildNotListed { file: "sources.jar".to_string(), deployed: "1.0-20231115.080000-3".to_string(), listed: None },
+        ]);
+
+        let build_1 = snapshot_version(None, "1.0-20231116.080000-1", "20231116080000");
+        assert_eq!(check_snapshot_metadata("1.0-SNAPSHOT", Some(&before), Some(&snapshot_xml(&[build_1.clone()])), &[build_1]), vec![
+            MetadataInconsistency::BuildNumberDecreased { file: "jar".to_string(), before: 2, after: 1 },
+        ]);
+
+        // a stale build was committed after a more recent one
+        let before = snapshot_xml(&[build_3.clone()]);
+        assert_eq!(check_snapshot_metadata("1.0-SNAPSHOT", Some(&before), Some(&before), &[build_2]), vec![
+            MetadataInconsistency::BuildNotListed { file: "jar".to_string(), deployed: "1.0-20231114.221320-2".to_string(), listed: Some("1.0-20231115.080000-3".to_string()) },
+        ]);
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::maven::coordinates::{MavenArtifactId, MavenClassifier, MavenGroupId};
+use crate::maven::paths::as_maven_path;
+use crate::maven::remote_repo::RemoteMavenRepo;
+
+/// Settings for periodically exporting the metadata store for analytics, so that data teams need
+///  not query the live store. The export is newline-delimited JSON, one record per line.
+#[derive(Clone, Debug)]
+pub struct MetadataExportConfig {
+    /// the file is replaced atomically on each export
+    pub path: PathBuf,
+    pub interval: Duration,
+}
+
+/// A line in the export file
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ExportRecord {
+    /// a locally available artifact and where it came from
+    Artifact {
+        path: String,
+        group_id: String,
+        artifact_id: String,
+        version: String,
+        classifier: Option<String>,
+        extension: String,
+        blob_key: String,
+        /// seconds since the epoch
+        fetched: u64,
+        /// seconds since the epoch
+        last_modified: u64,
+        upstream_headers: Vec<(String, String)>,
+    },
+    /// the known versions of an artifact
+    Versions {
+        group_id: String,
+        artifact_id: String,
+        versions: Vec<String>,
+        latest_version: String,
+        release_version: Option<String>,
+        last_updated: String,
+    },
+}
+
+fn epoch_secs(t: SystemTime) -> u64 {
+    t.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Collects the repository's metadata as export records, sorted for stable diffs between exports
+pub async fn export_metadata(repo: &RemoteMavenRepo) -> anyhow::Result<Vec<ExportRecord>> {
+    let mut artifacts = repo.get_local_artifact_details().await?;
+    artifacts.sort_by_key(|(artifact_ref, _, _)| as_maven_path(artifact_ref));
+
+    let mut result = Vec::new();
+    let mut artifact_ids = BTreeSet::new();
+    for (artifact_ref, blob_key, provenance) in artifacts {
+        let coordinates = &artifact_ref.coordinates;
+        artifact_ids.insert((coordinates.group_id.0.clone(), coordinates.artifact_id.0.clone()));
+
+        result.push(ExportRecord::Artifact {
+            path: as_maven_path(&artifact_ref),
+            group_id: coordinates.group_id.0.clone(),
+            artifact_id: coordinates.artifact_id.0.clone(),
+            version: coordinates.version.unqualified().to_string(),
+            classifier: match &artifact_ref.classifier {
+                MavenClassifier::Unclassified => None,
+                MavenClassifier::Classified(c) => Some(c.clone()),
+            },
+            extension: artifact_ref.file_extension.clone(),
+            blob_key: blob_key.as_hyphenated().to_string(),
+            fetched: epoch_secs(provenance.fetched),
+            last_modified: epoch_secs(provenance.last_modified),
+            upstream_headers: provenance.upstream_headers,
+        });
+    }
+
+    for (group_id, artifact_id) in artifact_ids {
+        let metadata = repo.get_artifact_metadata(&MavenGroupId(group_id.clone()), &MavenArtifactId(artifact_id.clone())).await?;
+        if let Some(metadata) = metadata {
+            result.push(ExportRecord::Versions {
+                group_id,
+                artifact_id,
+                versions: metadata.versions.iter().map(|v| v.unqualified().to_string()).collect(),
+                latest_version: metadata.latest_version.unqualified().to_string(),
+                release_version: metadata.release_version.map(|v| v.unqualified().to_string()),
+                last_updated: metadata.last_updated,
+            });
+        }
+    }
+    Ok(result)
+}
+
+pub fn as_ndjson(records: &[ExportRecord]) -> anyhow::Result<String> {
+    let mut result = String::new();
+    for record in records {
+        result.push_str(&serde_json::to_string(record)?);
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+/// Writes to a temporary file first so that readers never see a partial export
+pub async fn write_ndjson(path: &Path, records: &[ExportRecord]) -> anyhow::Result<()> {
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+
+    tokio::fs::write(&temp_path, as_ndjson(records)?).await?;
+    tokio::fs::rename(&temp_path, path).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[test]
+    fn test_as_ndjson() {
+        let records = vec![
+            ExportRecord::Artifact {
+                path: "com/example/lib/1.0/lib-1.0.jar".to_string(),
+                group_id: "com.example".to_string(),
+                artifact_id: "lib".to_string(),
+                version: "1.0".to_string(),
+                classifier: None,
+                extension: "jar".to_string(),
+                blob_key: Uuid::from_u64_pair(0, 1).as_hyphenated().to_string(),
+                fetched: 1700000000,
+                last_modified: 1600000000,
+                upstream_headers: vec![("x-origin".to_string(), "central".to_string())],
+            },
+            ExportRecord::Versions {
+                group_id: "com.example".to_string(),
+                artifact_id: "lib".to_string(),
+                versions: vec!["1.0".to_string()],
+                latest_version: "1.0".to_string(),
+                release_version: Some("1.0".to_string()),
+                last_updated: "20231114221320".to_string(),
+            },
+        ];
+
+        assert_eq!(as_ndjson(&records).unwrap(), concat!(
+            r#"{"kind":"artifact","path":"com/example/lib/1.0/lib-1.0.jar","group_id":"com.example","artifact_id":"lib","version":"1.0","classifier":null,"extension":"jar","blob_key":"00000000-0000-0000-0000-000000000001","fetched":1700000000,"last_modified":1600000000,"upstream_headers":[["x-origin","central"]]}"#, "\n",
+            r#"{"kind":"versions","group_id":"com.example","artifact_id":"lib","versions":["1.0"],"latest_version":"1.0","release_version":"1.0","last_updated":"20231114221320"}"#, "\n",
+        ));
+    }
+}
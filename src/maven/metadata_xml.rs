@@ -1,50 +1,422 @@
-#![allow(non_snake_case)]
+use lazy_static::lazy_static;
+use regex::Regex;
 
+use crate::maven::coordinates::{MavenArtifactId, MavenArtifactRef, MavenClassifier, MavenVersion};
+use crate::maven::remote_repo::{MavenArtifactMetadata, MavenPluginMetadata};
+use crate::maven::version_resolution::{compare_versions, is_snapshot};
 
-pub struct Metadata {
-    groupId: String,
-    artifactId: String,
-    versioning: Versioning,
-    version: String,
-    plugins: Plugins,
+/// The most recent timestamped build of a snapshot version for a classifier and extension
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct SnapshotVersion {
+    pub classifier: Option<String>,
+    /// without leading '.', e.g. 'jar'
+    pub extension: String,
+    /// the timestamped version, e.g. '1.0-20231114.221320-3' for '1.0-SNAPSHOT'
+    pub value: String,
+    /// 'yyyyMMddHHmmss'
+    pub updated: String,
 }
+impl SnapshotVersion {
+    pub fn for_artifact(artifact_ref: &MavenArtifactRef, updated: String) -> Option<SnapshotVersion> {
+        let MavenVersion::Snapshot { version, timestamp, build_number } = &artifact_ref.coordinates.version else {
+            return None;
+        };
+        let base_version = version.strip_suffix("SNAPSHOT")?;
+
+        Some(SnapshotVersion {
+            classifier: match &artifact_ref.classifier {
+                MavenClassifier::Unclassified => None,
+                MavenClassifier::Classified(c) => Some(c.clone()),
+            },
+            extension: artifact_ref.file_extension.trim_start_matches('.').to_string(),
+            value: match build_number {
+                None => format!("{}{}", base_version, timestamp),
+                Some(n) => format!("{}{}-{}", base_version, timestamp, n),
+            },
+            updated,
+        })
+    }
+
+    /// Parses the timestamped value back into a version, given the unqualified version
+    ///  (e.g. '1.0-SNAPSHOT'). Returns None if the value does not belong to that version.
+    pub fn version(&self, unqualified: &str) -> Option<MavenVersion> {
+        let base_version = unqualified.strip_suffix("SNAPSHOT")?;
+        let qualifier = self.value.strip_prefix(base_version)?;
+        let (timestamp, build_number) = match qualifier.split_once('-') {
+            Some((timestamp, build_number)) => (timestamp, Some(build_number.parse().ok()?)),
+            None => (qualifier, None),
+        };
+        if !TIMESTAMP_REGEX.is_match(timestamp) {
+            return None;
+        }
+
+        Some(MavenVersion::Snapshot {
+            version: unqualified.to_string(),
+            timestamp: timestamp.to_string(),
+            build_number,
+        })
+    }
+
+    /// The file extension the way [MavenArtifactRef] has it, i.e. with a leading '.'
+    pub fn file_extension(&self) -> String {
+        format!(".{}", self.extension)
+    }
+
+    /// Keeps the most recent build for each classifier and extension, dropping entries that do
+    ///  not belong to the (unqualified) version. The result is sorted by classifier and extension.
+    pub fn latest_per_file(candidates: Vec<SnapshotVersion>, version: &str) -> Vec<SnapshotVersion> {
+        // the timestamp and build number identify the most recent build, regardless of when it was fetched
+        let build_key = |sv: &SnapshotVersion| match sv.version(version) {
+            Some(MavenVersion::Snapshot { timestamp, build_number, .. }) => Some((timestamp, build_number)),
+            _ => None,
+        };
 
-pub struct Versioning {
-    latest: String,
-    release: String,
-    versions: Versions,
-    lastUpdated: String,
-    snapshot: Snapshot,
-    snapshotVersions: SnapshotVersions,
+        let mut latest: Vec<SnapshotVersion> = Vec::new();
+        for candidate in candidates {
+            if build_key(&candidate).is_none() {
+                continue;
+            }
+            match latest.iter_mut().find(|sv| sv.classifier == candidate.classifier && sv.extension == candidate.extension) {
+                Some(existing) => {
+                    if build_key(&candidate) > build_key(existing) {
+                        *existing = candidate;
+                    }
+                }
+                None => latest.push(candidate),
+            }
+        }
+        latest.sort_by(|a, b| (&a.classifier, &a.extension).cmp(&(&b.classifier, &b.extension)));
+        latest
+    }
 }
 
-pub struct Versions {
-    version: Vec<String>,
+lazy_static! {
+    static ref VERSIONS_REGEX: Regex = Regex::new(r"(?s)<versions>(.*?)</versions>").unwrap();
+    static ref VERSION_REGEX: Regex = Regex::new(r"<version>\s*([^<\s]+)\s*</version>").unwrap();
+    static ref SNAPSHOT_VERSION_REGEX: Regex = Regex::new(r"(?s)<snapshotVersion>(.*?)</snapshotVersion>").unwrap();
+    static ref CLASSIFIER_REGEX: Regex = Regex::new(r"<classifier>\s*([^<\s]*)\s*</classifier>").unwrap();
+    static ref EXTENSION_REGEX: Regex = Regex::new(r"<extension>\s*([^<\s]+)\s*</extension>").unwrap();
+    static ref VALUE_REGEX: Regex = Regex::new(r"<value>\s*([^<\s]+)\s*</value>").unwrap();
+    static ref UPDATED_REGEX: Regex = Regex::new(r"<updated>\s*([^<\s]+)\s*</updated>").unwrap();
+    static ref LATEST_REGEX: Regex = Regex::new(r"<latest>\s*([^<\s]+)\s*</latest>").unwrap();
+    static ref RELEASE_REGEX: Regex = Regex::new(r"<release>\s*([^<\s]+)\s*</release>").unwrap();
+    static ref LAST_UPDATED_REGEX: Regex = Regex::new(r"<lastUpdated>\s*([^<\s]+)\s*</lastUpdated>").unwrap();
+    static ref PLUGIN_REGEX: Regex = Regex::new(r"(?s)<plugin>(.*?)</plugin>").unwrap();
+    static ref NAME_REGEX: Regex = Regex::new(r"(?s)<name>\s*(.*?)\s*</name>").unwrap();
+    static ref PREFIX_REGEX: Regex = Regex::new(r"<prefix>\s*([^<\s]+)\s*</prefix>").unwrap();
+    static ref ARTIFACT_ID_REGEX: Regex = Regex::new(r"<artifactId>\s*([^<\s]+)\s*</artifactId>").unwrap();
+    static ref TIMESTAMP_REGEX: Regex = Regex::new(r"^\d{8}\.\d{6}$").unwrap();
 }
 
-pub struct Snapshot {
-    timestamp: String,
-    buildNumber: Option<u32>,
-    //TODO localCopy?
+/// Extracts the list of versions from an artifact level 'maven-metadata.xml' file
+pub fn parse_versions(xml: &str) -> Vec<String> {
+    VERSIONS_REGEX.captures_iter(xml)
+        .flat_map(|versions| VERSION_REGEX.captures_iter(versions.get(1).unwrap().as_str())
+            .map(|c| c[1].to_string())
+            .collect::<Vec<_>>())
+        .collect()
 }
 
-pub struct SnapshotVersions {
-    snapshotVersion: Vec<SnapshotVersion>,
+/// Parses an artifact level 'maven-metadata.xml' file. Versions are unqualified, i.e. snapshots are
+///  represented by their '-SNAPSHOT' version. Missing '<latest>' and '<release>' elements are
+///  derived from the versions in Maven's version order. Returns None if no versions are listed.
+pub fn parse_artifact_metadata(xml: &str) -> Option<MavenArtifactMetadata> {
+    let element = |regex: &Regex| regex.captures(xml).map(|c| c[1].to_string());
+
+    let versions = parse_versions(xml);
+    let highest = |include_snapshots: bool| versions.iter()
+        .filter(|v| include_snapshots || !is_snapshot(v))
+        .max_by(|a, b| compare_versions(a, b))
+        .cloned();
+
+    let latest_version = element(&LATEST_REGEX).or_else(|| highest(true))?;
+    let release_version = element(&RELEASE_REGEX).or_else(|| highest(false));
+    Some(MavenArtifactMetadata {
+        latest_version: MavenVersion::Release(latest_version),
+        release_version: release_version.map(MavenVersion::Release),
+        versions: versions.into_iter().map(MavenVersion::Release).collect(),
+        last_updated: element(&LAST_UPDATED_REGEX).unwrap_or_default(),
+    })
 }
 
-pub struct SnapshotVersion {
-    classifier: Option<String>,
-    extension: String,
-    value: String,
-    updated: String,
+/// The '<lastUpdated>' element of an artifact or version level 'maven-metadata.xml' file
+pub fn parse_last_updated(xml: &str) -> Option<String> {
+    LAST_UPDATED_REGEX.captures(xml).map(|c| c[1].to_string())
+}
+
+/// Extracts the '<snapshotVersion>' entries from a version level 'maven-metadata.xml' file,
+///  skipping incomplete entries
+pub fn parse_snapshot_versions(xml: &str) -> Vec<SnapshotVersion> {
+    let element = |regex: &Regex, s: &str| regex.captures(s).map(|c| c[1].to_string());
+
+    SNAPSHOT_VERSION_REGEX.captures_iter(xml)
+        .filter_map(|c| {
+            let s = c.get(1).unwrap().as_str();
+            Some(SnapshotVersion {
+                classifier: element(&CLASSIFIER_REGEX, s).filter(|c| !c.is_empty()),
+                extension: element(&EXTENSION_REGEX, s)?,
+                value: element(&VALUE_REGEX, s)?,
+                updated: element(&UPDATED_REGEX, s).unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+/// Extracts the '<plugin>' entries from a group level 'maven-metadata.xml' file, skipping entries
+///  without a prefix or artifact id
+pub fn parse_plugins(xml: &str) -> Vec<MavenPluginMetadata> {
+    let element = |regex: &Regex, s: &str| regex.captures(s).map(|c| c[1].to_string());
+
+    PLUGIN_REGEX.captures_iter(xml)
+        .filter_map(|c| {
+            let s = c.get(1).unwrap().as_str();
+            Some(MavenPluginMetadata {
+                name: unescape(&element(&NAME_REGEX, s).unwrap_or_default()),
+                prefix: element(&PREFIX_REGEX, s)?,
+                artifact_id: MavenArtifactId(element(&ARTIFACT_ID_REGEX, s)?),
+            })
+        })
+        .collect()
+}
+
+/// Renders a version level 'maven-metadata.xml' for a snapshot version. '<snapshot>' refers to the
+///  most recently updated entry for the sake of clients that predate '<snapshotVersions>'.
+pub fn render_snapshot_metadata(group_id: &str, artifact_id: &str, version: &str, snapshot_versions: &[SnapshotVersion]) -> String {
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<metadata modelVersion=\"1.1.0\">\n");
+    xml.push_str(&format!("  <groupId>{}</groupId>\n", escape(group_id)));
+    xml.push_str(&format!("  <artifactId>{}</artifactId>\n", escape(artifact_id)));
+    xml.push_str(&format!("  <version>{}</version>\n", escape(version)));
+    xml.push_str("  <versioning>\n");
+
+    let latest = snapshot_versions.iter()
+        .filter_map(|sv| sv.version(version).map(|v| (sv, v)))
+        .max_by(|(a, _), (b, _)| a.updated.cmp(&b.updated));
+    if let Some((latest, MavenVersion::Snapshot { timestamp, build_number, .. })) = latest {
+        xml.push_str("    <snapshot>\n");
+        xml.push_str(&format!("      <timestamp>{}</timestamp>\n", timestamp));
+        if let Some(build_number) = build_number {
+            xml.push_str(&format!("      <buildNumber>{}</buildNumber>\n", build_number));
+        }
+        xml.push_str("    </snapshot>\n");
+        xml.push_str(&format!("    <lastUpdated>{}</lastUpdated>\n", escape(&latest.updated)));
+    }
+
+    xml.push_str("    <snapshotVersions>\n");
+    for sv in snapshot_versions {
+        xml.push_str("      <snapshotVersion>\n");
+        if let Some(classifier) = &sv.classifier {
+            xml.push_str(&format!("        <classifier>{}</classifier>\n", escape(classifier)));
+        }
+        xml.push_str(&format!("        <extension>{}</extension>\n", escape(&sv.extension)));
+        xml.push_str(&format!("        <value>{}</value>\n", escape(&sv.value)));
+        xml.push_str(&format!("        <updated>{}</updated>\n", escape(&sv.updated)));
+        xml.push_str("      </snapshotVersion>\n");
+    }
+    xml.push_str("    </snapshotVersions>\n");
+    xml.push_str("  </versioning>\n");
+    xml.push_str("</metadata>\n");
+    xml
+}
+
+/// Renders an artifact level 'maven-metadata.xml', listing versions in Maven's version order. Since
+///  group level metadata shares its path (see [crate::maven::paths::ArtifactMetadataPath]),
+///  'plugins' of the corresponding group are included if there are any.
+pub fn render_artifact_metadata(group_id: &str, artifact_id: &str, metadata: Option<&MavenArtifactMetadata>, plugins: &[MavenPluginMetadata]) -> String {
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<metadata>\n");
+
+    if let Some(metadata) = metadata {
+        let mut versions = metadata.versions.iter()
+            .map(|v| v.unqualified())
+            .collect::<Vec<_>>();
+        versions.sort_by(|a, b| compare_versions(a, b));
+        versions.dedup();
+
+        xml.push_str(&format!("  <groupId>{}</groupId>\n", escape(group_id)));
+        xml.push_str(&format!("  <artifactId>{}</artifactId>\n", escape(artifact_id)));
+        xml.push_str("  <versioning>\n");
+        xml.push_str(&format!("    <latest>{}</latest>\n", escape(metadata.latest_version.unqualified())));
+        if let Some(release_version) = &metadata.release_version {
+            xml.push_str(&format!("    <release>{}</release>\n", escape(release_version.unqualified())));
+        }
+        xml.push_str("    <versions>\n");
+        for version in versions {
+            xml.push_str(&format!("      <version>{}</version>\n", escape(version)));
+        }
+        xml.push_str("    </versions>\n");
+        xml.push_str(&format!("    <lastUpdated>{}</lastUpdated>\n", escape(&metadata.last_updated)));
+        xml.push_str("  </versioning>\n");
+    }
+
+    if !plugins.is_empty() {
+        xml.push_str("  <plugins>\n");
+        for plugin in plugins {
+            xml.push_str("    <plugin>\n");
+            xml.push_str(&format!("      <name>{}</name>\n", escape(&plugin.name)));
+            xml.push_str(&format!("      <prefix>{}</prefix>\n", escape(&plugin.prefix)));
+            xml.push_str(&format!("      <artifactId>{}</artifactId>\n", escape(&plugin.artifact_id.0)));
+            xml.push_str("    </plugin>\n");
+        }
+        xml.push_str("  </plugins>\n");
+    }
+
+    xml.push_str("</metadata>\n");
+    xml
 }
 
-pub struct Plugins {
-    plugin: Vec<Plugin>,
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
 }
 
-pub struct Plugin {
-    name: Option<String>,
-    prefix: Option<String>,
-    artifactId: String,
-}
\ No newline at end of file
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::*;
+
+    use super::*;
+
+    fn snapshot_version(classifier: Option<&str>, extension: &str, value: &str, updated: &str) -> SnapshotVersion {
+        SnapshotVersion {
+            classifier: classifier.map(|c| c.to_string()),
+            extension: extension.to_string(),
+            value: value.to_string(),
+            updated: updated.to_string(),
+        }
+    }
+
+    #[rstest]
+    #[case::build_number("1.0-20231114.221320-3", Some(("20231114.221320", Some(3))))]
+    #[case::no_build_number("1.0-20231114.221320", Some(("20231114.221320", None)))]
+    #[case::other_version("1.1-20231114.221320-3", None)]
+    #[case::not_timestamped("1.0-SNAPSHOT", None)]
+    #[case::invalid_build_number("1.0-20231114.221320-x", None)]
+    fn test_snapshot_version(#[case] value: &str, #[case] expected: Option<(&str, Option<u32>)>) {
+        let expected = expected.map(|(timestamp, build_number)| MavenVersion::Snapshot {
+            version: "1.0-SNAPSHOT".to_string(),
+            timestamp: timestamp.to_string(),
+            build_number,
+        });
+        assert_eq!(snapshot_version(None, "jar", value, "").version("1.0-SNAPSHOT"), expected);
+    }
+
+    #[test]
+    fn test_latest_per_file() {
+        let latest = SnapshotVersion::latest_per_file(vec![
+            snapshot_version(None, "jar", "1.0-20231114.221320-3", "20231114221320"),
+            snapshot_version(None, "jar", "1.0-20231114.221320-10", "20231114221320"),
+            snapshot_version(Some("sources"), "jar", "1.0-20231115.080000-11", "20231115080000"),
+            snapshot_version(None, "jar", "1.1-20231116.080000-1", "20231116080000"),
+        ], "1.0-SNAPSHOT");
+
+        assert_eq!(latest, vec![
+            snapshot_version(None, "jar", "1.0-20231114.221320-10", "20231114221320"),
+            snapshot_version(Some("sources"), "jar", "1.0-20231115.080000-11", "20231115080000"),
+        ]);
+    }
+
+    #[test]
+    fn test_render_and_parse_artifact_metadata() {
+        let metadata = MavenArtifactMetadata {
+            latest_version: MavenVersion::Release("1.10".to_string()),
+            release_version: Some(MavenVersion::Release("1.10".to_string())),
+            versions: vec![
+                MavenVersion::Release("1.10".to_string()),
+                MavenVersion::Release("1.9".to_string()),
+                MavenVersion::Snapshot { version: "2.0-SNAPSHOT".to_string(), timestamp: "20231114.221320".to_string(), build_number: Some(1) },
+            ],
+            last_updated: "20231114221320".to_string(),
+        };
+
+        let xml = render_artifact_metadata("com.example", "a", Some(&metadata), &[]);
+        assert!(xml.contains("<artifactId>a</artifactId>"));
+        assert!(xml.contains("<latest>1.10</latest>"));
+        assert!(xml.contains("<release>1.10</release>"));
+        assert!(xml.contains("<lastUpdated>20231114221320</lastUpdated>"));
+        assert!(!xml.contains("<plugins>"));
+        assert_eq!(parse_versions(&xml), vec!["1.9", "1.10", "2.0-SNAPSHOT"]);
+    }
+
+    #[rstest]
+    #[case::complete("<metadata><versioning><latest>2.0-SNAPSHOT</latest><release>1.1</release><versions><version>1.0</version><version>1.1</version><version>2.0-SNAPSHOT</version></versions><lastUpdated>20231114221320</lastUpdated></versioning></metadata>",
+        Some(("2.0-SNAPSHOT", Some("1.1"), "20231114221320")))]
+    #[case::derived("<metadata><versioning><versions><version>1.10</version><version>1.9</version><version>2.0-SNAPSHOT</version></versions></versioning></metadata>",
+        Some(("2.0-SNAPSHOT", Some("1.10"), "")))]
+    #[case::only_snapshots("<metadata><versioning><versions><version>1.0-SNAPSHOT</version></versions></versioning></metadata>",
+        Some(("1.0-SNAPSHOT", None, "")))]
+    #[case::no_versions("<metadata><plugins/></metadata>", None)]
+    fn test_parse_artifact_metadata(#[case] xml: &str, #[case] expected: Option<(&str, Option<&str>, &str)>) {
+        let actual = parse_artifact_metadata(xml)
+            .map(|m| (m.latest_version.unqualified().to_string(), m.release_version.map(|v| v.unqualified().to_string()), m.last_updated));
+        assert_eq!(actual, expected.map(|(latest, release, last_updated)| (latest.to_string(), release.map(|r| r.to_string()), last_updated.to_string())));
+    }
+
+    #[test]
+    fn test_render_group_metadata() {
+        let plugins = vec![MavenPluginMetadata {
+            name: "Example <Plugin>".to_string(),
+            prefix: "example".to_string(),
+            artifact_id: MavenArtifactId("example-maven-plugin".to_string()),
+        }];
+
+        let xml = render_artifact_metadata("com.example", "plugins", None, &plugins);
+        assert!(!xml.contains("<versioning>"));
+        assert!(xml.contains("<name>Example &lt;Plugin&gt;</name>"));
+        assert!(xml.contains("<prefix>example</prefix>"));
+        assert!(xml.contains("<artifactId>example-maven-plugin</artifactId>"));
+
+        assert_eq!(parse_plugins(&xml), plugins);
+    }
+
+    #[test]
+    fn test_parse_plugins() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<metadata>
+  <plugins>
+    <plugin>
+      <name>Apache Maven Clean Plugin</name>
+      <prefix>clean</prefix>
+      <artifactId>maven-clean-plugin</artifactId>
+    </plugin>
+    <plugin>
+      <prefix>compiler</prefix>
+      <artifactId>maven-compiler-plugin</artifactId>
+    </plugin>
+    <plugin>
+      <name>no prefix</name>
+      <artifactId>maven-broken-plugin</artifactId>
+    </plugin>
+  </plugins>
+</metadata>"#;
+
+        assert_eq!(parse_plugins(xml), vec![
+            MavenPluginMetadata { name: "Apache Maven Clean Plugin".to_string(), prefix: "clean".to_string(), artifact_id: MavenArtifactId("maven-clean-plugin".to_string()) },
+            MavenPluginMetadata { name: "".to_string(), prefix: "compiler".to_string(), artifact_id: MavenArtifactId("maven-compiler-plugin".to_string()) },
+        ]);
+    }
+
+    #[test]
+    fn test_render_and_parse_snapshot_metadata() {
+        let snapshot_versions = vec![
+            snapshot_version(None, "jar", "1.0-20231114.221320-3", "20231114221320"),
+            snapshot_version(Some("sources"), "jar", "1.0-20231114.221320-3", "20231114221320"),
+            snapshot_version(None, "pom", "1.0-20231115.080000-4", "20231115080000"),
+        ];
+
+        let xml = render_snapshot_metadata("com.example", "a", "1.0-SNAPSHOT", &snapshot_versions);
+        assert!(xml.contains("<timestamp>20231115.080000</timestamp>"));
+        assert!(xml.contains("<buildNumber>4</buildNumber>"));
+        assert!(xml.contains("<lastUpdated>20231115080000</lastUpdated>"));
+
+        assert_eq!(parse_snapshot_versions(&xml), snapshot_versions);
+    }
+}
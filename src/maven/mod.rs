@@ -1,7 +1,34 @@
+pub mod access_times;
+pub mod advisories;
+pub mod artifact_key;
+pub mod blocking_rules_file;
+pub mod checksums;
 pub mod coordinates;
+pub mod dependency_updates;
+pub mod deploy_transactions;
+pub mod directory_listing;
+pub mod hosted_repo;
+pub mod jar_diff;
+pub mod jar_index;
 pub mod maven_repo_metadata;
+pub mod metadata_backup;
+pub mod metadata_consistency;
+pub mod metadata_export;
 pub mod metadata_xml;
+pub mod naming_policy;
+pub mod negative_cache;
 pub mod paths;
+pub mod pending_deploys;
+pub mod platform_classifiers;
+pub mod plugin_prefix;
+pub mod prefetch_plan;
+pub mod project_info;
+pub mod quarantine;
 pub mod remote_repo;
+pub mod snapshot_retention;
+pub mod timestamps;
+pub mod version_blocking;
+pub mod version_list;
+pub mod version_resolution;
 
 
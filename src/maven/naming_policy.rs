@@ -0,0 +1,232 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::maven::coordinates::MavenCoordinates;
+
+lazy_static! {
+    static ref SEMVER_REGEX: Regex = Regex::new(r"^\d+\.\d+\.\d+(-[0-9A-Za-z][0-9A-Za-z.-]*)?$").unwrap();
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VersionFormat {
+    #[default]
+    Any,
+    /// 'MAJOR.MINOR.PATCH' with an optional qualifier, e.g. '1.2.0', '1.2.0-rc1' or '1.2.0-SNAPSHOT'
+    Semver,
+}
+impl FromStr for VersionFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<VersionFormat> {
+        match s {
+            "any" => Ok(VersionFormat::Any),
+            "semver" => Ok(VersionFormat::Semver),
+            _ => Err(anyhow!("invalid version format '{}', must be 'any' or 'semver'", s)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NamingEnforcement {
+    /// deploys that violate the policy are refused
+    #[default]
+    Reject,
+    /// violations are only logged and recorded in the audit log, e.g. during a transition period
+    ///  before a new policy is enforced
+    Warn,
+}
+impl FromStr for NamingEnforcement {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<NamingEnforcement> {
+        match s {
+            "reject" => Ok(NamingEnforcement::Reject),
+            "warn" => Ok(NamingEnforcement::Warn),
+            _ => Err(anyhow!("invalid naming enforcement '{}', must be 'reject' or 'warn'", s)),
+        }
+    }
+}
+
+/// Conventions for the coordinates of artifacts deployed to a hosted repository. Patterns are
+///  regular expressions that must match the entire group or artifact id.
+#[derive(Clone, Eq, PartialEq, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NamingPolicyConfig {
+    /// e.g. 'com\.example(\..+)?' for 'com.example' and its subgroups
+    pub group_id_pattern: Option<String>,
+    /// e.g. '[a-z][a-z0-9]*(-[a-z0-9]+)*' for lower case words separated by '-'
+    pub artifact_id_pattern: Option<String>,
+    pub version_format: VersionFormat,
+    pub enforcement: NamingEnforcement,
+}
+impl NamingPolicyConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.group_id_pattern.is_some() || self.artifact_id_pattern.is_some() || self.version_format != VersionFormat::Any
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NamingRule {
+    GroupId,
+    ArtifactId,
+    VersionFormat,
+}
+
+/// How deployed coordinates violate a [NamingPolicy]
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+pub struct NamingViolation {
+    pub rule: NamingRule,
+    /// the offending group id, artifact id or version
+    pub value: String,
+    pub message: String,
+}
+
+/// A [NamingPolicyConfig] with compiled patterns
+#[derive(Clone, Debug)]
+pub struct NamingPolicy {
+    group_id: Option<Regex>,
+    artifact_id: Option<Regex>,
+    version_format: VersionFormat,
+    pub enforcement: NamingEnforcement,
+}
+impl NamingPolicy {
+    pub fn new(config: &NamingPolicyConfig) -> anyhow::Result<NamingPolicy> {
+        Ok(NamingPolicy {
+            group_id: anchored(config.group_id_pattern.as_deref()).context("invalid group id pattern")?,
+            artifact_id: anchored(config.artifact_id_pattern.as_deref()).context("invalid artifact id pattern")?,
+            version_format: config.version_format,
+            enforcement: config.enforcement,
+        })
+    }
+
+    /// All of the ways the coordinates violate the policy, i.e. an empty list if they conform. For
+    ///  snapshots, the version without the timestamp is checked.
+    pub fn check(&self, coordinates: &MavenCoordinates) -> Vec<NamingViolation> {
+        let mut violations = vec![];
+        if let Some(regex) = &self.group_id {
+            if !regex.is_match(&coordinates.group_id.0) {
+                violations.push(NamingViolation {
+                    rule: NamingRule::GroupId,
+                    value: coordinates.group_id.0.clone(),
+                    message: format!("group id '{}' does not match '{}'", coordinates.group_id.0, unanchored(regex)),
+                });
+            }
+        }
+        if let Some(regex) = &self.artifact_id {
+            if !regex.is_match(&coordinates.artifact_id.0) {
+                violations.push(NamingViolation {
+                    rule: NamingRule::ArtifactId,
+                    value: coordinates.artifact_id.0.clone(),
+                    message: format!("artifact id '{}' does not match '{}'", coordinates.artifact_id.0, unanchored(regex)),
+                });
+            }
+        }
+        let version = coordinates.version.unqualified();
+        if self.version_format == VersionFormat::Semver && !SEMVER_REGEX.is_match(version) {
+            violations.push(NamingViolation {
+                rule: NamingRule::VersionFormat,
+                value: version.to_string(),
+                message: format!("version '{}' is not 'MAJOR.MINOR.PATCH' with an optional '-<qualifier>'", version),
+            });
+        }
+        violations
+    }
+}
+
+fn anchored(pattern: Option<&str>) -> anyhow::Result<Option<Regex>> {
+    pattern
+        .map(|pattern| Regex::new(&format!("^(?:{})$", pattern)))
+        .transpose()
+        .map_err(anyhow::Error::from)
+}
+
+/// the configured pattern, for messages
+fn unanchored(regex: &Regex) -> &str {
+    let pattern = regex.as_str();
+    &pattern["^(?:".len()..pattern.len() - ")$".len()]
+}
+
+/// A deploy that was refused for violating its repository's [NamingPolicy]. Frontends answer it
+///  with '400 Bad Request' and the violations.
+#[derive(Clone, Debug)]
+pub struct NamingPolicyError {
+    pub path: String,
+    pub violations: Vec<NamingViolation>,
+}
+impl NamingPolicyError {
+    /// A machine-readable response body
+    pub fn as_json(&self) -> String {
+        serde_json::json!({
+            "error": "naming_policy",
+            "message": self.to_string(),
+            "violations": self.violations,
+        }).to_string()
+    }
+}
+impl Display for NamingPolicyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let messages = self.violations.iter()
+            .map(|violation| violation.message.as_str())
+            .collect::<Vec<_>>();
+        write!(f, "{} violates the repository's naming policy: {}", self.path, messages.join(", "))
+    }
+}
+impl std::error::Error for NamingPolicyError {}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use crate::maven::paths::parse_maven_path;
+
+    use super::*;
+
+    fn policy() -> NamingPolicy {
+        NamingPolicy::new(&NamingPolicyConfig {
+            group_id_pattern: Some(r"com\.example(\..+)?".to_string()),
+            artifact_id_pattern: Some("[a-z][a-z0-9]*(-[a-z0-9]+)*".to_string()),
+            version_format: VersionFormat::Semver,
+            enforcement: NamingEnforcement::Reject,
+        }).unwrap()
+    }
+
+    #[rstest]
+    #[case("com/example/lib/1.2.0/lib-1.2.0.jar", vec![])]
+    #[case("com/example/tools/cli-tool/1.2.0-rc1/cli-tool-1.2.0-rc1.pom", vec![])]
+    #[case("com/example/lib/1.2.0-SNAPSHOT/lib-1.2.0-SNAPSHOT-20240101.120000-1.jar", vec![])]
+    #[case("com/examples/lib/1.2.0/lib-1.2.0.jar", vec![NamingRule::GroupId])]
+    #[case("org/com/example/lib/1.2.0/lib-1.2.0.jar", vec![NamingRule::GroupId])]
+    #[case("com/example/myLib/1.2/myLib-1.2.jar", vec![NamingRule::ArtifactId, NamingRule::VersionFormat])]
+    #[case("com/example/lib/1.2.0.Final/lib-1.2.0.Final.jar", vec![NamingRule::VersionFormat])]
+    fn test_check(#[case] path: &str, #[case] expected: Vec<NamingRule>) {
+        let coordinates = parse_maven_path(path).unwrap().coordinates;
+        assert_eq!(policy().check(&coordinates).iter().map(|v| v.rule).collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_messages() {
+        let coordinates = parse_maven_path("org/acme/lib/1.0/lib-1.0.jar").unwrap().coordinates;
+        let e = NamingPolicyError {
+            path: "org/acme/lib/1.0/lib-1.0.jar".to_string(),
+            violations: policy().check(&coordinates),
+        };
+        assert_eq!(e.violations[0].message, r"group id 'org.acme' does not match 'com\.example(\..+)?'");
+        let json: serde_json::Value = serde_json::from_str(&e.as_json()).unwrap();
+        assert_eq!(json["violations"][1]["rule"], "version_format");
+        assert_eq!(json["violations"][1]["value"], "1.0");
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        assert!(NamingPolicy::new(&NamingPolicyConfig { group_id_pattern: Some("com\\.(".to_string()), ..Default::default() }).is_err());
+        assert!(NamingPolicy::new(&Default::default()).unwrap().check(&parse_maven_path("A/B/x/B-x.jar").unwrap().coordinates).is_empty());
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::sync::RwLock;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::maven::artifact_key::ArtifactKey;
+
+/// Why downloading an artifact failed, as far as remembering the failure is concerned
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum DownloadFailureKind {
+    /// upstream answered that the artifact does not exist
+    NotFound,
+    /// upstream could not be reached or failed, so the artifact may well exist
+    TransportError,
+}
+
+/// How long failed downloads are remembered before an artifact is requested upstream again
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub struct NegativeCachePolicy {
+    pub not_found_ttl: Duration,
+    /// zero disables remembering transport errors, leaving upstream outages to the circuit breaker
+    pub transport_error_ttl: Duration,
+    /// bounds memory usage e.g. for clients probing for many non-existent artifacts
+    pub max_entries: usize,
+    /// up to this much is added to each entry's TTL, so that artifacts that failed at the same
+    ///  time (e.g. during an outage) are not requested again all at once
+    pub jitter: Duration,
+}
+impl Default for NegativeCachePolicy {
+    fn default() -> NegativeCachePolicy {
+        NegativeCachePolicy {
+            not_found_ttl: Duration::from_secs(300),
+            transport_error_ttl: Duration::from_secs(30),
+            max_entries: 100_000,
+            jitter: Duration::ZERO,
+        }
+    }
+}
+impl NegativeCachePolicy {
+    pub fn ttl_for(&self, kind: DownloadFailureKind) -> Duration {
+        match kind {
+            DownloadFailureKind::NotFound => self.not_found_ttl,
+            DownloadFailureKind::TransportError => self.transport_error_ttl,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct FailedDownload {
+    kind: DownloadFailureKind,
+    expires_at: Instant,
+}
+
+/// Failed downloads by artifact, see [NegativeCachePolicy]
+pub struct NegativeCache {
+    policy: NegativeCachePolicy,
+    entries: RwLock<HashMap<ArtifactKey, FailedDownload>>,
+    /// randomly seeded per process, for spreading jitter across artifacts
+    jitter_seed: RandomState,
+}
+impl NegativeCache {
+    pub fn new(policy: NegativeCachePolicy) -> NegativeCache {
+        NegativeCache {
+            policy,
+            entries: Default::default(),
+            jitter_seed: RandomState::new(),
+        }
+    }
+
+    pub fn policy(&self) -> &NegativeCachePolicy {
+        &self.policy
+    }
+
+    /// The failure's kind and the time after which the artifact can be requested again, None if
+    ///  no failure is remembered
+    pub fn get(&self, key: &ArtifactKey) -> Option<(DownloadFailureKind, SystemTime)> {
+        let entry = *self.entries.read().unwrap().get(key)?;
+        let now = Instant::now();
+        if entry.expires_at <= now {
+            self.entries.write().unwrap().remove(key);
+            return None;
+        }
+        Some((entry.kind, SystemTime::now() + (entry.expires_at - now)))
+    }
+
+    pub fn insert(&self, key: ArtifactKey, kind: DownloadFailureKind) {
+        let ttl = self.policy.ttl_for(kind);
+        if ttl.is_zero() || self.policy.max_entries == 0 {
+            return;
+        }
+        let now = Instant::now();
+        let expires_at = now + ttl + self.jitter_for(&key);
+
+        let mut entries = self.entries.write().unwrap();
+        if entries.len() >= self.policy.max_entries && !entries.contains_key(&key) {
+            entries.retain(|_, e| e.expires_at > now);
+        }
+        if entries.len() >= self.policy.max_entries && !entries.contains_key(&key) {
+            let soonest = entries.iter()
+                .min_by_key(|(_, e)| e.expires_at)
+                .map(|(k, _)| k.clone());
+            if let Some(soonest) = soonest {
+                entries.remove(&soonest);
+            }
+        }
+        entries.insert(key, FailedDownload { kind, expires_at });
+    }
+
+    /// Forgets a failed download, returning true if one was remembered
+    pub fn remove(&self, key: &ArtifactKey) -> bool {
+        self.entries.write().unwrap().remove(key).is_some()
+    }
+
+    /// Forgets the failed downloads of all artifacts in a group and its subgroups, returning their
+    ///  number
+    pub fn remove_group(&self, group_prefix: &str) -> usize {
+        let mut entries = self.entries.write().unwrap();
+        let len_before = entries.len();
+        entries.retain(|key, _| !key.is_in_group(group_prefix));
+        len_before - entries.len()
+    }
+
+    pub fn clear(&self) {
+        self.entries.write().unwrap().clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn jitter_for(&self, key: &ArtifactKey) -> Duration {
+        let max_nanos = self.policy.jitter.as_nanos() as u64;
+        if max_nanos == 0 {
+            return Duration::ZERO;
+        }
+        let mut hasher = self.jitter_seed.build_hasher();
+        key.hash(&mut hasher);
+        Instant::now().hash(&mut hasher);
+        Duration::from_nanos(hasher.finish() % max_nanos)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::maven::paths::parse_maven_path;
+
+    use super::*;
+
+    fn key(path: &str) -> ArtifactKey {
+        ArtifactKey::for_artifact(&parse_maven_path(path).unwrap())
+    }
+
+    #[test]
+    fn test_ttl_by_kind() {
+        let cache = NegativeCache::new(NegativeCachePolicy {
+            transport_error_ttl: Duration::ZERO,
+            ..Default::default()
+        });
+        cache.insert(key("org/a/a/1.0/a-1.0.jar"), DownloadFailureKind::NotFound);
+        cache.insert(key("org/b/b/1.0/b-1.0.jar"), DownloadFailureKind::TransportError);
+
+        let (kind, retry_at) = cache.get(&key("org/a/a/1.0/a-1.0.jar")).unwrap();
+        assert_eq!(kind, DownloadFailureKind::NotFound);
+        assert!(retry_at > SystemTime::now() + Duration::from_secs(290));
+        assert!(cache.get(&key("org/b/b/1.0/b-1.0.jar")).is_none());
+    }
+
+    #[test]
+    fn test_expired() {
+        let cache = NegativeCache::new(NegativeCachePolicy {
+            not_found_ttl: Duration::from_millis(1),
+            ..Default::default()
+        });
+        cache.insert(key("org/a/a/1.0/a-1.0.jar"), DownloadFailureKind::NotFound);
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(cache.get(&key("org/a/a/1.0/a-1.0.jar")).is_none());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_max_entries() {
+        let cache = NegativeCache::new(NegativeCachePolicy {
+            max_entries: 2,
+            ..Default::default()
+        });
+        for artifact in ["a", "b", "c"] {
+            cache.insert(key(&format!("org/{0}/{0}/1.0/{0}-1.0.jar", artifact)), DownloadFailureKind::NotFound);
+        }
+        assert_eq!(cache.len(), 2);
+        // the entry that expires first is evicted
+        assert!(cache.get(&key("org/a/a/1.0/a-1.0.jar")).is_none());
+        assert!(cache.get(&key("org/c/c/1.0/c-1.0.jar")).is_some());
+    }
+
+    #[test]
+    fn test_remove_group() {
+        let cache = NegativeCache::new(Default::default());
+        for path in ["com/acme/a/1.0/a-1.0.jar", "com/acme/sub/b/1.0/b-1.0.jar", "com/acmex/c/1.0/c-1.0.jar"] {
+            cache.insert(key(path), DownloadFailureKind::NotFound);
+        }
+        assert_eq!(cache.remove_group("com.Acme"), 2);
+        assert!(cache.get(&key("com/acmex/c/1.0/c-1.0.jar")).is_some());
+        assert!(cache.remove(&key("com/acmex/c/1.0/c-1.0.jar")));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_jitter() {
+        let cache = NegativeCache::new(NegativeCachePolicy {
+            jitter: Duration::from_secs(60),
+            ..Default::default()
+        });
+        cache.insert(key("org/a/a/1.0/a-1.0.jar"), DownloadFailureKind::NotFound);
+        let (_, retry_at) = cache.get(&key("org/a/a/1.0/a-1.0.jar")).unwrap();
+        assert!(retry_at < SystemTime::now() + Duration::from_secs(361));
+    }
+}
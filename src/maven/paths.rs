@@ -1,20 +1,176 @@
 
 
 use anyhow::anyhow;
+use hex::ToHex;
 use lazy_static::lazy_static;
 use regex::Regex;
+use serde::Deserialize;
+use sha1::{Digest, Sha1};
+use sha2::{Digest as _, Sha256, Sha512};
 use crate::maven::coordinates::*;
 
 lazy_static! {
     static ref TIMESTAMP_REGEX: Regex = Regex::new(r"-\d{8}\.\d{6}").unwrap();
+    /// the end of a snapshot file name without an extension, e.g. 'a-1.0-SNAPSHOT-20231114.221320-7'
+    static ref SNAPSHOT_END_REGEX: Regex = Regex::new(r"-\d{8}\.\d{6}(-\d+)?$").unwrap();
+}
+
+/// Files accompanying an artifact, e.g. 'a-1.0.jar.sha1'. Their suffix is treated as part of the
+///  extension so that it does not get mistaken for part of a classifier or build number. They
+///  can be stacked, e.g. 'a-1.0.jar.asc.sha1' for the checksum of a signature.
+const COMPANION_SUFFIXES: [&str; 5] = [".sha1", ".md5", ".sha256", ".sha512", ".asc"];
+
+/// Extensions that contain a dot themselves. Other extensions end at the last dot, so that
+///  classifiers may contain dots (e.g. 'a-1.0-linux.x86_64.zip').
+const COMPOUND_EXTENSIONS: [&str; 5] = [".tar.gz", ".tar.bz2", ".tar.xz", ".tar.zst", ".tar.lz"];
+
+/// Checksum files that are served from (and validated against) the checksums of an artifact's
+///  blob. SHA-1 and MD5 are stored with every blob, the others are computed when they are needed,
+///  see [RepositoryChecksums](crate::maven::checksums::RepositoryChecksums).
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumKind {
+    Sha1,
+    Md5,
+    Sha256,
+    Sha512,
+}
+impl ChecksumKind {
+    pub const ALL: [ChecksumKind; 4] = [ChecksumKind::Sha1, ChecksumKind::Md5, ChecksumKind::Sha256, ChecksumKind::Sha512];
+
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            ChecksumKind::Sha1 => ".sha1",
+            ChecksumKind::Md5 => ".md5",
+            ChecksumKind::Sha256 => ".sha256",
+            ChecksumKind::Sha512 => ".sha512",
+        }
+    }
+
+    /// e.g. 'sha1', as in configuration and 'x-checksum-*' headers
+    pub fn name(&self) -> &'static str {
+        &self.suffix()[1..]
+    }
+
+    /// whether blob storage keeps this checksum with every blob
+    pub fn is_stored(&self) -> bool {
+        matches!(self, ChecksumKind::Sha1 | ChecksumKind::Md5)
+    }
+
+    /// The content of a checksum file for the given data, i.e. the hex encoded checksum
+    pub fn checksum_of(&self, data: &[u8]) -> String {
+        match self {
+            ChecksumKind::Sha1 => Sha1::digest(data).encode_hex(),
+            ChecksumKind::Md5 => md5::compute(data).encode_hex(),
+            ChecksumKind::Sha256 => Sha256::digest(data).encode_hex(),
+            ChecksumKind::Sha512 => Sha512::digest(data).encode_hex(),
+        }
+    }
+
+    /// the checksum kind for a checksum file's extension, e.g. '.sha1'
+    pub fn for_file_extension(file_extension: &str) -> Option<ChecksumKind> {
+        ChecksumKind::ALL.into_iter()
+            .find(|kind| kind.suffix() == file_extension)
+    }
+
+    fn for_file_name(file_name: &str) -> Option<ChecksumKind> {
+        ChecksumKind::ALL.into_iter()
+            .find(|kind| file_name.ends_with(kind.suffix()))
+    }
+}
+
+/// For a checksum file, returns the kind of checksum and the artifact it is a checksum of
+pub fn checksum_target(artifact_ref: &MavenArtifactRef) -> Option<(ChecksumKind, MavenArtifactRef)> {
+    let kind = ChecksumKind::for_file_name(&artifact_ref.file_extension)?;
+    let extension = &artifact_ref.file_extension[..artifact_ref.file_extension.len() - kind.suffix().len()];
+    if extension.is_empty() {
+        return None;
+    }
+
+    Some((kind, MavenArtifactRef {
+        file_extension: extension.to_string(),
+        ..artifact_ref.clone()
+    }))
+}
+
+/// A version level 'maven-metadata.xml' file for a snapshot version, or a checksum of it
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct SnapshotMetadataPath {
+    pub group_id: MavenGroupId,
+    pub artifact_id: MavenArtifactId,
+    /// ending in '-SNAPSHOT'
+    pub version: String,
+    pub checksum: Option<ChecksumKind>,
+}
+
+/// Recognizes paths like 'com/example/a/1.0-SNAPSHOT/maven-metadata.xml', optionally with a
+///  checksum suffix
+pub fn parse_snapshot_metadata_path(path: &str) -> Option<SnapshotMetadataPath> {
+    let (without_filename, file_name) = path.rsplit_once('/')?;
+    let checksum = ChecksumKind::for_file_name(file_name);
+    let file_name = &file_name[..file_name.len() - checksum.map(|c| c.suffix().len()).unwrap_or(0)];
+    if file_name != "maven-metadata.xml" {
+        return None;
+    }
+
+    let (without_version, version) = without_filename.rsplit_once('/')?;
+    if !version.ends_with("-SNAPSHOT") {
+        return None;
+    }
+    let (group_id, artifact_id) = without_version.rsplit_once('/')?;
+    if group_id.is_empty() || artifact_id.is_empty() {
+        return None;
+    }
+
+    Some(SnapshotMetadataPath {
+        group_id: MavenGroupId(group_id.replace('/', ".")),
+        artifact_id: MavenArtifactId(artifact_id.to_string()),
+        version: version.to_string(),
+        checksum,
+    })
+}
+
+/// An artifact level 'maven-metadata.xml' file, or a checksum of it. The path is ambiguous: Maven
+///  requests group level metadata (listing plugin prefixes) at the same kind of path, so
+///  'com/example/a/maven-metadata.xml' is also the group level metadata of 'com.example.a'.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ArtifactMetadataPath {
+    pub group_id: MavenGroupId,
+    pub artifact_id: MavenArtifactId,
+    pub checksum: Option<ChecksumKind>,
+}
+impl ArtifactMetadataPath {
+    /// the group this path is the group level metadata of
+    pub fn plugin_group_id(&self) -> MavenGroupId {
+        MavenGroupId(format!("{}.{}", self.group_id.0, self.artifact_id.0))
+    }
+}
+
+/// Recognizes paths like 'com/example/a/maven-metadata.xml', optionally with a checksum suffix.
+///  Version level metadata of snapshots is handled by [parse_snapshot_metadata_path].
+pub fn parse_artifact_metadata_path(path: &str) -> Option<ArtifactMetadataPath> {
+    let (without_filename, file_name) = path.rsplit_once('/')?;
+    let checksum = ChecksumKind::for_file_name(file_name);
+    let file_name = &file_name[..file_name.len() - checksum.map(|c| c.suffix().len()).unwrap_or(0)];
+    if file_name != "maven-metadata.xml" {
+        return None;
+    }
+
+    let (group_id, artifact_id) = without_filename.rsplit_once('/')?;
+    if group_id.is_empty() || artifact_id.is_empty() || artifact_id.ends_with("-SNAPSHOT") {
+        return None;
+    }
+
+    Some(ArtifactMetadataPath {
+        group_id: MavenGroupId(group_id.replace('/', ".")),
+        artifact_id: MavenArtifactId(artifact_id.to_string()),
+        checksum,
+    })
 }
 
 
 pub fn as_maven_path(artifact_ref: &MavenArtifactRef) -> String {
-    let version_string = match &artifact_ref.coordinates.version {
-        MavenVersion::Release(s) => s,
-        MavenVersion::Snapshot { version, .. } => version,
-    };
+    let version_string = artifact_ref.coordinates.version.unqualified();
 
     format!(
         "{}/{}/{}/{}",
@@ -28,26 +184,15 @@ pub fn as_maven_path(artifact_ref: &MavenArtifactRef) -> String {
 
 fn parse_maven_filename<'a>(file_name: &'a str, artifact_id: &str, version_string: &str) -> anyhow::Result<ParseFilenameResult<'a>> {
     let full_file_name = file_name;
-    if file_name.len() < artifact_id.len() + version_string.len() + 2 {
-        return Err(anyhow!("not a valid maven file name: {}", full_file_name));
-    }
 
-    if !file_name.starts_with(artifact_id) {
-        return Err(anyhow!("{} is not a valid maven file name: expected to start with artifact id {}", full_file_name, artifact_id));
-    }
-    let file_name = &file_name[artifact_id.len()+1 ..];
+    let file_name = file_name.strip_prefix(artifact_id)
+        .and_then(|s| s.strip_prefix('-'))
+        .ok_or_else(|| anyhow!("{} is not a valid maven file name: expected to start with artifact id {}", full_file_name, artifact_id))?;
 
-    if !file_name.starts_with(version_string) {
-        return Err(anyhow!("{} is not a valid maven file name: expected to have version string {}", full_file_name, version_string));
-    }
-    let file_name = &file_name[version_string.len() ..];
+    let file_name = file_name.strip_prefix(version_string)
+        .ok_or_else(|| anyhow!("{} is not a valid maven file name: expected to have version string {}", full_file_name, version_string))?;
 
-    let (file_name, extension) = if let Some(last_dot) = file_name.rfind('.') {
-        (&file_name[..last_dot], &file_name[last_dot..])
-    }
-    else {
-        (file_name, "")
-    };
+    let (file_name, extension) = split_extension(file_name, version_string.contains("-SNAPSHOT"));
 
     if version_string.contains("-SNAPSHOT") {
         // <artifactId>-<version>-<classifier>-<timestamp>-<buildNumber>.<extension>
@@ -109,6 +254,31 @@ fn parse_maven_filename<'a>(file_name: &'a str, artifact_id: &str, version_strin
     }
 }
 
+/// Splits the part of a file name after the version into classifier (and snapshot timestamp) on
+///  the one hand and the extension (including companion suffixes) on the other
+fn split_extension(file_name: &str, is_snapshot: bool) -> (&str, &str) {
+    let mut without_suffixes = file_name;
+    while let Some(without_suffix) = COMPANION_SUFFIXES.iter().find_map(|suffix| without_suffixes.strip_suffix(suffix)) {
+        without_suffixes = without_suffix;
+    }
+
+    let extension_start = if is_snapshot && SNAPSHOT_END_REGEX.is_match(without_suffixes) {
+        // the dot is the timestamp's
+        Some(without_suffixes.len())
+    }
+    else {
+        COMPOUND_EXTENSIONS.iter()
+            .find_map(|extension| without_suffixes.strip_suffix(extension))
+            .map(|without_extension| without_extension.len())
+            .or_else(|| without_suffixes.rfind('.'))
+    };
+
+    match extension_start {
+        Some(extension_start) => file_name.split_at(extension_start),
+        None => (without_suffixes, &file_name[without_suffixes.len()..]),
+    }
+}
+
 fn parse_classifier_and_timestamp<'a> (file_name: &'a str, full_file_name: &str) -> anyhow::Result<(Option<&'a str>, &'a str)> {
     if file_name.len() < 16 {
         return Err(anyhow!("snapshot without timestamp: {}", full_file_name));
@@ -170,7 +340,8 @@ pub fn parse_maven_path(path: &str) -> anyhow::Result<MavenArtifactRef> {
 
 
 
-fn maven_file_name(artifact_ref: &MavenArtifactRef) -> String {
+/// The file name of an artifact, e.g. 'a-1.0-sources.jar'
+pub fn maven_file_name(artifact_ref: &MavenArtifactRef) -> String {
     let classifier_string = match &artifact_ref.classifier {
         MavenClassifier::Unclassified => "".to_string(),
         MavenClassifier::Classified(c) => format!("-{}", c),
@@ -251,6 +422,33 @@ mod test {
     #[case::snapshot_invalid_build_number("a-1.0.0-SNAPSHOT-12345678.123456-a.jar", "a", "1.0.0-SNAPSHOT", None)]
 
     #[case::snapshot_lowercase_snapshot("a-1.0.0-snapshot-12345678.123456-a.jar", "a", "1.0.0-snapshot", Some(ParseFilenameResult{ version: MavenVersion::Release("1.0.0-snapshot".to_string()), classifier: Some("12345678.123456-a"), extension: ".jar"}))]
+
+    #[case::release_checksum("a-1.0.0.jar.sha1", "a", "1.0.0", Some(ParseFilenameResult{ version: MavenVersion::Release("1.0.0".to_string()), classifier: None, extension: ".jar.sha1"} ))]
+    #[case::release_classifier_checksum("a-1.0.0-cla.jar.md5", "a", "1.0.0", Some(ParseFilenameResult{ version: MavenVersion::Release("1.0.0".to_string()), classifier: Some("cla"), extension: ".jar.md5"} ))]
+    #[case::release_signature("a-1.0.0.pom.asc", "a", "1.0.0", Some(ParseFilenameResult{ version: MavenVersion::Release("1.0.0".to_string()), classifier: None, extension: ".pom.asc"} ))]
+    #[case::release_only_checksum_suffix("a-1.0.0.sha1", "a", "1.0.0", Some(ParseFilenameResult{ version: MavenVersion::Release("1.0.0".to_string()), classifier: None, extension: ".sha1"} ))]
+    #[case::snapshot_checksum("a-1.0.0-SNAPSHOT-12345678.123456.jar.sha1", "a", "1.0.0-SNAPSHOT", Some(ParseFilenameResult{ version: MavenVersion::Snapshot { version: "1.0.0-SNAPSHOT".to_string(), timestamp: "12345678.123456".to_string(), build_number: None }, classifier: None, extension: ".jar.sha1"}))]
+    #[case::snapshot_build_number_checksum("a-1.0.0-SNAPSHOT-12345678.123456-5.jar.md5", "a", "1.0.0-SNAPSHOT", Some(ParseFilenameResult{ version: MavenVersion::Snapshot { version: "1.0.0-SNAPSHOT".to_string(), timestamp: "12345678.123456".to_string(), build_number: Some(5) }, classifier: None, extension: ".jar.md5"}))]
+    #[case::snapshot_classifier_with_dash_checksum("a-1.0.0-SNAPSHOT-a-b-c-22222222.222222-5.jar.sha1", "a", "1.0.0-SNAPSHOT", Some(ParseFilenameResult{ version: MavenVersion::Snapshot { version: "1.0.0-SNAPSHOT".to_string(), timestamp: "22222222.222222".to_string(), build_number: Some(5) }, classifier: Some("a-b-c"), extension: ".jar.sha1"}))]
+    #[case::snapshot_classifier_like_timestamp_checksum("a-1.0.0-SNAPSHOT-11111111.111111-22222222.222222-5.pom.sha1", "a", "1.0.0-SNAPSHOT", Some(ParseFilenameResult{ version: MavenVersion::Snapshot { version: "1.0.0-SNAPSHOT".to_string(), timestamp: "22222222.222222".to_string(), build_number: Some(5) }, classifier: Some("11111111.111111"), extension: ".pom.sha1"}))]
+    #[case::snapshot_without_timestamp_checksum("a-1.0.0-SNAPSHOT.jar.sha1", "a", "1.0.0-SNAPSHOT", None)]
+    #[case::release_checksum_of_checksum("a-1.0.0.pom.sha1.md5", "a", "1.0.0", Some(ParseFilenameResult{ version: MavenVersion::Release("1.0.0".to_string()), classifier: None, extension: ".pom.sha1.md5"} ))]
+    #[case::release_signature_checksum("a-1.0.0-cla.jar.asc.sha256", "a", "1.0.0", Some(ParseFilenameResult{ version: MavenVersion::Release("1.0.0".to_string()), classifier: Some("cla"), extension: ".jar.asc.sha256"} ))]
+
+    #[case::release_compound_extension("a-1.0.0.tar.gz", "a", "1.0.0", Some(ParseFilenameResult{ version: MavenVersion::Release("1.0.0".to_string()), classifier: None, extension: ".tar.gz"} ))]
+    #[case::release_classifier_compound_extension("a-1.0.0-bin.tar.gz", "a", "1.0.0", Some(ParseFilenameResult{ version: MavenVersion::Release("1.0.0".to_string()), classifier: Some("bin"), extension: ".tar.gz"} ))]
+    #[case::release_compound_extension_checksum("a-1.0.0-bin.tar.bz2.sha1", "a", "1.0.0", Some(ParseFilenameResult{ version: MavenVersion::Release("1.0.0".to_string()), classifier: Some("bin"), extension: ".tar.bz2.sha1"} ))]
+    #[case::release_classifier_with_dots("a-1.0.0-linux.x86_64.zip", "a", "1.0.0", Some(ParseFilenameResult{ version: MavenVersion::Release("1.0.0".to_string()), classifier: Some("linux.x86_64"), extension: ".zip"} ))]
+    #[case::release_classifier_with_dots_compound_extension("a-1.0.0-linux.x86_64.tar.xz", "a", "1.0.0", Some(ParseFilenameResult{ version: MavenVersion::Release("1.0.0".to_string()), classifier: Some("linux.x86_64"), extension: ".tar.xz"} ))]
+    #[case::snapshot_compound_extension("a-1.0.0-SNAPSHOT-dist-12345678.123456-5.tar.gz", "a", "1.0.0-SNAPSHOT", Some(ParseFilenameResult{ version: MavenVersion::Snapshot { version: "1.0.0-SNAPSHOT".to_string(), timestamp: "12345678.123456".to_string(), build_number: Some(5) }, classifier: Some("dist"), extension: ".tar.gz"}))]
+
+    #[case::release_without_extension("a-1.0.0", "a", "1.0.0", Some(ParseFilenameResult{ version: MavenVersion::Release("1.0.0".to_string()), classifier: None, extension: ""} ))]
+    #[case::release_classifier_without_extension("a-1.0.0-bin", "a", "1.0.0", Some(ParseFilenameResult{ version: MavenVersion::Release("1.0.0".to_string()), classifier: Some("bin"), extension: ""} ))]
+    #[case::release_without_extension_checksum("a-1.0.0-bin.sha1", "a", "1.0.0", Some(ParseFilenameResult{ version: MavenVersion::Release("1.0.0".to_string()), classifier: Some("bin"), extension: ".sha1"} ))]
+    #[case::snapshot_without_extension("a-1.0.0-SNAPSHOT-12345678.123456-5", "a", "1.0.0-SNAPSHOT", Some(ParseFilenameResult{ version: MavenVersion::Snapshot { version: "1.0.0-SNAPSHOT".to_string(), timestamp: "12345678.123456".to_string(), build_number: Some(5) }, classifier: None, extension: ""}))]
+    #[case::snapshot_classifier_without_extension_checksum("a-1.0.0-SNAPSHOT-cla-12345678.123456-5.md5", "a", "1.0.0-SNAPSHOT", Some(ParseFilenameResult{ version: MavenVersion::Snapshot { version: "1.0.0-SNAPSHOT".to_string(), timestamp: "12345678.123456".to_string(), build_number: Some(5) }, classifier: Some("cla"), extension: ".md5"}))]
+    #[case::release_invalid_other_char_after_artifact("ax1.0.0.jar", "a", "1.0.0", None)]
+    #[case::release_invalid_non_ascii_after_artifact("aä1.0.0.jar", "a", "1.0.0", None)]
     fn test_parse_filename(#[case] file_name: &str, #[case] artifact_id: &str, #[case] version_string: &str, #[case] expected: Option<ParseFilenameResult>) {
         // This is a comprehensive test for parsing and formatting logic. It takes a single set of input data and
         //  hands it to the different formatting and parsing functions, ensuring consistent behavior
@@ -296,4 +494,51 @@ mod test {
 
         assert_eq!(full_path, as_maven_path(&parsed_artifact_ref));
     }
-}
\ No newline at end of file
+
+    #[rstest]
+    #[case::sha1("a/b/1.0/b-1.0-sources.jar.sha1", Some((ChecksumKind::Sha1, "a/b/1.0/b-1.0-sources.jar")))]
+    #[case::md5_snapshot("a/b/1.0-SNAPSHOT/b-1.0-SNAPSHOT-x-y-20231114.221320-7.pom.md5", Some((ChecksumKind::Md5, "a/b/1.0-SNAPSHOT/b-1.0-SNAPSHOT-x-y-20231114.221320-7.pom")))]
+    #[case::sha512("a/b/1.0/b-1.0.jar.sha512", Some((ChecksumKind::Sha512, "a/b/1.0/b-1.0.jar")))]
+    #[case::no_checksum("a/b/1.0/b-1.0.jar", None)]
+    #[case::signature("a/b/1.0/b-1.0.jar.asc", None)]
+    #[case::without_extension("a/b/1.0/b-1.0.sha1", None)]
+    #[case::compound_extension("a/b/1.0/b-1.0-bin.tar.gz.sha256", Some((ChecksumKind::Sha256, "a/b/1.0/b-1.0-bin.tar.gz")))]
+    #[case::checksum_of_checksum("a/b/1.0/b-1.0.jar.sha1.md5", Some((ChecksumKind::Md5, "a/b/1.0/b-1.0.jar.sha1")))]
+    fn test_checksum_target(#[case] path: &str, #[case] expected: Option<(ChecksumKind, &str)>) {
+        let actual = checksum_target(&parse_maven_path(path).unwrap())
+            .map(|(kind, target)| (kind, as_maven_path(&target)));
+        assert_eq!(actual, expected.map(|(kind, target)| (kind, target.to_string())));
+    }
+
+    #[rstest]
+    #[case::metadata("com/example/a/1.0-SNAPSHOT/maven-metadata.xml", Some(("com.example", "a", "1.0-SNAPSHOT", None)))]
+    #[case::metadata_sha1("com/example/a/1.0-SNAPSHOT/maven-metadata.xml.sha1", Some(("com.example", "a", "1.0-SNAPSHOT", Some(ChecksumKind::Sha1))))]
+    #[case::release("com/example/a/1.0/maven-metadata.xml", None)]
+    #[case::artifact_level("com/example/a/maven-metadata.xml", None)]
+    #[case::artifact("com/example/a/1.0-SNAPSHOT/a-1.0-SNAPSHOT-20231114.221320-1.jar", None)]
+    #[case::no_group("a/1.0-SNAPSHOT/maven-metadata.xml", None)]
+    fn test_parse_snapshot_metadata_path(#[case] path: &str, #[case] expected: Option<(&str, &str, &str, Option<ChecksumKind>)>) {
+        let expected = expected.map(|(group_id, artifact_id, version, checksum)| SnapshotMetadataPath {
+            group_id: MavenGroupId(group_id.to_string()),
+            artifact_id: MavenArtifactId(artifact_id.to_string()),
+            version: version.to_string(),
+            checksum,
+        });
+        assert_eq!(parse_snapshot_metadata_path(path), expected);
+    }
+
+    #[rstest]
+    #[case::artifact_level("com/example/a/maven-metadata.xml", Some(("com.example", "a", None)))]
+    #[case::md5("com/example/a/maven-metadata.xml.md5", Some(("com.example", "a", Some(ChecksumKind::Md5))))]
+    #[case::snapshot("com/example/a/1.0-SNAPSHOT/maven-metadata.xml", None)]
+    #[case::no_group("a/maven-metadata.xml", None)]
+    #[case::other_file("com/example/a/index.html", None)]
+    fn test_parse_artifact_metadata_path(#[case] path: &str, #[case] expected: Option<(&str, &str, Option<ChecksumKind>)>) {
+        let expected = expected.map(|(group_id, artifact_id, checksum)| ArtifactMetadataPath {
+            group_id: MavenGroupId(group_id.to_string()),
+            artifact_id: MavenArtifactId(artifact_id.to_string()),
+            checksum,
+        });
+        assert_eq!(parse_artifact_metadata_path(path), expected);
+    }
+}
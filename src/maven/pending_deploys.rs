@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+use hex::FromHex;
+use uuid::Uuid;
+
+use crate::maven::coordinates::MavenArtifactRef;
+use crate::maven::paths::ChecksumKind;
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum ChecksumOutcome {
+    /// the checksum matched, the blob can be registered for the artifact
+    Committed(Uuid),
+    /// the checksum did not match, the blob should be discarded
+    Rejected(Uuid),
+}
+
+struct PendingDeploy {
+    blob_key: Uuid,
+    md5: [u8;16],
+    sha1: [u8;20],
+    uploaded: Instant,
+}
+
+/// `mvn deploy` uploads an artifact's data first and its checksum files ('.sha1', '.md5') in
+///  separate follow-up requests, and some clients send checksums as HTTP trailers after the body.
+///  Uploaded artifacts are therefore held in a pending state until a checksum arrives for them, and
+///  are only committed (i.e. registered and made visible) once it matches the uploaded data.
+pub struct PendingDeploys {
+    timeout: Duration,
+    pending: Mutex<HashMap<MavenArtifactRef, PendingDeploy>>,
+}
+impl PendingDeploys {
+    pub fn new(timeout: Duration) -> PendingDeploys {
+        PendingDeploys {
+            timeout,
+            pending: Default::default(),
+        }
+    }
+
+    /// Registers uploaded data with the checksums calculated while storing it. If an upload was
+    ///  already pending for the artifact, it is replaced and its blob key is returned for cleanup.
+    pub fn add_upload(&self, artifact_ref: &MavenArtifactRef, blob_key: Uuid, md5: [u8;16], sha1: [u8;20]) -> Option<Uuid> {
+        self.pending.lock().unwrap()
+            .insert(artifact_ref.clone(), PendingDeploy {
+                blob_key,
+                md5,
+                sha1,
+                uploaded: Instant::now(),
+            })
+            .map(|prev| prev.blob_key)
+    }
+
+    /// The blob and SHA-1 of the artifact's pending upload, for verifying checksums that are not
+    ///  stored with blobs, see [PendingDeploys::resolve]
+    pub fn pending_upload(&self, artifact_ref: &MavenArtifactRef) -> Option<(Uuid, [u8;20])> {
+        self.pending.lock().unwrap()
+            .get(artifact_ref)
+            .map(|deploy| (deploy.blob_key, deploy.sha1))
+    }
+
+    /// Whether the blob belongs to an upload that is waiting for its checksum
+    pub fn contains_blob(&self, blob_key: &Uuid) -> bool {
+        self.pending.lock().unwrap()
+            .values()
+            .any(|deploy| &deploy.blob_key == blob_key)
+    }
+
+    /// Resolves a pending upload with the result of verifying a checksum against its blob. Fails if
+    ///  the upload was resolved or replaced in the meantime.
+    pub fn resolve(&self, artifact_ref: &MavenArtifactRef, blob_key: Uuid, matches: bool) -> anyhow::Result<ChecksumOutcome> {
+        let mut pending = self.pending.lock().unwrap();
+        match pending.get(artifact_ref) {
+            Some(deploy) if deploy.blob_key == blob_key => {
+                pending.remove(artifact_ref);
+            }
+            _ => return Err(anyhow!("no pending upload for checksum")),
+        }
+
+        if matches {
+            Ok(ChecksumOutcome::Committed(blob_key))
+        }
+        else {
+            Ok(ChecksumOutcome::Rejected(blob_key))
+        }
+    }
+
+    /// Validates a checksum against a pending upload, resolving it either way. The checksum is
+    ///  expected in the format of a Maven checksum file, i.e. hex optionally followed by a file name.
+    ///  Only checksums that are stored with blobs can be validated this way.
+    pub fn add_checksum(&self, artifact_ref: &MavenArtifactRef, kind: ChecksumKind, checksum: &str) -> anyhow::Result<ChecksumOutcome> {
+        let checksum = checksum.split_whitespace()
+            .next()
+            .unwrap_or("");
+
+        if !kind.is_stored() {
+            return Err(anyhow!("{} checksums are not stored with uploads", kind.suffix()));
+        }
+
+        let mut pending = self.pending.lock().unwrap();
+        let deploy = pending.remove(artifact_ref)
+            .ok_or_else(|| anyhow!("no pending upload for checksum"))?;
+
+        let matches = match kind {
+            ChecksumKind::Sha1 => <[u8;20]>::from_hex(checksum).map(|c| c == deploy.sha1).unwrap_or(false),
+            ChecksumKind::Md5 => <[u8;16]>::from_hex(checksum).map(|c| c == deploy.md5).unwrap_or(false),
+            ChecksumKind::Sha256 | ChecksumKind::Sha512 => false,
+        };
+
+        if matches {
+            Ok(ChecksumOutcome::Committed(deploy.blob_key))
+        }
+        else {
+            Ok(ChecksumOutcome::Rejected(deploy.blob_key))
+        }
+    }
+
+    /// Removes uploads that did not receive a checksum within the timeout, returning them so that
+    ///  their blobs can be deleted
+    pub fn remove_timed_out(&self) -> Vec<(MavenArtifactRef, Uuid)> {
+        let now = Instant::now();
+        let mut result = Vec::new();
+        self.pending.lock().unwrap()
+            .retain(|artifact_ref, deploy| {
+                if now.duration_since(deploy.uploaded) < self.timeout {
+                    true
+                }
+                else {
+                    result.push((artifact_ref.clone(), deploy.blob_key));
+                    false
+                }
+            });
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::maven::paths::parse_maven_path;
+    use super::*;
+
+    const SHA1: [u8;20] = [0x0b; 20];
+    const MD5: [u8;16] = [0x0c; 16];
+
+    fn artifact_ref() -> MavenArtifactRef {
+        parse_maven_path("com/example/lib/1.0/lib-1.0.jar").unwrap()
+    }
+
+    #[test]
+    fn test_matching_checksum_commits() {
+        let pending = PendingDeploys::new(Duration::from_secs(60));
+        let blob_key = Uuid::from_u64_pair(0, 1);
+        assert_eq!(pending.add_upload(&artifact_ref(), blob_key, MD5, SHA1), None);
+        assert!(pending.contains_blob(&blob_key));
+        assert!(!pending.contains_blob(&Uuid::from_u64_pair(0, 2)));
+
+        let checksum_file = format!("{}  lib-1.0.jar\n", hex::encode(SHA1));
+        assert_eq!(pending.add_checksum(&artifact_ref(), ChecksumKind::Sha1, &checksum_file).unwrap(), ChecksumOutcome::Committed(blob_key));
+        assert!(!pending.contains_blob(&blob_key));
+
+        // resolved, so a second checksum has nothing to refer to
+        assert!(pending.add_checksum(&artifact_ref(), ChecksumKind::Md5, &hex::encode(MD5)).is_err());
+    }
+
+    #[test]
+    fn test_mismatching_checksum_rejects() {
+        let pending = PendingDeploys::new(Duration::from_secs(60));
+        let blob_key = Uuid::from_u64_pair(0, 1);
+        pending.add_upload(&artifact_ref(), blob_key, MD5, SHA1);
+
+        assert_eq!(pending.add_checksum(&artifact_ref(), ChecksumKind::Md5, "not hex").unwrap(), ChecksumOutcome::Rejected(blob_key));
+    }
+
+    #[test]
+    fn test_resolve_verified_checksum() {
+        let pending = PendingDeploys::new(Duration::from_secs(60));
+        let blob_key = Uuid::from_u64_pair(0, 1);
+        pending.add_upload(&artifact_ref(), blob_key, MD5, SHA1);
+        assert!(pending.add_checksum(&artifact_ref(), ChecksumKind::Sha256, "00").is_err());
+        assert_eq!(pending.pending_upload(&artifact_ref()), Some((blob_key, SHA1)));
+
+        assert!(pending.resolve(&artifact_ref(), Uuid::from_u64_pair(0, 2), true).is_err());
+        assert_eq!(pending.resolve(&artifact_ref(), blob_key, true).unwrap(), ChecksumOutcome::Committed(blob_key));
+        assert_eq!(pending.pending_upload(&artifact_ref()), None);
+    }
+
+    #[test]
+    fn test_replace_and_time_out() {
+        let pending = PendingDeploys::new(Duration::ZERO);
+        let first = Uuid::from_u64_pair(0, 1);
+        let second = Uuid::from_u64_pair(0, 2);
+        pending.add_upload(&artifact_ref(), first, MD5, SHA1);
+        assert_eq!(pending.add_upload(&artifact_ref(), second, MD5, SHA1), Some(first));
+
+        assert_eq!(pending.remove_timed_out(), vec![(artifact_ref(), second)]);
+        assert!(pending.remove_timed_out().is_empty());
+    }
+}
@@ -0,0 +1,158 @@
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use serde::Serialize;
+
+/// Operating systems with the names that the 'os-maven-plugin' normalizes to, which are the
+///  de-facto standard for native classifiers
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Os {
+    Linux,
+    Osx,
+    Windows,
+}
+impl Os {
+    fn from_token(token: &str) -> Option<Os> {
+        match token {
+            "linux" => Some(Os::Linux),
+            "osx" | "macos" | "mac" | "darwin" => Some(Os::Osx),
+            "windows" | "win" | "win32" | "win64" => Some(Os::Windows),
+            _ => None,
+        }
+    }
+}
+impl FromStr for Os {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Os, anyhow::Error> {
+        Os::from_token(&s.to_ascii_lowercase())
+            .ok_or_else(|| anyhow!("unsupported operating system '{}', must be 'linux', 'osx' or 'windows'", s))
+    }
+}
+
+/// CPU architectures, named like [Os]
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Serialize)]
+pub enum Arch {
+    #[serde(rename = "x86_64")]
+    X86_64,
+    #[serde(rename = "x86_32")]
+    X86_32,
+    #[serde(rename = "aarch_64")]
+    Aarch64,
+    #[serde(rename = "arm_32")]
+    Arm32,
+}
+impl Arch {
+    fn from_token(token: &str) -> Option<Arch> {
+        match token {
+            "x86_64" | "amd64" | "x64" => Some(Arch::X86_64),
+            "x86_32" | "x86" | "i386" | "i686" => Some(Arch::X86_32),
+            "aarch_64" | "aarch64" | "arm64" => Some(Arch::Aarch64),
+            "arm_32" | "arm" | "armv7" | "armhf" => Some(Arch::Arm32),
+            _ => None,
+        }
+    }
+}
+impl FromStr for Arch {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Arch, anyhow::Error> {
+        Arch::from_token(&s.to_ascii_lowercase())
+            .ok_or_else(|| anyhow!("unsupported architecture '{}', must be e.g. 'x86_64' or 'aarch_64'", s))
+    }
+}
+
+/// The platform a classifier like 'natives-linux' or 'osx-aarch_64' is built for
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Serialize)]
+pub struct Platform {
+    pub os: Option<Os>,
+    pub arch: Option<Arch>,
+}
+
+/// None for classifiers that do not refer to a platform, e.g. 'sources'. Classifiers are split
+///  into tokens at '-' and '.' ('_' is part of names like 'x86_64').
+pub fn platform_of(classifier: &str) -> Option<Platform> {
+    let classifier = classifier.to_ascii_lowercase();
+    let tokens = classifier.split(['-', '.']).collect::<Vec<_>>();
+    let platform = Platform {
+        os: tokens.iter().find_map(|t| Os::from_token(t)),
+        arch: tokens.iter().find_map(|t| Arch::from_token(t)),
+    };
+    match platform {
+        Platform { os: None, arch: None } => None,
+        platform => Some(platform),
+    }
+}
+
+/// The classifiers matching the requested platform. Classifiers for the exact architecture are
+///  preferred, ones without an architecture (e.g. 'natives-linux') are the fallback. More than one
+///  result means the request is ambiguous.
+pub fn select_classifiers(classifiers: &[String], os: Option<Os>, arch: Option<Arch>) -> Vec<&String> {
+    let candidates = classifiers.iter()
+        .filter_map(|c| platform_of(c).map(|p| (c, p)))
+        .filter(|(_, p)| os.is_none() || p.os == os)
+        .collect::<Vec<_>>();
+
+    if arch.is_some() {
+        let exact = candidates.iter()
+            .filter(|(_, p)| p.arch == arch)
+            .map(|(c, _)| *c)
+            .collect::<Vec<_>>();
+        if !exact.is_empty() {
+            return exact;
+        }
+        return candidates.iter()
+            .filter(|(_, p)| p.arch.is_none())
+            .map(|(c, _)| *c)
+            .collect();
+    }
+    candidates.into_iter().map(|(c, _)| c).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("natives-linux", Some(Os::Linux), None)]
+    #[case("linux-x86_64", Some(Os::Linux), Some(Arch::X86_64))]
+    #[case("osx-aarch_64", Some(Os::Osx), Some(Arch::Aarch64))]
+    #[case("natives-macos-arm64", Some(Os::Osx), Some(Arch::Aarch64))]
+    #[case("Windows-x86", Some(Os::Windows), Some(Arch::X86_32))]
+    #[case("linux-armhf", Some(Os::Linux), Some(Arch::Arm32))]
+    fn test_platform_of(#[case] classifier: &str, #[case] os: Option<Os>, #[case] arch: Option<Arch>) {
+        assert_eq!(platform_of(classifier), Some(Platform { os, arch }));
+    }
+
+    #[rstest]
+    #[case("sources")]
+    #[case("javadoc")]
+    #[case("tests")]
+    fn test_platform_of_non_platform(#[case] classifier: &str) {
+        assert_eq!(platform_of(classifier), None);
+    }
+
+    #[rstest]
+    #[case(Some(Os::Linux), Some(Arch::X86_64), vec!["linux-x86_64"])]
+    #[case(Some(Os::Linux), Some(Arch::Aarch64), vec!["linux-aarch_64"])]
+    #[case(Some(Os::Osx), Some(Arch::Aarch64), vec!["natives-osx"])]
+    #[case(Some(Os::Linux), None, vec!["linux-x86_64", "linux-aarch_64"])]
+    #[case(Some(Os::Windows), Some(Arch::X86_64), vec![])]
+    fn test_select_classifiers(#[case] os: Option<Os>, #[case] arch: Option<Arch>, #[case] expected: Vec<&str>) {
+        let classifiers = ["sources", "linux-x86_64", "linux-aarch_64", "natives-osx"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(select_classifiers(&classifiers, os, arch), expected);
+    }
+
+    #[test]
+    fn test_parse_request() {
+        assert_eq!("macos".parse::<Os>().unwrap(), Os::Osx);
+        assert_eq!("AMD64".parse::<Arch>().unwrap(), Arch::X86_64);
+        assert!("beos".parse::<Os>().is_err());
+    }
+}
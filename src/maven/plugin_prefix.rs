@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use serde::Deserialize;
+
+use crate::maven::coordinates::{MavenArtifactId, MavenGroupId};
+use crate::maven::remote_repo::MavenPluginMetadata;
+use crate::util::change_kind::ChangeKind;
+
+/// What happens when a plugin is registered with a prefix that another plugin in the same group
+///  already has. Maven resolves 'mvn prefix:goal' through the group's prefixes, so they must be
+///  unique.
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginPrefixPolicy {
+    /// the registration fails with a [PluginPrefixConflict]
+    #[default]
+    Reject,
+    /// the other plugin is unregistered
+    Overwrite,
+}
+impl FromStr for PluginPrefixPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<PluginPrefixPolicy> {
+        match s {
+            "reject" => Ok(PluginPrefixPolicy::Reject),
+            "overwrite" => Ok(PluginPrefixPolicy::Overwrite),
+            _ => Err(anyhow!("unknown plugin prefix policy {}", s)),
+        }
+    }
+}
+
+/// A plugin registration was rejected because another plugin in its group has the same prefix
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct PluginPrefixConflict {
+    pub group_id: MavenGroupId,
+    pub prefix: String,
+    /// the plugin that has the prefix
+    pub artifact_id: MavenArtifactId,
+}
+impl PluginPrefixConflict {
+    pub fn of(e: &anyhow::Error) -> Option<&PluginPrefixConflict> {
+        e.chain().find_map(|cause| cause.downcast_ref::<PluginPrefixConflict>())
+    }
+}
+impl Display for PluginPrefixConflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "prefix {} is already registered for plugin {}:{}", self.prefix, self.group_id.0, self.artifact_id.0)
+    }
+}
+impl std::error::Error for PluginPrefixConflict {}
+
+/// Registers a plugin in its group's plugins, enforcing unique prefixes. Callers are responsible
+///  for holding a lock on the group's plugins, so that the check and the registration are atomic.
+pub fn register_with_unique_prefix(group_id: &MavenGroupId, plugins: &mut HashMap<MavenArtifactId, MavenPluginMetadata>, plugin_metadata: MavenPluginMetadata, policy: PluginPrefixPolicy) -> anyhow::Result<ChangeKind> {
+    let conflicting = plugins.values()
+        .find(|p| p.prefix == plugin_metadata.prefix && p.artifact_id != plugin_metadata.artifact_id)
+        .map(|p| p.artifact_id.clone());
+    if let Some(conflicting) = conflicting {
+        match policy {
+            PluginPrefixPolicy::Reject => return Err(PluginPrefixConflict {
+                group_id: group_id.clone(),
+                prefix: plugin_metadata.prefix,
+                artifact_id: conflicting,
+            }.into()),
+            PluginPrefixPolicy::Overwrite => {
+                plugins.remove(&conflicting);
+            }
+        }
+    }
+
+    match plugins.insert(plugin_metadata.artifact_id.clone(), plugin_metadata.clone()) {
+        None => Ok(ChangeKind::Inserted),
+        Some(prev) if prev == plugin_metadata => Ok(ChangeKind::Unchanged),
+        Some(_) => Ok(ChangeKind::Updated),
+    }
+}
+
+/// Adds upstream plugins to locally registered ones, e.g. for a proxied group level
+///  'maven-metadata.xml'. Local registrations take precedence: upstream plugins with the same
+///  artifact id or prefix as a local one are dropped.
+pub fn merge_plugins(local: Vec<MavenPluginMetadata>, upstream: &[MavenPluginMetadata]) -> Vec<MavenPluginMetadata> {
+    let mut result = local;
+    for plugin in upstream {
+        if !result.iter().any(|p| p.artifact_id == plugin.artifact_id || p.prefix == plugin.prefix) {
+            result.push(plugin.clone());
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn plugin(artifact_id: &str, prefix: &str) -> MavenPluginMetadata {
+        MavenPluginMetadata {
+            name: artifact_id.to_string(),
+            prefix: prefix.to_string(),
+            artifact_id: MavenArtifactId(artifact_id.to_string()),
+        }
+    }
+
+    #[rstest]
+    #[case::reject(PluginPrefixPolicy::Reject, &["a-plugin"])]
+    #[case::overwrite(PluginPrefixPolicy::Overwrite, &["b-plugin"])]
+    fn test_prefix_conflict(#[case] policy: PluginPrefixPolicy, #[case] expected: &[&str]) {
+        let group_id = MavenGroupId("org.example".to_string());
+        let mut plugins = HashMap::new();
+        register_with_unique_prefix(&group_id, &mut plugins, plugin("a-plugin", "x"), policy).unwrap();
+        // changing a plugin's own prefix is no conflict
+        assert_eq!(register_with_unique_prefix(&group_id, &mut plugins, plugin("a-plugin", "a"), policy).unwrap(), ChangeKind::Updated);
+
+        let result = register_with_unique_prefix(&group_id, &mut plugins, plugin("b-plugin", "a"), policy);
+        match policy {
+            PluginPrefixPolicy::Reject => assert_eq!(PluginPrefixConflict::of(&result.unwrap_err()).unwrap().artifact_id.0, "a-plugin"),
+            PluginPrefixPolicy::Overwrite => assert_eq!(result.unwrap(), ChangeKind::Inserted),
+        }
+
+        let mut registered = plugins.keys().map(|a| a.0.as_str()).collect::<Vec<_>>();
+        registered.sort();
+        assert_eq!(registered, expected);
+    }
+
+    #[test]
+    fn test_merge_plugins() {
+        let merged = merge_plugins(
+            vec![plugin("a-plugin", "a")],
+            &[plugin("a-plugin", "x"), plugin("b-plugin", "a"), plugin("c-plugin", "c")],
+        );
+        assert_eq!(merged, vec![plugin("a-plugin", "a"), plugin("c-plugin", "c")]);
+    }
+}
@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use crate::maven::paths::{checksum_target, parse_maven_path};
+
+/// Access log formats that prefetch plans can be built from
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum AccessLogFormat {
+    /// the 'combined' (or 'common') log format of a reverse proxy in front of arti-vault, with
+    ///  paths below '/repo/'
+    Combined,
+    /// Nexus' 'request.log', with paths below '/repository/<name>/' (or
+    ///  '/nexus/content/repositories/<name>/' for Nexus 2)
+    Nexus,
+    /// Artifactory's pipe separated 'request.log', with paths below '[/artifactory]/<repo key>/'
+    Artifactory,
+}
+impl FromStr for AccessLogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<AccessLogFormat, anyhow::Error> {
+        match s {
+            "combined" => Ok(AccessLogFormat::Combined),
+            "nexus" => Ok(AccessLogFormat::Nexus),
+            "artifactory" => Ok(AccessLogFormat::Artifactory),
+            other => Err(anyhow!("unknown access log format '{}', must be 'combined', 'nexus' or 'artifactory'", other)),
+        }
+    }
+}
+
+/// A request as far as it matters for prefetching
+#[derive(Eq, PartialEq, Debug)]
+struct LoggedRequest<'a> {
+    method: &'a str,
+    path: &'a str,
+    status: u16,
+}
+
+/// '1.2.3.4 - user [10/Oct/2023:13:55:36 +0000] "GET /path HTTP/1.1" 200 2326 ...'
+fn parse_combined_line(line: &str) -> Option<LoggedRequest<'_>> {
+    let (_, rest) = line.split_once('"')?;
+    let (request_line, rest) = rest.split_once('"')?;
+    let mut request_line = request_line.split_whitespace();
+    let method = request_line.next()?;
+    let path = request_line.next()?;
+    let status = rest.split_whitespace().next()?.parse().ok()?;
+    Some(LoggedRequest { method, path, status })
+}
+
+/// Both the legacy format ('20140508154145|0|REQUEST|1.2.3.4|user|GET|/path|HTTP/1.1|200|2326')
+///  and the format of Artifactory 7 ('2023-10-10T13:55:36.000Z|trace id|1.2.3.4|user|GET|/path|200|-1|2326|...')
+///  have the path after the method, followed by the status as the first three digit number
+fn parse_artifactory_line(line: &str) -> Option<LoggedRequest<'_>> {
+    let mut fields = line.split('|').map(str::trim);
+    let method = fields.find(|f| matches!(*f, "GET" | "HEAD" | "PUT" | "POST" | "DELETE"))?;
+    let path = fields.next()?;
+    let status = fields
+        .find(|f| f.len() == 3 && f.bytes().all(|b| b.is_ascii_digit()))?
+        .parse().ok()?;
+    Some(LoggedRequest { method, path, status })
+}
+
+/// The path below arti-vault's '/repo/' route, None for requests outside a repository
+fn repository_path(format: AccessLogFormat, path: &str) -> Option<&str> {
+    let path = path.split(['?', '#']).next()?;
+    match format {
+        AccessLogFormat::Combined => path.strip_prefix("/repo/"),
+        // artifacts from other repository managers go to the default repository
+        AccessLogFormat::Nexus => {
+            let below_repository = path.strip_prefix("/repository/")
+                .or_else(|| path.strip_prefix("/nexus/content/repositories/"))
+                .or_else(|| path.strip_prefix("/nexus/content/groups/"))?;
+            below_repository.split_once('/').map(|(_, p)| p)
+        }
+        AccessLogFormat::Artifactory => {
+            let path = path.strip_prefix("/artifactory").unwrap_or(path);
+            let (repo_key, path) = path.strip_prefix('/')?.split_once('/')?;
+            match repo_key {
+                "api" | "ui" => None,
+                _ => Some(path),
+            }
+        }
+    }
+}
+
+/// Artifacts to fetch from upstream ahead of time, most requested first
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct PrefetchPlan {
+    pub entries: Vec<PrefetchEntry>,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct PrefetchEntry {
+    /// relative to '/repo/', i.e. optionally starting with a repository name
+    pub path: String,
+    /// number of successful requests in the analyzed logs
+    pub requests: u64,
+}
+
+/// Counts successful artifact downloads in access logs, which are added line by line so that
+///  arbitrarily large logs can be analyzed. Checksums and metadata are not part of the plan:
+///  checksums are stored with their artifacts, and metadata changes too often to be worth
+///  prefetching.
+pub struct PrefetchPlanBuilder {
+    format: AccessLogFormat,
+    requests: HashMap<String, u64>,
+    pub num_lines: u64,
+    /// lines that are not requests in the configured format, e.g. because the format is wrong
+    pub num_unparseable_lines: u64,
+}
+impl PrefetchPlanBuilder {
+    pub fn new(format: AccessLogFormat) -> PrefetchPlanBuilder {
+        PrefetchPlanBuilder {
+            format,
+            requests: Default::default(),
+            num_lines: 0,
+            num_unparseable_lines: 0,
+        }
+    }
+
+    pub fn add_line(&mut self, line: &str) {
+        if line.trim().is_empty() {
+            return;
+        }
+        self.num_lines += 1;
+
+        let request = match self.format {
+            AccessLogFormat::Combined | AccessLogFormat::Nexus => parse_combined_line(line),
+            AccessLogFormat::Artifactory => parse_artifactory_line(line),
+        };
+        let request = match request {
+            Some(request) => request,
+            None => {
+                self.num_unparseable_lines += 1;
+                return;
+            }
+        };
+
+        // '304 Not Modified' is a client using its cached copy of the artifact
+        if request.method != "GET" || !(request.status == 200 || request.status == 304) {
+            return;
+        }
+        let path = match repository_path(self.format, request.path) {
+            Some(path) => path,
+            None => return,
+        };
+        match parse_maven_path(path) {
+            Ok(artifact_ref) if checksum_target(&artifact_ref).is_none() => {
+                *self.requests.entry(path.to_string()).or_default() += 1;
+            }
+            _ => {}
+        }
+    }
+
+    /// The `limit` most requested artifacts
+    pub fn build(self, limit: usize) -> PrefetchPlan {
+        let mut entries = self.requests.into_iter()
+            .map(|(path, requests)| PrefetchEntry { path, requests })
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| b.requests.cmp(&a.requests).then_with(|| a.path.cmp(&b.path)));
+        entries.truncate(limit);
+        PrefetchPlan { entries }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::combined(AccessLogFormat::Combined, r#"10.0.0.1 - - [10/Oct/2023:13:55:36 +0000] "GET /repo/org/a/a/1.0/a-1.0.jar HTTP/1.1" 200 2326 "-" "Apache-Maven/3.9.4""#)]
+    #[case::combined_named(AccessLogFormat::Combined, r#"10.0.0.1 - - [10/Oct/2023:13:55:36 +0000] "GET /repo/central/org/a/a/1.0/a-1.0.jar HTTP/1.1" 304 0"#)]
+    #[case::nexus(AccessLogFormat::Nexus, r#"10.0.0.1 - - [10/Oct/2023:13:55:36 +0000] "GET /repository/maven-central/org/a/a/1.0/a-1.0.jar HTTP/1.1" 200 - 2326 12 "Apache-Maven/3.9.4" [qtp-42]"#)]
+    #[case::nexus2(AccessLogFormat::Nexus, r#"10.0.0.1 - - [10/Oct/2023:13:55:36 +0000] "GET /nexus/content/groups/public/org/a/a/1.0/a-1.0.jar HTTP/1.1" 200 2326"#)]
+    #[case::artifactory_legacy(AccessLogFormat::Artifactory, "20231010135536|12|REQUEST|10.0.0.1|anonymous|GET|/libs-release/org/a/a/1.0/a-1.0.jar|HTTP/1.1|200|2326")]
+    #[case::artifactory7(AccessLogFormat::Artifactory, "2023-10-10T13:55:36.000Z|3f2a|10.0.0.1|anonymous|GET|/artifactory/libs-release/org/a/a/1.0/a-1.0.jar|200|-1|2326|12|Apache-Maven/3.9.4")]
+    fn test_counted(#[case] format: AccessLogFormat, #[case] line: &str) {
+        let mut builder = PrefetchPlanBuilder::new(format);
+        builder.add_line(line);
+        let plan = builder.build(10);
+        assert_eq!(plan.entries.len(), 1);
+        assert!(plan.entries[0].path.ends_with("org/a/a/1.0/a-1.0.jar"));
+    }
+
+    #[rstest]
+    #[case::not_found(r#"x - - [t] "GET /repo/org/a/a/1.0/a-1.0.jar HTTP/1.1" 404 0"#)]
+    #[case::head(r#"x - - [t] "HEAD /repo/org/a/a/1.0/a-1.0.jar HTTP/1.1" 200 0"#)]
+    #[case::checksum(r#"x - - [t] "GET /repo/org/a/a/1.0/a-1.0.jar.sha1 HTTP/1.1" 200 40"#)]
+    #[case::metadata(r#"x - - [t] "GET /repo/org/a/a/maven-metadata.xml HTTP/1.1" 200 400"#)]
+    #[case::outside_repo(r#"x - - [t] "GET /api/v1/resolve?g=org.a HTTP/1.1" 200 400"#)]
+    fn test_not_counted(#[case] line: &str) {
+        let mut builder = PrefetchPlanBuilder::new(AccessLogFormat::Combined);
+        builder.add_line(line);
+        assert_eq!(builder.num_unparseable_lines, 0);
+        assert!(builder.build(10).entries.is_empty());
+    }
+
+    #[test]
+    fn test_most_requested_first() {
+        let mut builder = PrefetchPlanBuilder::new(AccessLogFormat::Combined);
+        for path in ["org/a/a/1.0/a-1.0.jar", "org/b/b/1.0/b-1.0.jar", "org/b/b/1.0/b-1.0.jar", "org/c/c/1.0/c-1.0.jar"] {
+            builder.add_line(&format!(r#"x - - [t] "GET /repo/{} HTTP/1.1" 200 1"#, path));
+        }
+        builder.add_line("garbage");
+
+        assert_eq!(builder.num_lines, 5);
+        assert_eq!(builder.num_unparseable_lines, 1);
+        assert_eq!(builder.build(2).entries, vec![
+            PrefetchEntry { path: "org/b/b/1.0/b-1.0.jar".to_string(), requests: 2 },
+            PrefetchEntry { path: "org/a/a/1.0/a-1.0.jar".to_string(), requests: 1 },
+        ]);
+    }
+}
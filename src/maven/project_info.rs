@@ -0,0 +1,252 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::maven::jar_index::read_entries;
+
+/// license and notice files are a few kilobytes, larger ones are not included
+const MAX_NOTICE_FILE_SIZE: u64 = 256*1024;
+
+/// POM sections with elements that would be mistaken for the project's own, e.g. the parent's
+///  '<url>' or a dependency's '<name>'
+const NESTED_SECTIONS: &[&str] = &[
+    "parent", "organization", "licenses", "developers", "contributors", "mailingLists", "prerequisites",
+    "modules", "scm", "issueManagement", "ciManagement", "distributionManagement", "properties",
+    "dependencyManagement", "dependencies", "repositories", "pluginRepositories", "build", "reporting",
+    "profiles",
+];
+
+lazy_static! {
+    static ref COMMENT_REGEX: Regex = Regex::new(r"(?s)<!--.*?-->").unwrap();
+    static ref CDATA_REGEX: Regex = Regex::new(r"(?s)<!\[CDATA\[(.*?)\]\]>").unwrap();
+    static ref NESTED_SECTION_REGEXES: Vec<Regex> = NESTED_SECTIONS.iter()
+        .map(|section| Regex::new(&format!(r"(?s)<{0}\b[^>]*/>|<{0}\b[^>]*>.*?</{0}>", section)).unwrap())
+        .collect();
+    static ref LICENSES_REGEX: Regex = Regex::new(r"(?s)<licenses>(.*?)</licenses>").unwrap();
+    static ref LICENSE_REGEX: Regex = Regex::new(r"(?s)<license>(.*?)</license>").unwrap();
+    static ref DEVELOPERS_REGEX: Regex = Regex::new(r"(?s)<developers>(.*?)</developers>").unwrap();
+    static ref DEVELOPER_REGEX: Regex = Regex::new(r"(?s)<developer>(.*?)</developer>").unwrap();
+    static ref SCM_REGEX: Regex = Regex::new(r"(?s)<scm>(.*?)</scm>").unwrap();
+    static ref WHITESPACE_REGEX: Regex = Regex::new(r"\s+").unwrap();
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct PomLicense {
+    pub name: Option<String>,
+    pub url: Option<String>,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct PomDeveloper {
+    pub id: Option<String>,
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub organization: Option<String>,
+}
+
+/// What developer portals show about a project, as declared in its POM - values inherited from a
+///  parent POM are not resolved, and neither are property references like '${project.version}'
+#[derive(Clone, Eq, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct PomInfo {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub url: Option<String>,
+    pub licenses: Vec<PomLicense>,
+    pub scm_url: Option<String>,
+    pub developers: Vec<PomDeveloper>,
+}
+
+/// A license or notice file in a jar, e.g. 'META-INF/LICENSE.txt'
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct NoticeFile {
+    pub name: String,
+    pub content: String,
+}
+
+/// What the project info API returns for an artifact, cached in the repository's metadata store
+#[derive(Clone, Eq, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct ProjectInfo {
+    #[serde(flatten)]
+    pub pom: PomInfo,
+    /// only for jars
+    pub notice_files: Vec<NoticeFile>,
+}
+
+/// Extracts the [PomInfo] from a POM. This does not validate the POM, missing elements are None.
+pub fn parse_pom(xml: &str) -> PomInfo {
+    let xml = COMMENT_REGEX.replace_all(xml, "");
+    let mut top_level = xml.to_string();
+    for regex in NESTED_SECTION_REGEXES.iter() {
+        top_level = regex.replace_all(&top_level, "").to_string();
+    }
+
+    let licenses = LICENSES_REGEX.captures(&xml)
+        .map(|c| LICENSE_REGEX.captures_iter(&c[1])
+            .map(|license| PomLicense {
+                name: element(&license[1], "name"),
+                url: element(&license[1], "url"),
+            })
+            .collect())
+        .unwrap_or_default();
+    let developers = DEVELOPERS_REGEX.captures(&xml)
+        .map(|c| DEVELOPER_REGEX.captures_iter(&c[1])
+            .map(|developer| PomDeveloper {
+                id: element(&developer[1], "id"),
+                name: element(&developer[1], "name"),
+                email: element(&developer[1], "email"),
+                organization: element(&developer[1], "organization"),
+            })
+            .collect())
+        .unwrap_or_default();
+
+    PomInfo {
+        name: element(&top_level, "name"),
+        description: element(&top_level, "description"),
+        url: element(&top_level, "url"),
+        licenses,
+        scm_url: SCM_REGEX.captures(&xml).and_then(|c| element(&c[1], "url")),
+        developers,
+    }
+}
+
+/// The first element with the given name, unescaped and with whitespace collapsed. None if
+///  there is no such element or it is empty.
+fn element(xml: &str, name: &str) -> Option<String> {
+    let start_tag = format!("<{}>", name);
+    let start = xml.find(&start_tag)? + start_tag.len();
+    let end = start + xml[start..].find(&format!("</{}>", name))?;
+
+    let text = CDATA_REGEX.replace_all(&xml[start..end], "$1");
+    let text = WHITESPACE_REGEX.replace_all(text.trim(), " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&");
+    Some(text).filter(|text| !text.is_empty())
+}
+
+/// e.g. 'LICENSE', 'META-INF/NOTICE.txt' or 'META-INF/LICENSE-APACHE'
+fn is_notice_file(entry_name: &str) -> bool {
+    let (directory, file_name) = entry_name.rsplit_once('/').unwrap_or(("", entry_name));
+    let file_name = file_name.to_ascii_uppercase();
+    (directory.is_empty() || directory == "META-INF")
+        && ["LICENSE", "LICENCE", "NOTICE", "COPYING"].iter().any(|prefix| file_name.starts_with(prefix))
+}
+
+/// The license and notice files at the top level of a jar or in its 'META-INF' directory
+pub fn notice_files(jar: &[u8]) -> anyhow::Result<Vec<NoticeFile>> {
+    Ok(read_entries(jar, is_notice_file, MAX_NOTICE_FILE_SIZE)?
+        .into_iter()
+        .map(|(name, content)| NoticeFile {
+            name,
+            content: String::from_utf8_lossy(&content).to_string(),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use crate::maven::jar_index::test_jar::jar;
+
+    use super::*;
+
+    const POM: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<project xmlns="http://maven.apache.org/POM/4.0.0">
+  <modelVersion>4.0.0</modelVersion>
+  <parent>
+    <groupId>com.acme</groupId>
+    <artifactId>acme-parent</artifactId>
+    <version>3</version>
+    <name>Acme Parent</name>
+  </parent>
+  <artifactId>acme-lib</artifactId>
+  <!-- <name>Old Name</name> -->
+  <name>Acme Lib</name>
+  <description>
+    Utilities for
+    everything &amp; more
+  </description>
+  <url>https://acme.example.com/lib</url>
+  <licenses>
+    <license>
+      <name>Apache-2.0</name>
+      <url>https://www.apache.org/licenses/LICENSE-2.0.txt</url>
+    </license>
+    <license><name>MIT</name></license>
+  </licenses>
+  <developers>
+    <developer>
+      <id>jdoe</id>
+      <name>Jane Doe</name>
+      <email>jdoe@acme.example.com</email>
+    </developer>
+  </developers>
+  <scm>
+    <connection>scm:git:https://github.com/acme/lib.git</connection>
+    <url>https://github.com/acme/lib</url>
+  </scm>
+  <dependencies>
+    <dependency>
+      <name>not the project</name>
+    </dependency>
+  </dependencies>
+</project>"#;
+
+    #[test]
+    fn test_parse_pom() {
+        assert_eq!(parse_pom(POM), PomInfo {
+            name: Some("Acme Lib".to_string()),
+            description: Some("Utilities for everything & more".to_string()),
+            url: Some("https://acme.example.com/lib".to_string()),
+            licenses: vec![
+                PomLicense { name: Some("Apache-2.0".to_string()), url: Some("https://www.apache.org/licenses/LICENSE-2.0.txt".to_string()) },
+                PomLicense { name: Some("MIT".to_string()), url: None },
+            ],
+            scm_url: Some("https://github.com/acme/lib".to_string()),
+            developers: vec![PomDeveloper {
+                id: Some("jdoe".to_string()),
+                name: Some("Jane Doe".to_string()),
+                email: Some("jdoe@acme.example.com".to_string()),
+                organization: None,
+            }],
+        });
+    }
+
+    #[test]
+    fn test_parse_minimal_pom() {
+        let pom = "<project><parent><name>Parent</name></parent><artifactId>a</artifactId><description><![CDATA[a <b>bold</b> lib]]></description></project>";
+        assert_eq!(parse_pom(pom), PomInfo {
+            description: Some("a <b>bold</b> lib".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(parse_pom("not a pom"), PomInfo::default());
+    }
+
+    #[rstest]
+    #[case("LICENSE", true)]
+    #[case("META-INF/LICENSE.txt", true)]
+    #[case("META-INF/notice.md", true)]
+    #[case("META-INF/LICENSE-APACHE", true)]
+    #[case("META-INF/maven/com.acme/lib/LICENSE", false)]
+    #[case("com/acme/License.class", false)]
+    #[case("META-INF/MANIFEST.MF", false)]
+    fn test_is_notice_file(#[case] entry_name: &str, #[case] expected: bool) {
+        assert_eq!(is_notice_file(entry_name), expected);
+    }
+
+    #[test]
+    fn test_notice_files() {
+        let data = jar(&[
+            ("META-INF/MANIFEST.MF", b"Manifest-Version: 1.0\n"),
+            ("META-INF/LICENSE", b"Apache License"),
+            ("META-INF/NOTICE", b"Acme Lib\nCopyright Acme"),
+        ]);
+        assert_eq!(notice_files(&data).unwrap(), vec![
+            NoticeFile { name: "META-INF/LICENSE".to_string(), content: "Apache License".to_string() },
+            NoticeFile { name: "META-INF/NOTICE".to_string(), content: "Acme Lib\nCopyright Acme".to_string() },
+        ]);
+    }
+}
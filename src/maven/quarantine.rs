@@ -0,0 +1,105 @@
+use std::fmt::{Display, Formatter};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use futures::StreamExt;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::blob::blob_storage::BlobStream;
+use crate::maven::coordinates::MavenArtifactRef;
+use crate::util::repo_error::RepoError;
+
+/// What a remote repository has for an artifact
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactStatus {
+    Cached,
+    /// only downloads that failed validation (checksum or signature), see [QuarantinedArtifact]
+    Quarantined,
+}
+
+/// A download that failed validation. Its blob is kept for inspection until it is purged, but it
+///  is never served, and the next request for the artifact downloads it again.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct QuarantinedArtifact {
+    pub artifact_ref: MavenArtifactRef,
+    pub blob_key: Uuid,
+    /// why validation failed
+    pub reason: String,
+    pub quarantined_at: SystemTime,
+}
+
+/// The result of [RemoteRepoMetadataStore::quarantine_artifact](crate::maven::remote_repo::RemoteRepoMetadataStore::quarantine_artifact)
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum QuarantineOutcome {
+    /// the store does not keep quarantined artifacts, the blob should be deleted
+    NotKept,
+    /// Only the latest download of an artifact is kept, this is the blob of the one it replaced
+    ///  (if any), which should be deleted
+    Kept(Option<Uuid>),
+}
+
+/// Context for the [RepoError] of a download that was quarantined rather than discarded
+#[derive(Clone, Debug)]
+pub struct QuarantinedDownload {
+    pub path: String,
+    pub blob_key: Uuid,
+}
+impl Display for QuarantinedDownload {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} failed validation and was quarantined as blob {}", self.path, self.blob_key)
+    }
+}
+
+pub fn is_quarantined(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<QuarantinedDownload>().is_some()
+}
+
+/// Passes data through, but ends it normally instead of failing if it does not match its checksum,
+///  recording the mismatch in 'mismatch'. That way, the complete data is stored and can be
+///  quarantined.
+pub fn retain_checksum_mismatch(data: BlobStream<'_>, mismatch: Arc<Mutex<Option<anyhow::Error>>>) -> BlobStream<'_> {
+    Box::pin(data.scan(mismatch, |mismatch, chunk| {
+        let chunk = match chunk {
+            Err(e) if matches!(RepoError::of(&e), Some(RepoError::ChecksumMismatch(_))) => {
+                *mismatch.lock().unwrap() = Some(e);
+                None
+            }
+            chunk => Some(chunk),
+        };
+        futures::future::ready(chunk)
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use bytes::Bytes;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_retain_checksum_mismatch() {
+        let mismatch = Arc::new(Mutex::new(None));
+        let data = futures::stream::iter(vec![
+            Ok(Bytes::from_static(b"ja")),
+            Ok(Bytes::from_static(b"r")),
+            Err(RepoError::ChecksumMismatch("failed validation".to_string()).into()),
+        ]);
+        let chunks = retain_checksum_mismatch(Box::pin(data), mismatch.clone())
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks.iter().all(|chunk| chunk.is_ok()));
+        assert!(mismatch.lock().unwrap().is_some());
+
+        // other errors fail the data as before
+        let mismatch = Arc::new(Mutex::new(None));
+        let data = futures::stream::iter(vec![Ok(Bytes::from_static(b"ja")), Err(anyhow::anyhow!("connection reset"))]);
+        let chunks = retain_checksum_mismatch(Box::pin(data), mismatch.clone())
+            .collect::<Vec<_>>()
+            .await;
+        assert!(chunks[1].is_err());
+        assert!(mismatch.lock().unwrap().is_none());
+    }
+}
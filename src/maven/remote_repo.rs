@@ -1,110 +1,868 @@
-use std::collections::hash_map::Entry;
 use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
-use std::time::{Duration, Instant};
+use std::sync::{Arc, Mutex, RwLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
 
 use anyhow::anyhow;
 use async_trait::async_trait;
+use bytes::Bytes;
+use futures::TryStreamExt;
 use hyper::Uri;
+use hyper::header::HeaderName;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, Instrument, warn};
 use uuid::Uuid;
 
-use crate::blob::blob_storage::BlobStorage;
-use crate::maven::coordinates::{MavenArtifactId, MavenArtifactRef, MavenGroupId, MavenVersion};
-use crate::maven::paths::as_maven_path;
-use crate::util::blob::Blob;
+use crate::blob::blob_storage::{BlobOrigin, BlobStorage};
+use crate::maven::access_times::{AccessTime, AccessTimes};
+use crate::maven::artifact_key::ArtifactKey;
+use crate::maven::checksums::RepositoryChecksums;
+use crate::maven::coordinates::{MavenArtifactId, MavenArtifactRef, MavenCoordinates, MavenGroupId, MavenVersion};
+use crate::maven::directory_listing::{DirectoryListing, parse_html_index};
+use crate::maven::maven_repo_metadata::ArtifactIndex;
+use crate::maven::metadata_xml::{parse_artifact_metadata, parse_plugins, parse_snapshot_versions, render_artifact_metadata, render_snapshot_metadata, SnapshotVersion};
+use crate::maven::negative_cache::{DownloadFailureKind, NegativeCache, NegativeCachePolicy};
+use crate::maven::paths::{ArtifactMetadataPath, as_maven_path, ChecksumKind, parse_maven_path};
+use crate::maven::plugin_prefix::{merge_plugins, PluginPrefixPolicy, register_with_unique_prefix};
+use crate::maven::project_info::ProjectInfo;
+use crate::maven::quarantine::{ArtifactStatus, is_quarantined, QuarantinedArtifact, QuarantinedDownload, QuarantineOutcome, retain_checksum_mismatch};
+use crate::maven::snapshot_retention::{purge_snapshot_builds, SnapshotRetentionPolicy};
+use crate::maven::timestamps::format_maven_timestamp;
+use crate::maven::version_list::VersionListOptions;
+use crate::util::access_log::record_cache_outcome;
+use crate::util::blob::{Blob, BlobStat};
+use crate::util::byte_range::{BlobRange, ByteRange, UncachedRangePolicy};
 use crate::util::change_kind::ChangeKind;
+use crate::util::checksum_file::{ChecksumFilePolicy, is_checksum_mismatch, MAX_CHECKSUM_FILE_SIZE, verify_sha1_file};
+use crate::util::checksum_headers::ChecksumHeader;
+use crate::util::circuit_breaker::CircuitBreaker;
+use crate::util::content_hooks::ContentHooks;
+use crate::util::keyed_mutex::KeyedMutex;
+use crate::util::pgp::{parse_signature, PgpPublicKey, PgpSignature};
+use crate::util::prometheus_metrics::{CACHE_REQUESTS, DOWNLOADS_IN_FLIGHT, InFlightGuard};
+use crate::util::repo_error::RepoError;
+use crate::util::retry_later::{RetryLaterError, RetryLaterReason};
+use crate::util::signature_verification::{Keyring, MAX_SIGNATURE_SIZE, SignaturePolicy};
+use crate::util::slow_transfer::{is_slow_transfer, SlowTransferPolicy};
+use crate::util::upstream_client::UpstreamTlsConfig;
 use crate::util::validating_http_downloader::ValidatingHttpDownloader;
 
-pub struct RemoteMavenRepo<S: BlobStorage<Uuid>, M: RemoteRepoMetadataStore> {
+/// upstream directory listings are cached briefly since they change with every published artifact
+const DIRECTORY_LISTING_TTL: Duration = Duration::from_secs(60);
+/// the same goes for artifact level 'maven-metadata.xml' files
+const ARTIFACT_METADATA_TTL: Duration = Duration::from_secs(60);
+
+const MAX_DIRECTORY_LISTING_SIZE: usize = 4*1024*1024;
+const MAX_METADATA_SIZE: usize = 4*1024*1024;
+
+/// upstream metadata with the time it was fetched
+type CachedArtifactMetadata = (Instant, UpstreamMetadata);
+
+/// signatures and checksum files are not signed themselves
+fn is_signed_path(path: &str) -> bool {
+    match path.rsplit_once('.') {
+        Some((_, "asc")) => false,
+        Some((_, extension)) => ChecksumKind::for_file_extension(&format!(".{}", extension)).is_none(),
+        None => true,
+    }
+}
+
+fn failed_download_error(artifact_ref: &MavenArtifactRef, retry_at: SystemTime, kind: DownloadFailureKind) -> anyhow::Error {
+    RetryLaterError {
+        reason: match kind {
+            DownloadFailureKind::NotFound => RetryLaterReason::DownloadFailedRecently,
+            DownloadFailureKind::TransportError => RetryLaterReason::UpstreamUnavailable,
+        },
+        path: as_maven_path(artifact_ref),
+        retry_at,
+    }.into()
+}
+
+/// What [RemoteMavenRepo::evict_artifact] or [RemoteMavenRepo::evict_group] removed
+#[derive(Debug, Default)]
+pub struct Eviction {
+    /// cached artifacts with the blobs they referred to
+    pub artifacts: Vec<(MavenArtifactRef, Uuid)>,
+    /// the number of failed downloads that were forgotten
+    pub failed_downloads: usize,
+}
+impl Eviction {
+    pub fn is_empty(&self) -> bool {
+        self.artifacts.is_empty() && self.failed_downloads == 0
+    }
+}
+
+pub struct RemoteMavenRepo {
+    /// for labelling metrics, the upstream host unless configured
+    name: String,
     downloader: ValidatingHttpDownloader,
-    blob_storage: Arc<S>,
-    metadata_store: Arc<M>, //TODO dyn without M when this is not created as a local variable in the handler method
+    blob_storage: Arc<dyn BlobStorage<Uuid>>,
+    metadata_store: Arc<dyn RemoteRepoMetadataStore>,
+    directory_listing_passthrough: bool,
+    directory_listing_cache: Mutex<HashMap<String, (Instant, DirectoryListing)>>,
+    upstream_metadata_cache: Mutex<HashMap<(MavenGroupId, MavenArtifactId), CachedArtifactMetadata>>,
+    replay_upstream_headers: bool,
+    content_hooks: ContentHooks,
+    /// never contact upstream, serving only what is available locally
+    offline: bool,
+    slow_transfers: AtomicU64,
+    /// asked for metadata if upstream's does not match its checksum file
+    mirror_downloader: Option<ValidatingHttpDownloader>,
+    /// the most recent upstream metadata files that matched their checksums, by path
+    last_good_metadata: Mutex<HashMap<String, Bytes>>,
+    metadata_checksum_mismatches: AtomicU64,
+    /// artifact downloads are refused without contacting upstream while this is open
+    upstream_breaker: CircuitBreaker,
+    /// concurrent requests for an artifact that is not available locally wait for a single
+    ///  download, and are then served from the stored blob
+    artifact_downloads: KeyedMutex<ArtifactKey>,
+    uncached_range_policy: UncachedRangePolicy,
+    snapshot_retention: SnapshotRetentionPolicy,
+    checksums: RepositoryChecksums,
+    access_times: AccessTimes,
+    signature_policy: SignaturePolicy,
+    keyring: Arc<Keyring>,
 }
 
-impl <S: BlobStorage<Uuid>, M: RemoteRepoMetadataStore> RemoteMavenRepo<S, M> {
-    pub fn new(base_uri: String, blob_storage: Arc<S>, metadata_store: M) -> anyhow::Result<RemoteMavenRepo<S, M>> {
+impl RemoteMavenRepo {
+    pub fn new(base_uri: String, blob_storage: Arc<dyn BlobStorage<Uuid>>, metadata_store: Arc<dyn RemoteRepoMetadataStore>) -> anyhow::Result<RemoteMavenRepo> {
         let mut base_uri = base_uri;
         if !base_uri.ends_with('/') {
             base_uri.push('/');
         }
 
         // check that the base URI is valid
-        Uri::try_from(base_uri.clone())?;
+        let uri = Uri::try_from(base_uri.clone())?;
 
         Ok(RemoteMavenRepo {
+            name: uri.host().unwrap_or_default().to_string(),
             downloader: ValidatingHttpDownloader::new(base_uri)?,
             blob_storage,
-            metadata_store: Arc::new(metadata_store),
+            metadata_store,
+            directory_listing_passthrough: false,
+            directory_listing_cache: Default::default(),
+            upstream_metadata_cache: Default::default(),
+            replay_upstream_headers: false,
+            content_hooks: ContentHooks::new(),
+            offline: false,
+            slow_transfers: AtomicU64::new(0),
+            mirror_downloader: None,
+            last_good_metadata: Default::default(),
+            metadata_checksum_mismatches: AtomicU64::new(0),
+            upstream_breaker: Default::default(),
+            artifact_downloads: KeyedMutex::new(),
+            uncached_range_policy: Default::default(),
+            snapshot_retention: Default::default(),
+            checksums: Default::default(),
+            access_times: AccessTimes::new(),
+            signature_policy: Default::default(),
+            keyring: Arc::new(Keyring::new()),
+        })
+    }
+
+    pub fn with_name(self, name: impl Into<String>) -> RemoteMavenRepo {
+        RemoteMavenRepo {
+            name: name.into(),
+            ..self
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// If enabled, directory listings include the upstream repository's directory index, allowing
+    ///  to browse content that is not cached locally
+    pub fn with_directory_listing_passthrough(self, enabled: bool) -> RemoteMavenRepo {
+        RemoteMavenRepo {
+            directory_listing_passthrough: enabled,
+            ..self
+        }
+    }
+
+    /// Upstream response headers with the given names (e.g. 'x-artifactory-id') are stored with
+    ///  an artifact's provenance. If `replay` is set, they are also returned with the artifact for
+    ///  clients that rely on vendor-specific headers.
+    pub fn with_persisted_headers(self, header_names: Vec<HeaderName>, replay: bool) -> RemoteMavenRepo {
+        RemoteMavenRepo {
+            downloader: self.downloader.with_captured_headers(header_names),
+            replay_upstream_headers: replay,
+            ..self
+        }
+    }
+
+    /// Hooks for inspecting or transforming artifacts as they are downloaded into the cache
+    pub fn with_content_hooks(self, content_hooks: ContentHooks) -> RemoteMavenRepo {
+        RemoteMavenRepo {
+            content_hooks,
+            ..self
+        }
+    }
+
+    /// Upstream transfers that are slower than the policy allows are aborted and retried once on a
+    ///  fresh connection
+    pub fn with_slow_transfer_policy(self, slow_transfer_policy: Option<SlowTransferPolicy>) -> RemoteMavenRepo {
+        RemoteMavenRepo {
+            downloader: self.downloader.with_slow_transfer_policy(slow_transfer_policy),
+            ..self
+        }
+    }
+
+    /// see [ValidatingHttpDownloader::with_checksum_headers]
+    pub fn with_checksum_headers(self, checksum_headers: Vec<ChecksumHeader>) -> RemoteMavenRepo {
+        RemoteMavenRepo {
+            downloader: self.downloader.with_checksum_headers(checksum_headers),
+            ..self
+        }
+    }
+
+    /// see [ValidatingHttpDownloader::with_checksum_file_policy]
+    pub fn with_checksum_file_policy(self, checksum_file_policy: ChecksumFilePolicy) -> RemoteMavenRepo {
+        RemoteMavenRepo {
+            downloader: self.downloader.with_checksum_file_policy(checksum_file_policy),
+            ..self
+        }
+    }
+
+    /// Artifacts are verified against their upstream '.asc' signature before they are cached, see
+    ///  [SignaturePolicy]. Repositories can share a keyring, so keys from the keyserver are only
+    ///  requested once.
+    pub fn with_signature_verification(self, signature_policy: SignaturePolicy, keyring: Arc<Keyring>) -> RemoteMavenRepo {
+        RemoteMavenRepo {
+            signature_policy,
+            keyring,
+            ..self
+        }
+    }
+
+    /// see [ValidatingHttpDownloader::with_tls_config]
+    pub fn with_tls_config(self, tls_config: UpstreamTlsConfig) -> anyhow::Result<RemoteMavenRepo> {
+        Ok(RemoteMavenRepo {
+            downloader: self.downloader.with_tls_config(tls_config)?,
+            ..self
         })
     }
 
+    /// Upstream metadata that does not match its checksum file is requested from this mirror of
+    ///  the upstream repository once before falling back to the last good copy. The mirror uses
+    ///  the upstream's client settings, so this should be called after configuring them.
+    pub fn with_mirror(self, mirror_uri: Option<String>) -> anyhow::Result<RemoteMavenRepo> {
+        let mirror_downloader = match mirror_uri {
+            Some(uri) => Some(self.downloader.for_base_uri(uri)?),
+            None => None,
+        };
+        Ok(RemoteMavenRepo {
+            mirror_downloader,
+            ..self
+        })
+    }
 
-    //TODO distinguish between 'not found' and 'error'?
+    pub fn with_circuit_breaker(self, upstream_breaker: CircuitBreaker) -> RemoteMavenRepo {
+        RemoteMavenRepo {
+            upstream_breaker,
+            ..self
+        }
+    }
 
+    /// Ranges are only passed through to upstream if there are no content hooks, since those
+    ///  need to see the entire content
+    pub fn with_uncached_range_policy(self, uncached_range_policy: UncachedRangePolicy) -> RemoteMavenRepo {
+        RemoteMavenRepo {
+            uncached_range_policy,
+            ..self
+        }
+    }
+
+    pub fn with_snapshot_retention(self, snapshot_retention: SnapshotRetentionPolicy) -> RemoteMavenRepo {
+        RemoteMavenRepo {
+            snapshot_retention,
+            ..self
+        }
+    }
+
+    /// The checksum files the repository serves, strongest first. Checksum files of other kinds
+    ///  are passed through from upstream like any other file.
+    pub fn with_checksums(self, checksum_kinds: Vec<ChecksumKind>) -> RemoteMavenRepo {
+        RemoteMavenRepo {
+            checksums: RepositoryChecksums::new(checksum_kinds),
+            ..self
+        }
+    }
+
+    pub fn checksums(&self) -> &RepositoryChecksums {
+        &self.checksums
+    }
+
+    /// An offline repository serves only artifacts that are available locally
+    pub fn with_offline(self, offline: bool) -> RemoteMavenRepo {
+        RemoteMavenRepo {
+            offline,
+            ..self
+        }
+    }
+
+    #[cfg(feature = "fault-injection")]
+    pub fn with_fault_injector(self, fault_injector: Arc<crate::util::fault_injection::FaultInjector>) -> RemoteMavenRepo {
+        RemoteMavenRepo {
+            downloader: self.downloader.with_fault_injector(fault_injector),
+            ..self
+        }
+    }
+
+    fn replayed_headers(&self, provenance: &ArtifactProvenance) -> Vec<(String, String)> {
+        if self.replay_upstream_headers {
+            provenance.upstream_headers.clone()
+        }
+        else {
+            vec![]
+        }
+    }
+
+
+    /// Failures carry a [RepoError] that tells 'not found' apart from upstream and local problems
+    #[tracing::instrument(level = "debug", skip_all, fields(artifact = as_maven_path(artifact_ref)))]
     pub async fn get_artifact(&self, artifact_ref: &MavenArtifactRef) -> anyhow::Result<Blob> {
         match self.metadata_store
             .decide_get_artifact(artifact_ref).await?
         {
-            GetArtifactDecision::Local(id) => {
-                match self.blob_storage.get(&id).await? {
-                    Some(blob) => {
-                        Ok(blob)
-                    }
-                    None => {
-                        //TODO repair local metadata - the blob is referenced but does not exist
-                        Err(anyhow!("TODO local blob not found")) //TODO
-                    }
-                }
+            GetArtifactDecision::Local { blob_key, provenance } => {
+                CACHE_REQUESTS.with_label_values(&[&self.name, "hit"]).inc();
+                record_cache_outcome("hit");
+                self.get_local_artifact(artifact_ref, &blob_key, &provenance).await
             },
+            GetArtifactDecision::Download if self.offline => {
+                Err(RepoError::NotFound(format!("{} is not available in offline repository", as_maven_path(artifact_ref))).into())
+            }
             GetArtifactDecision::Download => {
-                match self.downloader.get(&as_maven_path(&artifact_ref)).await {
-                    Ok(stream) => {
-                        let key = self.blob_storage.insert(stream.data)
-                            .await?;
-                        self.metadata_store.register_artifact(artifact_ref, &key)
-                            .await?;
-                        match self.blob_storage.get(&key)
-                            .await?
-                        {
-                            None => Err(anyhow!("TODO stored but not found")),
-                            Some(s) => Ok(s),
-                        }
+                CACHE_REQUESTS.with_label_values(&[&self.name, "miss"]).inc();
+                record_cache_outcome("miss");
+                if let Some(retry_at) = self.upstream_breaker.retry_at() {
+                    return Err(RetryLaterError {
+                        reason: RetryLaterReason::UpstreamUnavailable,
+                        path: as_maven_path(artifact_ref),
+                        retry_at,
+                    }.into());
+                }
+
+                let _download_lock = self.artifact_downloads.lock(ArtifactKey::for_artifact(artifact_ref)).await;
+                // a concurrent request may have downloaded the artifact (or failed to) while this
+                //  one was waiting for the lock
+                match self.metadata_store
+                    .decide_get_artifact(artifact_ref).await?
+                {
+                    GetArtifactDecision::Local { blob_key, provenance } => self.get_local_artifact(artifact_ref, &blob_key, &provenance).await,
+                    GetArtifactDecision::Download => self.download_artifact(artifact_ref).await,
+                    GetArtifactDecision::Fail { retry_at, kind } => Err(failed_download_error(artifact_ref, retry_at, kind)),
+                }
+            }
+            GetArtifactDecision::Fail { retry_at, kind } => {
+                Err(failed_download_error(artifact_ref, retry_at, kind))
+            }
+        }
+    }
+
+    async fn get_local_artifact(&self, artifact_ref: &MavenArtifactRef, blob_key: &Uuid, provenance: &ArtifactProvenance) -> anyhow::Result<Blob> {
+        match self.blob_storage.get(blob_key).await? {
+            Some(blob) => {
+                self.access_times.record(artifact_ref, SystemTime::now());
+                Ok(Blob {
+                    last_modified: Some(provenance.last_modified),
+                    upstream_headers: self.replayed_headers(provenance),
+                    ..blob
+                })
+            }
+            None => {
+                //TODO repair local metadata - the blob is referenced but does not exist
+                Err(RepoError::Internal(format!("blob {} of {} not found", blob_key, as_maven_path(artifact_ref))).into())
+            }
+        }
+    }
+
+    async fn download_artifact(&self, artifact_ref: &MavenArtifactRef) -> anyhow::Result<Blob> {
+        let _in_flight = InFlightGuard::new(DOWNLOADS_IN_FLIGHT.with_label_values(&[&self.name]));
+        let stored = match self.download_and_store(artifact_ref, false).await {
+            Err(e) if is_slow_transfer(&e) => {
+                self.slow_transfers.fetch_add(1, Ordering::Relaxed);
+                warn!("{} for {}, retrying on a fresh connection", e, as_maven_path(artifact_ref));
+                self.download_and_store(artifact_ref, true).await
+            }
+            other => other,
+        };
+
+        match stored {
+            Ok((key, provenance)) => {
+                self.upstream_breaker.record_success();
+                match self.blob_storage.get(&key)
+                    .await?
+                {
+                    None => Err(RepoError::Internal(format!("blob {} of {} not found after storing it", key, as_maven_path(artifact_ref))).into()),
+                    Some(s) => Ok(Blob {
+                        last_modified: Some(provenance.last_modified),
+                        upstream_headers: self.replayed_headers(&provenance),
+                        ..s
+                    }),
+                }
+            }
+            // the next request downloads a quarantined artifact again rather than failing
+            Err(e) if is_quarantined(&e) => {
+                self.upstream_breaker.record_success();
+                Err(e)
+            }
+            // upstream answered, so it is available and only the artifact is missing
+            Err(e) if RepoError::is_not_found(&e) => {
+                self.upstream_breaker.record_success();
+                let _ = self.metadata_store.register_failed_download(artifact_ref, DownloadFailureKind::NotFound)
+                    .await;
+                Err(e)
+            }
+            Err(e) => {
+                self.upstream_breaker.record_failure();
+                let _ = self.metadata_store.register_failed_download(artifact_ref, DownloadFailureKind::TransportError)
+                    .await;
+                match RepoError::of(&e) {
+                    Some(_) => Err(e),
+                    None => Err(RepoError::UpstreamUnavailable(format!("failed to download {}: {}", as_maven_path(artifact_ref), e)).into()),
+                }
+            }
+        }
+    }
+
+    async fn download_and_store(&self, artifact_ref: &MavenArtifactRef, fresh_connection: bool) -> anyhow::Result<(Uuid, ArtifactProvenance)> {
+        let path = as_maven_path(artifact_ref);
+        // requested first, so that the artifact can be verified while it is stored
+        let signature = self.fetch_signature(&path).await?;
+        let stream = if fresh_connection {
+            self.downloader.get_with_fresh_connection(&path).await?
+        }
+        else {
+            self.downloader.get(&path).await?
+        };
+
+        let now = SystemTime::now();
+        let provenance = ArtifactProvenance {
+            fetched: now,
+            // fall back to the download time if upstream does not send 'Last-Modified'
+            last_modified: stream.last_modified.unwrap_or(now),
+            upstream_headers: stream.upstream_headers,
+        };
+        // transformers may change the content's length
+        let expected_size = stream.size.filter(|_| self.content_hooks.is_empty());
+        // the signature covers upstream's content, before any transformation
+        let hasher = signature.as_ref().map(|(signature, _)| Arc::new(Mutex::new(signature.hasher())));
+        let upstream_data = match &hasher {
+            Some(hasher) => {
+                let hasher = hasher.clone();
+                Box::pin(stream.data.inspect_ok(move |chunk| hasher.lock().unwrap().update(chunk)))
+            }
+            None => stream.data,
+        };
+        // data that fails validation is stored anyway, for quarantining it
+        let checksum_mismatch = Arc::new(Mutex::new(None));
+        let upstream_data = retain_checksum_mismatch(upstream_data, checksum_mismatch.clone());
+        let data = self.content_hooks.apply(&path, upstream_data);
+        let key = self.blob_storage.insert_with_size(data, expected_size)
+            .await?;
+        let mut validation_error = checksum_mismatch.lock().unwrap().take();
+        if let (None, Some((signature, public_key)), Some(hasher)) = (&validation_error, signature, hasher) {
+            let hasher = hasher.lock().unwrap().clone();
+            if let Err(e) = signature.verify(hasher, &public_key) {
+                validation_error = Some(RepoError::InvalidSignature(format!("{} with key {:016X}: {}", path, public_key.key_id(), e)).into());
+            }
+        }
+        if let Some(e) = validation_error {
+            return Err(self.quarantine(artifact_ref, key, e).await);
+        }
+        // only needed for rebuilding metadata after a restart, so this does not fail the download
+        let origin = BlobOrigin { path, fetched: provenance.fetched, last_modified: provenance.last_modified };
+        if let Err(e) = self.blob_storage.record_origin(&key, &origin).await {
+            warn!("error recording the origin of blob {}: {}", key, e);
+        }
+        self.metadata_store.register_artifact(artifact_ref, &key, &provenance)
+            .await?;
+        self.access_times.record(artifact_ref, now);
+        Ok((key, provenance))
+    }
+
+    /// Keeps the blob of a download that failed validation, see [QuarantinedArtifact], or deletes
+    ///  it if the metadata store does not keep quarantined artifacts. The blob of a previous
+    ///  download of the artifact that was quarantined is deleted. Returns the validation error.
+    async fn quarantine(&self, artifact_ref: &MavenArtifactRef, blob_key: Uuid, validation_error: anyhow::Error) -> anyhow::Error {
+        let path = as_maven_path(artifact_ref);
+        let quarantined = QuarantinedArtifact {
+            artifact_ref: artifact_ref.clone(),
+            blob_key,
+            reason: validation_error.to_string(),
+            quarantined_at: SystemTime::now(),
+        };
+        match self.metadata_store.quarantine_artifact(quarantined).await {
+            Ok(QuarantineOutcome::Kept(replaced)) => {
+                warn!("{} failed validation, quarantined as blob {}: {}", path, blob_key, validation_error);
+                if let Some(replaced) = replaced {
+                    if let Err(e) = self.blob_storage.delete(&replaced).await {
+                        warn!("error deleting blob {} of a superseded quarantined download: {}", replaced, e);
                     }
-                    Err(_e) => {
-                        let _ = self.metadata_store.register_failed_download(artifact_ref)
-                            .await;
-                        Err(anyhow!("failed to download"))
+                }
+                return validation_error.context(QuarantinedDownload { path, blob_key });
+            }
+            Ok(QuarantineOutcome::NotKept) => {}
+            Err(e) => warn!("error quarantining {}: {}", path, e),
+        }
+        if let Err(e) = self.blob_storage.delete(&blob_key).await {
+            warn!("error deleting blob {} that failed validation: {}", blob_key, e);
+        }
+        validation_error
+    }
+
+    /// The signature an artifact is verified against, with the key that made it, see
+    ///  [SignaturePolicy]. Fails if the policy requires a signature that can not be checked.
+    async fn fetch_signature(&self, path: &str) -> anyhow::Result<Option<(PgpSignature, PgpPublicKey)>> {
+        if self.signature_policy == SignaturePolicy::Ignore || !is_signed_path(path) {
+            return Ok(None);
+        }
+
+        let checkable = match self.downloader.get_bounded(&format!("{}.asc", path), MAX_SIGNATURE_SIZE).await {
+            Ok(data) => self.signature_with_key(&data).await,
+            Err(e) => Err(e.context("no signature")),
+        };
+        match checkable {
+            Ok(checkable) => Ok(Some(checkable)),
+            Err(e) if self.signature_policy == SignaturePolicy::Require => Err(RepoError::InvalidSignature(format!("{}: {:#}", path, e)).into()),
+            Err(e) => {
+                debug!("not verifying the signature of {}: {:#}", path, e);
+                Ok(None)
+            }
+        }
+    }
+
+    async fn signature_with_key(&self, data: &[u8]) -> anyhow::Result<(PgpSignature, PgpPublicKey)> {
+        let signature = parse_signature(data)?;
+        let key_id = signature.issuer
+            .ok_or_else(|| anyhow!("signature does not name its key"))?;
+        match self.keyring.get(key_id).await? {
+            Some(key) => Ok((signature, key)),
+            None => Err(anyhow!("unknown key {:016X}", key_id)),
+        }
+    }
+
+    /// Registers the artifacts that blob storage recorded the origin of (see
+    ///  [BlobStorage::record_origin]), for starting with a populated cache after a restart. Does
+    ///  nothing if artifacts are registered already. Returns the number of registered artifacts.
+    pub async fn rebuild_metadata_from_blob_storage(&self) -> anyhow::Result<usize> {
+        if !self.metadata_store.get_local_artifacts().await?.is_empty() {
+            return Ok(0);
+        }
+
+        let mut origins = self.blob_storage.origins().await?;
+        // if a path was downloaded more than once, the latest download wins
+        origins.sort_by_key(|(_, origin)| origin.fetched);
+        let mut num_registered = 0;
+        for (key, origin) in origins {
+            let artifact_ref = match parse_maven_path(&origin.path) {
+                Ok(artifact_ref) => artifact_ref,
+                Err(e) => {
+                    warn!("skipping blob {} with invalid origin '{}': {}", key, origin.path, e);
+                    continue;
+                }
+            };
+            let provenance = ArtifactProvenance {
+                fetched: origin.fetched,
+                last_modified: origin.last_modified,
+                upstream_headers: vec![],
+            };
+            self.metadata_store.register_artifact(&artifact_ref, &key, &provenance).await?;
+            num_registered += 1;
+        }
+        Ok(num_registered)
+    }
+
+    /// Initializes the access times of cached artifacts that have none, e.g. after an upgrade from a
+    ///  version that did not track them: from blob storage (see [BlobStorage::last_access]), or the
+    ///  time of download if blob storage does not know. Artifacts that are served in the meantime
+    ///  keep their tracked access time. Returns the number of initialized access times.
+    pub async fn backfill_access_times(&self) -> anyhow::Result<usize> {
+        let mut num_backfilled = 0;
+        for (artifact_ref, blob_key, provenance) in self.metadata_store.get_local_artifact_details().await? {
+            if self.access_times.get(&artifact_ref).is_some() {
+                continue;
+            }
+            let at = match self.blob_storage.last_access(&blob_key).await {
+                Ok(Some(at)) => at,
+                Ok(None) => provenance.fetched,
+                Err(e) => {
+                    debug!("no last access time for blob {} of {}: {}", blob_key, as_maven_path(&artifact_ref), e);
+                    provenance.fetched
+                }
+            };
+            if self.access_times.backfill(&artifact_ref, at) {
+                num_backfilled += 1;
+            }
+        }
+        Ok(num_backfilled)
+    }
+
+    /// When a cached artifact was last served, see [AccessTimes]
+    pub fn last_access(&self, artifact_ref: &MavenArtifactRef) -> Option<AccessTime> {
+        self.access_times.get(artifact_ref)
+    }
+
+    /// The number of upstream transfers that were aborted for being too slow, see [SlowTransferPolicy]
+    pub fn slow_transfer_count(&self) -> u64 {
+        self.slow_transfers.load(Ordering::Relaxed)
+    }
+
+    /// The number of upstream metadata files (including those from the mirror) that did not match
+    ///  their checksum files
+    pub fn metadata_checksum_mismatch_count(&self) -> u64 {
+        self.metadata_checksum_mismatches.load(Ordering::Relaxed)
+    }
+
+    /// Returns an artifact's metadata without opening its data. Artifacts that are not available
+    ///  locally are downloaded first.
+    pub async fn get_artifact_stat(&self, artifact_ref: &MavenArtifactRef) -> anyhow::Result<BlobStat> {
+        if let GetArtifactDecision::Local { blob_key, provenance } = self.metadata_store.decide_get_artifact(artifact_ref).await? {
+            if let Some(stat) = self.blob_storage.stat(&blob_key).await? {
+                return Ok(self.stat_with_provenance(stat, &provenance));
+            }
+        }
+
+        // 'get_artifact' stores the downloaded artifact before returning, so this is local now
+        self.get_artifact(artifact_ref).await?;
+        match self.metadata_store.decide_get_artifact(artifact_ref).await? {
+            GetArtifactDecision::Local { blob_key, provenance } => {
+                match self.blob_storage.stat(&blob_key).await? {
+                    Some(stat) => Ok(self.stat_with_provenance(stat, &provenance)),
+                    None => Err(RepoError::Internal(format!("blob {} of {} not found", blob_key, as_maven_path(artifact_ref))).into()),
+                }
+            }
+            _ => Err(RepoError::Internal(format!("{} not registered after storing it", as_maven_path(artifact_ref))).into()),
+        }
+    }
+
+    /// Returns part of an artifact, e.g. for clients resuming an interrupted download. How
+    ///  artifacts that are not available locally are handled depends on the
+    ///  [UncachedRangePolicy].
+    pub async fn get_artifact_range(self: &Arc<Self>, artifact_ref: &MavenArtifactRef, range: &ByteRange) -> anyhow::Result<BlobRange> {
+        let passes_through = self.uncached_range_policy != UncachedRangePolicy::Download && !self.offline && self.content_hooks.is_empty();
+        if passes_through && matches!(self.metadata_store.decide_get_artifact(artifact_ref).await?, GetArtifactDecision::Download) {
+            return self.get_uncached_artifact_range(artifact_ref, range).await;
+        }
+
+        let total_size = self.get_artifact_stat(artifact_ref).await?.size;
+        let range = match range.resolve(total_size) {
+            Some(range) => range,
+            None => return Ok(BlobRange::Unsatisfiable { total_size }),
+        };
+
+        match self.metadata_store.decide_get_artifact(artifact_ref).await? {
+            GetArtifactDecision::Local { blob_key, provenance } => {
+                match self.blob_storage.get_range(&blob_key, range.clone()).await? {
+                    Some(blob) => {
+                        self.access_times.record(artifact_ref, SystemTime::now());
+                        Ok(BlobRange::Partial {
+                            blob: Blob {
+                                last_modified: Some(provenance.last_modified),
+                                upstream_headers: self.replayed_headers(&provenance),
+                                ..blob
+                            },
+                            range,
+                            total_size,
+                        })
                     }
+                    None => Err(RepoError::Internal(format!("blob {} of {} not found", blob_key, as_maven_path(artifact_ref))).into()),
                 }
             }
-            GetArtifactDecision::Fail => {
-                //TODO distinguish 404 from general network failure - per-artifact retry interval vs. general 'circuit breaker'
-                //  -> integrate that logic in the downloader?
-                Err(anyhow!("TODO skipping due to a previous failure to download"))
+            _ => Err(RepoError::Internal(format!("{} not registered after storing it", as_maven_path(artifact_ref))).into()),
+        }
+    }
+
+    async fn get_uncached_artifact_range(self: &Arc<Self>, artifact_ref: &MavenArtifactRef, range: &ByteRange) -> anyhow::Result<BlobRange> {
+        if let Some(retry_at) = self.upstream_breaker.retry_at() {
+            return Err(RetryLaterError {
+                reason: RetryLaterReason::UpstreamUnavailable,
+                path: as_maven_path(artifact_ref),
+                retry_at,
+            }.into());
+        }
+
+        let result = self.downloader.get_range(&as_maven_path(artifact_ref), range).await;
+        match &result {
+            Err(e) if !RepoError::is_not_found(e) => self.upstream_breaker.record_failure(),
+            _ => self.upstream_breaker.record_success(),
+        }
+
+        if result.is_ok() && self.uncached_range_policy == UncachedRangePolicy::PassthroughAndFill {
+            // concurrent fills and requests for the entire artifact share a single download
+            let repo = self.clone();
+            let artifact_ref = artifact_ref.clone();
+            tokio::spawn(async move {
+                if let Err(e) = repo.get_artifact_stat(&artifact_ref).await {
+                    debug!("background cache fill failed for {}: {}", as_maven_path(&artifact_ref), e);
+                }
+            }.in_current_span());
+        }
+        result
+    }
+
+    fn stat_with_provenance(&self, stat: BlobStat, provenance: &ArtifactProvenance) -> BlobStat {
+        BlobStat {
+            last_modified: Some(provenance.last_modified),
+            upstream_headers: self.replayed_headers(provenance),
+            ..stat
+        }
+    }
+
+    /// The content of an artifact's checksum file, i.e. its hex encoded checksum. Fails with
+    ///  [RepoError::NotFound] if the repository does not serve this kind of checksum.
+    pub async fn get_artifact_checksum(&self, artifact_ref: &MavenArtifactRef, kind: ChecksumKind) -> anyhow::Result<String> {
+        // this ensures that the artifact is downloaded if possible (it will likely be queried next
+        //  after the checksum is queried)
+        let stat = self.get_artifact_stat(artifact_ref).await?;
+        self.checksums.served(stat.md5, stat.sha1, kind, || async { Ok(self.get_artifact(artifact_ref).await?.data) }).await?
+            .ok_or_else(|| RepoError::NotFound(format!("no {} checksum for {}", kind.name(), as_maven_path(artifact_ref))).into())
+    }
+
+    /// 'directory_path' is relative to the repository root, e.g. 'org/apache/' - an empty string
+    ///  denotes the root directory
+    pub async fn get_directory_listing(&self, directory_path: &str) -> anyhow::Result<DirectoryListing> {
+        let prefix = directory_prefix(directory_path);
+        let local_listing = local_directory_listing(self.metadata_store.as_ref(), &prefix).await?;
+
+        if !self.directory_listing_passthrough || self.offline {
+            return Ok(local_listing);
+        }
+
+        Ok(local_listing.merge(&self.get_upstream_directory_listing(&prefix).await))
+    }
+
+    async fn get_upstream_directory_listing(&self, prefix: &str) -> DirectoryListing {
+        if let Some((fetched, listing)) = self.directory_listing_cache.lock().unwrap().get(prefix) {
+            if fetched.elapsed() < DIRECTORY_LISTING_TTL {
+                return listing.clone();
+            }
+        }
+
+        let listing = match self.downloader.get_bounded(prefix, MAX_DIRECTORY_LISTING_SIZE).await {
+            Ok(html) => parse_html_index(&String::from_utf8_lossy(&html)),
+            Err(e) => {
+                // the listing is a convenience, so fall back to local data rather than failing
+                warn!("failed to fetch upstream directory listing for '{}': {}", prefix, e);
+                return DirectoryListing::default();
+            }
+        };
+
+        let mut cache = self.directory_listing_cache.lock().unwrap();
+        cache.retain(|_, (fetched, _)| fetched.elapsed() < DIRECTORY_LISTING_TTL);
+        cache.insert(prefix.to_string(), (Instant::now(), listing.clone()));
+        listing
+    }
+
+    /// checks if any artifact in this repository refers to the given blob
+    pub async fn get_local_artifact_details(&self) -> anyhow::Result<Vec<(MavenArtifactRef, Uuid, ArtifactProvenance)>> {
+        self.metadata_store.get_local_artifact_details().await
+    }
+
+    pub async fn is_blob_referenced(&self, blob_key: &Uuid) -> anyhow::Result<bool> {
+        self.metadata_store.is_blob_referenced(blob_key).await
+    }
+
+    /// Removes cached snapshot builds that the repository's [SnapshotRetentionPolicy] does not
+    ///  keep, returning the removed artifacts. Their blobs are left to garbage collection since
+    ///  clones may still refer to them.
+    pub async fn purge_snapshots(&self) -> anyhow::Result<Vec<MavenArtifactRef>> {
+        Ok(purge_snapshot_builds(self.metadata_store.as_ref(), &self.snapshot_retention).await?
+            .into_iter()
+            .map(|(artifact_ref, _)| artifact_ref)
+            .collect())
+    }
+
+    /// Removes a cached artifact and any failed download of it, so that the next request downloads
+    ///  it from upstream again. The artifact's blob is left to the caller since clones may still
+    ///  refer to it.
+    pub async fn evict_artifact(&self, artifact_ref: &MavenArtifactRef) -> anyhow::Result<Eviction> {
+        let artifacts = self.metadata_store.unregister_artifact(artifact_ref).await?
+            .map(|blob_key| vec![(artifact_ref.clone(), blob_key)])
+            .unwrap_or_default();
+        self.access_times.remove(artifact_ref);
+        let failed_downloads = self.metadata_store.forget_failed_download(artifact_ref).await? as usize;
+        self.forget_upstream_metadata(&artifact_ref.coordinates.group_id.0);
+        Ok(Eviction { artifacts, failed_downloads })
+    }
+
+    /// Like [RemoteMavenRepo::evict_artifact] for all artifacts in a group and its subgroups, e.g.
+    ///  when upstream published corrected artifacts
+    pub async fn evict_group(&self, group_prefix: &str) -> anyhow::Result<Eviction> {
+        let mut artifacts = Vec::new();
+        for (artifact_ref, _, _) in self.metadata_store.get_local_artifact_details().await? {
+            if !artifact_ref.coordinates.group_id.is_in(group_prefix) {
+                continue;
+            }
+            if let Some(blob_key) = self.metadata_store.unregister_artifact(&artifact_ref).await? {
+                self.access_times.remove(&artifact_ref);
+                artifacts.push((artifact_ref, blob_key));
             }
         }
+        let failed_downloads = self.metadata_store.forget_failed_downloads(group_prefix).await?;
+        self.forget_upstream_metadata(group_prefix);
+        Ok(Eviction { artifacts, failed_downloads })
+    }
+
+    /// discards cached upstream listings and metadata in a group and its subgroups
+    fn forget_upstream_metadata(&self, group_prefix: &str) {
+        let group_path = format!("{}/", group_prefix.replace('.', "/"));
+        let is_in_group = |path: &str| group_path == "/" || path.starts_with(&group_path);
+
+        self.directory_listing_cache.lock().unwrap().retain(|prefix, _| !is_in_group(prefix));
+        self.upstream_metadata_cache.lock().unwrap().retain(|(group_id, _), _| !group_id.is_in(group_prefix));
+        self.last_good_metadata.lock().unwrap().retain(|path, _| !is_in_group(path));
+    }
+
+    pub async fn get_project_info(&self, artifact_ref: &MavenArtifactRef) -> anyhow::Result<Option<ProjectInfo>> {
+        self.metadata_store.get_project_info(artifact_ref).await
+    }
+
+    pub async fn cache_project_info(&self, artifact_ref: &MavenArtifactRef, project_info: ProjectInfo) -> anyhow::Result<()> {
+        self.metadata_store.cache_project_info(artifact_ref, project_info).await
+    }
+
+    pub async fn get_quarantined_artifacts(&self) -> anyhow::Result<Vec<QuarantinedArtifact>> {
+        self.metadata_store.get_quarantined_artifacts().await
     }
 
-    pub async fn get_artifact_md5(&self, artifact_ref: &MavenArtifactRef) -> anyhow::Result<[u8;16]> {
-        // delegating to 'get_artifact' ensures that the artifact is downloaded if possible (it
-        //  will likely be queried next after the checksum is queried), and it does not incur
-        //  big overhead since the artifact's data is only fetched as a Stream, i.e. lazily
-        Ok(self.get_artifact(artifact_ref)
-            .await?
-            .md5
-            .expect("locally stored artifacts have their md5 checksum stored"))
+    /// A quarantined download with its blob, e.g. for inspecting it. None if no download with this
+    ///  blob is quarantined.
+    pub async fn get_quarantined_blob(&self, blob_key: &Uuid) -> anyhow::Result<Option<(QuarantinedArtifact, Blob)>> {
+        let quarantined = self.metadata_store.get_quarantined_artifacts().await?
+            .into_iter()
+            .find(|quarantined| &quarantined.blob_key == blob_key);
+        let Some(quarantined) = quarantined else { return Ok(None) };
+        Ok(self.blob_storage.get(blob_key).await?
+            .map(|blob| (quarantined, blob)))
     }
 
-    pub async fn get_artifact_sha1(&self, artifact_ref: &MavenArtifactRef) -> anyhow::Result<[u8;20]> {
-        // delegating to 'get_artifact' ensures that the artifact is downloaded if possible (it
-        //  will likely be queried next after the checksum is queried), and it does not incur
-        //  big overhead since the artifact's data is only fetched as a Stream, i.e. lazily
-        Ok(self.get_artifact(artifact_ref)
-            .await?
-            .sha1
-            .expect("locally stored artifacts have their md5 checksum stored"))
+    /// Forgets a quarantined download, returning it if there was one. Its blob is left to the
+    ///  caller.
+    pub async fn remove_quarantined_artifact(&self, blob_key: &Uuid) -> anyhow::Result<Option<QuarantinedArtifact>> {
+        self.metadata_store.remove_quarantined_artifact(blob_key).await
     }
 
+    /// None if the artifact is neither cached nor quarantined
+    pub async fn artifact_status(&self, artifact_ref: &MavenArtifactRef) -> anyhow::Result<Option<ArtifactStatus>> {
+        if let GetArtifactDecision::Local { .. } = self.metadata_store.decide_get_artifact(artifact_ref).await? {
+            return Ok(Some(ArtifactStatus::Cached));
+        }
+        let is_quarantined = self.metadata_store.get_quarantined_artifacts().await?
+            .iter()
+            .any(|quarantined| &quarantined.artifact_ref == artifact_ref);
+        Ok(is_quarantined.then_some(ArtifactStatus::Quarantined))
+    }
+
+    /// for blobs that are not referenced any more, e.g. after [RemoteMavenRepo::evict_artifact]
+    pub async fn delete_blob(&self, blob_key: &Uuid) -> anyhow::Result<bool> {
+        self.blob_storage.delete(blob_key).await
+    }
+
+    /// Fails with a [crate::maven::plugin_prefix::PluginPrefixConflict] if another plugin in the
+    ///  group has the same prefix, unless the metadata store is configured to overwrite it
     pub async fn register_plugin(&self, group_id: MavenGroupId, plugin_metadata: MavenPluginMetadata) -> anyhow::Result<ChangeKind> {
         self.metadata_store.register_plugin(group_id, plugin_metadata).await
     }
@@ -115,6 +873,23 @@ impl <S: BlobStorage<Uuid>, M: RemoteRepoMetadataStore> RemoteMavenRepo<S, M> {
         self.metadata_store.unregister_plugin(group_id, artifact_id).await
     }
 
+    pub async fn set_ttl_override(&self, ttl_override: TtlOverride) -> anyhow::Result<ChangeKind> {
+        self.metadata_store.set_ttl_override(ttl_override).await
+    }
+
+    pub async fn remove_ttl_override(&self, group_prefix: &str) -> anyhow::Result<bool> {
+        self.metadata_store.remove_ttl_override(group_prefix).await
+    }
+
+    pub async fn get_ttl_overrides(&self) -> anyhow::Result<Vec<TtlOverride>> {
+        self.metadata_store.get_ttl_overrides().await
+    }
+
+    /// see [RemoteRepoMetadataStore::failed_download_count]
+    pub async fn failed_download_count(&self) -> anyhow::Result<usize> {
+        self.metadata_store.failed_download_count().await
+    }
+
     pub async fn get_group_metadata(&self, group_id: &MavenGroupId) -> anyhow::Result<MavenGroupMetadata> {
         Ok(MavenGroupMetadata {
             plugins: self.metadata_store.get_plugins(group_id).await?
@@ -125,7 +900,276 @@ impl <S: BlobStorage<Uuid>, M: RemoteRepoMetadataStore> RemoteMavenRepo<S, M> {
         Ok(self.metadata_store.get_artifact_metadata(group_id, artifact_id).await?)
     }
 
-    //TODO get_version_metadata()
+    /// see [RemoteRepoMetadataStore::get_artifact_metadata_many]
+    pub async fn get_artifact_metadata_many(&self, artifacts: &[(MavenGroupId, MavenArtifactId)]) -> anyhow::Result<Vec<Option<MavenArtifactMetadata>>> {
+        self.metadata_store.get_artifact_metadata_many(artifacts).await
+    }
+
+    /// see [RemoteRepoMetadataStore::get_artifact_metadata_as_of]
+    pub async fn get_artifact_metadata_as_of(&self, artifacts: &[(MavenGroupId, MavenArtifactId)], as_of: SystemTime) -> anyhow::Result<Vec<Option<MavenArtifactMetadata>>> {
+        self.metadata_store.get_artifact_metadata_as_of(artifacts, as_of).await
+    }
+
+    /// Creates an offline copy of this repository's current state, e.g. as a frozen snapshot for
+    ///  reproducibility audits. Blobs are shared rather than copied: they are immutable, and
+    ///  artifacts stored later in either repository get blobs of their own.
+    pub async fn frozen_clone(&self) -> anyhow::Result<RemoteMavenRepo> {
+        let snapshot = RepoMetadataSnapshot {
+            // cached artifacts must not expire since they can not be fetched again
+            ttl_overrides: vec![],
+            ..self.metadata_store.snapshot().await?
+        };
+
+        Ok(RemoteMavenRepo {
+            name: self.name.clone(),
+            downloader: self.downloader.clone(),
+            blob_storage: self.blob_storage.clone(),
+            metadata_store: Arc::new(DummyRemoteRepoMetadataStore::from_snapshot(snapshot)),
+            directory_listing_passthrough: false,
+            directory_listing_cache: Default::default(),
+            upstream_metadata_cache: Default::default(),
+            replay_upstream_headers: self.replay_upstream_headers,
+            content_hooks: self.content_hooks.clone(),
+            offline: true,
+            slow_transfers: AtomicU64::new(0),
+            mirror_downloader: None,
+            last_good_metadata: Default::default(),
+            metadata_checksum_mismatches: AtomicU64::new(0),
+            upstream_breaker: Default::default(),
+            artifact_downloads: KeyedMutex::new(),
+            uncached_range_policy: Default::default(),
+            snapshot_retention: Default::default(),
+            checksums: RepositoryChecksums::new(self.checksums.kinds().to_vec()),
+            access_times: AccessTimes::new(),
+            signature_policy: Default::default(),
+            keyring: self.keyring.clone(),
+        })
+    }
+
+    pub async fn get_metadata_snapshot(&self) -> anyhow::Result<RepoMetadataSnapshot> {
+        self.metadata_store.snapshot().await
+    }
+
+    pub async fn restore_metadata_snapshot(&self, snapshot: RepoMetadataSnapshot) -> anyhow::Result<()> {
+        self.directory_listing_cache.lock().unwrap().clear();
+        self.upstream_metadata_cache.lock().unwrap().clear();
+        self.last_good_metadata.lock().unwrap().clear();
+        self.metadata_store.restore(snapshot).await
+    }
+
+    pub async fn get_blob_stat(&self, blob_key: &Uuid) -> anyhow::Result<Option<BlobStat>> {
+        self.blob_storage.stat(blob_key).await
+    }
+
+    /// All versions of an artifact that are known locally or listed in the upstream repository's
+    ///  'maven-metadata.xml', in no particular order. Snapshots are listed without timestamps.
+    pub async fn get_available_versions(&self, group_id: &MavenGroupId, artifact_id: &MavenArtifactId) -> anyhow::Result<Vec<String>> {
+        let mut versions = match self.metadata_store.get_artifact_metadata(group_id, artifact_id).await? {
+            Some(metadata) => metadata.versions.iter()
+                .map(|v| v.unqualified().to_string())
+                .collect(),
+            None => vec![],
+        };
+
+        if self.offline {
+            return Ok(versions);
+        }
+
+        if let Some(upstream) = self.get_upstream_artifact_metadata(group_id, artifact_id).await.artifact {
+            versions.extend(upstream.versions.iter().map(|v| v.unqualified().to_string()));
+        }
+        versions.sort();
+        versions.dedup();
+        Ok(versions)
+    }
+
+    /// The upstream repository's artifact level 'maven-metadata.xml', parsed and cached briefly. The
+    ///  same file is the group level metadata of the group 'group_id.artifact_id', so its plugins
+    ///  are kept as well. Empty if upstream is unavailable, does not know the path, or this
+    ///  repository is offline.
+    async fn get_upstream_artifact_metadata(&self, group_id: &MavenGroupId, artifact_id: &MavenArtifactId) -> UpstreamMetadata {
+        if self.offline {
+            return UpstreamMetadata::default();
+        }
+
+        let cache_key = (group_id.clone(), artifact_id.clone());
+        if let Some((fetched, metadata)) = self.upstream_metadata_cache.lock().unwrap().get(&cache_key) {
+            if fetched.elapsed() < ARTIFACT_METADATA_TTL {
+                return metadata.clone();
+            }
+        }
+
+        let metadata_path = format!("{}/{}/maven-metadata.xml", group_id.0.replace('.', "/"), artifact_id.0);
+        let metadata = match self.get_upstream_metadata_file(&metadata_path).await {
+            Ok(xml) => {
+                let xml = String::from_utf8_lossy(&xml);
+                UpstreamMetadata {
+                    artifact: parse_artifact_metadata(&xml),
+                    plugins: parse_plugins(&xml),
+                }
+            }
+            Err(e) => {
+                // local versions are still useful if upstream is unavailable - failures are not
+                //  cached so that the next request tries again
+                warn!("failed to fetch upstream metadata for '{}': {}", metadata_path, e);
+                return UpstreamMetadata::default();
+            }
+        };
+
+        let mut cache = self.upstream_metadata_cache.lock().unwrap();
+        cache.retain(|_, (fetched, _)| fetched.elapsed() < ARTIFACT_METADATA_TTL);
+        cache.insert(cache_key, (Instant::now(), metadata.clone()));
+        metadata
+    }
+
+    /// Fetches an upstream metadata file and checks it against its '.sha1' file (if upstream has
+    ///  one). Mirrors are surprisingly often inconsistent here, so a mismatch is counted, the
+    ///  configured mirror is tried once, and the last copy that matched its checksum is served
+    ///  rather than failing.
+    async fn get_upstream_metadata_file(&self, path: &str) -> anyhow::Result<Bytes> {
+        let e = match fetch_verified_metadata(&self.downloader, path).await {
+            Ok(data) => return Ok(self.remember_good_metadata(path, data)),
+            Err(e) if is_checksum_mismatch(&e) => e,
+            Err(e) => return Err(e),
+        };
+        self.metadata_checksum_mismatches.fetch_add(1, Ordering::Relaxed);
+        warn!("{}", e);
+
+        if let Some(mirror) = &self.mirror_downloader {
+            match fetch_verified_metadata(mirror, path).await {
+                Ok(data) => return Ok(self.remember_good_metadata(path, data)),
+                Err(mirror_e) => {
+                    if is_checksum_mismatch(&mirror_e) {
+                        self.metadata_checksum_mismatches.fetch_add(1, Ordering::Relaxed);
+                    }
+                    warn!("mirror failed as well: {}", mirror_e);
+                }
+            }
+        }
+
+        match self.last_good_metadata.lock().unwrap().get(path) {
+            Some(data) => {
+                warn!("serving last good copy of {}", path);
+                Ok(data.clone())
+            }
+            None => Err(e),
+        }
+    }
+
+    fn remember_good_metadata(&self, path: &str, data: Bytes) -> Bytes {
+        self.last_good_metadata.lock().unwrap().insert(path.to_string(), data.clone());
+        data
+    }
+
+    /// Produces the artifact level 'maven-metadata.xml' from the metadata store, merged with the
+    ///  upstream repository's, so that clients can resolve LATEST and RELEASE. Returns None if
+    ///  neither versions nor plugins are known.
+    pub async fn get_artifact_metadata_xml(&self, metadata_path: &ArtifactMetadataPath, version_list: &VersionListOptions) -> anyhow::Result<Option<String>> {
+        let upstream = self.get_upstream_artifact_metadata(&metadata_path.group_id, &metadata_path.artifact_id).await;
+        render_local_artifact_metadata(self.metadata_store.as_ref(), metadata_path, upstream, version_list).await
+    }
+
+    /// The most recent timestamped build of a snapshot version for each classifier and extension,
+    ///  merged from local artifacts and the upstream version level 'maven-metadata.xml'.
+    ///  'version' is unqualified, e.g. '1.0-SNAPSHOT'.
+    pub async fn get_snapshot_versions(&self, group_id: &MavenGroupId, artifact_id: &MavenArtifactId, version: &str) -> anyhow::Result<Vec<SnapshotVersion>> {
+        let mut candidates = local_snapshot_versions(self.metadata_store.as_ref(), group_id, artifact_id, version).await?;
+
+        if !self.offline {
+            let metadata_path = format!("{}/{}/{}/maven-metadata.xml", group_id.0.replace('.', "/"), artifact_id.0, version);
+            match self.get_upstream_metadata_file(&metadata_path).await {
+                Ok(xml) => candidates.extend(parse_snapshot_versions(&String::from_utf8_lossy(&xml))),
+                Err(e) => warn!("failed to fetch upstream metadata for '{}': {}", metadata_path, e),
+            }
+        }
+
+        Ok(SnapshotVersion::latest_per_file(candidates, version))
+    }
+
+    /// Produces the version level 'maven-metadata.xml' for a snapshot version, see
+    ///  [RemoteMavenRepo::get_snapshot_versions]. Returns None if there is no known build.
+    pub async fn get_snapshot_metadata(&self, group_id: &MavenGroupId, artifact_id: &MavenArtifactId, version: &str) -> anyhow::Result<Option<String>> {
+        let snapshot_versions = self.get_snapshot_versions(group_id, artifact_id, version).await?;
+        if snapshot_versions.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(render_snapshot_metadata(&group_id.0, &artifact_id.0, version, &snapshot_versions)))
+    }
+}
+
+/// Fetches a metadata file and its '.sha1' file, failing with a
+///  [ChecksumMismatchError](crate::util::checksum_file::ChecksumMismatchError) if they disagree.
+///  Not all repositories publish checksums for metadata, so a missing checksum file is accepted.
+async fn fetch_verified_metadata(downloader: &ValidatingHttpDownloader, path: &str) -> anyhow::Result<Bytes> {
+    let data = downloader.get_bounded(path, MAX_METADATA_SIZE).await?;
+    match downloader.get_bounded(&format!("{}.sha1", path), MAX_CHECKSUM_FILE_SIZE).await {
+        Ok(sha1_file) => verify_sha1_file(path, &data, &String::from_utf8_lossy(&sha1_file))?,
+        Err(e) => debug!("no checksum for upstream metadata {}: {}", path, e),
+    }
+    Ok(data)
+}
+
+/// The '<snapshotVersion>' entries for locally available builds of a snapshot version
+pub async fn local_snapshot_versions<M: RemoteRepoMetadataStore + ?Sized>(metadata_store: &M, group_id: &MavenGroupId, artifact_id: &MavenArtifactId, version: &str) -> anyhow::Result<Vec<SnapshotVersion>> {
+    Ok(metadata_store.get_local_artifact_details().await?
+        .into_iter()
+        .filter(|(artifact_ref, _, _)| {
+            let coordinates = &artifact_ref.coordinates;
+            &coordinates.group_id == group_id
+                && &coordinates.artifact_id == artifact_id
+                && coordinates.version.unqualified() == version
+                // checksums and signatures are not listed separately
+                && !artifact_ref.file_extension.trim_start_matches('.').contains('.')
+        })
+        .filter_map(|(artifact_ref, _, provenance)| SnapshotVersion::for_artifact(&artifact_ref, format_maven_timestamp(provenance.last_modified)))
+        .collect())
+}
+
+/// Renders the artifact level 'maven-metadata.xml' for versions and plugins from the metadata
+///  store, merged with 'upstream' metadata. Returns None if neither versions nor the
+///  corresponding group's plugins are known.
+pub async fn render_local_artifact_metadata<M: RemoteRepoMetadataStore + ?Sized>(metadata_store: &M, metadata_path: &ArtifactMetadataPath, upstream: UpstreamMetadata, version_list: &VersionListOptions) -> anyhow::Result<Option<String>> {
+    let local = metadata_store.get_artifact_metadata(&metadata_path.group_id, &metadata_path.artifact_id).await?;
+    let metadata = merge_artifact_metadata(local, upstream.artifact)
+        .map(|metadata| MavenArtifactMetadata {
+            versions: version_list.apply(&metadata.versions).versions,
+            ..metadata
+        });
+    let plugins = merge_plugins(metadata_store.get_plugins(&metadata_path.plugin_group_id()).await?, &upstream.plugins);
+
+    if metadata.is_none() && plugins.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(render_artifact_metadata(&metadata_path.group_id.0, &metadata_path.artifact_id.0, metadata.as_ref(), &plugins)))
+}
+
+/// Merges the versions of both, taking 'latest' and 'release' from the more recently updated one
+///  (falling back to the other one's release if it has none)
+fn merge_artifact_metadata(local: Option<MavenArtifactMetadata>, upstream: Option<MavenArtifactMetadata>) -> Option<MavenArtifactMetadata> {
+    let (local, upstream) = match (local, upstream) {
+        (None, None) => return None,
+        (Some(m), None) | (None, Some(m)) => return Some(m),
+        (Some(local), Some(upstream)) => (local, upstream),
+    };
+
+    let (newer, older) = match local.last_updated >= upstream.last_updated {
+        true => (local, upstream),
+        false => (upstream, local),
+    };
+
+    let mut versions = newer.versions;
+    for version in older.versions {
+        if !versions.iter().any(|v| v.unqualified() == version.unqualified()) {
+            versions.push(version);
+        }
+    }
+
+    Some(MavenArtifactMetadata {
+        latest_version: newer.latest_version,
+        release_version: newer.release_version.or(older.release_version),
+        versions,
+        last_updated: newer.last_updated,
+    })
 }
 
 // https://maven.apache.org/ref/3.9.5/maven-repository-metadata/repository-metadata.html
@@ -134,7 +1178,7 @@ pub struct MavenArtifactMetadata {
     /// 'What the last version added to the directory is, including both releases and snapshots'
     pub latest_version: MavenVersion,
     /// 'What the last version added to the directory is, for the releases only'
-    pub release_version: MavenVersion,
+    pub release_version: Option<MavenVersion>,
     /// 'Versions available of the artifact (both releases and snapshots)'
     pub versions: Vec<MavenVersion>,
     /// 'When the metadata was last updated. The timestamp is expressed using UTC in the format yyyyMMddHHmmss.
@@ -143,12 +1187,21 @@ pub struct MavenArtifactMetadata {
 
 
 
+/// An upstream 'maven-metadata.xml' that is artifact level or group level metadata (or both, see
+///  [ArtifactMetadataPath])
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct UpstreamMetadata {
+    /// None if upstream does not know the artifact
+    pub artifact: Option<MavenArtifactMetadata>,
+    pub plugins: Vec<MavenPluginMetadata>,
+}
+
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct MavenGroupMetadata {
     pub plugins: Vec<MavenPluginMetadata>,
 }
 
-#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub struct MavenPluginMetadata {
     /// 'Display name for the plugin'
     pub name: String,
@@ -158,20 +1211,126 @@ pub struct MavenPluginMetadata {
     pub artifact_id: MavenArtifactId,
 }
 
+/// Overrides how long artifacts in groups starting with a given prefix are cached before they
+///  are fetched from upstream again, e.g. for an internal snapshot group that needs near-real-time
+///  updates. Artifacts without an applicable override are cached indefinitely.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct TtlOverride {
+    /// e.g. 'com.example.internal', see [MavenGroupId::is_in]. If several overrides apply, the
+    ///  longest prefix wins.
+    pub group_prefix: String,
+    pub ttl_secs: u64,
+}
+
+/// Where a locally available artifact came from
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ArtifactProvenance {
+    /// when the artifact was downloaded
+    pub fetched: SystemTime,
+    /// upstream 'Last-Modified' timestamp, or the time of download if there was none
+    pub last_modified: SystemTime,
+    /// upstream response headers on the configured allowlist, as (name, value)
+    pub upstream_headers: Vec<(String, String)>,
+}
+
+/// An artifact's versions, each with its 'last updated' timestamp
+pub type VersionTimestamps = Vec<(MavenVersion, String)>;
+
+/// A metadata store's complete content at a point in time, e.g. for creating a copy or a backup
+#[derive(Clone, Eq, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct RepoMetadataSnapshot {
+    pub artifacts: Vec<(MavenArtifactRef, Uuid, ArtifactProvenance)>,
+    pub plugins: Vec<(MavenGroupId, MavenPluginMetadata)>,
+    pub artifact_versions: Vec<(MavenGroupId, MavenArtifactId, VersionTimestamps)>,
+    pub ttl_overrides: Vec<TtlOverride>,
+}
+
+/// 'directory_path' with a trailing '/', except for the root directory
+pub fn directory_prefix(directory_path: &str) -> String {
+    let mut prefix = directory_path.to_string();
+    if !prefix.is_empty() && !prefix.ends_with('/') {
+        prefix.push('/');
+    }
+    prefix
+}
+
+/// The locally available children of a directory, see [directory_prefix]
+pub async fn local_directory_listing(metadata_store: &dyn RemoteRepoMetadataStore, prefix: &str) -> anyhow::Result<DirectoryListing> {
+    let index = ArtifactIndex::new(metadata_store.get_local_artifacts().await?);
+    Ok(index.directory_listing(prefix))
+}
 
 pub enum GetArtifactDecision {
-    Local(Uuid),
+    Local {
+        blob_key: Uuid,
+        provenance: ArtifactProvenance,
+    },
     Download,
-    Fail, // failed to download from remote recently, wait before retry
+    /// failed to download from remote recently, wait before retry
+    Fail {
+        retry_at: SystemTime,
+        kind: DownloadFailureKind,
+    },
 }
 
 #[async_trait]
 pub trait RemoteRepoMetadataStore: Send + Sync {
     async fn decide_get_artifact(&self, artifact_ref: &MavenArtifactRef) -> anyhow::Result<GetArtifactDecision>;
 
-    async fn register_artifact(&self, artifact_ref: &MavenArtifactRef, blob_key: &Uuid) -> anyhow::Result<()>;
+    async fn register_artifact(&self, artifact_ref: &MavenArtifactRef, blob_key: &Uuid, provenance: &ArtifactProvenance) -> anyhow::Result<ChangeKind>;
+
+    /// Remembers a failed download according to the store's [NegativeCachePolicy], which may
+    ///  ignore failures of some kinds
+    async fn register_failed_download(&self, artifact_ref: &MavenArtifactRef, kind: DownloadFailureKind) -> anyhow::Result<()>;
+
+    /// the number of remembered failed downloads
+    async fn failed_download_count(&self) -> anyhow::Result<usize>;
+
+    /// Forgets a failed download, returning true if one was remembered
+    async fn forget_failed_download(&self, artifact_ref: &MavenArtifactRef) -> anyhow::Result<bool>;
+
+    /// Forgets the failed downloads of all artifacts in a group and its subgroups, returning
+    ///  their number
+    async fn forget_failed_downloads(&self, group_prefix: &str) -> anyhow::Result<usize>;
+
+    /// Removes a locally available artifact, returning the blob it referred to. The artifact's
+    ///  version remains in the artifact metadata.
+    async fn unregister_artifact(&self, artifact_ref: &MavenArtifactRef) -> anyhow::Result<Option<Uuid>>;
+
+    /// all artifacts that are available locally
+    async fn get_local_artifacts(&self) -> anyhow::Result<Vec<MavenArtifactRef>>;
+
+    /// all artifacts that are available locally, with their blob keys and provenance
+    async fn get_local_artifact_details(&self) -> anyhow::Result<Vec<(MavenArtifactRef, Uuid, ArtifactProvenance)>>;
 
-    async fn register_failed_download(&self, artifact_ref: &MavenArtifactRef) -> anyhow::Result<()>;
+    /// checks if any artifact refers to the given blob, including quarantined artifacts
+    async fn is_blob_referenced(&self, blob_key: &Uuid) -> anyhow::Result<bool>;
+
+    /// Keeps a download that failed validation, see [QuarantinedArtifact], replacing a previously
+    ///  quarantined download of the same artifact
+    async fn quarantine_artifact(&self, _quarantined: QuarantinedArtifact) -> anyhow::Result<QuarantineOutcome> {
+        Ok(QuarantineOutcome::NotKept)
+    }
+
+    async fn get_quarantined_artifacts(&self) -> anyhow::Result<Vec<QuarantinedArtifact>> {
+        Ok(vec![])
+    }
+
+    /// Forgets a quarantined artifact, returning it if there was one with the given blob
+    async fn remove_quarantined_artifact(&self, _blob_key: &Uuid) -> anyhow::Result<Option<QuarantinedArtifact>> {
+        Ok(None)
+    }
+
+    /// [ProjectInfo] that was cached for an artifact, see [RemoteRepoMetadataStore::cache_project_info]
+    async fn get_project_info(&self, _artifact_ref: &MavenArtifactRef) -> anyhow::Result<Option<ProjectInfo>> {
+        Ok(None)
+    }
+
+    /// Caches an artifact's [ProjectInfo] until the artifact or its version's POM is registered
+    ///  again or unregistered. Stores that do not cache it ignore it.
+    async fn cache_project_info(&self, _artifact_ref: &MavenArtifactRef, _project_info: ProjectInfo) -> anyhow::Result<()> {
+        Ok(())
+    }
 
     async fn register_plugin(&self, group_id: MavenGroupId, plugin_metadata: MavenPluginMetadata) -> anyhow::Result<ChangeKind>;
     async fn unregister_plugin(&self, group_id: &MavenGroupId, artifact_id: &MavenArtifactId) -> anyhow::Result<bool>;
@@ -179,80 +1338,312 @@ pub trait RemoteRepoMetadataStore: Send + Sync {
 
     async fn get_artifact_metadata(&self, group_id: &MavenGroupId, artifact_id: &MavenArtifactId) -> anyhow::Result<Option<MavenArtifactMetadata>>;
 
+    /// Like [RemoteRepoMetadataStore::get_artifact_metadata] for several artifacts, in the order of
+    ///  the given keys. All results come from the same consistent state of the store, in a single
+    ///  round trip for stores backed by a database.
+    async fn get_artifact_metadata_many(&self, artifacts: &[(MavenGroupId, MavenArtifactId)]) -> anyhow::Result<Vec<Option<MavenArtifactMetadata>>>;
+
+    /// Like [RemoteRepoMetadataStore::get_artifact_metadata_many], but as it was at a given point
+    ///  in time, e.g. for auditing how dependencies were resolved back then. This requires an
+    ///  append-only history of version changes, e.g. a history table for stores backed by a
+    ///  database.
+    async fn get_artifact_metadata_as_of(&self, artifacts: &[(MavenGroupId, MavenArtifactId)], as_of: SystemTime) -> anyhow::Result<Vec<Option<MavenArtifactMetadata>>>;
+
+    async fn set_ttl_override(&self, ttl_override: TtlOverride) -> anyhow::Result<ChangeKind>;
+    async fn remove_ttl_override(&self, group_prefix: &str) -> anyhow::Result<bool>;
+    async fn get_ttl_overrides(&self) -> anyhow::Result<Vec<TtlOverride>>;
+
+    /// a consistent copy of the store's content, not including transient state like failed
+    ///  downloads
+    async fn snapshot(&self) -> anyhow::Result<RepoMetadataSnapshot>;
+
+    /// replaces the store's entire content, discarding transient state
+    async fn restore(&self, snapshot: RepoMetadataSnapshot) -> anyhow::Result<()>;
+
     //TODO add / update artifact metadata
 }
 
 
 
 pub struct DummyRemoteRepoMetadataStore {
-    local_artifacts: RwLock<HashMap<MavenArtifactRef, Uuid>>,
-    failed_downloads: RwLock<HashMap<MavenArtifactRef, Instant>>,
+    local_artifacts: RwLock<HashMap<MavenArtifactRef, (Uuid, ArtifactProvenance)>>,
+    /// keyed canonically so that equivalent paths share an entry
+    failed_downloads: NegativeCache,
     plugins: RwLock<HashMap<MavenGroupId, HashMap<MavenArtifactId, MavenPluginMetadata>>>,
+    plugin_prefix_policy: PluginPrefixPolicy,
     artifact_versions: RwLock<HashMap<MavenGroupId, HashMap<MavenArtifactId, Vec<(MavenVersion, String)>>>>,
+    /// append-only: every change to `artifact_versions`, for answering 'as of' queries
+    version_history: RwLock<HashMap<MavenGroupId, HashMap<MavenArtifactId, VersionTimestamps>>>,
+    ttl_overrides: RwLock<Vec<TtlOverride>>,
+    quarantined: RwLock<Vec<QuarantinedArtifact>>,
+    project_info: RwLock<HashMap<MavenArtifactRef, ProjectInfo>>,
 }
 
 impl DummyRemoteRepoMetadataStore {
     pub fn new() -> DummyRemoteRepoMetadataStore {
         DummyRemoteRepoMetadataStore {
             local_artifacts: Default::default(),
-            failed_downloads: Default::default(),
+            failed_downloads: NegativeCache::new(Default::default()),
             plugins: Default::default(),
+            plugin_prefix_policy: Default::default(),
             artifact_versions: Default::default(),
+            version_history: Default::default(),
+            ttl_overrides: Default::default(),
+            quarantined: Default::default(),
+            project_info: Default::default(),
+        }
+    }
+
+    pub fn with_negative_cache_policy(self, policy: NegativeCachePolicy) -> DummyRemoteRepoMetadataStore {
+        DummyRemoteRepoMetadataStore {
+            failed_downloads: NegativeCache::new(policy),
+            ..self
+        }
+    }
+
+    pub fn with_plugin_prefix_policy(self, plugin_prefix_policy: PluginPrefixPolicy) -> DummyRemoteRepoMetadataStore {
+        DummyRemoteRepoMetadataStore {
+            plugin_prefix_policy,
+            ..self
+        }
+    }
+
+    pub fn from_snapshot(snapshot: RepoMetadataSnapshot) -> DummyRemoteRepoMetadataStore {
+        let mut plugins: HashMap<MavenGroupId, HashMap<MavenArtifactId, MavenPluginMetadata>> = HashMap::new();
+        for (group_id, plugin_metadata) in snapshot.plugins {
+            plugins.entry(group_id).or_default().insert(plugin_metadata.artifact_id.clone(), plugin_metadata);
+        }
+        let mut artifact_versions: HashMap<MavenGroupId, HashMap<MavenArtifactId, VersionTimestamps>> = HashMap::new();
+        for (group_id, artifact_id, versions) in snapshot.artifact_versions {
+            artifact_versions.entry(group_id).or_default().insert(artifact_id, versions);
+        }
+
+        DummyRemoteRepoMetadataStore {
+            local_artifacts: RwLock::new(snapshot.artifacts.into_iter()
+                .map(|(artifact_ref, blob_key, provenance)| (artifact_ref, (blob_key, provenance)))
+                .collect()),
+            failed_downloads: NegativeCache::new(Default::default()),
+            plugins: RwLock::new(plugins),
+            plugin_prefix_policy: Default::default(),
+            version_history: RwLock::new(artifact_versions.clone()),
+            artifact_versions: RwLock::new(artifact_versions),
+            ttl_overrides: RwLock::new(snapshot.ttl_overrides),
+            quarantined: Default::default(),
+            project_info: Default::default(),
         }
     }
+
+    /// project info depends on the version's POM, so any change to the version's artifacts
+    ///  invalidates it
+    fn forget_project_info(&self, coordinates: &MavenCoordinates) {
+        self.project_info.write().unwrap().retain(|artifact_ref, _| &artifact_ref.coordinates != coordinates);
+    }
+
+    fn ttl_for(&self, group_id: &MavenGroupId) -> Option<Duration> {
+        self.ttl_overrides.read().unwrap()
+            .iter()
+            .filter(|o| group_id.is_in(&o.group_prefix))
+            .max_by_key(|o| o.group_prefix.len())
+            .map(|o| Duration::from_secs(o.ttl_secs))
+    }
+
+    /// Adds a version to the artifact's version list. There is one entry per snapshot version,
+    ///  referring to the most recent timestamped build that was registered.
+    fn register_version(&self, coordinates: &MavenCoordinates, registered_at: SystemTime) {
+        let timestamp = format_maven_timestamp(registered_at);
+
+        let mut artifact_versions = self.artifact_versions.write().unwrap();
+        let versions = artifact_versions.entry(coordinates.group_id.clone())
+            .or_default()
+            .entry(coordinates.artifact_id.clone())
+            .or_default();
+
+        let is_change = match versions.iter_mut().find(|(v, _)| v.unqualified() == coordinates.version.unqualified()) {
+            Some(existing) if existing.0 == coordinates.version => false,
+            Some(existing) => {
+                *existing = (coordinates.version.clone(), timestamp.clone());
+                true
+            }
+            None => {
+                versions.push((coordinates.version.clone(), timestamp.clone()));
+                true
+            }
+        };
+        if is_change {
+            self.version_history.write().unwrap()
+                .entry(coordinates.group_id.clone())
+                .or_default()
+                .entry(coordinates.artifact_id.clone())
+                .or_default()
+                .push((coordinates.version.clone(), timestamp));
+        }
+    }
+
+    fn artifact_metadata(artifact_versions: &HashMap<MavenGroupId, HashMap<MavenArtifactId, VersionTimestamps>>, group_id: &MavenGroupId, artifact_id: &MavenArtifactId) -> Option<MavenArtifactMetadata> {
+        Self::metadata_of(artifact_versions.get(group_id)?.get(artifact_id)?)
+    }
+
+    /// Replays the history up to a point in time, 'as_of' being a Maven timestamp
+    fn artifact_metadata_as_of(version_history: &HashMap<MavenGroupId, HashMap<MavenArtifactId, VersionTimestamps>>, group_id: &MavenGroupId, artifact_id: &MavenArtifactId, as_of: &str) -> Option<MavenArtifactMetadata> {
+        let mut versions: VersionTimestamps = Vec::new();
+        for (version, timestamp) in version_history.get(group_id)?.get(artifact_id)? {
+            if timestamp.as_str() > as_of {
+                continue;
+            }
+            match versions.iter_mut().find(|(v, _)| v.unqualified() == version.unqualified()) {
+                Some(existing) if &existing.1 > timestamp => {}
+                Some(existing) => *existing = (version.clone(), timestamp.clone()),
+                None => versions.push((version.clone(), timestamp.clone())),
+            }
+        }
+        Self::metadata_of(&versions)
+    }
+
+    fn metadata_of(versions: &[(MavenVersion, String)]) -> Option<MavenArtifactMetadata> {
+        let (latest_version, last_updated) = versions.iter()
+            .max_by_key(|(_, timestamp)| timestamp)
+            .map(|(version, timestamp)| (version.clone(), timestamp.clone()))?;
+
+        let release_version = versions.iter()
+            .filter(|(version, _)| matches!(version, MavenVersion::Release(_)))
+            .max_by_key(|(_, timestamp)| timestamp)
+            .map(|(version, _)| version.clone());
+
+        let versions = versions.iter()
+            .map(|(version, _)| version.clone())
+            .collect();
+
+        Some(MavenArtifactMetadata {
+            latest_version,
+            release_version,
+            versions,
+            last_updated,
+        })
+    }
 }
 
 #[async_trait]
 impl RemoteRepoMetadataStore for DummyRemoteRepoMetadataStore {
     async fn decide_get_artifact(&self, artifact_ref: &MavenArtifactRef) -> anyhow::Result<GetArtifactDecision> {
-        if let Some(key) = self.local_artifacts.read().unwrap().get(artifact_ref) {
-            Ok(GetArtifactDecision::Local(key.clone()))
-        }
-        else if let Some(download_failure) = self.failed_downloads.read().unwrap().get(artifact_ref) {
-            let now = Instant::now();
-
-            // configurable retry interval
-            if 300 < now.checked_duration_since(download_failure.clone()).unwrap_or(Duration::from_secs(0)).as_secs() {
-                self.failed_downloads.write().unwrap().remove(artifact_ref);
+        let local = self.local_artifacts.read().unwrap().get(artifact_ref).cloned();
+        if let Some((blob_key, provenance)) = local {
+            let is_expired = match self.ttl_for(&artifact_ref.coordinates.group_id) {
+                Some(ttl) => provenance.fetched.elapsed().map(|age| age > ttl).unwrap_or(false),
+                None => false,
+            };
+            if is_expired {
                 Ok(GetArtifactDecision::Download)
             }
             else {
-                Ok(GetArtifactDecision::Fail)
+                Ok(GetArtifactDecision::Local { blob_key, provenance })
             }
         }
+        else if let Some((kind, retry_at)) = self.failed_downloads.get(&ArtifactKey::for_artifact(artifact_ref)) {
+            Ok(GetArtifactDecision::Fail { retry_at, kind })
+        }
         else {
             Ok(GetArtifactDecision::Download)
         }
     }
 
-    async fn register_artifact(&self, artifact_ref: &MavenArtifactRef, blob_key: &Uuid) -> anyhow::Result<()> {
+    async fn register_artifact(&self, artifact_ref: &MavenArtifactRef, blob_key: &Uuid, provenance: &ArtifactProvenance) -> anyhow::Result<ChangeKind> {
         //TODO clean up if the artifact was previously registered
-        self.local_artifacts.write().unwrap().insert(artifact_ref.clone(), blob_key.clone());
+        let new_value = (*blob_key, provenance.clone());
+        let change_kind = match self.local_artifacts.write().unwrap().insert(artifact_ref.clone(), new_value.clone()) {
+            None => ChangeKind::Inserted,
+            Some(prev) if prev == new_value => ChangeKind::Unchanged,
+            Some(_) => ChangeKind::Updated,
+        };
+        if change_kind.is_change() {
+            self.register_version(&artifact_ref.coordinates, SystemTime::now());
+            self.forget_project_info(&artifact_ref.coordinates);
+        }
+        Ok(change_kind)
+    }
+
+    async fn register_failed_download(&self, artifact_ref: &MavenArtifactRef, kind: DownloadFailureKind) -> anyhow::Result<()> {
+        self.failed_downloads.insert(ArtifactKey::for_artifact(artifact_ref), kind);
         Ok(())
     }
 
-    async fn register_failed_download(&self, artifact_ref: &MavenArtifactRef) -> anyhow::Result<()> {
-        self.failed_downloads.write().unwrap().insert(artifact_ref.clone(), Instant::now());
+    async fn forget_failed_download(&self, artifact_ref: &MavenArtifactRef) -> anyhow::Result<bool> {
+        Ok(self.failed_downloads.remove(&ArtifactKey::for_artifact(artifact_ref)))
+    }
+
+    async fn forget_failed_downloads(&self, group_prefix: &str) -> anyhow::Result<usize> {
+        Ok(self.failed_downloads.remove_group(group_prefix))
+    }
+
+    async fn failed_download_count(&self) -> anyhow::Result<usize> {
+        Ok(self.failed_downloads.len())
+    }
+
+    async fn unregister_artifact(&self, artifact_ref: &MavenArtifactRef) -> anyhow::Result<Option<Uuid>> {
+        self.forget_project_info(&artifact_ref.coordinates);
+        Ok(self.local_artifacts.write().unwrap()
+            .remove(artifact_ref)
+            .map(|(blob_key, _)| blob_key))
+    }
+
+    async fn get_local_artifacts(&self) -> anyhow::Result<Vec<MavenArtifactRef>> {
+        Ok(self.local_artifacts.read().unwrap()
+            .keys()
+            .cloned()
+            .collect())
+    }
+
+    async fn get_local_artifact_details(&self) -> anyhow::Result<Vec<(MavenArtifactRef, Uuid, ArtifactProvenance)>> {
+        Ok(self.local_artifacts.read().unwrap()
+            .iter()
+            .map(|(artifact_ref, (blob_key, provenance))| (artifact_ref.clone(), *blob_key, provenance.clone()))
+            .collect())
+    }
+
+    async fn is_blob_referenced(&self, blob_key: &Uuid) -> anyhow::Result<bool> {
+        let is_cached = self.local_artifacts.read().unwrap()
+            .values()
+            .any(|(k, _)| k == blob_key);
+        Ok(is_cached || self.quarantined.read().unwrap()
+            .iter()
+            .any(|quarantined| &quarantined.blob_key == blob_key))
+    }
+
+    async fn quarantine_artifact(&self, quarantined: QuarantinedArtifact) -> anyhow::Result<QuarantineOutcome> {
+        let mut all = self.quarantined.write().unwrap();
+        let replaced = match all.iter_mut().find(|q| q.artifact_ref == quarantined.artifact_ref) {
+            Some(existing) => Some(std::mem::replace(existing, quarantined).blob_key),
+            None => {
+                all.push(quarantined);
+                None
+            }
+        };
+        Ok(QuarantineOutcome::Kept(replaced))
+    }
+
+    async fn get_quarantined_artifacts(&self) -> anyhow::Result<Vec<QuarantinedArtifact>> {
+        Ok(self.quarantined.read().unwrap().clone())
+    }
+
+    async fn remove_quarantined_artifact(&self, blob_key: &Uuid) -> anyhow::Result<Option<QuarantinedArtifact>> {
+        let mut quarantined = self.quarantined.write().unwrap();
+        Ok(quarantined.iter()
+            .position(|q| &q.blob_key == blob_key)
+            .map(|idx| quarantined.remove(idx)))
+    }
+
+    async fn get_project_info(&self, artifact_ref: &MavenArtifactRef) -> anyhow::Result<Option<ProjectInfo>> {
+        Ok(self.project_info.read().unwrap().get(artifact_ref).cloned())
+    }
+
+    async fn cache_project_info(&self, artifact_ref: &MavenArtifactRef, project_info: ProjectInfo) -> anyhow::Result<()> {
+        self.project_info.write().unwrap().insert(artifact_ref.clone(), project_info);
         Ok(())
     }
 
     async fn register_plugin(&self, group_id: MavenGroupId, plugin_metadata: MavenPluginMetadata) -> anyhow::Result<ChangeKind> {
         let mut plugins = self.plugins.write().unwrap();
-        match plugins.entry(group_id) {
-            Entry::Occupied(mut e) => {
-                let prev = e.get_mut().insert(plugin_metadata.artifact_id.clone(), plugin_metadata);
-                if prev.is_some() {
-                    Ok(ChangeKind::Updated)
-                }
-                else {
-                    Ok(ChangeKind::Inserted)
-                }
-            }
-            Entry::Vacant(e) => {
-                e.insert([(plugin_metadata.artifact_id.clone(), plugin_metadata)].into());
-                Ok(ChangeKind::Inserted)
-            }
-        }
+        let group_plugins = plugins.entry(group_id.clone()).or_default();
+        register_with_unique_prefix(&group_id, group_plugins, plugin_metadata, self.plugin_prefix_policy)
     }
 
     async fn unregister_plugin(&self, group_id: &MavenGroupId, artifact_id: &MavenArtifactId) -> anyhow::Result<bool> {
@@ -280,41 +1671,477 @@ impl RemoteRepoMetadataStore for DummyRemoteRepoMetadataStore {
     }
 
     async fn get_artifact_metadata(&self, group_id: &MavenGroupId, artifact_id: &MavenArtifactId) -> anyhow::Result<Option<MavenArtifactMetadata>> {
-        match self.artifact_versions.read().unwrap().get(group_id) {
-            None => Ok(None),
-            Some(artifacts) => {
-                match artifacts.get(artifact_id) {
-                    None => Ok(None),
-                    Some(versions) => {
-                        if versions.is_empty() {
-                            return Ok(None);
-                        }
-
-                        let (latest_version, last_updated) = versions.iter()
-                            .max_by_key(|(_, timestamp)| timestamp)
-                            .map(|(version, timestamp)| (version.clone(), timestamp.clone()))
-                            .unwrap();
-
-                        let release_version = versions.iter()
-                            .filter(|(version, _)| matches!(version, MavenVersion::Release(_)))
-                            .max_by_key(|(_, timestamp)| timestamp)
-                            .map(|(version, _)| version.clone())
-                            .unwrap();
-
-                        let versions = versions.iter()
-                            .map(|(version, _)| version.clone())
-                            .collect();
-
-                        Ok(Some(MavenArtifactMetadata {
-                            latest_version,
-                            release_version,
-                            versions,
-                            last_updated,
-                        }))
+        let artifact_versions = self.artifact_versions.read().unwrap();
+        Ok(Self::artifact_metadata(&artifact_versions, group_id, artifact_id))
+    }
+
+    async fn get_artifact_metadata_many(&self, artifacts: &[(MavenGroupId, MavenArtifactId)]) -> anyhow::Result<Vec<Option<MavenArtifactMetadata>>> {
+        let artifact_versions = self.artifact_versions.read().unwrap();
+        Ok(artifacts.iter()
+            .map(|(group_id, artifact_id)| Self::artifact_metadata(&artifact_versions, group_id, artifact_id))
+            .collect())
+    }
+
+    async fn get_artifact_metadata_as_of(&self, artifacts: &[(MavenGroupId, MavenArtifactId)], as_of: SystemTime) -> anyhow::Result<Vec<Option<MavenArtifactMetadata>>> {
+        let as_of = format_maven_timestamp(as_of);
+        let version_history = self.version_history.read().unwrap();
+        Ok(artifacts.iter()
+            .map(|(group_id, artifact_id)| Self::artifact_metadata_as_of(&version_history, group_id, artifact_id, &as_of))
+            .collect())
+    }
+
+    async fn set_ttl_override(&self, ttl_override: TtlOverride) -> anyhow::Result<ChangeKind> {
+        let mut ttl_overrides = self.ttl_overrides.write().unwrap();
+        match ttl_overrides.iter_mut().find(|o| o.group_prefix == ttl_override.group_prefix) {
+            Some(existing) if *existing == ttl_override => Ok(ChangeKind::Unchanged),
+            Some(existing) => {
+                *existing = ttl_override;
+                Ok(ChangeKind::Updated)
+            }
+            None => {
+                ttl_overrides.push(ttl_override);
+                Ok(ChangeKind::Inserted)
+            }
+        }
+    }
+
+    async fn remove_ttl_override(&self, group_prefix: &str) -> anyhow::Result<bool> {
+        let mut ttl_overrides = self.ttl_overrides.write().unwrap();
+        let len_before = ttl_overrides.len();
+        ttl_overrides.retain(|o| o.group_prefix != group_prefix);
+        Ok(ttl_overrides.len() != len_before)
+    }
+
+    async fn get_ttl_overrides(&self) -> anyhow::Result<Vec<TtlOverride>> {
+        Ok(self.ttl_overrides.read().unwrap().clone())
+    }
+
+    async fn snapshot(&self) -> anyhow::Result<RepoMetadataSnapshot> {
+        // holding all locks at the same time for consistency
+        let local_artifacts = self.local_artifacts.read().unwrap();
+        let plugins = self.plugins.read().unwrap();
+        let artifact_versions = self.artifact_versions.read().unwrap();
+        let ttl_overrides = self.ttl_overrides.read().unwrap();
+
+        Ok(RepoMetadataSnapshot {
+            artifacts: local_artifacts.iter()
+                .map(|(artifact_ref, (blob_key, provenance))| (artifact_ref.clone(), *blob_key, provenance.clone()))
+                .collect(),
+            plugins: plugins.iter()
+                .flat_map(|(group_id, by_artifact)| by_artifact.values().map(|p| (group_id.clone(), p.clone())))
+                .collect(),
+            artifact_versions: artifact_versions.iter()
+                .flat_map(|(group_id, by_artifact)| by_artifact.iter().map(|(artifact_id, versions)| (group_id.clone(), artifact_id.clone(), versions.clone())))
+                .collect(),
+            ttl_overrides: ttl_overrides.clone(),
+        })
+    }
+
+    async fn restore(&self, snapshot: RepoMetadataSnapshot) -> anyhow::Result<()> {
+        let restored = DummyRemoteRepoMetadataStore::from_snapshot(snapshot);
+
+        // holding all locks at the same time so that readers never see a partial restore
+        let mut local_artifacts = self.local_artifacts.write().unwrap();
+        let mut plugins = self.plugins.write().unwrap();
+        let mut artifact_versions = self.artifact_versions.write().unwrap();
+        let mut version_history = self.version_history.write().unwrap();
+        let mut ttl_overrides = self.ttl_overrides.write().unwrap();
+
+        // the history is kept, and restored versions are added to it with their original timestamps
+        for (group_id, by_artifact) in restored.version_history.into_inner().unwrap() {
+            for (artifact_id, restored_history) in by_artifact {
+                let history = version_history.entry(group_id.clone()).or_default().entry(artifact_id).or_default();
+                for entry in restored_history {
+                    if !history.contains(&entry) {
+                        history.push(entry);
+                    }
+                }
+            }
+        }
+        *local_artifacts = restored.local_artifacts.into_inner().unwrap();
+        // failed downloads are transient state that is not part of a snapshot, and so is cached
+        //  project info
+        self.failed_downloads.clear();
+        self.project_info.write().unwrap().clear();
+        *plugins = restored.plugins.into_inner().unwrap();
+        *artifact_versions = restored.artifact_versions.into_inner().unwrap();
+        *ttl_overrides = restored.ttl_overrides.into_inner().unwrap();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::ops::Range;
+
+    use futures::StreamExt;
+    use hyper::{Body, Request, Response, Server, StatusCode};
+    use hyper::service::{make_service_fn, service_fn};
+    use rstest::rstest;
 
+    use crate::blob::transient_blob_storage::TransientBlobStorage;
+    use crate::maven::access_times::AccessTimeSource;
+    use crate::util::pgp::parse_public_keys;
+    use crate::util::pgp::test_keys::{ED25519_KEY, ED25519_SIGNATURE};
+    use crate::util::prometheus_metrics::{CACHE_REQUESTS, UPSTREAM_FAILURES};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_get_artifact_metadata_many() {
+        let store = DummyRemoteRepoMetadataStore::from_snapshot(RepoMetadataSnapshot {
+            artifact_versions: vec![(
+                MavenGroupId("org.a".to_string()),
+                MavenArtifactId("a".to_string()),
+                vec![
+                    (MavenVersion::Release("1.0".to_string()), "20230101000000".to_string()),
+                    (MavenVersion::Release("1.1".to_string()), "20230201000000".to_string()),
+                ],
+            )],
+            ..Default::default()
+        });
+
+        let artifacts = vec![
+            (MavenGroupId("org.b".to_string()), MavenArtifactId("b".to_string())),
+            (MavenGroupId("org.a".to_string()), MavenArtifactId("a".to_string())),
+        ];
+        let metadata = store.get_artifact_metadata_many(&artifacts).await.unwrap();
+        assert_eq!(metadata.len(), 2);
+        assert!(metadata[0].is_none());
+        assert_eq!(metadata[1].as_ref().unwrap().latest_version, MavenVersion::Release("1.1".to_string()));
+        assert_eq!(metadata[1], store.get_artifact_metadata(&artifacts[1].0, &artifacts[1].1).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_get_artifact_metadata_as_of() {
+        let store = DummyRemoteRepoMetadataStore::from_snapshot(RepoMetadataSnapshot {
+            artifact_versions: vec![(
+                MavenGroupId("org.a".to_string()),
+                MavenArtifactId("a".to_string()),
+                vec![
+                    (MavenVersion::Release("1.0".to_string()), "20230101000000".to_string()),
+                    (MavenVersion::Release("1.1".to_string()), "20230201000000".to_string()),
+                ],
+            )],
+            ..Default::default()
+        });
+        let artifacts = vec![(MavenGroupId("org.a".to_string()), MavenArtifactId("a".to_string()))];
+        let as_of = |s: &str| crate::maven::timestamps::parse_maven_timestamp(s).unwrap();
+
+        assert_eq!(store.get_artifact_metadata_as_of(&artifacts, as_of("20221231000000")).await.unwrap(), vec![None]);
+
+        let metadata = store.get_artifact_metadata_as_of(&artifacts, as_of("20230115000000")).await.unwrap();
+        let metadata = metadata[0].as_ref().unwrap();
+        assert_eq!(metadata.versions, vec![MavenVersion::Release("1.0".to_string())]);
+        assert_eq!(metadata.last_updated, "20230101000000");
+
+        // restoring an older state does not rewrite history
+        store.restore(RepoMetadataSnapshot::default()).await.unwrap();
+        assert!(store.get_artifact_metadata(&artifacts[0].0, &artifacts[0].1).await.unwrap().is_none());
+        let metadata = store.get_artifact_metadata_as_of(&artifacts, as_of("20230301000000")).await.unwrap();
+        assert_eq!(metadata[0].as_ref().unwrap().versions.len(), 2);
+    }
+
+    #[rstest]
+    #[case::no_override(vec![], false)]
+    #[case::group(vec![("com.acme.lib", 3600)], false)]
+    #[case::group_expired(vec![("com.acme.lib", 60)], true)]
+    #[case::parent_group_expired(vec![("com.acme", 60)], true)]
+    #[case::all_groups_expired(vec![("", 60)], true)]
+    #[case::longest_prefix_wins(vec![("com.acme", 60), ("com.acme.lib", 3600)], false)]
+    #[case::longest_prefix_wins_reversed(vec![("com.acme.lib", 60), ("com.acme", 3600)], true)]
+    #[case::prefix_boundary(vec![("com.ac", 60)], false)]
+    #[case::sibling_group(vec![("com.acme.lib2", 60)], false)]
+    #[case::longer_segment_ignored(vec![("com.acme", 3600), ("com.acme.li", 60)], false)]
+    #[tokio::test]
+    async fn test_decide_get_artifact_ttl(#[case] overrides: Vec<(&str, u64)>, #[case] expect_expired: bool) {
+        let store = DummyRemoteRepoMetadataStore::new();
+        let artifact_ref = crate::maven::paths::parse_maven_path("com/acme/lib/core/1.0/core-1.0.jar").unwrap();
+        let fetched = SystemTime::now() - Duration::from_secs(600);
+        let provenance = ArtifactProvenance {
+            fetched,
+            last_modified: fetched,
+            upstream_headers: vec![],
+        };
+        store.register_artifact(&artifact_ref, &Uuid::from_u128(1), &provenance).await.unwrap();
+        for (group_prefix, ttl_secs) in overrides {
+            store.set_ttl_override(TtlOverride { group_prefix: group_prefix.to_string(), ttl_secs }).await.unwrap();
+        }
+
+        match store.decide_get_artifact(&artifact_ref).await.unwrap() {
+            GetArtifactDecision::Download => assert!(expect_expired),
+            GetArtifactDecision::Local { blob_key, .. } => {
+                assert!(!expect_expired);
+                assert_eq!(blob_key, Uuid::from_u128(1));
+            }
+            GetArtifactDecision::Fail { .. } => panic!("unexpected decision"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_evict() {
+        let store = Arc::new(DummyRemoteRepoMetadataStore::new());
+        let repo = RemoteMavenRepo::new("http://127.0.0.1:1".to_string(), Arc::new(TransientBlobStorage::new()), store.clone()).unwrap();
+        let artifact = |path: &str| crate::maven::paths::parse_maven_path(path).unwrap();
+        let provenance = ArtifactProvenance {
+            fetched: SystemTime::now(),
+            last_modified: SystemTime::now(),
+            upstream_headers: vec![],
+        };
+        let blob_key = Uuid::from_u128(1);
+        for path in ["com/acme/a/1.0/a-1.0.jar", "com/acme/sub/b/1.0/b-1.0.jar", "com/acmex/c/1.0/c-1.0.jar"] {
+            store.register_artifact(&artifact(path), &blob_key, &provenance).await.unwrap();
+        }
+        store.register_failed_download(&artifact("com/acme/a/1.1/a-1.1.jar"), DownloadFailureKind::NotFound).await.unwrap();
+
+        let eviction = repo.evict_group("com.acme").await.unwrap();
+        let mut evicted = eviction.artifacts.iter().map(|(a, _)| as_maven_path(a)).collect::<Vec<_>>();
+        evicted.sort();
+        assert_eq!(evicted, vec!["com/acme/a/1.0/a-1.0.jar", "com/acme/sub/b/1.0/b-1.0.jar"]);
+        assert_eq!(eviction.failed_downloads, 1);
+        assert_eq!(store.get_local_artifacts().await.unwrap(), vec![artifact("com/acmex/c/1.0/c-1.0.jar")]);
+        assert!(repo.evict_group("com.acme").await.unwrap().is_empty());
+
+        let eviction = repo.evict_artifact(&artifact("com/acmex/c/1.0/c-1.0.jar")).await.unwrap();
+        assert_eq!(eviction.artifacts, vec![(artifact("com/acmex/c/1.0/c-1.0.jar"), blob_key)]);
+        assert!(!repo.is_blob_referenced(&blob_key).await.unwrap());
+    }
+
+    /// serves 'body' for 'path', and 404 for everything else
+    fn serve_upstream(path: &'static str, body: &'static str) -> String {
+        serve_upstream_files(vec![(path, body)])
+    }
+
+    /// serves each (path, body), and 404 for everything else
+    fn serve_upstream_files(files: Vec<(&'static str, &'static str)>) -> String {
+        let files = Arc::new(files);
+        let make_service = make_service_fn(move |_| {
+            let files = files.clone();
+            async move {
+                Ok::<_, hyper::Error>(service_fn(move |request: Request<Body>| {
+                    let body = files.iter()
+                        .find(|(path, _)| *path == request.uri().path())
+                        .map(|(_, body)| *body);
+                    async move {
+                        Ok::<_, hyper::Error>(match body {
+                            Some(body) => Response::new(Body::from(body)),
+                            None => Response::builder().status(StatusCode::NOT_FOUND).body(Body::empty()).unwrap(),
+                        })
                     }
+                }))
+            }
+        });
+        let server = Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(make_service);
+        let uri = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+        uri
+    }
+
+    #[tokio::test]
+    async fn test_upstream_group_metadata() {
+        let upstream = serve_upstream("/org/example/plugins/maven-metadata.xml", r#"<metadata>
+  <plugins>
+    <plugin><name>Clean</name><prefix>clean</prefix><artifactId>maven-clean-plugin</artifactId></plugin>
+    <plugin><name>Compiler</name><prefix>compiler</prefix><artifactId>maven-compiler-plugin</artifactId></plugin>
+  </plugins>
+</metadata>"#);
+        let repo = RemoteMavenRepo::new(upstream, Arc::new(TransientBlobStorage::new()), Arc::new(DummyRemoteRepoMetadataStore::new())).unwrap();
+        repo.register_plugin(MavenGroupId("org.example.plugins".to_string()), MavenPluginMetadata {
+            name: "Our Clean".to_string(),
+            prefix: "clean".to_string(),
+            artifact_id: MavenArtifactId("our-clean-plugin".to_string()),
+        }).await.unwrap();
+
+        let metadata_path = crate::maven::paths::parse_artifact_metadata_path("org/example/plugins/maven-metadata.xml").unwrap();
+        let xml = repo.get_artifact_metadata_xml(&metadata_path, &Default::default()).await.unwrap().unwrap();
+        assert_eq!(parse_plugins(&xml).iter().map(|p| p.artifact_id.0.as_str()).collect::<Vec<_>>(), vec!["our-clean-plugin", "maven-compiler-plugin"]);
+        assert!(!xml.contains("<versioning>"));
+
+        // unknown upstream and locally
+        let metadata_path = crate::maven::paths::parse_artifact_metadata_path("org/example/other/maven-metadata.xml").unwrap();
+        assert_eq!(repo.get_artifact_metadata_xml(&metadata_path, &Default::default()).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_cache_metrics() {
+        let upstream = serve_upstream("/com/acme/a/1.0/a-1.0.jar", "jar");
+        let upstream_label = upstream.trim_start_matches("http://").to_string();
+        let repo = RemoteMavenRepo::new(upstream, Arc::new(TransientBlobStorage::new()), Arc::new(DummyRemoteRepoMetadataStore::new())).unwrap()
+            .with_name("test-cache-metrics");
+        let requests = |result: &str| CACHE_REQUESTS.with_label_values(&["test-cache-metrics", result]).get();
+
+        let artifact_ref = crate::maven::paths::parse_maven_path("com/acme/a/1.0/a-1.0.jar").unwrap();
+        for _ in 0..2 {
+            let mut blob = repo.get_artifact(&artifact_ref).await.unwrap();
+            while blob.data.next().await.is_some() {}
+        }
+        assert_eq!((requests("miss"), requests("hit")), (1, 1));
+
+        let missing = crate::maven::paths::parse_maven_path("com/acme/a/1.1/a-1.1.jar").unwrap();
+        assert!(repo.get_artifact(&missing).await.is_err());
+        assert_eq!(requests("miss"), 2);
+        assert_eq!(UPSTREAM_FAILURES.with_label_values(&[&upstream_label, "not_found"]).get(), 1);
+        assert_eq!(repo.failed_download_count().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_backfill_access_times() {
+        let upstream = serve_upstream("/com/acme/a/1.0/a-1.0.jar", "jar");
+        let blob_storage = Arc::new(TransientBlobStorage::new());
+        let metadata_store = Arc::new(DummyRemoteRepoMetadataStore::new());
+        let artifact_ref = crate::maven::paths::parse_maven_path("com/acme/a/1.0/a-1.0.jar").unwrap();
+
+        let repo = RemoteMavenRepo::new(upstream.clone(), blob_storage.clone(), metadata_store.clone()).unwrap();
+        repo.get_artifact(&artifact_ref).await.unwrap();
+        assert_eq!(repo.last_access(&artifact_ref).unwrap().source, AccessTimeSource::Tracked);
+
+        // a restart after an upgrade, with artifacts that were cached before access times were tracked
+        let repo = RemoteMavenRepo::new(upstream, blob_storage, metadata_store).unwrap();
+        assert_eq!(repo.last_access(&artifact_ref), None);
+        assert_eq!(repo.backfill_access_times().await.unwrap(), 1);
+        let backfilled = repo.last_access(&artifact_ref).unwrap();
+        assert_eq!(backfilled.source, AccessTimeSource::Backfilled);
+        assert!(backfilled.at <= SystemTime::now());
+        assert_eq!(repo.backfill_access_times().await.unwrap(), 0);
+
+        repo.get_artifact(&artifact_ref).await.unwrap();
+        assert_eq!(repo.last_access(&artifact_ref).unwrap().source, AccessTimeSource::Tracked);
+    }
+
+    #[rstest]
+    #[case(SignaturePolicy::Require, vec!["com/acme/a/1.0/a-1.0.jar", "com/acme/a/1.0/a-1.0.jar.asc"])]
+    #[case(SignaturePolicy::VerifyIfPresent, vec!["com/acme/a/1.0/a-1.0.jar", "com/acme/a/1.0/a-1.0.jar.asc", "com/acme/c/1.0/c-1.0.jar"])]
+    #[case(SignaturePolicy::Ignore, vec!["com/acme/a/1.0/a-1.0.jar", "com/acme/a/1.0/a-1.0.jar.asc", "com/acme/b/1.0/b-1.0.jar", "com/acme/c/1.0/c-1.0.jar"])]
+    #[tokio::test]
+    async fn test_signature_verification(#[case] signature_policy: SignaturePolicy, #[case] expected_cached: Vec<&str>) {
+        let upstream = serve_upstream_files(vec![
+            ("/com/acme/a/1.0/a-1.0.jar", "jar"),
+            ("/com/acme/a/1.0/a-1.0.jar.asc", ED25519_SIGNATURE),
+            // modified after signing
+            ("/com/acme/b/1.0/b-1.0.jar", "jaR"),
+            ("/com/acme/b/1.0/b-1.0.jar.asc", ED25519_SIGNATURE),
+            // not signed
+            ("/com/acme/c/1.0/c-1.0.jar", "jar"),
+        ]);
+        let keyring = Arc::new(Keyring::new());
+        keyring.add_keys(parse_public_keys(ED25519_KEY.as_bytes()).unwrap());
+        let metadata_store = Arc::new(DummyRemoteRepoMetadataStore::new());
+        let repo = RemoteMavenRepo::new(upstream, Arc::new(TransientBlobStorage::new()), metadata_store.clone()).unwrap()
+            .with_signature_verification(signature_policy, keyring);
+
+        for path in ["com/acme/a/1.0/a-1.0.jar", "com/acme/a/1.0/a-1.0.jar.asc", "com/acme/b/1.0/b-1.0.jar", "com/acme/c/1.0/c-1.0.jar"] {
+            let artifact_ref = crate::maven::paths::parse_maven_path(path).unwrap();
+            match repo.get_artifact(&artifact_ref).await {
+                Ok(_) => assert!(expected_cached.contains(&path)),
+                Err(e) => {
+                    assert!(!expected_cached.contains(&path));
+                    assert!(matches!(RepoError::of(&e), Some(RepoError::InvalidSignature(_))), "{}", e);
+                }
+            }
+        }
+        let mut cached = metadata_store.get_local_artifacts().await.unwrap().iter()
+            .map(as_maven_path)
+            .collect::<Vec<_>>();
+        cached.sort();
+        assert_eq!(cached, expected_cached);
+
+        let quarantined = repo.get_quarantined_artifacts().await.unwrap().iter()
+            .map(|quarantined| as_maven_path(&quarantined.artifact_ref))
+            .collect::<Vec<_>>();
+        match signature_policy {
+            SignaturePolicy::Ignore => assert!(quarantined.is_empty()),
+            _ => assert_eq!(quarantined, vec!["com/acme/b/1.0/b-1.0.jar"]),
+        }
+    }
+
+    #[rstest]
+    #[case::from_to(ByteRange::FromTo(2, 4), Some(2..5), "cde")]
+    #[case::unbounded_end(ByteRange::FromTo(2, u64::MAX), Some(2..9), "cdefghi")]
+    #[case::suffix(ByteRange::Suffix(3), Some(6..9), "ghi")]
+    #[case::unsatisfiable(ByteRange::From(9), None, "")]
+    #[tokio::test]
+    async fn test_get_artifact_range(#[case] range: ByteRange, #[case] expected_range: Option<Range<u64>>, #[case] expected_data: &str) {
+        let upstream = serve_upstream("/com/acme/a/1.0/a-1.0.jar", "abcdefghi");
+        let repo = Arc::new(RemoteMavenRepo::new(upstream, Arc::new(TransientBlobStorage::new()), Arc::new(DummyRemoteRepoMetadataStore::new())).unwrap()
+            .with_checksum_file_policy(ChecksumFilePolicy::VerifyIfPresent));
+        let artifact_ref = crate::maven::paths::parse_maven_path("com/acme/a/1.0/a-1.0.jar").unwrap();
+
+        match repo.get_artifact_range(&artifact_ref, &range).await.unwrap() {
+            BlobRange::Partial { mut blob, range, total_size } => {
+                assert_eq!(Some(range), expected_range);
+                assert_eq!(total_size, 9);
+                let mut data = vec![];
+                while let Some(chunk) = blob.data.next().await {
+                    data.extend_from_slice(&chunk.unwrap());
                 }
+                assert_eq!(data, expected_data.as_bytes());
+            }
+            BlobRange::Unsatisfiable { total_size } => {
+                assert_eq!(expected_range, None);
+                assert_eq!(total_size, 9);
             }
         }
     }
+
+    #[tokio::test]
+    async fn test_quarantine() {
+        let upstream = serve_upstream_files(vec![
+            ("/com/acme/a/1.0/a-1.0.jar", "jar"),
+            ("/com/acme/a/1.0/a-1.0.jar.sha1", "0123456789012345678901234567890123456789"),
+        ]);
+        let blob_storage = Arc::new(TransientBlobStorage::new());
+        let repo = RemoteMavenRepo::new(upstream, blob_storage.clone(), Arc::new(DummyRemoteRepoMetadataStore::new())).unwrap()
+            .with_checksum_file_policy(ChecksumFilePolicy::VerifyIfPresent);
+        let artifact_ref = crate::maven::paths::parse_maven_path("com/acme/a/1.0/a-1.0.jar").unwrap();
+        assert_eq!(repo.artifact_status(&artifact_ref).await.unwrap(), None);
+
+        // every request downloads the artifact again rather than remembering the failure
+        let mut blob_keys = vec![];
+        for _ in 0..2 {
+            let e = repo.get_artifact(&artifact_ref).await.err().unwrap();
+            assert!(matches!(RepoError::of(&e), Some(RepoError::ChecksumMismatch(_))), "{}", e);
+            assert!(is_quarantined(&e));
+            blob_keys.push(e.downcast_ref::<QuarantinedDownload>().unwrap().blob_key);
+        }
+        assert_eq!(repo.failed_download_count().await.unwrap(), 0);
+        assert_eq!(repo.artifact_status(&artifact_ref).await.unwrap(), Some(ArtifactStatus::Quarantined));
+
+        // only the latest download is kept
+        let quarantined = repo.get_quarantined_artifacts().await.unwrap();
+        assert_eq!(quarantined.len(), 1);
+        assert_eq!(quarantined[0].blob_key, blob_keys[1]);
+        assert!(blob_storage.get(&blob_keys[0]).await.unwrap().is_none());
+        assert!(!repo.is_blob_referenced(&blob_keys[0]).await.unwrap());
+        let blob_key = quarantined[0].blob_key;
+        assert_eq!(quarantined[0].artifact_ref, artifact_ref);
+        assert!(repo.is_blob_referenced(&blob_key).await.unwrap());
+        let mut blob = blob_storage.get(&blob_key).await.unwrap().unwrap();
+        assert_eq!(blob.data.next().await.unwrap().unwrap(), Bytes::from_static(b"jar"));
+
+        assert_eq!(repo.remove_quarantined_artifact(&blob_key).await.unwrap(), Some(quarantined[0].clone()));
+        assert!(!repo.is_blob_referenced(&blob_key).await.unwrap());
+        assert_eq!(repo.remove_quarantined_artifact(&blob_key).await.unwrap(), None);
+    }
+
+    #[cfg(feature = "fs-storage")]
+    #[tokio::test]
+    async fn test_rebuild_metadata_from_blob_storage() {
+        let upstream = serve_upstream("/com/acme/a/1.0/a-1.0.jar", "jar");
+        let root = std::env::temp_dir().join(format!("arti-vault-test-{}", Uuid::new_v4().as_hyphenated()));
+        let blob_storage = Arc::new(crate::blob::fs_blob_storage::FsBlobStorage::new(root.clone()));
+        let artifact_ref = crate::maven::paths::parse_maven_path("com/acme/a/1.0/a-1.0.jar").unwrap();
+
+        let repo = RemoteMavenRepo::new(upstream.clone(), blob_storage.clone(), Arc::new(DummyRemoteRepoMetadataStore::new())).unwrap();
+        let mut blob = repo.get_artifact(&artifact_ref).await.unwrap();
+        while blob.data.next().await.is_some() {}
+
+        // a restart with the same storage, but without metadata
+        let repo = RemoteMavenRepo::new(upstream, blob_storage, Arc::new(DummyRemoteRepoMetadataStore::new())).unwrap();
+        assert_eq!(repo.rebuild_metadata_from_blob_storage().await.unwrap(), 1);
+        assert_eq!(repo.metadata_store.get_local_artifacts().await.unwrap(), vec![artifact_ref]);
+        // metadata is not rebuilt twice
+        assert_eq!(repo.rebuild_metadata_from_blob_storage().await.unwrap(), 0);
+
+        let _ = tokio::fs::remove_dir_all(&root).await;
+    }
 }
@@ -0,0 +1,134 @@
+use std::collections::{BTreeSet, HashMap};
+use std::time::{Duration, SystemTime};
+
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::maven::coordinates::{MavenArtifactRef, MavenVersion};
+use crate::maven::remote_repo::RemoteRepoMetadataStore;
+use crate::maven::timestamps::parse_maven_timestamp;
+
+/// Which timestamped builds of a snapshot version are kept when old builds are purged: a build is
+///  kept if it is one of the `keep_builds` newest builds of its version, or if it is younger than
+///  `max_age_days`. Nothing is purged if neither is set.
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default, Deserialize)]
+pub struct SnapshotRetentionPolicy {
+    #[serde(default)]
+    pub keep_builds: Option<usize>,
+    #[serde(default)]
+    pub max_age_days: Option<u64>,
+}
+impl SnapshotRetentionPolicy {
+    pub fn is_enabled(&self) -> bool {
+        self.keep_builds.is_some() || self.max_age_days.is_some()
+    }
+
+    /// 'rank' is the number of newer builds of the same version
+    fn keeps(&self, rank: usize, age: Duration) -> bool {
+        if !self.is_enabled() {
+            return true;
+        }
+        self.keep_builds.map(|n| rank < n).unwrap_or(false)
+            || self.max_age_days.map(|days| age <= Duration::from_secs(days * 86400)).unwrap_or(false)
+    }
+}
+
+/// A build's files share its timestamp and build number, e.g. its pom, jar and sources jar
+type BuildId = (String, Option<u32>);
+
+/// Selects the artifacts of builds the policy does not keep. A build's age is taken from its
+///  timestamp, or from when it was stored if the timestamp can not be parsed.
+pub fn builds_to_purge(artifacts: &[(MavenArtifactRef, SystemTime)], policy: &SnapshotRetentionPolicy, now: SystemTime) -> Vec<MavenArtifactRef> {
+    let mut builds_by_version = HashMap::new();
+    for (artifact_ref, stored) in artifacts {
+        if let MavenVersion::Snapshot { version, timestamp, build_number } = &artifact_ref.coordinates.version {
+            let version_key = (&artifact_ref.coordinates.group_id, &artifact_ref.coordinates.artifact_id, version);
+            let built = parse_maven_timestamp(&timestamp.replace('.', "")).unwrap_or(*stored);
+            builds_by_version.entry(version_key)
+                .or_insert_with(BTreeSet::new)
+                .insert(((timestamp.clone(), *build_number), built));
+        }
+    }
+
+    let mut purged_builds = HashMap::new();
+    for (version_key, builds) in builds_by_version {
+        let purged = builds.into_iter()
+            .rev()
+            .enumerate()
+            .filter(|(rank, (_, built))| !policy.keeps(*rank, now.duration_since(*built).unwrap_or_default()))
+            .map(|(_, (build_id, _))| build_id)
+            .collect::<BTreeSet<BuildId>>();
+        purged_builds.insert(version_key, purged);
+    }
+
+    artifacts.iter()
+        .map(|(artifact_ref, _)| artifact_ref)
+        .filter(|artifact_ref| match &artifact_ref.coordinates.version {
+            MavenVersion::Snapshot { version, timestamp, build_number } => purged_builds
+                .get(&(&artifact_ref.coordinates.group_id, &artifact_ref.coordinates.artifact_id, version))
+                .map(|purged| purged.contains(&(timestamp.clone(), *build_number)))
+                .unwrap_or(false),
+            MavenVersion::Release(_) => false,
+        })
+        .cloned()
+        .collect()
+}
+
+/// Unregisters the artifacts of builds the policy does not keep, returning them with their blobs.
+///  The snapshot metadata is generated from the registered builds, so it no longer lists them.
+pub async fn purge_snapshot_builds(metadata_store: &dyn RemoteRepoMetadataStore, policy: &SnapshotRetentionPolicy) -> anyhow::Result<Vec<(MavenArtifactRef, Uuid)>> {
+    if !policy.is_enabled() {
+        return Ok(vec![]);
+    }
+
+    let artifacts = metadata_store.get_local_artifact_details().await?
+        .into_iter()
+        .map(|(artifact_ref, _, provenance)| (artifact_ref, provenance.fetched))
+        .collect::<Vec<_>>();
+
+    let mut result = Vec::new();
+    for artifact_ref in builds_to_purge(&artifacts, policy, SystemTime::now()) {
+        if let Some(blob_key) = metadata_store.unregister_artifact(&artifact_ref).await? {
+            result.push((artifact_ref, blob_key));
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use crate::maven::paths::{as_maven_path, parse_maven_path};
+
+    use super::*;
+
+    const NOW: &str = "20240301000000";
+
+    fn artifacts() -> Vec<(MavenArtifactRef, SystemTime)> {
+        [
+            "com/example/lib/1.0-SNAPSHOT/lib-1.0-SNAPSHOT-20240101.120000-1.pom",
+            "com/example/lib/1.0-SNAPSHOT/lib-1.0-SNAPSHOT-20240101.120000-1.jar",
+            "com/example/lib/1.0-SNAPSHOT/lib-1.0-SNAPSHOT-20240201.120000-2.pom",
+            "com/example/lib/1.0-SNAPSHOT/lib-1.0-SNAPSHOT-20240225.120000-3.pom",
+            "com/example/lib/2.0-SNAPSHOT/lib-2.0-SNAPSHOT-20240101.120000-1.pom",
+            "com/example/lib/1.0/lib-1.0.pom",
+        ].iter()
+            .map(|path| (parse_maven_path(path).unwrap(), SystemTime::UNIX_EPOCH))
+            .collect()
+    }
+
+    #[rstest]
+    #[case::disabled(None, None, &[])]
+    #[case::keep_builds(Some(1), None, &["lib-1.0-SNAPSHOT-20240101.120000-1.pom", "lib-1.0-SNAPSHOT-20240101.120000-1.jar", "lib-1.0-SNAPSHOT-20240201.120000-2.pom"])]
+    #[case::max_age(None, Some(10), &["lib-1.0-SNAPSHOT-20240101.120000-1.pom", "lib-1.0-SNAPSHOT-20240101.120000-1.jar", "lib-1.0-SNAPSHOT-20240201.120000-2.pom", "lib-2.0-SNAPSHOT-20240101.120000-1.pom"])]
+    #[case::either(Some(2), Some(40), &["lib-1.0-SNAPSHOT-20240101.120000-1.pom", "lib-1.0-SNAPSHOT-20240101.120000-1.jar"])]
+    fn test_builds_to_purge(#[case] keep_builds: Option<usize>, #[case] max_age_days: Option<u64>, #[case] expected: &[&str]) {
+        let policy = SnapshotRetentionPolicy { keep_builds, max_age_days };
+        let purged = builds_to_purge(&artifacts(), &policy, parse_maven_timestamp(NOW).unwrap());
+        let purged = purged.iter()
+            .map(|artifact_ref| as_maven_path(artifact_ref).rsplit('/').next().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(purged, expected);
+    }
+}
@@ -0,0 +1,93 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Formats a point in time the way maven-metadata.xml does, i.e. as 'yyyyMMddHHmmss' in UTC
+pub fn format_maven_timestamp(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    let secs_of_day = secs % 86400;
+
+    format!("{:04}{:02}{:02}{:02}{:02}{:02}",
+            year, month, day,
+            secs_of_day / 3600,
+            (secs_of_day / 60) % 60,
+            secs_of_day % 60,
+    )
+}
+
+/// Parses a timestamp in the format of [format_maven_timestamp], None if it is malformed
+pub fn parse_maven_timestamp(s: &str) -> Option<SystemTime> {
+    if s.len() != 14 || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let field = |range: std::ops::Range<usize>| s[range].parse::<u32>().ok();
+    let (year, month, day) = (field(0..4)? as i64, field(4..6)?, field(6..8)?);
+    let (hour, minute, second) = (field(8..10)?, field(10..12)?, field(12..14)?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+
+    let secs = days_from_civil(year, month, day) * 86400 + (hour * 3600 + minute * 60 + second) as i64;
+    Some(UNIX_EPOCH + Duration::from_secs(u64::try_from(secs).ok()?))
+}
+
+/// The inverse of [civil_from_days], see
+///  http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 } as i64;
+    let day_of_year = (153*mp + 2)/5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era/4 - year_of_era/100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Converts days since 1970-01-01 to (year, month, day), see
+///  http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era/1460 + day_of_era/36524 - day_of_era/146096) / 365;
+    let day_of_year = day_of_era - (365*year_of_era + year_of_era/4 - year_of_era/100);
+    let mp = (5*day_of_year + 2) / 153;
+    let day = (day_of_year - (153*mp + 2)/5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::*;
+    use super::*;
+
+    #[rstest]
+    #[case::epoch(0, "19700101000000")]
+    #[case::leap_day(951782400, "20000229000000")]
+    #[case::end_of_year(1704067199, "20231231235959")]
+    #[case::recent(1698316245, "20231026103045")]
+    fn test_format_maven_timestamp(#[case] secs_since_epoch: u64, #[case] expected: &str) {
+        assert_eq!(format_maven_timestamp(UNIX_EPOCH + Duration::from_secs(secs_since_epoch)), expected);
+    }
+
+    #[rstest]
+    #[case::epoch(0)]
+    #[case::leap_day(951782400)]
+    #[case::end_of_year(1704067199)]
+    #[case::recent(1698316245)]
+    fn test_parse_maven_timestamp(#[case] secs_since_epoch: u64) {
+        let time = UNIX_EPOCH + Duration::from_secs(secs_since_epoch);
+        assert_eq!(parse_maven_timestamp(&format_maven_timestamp(time)), Some(time));
+    }
+
+    #[rstest]
+    #[case("2023102610304")]
+    #[case("20231326103045")]
+    #[case("2023-10-26 10:30")]
+    fn test_parse_invalid_maven_timestamp(#[case] s: &str) {
+        assert_eq!(parse_maven_timestamp(s), None);
+    }
+}
@@ -0,0 +1,186 @@
+use std::sync::RwLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::maven::coordinates::{MavenArtifactId, MavenArtifactRef, MavenGroupId};
+use crate::util::change_kind::ChangeKind;
+
+/// A rule marking versions of an artifact as blocked, e.g. because of a known vulnerability
+///  (think log4j-core 2.14.x). Requests for blocked versions are rejected regardless of whether
+///  the artifact is available locally or upstream.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct VersionBlockingRule {
+    pub group_id: MavenGroupId,
+    pub artifact_id: MavenArtifactId,
+    /// Either an exact version string (e.g. '2.14.1'), or a prefix followed by a trailing '*'
+    ///  (e.g. '2.14.*'). A single '*' blocks all versions of the artifact.
+    ///
+    /// Snapshot versions are matched by their unqualified version string (e.g. '1.0-SNAPSHOT').
+    pub version_pattern: String,
+    /// Human readable explanation of the policy, returned to clients requesting a blocked version
+    pub message: String,
+}
+impl VersionBlockingRule {
+    pub fn matches(&self, artifact_ref: &MavenArtifactRef) -> bool {
+        matches_version_pattern(&self.group_id, &self.artifact_id, &self.version_pattern, artifact_ref)
+    }
+
+    fn has_same_target(&self, other: &VersionBlockingRule) -> bool {
+        self.group_id == other.group_id
+            && self.artifact_id == other.artifact_id
+            && self.version_pattern == other.version_pattern
+    }
+}
+
+/// Checks if an artifact ref has the given group and artifact id, and a version matching the
+///  given pattern. A pattern is either an exact version string, or a prefix followed by a trailing
+///  '*'. Snapshot versions are matched by their unqualified version string.
+pub fn matches_version_pattern(group_id: &MavenGroupId, artifact_id: &MavenArtifactId, version_pattern: &str, artifact_ref: &MavenArtifactRef) -> bool {
+    if group_id != &artifact_ref.coordinates.group_id || artifact_id != &artifact_ref.coordinates.artifact_id {
+        return false;
+    }
+
+    let version = artifact_ref.coordinates.version.unqualified();
+
+    match version_pattern.strip_suffix('*') {
+        Some(prefix) => version.starts_with(prefix),
+        None => version == version_pattern,
+    }
+}
+
+/// The administrator-maintained list of blocked versions, and the rules from the blocking rules
+///  file if there is one (see [BlockingRulesFileConfig](crate::maven::blocking_rules_file::BlockingRulesFileConfig)).
+#[derive(Default)]
+pub struct VersionBlockList {
+    rules: RwLock<Vec<VersionBlockingRule>>,
+    file_rules: RwLock<Vec<VersionBlockingRule>>,
+}
+impl VersionBlockList {
+    pub fn new() -> VersionBlockList {
+        Default::default()
+    }
+
+    /// Adds a rule, replacing an existing rule for the same group, artifact and version pattern
+    pub fn add_rule(&self, rule: VersionBlockingRule) -> ChangeKind {
+        let mut rules = self.rules.write().unwrap();
+        match rules.iter_mut().find(|r| r.has_same_target(&rule)) {
+            Some(existing) if *existing == rule => ChangeKind::Unchanged,
+            Some(existing) => {
+                *existing = rule;
+                ChangeKind::Updated
+            }
+            None => {
+                rules.push(rule);
+                ChangeKind::Inserted
+            }
+        }
+    }
+
+    /// Returns true iff a rule with the given target existed and was removed
+    pub fn remove_rule(&self, group_id: &MavenGroupId, artifact_id: &MavenArtifactId, version_pattern: &str) -> bool {
+        let mut rules = self.rules.write().unwrap();
+        let len_before = rules.len();
+        rules.retain(|r| !(&r.group_id == group_id && &r.artifact_id == artifact_id && r.version_pattern == version_pattern));
+        rules.len() != len_before
+    }
+
+    pub fn rules(&self) -> Vec<VersionBlockingRule> {
+        self.rules.read().unwrap().clone()
+    }
+
+    /// Replaces all rules from the blocking rules file at once, returning true iff they changed.
+    ///  Rules added through [VersionBlockList::add_rule] are not affected.
+    pub fn replace_file_rules(&self, rules: Vec<VersionBlockingRule>) -> bool {
+        let mut file_rules = self.file_rules.write().unwrap();
+        if *file_rules == rules {
+            return false;
+        }
+        *file_rules = rules;
+        true
+    }
+
+    pub fn file_rules(&self) -> Vec<VersionBlockingRule> {
+        self.file_rules.read().unwrap().clone()
+    }
+
+    /// Returns the first rule blocking the given artifact, if any - administrator-maintained rules
+    ///  take precedence over those from the blocking rules file
+    pub fn find_blocking_rule(&self, artifact_ref: &MavenArtifactRef) -> Option<VersionBlockingRule> {
+        let find = |rules: &RwLock<Vec<VersionBlockingRule>>| rules.read().unwrap()
+            .iter()
+            .find(|r| r.matches(artifact_ref))
+            .cloned();
+        find(&self.rules).or_else(|| find(&self.file_rules))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::*;
+
+    use crate::maven::coordinates::{MavenClassifier, MavenCoordinates, MavenVersion};
+
+    use super::*;
+
+    fn artifact_ref(group_id: &str, artifact_id: &str, version: MavenVersion) -> MavenArtifactRef {
+        MavenArtifactRef {
+            coordinates: MavenCoordinates {
+                group_id: MavenGroupId(group_id.to_string()),
+                artifact_id: MavenArtifactId(artifact_id.to_string()),
+                version,
+            },
+            classifier: MavenClassifier::Unclassified,
+            file_extension: ".jar".to_string(),
+        }
+    }
+
+    fn release(v: &str) -> MavenVersion {
+        MavenVersion::Release(v.to_string())
+    }
+
+    fn snapshot(v: &str) -> MavenVersion {
+        MavenVersion::Snapshot { version: v.to_string(), timestamp: "20231101.123456".to_string(), build_number: Some(1) }
+    }
+
+    #[rstest]
+    #[case::exact("2.14.1", "a.b", "c", release("2.14.1"), true)]
+    #[case::exact_other_version("2.14.1", "a.b", "c", release("2.14.10"), false)]
+    #[case::wildcard("2.14.*", "a.b", "c", release("2.14.10"), true)]
+    #[case::wildcard_other_version("2.14.*", "a.b", "c", release("2.15.0"), false)]
+    #[case::all_versions("*", "a.b", "c", release("1.0"), true)]
+    #[case::other_group("*", "a.x", "c", release("1.0"), false)]
+    #[case::other_artifact("*", "a.b", "x", release("1.0"), false)]
+    #[case::snapshot("1.0-SNAPSHOT", "a.b", "c", snapshot("1.0-SNAPSHOT"), true)]
+    #[case::snapshot_wildcard("1.*", "a.b", "c", snapshot("1.0-SNAPSHOT"), true)]
+    fn test_matches(#[case] version_pattern: &str, #[case] group_id: &str, #[case] artifact_id: &str, #[case] version: MavenVersion, #[case] expected: bool) {
+        let rule = VersionBlockingRule {
+            group_id: MavenGroupId("a.b".to_string()),
+            artifact_id: MavenArtifactId("c".to_string()),
+            version_pattern: version_pattern.to_string(),
+            message: "".to_string(),
+        };
+        assert_eq!(rule.matches(&artifact_ref(group_id, artifact_id, version)), expected);
+    }
+
+    #[test]
+    fn test_file_rules() {
+        let rule = |version_pattern: &str, message: &str| VersionBlockingRule {
+            group_id: MavenGroupId("a.b".to_string()),
+            artifact_id: MavenArtifactId("c".to_string()),
+            version_pattern: version_pattern.to_string(),
+            message: message.to_string(),
+        };
+        let block_list = VersionBlockList::new();
+        block_list.add_rule(rule("1.0", "admin"));
+        assert!(block_list.replace_file_rules(vec![rule("1.0", "file"), rule("2.*", "file")]));
+        assert!(!block_list.replace_file_rules(vec![rule("1.0", "file"), rule("2.*", "file")]));
+
+        let message = |version: &str| block_list.find_blocking_rule(&artifact_ref("a.b", "c", release(version))).map(|r| r.message);
+        assert_eq!(message("1.0").as_deref(), Some("admin"));
+        assert_eq!(message("2.1").as_deref(), Some("file"));
+
+        assert!(block_list.replace_file_rules(vec![]));
+        assert_eq!(message("2.1"), None);
+        assert_eq!(block_list.rules().len(), 1);
+    }
+}
@@ -0,0 +1,104 @@
+use serde::Deserialize;
+
+use crate::maven::coordinates::MavenVersion;
+use crate::maven::version_resolution::compare_versions;
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VersionOrder {
+    #[default]
+    Ascending,
+    /// newest first
+    Descending,
+}
+
+/// Trims the versions of an artifact for browsing-oriented consumers, since old artifacts can
+///  have thousands of versions. Maven itself never asks for this, so the defaults return all
+///  versions. Taken from the query string, e.g. '?latest_per_major=3&order=descending&limit=20'.
+#[derive(Clone, Eq, PartialEq, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct VersionListOptions {
+    /// keeps only the newest versions of each major version
+    pub latest_per_major: Option<usize>,
+    pub order: VersionOrder,
+    /// number of versions to skip, applied after filtering and sorting
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+/// A page of an artifact's versions, see [VersionListOptions::apply]
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct VersionPage {
+    pub versions: Vec<MavenVersion>,
+    /// number of versions after filtering, but before pagination
+    pub total: usize,
+}
+
+impl VersionListOptions {
+    /// Versions are sorted the way Maven compares them, with duplicates removed
+    pub fn apply(&self, versions: &[MavenVersion]) -> VersionPage {
+        let mut versions = versions.to_vec();
+        versions.sort_by(|a, b| compare_versions(a.unqualified(), b.unqualified()));
+        versions.dedup_by(|a, b| a.unqualified() == b.unqualified());
+
+        if let Some(n) = self.latest_per_major {
+            let mut num_newer_in_major = 0;
+            let mut kept = Vec::new();
+            for (i, version) in versions.iter().enumerate().rev() {
+                let is_newest_in_major = versions.get(i + 1)
+                    .map(|newer| major_of(newer.unqualified()) != major_of(version.unqualified()))
+                    .unwrap_or(true);
+                if is_newest_in_major {
+                    num_newer_in_major = 0;
+                }
+                if num_newer_in_major < n {
+                    kept.push(version.clone());
+                }
+                num_newer_in_major += 1;
+            }
+            kept.reverse();
+            versions = kept;
+        }
+
+        let total = versions.len();
+        if self.order == VersionOrder::Descending {
+            versions.reverse();
+        }
+        VersionPage {
+            versions: versions.into_iter()
+                .skip(self.offset)
+                .take(self.limit.unwrap_or(usize::MAX))
+                .collect(),
+            total,
+        }
+    }
+}
+
+/// the part before the first '.' or '-', e.g. '5' for '5.3.1' or '2023' for '2023-10'
+fn major_of(version: &str) -> &str {
+    version.split(['.', '-']).next().unwrap_or(version)
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn versions(versions: &[&str]) -> Vec<MavenVersion> {
+        versions.iter()
+            .map(|v| MavenVersion::Release(v.to_string()))
+            .collect()
+    }
+
+    #[rstest]
+    #[case::all(VersionListOptions::default(), &["1.0", "1.1", "1.2", "2.0", "2.1", "10.0"], 6)]
+    #[case::latest_per_major(VersionListOptions { latest_per_major: Some(2), ..Default::default() }, &["1.1", "1.2", "2.0", "2.1", "10.0"], 5)]
+    #[case::descending(VersionListOptions { order: VersionOrder::Descending, limit: Some(2), ..Default::default() }, &["10.0", "2.1"], 6)]
+    #[case::page(VersionListOptions { offset: 2, limit: Some(3), ..Default::default() }, &["1.2", "2.0", "2.1"], 6)]
+    #[case::combined(VersionListOptions { latest_per_major: Some(1), order: VersionOrder::Descending, offset: 1, limit: None }, &["2.1", "1.2"], 3)]
+    fn test_apply(#[case] options: VersionListOptions, #[case] expected: &[&str], #[case] total: usize) {
+        let page = options.apply(&versions(&["2.0", "1.0", "10.0", "1.2", "2.1", "1.1", "2.0"]));
+        assert_eq!(page, VersionPage { versions: versions(expected), total });
+    }
+}
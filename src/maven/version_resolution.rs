@@ -0,0 +1,392 @@
+use std::cmp::Ordering;
+
+use anyhow::anyhow;
+
+/// Compares two version strings the way Maven does (see Maven's 'ComparableVersion'): versions
+///  are split into numeric and alphabetic items at '.', '-' and transitions between digits and
+///  letters, and well-known qualifiers are ordered 'alpha' < 'beta' < 'milestone' < 'rc' <
+///  'snapshot' < (release) < 'sp'. Unknown qualifiers sort after these, alphabetically.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let a = parse_version(a);
+    let b = parse_version(b);
+    compare_lists(&a, &b)
+}
+
+pub fn is_snapshot(version: &str) -> bool {
+    version.ends_with("-SNAPSHOT")
+}
+
+#[derive(Debug, Clone)]
+enum Item {
+    /// decimal digits without leading zeros, so that arbitrarily large numbers can be compared
+    Int(String),
+    Str(String),
+    List(Vec<Item>),
+}
+
+const QUALIFIERS: [&str; 7] = ["alpha", "beta", "milestone", "rc", "snapshot", "", "sp"];
+
+impl Item {
+    fn is_null(&self) -> bool {
+        match self {
+            Item::Int(n) => n.is_empty(),
+            Item::Str(s) => s.is_empty(),
+            Item::List(l) => l.is_empty(),
+        }
+    }
+
+    fn int(digits: &str) -> Item {
+        Item::Int(digits.trim_start_matches('0').to_string())
+    }
+
+    fn str(s: &str, followed_by_digit: bool) -> Item {
+        let s = match s {
+            "a" if followed_by_digit => "alpha",
+            "b" if followed_by_digit => "beta",
+            "m" if followed_by_digit => "milestone",
+            "ga" | "final" | "release" => "",
+            "cr" => "rc",
+            s => s,
+        };
+        Item::Str(s.to_string())
+    }
+}
+
+fn qualifier_rank(qualifier: &str) -> String {
+    match QUALIFIERS.iter().position(|q| *q == qualifier) {
+        Some(idx) => idx.to_string(),
+        None => format!("{}-{}", QUALIFIERS.len(), qualifier),
+    }
+}
+
+fn compare_ints(a: &str, b: &str) -> Ordering {
+    a.len().cmp(&b.len())
+        .then_with(|| a.cmp(b))
+}
+
+/// compares an item to a missing item, e.g. the third item of '1.0' when comparing it to '1.0.1'
+fn compare_to_null(item: &Item) -> Ordering {
+    match item {
+        Item::Int(n) => if n.is_empty() { Ordering::Equal } else { Ordering::Greater },
+        Item::Str(s) => qualifier_rank(s).cmp(&qualifier_rank("")),
+        Item::List(l) => l.first().map(compare_to_null).unwrap_or(Ordering::Equal),
+    }
+}
+
+fn compare_items(a: &Item, b: &Item) -> Ordering {
+    match (a, b) {
+        (Item::Int(a), Item::Int(b)) => compare_ints(a, b),
+        (Item::Int(_), _) => Ordering::Greater,
+        (Item::Str(_), Item::Int(_)) => Ordering::Less,
+        (Item::Str(a), Item::Str(b)) => qualifier_rank(a).cmp(&qualifier_rank(b)),
+        (Item::Str(_), Item::List(_)) => Ordering::Less,
+        (Item::List(_), Item::Int(_)) => Ordering::Less,
+        (Item::List(_), Item::Str(_)) => Ordering::Greater,
+        (Item::List(a), Item::List(b)) => compare_lists(a, b),
+    }
+}
+
+fn compare_lists(a: &[Item], b: &[Item]) -> Ordering {
+    for idx in 0..a.len().max(b.len()) {
+        let result = match (a.get(idx), b.get(idx)) {
+            (Some(a), Some(b)) => compare_items(a, b),
+            (Some(a), None) => compare_to_null(a),
+            (None, Some(b)) => compare_to_null(b).reverse(),
+            (None, None) => Ordering::Equal,
+        };
+        if result != Ordering::Equal {
+            return result;
+        }
+    }
+    Ordering::Equal
+}
+
+/// removes null items at the end and before trailing sub lists, so that e.g. '1.0' and '1' as
+///  well as '1.0-1' and '1-1' are equal
+fn normalize(list: &mut Vec<Item>) {
+    for idx in (0..list.len()).rev() {
+        if list[idx].is_null() {
+            list.remove(idx);
+        }
+        else if !matches!(list[idx], Item::List(_)) {
+            break;
+        }
+    }
+}
+
+fn parse_version(version: &str) -> Vec<Item> {
+    let version = version.to_lowercase();
+
+    // nested lists are started by '-' and digit / letter transitions; they are kept as a stack
+    //  of item vectors and folded into their parents at the end
+    let mut stack: Vec<Vec<Item>> = vec![vec![]];
+    let mut start = 0;
+    let mut is_digit = false;
+
+    for (i, c) in version.char_indices() {
+        if c == '.' || c == '-' {
+            let item = if i == start {
+                Item::Int(String::new())
+            }
+            else if is_digit {
+                Item::int(&version[start..i])
+            }
+            else {
+                Item::str(&version[start..i], false)
+            };
+            stack.last_mut().unwrap().push(item);
+            start = i + 1;
+            if c == '-' {
+                stack.push(vec![]);
+            }
+        }
+        else if c.is_ascii_digit() {
+            if !is_digit && i > start {
+                // a qualifier followed by a digit, e.g. 'a1'
+                stack.last_mut().unwrap().push(Item::str(&version[start..i], true));
+                start = i;
+                stack.push(vec![]);
+            }
+            is_digit = true;
+        }
+        else {
+            if is_digit && i > start {
+                stack.last_mut().unwrap().push(Item::int(&version[start..i]));
+                start = i;
+                stack.push(vec![]);
+            }
+            is_digit = false;
+        }
+    }
+    if version.len() > start {
+        let item = if is_digit {
+            Item::int(&version[start..])
+        }
+        else {
+            Item::str(&version[start..], false)
+        };
+        stack.last_mut().unwrap().push(item);
+    }
+
+    while stack.len() > 1 {
+        let mut list = stack.pop().unwrap();
+        normalize(&mut list);
+        stack.last_mut().unwrap().push(Item::List(list));
+    }
+    let mut result = stack.pop().unwrap();
+    normalize(&mut result);
+    result
+}
+
+/// A bound of a [VersionRange]
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct VersionBound {
+    pub version: String,
+    pub inclusive: bool,
+}
+
+/// A version range like '[1.0,2.0)', with missing bounds meaning 'unbounded'
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct VersionRange {
+    pub lower: Option<VersionBound>,
+    pub upper: Option<VersionBound>,
+}
+impl VersionRange {
+    pub fn contains(&self, version: &str) -> bool {
+        let above_lower = match &self.lower {
+            None => true,
+            Some(bound) => match compare_versions(version, &bound.version) {
+                Ordering::Greater => true,
+                Ordering::Equal => bound.inclusive,
+                Ordering::Less => false,
+            },
+        };
+        let below_upper = match &self.upper {
+            None => true,
+            Some(bound) => match compare_versions(version, &bound.version) {
+                Ordering::Less => true,
+                Ordering::Equal => bound.inclusive,
+                Ordering::Greater => false,
+            },
+        };
+        above_lower && below_upper
+    }
+
+    fn parse(s: &str) -> anyhow::Result<VersionRange> {
+        let lower_inclusive = match s.chars().next() {
+            Some('[') => true,
+            Some('(') => false,
+            _ => return Err(anyhow!("version range must start with '[' or '(': {}", s)),
+        };
+        let upper_inclusive = match s.chars().last() {
+            Some(']') => true,
+            Some(')') => false,
+            _ => return Err(anyhow!("version range must end with ']' or ')': {}", s)),
+        };
+        let inner = &s[1..s.len()-1];
+
+        let bound = |version: &str, inclusive: bool| {
+            let version = version.trim();
+            if version.is_empty() {
+                None
+            }
+            else {
+                Some(VersionBound { version: version.to_string(), inclusive })
+            }
+        };
+
+        match inner.split_once(',') {
+            None => {
+                // '[1.0]' is an exact version
+                if !lower_inclusive || !upper_inclusive || inner.trim().is_empty() {
+                    return Err(anyhow!("a single version range must be of the form '[x]': {}", s));
+                }
+                Ok(VersionRange {
+                    lower: bound(inner, true),
+                    upper: bound(inner, true),
+                })
+            }
+            Some((lower, upper)) => {
+                let range = VersionRange {
+                    lower: bound(lower, lower_inclusive),
+                    upper: bound(upper, upper_inclusive),
+                };
+                if let (Some(lower), Some(upper)) = (&range.lower, &range.upper) {
+                    if compare_versions(&lower.version, &upper.version) == Ordering::Greater {
+                        return Err(anyhow!("lower bound is greater than upper bound: {}", s));
+                    }
+                }
+                Ok(range)
+            }
+        }
+    }
+}
+
+/// A dependency version constraint as it can appear in a POM, or one of the keywords 'LATEST'
+///  and 'RELEASE'
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum VersionConstraint {
+    /// a plain version like '1.0', selecting exactly that version
+    Soft(String),
+    /// one or more comma separated ranges like '(,1.0],[1.2,)', matching versions in any of them
+    Ranges(Vec<VersionRange>),
+    /// the highest version including snapshots
+    Latest,
+    /// the highest release version
+    Release,
+}
+impl VersionConstraint {
+    pub fn parse(s: &str) -> anyhow::Result<VersionConstraint> {
+        let s = s.trim();
+        match s {
+            "" => return Err(anyhow!("empty version constraint")),
+            "LATEST" => return Ok(VersionConstraint::Latest),
+            "RELEASE" => return Ok(VersionConstraint::Release),
+            _ => {}
+        }
+
+        if !s.starts_with('[') && !s.starts_with('(') {
+            return Ok(VersionConstraint::Soft(s.to_string()));
+        }
+
+        let mut ranges = Vec::new();
+        let mut remainder = s;
+        while !remainder.is_empty() {
+            let end = remainder.find([']', ')'])
+                .ok_or_else(|| anyhow!("unterminated version range: {}", s))?;
+            ranges.push(VersionRange::parse(&remainder[..=end])?);
+
+            remainder = remainder[end+1..].trim_start();
+            if let Some(r) = remainder.strip_prefix(',') {
+                remainder = r.trim_start();
+            }
+            else if !remainder.is_empty() {
+                return Err(anyhow!("version ranges must be separated by ',': {}", s));
+            }
+        }
+        Ok(VersionConstraint::Ranges(ranges))
+    }
+
+    pub fn matches(&self, version: &str) -> bool {
+        match self {
+            VersionConstraint::Soft(v) => compare_versions(v, version) == Ordering::Equal,
+            VersionConstraint::Ranges(ranges) => ranges.iter().any(|r| r.contains(version)),
+            VersionConstraint::Latest => true,
+            VersionConstraint::Release => !is_snapshot(version),
+        }
+    }
+
+    /// Selects the highest matching version. Snapshots are only selected by ranges if
+    ///  `include_snapshots` is set, or explicitly by a soft version or 'LATEST'.
+    pub fn select<'a>(&self, candidates: &'a [String], include_snapshots: bool) -> Option<&'a str> {
+        candidates.iter()
+            .filter(|v| self.matches(v))
+            .filter(|v| include_snapshots || !is_snapshot(v) || !matches!(self, VersionConstraint::Ranges(_)))
+            .max_by(|a, b| compare_versions(a, b))
+            .map(|v| v.as_str())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("1", "1.0", Ordering::Equal)]
+    #[case("1.0.0", "1", Ordering::Equal)]
+    #[case("1.ga", "1", Ordering::Equal)]
+    #[case("1-final", "1", Ordering::Equal)]
+    #[case("1.0", "1.0.1", Ordering::Less)]
+    #[case("1.9", "1.10", Ordering::Less)]
+    #[case("1.0-alpha-1", "1.0", Ordering::Less)]
+    #[case("1.0-alpha1", "1.0-beta1", Ordering::Less)]
+    #[case("1.0-a1", "1.0-alpha-1", Ordering::Equal)]
+    #[case("1.0-beta1", "1.0-rc1", Ordering::Less)]
+    #[case("1.0-cr1", "1.0-rc1", Ordering::Equal)]
+    #[case("1.0-rc1", "1.0-SNAPSHOT", Ordering::Less)]
+    #[case("1.0-SNAPSHOT", "1.0", Ordering::Less)]
+    #[case("1.0", "1.0-sp1", Ordering::Less)]
+    #[case("1.0-sp1", "1.0-foo", Ordering::Less)]
+    #[case("1.0-foo", "1.0.1", Ordering::Less)]
+    #[case("2.0.0-M1", "2.0.0", Ordering::Less)]
+    #[case("99999999999999999999", "100000000000000000000", Ordering::Less)]
+    fn test_compare_versions(#[case] a: &str, #[case] b: &str, #[case] expected: Ordering) {
+        assert_eq!(compare_versions(a, b), expected);
+        assert_eq!(compare_versions(b, a), expected.reverse());
+    }
+
+    fn candidates() -> Vec<String> {
+        ["1.0", "1.1", "1.2-SNAPSHOT", "1.10", "2.0-rc1", "2.0", "2.1-SNAPSHOT"]
+            .iter().map(|s| s.to_string()).collect()
+    }
+
+    #[rstest]
+    #[case("1.1", false, Some("1.1"))]
+    #[case("1.5", false, None)]
+    #[case("[1.0,2.0)", false, Some("2.0-rc1"))] // pre-releases are below the release, as in Maven
+    #[case("[1.0,2.0]", false, Some("2.0"))]
+    #[case("[1.0,1.10)", false, Some("1.1"))]
+    #[case("[1.0,1.10)", true, Some("1.2-SNAPSHOT"))]
+    #[case("(,1.0]", false, Some("1.0"))]
+    #[case("(,1.0),[1.5,)", false, Some("2.0"))]
+    #[case("[1.1]", false, Some("1.1"))]
+    #[case("[3.0,)", false, None)]
+    #[case("RELEASE", false, Some("2.0"))]
+    #[case("LATEST", false, Some("2.1-SNAPSHOT"))]
+    fn test_select(#[case] constraint: &str, #[case] include_snapshots: bool, #[case] expected: Option<&str>) {
+        let constraint = VersionConstraint::parse(constraint).unwrap();
+        assert_eq!(constraint.select(&candidates(), include_snapshots), expected);
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("[1.0,2.0")]
+    #[case("[2.0,1.0]")]
+    #[case("(1.0)")]
+    #[case("[1.0,2.0) [3.0,)")]
+    fn test_parse_invalid(#[case] constraint: &str) {
+        assert!(VersionConstraint::parse(constraint).is_err());
+    }
+}
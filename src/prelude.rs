@@ -0,0 +1,21 @@
+//! The types and functions embedders build on, which stay source compatible across minor
+//!  versions: `use arti_vault::prelude::*;`
+//!
+//! Everything else may change when the server's internals are refactored, even if it is public.
+//!  Changes to this module need a new major version, and `tests/api_stability.rs` compiles against
+//!  it the way embedders use it to catch accidental breakage.
+
+pub use uuid::Uuid;
+
+pub use crate::blob::blob_storage::{BlobStorage, BlobStream};
+#[cfg(feature = "fs-storage")]
+pub use crate::blob::fs_blob_storage::FsBlobStorage;
+pub use crate::blob::transient_blob_storage::TransientBlobStorage;
+pub use crate::maven::coordinates::{MavenArtifactId, MavenArtifactRef, MavenClassifier, MavenCoordinates, MavenGroupId, MavenVersion};
+pub use crate::maven::hosted_repo::{DeleteOutcome, DeployOutcome, HostedMavenRepo};
+pub use crate::maven::paths::{as_maven_path, ChecksumKind, maven_file_name, parse_maven_path};
+pub use crate::maven::remote_repo::{DummyRemoteRepoMetadataStore, RemoteMavenRepo, RemoteRepoMetadataStore};
+pub use crate::repository_manager::{RepositoryConfig, RepositoryKind, RepositoryManager, RepositoryManagerConfig};
+pub use crate::util::blob::{Blob, BlobStat};
+pub use crate::util::content_hooks::ContentStream;
+pub use crate::util::repo_error::RepoError;
@@ -0,0 +1,1639 @@
+#[cfg(feature = "fs-storage")]
+use std::fmt::{Debug, Formatter};
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+#[cfg(feature = "fs-storage")]
+use async_trait::async_trait;
+use futures::StreamExt;
+use hyper::header::HeaderName;
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use anyhow::{anyhow, Context};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+#[cfg(feature = "fs-storage")]
+use crate::blob::fs_blob_storage::{FsBlobStorage, IsReferencedChecker};
+use crate::auth::access::AccessPolicy;
+use crate::blob::blob_id::{BlobIdCodec, SignedBlobIds};
+use crate::blob::configured_blob_storage::BlobStorageConfig;
+use crate::maven::advisories::{AdvisoryTable, ReplacementAdvisory};
+use crate::maven::blocking_rules_file::{BlockingRulesFileConfig, load_blocking_rules};
+use crate::maven::checksums::default_checksum_kinds;
+use crate::maven::coordinates::{MavenArtifactId, MavenArtifactRef, MavenClassifier, MavenCoordinates, MavenGroupId, MavenVersion};
+use crate::maven::hosted_repo::{DeleteOutcome, DeployOutcome, HostedMavenRepo};
+use crate::maven::metadata_backup::{create_backup, MetadataBackup, restore_backup, RestoreReport};
+use crate::maven::metadata_export::{export_metadata, MetadataExportConfig, write_ndjson};
+use crate::maven::naming_policy::{NamingEnforcement, NamingPolicy, NamingPolicyConfig, NamingPolicyError};
+use crate::maven::negative_cache::NegativeCachePolicy;
+use crate::maven::plugin_prefix::PluginPrefixPolicy;
+use crate::maven::paths::{as_maven_path, ChecksumKind, parse_maven_path};
+use crate::maven::platform_classifiers::platform_of;
+use crate::maven::prefetch_plan::PrefetchPlan;
+use crate::maven::quarantine::{ArtifactStatus, QuarantinedArtifact};
+use crate::maven::remote_repo::{DummyRemoteRepoMetadataStore, Eviction, RemoteMavenRepo};
+use crate::maven::snapshot_retention::SnapshotRetentionPolicy;
+use crate::maven::version_blocking::{VersionBlockingRule, VersionBlockList};
+use crate::maven::version_resolution::{is_snapshot, VersionConstraint};
+use crate::util::anonymization::AnonymizationPolicy;
+use crate::util::audit_log::{AuditEventKind, AuditLog};
+use crate::util::blob::{Blob, BlobStat};
+use crate::util::byte_range::UncachedRangePolicy;
+use crate::util::checksum_file::ChecksumFilePolicy;
+use crate::util::checksum_headers::{ChecksumHeader, default_checksum_headers};
+use crate::util::content_hooks::{ContentHooks, ContentStream};
+use crate::util::content_type::DispositionPolicy;
+use crate::util::deploy_metrics::{DeployAlertThreshold, DeployMetrics, send_alert_webhook};
+use crate::util::lifecycle_hooks::RepositoryLifecycleHooks;
+use crate::util::prometheus_metrics::NEGATIVE_CACHE_ENTRIES;
+use crate::util::scheduler::Scheduler;
+use crate::util::signature_verification::{Keyring, SignaturePolicy};
+use crate::util::slow_transfer::SlowTransferPolicy;
+use crate::util::tasks::{TaskContext, TaskRegistry};
+use crate::util::transfer_metrics::TransferMetrics;
+use crate::util::upstream_client::{ClientCertificate, UpstreamTlsConfig};
+use crate::util::uuid_generator::{RandomUuidGenerator, SeededUuidGenerator, UuidGenerator};
+
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RepositoryKind {
+    /// proxies and caches an upstream repository
+    Remote {
+        upstream_uri: String,
+        /// another mirror of the upstream repository, asked for metadata that does not match its
+        ///  checksum upstream
+        #[serde(default)]
+        mirror_uri: Option<String>,
+        #[serde(flatten)]
+        tls: UpstreamTlsConfig,
+        /// upstream response headers that downloads are validated against, in order
+        #[serde(default = "default_checksum_headers")]
+        checksum_headers: Vec<ChecksumHeader>,
+        /// whether downloads without a checksum header are validated against upstream's checksum files
+        #[serde(default)]
+        checksum_files: ChecksumFilePolicy,
+        /// whether artifacts are verified against their '.asc' signature upstream
+        #[serde(default)]
+        signatures: SignaturePolicy,
+    },
+    /// artifacts are deployed to it directly
+    Hosted,
+}
+
+/// A repository served at '/repo/<name>/', with its own blob storage and metadata store
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize)]
+pub struct RepositoryConfig {
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: RepositoryKind,
+    #[serde(default)]
+    pub blob_storage: BlobStorageConfig,
+    #[serde(default)]
+    pub snapshot_retention: SnapshotRetentionPolicy,
+    /// who may read from and deploy to the repository, see [Authenticator](crate::auth::authenticator::Authenticator)
+    #[serde(default)]
+    pub access: AccessPolicy,
+    /// the checksum files the repository serves, strongest first
+    #[serde(default = "default_checksum_kinds")]
+    pub checksums: Vec<ChecksumKind>,
+    /// conventions for deployed coordinates, for hosted repositories only
+    #[serde(default)]
+    pub naming_policy: NamingPolicyConfig,
+}
+
+/// Parses a repository's configuration from its short form, e.g. 'central=remote:https://repo1.maven.org/maven2'
+///  or 'internal=hosted;fs=/var/lib/arti-vault/internal'. Options follow the type separated by ';':
+///  'fs=<root>' for file system blob storage (transient otherwise), and settings for remote
+///  repositories: 'mirror=<URI>', and TLS settings (see [UpstreamTlsConfig]): 'client_cert=<path>'
+///  and 'client_key=<path>', 'ca=<path>' (repeatable), 'system_roots=false' and 'insecure_skip_verify'.
+///  'keep_snapshots=<n>' and 'snapshot_max_age_days=<days>' set the [SnapshotRetentionPolicy].
+///  'read=<users>' and 'deploy=<users>' restrict access (see [AccessPolicy]), with user names
+///  separated by '+'. 'checksums=<kinds>' sets the checksum files the repository serves, e.g.
+///  'sha512+sha256+sha1+md5'. 'checksum_headers=<headers>' sets the upstream headers downloads
+///  are validated against (see [ChecksumHeader]), separated by '+', and 'checksum_files=<policy>'
+///  whether downloads without one are validated against checksum files (see [ChecksumFilePolicy]).
+///  'signatures=<policy>' sets whether artifacts are verified against their signature (see
+///  [SignaturePolicy]). Hosted repositories take a [NamingPolicyConfig] as 'group_id_pattern=<regex>',
+///  'artifact_id_pattern=<regex>', 'version_format=semver' and 'naming_enforcement=warn'.
+pub fn parse_repository_config(s: &str) -> anyhow::Result<RepositoryConfig> {
+    let (name, rest) = s.split_once('=')
+        .ok_or_else(|| anyhow!("repository configuration '{}' must start with '<name>='", s))?;
+    let name = name.trim();
+    validate_repository_name(name)?;
+
+    let mut parts = rest.split(';').map(|part| part.trim());
+    let kind = parts.next().unwrap_or_default();
+
+    #[cfg(feature = "fs-storage")]
+    let mut fs_root = None;
+    #[cfg(feature = "fs-storage")]
+    let mut verify_reads = false;
+    let mut mirror_uri = None;
+    let mut tls = UpstreamTlsConfig::default();
+    let mut cert_path = None;
+    let mut key_path = None;
+    let mut snapshot_retention = SnapshotRetentionPolicy::default();
+    let mut access = AccessPolicy::default();
+    let mut checksums = default_checksum_kinds();
+    let mut checksum_headers = None;
+    let mut checksum_files = None;
+    let mut signatures = None;
+    let mut naming_policy = NamingPolicyConfig::default();
+    for option in parts {
+        match option.split_once('=') {
+            #[cfg(feature = "fs-storage")]
+            Some(("fs", root)) if !root.is_empty() => fs_root = Some(root.into()),
+            Some(("mirror", uri)) if !uri.is_empty() => mirror_uri = Some(uri.to_string()),
+            Some(("client_cert", path)) if !path.is_empty() => cert_path = Some(path.into()),
+            Some(("client_key", path)) if !path.is_empty() => key_path = Some(path.into()),
+            Some(("ca", path)) if !path.is_empty() => tls.ca_certificates.push(path.into()),
+            Some(("system_roots", "false")) => tls.use_system_roots = false,
+            Some(("keep_snapshots", n)) => snapshot_retention.keep_builds = Some(n.parse().with_context(|| format!("invalid 'keep_snapshots' for repository '{}'", name))?),
+            Some(("snapshot_max_age_days", days)) => snapshot_retention.max_age_days = Some(days.parse().with_context(|| format!("invalid 'snapshot_max_age_days' for repository '{}'", name))?),
+            Some(("read", users)) => access.read = Some(split_users(users)),
+            Some(("deploy", users)) => access.deploy = Some(split_users(users)),
+            Some(("checksums", kinds)) => checksums = kinds.split('+')
+                .map(|kind| ChecksumKind::for_file_extension(&format!(".{}", kind.trim()))
+                    .ok_or_else(|| anyhow!("unsupported checksum '{}' for repository '{}'", kind, name)))
+                .collect::<anyhow::Result<_>>()?,
+            Some(("checksum_headers", headers)) => checksum_headers = Some(headers.split('+')
+                .map(|header| header.parse().with_context(|| format!("invalid 'checksum_headers' for repository '{}'", name)))
+                .collect::<anyhow::Result<_>>()?),
+            Some(("checksum_files", policy)) => checksum_files = Some(policy.parse().with_context(|| format!("invalid 'checksum_files' for repository '{}'", name))?),
+            Some(("signatures", policy)) => signatures = Some(policy.parse().with_context(|| format!("invalid 'signatures' for repository '{}'", name))?),
+            Some(("group_id_pattern", pattern)) if !pattern.is_empty() => naming_policy.group_id_pattern = Some(pattern.to_string()),
+            Some(("artifact_id_pattern", pattern)) if !pattern.is_empty() => naming_policy.artifact_id_pattern = Some(pattern.to_string()),
+            Some(("version_format", format)) => naming_policy.version_format = format.parse().with_context(|| format!("invalid 'version_format' for repository '{}'", name))?,
+            Some(("naming_enforcement", enforcement)) => naming_policy.enforcement = enforcement.parse().with_context(|| format!("invalid 'naming_enforcement' for repository '{}'", name))?,
+            None if option == "insecure_skip_verify" => tls.insecure_skip_verify = true,
+            #[cfg(feature = "fs-storage")]
+            None if option == "verify_reads" => match fs_root {
+                Some(_) => verify_reads = true,
+                None => return Err(anyhow!("'verify_reads' for repository '{}' requires 'fs' storage before it", name)),
+            },
+            _ => return Err(anyhow!("unsupported option for repository '{}': '{}'", name, option)),
+        }
+    }
+    #[cfg(feature = "fs-storage")]
+    let blob_storage = match fs_root {
+        Some(root) => BlobStorageConfig::Fs { root, verify_reads },
+        None => BlobStorageConfig::Transient,
+    };
+    #[cfg(not(feature = "fs-storage"))]
+    let blob_storage = BlobStorageConfig::Transient;
+    tls.client_certificate = match (cert_path, key_path) {
+        (Some(cert_path), Some(key_path)) => Some(ClientCertificate { cert_path, key_path }),
+        (None, None) => None,
+        _ => return Err(anyhow!("repository '{}' requires both 'client_cert' and 'client_key' for a client certificate", name)),
+    };
+
+    let kind = match kind.split_once(':') {
+        Some(("remote", upstream_uri)) if !upstream_uri.is_empty() => RepositoryKind::Remote {
+            upstream_uri: upstream_uri.to_string(),
+            mirror_uri,
+            tls,
+            checksum_headers: checksum_headers.unwrap_or_else(default_checksum_headers),
+            checksum_files: checksum_files.unwrap_or_default(),
+            signatures: signatures.unwrap_or_default(),
+        },
+        None if kind == "hosted" && tls == UpstreamTlsConfig::default() && mirror_uri.is_none() && checksum_headers.is_none() && checksum_files.is_none() && signatures.is_none() => RepositoryKind::Hosted,
+        None if kind == "hosted" => return Err(anyhow!("hosted repository '{}' can not have upstream settings", name)),
+        _ => return Err(anyhow!("repository '{}' must be 'remote:<upstream URI>' or 'hosted', was '{}'", name, kind)),
+    };
+
+    Ok(RepositoryConfig {
+        name: name.to_string(),
+        kind,
+        blob_storage,
+        snapshot_retention,
+        access,
+        checksums,
+        naming_policy,
+    })
+}
+
+fn split_users(s: &str) -> Vec<String> {
+    s.split('+')
+        .map(|user| user.trim())
+        .filter(|user| !user.is_empty())
+        .map(|user| user.to_string())
+        .collect()
+}
+
+/// Settings for creating a [RepositoryManager]
+#[derive(Clone, Debug)]
+pub struct RepositoryManagerConfig {
+    /// the first remote repository is the default, which serves paths that do not start with a
+    ///  repository name and is the target of administrative operations
+    pub repositories: Vec<RepositoryConfig>,
+    /// deterministic mode for reproducible storage layout and logs, e.g. for bug reports
+    pub uuid_seed: Option<u64>,
+    /// allowlist of upstream response headers to store with each artifact
+    pub persisted_headers: Vec<HeaderName>,
+    /// return persisted upstream headers to clients
+    pub replay_upstream_headers: bool,
+    pub audit_log_capacity: usize,
+    /// plugins inspecting or transforming artifacts as they enter the repository
+    pub content_hooks: ContentHooks,
+    /// plugins provisioning and releasing resources of repositories that are created at runtime
+    pub lifecycle_hooks: RepositoryLifecycleHooks,
+    pub deploy_alert_threshold: DeployAlertThreshold,
+    /// deploy anomalies are always logged, and posted to this URI if it is set
+    pub deploy_alert_webhook: Option<String>,
+    /// secret for signing blob storage integrity manifests - creating manifests is disabled if
+    ///  this is not set
+    pub manifest_signing_key: Option<String>,
+    /// secret for the opaque ids in download URLs of quarantined blobs, see [SignedBlobIds] -
+    ///  these URLs are disabled if this is not set
+    pub blob_id_secret: Option<String>,
+    /// how browsers are told to handle artifact downloads
+    pub content_disposition: DispositionPolicy,
+    /// None disables aborting slow upstream transfers
+    pub slow_transfer_policy: Option<SlowTransferPolicy>,
+    /// how long remote repositories wait before requesting an artifact again after its download failed
+    pub negative_cache: NegativeCachePolicy,
+    /// what happens when a plugin is registered with a prefix another plugin in its group has
+    pub plugin_prefix_policy: PluginPrefixPolicy,
+    /// how remote repositories answer range requests for artifacts they have not cached yet
+    pub uncached_range_policy: UncachedRangePolicy,
+    /// public keys for repositories that verify signatures, see [Keyring::load]
+    pub signature_keyring: Option<PathBuf>,
+    /// see [Keyring::with_keyserver]
+    pub signature_keyserver: Option<String>,
+}
+impl Default for RepositoryManagerConfig {
+    fn default() -> RepositoryManagerConfig {
+        RepositoryManagerConfig {
+            repositories: vec![RepositoryConfig {
+                name: "central".to_string(),
+                kind: RepositoryKind::Remote {
+                    upstream_uri: "https://repo1.maven.org/maven2".to_string(),
+                    mirror_uri: None,
+                    tls: Default::default(),
+                    checksum_headers: default_checksum_headers(),
+                    checksum_files: Default::default(),
+                    signatures: Default::default(),
+                },
+                blob_storage: BlobStorageConfig::Transient,
+                snapshot_retention: Default::default(),
+                access: Default::default(),
+                checksums: default_checksum_kinds(),
+                naming_policy: Default::default(),
+            }],
+            uuid_seed: None,
+            persisted_headers: vec![],
+            replay_upstream_headers: false,
+            audit_log_capacity: 1000,
+            content_hooks: ContentHooks::new(),
+            lifecycle_hooks: RepositoryLifecycleHooks::new(),
+            deploy_alert_threshold: Default::default(),
+            deploy_alert_webhook: None,
+            manifest_signing_key: None,
+            blob_id_secret: None,
+            content_disposition: Default::default(),
+            slow_transfer_policy: Some(Default::default()),
+            negative_cache: Default::default(),
+            plugin_prefix_policy: Default::default(),
+            uncached_range_policy: Default::default(),
+            signature_keyring: None,
+            signature_keyserver: None,
+        }
+    }
+}
+
+/// orphaned data younger than this may still be in use by an in-flight operation
+#[cfg(feature = "fs-storage")]
+pub const DEFAULT_ORPHAN_GRACE_PERIOD: Duration = Duration::from_secs(3600);
+
+/// Settings for periodically deleting orphaned data (most notably blobs that no artifact refers
+///  to) from the default repository's file system blob storage, see [FsBlobStorage::fsck]
+#[cfg(feature = "fs-storage")]
+#[derive(Clone, Debug)]
+pub struct BlobGcConfig {
+    pub interval: Duration,
+    pub grace_period: Duration,
+    /// orphans are only logged, e.g. for checking the effect before enabling deletion
+    pub dry_run: bool,
+}
+
+/// Adapter for checking blob references against the repository's metadata
+#[cfg(feature = "fs-storage")]
+pub struct RepoReferenceChecker<'a> {
+    manager: &'a RepositoryManager,
+}
+#[cfg(feature = "fs-storage")]
+impl Debug for RepoReferenceChecker<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RepoReferenceChecker")
+    }
+}
+#[cfg(feature = "fs-storage")]
+#[async_trait]
+impl IsReferencedChecker for RepoReferenceChecker<'_> {
+    async fn is_referenced(&self, key: &Uuid) -> anyhow::Result<bool> {
+        self.manager.is_blob_referenced(key).await
+    }
+}
+
+/// number of artifacts that a warm-up fetches concurrently
+const WARM_UP_CONCURRENCY: usize = 4;
+
+/// finished tasks are kept for querying their results, up to this number
+const MAX_FINISHED_TASKS: usize = 100;
+
+/// The outcome of [RepositoryManager::warm_up]
+#[derive(Clone, Eq, PartialEq, Debug, Default, Serialize)]
+pub struct WarmUpReport {
+    /// fetched from upstream or cached already
+    pub fetched_artifacts: usize,
+    /// artifacts in hosted repositories, and blocked versions
+    pub skipped_artifacts: usize,
+    /// as (path, reason)
+    pub failed_artifacts: Vec<(String, String)>,
+}
+
+/// A repository found by name, see [RepositoryManager::find_repository]
+pub enum RepositoryRef<'a> {
+    Remote(&'a Arc<RemoteMavenRepo>),
+    Hosted(&'a HostedMavenRepo),
+}
+
+/// A request for a version that is blocked by policy
+pub struct BlockedVersion {
+    pub rule: VersionBlockingRule,
+    pub advisory: Option<ReplacementAdvisory>,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeployRefusalReason {
+    UnknownRepository,
+    VersionExists,
+    Blocked,
+    NamingPolicy,
+}
+
+/// Why a deploy would be refused, see [RepositoryManager::check_deploy]
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+pub struct DeployRefusal {
+    pub reason: DeployRefusalReason,
+    pub message: String,
+}
+
+/// Owns repositories, storage, policies and background jobs, and provides the operations that
+///  frontends (HTTP handlers, and potentially other protocols or a CLI) are built on. Frontends
+///  should not need to know how these parts are wired together.
+pub struct RepositoryManager {
+    /// the default repository, see [RepositoryManagerConfig::repositories]
+    pub repo: Arc<RemoteMavenRepo>,
+    pub blocked_versions: VersionBlockList,
+    pub advisories: AdvisoryTable,
+    pub audit_log: AuditLog,
+    pub scheduler: Scheduler,
+    /// long-running admin operations
+    pub tasks: TaskRegistry,
+    pub deploy_metrics: DeployMetrics,
+    /// responses whose body did not have the artifact's size
+    pub transfer_metrics: Arc<TransferMetrics>,
+    deploy_alert_webhook: Option<String>,
+    pub content_disposition: DispositionPolicy,
+    /// the default repository's blob storage, for maintenance operations that are specific to file
+    ///  system storage
+    #[cfg(feature = "fs-storage")]
+    pub fs_blob_storage: Option<Arc<FsBlobStorage>>,
+    #[cfg(feature = "fs-storage")]
+    pub manifest_signing_key: Option<String>,
+    blob_ids: Option<SignedBlobIds>,
+    /// frozen clones by name, with the name of the repository they were cloned from
+    clones: RwLock<BTreeMap<String, (String, Arc<RemoteMavenRepo>)>>,
+    lifecycle_hooks: RepositoryLifecycleHooks,
+    /// remote repositories by name, including the default repository
+    remotes: BTreeMap<String, Arc<RemoteMavenRepo>>,
+    /// hosted repositories by name
+    hosted: BTreeMap<String, HostedMavenRepo>,
+    /// by hosted repository name, for repositories that have one
+    naming_policies: BTreeMap<String, NamingPolicy>,
+}
+impl RepositoryManager {
+    pub fn new(config: RepositoryManagerConfig) -> anyhow::Result<RepositoryManager> {
+        let uuid_generator: Arc<dyn UuidGenerator> = match config.uuid_seed {
+            Some(seed) => {
+                info!("using seeded UUIDs with seed {}", seed);
+                Arc::new(SeededUuidGenerator::new(seed))
+            }
+            None => Arc::new(RandomUuidGenerator::default()),
+        };
+
+        let mut default_repo = None;
+        #[cfg(feature = "fs-storage")]
+        let mut fs_blob_storage = None;
+        let mut remotes = BTreeMap::new();
+        let mut hosted = BTreeMap::new();
+        let mut naming_policies = BTreeMap::new();
+        let mut keyring = match &config.signature_keyring {
+            Some(path) => Keyring::load(path)?,
+            None => Keyring::new(),
+        };
+        if let Some(keyserver_uri) = config.signature_keyserver {
+            keyring = keyring.with_keyserver(keyserver_uri)?;
+        }
+        let keyring = Arc::new(keyring);
+        for repository in config.repositories {
+            validate_repository_name(&repository.name)?;
+            if remotes.contains_key(&repository.name) || hosted.contains_key(&repository.name) {
+                return Err(anyhow!("repository '{}' is configured twice", repository.name));
+            }
+
+            let blob_storage = Arc::new(repository.blob_storage.create(uuid_generator.clone()));
+            match repository.kind {
+                RepositoryKind::Remote { upstream_uri, mirror_uri, tls, checksum_headers, checksum_files, signatures } => {
+                    if repository.naming_policy.is_enabled() {
+                        return Err(anyhow!("repository '{}' has a naming policy, which only applies to hosted repositories", repository.name));
+                    }
+                    if signatures != SignaturePolicy::Ignore && !keyring.can_verify() {
+                        return Err(anyhow!("repository '{}' verifies signatures, but there is neither a signature keyring nor a keyserver", repository.name));
+                    }
+                    #[cfg(feature = "fs-storage")]
+                    if default_repo.is_none() {
+                        fs_blob_storage = blob_storage.as_fs().cloned();
+                    }
+                    let metadata_store = Arc::new(DummyRemoteRepoMetadataStore::new()
+                        .with_negative_cache_policy(config.negative_cache)
+                        .with_plugin_prefix_policy(config.plugin_prefix_policy));
+                    let remote = RemoteMavenRepo::new(upstream_uri, blob_storage, metadata_store)?
+                        .with_name(repository.name.clone())
+                        .with_directory_listing_passthrough(true)
+                        .with_persisted_headers(config.persisted_headers.clone(), config.replay_upstream_headers)
+                        .with_content_hooks(config.content_hooks.clone())
+                        .with_slow_transfer_policy(config.slow_transfer_policy)
+                        .with_uncached_range_policy(config.uncached_range_policy)
+                        .with_snapshot_retention(repository.snapshot_retention)
+                        .with_checksums(repository.checksums)
+                        .with_checksum_headers(checksum_headers)
+                        .with_checksum_file_policy(checksum_files)
+                        .with_signature_verification(signatures, keyring.clone())
+                        .with_tls_config(tls)
+                        .and_then(|remote| remote.with_mirror(mirror_uri))
+                        .with_context(|| format!("error configuring repository '{}'", repository.name))?;
+                    let remote = Arc::new(remote);
+                    default_repo.get_or_insert_with(|| remote.clone());
+                    remotes.insert(repository.name, remote);
+                }
+                RepositoryKind::Hosted => {
+                    if repository.naming_policy.is_enabled() {
+                        let naming_policy = NamingPolicy::new(&repository.naming_policy)
+                            .with_context(|| format!("invalid naming policy for repository '{}'", repository.name))?;
+                        naming_policies.insert(repository.name.clone(), naming_policy);
+                    }
+                    let hosted_repo = HostedMavenRepo::new(repository.name.clone(), blob_storage, Arc::new(DummyRemoteRepoMetadataStore::new()))
+                        .with_content_hooks(config.content_hooks.clone())
+                        .with_snapshot_retention(repository.snapshot_retention)
+                        .with_checksums(repository.checksums);
+                    hosted.insert(repository.name, hosted_repo);
+                }
+            }
+        }
+        let repo = default_repo
+            .ok_or_else(|| anyhow!("at least one remote repository must be configured"))?;
+
+        Ok(RepositoryManager {
+            repo,
+            blocked_versions: VersionBlockList::new(),
+            advisories: AdvisoryTable::new(),
+            audit_log: AuditLog::new(config.audit_log_capacity),
+            scheduler: Scheduler::new(uuid_generator.clone()),
+            tasks: TaskRegistry::new(MAX_FINISHED_TASKS, uuid_generator.clone()),
+            deploy_metrics: DeployMetrics::new(config.deploy_alert_threshold),
+            transfer_metrics: Arc::new(TransferMetrics::new()),
+            deploy_alert_webhook: config.deploy_alert_webhook,
+            content_disposition: config.content_disposition,
+            #[cfg(feature = "fs-storage")]
+            fs_blob_storage,
+            #[cfg(feature = "fs-storage")]
+            manifest_signing_key: config.manifest_signing_key,
+            blob_ids: config.blob_id_secret
+                .map(|secret| SignedBlobIds::new(secret.as_bytes()))
+                .transpose()?,
+            clones: Default::default(),
+            lifecycle_hooks: config.lifecycle_hooks,
+            remotes,
+            hosted,
+            naming_policies,
+        })
+    }
+
+    /// Blocked versions are rejected regardless of cache state or upstream availability. Requests
+    ///  for them are recorded in the audit log.
+    pub fn check_blocked(&self, artifact_ref: &MavenArtifactRef, repo_path: &str) -> Option<BlockedVersion> {
+        let rule = self.blocked_versions.find_blocking_rule(artifact_ref)?;
+
+        info!("rejecting request for blocked version: {}", repo_path);
+        self.audit_log.record(AuditEventKind::BlockedVersionRequested, repo_path, rule.message.clone());
+
+        Some(BlockedVersion {
+            rule,
+            advisory: self.advisories.find_advisory(artifact_ref),
+        })
+    }
+
+    pub fn find_advisory(&self, artifact_ref: &MavenArtifactRef) -> Option<ReplacementAdvisory> {
+        self.advisories.find_advisory(artifact_ref)
+    }
+
+    pub async fn get_artifact(&self, artifact_ref: &MavenArtifactRef) -> anyhow::Result<Blob> {
+        self.repo.get_artifact(artifact_ref).await
+    }
+
+    pub async fn get_artifact_stat(&self, artifact_ref: &MavenArtifactRef) -> anyhow::Result<BlobStat> {
+        self.repo.get_artifact_stat(artifact_ref).await
+    }
+
+    /// Resolves an artifact of a snapshot version to the most recent timestamped build with the
+    ///  same classifier and extension
+    pub async fn resolve_snapshot(&self, artifact_ref: &MavenArtifactRef) -> anyhow::Result<Option<MavenArtifactRef>> {
+        let coordinates = &artifact_ref.coordinates;
+        let version = coordinates.version.unqualified();
+        let classifier = match &artifact_ref.classifier {
+            MavenClassifier::Unclassified => None,
+            MavenClassifier::Classified(c) => Some(c.clone()),
+        };
+
+        let snapshot_versions = self.repo.get_snapshot_versions(&coordinates.group_id, &coordinates.artifact_id, version).await?;
+        Ok(snapshot_versions.iter()
+            .find(|sv| sv.classifier == classifier && sv.file_extension() == artifact_ref.file_extension)
+            .and_then(|sv| sv.version(version))
+            .map(|version| MavenArtifactRef {
+                coordinates: MavenCoordinates {
+                    version,
+                    ..coordinates.clone()
+                },
+                ..artifact_ref.clone()
+            }))
+    }
+
+    /// The grace period for checking blob storage for orphans on request, defaulting to
+    ///  [DEFAULT_ORPHAN_GRACE_PERIOD]. A deploy's blobs are stored before it is complete, so
+    ///  periods shorter than the longest hosted deploy timeout are refused.
+    #[cfg(feature = "fs-storage")]
+    pub fn orphan_grace_period(&self, grace_period_secs: Option<u64>) -> anyhow::Result<Duration> {
+        let grace_period = grace_period_secs.map(Duration::from_secs).unwrap_or(DEFAULT_ORPHAN_GRACE_PERIOD);
+        let min_grace_period = self.hosted.values()
+            .map(|hosted| hosted.deploy_timeout())
+            .max()
+            .unwrap_or_default();
+        if grace_period < min_grace_period {
+            return Err(anyhow!("the grace period must be at least the hosted deploy timeout of {}s", min_grace_period.as_secs()));
+        }
+        Ok(grace_period)
+    }
+
+    /// for checking blob storage for orphans, see [FsBlobStorage::fsck]
+    #[cfg(feature = "fs-storage")]
+    pub fn blob_reference_checker(&self) -> RepoReferenceChecker<'_> {
+        RepoReferenceChecker { manager: self }
+    }
+
+    /// For deploy rate metrics. Anomalies are recorded in the audit log and posted to the
+    ///  configured webhook.
+    pub fn record_deploy(&self, repository: &str, principal: &str, size: u64) {
+        let alert = match self.deploy_metrics.record_deploy(repository, principal, size) {
+            Some(alert) => alert,
+            None => return,
+        };
+
+        self.audit_log.record_by(
+            AuditEventKind::DeployAnomaly,
+            alert.repository.clone(),
+            format!("{} deploys with {} bytes within {}s", alert.deploys, alert.bytes, alert.window_secs),
+            &alert.principal,
+        );
+        if let Some(webhook) = self.deploy_alert_webhook.clone() {
+            tokio::spawn(async move {
+                if let Err(e) = send_alert_webhook(&webhook, &alert).await {
+                    warn!("failed to send deploy alert to webhook: {}", e);
+                }
+            });
+        }
+    }
+
+    /// Finds the repository a repository path refers to by its first segment, returning it together
+    ///  with the path relative to it. Repository names take precedence over upstream paths starting
+    ///  with the same name.
+    pub fn find_repository<'a>(&self, repo_path: &'a str) -> Option<(RepositoryRef<'_>, &'a str)> {
+        let (name, path) = repo_path.split_once('/')?;
+        if let Some(remote) = self.remotes.get(name) {
+            return Some((RepositoryRef::Remote(remote), path));
+        }
+        self.hosted.get(name).map(|hosted| (RepositoryRef::Hosted(hosted), path))
+    }
+
+    /// Updates the metrics that are sampled rather than counted, see [crate::util::prometheus_metrics]
+    pub async fn sample_metrics(&self) -> anyhow::Result<()> {
+        for (name, remote) in &self.remotes {
+            NEGATIVE_CACHE_ENTRIES.with_label_values(&[name]).set(remote.failed_download_count().await? as i64);
+        }
+        Ok(())
+    }
+
+    pub fn repository_names(&self) -> Vec<String> {
+        self.remotes.keys()
+            .chain(self.hosted.keys())
+            .cloned()
+            .collect()
+    }
+
+    /// Checks whether a deploy of the given version would be accepted, so that clients can find out
+    ///  before uploading anything. The repository name can be omitted if there is only one hosted
+    ///  repository. Returns all reasons for refusing the deploy, i.e. an empty list if it would be
+    ///  accepted.
+    pub async fn check_deploy(&self, repo_name: Option<&str>, coordinates: &MavenCoordinates) -> anyhow::Result<Vec<DeployRefusal>> {
+        let hosted = match repo_name {
+            Some(repo_name) => self.hosted.get(repo_name),
+            None if self.hosted.len() == 1 => self.hosted.values().next(),
+            None => None,
+        };
+        let hosted = match hosted {
+            Some(hosted) => hosted,
+            None => return Ok(vec![DeployRefusal {
+                reason: DeployRefusalReason::UnknownRepository,
+                message: match repo_name {
+                    Some(repo_name) => format!("there is no hosted repository '{}'", repo_name),
+                    None => "a hosted repository must be specified".to_string(),
+                },
+            }]),
+        };
+
+        let mut refusals = vec![];
+        if hosted.is_release_deployed(coordinates).await? {
+            refusals.push(DeployRefusal {
+                reason: DeployRefusalReason::VersionExists,
+                message: format!("version {} exists already, and releases can not be redeployed", coordinates.version.unqualified()),
+            });
+        }
+        let artifact_ref = MavenArtifactRef {
+            coordinates: coordinates.clone(),
+            classifier: MavenClassifier::Unclassified,
+            file_extension: ".pom".to_string(),
+        };
+        if let Some(rule) = self.blocked_versions.find_blocking_rule(&artifact_ref) {
+            refusals.push(DeployRefusal {
+                reason: DeployRefusalReason::Blocked,
+                message: rule.message,
+            });
+        }
+        if let Some(naming_policy) = self.naming_policies.get(hosted.name()).filter(|policy| policy.enforcement == NamingEnforcement::Reject) {
+            refusals.extend(naming_policy.check(coordinates).into_iter()
+                .map(|violation| DeployRefusal {
+                    reason: DeployRefusalReason::NamingPolicy,
+                    message: violation.message,
+                }));
+        }
+        //TODO check quotas once there are any
+        Ok(refusals)
+    }
+
+    /// Deploys a file to a hosted repository, see [HostedMavenRepo::deploy]. Returns None if there
+    ///  is no hosted repository with the given name. Blocked versions are refused, since they could
+    ///  not be downloaded anyway. Coordinates that violate the repository's naming policy fail with
+    ///  a [NamingPolicyError], or are recorded in the audit log once committed if the policy only
+    ///  warns.
+    pub async fn deploy(&self, repo_name: &str, path: &str, principal: &str, data: ContentStream, expected_size: Option<u64>) -> anyhow::Result<Option<DeployOutcome>> {
+        let hosted = match self.hosted.get(repo_name) {
+            Some(hosted) => hosted,
+            None => return Ok(None),
+        };
+        if let Some(rule) = parse_maven_path(path).ok().and_then(|artifact_ref| self.blocked_versions.find_blocking_rule(&artifact_ref)) {
+            return Ok(Some(DeployOutcome::Conflict(rule.message)));
+        }
+        let naming_policy = self.naming_policies.get(repo_name);
+        if let (Some(naming_policy), Ok(artifact_ref)) = (naming_policy, parse_maven_path(path)) {
+            let violations = naming_policy.check(&artifact_ref.coordinates);
+            if !violations.is_empty() && naming_policy.enforcement == NamingEnforcement::Reject {
+                return Err(NamingPolicyError { path: path.to_string(), violations }.into());
+            }
+        }
+
+        let size = Arc::new(AtomicU64::new(0));
+        let counted_size = size.clone();
+        let data = Box::pin(data.inspect(move |chunk| {
+            if let Ok(chunk) = chunk {
+                counted_size.fetch_add(chunk.len() as u64, Ordering::Relaxed);
+            }
+        }));
+
+        let (outcome, inconsistencies) = hosted.deploy_checked(path, data, expected_size).await?;
+        match &outcome {
+            DeployOutcome::Pending => self.record_deploy(repo_name, principal, size.load(Ordering::Relaxed)),
+            DeployOutcome::Committed(artifacts) => {
+                for artifact_ref in artifacts {
+                    self.audit_log.record_by(AuditEventKind::ArtifactDeployed, format!("{}/{}", repo_name, as_maven_path(artifact_ref)), "deployed", principal);
+                }
+                if let Some(naming_policy) = naming_policy {
+                    self.record_naming_violations(repo_name, naming_policy, artifacts, principal);
+                }
+                if let Some(artifact_ref) = artifacts.first() {
+                    let coordinates = &artifact_ref.coordinates;
+                    for inconsistency in inconsistencies {
+                        let subject = format!("{}/{}/{}/maven-metadata.xml", repo_name, coordinates.group_id.0.replace('.', "/"), coordinates.artifact_id.0);
+                        self.audit_log.record_by(AuditEventKind::MetadataInconsistency, subject, inconsistency.to_string(), principal);
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(Some(outcome))
+    }
+
+    /// Violations of a policy that only warns, once per committed version rather than per file
+    fn record_naming_violations(&self, repo_name: &str, naming_policy: &NamingPolicy, artifacts: &[MavenArtifactRef], principal: &str) {
+        let versions = artifacts.iter()
+            .map(|artifact_ref| &artifact_ref.coordinates)
+            .collect::<HashSet<_>>();
+        for coordinates in versions {
+            let violations = naming_policy.check(coordinates);
+            if violations.is_empty() {
+                continue;
+            }
+            let subject = format!("{}/{}:{}:{}", repo_name, coordinates.group_id.0, coordinates.artifact_id.0, coordinates.version.unqualified());
+            let details = violations.iter()
+                .map(|violation| violation.message.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            warn!("deploy of {} violates the naming policy: {}", subject, details);
+            self.audit_log.record_by(AuditEventKind::NamingPolicyViolation, subject, details, principal);
+        }
+    }
+
+    /// Deletes an artifact or a directory from a hosted repository, see [HostedMavenRepo::delete].
+    ///  Returns None if there is no hosted repository with the given name.
+    pub async fn delete(&self, repo_name: &str, path: &str, principal: &str) -> anyhow::Result<Option<DeleteOutcome>> {
+        let hosted = match self.hosted.get(repo_name) {
+            Some(hosted) => hosted,
+            None => return Ok(None),
+        };
+        let outcome = hosted.delete(path).await?;
+        if let DeleteOutcome::Deleted(artifacts) = &outcome {
+            for artifact_ref in artifacts {
+                self.audit_log.record_by(AuditEventKind::ArtifactDeleted, format!("{}/{}", repo_name, as_maven_path(artifact_ref)), "deleted", principal);
+            }
+        }
+        Ok(Some(outcome))
+    }
+
+    /// Evicts a cached artifact from a remote repository, see [RemoteMavenRepo::evict_artifact],
+    ///  deleting its blob unless a clone still refers to it. Returns None if there is no remote
+    ///  repository with the given name.
+    pub async fn evict_artifact(&self, repo_name: &str, artifact_ref: &MavenArtifactRef, principal: &str) -> anyhow::Result<Option<Eviction>> {
+        let remote = match self.remotes.get(repo_name) {
+            Some(remote) => remote,
+            None => return Ok(None),
+        };
+        let eviction = remote.evict_artifact(artifact_ref).await?;
+        if !eviction.is_empty() {
+            self.audit_log.record_by(AuditEventKind::ArtifactDeleted, format!("{}/{}", repo_name, as_maven_path(artifact_ref)), "evicted", principal);
+        }
+        self.delete_evicted_blobs(remote, &eviction).await?;
+        Ok(Some(eviction))
+    }
+
+    /// Evicts all cached artifacts in a group and its subgroups from a remote repository, see
+    ///  [RemoteMavenRepo::evict_group]. Returns None if there is no remote repository with the
+    ///  given name.
+    pub async fn evict_group(&self, repo_name: &str, group_prefix: &str, principal: &str) -> anyhow::Result<Option<Eviction>> {
+        let remote = match self.remotes.get(repo_name) {
+            Some(remote) => remote,
+            None => return Ok(None),
+        };
+        let eviction = remote.evict_group(group_prefix).await?;
+        self.audit_log.record_by(
+            AuditEventKind::CacheInvalidated,
+            format!("{}/{}", repo_name, group_prefix),
+            format!("{} artifacts and {} failed downloads evicted", eviction.artifacts.len(), eviction.failed_downloads),
+            principal,
+        );
+        self.delete_evicted_blobs(remote, &eviction).await?;
+        Ok(Some(eviction))
+    }
+
+    async fn delete_evicted_blobs(&self, remote: &RemoteMavenRepo, eviction: &Eviction) -> anyhow::Result<()> {
+        for (_, blob_key) in &eviction.artifacts {
+            self.delete_unreferenced_blob(remote, blob_key).await?;
+        }
+        Ok(())
+    }
+
+    async fn delete_unreferenced_blob(&self, remote: &RemoteMavenRepo, blob_key: &Uuid) -> anyhow::Result<()> {
+        if self.is_blob_referenced(blob_key).await? {
+            return Ok(());
+        }
+        if let Err(e) = remote.delete_blob(blob_key).await {
+            // orphans are cleaned up eventually by blob GC
+            warn!("failed to delete blob {}: {}", blob_key, e);
+        }
+        Ok(())
+    }
+
+    /// A remote repository's quarantined downloads, each with the current status of its artifact,
+    ///  e.g. whether a later download succeeded. Returns None if there is no remote repository
+    ///  with the given name.
+    pub async fn get_quarantined_artifacts(&self, repo_name: &str) -> anyhow::Result<Option<Vec<(QuarantinedArtifact, Option<ArtifactStatus>)>>> {
+        let remote = match self.remotes.get(repo_name) {
+            Some(remote) => remote,
+            None => return Ok(None),
+        };
+        let mut result = Vec::new();
+        for quarantined in remote.get_quarantined_artifacts().await? {
+            let status = remote.artifact_status(&quarantined.artifact_ref).await?;
+            result.push((quarantined, status));
+        }
+        Ok(Some(result))
+    }
+
+    /// The path below which a quarantined download can be fetched without further credentials,
+    ///  None if [RepositoryManagerConfig::blob_id_secret] is not set. The path contains an opaque
+    ///  id rather than the blob key, so that blob keys can not be enumerated or guessed.
+    pub fn quarantined_blob_path(&self, repo_name: &str, blob_key: &Uuid) -> Option<String> {
+        let blob_ids = self.blob_ids.as_ref()?;
+        Some(format!("/api/v1/quarantined-blobs/{}/{}", repo_name, blob_ids.encode(blob_key)))
+    }
+
+    /// A quarantined download by the id from its [RepositoryManager::quarantined_blob_path]. None
+    ///  if the id is invalid, or the download is no longer quarantined.
+    pub async fn get_quarantined_blob(&self, repo_name: &str, blob_id: &str) -> anyhow::Result<Option<(QuarantinedArtifact, Blob)>> {
+        let (Some(blob_ids), Some(remote)) = (&self.blob_ids, self.remotes.get(repo_name)) else { return Ok(None) };
+        let Some(blob_key) = blob_ids.decode(blob_id) else { return Ok(None) };
+        remote.get_quarantined_blob(&blob_key).await
+    }
+
+    /// Purges a remote repository's quarantined downloads - the one with the given blob, or all of
+    ///  them - deleting their blobs. Returns None if there is no remote repository with the given
+    ///  name.
+    pub async fn purge_quarantined_artifacts(&self, repo_name: &str, blob_key: Option<Uuid>, principal: &str) -> anyhow::Result<Option<Vec<QuarantinedArtifact>>> {
+        let remote = match self.remotes.get(repo_name) {
+            Some(remote) => remote,
+            None => return Ok(None),
+        };
+        let blob_keys = match blob_key {
+            Some(blob_key) => vec![blob_key],
+            None => remote.get_quarantined_artifacts().await?.iter()
+                .map(|quarantined| quarantined.blob_key)
+                .collect(),
+        };
+
+        let mut purged = Vec::new();
+        for blob_key in blob_keys {
+            if let Some(quarantined) = remote.remove_quarantined_artifact(&blob_key).await? {
+                self.audit_log.record_by(AuditEventKind::QuarantinePurged, format!("{}/{}", repo_name, as_maven_path(&quarantined.artifact_ref)), format!("blob {}", blob_key), principal);
+                self.delete_unreferenced_blob(remote, &blob_key).await?;
+                purged.push(quarantined);
+            }
+        }
+        Ok(Some(purged))
+    }
+
+    async fn is_blob_referenced(&self, blob_key: &Uuid) -> anyhow::Result<bool> {
+        for remote in self.remotes.values() {
+            if remote.is_blob_referenced(blob_key).await? {
+                return Ok(true);
+            }
+        }
+        for hosted in self.hosted.values() {
+            if hosted.is_blob_referenced(blob_key).await? {
+                return Ok(true);
+            }
+        }
+        // clones share their origin's blobs
+        let clones = self.clones.read().unwrap().values().map(|(_, clone)| clone.clone()).collect::<Vec<_>>();
+        for clone in clones {
+            if clone.is_blob_referenced(blob_key).await? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Starts periodically discarding incomplete deploys to hosted repositories
+    pub fn schedule_deploy_cleanup(self: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        // the scheduler is owned by the manager, so its jobs must not keep the manager alive
+        let manager = Arc::downgrade(self);
+        self.scheduler.schedule("deploy cleanup", interval, move || {
+            let manager = manager.clone();
+            async move {
+                if let Some(manager) = manager.upgrade() {
+                    for hosted in manager.hosted.values() {
+                        hosted.remove_timed_out_deploys().await;
+                    }
+                }
+                Ok(())
+            }
+        })
+    }
+
+    /// Clones a remote or hosted repository's current state into a new read-only repository with
+    ///  the given name. Returns false if a clone with that name exists already.
+    pub async fn clone_repository(&self, source: &str, name: &str) -> anyhow::Result<bool> {
+        validate_repository_name(name)?;
+        if !self.remotes.contains_key(source) && !self.hosted.contains_key(source) {
+            return Err(anyhow!("there is no repository '{}' to clone", source));
+        }
+        if self.clones.read().unwrap().contains_key(name) {
+            return Ok(false);
+        }
+
+        self.lifecycle_hooks.on_create(name).await
+            .with_context(|| format!("error creating clone '{}'", name))?;
+        let clone = match self.remotes.get(source) {
+            Some(remote) => remote.frozen_clone().await,
+            None => self.hosted[source].frozen_clone().await,
+        };
+        let clone = match clone {
+            Ok(clone) => Arc::new(clone.with_name(name)),
+            Err(e) => {
+                self.lifecycle_hooks.on_delete(name).await;
+                return Err(e);
+            }
+        };
+        let inserted = match self.clones.write().unwrap().entry(name.to_string()) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(e) => {
+                e.insert((source.to_string(), clone));
+                true
+            }
+        };
+        // a concurrent request created a clone with the same name in the meantime
+        if !inserted {
+            self.lifecycle_hooks.on_delete(name).await;
+        }
+        Ok(inserted)
+    }
+
+    pub fn get_clone(&self, name: &str) -> Option<Arc<RemoteMavenRepo>> {
+        self.clones.read().unwrap().get(name).map(|(_, clone)| clone.clone())
+    }
+
+    /// The name of the repository a clone was created from, which governs access to it
+    pub fn clone_source(&self, name: &str) -> Option<String> {
+        self.clones.read().unwrap().get(name).map(|(source, _)| source.clone())
+    }
+
+    pub fn clone_names(&self) -> Vec<String> {
+        self.clones.read().unwrap().keys().cloned().collect()
+    }
+
+    /// Blobs that are only referenced by the removed clone become orphans. Lifecycle hooks run in
+    ///  the background, so they must not rely on the clone's name being unused.
+    pub fn remove_clone(&self, name: &str) -> bool {
+        if self.clones.write().unwrap().remove(name).is_none() {
+            return false;
+        }
+        if !self.lifecycle_hooks.is_empty() {
+            let lifecycle_hooks = self.lifecycle_hooks.clone();
+            let name = name.to_string();
+            tokio::spawn(async move {
+                lifecycle_hooks.on_delete(&name).await;
+            });
+        }
+        true
+    }
+
+    /// Selects the highest available version satisfying a constraint, skipping blocked versions
+    pub async fn resolve_version(&self, group_id: &MavenGroupId, artifact_id: &MavenArtifactId, constraint: &VersionConstraint, include_snapshots: bool) -> anyhow::Result<Option<String>> {
+        let versions = self.available_versions(group_id, artifact_id).await?;
+        Ok(constraint.select(&versions, include_snapshots)
+            .map(|v| v.to_string()))
+    }
+
+    /// The versions of an artifact that are known locally or upstream and not blocked, in no
+    ///  particular order
+    pub async fn available_versions(&self, group_id: &MavenGroupId, artifact_id: &MavenArtifactId) -> anyhow::Result<Vec<String>> {
+        let mut versions = self.repo.get_available_versions(group_id, artifact_id).await?;
+        versions.retain(|version| {
+            let artifact_ref = MavenArtifactRef {
+                coordinates: MavenCoordinates {
+                    group_id: group_id.clone(),
+                    artifact_id: artifact_id.clone(),
+                    version: MavenVersion::Release(version.clone()),
+                },
+                classifier: MavenClassifier::Unclassified,
+                file_extension: ".pom".to_string(),
+            };
+            self.blocked_versions.find_blocking_rule(&artifact_ref).is_none()
+        });
+        Ok(versions)
+    }
+
+    /// The classifiers of a version's artifacts with the given file extension (e.g. '.jar') that
+    ///  refer to a platform, see [platform_of], from local artifacts and the upstream directory
+    ///  listing. For snapshots, the builds in the snapshot metadata are included as well.
+    pub async fn platform_classifiers(&self, group_id: &MavenGroupId, artifact_id: &MavenArtifactId, version: &str, file_extension: &str) -> anyhow::Result<Vec<String>> {
+        let directory_path = format!("{}/{}/{}/", group_id.0.replace('.', "/"), artifact_id.0, version);
+        let mut classifiers = self.repo.get_directory_listing(&directory_path).await?
+            .entries
+            .into_iter()
+            .filter(|e| !e.is_directory)
+            .filter_map(|e| parse_maven_path(&format!("{}{}", directory_path, e.name)).ok())
+            .filter(|artifact_ref| artifact_ref.file_extension == file_extension)
+            .filter_map(|artifact_ref| match artifact_ref.classifier {
+                MavenClassifier::Classified(c) => Some(c),
+                MavenClassifier::Unclassified => None,
+            })
+            .collect::<Vec<_>>();
+
+        if is_snapshot(version) {
+            classifiers.extend(self.repo.get_snapshot_versions(group_id, artifact_id, version).await?
+                .into_iter()
+                .filter(|sv| sv.file_extension() == file_extension)
+                .filter_map(|sv| sv.classifier));
+        }
+
+        classifiers.retain(|c| platform_of(c).is_some());
+        classifiers.sort();
+        classifiers.dedup();
+        Ok(classifiers)
+    }
+
+    /// Registers the artifacts remote repositories' blob storage recorded the origin of, for
+    ///  repositories whose metadata is empty (e.g. after a restart). Hosted repositories' metadata
+    ///  is not rebuilt, their blobs do not record where they were deployed.
+    pub async fn rebuild_cache_metadata(&self) -> anyhow::Result<usize> {
+        let mut num_registered = 0;
+        for (name, remote) in &self.remotes {
+            let n = remote.rebuild_metadata_from_blob_storage().await
+                .with_context(|| format!("error rebuilding metadata of repository '{}'", name))?;
+            if n > 0 {
+                info!("registered {} artifacts from blob storage in repository '{}'", n, name);
+            }
+            num_registered += n;
+        }
+        Ok(num_registered)
+    }
+
+    /// Starts a task that initializes the access times of cached artifacts in all remote
+    ///  repositories that have none, see [RemoteMavenRepo::backfill_access_times]. Its result is
+    ///  the number of initialized access times per repository.
+    pub fn start_access_time_backfill(self: &Arc<Self>) -> Uuid {
+        let manager = self.clone();
+        self.tasks.spawn("access time backfill", |task| async move {
+            let mut backfilled = BTreeMap::new();
+            for (i, (name, remote)) in manager.remotes.iter().enumerate() {
+                let n = remote.backfill_access_times().await
+                    .with_context(|| format!("error backfilling access times of repository '{}'", name))?;
+                task.log(format!("initialized {} access times in repository '{}'", n, name));
+                task.set_progress(i as u64 + 1, manager.remotes.len() as u64);
+                backfilled.insert(name.clone(), n);
+            }
+            Ok(backfilled)
+        })
+    }
+
+    pub async fn create_metadata_backup(&self) -> anyhow::Result<MetadataBackup> {
+        create_backup(&self.repo).await
+    }
+
+    /// see [restore_backup]
+    pub async fn restore_metadata_backup(&self, backup: MetadataBackup, skip_missing: bool) -> anyhow::Result<RestoreReport> {
+        let report = restore_backup(&self.repo, backup, skip_missing).await?;
+        self.audit_log.record(
+            AuditEventKind::MetadataRestored,
+            "",
+            format!("{} artifacts restored, {} skipped", report.restored_artifacts, report.skipped_artifacts.len()),
+        );
+        Ok(report)
+    }
+
+    /// Fetches the artifacts of a prefetch plan ahead of their first request, e.g. after migrating
+    ///  from another repository manager. Artifacts that are cached already are not downloaded again,
+    ///  and hosted repositories have nothing to fetch.
+    pub async fn warm_up(&self, plan: &PrefetchPlan, task: &TaskContext) -> WarmUpReport {
+        let fetches = plan.entries.iter()
+            .map(|entry| async move { (entry.path.as_str(), self.warm_up_artifact(&entry.path).await) })
+            .collect::<Vec<_>>();
+        let mut results = futures::stream::iter(fetches)
+            .buffer_unordered(WARM_UP_CONCURRENCY);
+
+        let mut report = WarmUpReport::default();
+        let mut num_done = 0;
+        while let Some((path, result)) = results.next().await {
+            match result {
+                Ok(true) => report.fetched_artifacts += 1,
+                Ok(false) => report.skipped_artifacts += 1,
+                Err(e) => {
+                    task.log(format!("failed to fetch {}: {}", path, e));
+                    report.failed_artifacts.push((path.to_string(), e.to_string()));
+                }
+            }
+            num_done += 1;
+            task.set_progress(num_done, plan.entries.len() as u64);
+        }
+        info!("warm-up fetched {} artifacts, skipped {}, failed {}", report.fetched_artifacts, report.skipped_artifacts, report.failed_artifacts.len());
+        report
+    }
+
+    /// false if the artifact was skipped
+    async fn warm_up_artifact(&self, path: &str) -> anyhow::Result<bool> {
+        let (remote, repo_path) = match self.find_repository(path) {
+            Some((RepositoryRef::Hosted(_), _)) => return Ok(false),
+            Some((RepositoryRef::Remote(remote), repo_path)) => (remote, repo_path),
+            None => (&self.repo, path),
+        };
+        let artifact_ref = parse_maven_path(repo_path)?;
+        // not via check_blocked: nobody requested the version, so there is nothing to audit
+        if self.blocked_versions.find_blocking_rule(&artifact_ref).is_some() {
+            return Ok(false);
+        }
+        remote.get_artifact(&artifact_ref).await?;
+        Ok(true)
+    }
+
+    /// Starts periodically purging old snapshot builds from the repositories that have a
+    ///  [SnapshotRetentionPolicy]
+    pub fn schedule_snapshot_purge(self: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        // the scheduler is owned by the manager, so its jobs must not keep the manager alive
+        let manager = Arc::downgrade(self);
+        self.scheduler.schedule("snapshot purge", interval, move || {
+            let manager = manager.clone();
+            async move {
+                if let Some(manager) = manager.upgrade() {
+                    for (name, remote) in &manager.remotes {
+                        let purged = remote.purge_snapshots().await?;
+                        if !purged.is_empty() {
+                            info!("purged {} cached snapshot files from {}", purged.len(), name);
+                        }
+                    }
+                    for (name, hosted) in &manager.hosted {
+                        let purged = hosted.purge_snapshots().await?;
+                        if !purged.is_empty() {
+                            info!("purged {} snapshot files from {}", purged.len(), name);
+                        }
+                    }
+                }
+                Ok(())
+            }
+        })
+    }
+
+    /// Starts periodically deleting orphaned data from the default repository's blob storage.
+    ///  Reclaimed bytes are accumulated in the blob storage's metrics, see
+    ///  [FsBlobStorage::metrics]
+    #[cfg(feature = "fs-storage")]
+    pub fn schedule_blob_gc(self: &Arc<Self>, config: BlobGcConfig) -> JoinHandle<()> {
+        // the scheduler is owned by the manager, so its jobs must not keep the manager alive
+        let manager = Arc::downgrade(self);
+        self.scheduler.schedule("blob gc", config.interval, move || {
+            let manager = manager.clone();
+            let config = config.clone();
+            async move {
+                let manager = match manager.upgrade() {
+                    Some(manager) => manager,
+                    None => return Ok(()),
+                };
+                if let Some(fs_blob_storage) = &manager.fs_blob_storage {
+                    let report = fs_blob_storage.fsck(&config.grace_period, config.dry_run, &manager.blob_reference_checker()).await?;
+                    if config.dry_run {
+                        info!("blob gc (dry run) found {} orphans with {} bytes", report.orphans.len(), report.orphans.iter().map(|o| o.size).sum::<u64>());
+                    }
+                    else {
+                        info!("blob gc deleted {} orphans, reclaiming {} bytes", report.orphans.len(), report.reclaimed_bytes());
+                    }
+                }
+                Ok(())
+            }
+        })
+    }
+
+    /// Starts periodically anonymizing principals in the audit log and deploy metrics once they
+    ///  are older than the policy's retention period
+    pub fn schedule_anonymization(self: &Arc<Self>, policy: AnonymizationPolicy) -> JoinHandle<()> {
+        // often enough that principals are kept for little longer than the retention period
+        let interval = (policy.retention / 10).clamp(Duration::from_secs(60), Duration::from_secs(3600));
+        // the scheduler is owned by the manager, so its jobs must not keep the manager alive
+        let manager = Arc::downgrade(self);
+        self.scheduler.schedule("anonymization", interval, move || {
+            let manager = manager.clone();
+            let policy = policy.clone();
+            async move {
+                if let Some(manager) = manager.upgrade() {
+                    let events = manager.audit_log.anonymize(&policy);
+                    let principals = manager.deploy_metrics.anonymize(&policy);
+                    if events + principals > 0 {
+                        info!("anonymized {} audit events and the deploy metrics of {} principals", events, principals);
+                    }
+                }
+                Ok(())
+            }
+        })
+    }
+
+    /// Starts checking the blocking rules file for changes, see [BlockingRulesFileConfig]. The
+    ///  rules are expected to be loaded already, so that they apply from the first request.
+    pub fn schedule_blocking_rules_reload(self: &Arc<Self>, config: BlockingRulesFileConfig) -> JoinHandle<()> {
+        let manager = Arc::downgrade(self);
+        self.scheduler.schedule("blocking rules reload", config.check_interval, move || {
+            let manager = manager.clone();
+            let path = config.path.clone();
+            async move {
+                if let Some(manager) = manager.upgrade() {
+                    // an invalid file (e.g. one that is being written) keeps the previous rules
+                    let rules = load_blocking_rules(&path).await?;
+                    let num_rules = rules.len();
+                    if manager.blocked_versions.replace_file_rules(rules) {
+                        info!("reloaded {} blocking rules from {:?}", num_rules, path);
+                        manager.audit_log.record(AuditEventKind::BlockingRulesFileReloaded, path.to_string_lossy(), format!("{} rules", num_rules));
+                    }
+                }
+                Ok(())
+            }
+        })
+    }
+
+    /// Starts periodically exporting the metadata store, see [MetadataExportConfig]
+    pub fn schedule_metadata_export(self: &Arc<Self>, config: MetadataExportConfig) -> JoinHandle<()> {
+        // the scheduler is owned by the manager, so its jobs must not keep the manager alive
+        let manager = Arc::downgrade(self);
+        self.scheduler.schedule("metadata export", config.interval, move || {
+            let manager = manager.clone();
+            let path = config.path.clone();
+            async move {
+                if let Some(manager) = manager.upgrade() {
+                    let records = export_metadata(&manager.repo).await?;
+                    write_ndjson(&path, &records).await?;
+                    info!("exported {} metadata records to {:?}", records.len(), path);
+                }
+                Ok(())
+            }
+        })
+    }
+}
+
+pub fn validate_repository_name(name: &str) -> anyhow::Result<()> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.') {
+        return Err(anyhow!("invalid repository name '{}': only letters, digits, '-', '_' and '.' are allowed", name));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use async_trait::async_trait;
+    use rstest::rstest;
+    use sha1::{Digest as _, Sha1};
+
+    use crate::maven::prefetch_plan::PrefetchEntry;
+    use crate::util::lifecycle_hooks::RepositoryLifecycleHook;
+    use crate::util::checksum_headers::ChecksumHeaderFormat;
+    use super::*;
+
+    #[rstest]
+    #[case("central=remote:https://repo1.maven.org/maven2", "central", RepositoryKind::Remote { upstream_uri: "https://repo1.maven.org/maven2".to_string(), mirror_uri: None, tls: Default::default(), checksum_headers: default_checksum_headers(), checksum_files: Default::default(), signatures: Default::default() }, BlobStorageConfig::Transient)]
+    #[case("mirror=remote:https://mirror.example.com;client_cert=/tls/client.pem;client_key=/tls/client.key", "mirror", RepositoryKind::Remote {
+        upstream_uri: "https://mirror.example.com".to_string(),
+        mirror_uri: None,
+        tls: UpstreamTlsConfig {
+            client_certificate: Some(ClientCertificate { cert_path: "/tls/client.pem".into(), key_path: "/tls/client.key".into() }),
+            ..Default::default()
+        },
+        checksum_headers: default_checksum_headers(),
+        checksum_files: Default::default(),
+        signatures: Default::default(),
+    }, BlobStorageConfig::Transient)]
+    #[case("lab=remote:https://lab.example.com;ca=/tls/a.pem;ca=/tls/b.pem;system_roots=false;insecure_skip_verify", "lab", RepositoryKind::Remote {
+        upstream_uri: "https://lab.example.com".to_string(),
+        mirror_uri: None,
+        tls: UpstreamTlsConfig {
+            client_certificate: None,
+            ca_certificates: vec!["/tls/a.pem".into(), "/tls/b.pem".into()],
+            use_system_roots: false,
+            insecure_skip_verify: true,
+        },
+        checksum_headers: default_checksum_headers(),
+        checksum_files: Default::default(),
+        signatures: Default::default(),
+    }, BlobStorageConfig::Transient)]
+    #[case("central=remote:https://repo1.maven.org/maven2;mirror=https://repo.maven.apache.org/maven2", "central", RepositoryKind::Remote {
+        upstream_uri: "https://repo1.maven.org/maven2".to_string(),
+        mirror_uri: Some("https://repo.maven.apache.org/maven2".to_string()),
+        tls: Default::default(),
+        checksum_headers: default_checksum_headers(),
+        checksum_files: Default::default(),
+        signatures: Default::default(),
+    }, BlobStorageConfig::Transient)]
+    #[case(" internal = hosted ", "internal", RepositoryKind::Hosted, BlobStorageConfig::Transient)]
+    #[cfg_attr(feature = "fs-storage", case("internal=hosted;fs=/data/internal", "internal", RepositoryKind::Hosted, BlobStorageConfig::Fs { root: "/data/internal".into(), verify_reads: false }))]
+    #[cfg_attr(feature = "fs-storage", case("internal=hosted;fs=/data/internal;verify_reads", "internal", RepositoryKind::Hosted, BlobStorageConfig::Fs { root: "/data/internal".into(), verify_reads: true }))]
+    fn test_parse_repository_config(#[case] s: &str, #[case] name: &str, #[case] kind: RepositoryKind, #[case] blob_storage: BlobStorageConfig) {
+        assert_eq!(parse_repository_config(s).unwrap(), RepositoryConfig {
+            name: name.to_string(),
+            kind,
+            blob_storage,
+            snapshot_retention: Default::default(),
+            access: Default::default(),
+            checksums: default_checksum_kinds(),
+            naming_policy: Default::default(),
+        });
+    }
+
+    #[test]
+    fn test_parse_snapshot_retention() {
+        let config = parse_repository_config("internal=hosted;keep_snapshots=5;snapshot_max_age_days=30").unwrap();
+        assert_eq!(config.snapshot_retention, SnapshotRetentionPolicy { keep_builds: Some(5), max_age_days: Some(30) });
+        assert!(parse_repository_config("internal=hosted;keep_snapshots=all").is_err());
+    }
+
+    #[test]
+    fn test_parse_checksum_headers() {
+        let config = parse_repository_config("gcs=remote:https://storage.googleapis.com/maven;checksum_headers=x-goog-hash=md5:goog_hash+x-checksum-sha1=sha1").unwrap();
+        let RepositoryKind::Remote { checksum_headers, .. } = config.kind else { panic!() };
+        assert_eq!(checksum_headers, vec![
+            ChecksumHeader::new("x-goog-hash", ChecksumKind::Md5, ChecksumHeaderFormat::GoogHash),
+            ChecksumHeader::new("x-checksum-sha1", ChecksumKind::Sha1, ChecksumHeaderFormat::Hex),
+        ]);
+        assert!(parse_repository_config("gcs=remote:https://storage.googleapis.com/maven;checksum_headers=x-goog-hash=crc32c").is_err());
+        assert!(parse_repository_config("internal=hosted;checksum_headers=x-checksum-sha1=sha1").is_err());
+    }
+
+    #[test]
+    fn test_parse_checksum_files() {
+        let config = parse_repository_config("plain=remote:https://maven.example.com;checksum_files=require").unwrap();
+        let RepositoryKind::Remote { checksum_files, .. } = config.kind else { panic!() };
+        assert_eq!(checksum_files, ChecksumFilePolicy::Require);
+        assert!(parse_repository_config("plain=remote:https://maven.example.com;checksum_files=always").is_err());
+        assert!(parse_repository_config("internal=hosted;checksum_files=require").is_err());
+    }
+
+    #[test]
+    fn test_parse_signatures() {
+        let config = parse_repository_config("signed=remote:https://maven.example.com;signatures=require").unwrap();
+        let RepositoryKind::Remote { signatures, .. } = &config.kind else { panic!() };
+        assert_eq!(*signatures, SignaturePolicy::Require);
+        assert!(parse_repository_config("signed=remote:https://maven.example.com;signatures=strict").is_err());
+        assert!(parse_repository_config("internal=hosted;signatures=require").is_err());
+
+        // verifying signatures requires keys
+        let manager_config = |signature_keyserver: Option<&str>| RepositoryManagerConfig {
+            repositories: vec![config.clone()],
+            signature_keyserver: signature_keyserver.map(|uri| uri.to_string()),
+            ..Default::default()
+        };
+        assert!(RepositoryManager::new(manager_config(None)).is_err());
+        assert!(RepositoryManager::new(manager_config(Some("https://keyserver.ubuntu.com"))).is_ok());
+    }
+
+    #[test]
+    fn test_parse_checksums() {
+        let config = parse_repository_config("internal=hosted;checksums=sha512+sha1").unwrap();
+        assert_eq!(config.checksums, vec![ChecksumKind::Sha512, ChecksumKind::Sha1]);
+        assert!(parse_repository_config("internal=hosted;checksums=sha3").is_err());
+    }
+
+    #[test]
+    fn test_parse_access() {
+        let config = parse_repository_config("internal=hosted;read=*;deploy=ci+alice").unwrap();
+        assert_eq!(config.access, AccessPolicy {
+            read: Some(vec!["*".to_string()]),
+            deploy: Some(vec!["ci".to_string(), "alice".to_string()]),
+        });
+    }
+
+    #[rstest]
+    #[case("central")]
+    #[case("=hosted")]
+    #[case("a/b=hosted")]
+    #[case("central=remote:")]
+    #[case("central=proxy:https://repo1.maven.org/maven2")]
+    #[case("internal=hosted;s3=bucket")]
+    #[case("mirror=remote:https://mirror.example.com;client_cert=/tls/client.pem")]
+    #[case("internal=hosted;client_cert=/tls/client.pem;client_key=/tls/client.key")]
+    #[case("internal=hosted;insecure_skip_verify")]
+    #[case("internal=hosted;mirror=https://mirror.example.com")]
+    #[case("internal=hosted;verify_reads")]
+    fn test_parse_repository_config_invalid(#[case] s: &str) {
+        assert!(parse_repository_config(s).is_err());
+    }
+
+    #[test]
+    fn test_requires_remote_repository() {
+        let config = RepositoryManagerConfig {
+            repositories: vec![parse_repository_config("internal=hosted").unwrap()],
+            ..Default::default()
+        };
+        assert!(RepositoryManager::new(config).is_err());
+    }
+
+    #[test]
+    fn test_find_repository() {
+        let config = RepositoryManagerConfig {
+            repositories: vec![
+                parse_repository_config("central=remote:https://repo1.maven.org/maven2").unwrap(),
+                parse_repository_config("internal=hosted").unwrap(),
+            ],
+            ..Default::default()
+        };
+        let manager = RepositoryManager::new(config).unwrap();
+
+        assert!(matches!(manager.find_repository("central/org/a/1.0/a-1.0.jar"), Some((RepositoryRef::Remote(_), "org/a/1.0/a-1.0.jar"))));
+        assert!(matches!(manager.find_repository("internal/org/"), Some((RepositoryRef::Hosted(_), "org/"))));
+        assert!(manager.find_repository("org/a/1.0/a-1.0.jar").is_none());
+        assert_eq!(manager.repository_names(), vec!["central".to_string(), "internal".to_string()]);
+    }
+
+    #[cfg(feature = "fs-storage")]
+    #[test]
+    fn test_orphan_grace_period() {
+        let config = RepositoryManagerConfig {
+            repositories: vec![
+                parse_repository_config("central=remote:https://repo1.maven.org/maven2").unwrap(),
+                parse_repository_config("internal=hosted").unwrap(),
+            ],
+            ..Default::default()
+        };
+        let manager = RepositoryManager::new(config).unwrap();
+
+        assert_eq!(manager.orphan_grace_period(None).unwrap(), DEFAULT_ORPHAN_GRACE_PERIOD);
+        assert_eq!(manager.orphan_grace_period(Some(600)).unwrap(), Duration::from_secs(600));
+        // shorter than the deploy timeout
+        assert!(manager.orphan_grace_period(Some(599)).is_err());
+        assert!(manager.orphan_grace_period(Some(0)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_check_deploy() {
+        let config = RepositoryManagerConfig {
+            repositories: vec![
+                parse_repository_config("central=remote:https://repo1.maven.org/maven2").unwrap(),
+                parse_repository_config("internal=hosted").unwrap(),
+            ],
+            ..Default::default()
+        };
+        let manager = RepositoryManager::new(config).unwrap();
+        let coordinates = parse_maven_path("com/example/lib/1.0/lib-1.0.jar").unwrap().coordinates;
+
+        assert!(manager.check_deploy(None, &coordinates).await.unwrap().is_empty());
+        assert!(manager.check_deploy(Some("internal"), &coordinates).await.unwrap().is_empty());
+        assert_eq!(manager.check_deploy(Some("central"), &coordinates).await.unwrap()[0].reason, DeployRefusalReason::UnknownRepository);
+
+        manager.blocked_versions.add_rule(VersionBlockingRule {
+            group_id: coordinates.group_id.clone(),
+            artifact_id: coordinates.artifact_id.clone(),
+            version_pattern: "1.*".to_string(),
+            message: "use 2.x".to_string(),
+        });
+        assert_eq!(manager.check_deploy(None, &coordinates).await.unwrap(), vec![DeployRefusal {
+            reason: DeployRefusalReason::Blocked,
+            message: "use 2.x".to_string(),
+        }]);
+    }
+
+    #[tokio::test]
+    async fn test_naming_policy() {
+        let config = RepositoryManagerConfig {
+            repositories: vec![
+                parse_repository_config("central=remote:https://repo1.maven.org/maven2").unwrap(),
+                parse_repository_config(r"strict=hosted;group_id_pattern=com\.example(\..+)?;version_format=semver").unwrap(),
+                parse_repository_config(r"lenient=hosted;group_id_pattern=com\.example(\..+)?;naming_enforcement=warn").unwrap(),
+            ],
+            ..Default::default()
+        };
+        let manager = RepositoryManager::new(config).unwrap();
+        let data = |content: &str| -> ContentStream { Box::pin(futures::stream::iter(vec![Ok(bytes::Bytes::from(content.to_string()))])) };
+
+        let e = manager.deploy("strict", "org/acme/lib/1.0/lib-1.0.jar", "ci", data("jar"), None).await.unwrap_err();
+        let e = e.downcast_ref::<NamingPolicyError>().unwrap();
+        assert_eq!(e.violations.len(), 2);
+        let coordinates = parse_maven_path("org/acme/lib/1.0/lib-1.0.jar").unwrap().coordinates;
+        let refusals = manager.check_deploy(Some("strict"), &coordinates).await.unwrap();
+        assert_eq!(refusals.iter().map(|r| r.reason).collect::<Vec<_>>(), vec![DeployRefusalReason::NamingPolicy, DeployRefusalReason::NamingPolicy]);
+
+        // warnings are recorded once per committed version
+        assert!(manager.check_deploy(Some("lenient"), &coordinates).await.unwrap().is_empty());
+        for file in ["lib-1.0.jar", "lib-1.0.pom"] {
+            let path = format!("org/acme/lib/1.0/{}", file);
+            manager.deploy("lenient", &path, "ci", data(file), None).await.unwrap();
+            manager.deploy("lenient", &format!("{}.sha1", path), "ci", data(&ChecksumKind::Sha1.checksum_of(file.as_bytes())), None).await.unwrap();
+        }
+        let outcome = manager.deploy("lenient", "org/acme/lib/maven-metadata.xml", "ci", data("<metadata/>"), None).await.unwrap();
+        assert!(matches!(outcome, Some(DeployOutcome::Committed(artifacts)) if artifacts.len() == 2));
+        let violations = manager.audit_log.recent_events().into_iter()
+            .filter(|event| event.kind == AuditEventKind::NamingPolicyViolation)
+            .collect::<Vec<_>>();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].subject, "lenient/org.acme:lib:1.0");
+
+        assert!(RepositoryManager::new(RepositoryManagerConfig {
+            repositories: vec![parse_repository_config("central=remote:https://repo1.maven.org/maven2;version_format=semver").unwrap()],
+            ..Default::default()
+        }).is_err());
+        assert!(parse_repository_config("strict=hosted;version_format=calver").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_warm_up_skips_without_fetching() {
+        let config = RepositoryManagerConfig {
+            repositories: vec![
+                parse_repository_config("central=remote:https://repo1.maven.org/maven2").unwrap(),
+                parse_repository_config("internal=hosted").unwrap(),
+            ],
+            ..Default::default()
+        };
+        let manager = RepositoryManager::new(config).unwrap();
+        manager.blocked_versions.add_rule(VersionBlockingRule {
+            group_id: MavenGroupId("org.a".to_string()),
+            artifact_id: MavenArtifactId("a".to_string()),
+            version_pattern: "1.*".to_string(),
+            message: "use 2.x".to_string(),
+        });
+
+        let plan = PrefetchPlan {
+            entries: ["internal/org/b/b/1.0/b-1.0.jar", "central/org/a/a/1.0/a-1.0.jar", "org/a/a/not-an-artifact"].iter()
+                .map(|path| PrefetchEntry { path: path.to_string(), requests: 1 })
+                .collect(),
+        };
+        let report = manager.warm_up(&plan, &TaskContext::detached()).await;
+        assert_eq!(report.fetched_artifacts, 0);
+        assert_eq!(report.skipped_artifacts, 2);
+        assert_eq!(report.failed_artifacts.len(), 1);
+        assert_eq!(report.failed_artifacts[0].0, "org/a/a/not-an-artifact");
+    }
+
+    struct RejectingHook;
+    #[async_trait]
+    impl RepositoryLifecycleHook for RejectingHook {
+        fn name(&self) -> &str {
+            "rejecting"
+        }
+        async fn on_create(&self, _repository: &str) -> anyhow::Result<()> {
+            Err(anyhow!("no capacity"))
+        }
+        async fn on_delete(&self, _repository: &str) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_clone_hosted_repository() {
+        let manager = RepositoryManager::new(RepositoryManagerConfig {
+            repositories: vec![
+                parse_repository_config("central=remote:http://127.0.0.1:1").unwrap(),
+                parse_repository_config("internal=hosted").unwrap(),
+            ],
+            ..Default::default()
+        }).unwrap();
+        let jar_path = "com/acme/lib/1.0-SNAPSHOT/lib-1.0-SNAPSHOT-20231114.221320-1.jar";
+        let pom_path = "com/acme/lib/1.0-SNAPSHOT/lib-1.0-SNAPSHOT-20231114.221320-1.pom";
+        for (path, content) in [
+            (jar_path.to_string(), "jar".to_string()),
+            (format!("{}.sha1", jar_path), hex::encode(Sha1::digest(b"jar"))),
+            (pom_path.to_string(), "pom".to_string()),
+            (format!("{}.sha1", pom_path), hex::encode(Sha1::digest(b"pom"))),
+            ("com/acme/lib/1.0-SNAPSHOT/maven-metadata.xml".to_string(), "<metadata/>".to_string()),
+        ] {
+            let data: ContentStream = Box::pin(futures::stream::iter(vec![Ok(bytes::Bytes::from(content))]));
+            manager.hosted["internal"].deploy(&path, data).await.unwrap();
+        }
+
+        assert!(manager.clone_repository("internal", "frozen").await.unwrap());
+        assert!(!manager.clone_repository("internal", "frozen").await.unwrap());
+        assert!(manager.clone_repository("unknown", "other").await.is_err());
+        assert_eq!(manager.clone_source("frozen"), Some("internal".to_string()));
+        assert_eq!(manager.clone_names(), vec!["frozen".to_string()]);
+
+        let clone = manager.get_clone("frozen").unwrap();
+        let jar = parse_maven_path(jar_path).unwrap();
+        let blob_key = clone.get_metadata_snapshot().await.unwrap().artifacts.iter()
+            .find(|(artifact_ref, _, _)| artifact_ref == &jar)
+            .map(|(_, blob_key, _)| *blob_key)
+            .unwrap();
+        let read_jar = || async {
+            let mut data = clone.get_artifact(&jar).await.unwrap().data;
+            data.next().await.unwrap().unwrap()
+        };
+        assert_eq!(read_jar().await, bytes::Bytes::from_static(b"jar"));
+
+        // the clone keeps serving artifacts deleted from its source
+        assert!(matches!(manager.delete("internal", jar_path, "ops").await.unwrap(), Some(DeleteOutcome::Deleted(_))));
+        assert!(manager.hosted["internal"].get_artifact(&jar).await.unwrap().is_none());
+        assert_eq!(read_jar().await, bytes::Bytes::from_static(b"jar"));
+        assert!(manager.is_blob_referenced(&blob_key).await.unwrap());
+
+        // afterwards, the blob is left to garbage collection
+        assert!(manager.remove_clone("frozen"));
+        assert!(!manager.remove_clone("frozen"));
+        assert!(manager.get_clone("frozen").is_none());
+        assert!(!manager.is_blob_referenced(&blob_key).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle_hook_aborts_clone() {
+        let manager = RepositoryManager::new(RepositoryManagerConfig {
+            lifecycle_hooks: RepositoryLifecycleHooks::new().with_hook(Arc::new(RejectingHook)),
+            ..Default::default()
+        }).unwrap();
+
+        assert!(manager.clone_repository("central", "snapshot").await.is_err());
+        assert!(manager.clone_names().is_empty());
+    }
+}
@@ -0,0 +1,228 @@
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use axum::body::{boxed, BoxBody};
+use axum::middleware::Next;
+use axum::response::Response;
+use bytes::Bytes;
+use hyper::{Body, HeaderMap, Request, StatusCode};
+use hyper::body::{HttpBody, SizeHint};
+use hyper::header::{HeaderName, HeaderValue};
+use tracing::{info, info_span, Instrument};
+use uuid::Uuid;
+
+use crate::util::latency_metrics::{LatencyKind, REQUEST_LATENCIES};
+
+pub const REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// incoming request ids that are longer than this (or contain anything but visible ASCII) are
+///  replaced rather than propagated, so that clients can not inject arbitrary text into the log
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+tokio::task_local! {
+    static CURRENT_REQUEST: Arc<RequestContext>;
+}
+
+/// What is known about a request while it is handled, for the access log
+struct RequestContext {
+    id: String,
+    cache_outcome: Mutex<Option<&'static str>>,
+}
+
+/// The id of the request the current task is handling, None outside of request handling (e.g.
+///  in spawned tasks)
+pub fn current_request_id() -> Option<String> {
+    CURRENT_REQUEST.try_with(|request| request.id.clone()).ok()
+}
+
+/// Records whether the current request was served from cache ('hit') or from upstream ('miss'),
+///  if there is a current request
+pub fn record_cache_outcome(outcome: &'static str) {
+    let _ = CURRENT_REQUEST.try_with(|request| *request.cache_outcome.lock().unwrap() = Some(outcome));
+}
+
+fn request_id(headers: &HeaderMap) -> String {
+    headers.get(REQUEST_ID)
+        .and_then(|h| h.to_str().ok())
+        .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN && id.chars().all(|c| c.is_ascii_graphic()))
+        .map(|id| id.to_string())
+        .unwrap_or_else(|| Uuid::new_v4().to_string())
+}
+
+/// Middleware that assigns each request an id (or propagates the client's 'X-Request-Id'), makes
+///  it part of all spans while the request is handled, returns it in the response and logs one
+///  'access_log' line per request once the response body is done.
+pub async fn access_log(request: Request<Body>, next: Next<Body>) -> Response {
+    let started = Instant::now();
+    let context = Arc::new(RequestContext {
+        id: request_id(request.headers()),
+        cache_outcome: Mutex::new(None),
+    });
+    let method = request.method().to_string();
+    let path = request.uri().path().to_string();
+
+    let span = info_span!("request", request_id = context.id.as_str());
+    let response = CURRENT_REQUEST.scope(context.clone(), next.run(request))
+        .instrument(span)
+        .await;
+
+    record_latency(&path, *context.cache_outcome.lock().unwrap(), response.status(), started.elapsed());
+
+    let (mut parts, body) = response.into_parts();
+    if let Ok(value) = HeaderValue::from_str(&context.id) {
+        parts.headers.insert(REQUEST_ID, value);
+    }
+    let body = AccessLogBody {
+        entry: AccessLogEntry {
+            context,
+            method,
+            path,
+            status: parts.status.as_u16(),
+            started,
+            bytes: 0,
+        },
+        body,
+    };
+    Response::from_parts(parts, boxed(body))
+}
+
+/// Successful artifact and metadata requests count towards [REQUEST_LATENCIES], with the time
+///  until the response headers were ready
+fn record_latency(path: &str, cache_outcome: Option<&'static str>, status: StatusCode, latency: Duration) {
+    if let Some(kind) = latency_kind(path, cache_outcome, status) {
+        REQUEST_LATENCIES.record(kind, latency);
+    }
+}
+
+fn latency_kind(path: &str, cache_outcome: Option<&'static str>, status: StatusCode) -> Option<LatencyKind> {
+    if !(status.is_success() || status == StatusCode::NOT_MODIFIED) {
+        return None;
+    }
+    match cache_outcome {
+        Some("hit") => Some(LatencyKind::CacheHit),
+        Some("miss") => Some(LatencyKind::CacheMiss),
+        _ if path.ends_with("/maven-metadata.xml") => Some(LatencyKind::Metadata),
+        _ => None,
+    }
+}
+
+struct AccessLogEntry {
+    context: Arc<RequestContext>,
+    method: String,
+    path: String,
+    status: u16,
+    started: Instant,
+    bytes: u64,
+}
+impl Drop for AccessLogEntry {
+    fn drop(&mut self) {
+        let cache = self.context.cache_outcome.lock().unwrap().unwrap_or("-");
+        info!(
+            target: "access_log",
+            request_id = self.context.id.as_str(),
+            method = self.method.as_str(),
+            path = self.path.as_str(),
+            status = self.status,
+            bytes = self.bytes,
+            cache,
+            duration_ms = self.started.elapsed().as_millis() as u64,
+        );
+    }
+}
+
+/// Counts the bytes of a response body, logging the request when the body is done or dropped
+///  (e.g. because the client went away)
+struct AccessLogBody {
+    entry: AccessLogEntry,
+    body: BoxBody,
+}
+impl HttpBody for AccessLogBody {
+    type Data = Bytes;
+    type Error = axum::Error;
+
+    fn poll_data(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, axum::Error>>> {
+        let result = Pin::new(&mut self.body).poll_data(cx);
+        if let Poll::Ready(Some(Ok(data))) = &result {
+            self.entry.bytes += data.len() as u64;
+        }
+        result
+    }
+
+    fn poll_trailers(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<Option<HeaderMap>, axum::Error>> {
+        Pin::new(&mut self.body).poll_trailers(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.body.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.body.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use axum::Router;
+    use axum::middleware;
+    use axum::routing::get;
+    use rstest::rstest;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    async fn handler() -> String {
+        record_cache_outcome("hit");
+        current_request_id().unwrap()
+    }
+
+    async fn get_with_request_id(request_id: Option<&str>) -> (Option<String>, String) {
+        let app = Router::new()
+            .route("/", get(handler))
+            .layer(middleware::from_fn(access_log));
+        let mut request = Request::builder().uri("/");
+        if let Some(request_id) = request_id {
+            request = request.header(REQUEST_ID, request_id);
+        }
+        let response = app.oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+        let header = response.headers().get(REQUEST_ID).map(|h| h.to_str().unwrap().to_string());
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (header, String::from_utf8_lossy(&body).to_string())
+    }
+
+    #[tokio::test]
+    async fn test_propagated_request_id() {
+        let (header, body) = get_with_request_id(Some("abc-123")).await;
+        assert_eq!(header.as_deref(), Some("abc-123"));
+        assert_eq!(body, "abc-123");
+    }
+
+    #[tokio::test]
+    async fn test_generated_request_id() {
+        let (header, body) = get_with_request_id(None).await;
+        assert!(Uuid::parse_str(&body).is_ok());
+        assert_eq!(header, Some(body));
+
+        let (header, body) = get_with_request_id(Some("no spaces")).await;
+        assert!(Uuid::parse_str(&body).is_ok());
+        assert_eq!(header, Some(body));
+    }
+
+    #[rstest]
+    #[case("/repo/central/a/b/1.0/b-1.0.jar", Some("hit"), StatusCode::OK, Some(LatencyKind::CacheHit))]
+    #[case("/repo/central/a/b/1.0/b-1.0.jar", Some("miss"), StatusCode::NOT_MODIFIED, Some(LatencyKind::CacheMiss))]
+    #[case("/repo/central/a/b/maven-metadata.xml", None, StatusCode::OK, Some(LatencyKind::Metadata))]
+    #[case("/repo/central/a/b/1.0/b-1.0.jar", Some("miss"), StatusCode::NOT_FOUND, None)]
+    #[case("/api/v1/search", None, StatusCode::OK, None)]
+    fn test_latency_kind(#[case] path: &str, #[case] cache_outcome: Option<&'static str>, #[case] status: StatusCode, #[case] expected: Option<LatencyKind>) {
+        assert_eq!(latency_kind(path, cache_outcome, status), expected);
+    }
+
+    #[test]
+    fn test_outside_of_request() {
+        assert_eq!(current_request_id(), None);
+        record_cache_outcome("miss");
+    }
+}
@@ -0,0 +1,105 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use serde::Deserialize;
+use sha2::{Digest as _, Sha256};
+use uuid::Uuid;
+
+/// what anonymized principals are replaced with in [AnonymizationMode::Drop]
+pub const ANONYMOUS: &str = "anonymous";
+const HASH_PREFIX: &str = "anon:";
+
+/// How principals are anonymized in audit events and statistics
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnonymizationMode {
+    /// replaced by a salted hash, so that events of the same principal can still be correlated
+    Hash,
+    /// replaced by 'anonymous'
+    Drop,
+}
+impl FromStr for AnonymizationMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<AnonymizationMode> {
+        match s {
+            "hash" => Ok(AnonymizationMode::Hash),
+            "drop" => Ok(AnonymizationMode::Drop),
+            _ => Err(anyhow!("invalid anonymization mode '{}', must be 'hash' or 'drop'", s)),
+        }
+    }
+}
+
+/// Anonymizes principals once they are older than the retention period, for privacy sensitive
+///  deployments. The salt is random per process, so hashes can not be correlated across restarts
+///  or reversed by hashing known user names.
+#[derive(Clone, Debug)]
+pub struct AnonymizationPolicy {
+    pub mode: AnonymizationMode,
+    pub retention: Duration,
+    salt: String,
+}
+impl AnonymizationPolicy {
+    pub fn new(mode: AnonymizationMode, retention: Duration) -> AnonymizationPolicy {
+        AnonymizationPolicy {
+            mode,
+            retention,
+            salt: Uuid::new_v4().to_string(),
+        }
+    }
+
+    pub fn with_salt(self, salt: impl Into<String>) -> AnonymizationPolicy {
+        AnonymizationPolicy {
+            salt: salt.into(),
+            ..self
+        }
+    }
+
+    /// Returns None if the principal is anonymized already
+    pub fn anonymize(&self, principal: &str) -> Option<String> {
+        if is_anonymized(principal) {
+            return None;
+        }
+        match self.mode {
+            AnonymizationMode::Hash => {
+                let hash = Sha256::digest(format!("{}:{}", self.salt, principal));
+                Some(format!("{}{}", HASH_PREFIX, &hex::encode(hash)[..16]))
+            }
+            AnonymizationMode::Drop => Some(ANONYMOUS.to_string()),
+        }
+    }
+}
+
+pub fn is_anonymized(principal: &str) -> bool {
+    principal == ANONYMOUS || principal.starts_with(HASH_PREFIX)
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::hash(AnonymizationMode::Hash)]
+    #[case::drop(AnonymizationMode::Drop)]
+    fn test_anonymize(#[case] mode: AnonymizationMode) {
+        let policy = AnonymizationPolicy::new(mode, Duration::ZERO).with_salt("salt");
+
+        let alice = policy.anonymize("alice").unwrap();
+        assert!(is_anonymized(&alice));
+        assert!(!alice.contains("alice"));
+        assert_eq!(policy.anonymize(&alice), None);
+        assert_eq!(policy.anonymize("alice").unwrap(), alice);
+
+        let bob = policy.anonymize("bob").unwrap();
+        assert_eq!(alice == bob, mode == AnonymizationMode::Drop);
+    }
+
+    #[test]
+    fn test_salt() {
+        let policy = AnonymizationPolicy::new(AnonymizationMode::Hash, Duration::ZERO);
+        assert_ne!(policy.clone().with_salt("a").anonymize("alice"), policy.with_salt("b").anonymize("alice"));
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tracing::info;
+
+use crate::util::anonymization::AnonymizationPolicy;
+
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+pub enum AuditEventKind {
+    BlockedVersionRequested,
+    BlockingRuleAdded,
+    BlockingRuleRemoved,
+    BlockingRulesFileReloaded,
+    AdvisoryAdded,
+    AdvisoryRemoved,
+    PluginRegistered,
+    PluginUnregistered,
+    OrphanPurged,
+    TtlOverrideSet,
+    TtlOverrideRemoved,
+    RepositoryCloned,
+    RepositoryCloneRemoved,
+    MetadataRestored,
+    DeployAnomaly,
+    ArtifactDeployed,
+    MetadataInconsistency,
+    NamingPolicyViolation,
+    ArtifactDeleted,
+    CacheInvalidated,
+    QuarantinePurged,
+    IntegrityManifestVerified,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+pub struct AuditEvent {
+    /// seconds since the UNIX epoch
+    pub timestamp: u64,
+    pub kind: AuditEventKind,
+    /// what the event refers to, typically a repository path or Maven coordinates
+    pub subject: String,
+    pub details: String,
+    /// who caused the event, if it was caused by a request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub principal: Option<String>,
+}
+
+/// Records security relevant events. All events are logged with the 'audit' tracing target, and
+///  the most recent events are kept in memory for querying through the admin API.
+pub struct AuditLog {
+    capacity: usize,
+    recent_events: Mutex<VecDeque<AuditEvent>>,
+}
+impl AuditLog {
+    pub fn new(capacity: usize) -> AuditLog {
+        AuditLog {
+            capacity,
+            recent_events: Default::default(),
+        }
+    }
+
+    pub fn record(&self, kind: AuditEventKind, subject: impl Into<String>, details: impl Into<String>) {
+        self.record_event(kind, subject.into(), details.into(), None);
+    }
+
+    /// Records an event caused by a principal, which can be anonymized later, see
+    ///  [AuditLog::anonymize]
+    pub fn record_by(&self, kind: AuditEventKind, subject: impl Into<String>, details: impl Into<String>, principal: &str) {
+        self.record_event(kind, subject.into(), details.into(), Some(principal.to_string()));
+    }
+
+    fn record_event(&self, kind: AuditEventKind, subject: String, details: String, principal: Option<String>) {
+        let event = AuditEvent {
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            kind,
+            subject,
+            details,
+            principal,
+        };
+
+        match &event.principal {
+            Some(principal) => info!(target: "audit", "{:?} {}: {} by {}", event.kind, event.subject, event.details, principal),
+            None => info!(target: "audit", "{:?} {}: {}", event.kind, event.subject, event.details),
+        }
+
+        let mut recent_events = self.recent_events.lock().unwrap();
+        if recent_events.len() >= self.capacity {
+            recent_events.pop_front();
+        }
+        recent_events.push_back(event);
+    }
+
+    /// Returns the most recent events, oldest first
+    pub fn recent_events(&self) -> Vec<AuditEvent> {
+        self.recent_events.lock().unwrap()
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Anonymizes the principals of events older than the policy's retention period. Returns the
+    ///  number of anonymized events.
+    pub fn anonymize(&self, policy: &AnonymizationPolicy) -> usize {
+        self.anonymize_at(policy, SystemTime::now())
+    }
+
+    fn anonymize_at(&self, policy: &AnonymizationPolicy, now: SystemTime) -> usize {
+        let cutoff = now.checked_sub(policy.retention)
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .unwrap_or(Duration::ZERO)
+            .as_secs();
+
+        let mut anonymized = 0;
+        for event in self.recent_events.lock().unwrap().iter_mut() {
+            if event.timestamp > cutoff {
+                continue;
+            }
+            if let Some(principal) = event.principal.as_ref().and_then(|p| policy.anonymize(p)) {
+                event.principal = Some(principal);
+                anonymized += 1;
+            }
+        }
+        anonymized
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::util::anonymization::{AnonymizationMode, ANONYMOUS};
+
+    use super::*;
+
+    #[test]
+    fn test_anonymize() {
+        let audit_log = AuditLog::new(10);
+        audit_log.record_by(AuditEventKind::ArtifactDeployed, "internal/a", "deployed", "alice");
+        audit_log.record(AuditEventKind::BlockingRuleAdded, "com.acme", "");
+
+        let policy = AnonymizationPolicy::new(AnonymizationMode::Drop, Duration::from_secs(3600));
+        assert_eq!(audit_log.anonymize(&policy), 0);
+        assert_eq!(audit_log.recent_events()[0].principal.as_deref(), Some("alice"));
+
+        let later = SystemTime::now() + Duration::from_secs(3601);
+        assert_eq!(audit_log.anonymize_at(&policy, later), 1);
+        assert_eq!(audit_log.anonymize_at(&policy, later), 0);
+        let events = audit_log.recent_events();
+        assert_eq!(events[0].principal.as_deref(), Some(ANONYMOUS));
+        assert_eq!(events[1].principal, None);
+    }
+}
@@ -1,10 +1,38 @@
 use std::pin::Pin;
+use std::time::SystemTime;
 
 use bytes::Bytes;
 use futures_core::Stream;
 
+/// A blob's data as a stream, with the metadata known when it is opened. This is the one type
+///  that all [BlobStorage](crate::blob::blob_storage::BlobStorage) implementations return, and
+///  downloads produce it as well, so data can be passed between them without conversion.
+///
+/// Storage backends know the checksums of the blobs they store, downloads only if upstream sends
+///  them. The content type is not part of a blob since it follows from the artifact's file
+///  extension, see [content_type_for_extension](crate::util::content_type::content_type_for_extension).
 pub struct Blob {
     pub data: Pin<Box<dyn Stream<Item = anyhow::Result<Bytes>> + Send + 'static>>,
     pub md5: Option<[u8;16]>,
     pub sha1: Option<[u8;20]>,
+    /// the data's length in bytes, if known in advance
+    pub size: Option<u64>,
+    /// the 'Last-Modified' timestamp of the artifact stored in the blob, if known
+    pub last_modified: Option<SystemTime>,
+    /// upstream response headers to be passed on to clients, as (name, value)
+    pub upstream_headers: Vec<(String, String)>,
+}
+
+/// A blob's metadata, available without opening its data
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct BlobStat {
+    pub size: u64,
+    pub md5: Option<[u8;16]>,
+    pub sha1: Option<[u8;20]>,
+    /// when the blob was stored
+    pub created: SystemTime,
+    /// see [Blob::last_modified]
+    pub last_modified: Option<SystemTime>,
+    /// see [Blob::upstream_headers]
+    pub upstream_headers: Vec<(String, String)>,
 }
@@ -0,0 +1,212 @@
+use std::ops::Range;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use bytes::Bytes;
+use futures::StreamExt;
+use futures_core::Stream;
+use serde::Deserialize;
+
+use crate::util::blob::Blob;
+
+/// A single range from a 'Range' header. Requests for several ranges are answered with the
+///  entire content, which the HTTP spec allows, so they are not supported here.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum ByteRange {
+    /// 'bytes=100-'
+    From(u64),
+    /// 'bytes=100-199', with the last byte included
+    FromTo(u64, u64),
+    /// 'bytes=-100', i.e. the last 100 bytes
+    Suffix(u64),
+}
+impl ByteRange {
+    /// None for headers that are to be ignored, i.e. invalid or unsupported ones
+    pub fn parse(header: &str) -> Option<ByteRange> {
+        let spec = header.trim().strip_prefix("bytes=")?;
+        if spec.contains(',') {
+            return None;
+        }
+        match spec.trim().split_once('-')? {
+            ("", suffix) => Some(ByteRange::Suffix(suffix.parse().ok()?)),
+            (start, "") => Some(ByteRange::From(start.parse().ok()?)),
+            (start, end) => {
+                let (start, end) = (start.parse().ok()?, end.parse().ok()?);
+                match start <= end {
+                    true => Some(ByteRange::FromTo(start, end)),
+                    false => None,
+                }
+            }
+        }
+    }
+
+    /// e.g. for passing the range on to upstream
+    pub fn to_header(&self) -> String {
+        match *self {
+            ByteRange::From(start) => format!("bytes={}-", start),
+            ByteRange::FromTo(start, end) => format!("bytes={}-{}", start, end),
+            ByteRange::Suffix(len) => format!("bytes=-{}", len),
+        }
+    }
+
+    /// The bytes to serve from content of the given size, None if the range is unsatisfiable
+    pub fn resolve(&self, size: u64) -> Option<Range<u64>> {
+        match *self {
+            ByteRange::From(start) if start < size => Some(start..size),
+            ByteRange::FromTo(start, end) if start < size => Some(start..size.min(end.saturating_add(1))),
+            ByteRange::Suffix(len) if len > 0 && size > 0 => Some(size.saturating_sub(len)..size),
+            _ => None,
+        }
+    }
+}
+
+/// The result of a 'Range' request for an artifact
+pub enum BlobRange {
+    Partial {
+        /// with the range's data and size
+        blob: Blob,
+        range: Range<u64>,
+        total_size: u64,
+    },
+    Unsatisfiable {
+        total_size: u64,
+    },
+}
+
+/// Parses a 'Content-Range' header into the range (None for 'bytes */1000', i.e. an
+///  unsatisfiable range) and the total size
+pub fn parse_content_range(header: &str) -> Option<(Option<Range<u64>>, u64)> {
+    let (range, total_size) = header.trim().strip_prefix("bytes ")?.split_once('/')?;
+    let total_size = total_size.parse().ok()?;
+    if range == "*" {
+        return Some((None, total_size));
+    }
+    let (start, end) = range.split_once('-')?;
+    let (start, end): (u64, u64) = (start.parse().ok()?, end.parse().ok()?);
+    match start <= end && end < total_size {
+        true => Some((Some(start..end + 1), total_size)),
+        false => None,
+    }
+}
+
+/// How remote repositories answer 'Range' requests for artifacts that are not cached yet
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UncachedRangePolicy {
+    /// the entire artifact is downloaded and cached before the range is served from it
+    #[default]
+    Download,
+    /// the range is requested from upstream and served without caching anything
+    Passthrough,
+    /// like 'passthrough', but the entire artifact is downloaded and cached in the background
+    PassthroughAndFill,
+}
+impl FromStr for UncachedRangePolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<UncachedRangePolicy> {
+        match s {
+            "download" => Ok(UncachedRangePolicy::Download),
+            "passthrough" => Ok(UncachedRangePolicy::Passthrough),
+            "passthrough_and_fill" => Ok(UncachedRangePolicy::PassthroughAndFill),
+            _ => Err(anyhow!("unknown uncached range policy {}", s)),
+        }
+    }
+}
+
+/// Passes on the part of the data that is within the range, reading the data before it and ending
+///  after it
+pub fn slice_stream(data: impl Stream<Item=anyhow::Result<Bytes>> + Send, range: Range<u64>) -> impl Stream<Item=anyhow::Result<Bytes>> + Send {
+    data
+        .scan(0u64, move |offset, chunk| {
+            let sliced = match chunk {
+                Err(e) => Some(Some(Err(e))),
+                Ok(chunk) => {
+                    let chunk_start = *offset;
+                    *offset += chunk.len() as u64;
+                    match chunk_start < range.end {
+                        true => {
+                            let from = range.start.saturating_sub(chunk_start).min(chunk.len() as u64) as usize;
+                            let to = (range.end - chunk_start).min(chunk.len() as u64) as usize;
+                            Some(Some(Ok(chunk.slice(from..to))))
+                        }
+                        false => None,
+                    }
+                }
+            };
+            futures::future::ready(sliced)
+        })
+        .filter_map(|chunk| async move {
+            match chunk {
+                Some(Ok(bytes)) if bytes.is_empty() => None,
+                other => other,
+            }
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("bytes=100-", Some(ByteRange::From(100)))]
+    #[case("bytes=100-199", Some(ByteRange::FromTo(100, 199)))]
+    #[case("bytes=-100", Some(ByteRange::Suffix(100)))]
+    #[case("bytes=200-100", None)]
+    #[case("bytes=0-1,5-6", None)]
+    #[case("items=0-1", None)]
+    #[case("bytes=a-", None)]
+    #[case("bytes=0-18446744073709551615", Some(ByteRange::FromTo(0, u64::MAX)))]
+    fn test_parse(#[case] header: &str, #[case] expected: Option<ByteRange>) {
+        assert_eq!(ByteRange::parse(header), expected);
+    }
+
+    #[rstest]
+    #[case(ByteRange::From(3), Some(3..10))]
+    #[case(ByteRange::From(10), None)]
+    #[case(ByteRange::FromTo(2, 4), Some(2..5))]
+    #[case(ByteRange::FromTo(2, 400), Some(2..10))]
+    #[case(ByteRange::FromTo(0, u64::MAX), Some(0..10))]
+    #[case(ByteRange::Suffix(4), Some(6..10))]
+    #[case(ByteRange::Suffix(400), Some(0..10))]
+    #[case(ByteRange::Suffix(0), None)]
+    fn test_resolve(#[case] range: ByteRange, #[case] expected: Option<Range<u64>>) {
+        assert_eq!(range.resolve(10), expected);
+    }
+
+    #[rstest]
+    #[case(ByteRange::From(3))]
+    #[case(ByteRange::FromTo(2, 4))]
+    #[case(ByteRange::Suffix(4))]
+    fn test_to_header(#[case] range: ByteRange) {
+        assert_eq!(ByteRange::parse(&range.to_header()), Some(range));
+    }
+
+    #[rstest]
+    #[case("bytes 2-4/10", Some((Some(2..5), 10)))]
+    #[case("bytes */10", Some((None, 10)))]
+    #[case("bytes 2-10/10", None)]
+    #[case("bytes 2-4/*", None)]
+    #[case("items 2-4/10", None)]
+    fn test_parse_content_range(#[case] header: &str, #[case] expected: Option<(Option<Range<u64>>, u64)>) {
+        assert_eq!(parse_content_range(header), expected);
+    }
+
+    #[rstest]
+    #[case(0..9, "abcdefghi")]
+    #[case(2..7, "cdefg")]
+    #[case(3..6, "def")]
+    #[case(8..9, "i")]
+    #[tokio::test]
+    async fn test_slice_stream(#[case] range: Range<u64>, #[case] expected: &str) {
+        let data = futures::stream::iter(["abc", "def", "ghi"].map(|s| Ok(Bytes::from_static(s.as_bytes()))));
+        let sliced = slice_stream(data, range)
+            .map(|chunk| chunk.unwrap())
+            .collect::<Vec<_>>()
+            .await
+            .concat();
+        assert_eq!(sliced, expected.as_bytes());
+    }
+}
@@ -0,0 +1,54 @@
+use hyper::header::{CACHE_CONTROL, HeaderValue, VARY};
+use hyper::http::response;
+use hyper::Response;
+
+use crate::maven::coordinates::{MavenArtifactRef, MavenVersion};
+
+/// The caching policy for responses, determining 'Cache-Control' and 'Vary' headers. This is what
+///  makes it safe to put an HTTP caching proxy or CDN in front of the vault:
+///
+/// * Release artifacts rarely change once published, but they can still be deleted, purged or
+///   blocked, so caches keep them for hours rather than forever - and never as 'immutable'
+/// * Snapshots can be replaced at any time, so caches must revalidate
+/// * Admin API responses and policy decisions (e.g. blocked versions) must never be cached since
+///   they can change at any time and may contain sensitive data
+///
+/// Cacheable responses vary on 'Authorization' so that a shared cache never serves a response
+///  obtained with one principal's credentials to a different (or anonymous) client.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum CachePolicy {
+    Release,
+    Revalidate,
+    NoStore,
+}
+impl CachePolicy {
+    pub fn for_artifact(artifact_ref: &MavenArtifactRef) -> CachePolicy {
+        match artifact_ref.coordinates.version {
+            MavenVersion::Release(_) => CachePolicy::Release,
+            MavenVersion::Snapshot { .. } => CachePolicy::Revalidate,
+        }
+    }
+
+    pub fn cache_control(&self) -> &'static str {
+        match self {
+            CachePolicy::Release => "public, max-age=3600, s-maxage=86400",
+            CachePolicy::Revalidate => "public, no-cache",
+            CachePolicy::NoStore => "no-store",
+        }
+    }
+
+    pub fn apply(&self, response_builder: response::Builder) -> response::Builder {
+        let response_builder = response_builder.header(CACHE_CONTROL, self.cache_control());
+        match self {
+            CachePolicy::NoStore => response_builder,
+            _ => response_builder.header(VARY, "Authorization"),
+        }
+    }
+}
+
+/// Marks a response as 'no-store', intended for use with [axum::middleware::map_response] on
+///  routes that must never be cached
+pub async fn no_store<B>(mut response: Response<B>) -> Response<B> {
+    response.headers_mut().insert(CACHE_CONTROL, HeaderValue::from_static(CachePolicy::NoStore.cache_control()));
+    response
+}
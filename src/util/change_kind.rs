@@ -1,6 +1,16 @@
 
 
+/// The outcome of a mutation, allowing callers to distinguish actual changes from no-ops (e.g. for
+///  returning '201 Created' vs. '200 OK', or skipping audit log entries)
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
 pub enum ChangeKind {
     Updated,
     Inserted,
-}
\ No newline at end of file
+    /// the mutation was a no-op, e.g. because identical data was already present
+    Unchanged,
+}
+impl ChangeKind {
+    pub fn is_change(&self) -> bool {
+        !matches!(self, ChangeKind::Unchanged)
+    }
+}
@@ -0,0 +1,122 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use hex::{FromHex, ToHex};
+use serde::Deserialize;
+use sha1::{Digest, Sha1};
+
+/// checksum files are a hash and maybe a file name, anything longer is not a checksum file
+pub const MAX_CHECKSUM_FILE_SIZE: usize = 1024;
+
+/// Whether downloads that come without a checksum header are validated against upstream's '.sha1'
+///  file (or its '.md5' file if there is no '.sha1' file), which is requested in parallel to the
+///  download
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChecksumFilePolicy {
+    /// downloads without a checksum header or a checksum file fail
+    Require,
+    /// a missing or invalid checksum file is accepted
+    VerifyIfPresent,
+    /// checksum files are not requested
+    #[default]
+    Ignore,
+}
+impl FromStr for ChecksumFilePolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<ChecksumFilePolicy> {
+        match s {
+            "require" => Ok(ChecksumFilePolicy::Require),
+            "verify_if_present" => Ok(ChecksumFilePolicy::VerifyIfPresent),
+            "ignore" => Ok(ChecksumFilePolicy::Ignore),
+            _ => Err(anyhow!("invalid checksum file policy '{}', must be 'require', 'verify_if_present' or 'ignore'", s)),
+        }
+    }
+}
+
+/// Upstream content that does not match its published checksum file
+#[derive(Debug)]
+pub struct ChecksumMismatchError {
+    pub path: String,
+    pub expected: String,
+    pub actual: String,
+}
+impl Display for ChecksumMismatchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "checksum mismatch for {}: checksum file has {}, content has {}", self.path, self.expected, self.actual)
+    }
+}
+impl std::error::Error for ChecksumMismatchError {}
+
+/// Checks if a download failed because the content did not match its checksum file
+pub fn is_checksum_mismatch(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<ChecksumMismatchError>().is_some()
+}
+
+/// Extracts the hash from a '.sha1' file's content. Some tools write 'sha1sum' style files with
+///  the file name after the hash, so only the first token is used.
+pub fn parse_sha1_file(content: &str) -> Option<[u8;20]> {
+    content.split_whitespace()
+        .next()
+        .and_then(|s| <[u8;20]>::from_hex(s).ok())
+}
+
+/// Extracts the hash from a '.md5' file's content, see [parse_sha1_file]
+pub fn parse_md5_file(content: &str) -> Option<[u8;16]> {
+    content.split_whitespace()
+        .next()
+        .and_then(|s| <[u8;16]>::from_hex(s).ok())
+}
+
+/// Verifies data against the content of its '.sha1' file. A checksum file without a parseable
+///  hash is treated as a mismatch since it is equally unfit for verification.
+pub fn verify_sha1_file(path: &str, data: &[u8], sha1_file: &str) -> Result<(), ChecksumMismatchError> {
+    let actual: [u8;20] = Sha1::digest(data).into();
+    if parse_sha1_file(sha1_file) == Some(actual) {
+        return Ok(());
+    }
+    Err(ChecksumMismatchError {
+        path: path.to_string(),
+        expected: sha1_file.trim().to_string(),
+        actual: actual.encode_hex(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::*;
+
+    // sha1 of 'abc'
+    const ABC_SHA1: &str = "a9993e364706816aba3e25717850c26c9cd0d89d";
+
+    #[rstest]
+    #[case(ABC_SHA1, true)]
+    #[case("a9993e364706816aba3e25717850c26c9cd0d89d\n", true)]
+    #[case("A9993E364706816ABA3E25717850C26C9CD0D89D", true)]
+    #[case("a9993e364706816aba3e25717850c26c9cd0d89d  maven-metadata.xml", true)]
+    #[case("0000000000000000000000000000000000000000", false)]
+    #[case("a9993e36", false)]
+    #[case("", false)]
+    fn test_verify_sha1_file(#[case] sha1_file: &str, #[case] expected_ok: bool) {
+        assert_eq!(verify_sha1_file("maven-metadata.xml", b"abc", sha1_file).is_ok(), expected_ok);
+    }
+
+    #[rstest]
+    #[case("900150983cd24fb0d6963f7d28e17f72", true)]
+    #[case("900150983cd24fb0d6963f7d28e17f72  abc.jar\n", true)]
+    #[case(ABC_SHA1, false)]
+    fn test_parse_md5_file(#[case] md5_file: &str, #[case] expected_some: bool) {
+        assert_eq!(parse_md5_file(md5_file).is_some(), expected_some);
+    }
+
+    #[test]
+    fn test_is_checksum_mismatch() {
+        let e: anyhow::Error = verify_sha1_file("maven-metadata.xml", b"abd", ABC_SHA1).unwrap_err().into();
+        assert!(is_checksum_mismatch(&e));
+        assert!(!is_checksum_mismatch(&anyhow::anyhow!("connection refused")));
+    }
+}
@@ -0,0 +1,220 @@
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use hyper::HeaderMap;
+use hyper::header::HeaderName;
+use serde::Deserialize;
+
+use crate::maven::paths::ChecksumKind;
+
+/// How a checksum is encoded in a response header
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum ChecksumHeaderFormat {
+    /// e.g. Artifactory's 'X-Checksum-Sha1'
+    Hex,
+    /// e.g. 'Content-MD5' of Azure Blob Storage
+    Base64,
+    /// a hex checksum in an entity tag, with quotes and 'W/' prefix - other entity tags are ignored
+    ETag,
+    /// comma separated '<algorithm>=<base64>' entries as in GCS's 'x-goog-hash', ignored if there
+    ///  is no entry for the algorithm
+    GoogHash,
+}
+impl FromStr for ChecksumHeaderFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<ChecksumHeaderFormat> {
+        match s {
+            "hex" => Ok(ChecksumHeaderFormat::Hex),
+            "base64" => Ok(ChecksumHeaderFormat::Base64),
+            "etag" => Ok(ChecksumHeaderFormat::ETag),
+            "goog_hash" => Ok(ChecksumHeaderFormat::GoogHash),
+            _ => Err(anyhow!("invalid checksum header format '{}', must be 'hex', 'base64', 'etag' or 'goog_hash'", s)),
+        }
+    }
+}
+
+/// An upstream response header that carries a checksum of the body, which downloads are validated
+///  against. Configured as '<header name>=<sha1|md5>[:<format>]', e.g. 'content-md5=md5:base64',
+///  with 'hex' as the default format.
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize)]
+#[serde(try_from = "String")]
+pub struct ChecksumHeader {
+    pub name: HeaderName,
+    pub kind: ChecksumKind,
+    pub format: ChecksumHeaderFormat,
+}
+impl ChecksumHeader {
+    pub fn new(name: &str, kind: ChecksumKind, format: ChecksumHeaderFormat) -> ChecksumHeader {
+        ChecksumHeader {
+            name: HeaderName::from_str(name).unwrap(),
+            kind,
+            format,
+        }
+    }
+
+    /// The raw checksum from the response headers, None if the header is missing or does not
+    ///  apply. Fails for a hex or base64 header that is not a valid checksum.
+    pub fn extract(&self, headers: &HeaderMap) -> anyhow::Result<Option<Vec<u8>>> {
+        let value = match headers.get(&self.name).and_then(|h| h.to_str().ok()) {
+            Some(value) => value.trim(),
+            None => return Ok(None),
+        };
+        let checksum = match self.format {
+            ChecksumHeaderFormat::Hex => hex::decode(value).ok(),
+            ChecksumHeaderFormat::Base64 => STANDARD.decode(value).ok(),
+            ChecksumHeaderFormat::ETag => {
+                let etag = value.strip_prefix("W/").unwrap_or(value).trim_matches('"');
+                match hex::decode(etag) {
+                    Ok(checksum) if checksum.len() == self.len() => Some(checksum),
+                    _ => return Ok(None),
+                }
+            }
+            ChecksumHeaderFormat::GoogHash => match value.split(',')
+                .filter_map(|entry| entry.trim().split_once('='))
+                .find(|(algorithm, _)| *algorithm == self.kind.name())
+            {
+                Some((_, checksum)) => STANDARD.decode(checksum).ok(),
+                None => return Ok(None),
+            },
+        };
+        match checksum {
+            Some(checksum) if checksum.len() == self.len() => Ok(Some(checksum)),
+            _ => Err(anyhow!("invalid {} checksum in header '{}': '{}'", self.kind.name(), self.name, value)),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self.kind {
+            ChecksumKind::Md5 => 16,
+            _ => 20,
+        }
+    }
+}
+impl FromStr for ChecksumHeader {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<ChecksumHeader> {
+        let (name, rest) = s.split_once('=')
+            .ok_or_else(|| anyhow!("checksum header '{}' must be '<header name>=<sha1|md5>[:<format>]'", s))?;
+        let name = HeaderName::from_str(name.trim())
+            .map_err(|_| anyhow!("invalid header name in checksum header '{}'", s))?;
+        let (kind, format) = match rest.split_once(':') {
+            Some((kind, format)) => (kind, format.parse()?),
+            None => (rest, ChecksumHeaderFormat::Hex),
+        };
+        // downloads can only be validated against the checksums blob storage keeps
+        let kind = ChecksumKind::for_file_extension(&format!(".{}", kind.trim()))
+            .filter(|kind| kind.is_stored())
+            .ok_or_else(|| anyhow!("unsupported checksum '{}' in checksum header '{}', must be 'sha1' or 'md5'", kind, s))?;
+        Ok(ChecksumHeader { name, kind, format })
+    }
+}
+impl TryFrom<String> for ChecksumHeader {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> anyhow::Result<ChecksumHeader> {
+        s.parse()
+    }
+}
+
+/// Artifactory's and Nexus' 'X-Checksum-*', checksums in GCS custom metadata as uploaded by
+///  Maven wagons, and SHA-1 entity tags - the first header of each kind that is present is used
+pub fn default_checksum_headers() -> Vec<ChecksumHeader> {
+    vec![
+        ChecksumHeader::new("x-checksum-sha1", ChecksumKind::Sha1, ChecksumHeaderFormat::Hex),
+        ChecksumHeader::new("x-goog-meta-checksum-sha1", ChecksumKind::Sha1, ChecksumHeaderFormat::Hex),
+        ChecksumHeader::new("etag", ChecksumKind::Sha1, ChecksumHeaderFormat::ETag),
+        ChecksumHeader::new("x-checksum-md5", ChecksumKind::Md5, ChecksumHeaderFormat::Hex),
+        ChecksumHeader::new("x-goog-meta-checksum-md5", ChecksumKind::Md5, ChecksumHeaderFormat::Hex),
+    ]
+}
+
+/// The checksum of the given kind from the first configured header that has one
+pub fn find_checksum(checksum_headers: &[ChecksumHeader], kind: ChecksumKind, headers: &HeaderMap) -> anyhow::Result<Option<Vec<u8>>> {
+    for checksum_header in checksum_headers.iter().filter(|h| h.kind == kind) {
+        if let Some(checksum) = checksum_header.extract(headers)? {
+            return Ok(Some(checksum));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod test {
+    use hyper::header::HeaderValue;
+    use rstest::rstest;
+
+    use super::*;
+
+    const SHA1: &str = "a9993e364706816aba3e25717850c26c9cd0d89d";
+    const MD5: &str = "900150983cd24fb0d6963f7d28e17f72";
+
+    fn headers(entries: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in entries {
+            headers.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[rstest]
+    #[case::hex("x-checksum-sha1=sha1", "x-checksum-sha1", SHA1)]
+    #[case::etag("etag=sha1:etag", "etag", "\"a9993e364706816aba3e25717850c26c9cd0d89d\"")]
+    #[case::weak_etag("etag=sha1:etag", "etag", "W/\"a9993e364706816aba3e25717850c26c9cd0d89d\"")]
+    #[case::base64("x-sha1=sha1:base64", "x-sha1", "qZk+NkcGgWq6PiVxeFDCbJzQ2J0=")]
+    fn test_extract_sha1(#[case] config: &str, #[case] name: &'static str, #[case] value: &str) {
+        let checksum_header = config.parse::<ChecksumHeader>().unwrap();
+        assert_eq!(checksum_header.extract(&headers(&[(name, value)])).unwrap(), Some(hex::decode(SHA1).unwrap()));
+    }
+
+    #[rstest]
+    #[case::content_md5("content-md5=md5:base64", "content-md5", "kAFQmDzST7DWlj99KOF/cg==")]
+    #[case::goog_hash("x-goog-hash=md5:goog_hash", "x-goog-hash", "crc32c=n03x6A==, md5=kAFQmDzST7DWlj99KOF/cg==")]
+    fn test_extract_md5(#[case] config: &str, #[case] name: &'static str, #[case] value: &str) {
+        let checksum_header = config.parse::<ChecksumHeader>().unwrap();
+        assert_eq!(checksum_header.extract(&headers(&[(name, value)])).unwrap(), Some(hex::decode(MD5).unwrap()));
+    }
+
+    #[test]
+    fn test_not_applicable() {
+        let etag = "etag=sha1:etag".parse::<ChecksumHeader>().unwrap();
+        assert_eq!(etag.extract(&headers(&[("etag", "\"5f3c-abc\"")])).unwrap(), None);
+        assert_eq!(etag.extract(&headers(&[])).unwrap(), None);
+
+        let goog_hash = "x-goog-hash=md5:goog_hash".parse::<ChecksumHeader>().unwrap();
+        assert_eq!(goog_hash.extract(&headers(&[("x-goog-hash", "crc32c=n03x6A==")])).unwrap(), None);
+    }
+
+    #[rstest]
+    #[case::not_hex("x-checksum-sha1=sha1", "xyz")]
+    #[case::wrong_length("x-checksum-sha1=sha1", MD5)]
+    #[case::not_base64("content-md5=md5:base64", "%%%")]
+    fn test_extract_invalid(#[case] config: &str, #[case] value: &str) {
+        let checksum_header = config.parse::<ChecksumHeader>().unwrap();
+        let name = checksum_header.name.clone();
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_str(value).unwrap());
+        assert!(checksum_header.extract(&headers).is_err());
+    }
+
+    #[rstest]
+    #[case("x-checksum-sha1")]
+    #[case("x-checksum-sha256=sha256")]
+    #[case("x-checksum-md5=md5:binary")]
+    #[case("in valid=md5")]
+    fn test_parse_invalid(#[case] config: &str) {
+        assert!(config.parse::<ChecksumHeader>().is_err());
+    }
+
+    #[test]
+    fn test_find_checksum() {
+        let headers = headers(&[("etag", "\"5f3c-abc\""), ("x-goog-meta-checksum-sha1", SHA1), ("x-checksum-md5", MD5)]);
+        let checksum_headers = default_checksum_headers();
+        assert_eq!(find_checksum(&checksum_headers, ChecksumKind::Sha1, &headers).unwrap(), Some(hex::decode(SHA1).unwrap()));
+        assert_eq!(find_checksum(&checksum_headers, ChecksumKind::Md5, &headers).unwrap(), Some(hex::decode(MD5).unwrap()));
+        assert_eq!(find_checksum(&checksum_headers[..2], ChecksumKind::Md5, &headers).unwrap(), None);
+    }
+}
@@ -0,0 +1,88 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Stops requests to an upstream server after a number of consecutive failures, so that clients
+///  get a quick answer instead of waiting for timeouts while the server is down. After
+///  `open_duration`, requests are let through again, and the next failure reopens the breaker.
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    open_duration: Duration,
+    state: Mutex<BreakerState>,
+}
+
+#[derive(Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    /// when the breaker was opened, and the corresponding wall clock time for reporting
+    opened: Option<(Instant, SystemTime)>,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> CircuitBreaker {
+        CircuitBreaker::new(5, Duration::from_secs(30))
+    }
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, open_duration: Duration) -> CircuitBreaker {
+        CircuitBreaker {
+            failure_threshold,
+            open_duration,
+            state: Default::default(),
+        }
+    }
+
+    /// None if requests are allowed, the time at which they will be allowed again otherwise
+    pub fn retry_at(&self) -> Option<SystemTime> {
+        let state = self.state.lock().unwrap();
+        let (opened, opened_wall_clock) = state.opened?;
+        match opened.elapsed() < self.open_duration {
+            true => Some(opened_wall_clock + self.open_duration),
+            false => None,
+        }
+    }
+
+    pub fn record_success(&self) {
+        *self.state.lock().unwrap() = Default::default();
+    }
+
+    pub fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures += 1;
+        if state.consecutive_failures >= self.failure_threshold {
+            state.opened = Some((Instant::now(), SystemTime::now()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_opens_after_threshold() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+        breaker.record_failure();
+        assert!(breaker.retry_at().is_none());
+        breaker.record_failure();
+
+        let retry_at = breaker.retry_at().unwrap();
+        assert!(retry_at > SystemTime::now() + Duration::from_secs(50));
+    }
+
+    #[test]
+    fn test_success_resets() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+        breaker.record_failure();
+        breaker.record_success();
+        breaker.record_failure();
+        assert!(breaker.retry_at().is_none());
+    }
+
+    #[test]
+    fn test_half_open_after_duration() {
+        let breaker = CircuitBreaker::new(1, Duration::ZERO);
+        breaker.record_failure();
+        assert!(breaker.retry_at().is_none());
+    }
+}
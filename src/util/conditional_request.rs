@@ -0,0 +1,87 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use hex::ToHex;
+use hyper::header::{HeaderMap, IF_MODIFIED_SINCE, IF_NONE_MATCH};
+
+/// Blobs are identified by their content, so their SHA1 is a strong entity tag that is the same
+///  on all instances and survives restarts
+pub fn etag_for_sha1(sha1: &[u8;20]) -> String {
+    format!("\"{}\"", sha1.encode_hex::<String>())
+}
+
+pub fn is_conditional(headers: &HeaderMap) -> bool {
+    headers.contains_key(IF_NONE_MATCH) || headers.contains_key(IF_MODIFIED_SINCE)
+}
+
+/// True if a conditional GET can be answered with '304 Not Modified'. 'If-None-Match' takes
+///  precedence over 'If-Modified-Since', which is ignored if it can not be parsed.
+pub fn is_not_modified(headers: &HeaderMap, etag: Option<&str>, last_modified: Option<SystemTime>) -> bool {
+    if let Some(if_none_match) = headers.get(IF_NONE_MATCH) {
+        let (if_none_match, etag) = match (if_none_match.to_str(), etag) {
+            (Ok(if_none_match), Some(etag)) => (if_none_match, etag),
+            _ => return false,
+        };
+        // weak comparison, as required for 'If-None-Match'
+        return if_none_match.split(',')
+            .map(str::trim)
+            .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag);
+    }
+
+    let if_modified_since = headers.get(IF_MODIFIED_SINCE)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| httpdate::parse_http_date(h).ok());
+    match (if_modified_since, last_modified) {
+        // HTTP dates have a resolution of seconds
+        (Some(if_modified_since), Some(last_modified)) => truncate_to_secs(last_modified) <= if_modified_since,
+        _ => false,
+    }
+}
+
+fn truncate_to_secs(t: SystemTime) -> SystemTime {
+    match t.duration_since(UNIX_EPOCH) {
+        Ok(d) => UNIX_EPOCH + Duration::from_secs(d.as_secs()),
+        Err(_) => t,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hyper::header::HeaderValue;
+    use rstest::rstest;
+
+    use super::*;
+
+    const ETAG: &str = "\"a9993e364706816aba3e25717850c26c9cd0d89d\"";
+
+    fn headers(name: &str, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(hyper::header::HeaderName::from_bytes(name.as_bytes()).unwrap(), HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_etag_for_sha1() {
+        let sha1 = hex::decode("a9993e364706816aba3e25717850c26c9cd0d89d").unwrap();
+        assert_eq!(etag_for_sha1(&sha1.try_into().unwrap()), ETAG);
+    }
+
+    #[rstest]
+    #[case("if-none-match", ETAG, true)]
+    #[case("if-none-match", "\"other\", W/\"a9993e364706816aba3e25717850c26c9cd0d89d\"", true)]
+    #[case("if-none-match", "*", true)]
+    #[case("if-none-match", "\"other\"", false)]
+    #[case("if-modified-since", "Tue, 10 Oct 2023 13:55:36 GMT", true)]
+    #[case("if-modified-since", "Tue, 10 Oct 2023 13:55:35 GMT", false)]
+    #[case("if-modified-since", "yesterday", false)]
+    fn test_is_not_modified(#[case] name: &str, #[case] value: &str, #[case] expected: bool) {
+        let last_modified = httpdate::parse_http_date("Tue, 10 Oct 2023 13:55:36 GMT").unwrap() + Duration::from_millis(300);
+        assert_eq!(is_not_modified(&headers(name, value), Some(ETAG), Some(last_modified)), expected);
+    }
+
+    #[test]
+    fn test_if_none_match_takes_precedence() {
+        let mut headers = headers("if-none-match", "\"other\"");
+        headers.insert(IF_MODIFIED_SINCE, HeaderValue::from_static("Tue, 10 Oct 2023 13:55:36 GMT"));
+        assert!(!is_not_modified(&headers, Some(ETAG), Some(UNIX_EPOCH)));
+    }
+}
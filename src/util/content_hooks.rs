@@ -0,0 +1,242 @@
+use std::fmt::{Debug, Formatter};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use anyhow::anyhow;
+use bytes::Bytes;
+use futures_core::Stream;
+use tracing::warn;
+
+pub type ContentStream = Pin<Box<dyn Stream<Item = anyhow::Result<Bytes>> + Send + 'static>>;
+
+/// Observes an artifact's content as it is stored, e.g. for virus scanning or verifying jar
+///  signatures. Inspectors see the data but cannot change it.
+pub trait ContentInspector: Send + Sync {
+    /// for logging
+    fn name(&self) -> &str;
+
+    /// Starts inspecting the content stored under a given repository path
+    fn start(&self, path: &str) -> Box<dyn ContentInspection>;
+}
+
+/// The inspection of a single stream of content, see [ContentInspector]
+pub trait ContentInspection: Send {
+    fn update(&mut self, chunk: &[u8]) -> anyhow::Result<()>;
+
+    /// Called after the last chunk. This is where inspectors that need the entire content make
+    ///  their decision.
+    fn finish(self: Box<Self>) -> anyhow::Result<()>;
+}
+
+/// Replaces an artifact's content as it is stored. A transformer signals failure by returning a
+///  stream that yields an error.
+pub trait ContentTransformer: Send + Sync {
+    /// for logging
+    fn name(&self) -> &str;
+
+    fn transform(&self, path: &str, data: ContentStream) -> ContentStream;
+}
+
+/// What happens if an inspector rejects content or fails. Transformers always reject on failure
+///  since there is no content to fall back to.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum HookFailurePolicy {
+    /// The content is not stored, and the request that caused it to be stored fails
+    Reject,
+    /// The failure is logged, and the content is stored anyway
+    Warn,
+}
+
+#[derive(Clone)]
+enum ContentHook {
+    Inspector(Arc<dyn ContentInspector>, HookFailurePolicy),
+    Transformer(Arc<dyn ContentTransformer>),
+}
+
+/// Hooks that are applied to artifact content when it enters a repository, i.e. during cache fill
+///  and deploy. They are configured per repository and applied in the order they were added.
+#[derive(Clone, Default)]
+pub struct ContentHooks {
+    hooks: Vec<ContentHook>,
+}
+impl Debug for ContentHooks {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let names = self.hooks.iter()
+            .map(|h| match h {
+                ContentHook::Inspector(inspector, _) => inspector.name(),
+                ContentHook::Transformer(transformer) => transformer.name(),
+            })
+            .collect::<Vec<_>>();
+        write!(f, "ContentHooks{:?}", names)
+    }
+}
+impl ContentHooks {
+    pub fn new() -> ContentHooks {
+        Default::default()
+    }
+
+    pub fn with_inspector(mut self, inspector: Arc<dyn ContentInspector>, failure_policy: HookFailurePolicy) -> ContentHooks {
+        self.hooks.push(ContentHook::Inspector(inspector, failure_policy));
+        self
+    }
+
+    pub fn with_transformer(mut self, transformer: Arc<dyn ContentTransformer>) -> ContentHooks {
+        self.hooks.push(ContentHook::Transformer(transformer));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Wraps content that is about to be stored under the given path. A rejection surfaces as an
+    ///  error at the end of the returned stream at the latest, so the content is never committed
+    ///  to blob storage.
+    pub fn apply(&self, path: &str, data: ContentStream) -> ContentStream {
+        self.hooks.iter()
+            .fold(data, |data, hook| match hook {
+                ContentHook::Inspector(inspector, failure_policy) => Box::pin(InspectingStream {
+                    inner: data,
+                    inspector_name: inspector.name().to_string(),
+                    path: path.to_string(),
+                    inspection: Some(inspector.start(path)),
+                    failure_policy: *failure_policy,
+                }),
+                ContentHook::Transformer(transformer) => transformer.transform(path, data),
+            })
+    }
+}
+
+struct InspectingStream {
+    inner: ContentStream,
+    inspector_name: String,
+    path: String,
+    /// None after the inspection finished or failed
+    inspection: Option<Box<dyn ContentInspection>>,
+    failure_policy: HookFailurePolicy,
+}
+impl InspectingStream {
+    fn on_failure(&mut self, e: anyhow::Error) -> Option<anyhow::Result<Bytes>> {
+        self.inspection = None;
+        match self.failure_policy {
+            HookFailurePolicy::Reject => {
+                warn!("{} rejected {}: {}", self.inspector_name, self.path, e);
+                Some(Err(anyhow!("rejected by {}: {}", self.inspector_name, e)))
+            }
+            HookFailurePolicy::Warn => {
+                warn!("{} failed for {}, storing it anyway: {}", self.inspector_name, self.path, e);
+                None
+            }
+        }
+    }
+}
+impl Stream for InspectingStream {
+    type Item = anyhow::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.inner.as_mut().poll_next(cx) {
+            Poll::Ready(Some(Ok(chunk))) => {
+                if let Some(inspection) = self.inspection.as_mut() {
+                    if let Err(e) = inspection.update(&chunk) {
+                        if let Some(rejection) = self.on_failure(e) {
+                            return Poll::Ready(Some(rejection));
+                        }
+                    }
+                }
+                Poll::Ready(Some(Ok(chunk)))
+            }
+            Poll::Ready(None) => {
+                if let Some(inspection) = self.inspection.take() {
+                    if let Err(e) = inspection.finish() {
+                        return Poll::Ready(self.on_failure(e));
+                    }
+                }
+                Poll::Ready(None)
+            }
+            other => other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::StreamExt;
+    use rstest::rstest;
+
+    use super::*;
+
+    /// rejects content containing a given byte sequence in a single chunk
+    struct MarkerInspector(&'static [u8]);
+    struct MarkerInspection(&'static [u8]);
+    impl ContentInspector for MarkerInspector {
+        fn name(&self) -> &str {
+            "marker"
+        }
+        fn start(&self, _path: &str) -> Box<dyn ContentInspection> {
+            Box::new(MarkerInspection(self.0))
+        }
+    }
+    impl ContentInspection for MarkerInspection {
+        fn update(&mut self, chunk: &[u8]) -> anyhow::Result<()> {
+            if chunk.windows(self.0.len()).any(|w| w == self.0) {
+                return Err(anyhow!("found marker"));
+            }
+            Ok(())
+        }
+        fn finish(self: Box<Self>) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    struct UppercaseTransformer;
+    impl ContentTransformer for UppercaseTransformer {
+        fn name(&self) -> &str {
+            "uppercase"
+        }
+        fn transform(&self, _path: &str, data: ContentStream) -> ContentStream {
+            Box::pin(data.map(|chunk| chunk.map(|b| Bytes::from(b.to_ascii_uppercase()))))
+        }
+    }
+
+    fn content(chunks: &[&'static str]) -> ContentStream {
+        Box::pin(futures::stream::iter(chunks.iter().map(|c| Ok(Bytes::from_static(c.as_bytes()))).collect::<Vec<_>>()))
+    }
+
+    async fn collect(data: ContentStream) -> anyhow::Result<Vec<u8>> {
+        let mut result = vec![];
+        let mut data = data;
+        while let Some(chunk) = data.next().await {
+            result.extend_from_slice(&chunk?);
+        }
+        Ok(result)
+    }
+
+    #[rstest]
+    #[case::clean_reject(HookFailurePolicy::Reject, &["abc", "def"], Some("abcdef"))]
+    #[case::clean_warn(HookFailurePolicy::Warn, &["abc", "def"], Some("abcdef"))]
+    #[case::infected_reject(HookFailurePolicy::Reject, &["abc", "EICAR"], None)]
+    #[case::infected_warn(HookFailurePolicy::Warn, &["abc", "EICAR"], Some("abcEICAR"))]
+    #[tokio::test]
+    async fn test_inspector_failure_policy(#[case] failure_policy: HookFailurePolicy, #[case] chunks: &[&'static str], #[case] expected: Option<&str>) {
+        let hooks = ContentHooks::new()
+            .with_inspector(Arc::new(MarkerInspector(b"EICAR")), failure_policy);
+
+        let result = collect(hooks.apply("a/b/1.0/b-1.0.jar", content(chunks))).await;
+        assert_eq!(result.ok(), expected.map(|s| s.as_bytes().to_vec()));
+    }
+
+    #[tokio::test]
+    async fn test_hooks_apply_in_order() {
+        // the inspector sees the transformed content
+        let hooks = ContentHooks::new()
+            .with_transformer(Arc::new(UppercaseTransformer))
+            .with_inspector(Arc::new(MarkerInspector(b"EICAR")), HookFailurePolicy::Reject);
+        assert!(collect(hooks.apply("a/b/1.0/b-1.0.jar", content(&["eicar"]))).await.is_err());
+
+        let hooks = ContentHooks::new()
+            .with_inspector(Arc::new(MarkerInspector(b"EICAR")), HookFailurePolicy::Reject)
+            .with_transformer(Arc::new(UppercaseTransformer));
+        assert_eq!(collect(hooks.apply("a/b/1.0/b-1.0.jar", content(&["eicar"]))).await.unwrap(), b"EICAR".to_vec());
+    }
+}
@@ -0,0 +1,106 @@
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use serde::Deserialize;
+
+/// Whether browsers display artifacts or download them, see [content_disposition]
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DispositionPolicy {
+    /// text-ish artifacts like poms are displayed, everything else is downloaded
+    #[default]
+    InlineText,
+    /// all artifacts are downloaded
+    Attachment,
+}
+impl FromStr for DispositionPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<DispositionPolicy> {
+        match s {
+            "inline_text" => Ok(DispositionPolicy::InlineText),
+            "attachment" => Ok(DispositionPolicy::Attachment),
+            _ => Err(anyhow!("unknown content disposition policy {}", s)),
+        }
+    }
+}
+
+/// The 'Content-Disposition' header for an artifact, with its Maven file name as the name browsers
+///  save it as
+pub fn content_disposition(file_extension: &str, file_name: &str, policy: DispositionPolicy) -> String {
+    let is_text = matches!(content_type_for_extension(file_extension), "application/xml" | "application/json" | "text/plain" | "application/pgp-signature");
+    let disposition = match policy {
+        DispositionPolicy::InlineText if is_text => "inline",
+        _ => "attachment",
+    };
+    let file_name = file_name.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("{}; filename=\"{}\"", disposition, file_name)
+}
+
+/// The 'Content-Type' for a repository file, based on its extension including the leading '.'
+///  (e.g. '.jar' or '.tar.gz')
+pub fn content_type_for_extension(file_extension: &str) -> &'static str {
+    let file_extension = file_extension.to_ascii_lowercase();
+    let last_extension = file_extension.rsplit('.').next().unwrap_or("");
+
+    match last_extension {
+        "jar" | "war" | "ear" | "aar" => "application/java-archive",
+        "pom" | "xml" => "application/xml",
+        "module" | "json" => "application/json",
+        "zip" => "application/zip",
+        "gz" | "tgz" => "application/gzip",
+        "sha1" | "sha256" | "sha512" | "md5" | "txt" => "text/plain",
+        "asc" => "application/pgp-signature",
+        _ => "application/octet-stream",
+    }
+}
+
+/// The syntax of text-like files for previews (e.g. 'xml' for poms), None for binary files
+pub fn preview_syntax(file_extension: &str) -> Option<&'static str> {
+    match content_type_for_extension(file_extension) {
+        "application/xml" => Some("xml"),
+        "application/json" => Some("json"),
+        "text/plain" | "application/pgp-signature" => Some("text"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(".pom", "a-1.0.pom", DispositionPolicy::InlineText, "inline; filename=\"a-1.0.pom\"")]
+    #[case(".jar.sha1", "a-1.0.jar.sha1", DispositionPolicy::InlineText, "inline; filename=\"a-1.0.jar.sha1\"")]
+    #[case(".jar", "a-1.0.jar", DispositionPolicy::InlineText, "attachment; filename=\"a-1.0.jar\"")]
+    #[case(".pom", "a-1.0.pom", DispositionPolicy::Attachment, "attachment; filename=\"a-1.0.pom\"")]
+    #[case(".pom", "a\"b-1.0.pom", DispositionPolicy::InlineText, "inline; filename=\"a\\\"b-1.0.pom\"")]
+    fn test_content_disposition(#[case] file_extension: &str, #[case] file_name: &str, #[case] policy: DispositionPolicy, #[case] expected: &str) {
+        assert_eq!(content_disposition(file_extension, file_name, policy), expected);
+    }
+
+    #[rstest]
+    #[case(".jar", "application/java-archive")]
+    #[case(".JAR", "application/java-archive")]
+    #[case(".pom", "application/xml")]
+    #[case(".tar.gz", "application/gzip")]
+    #[case(".jar.sha1", "text/plain")]
+    #[case(".jar.asc", "application/pgp-signature")]
+    #[case(".so", "application/octet-stream")]
+    #[case("", "application/octet-stream")]
+    fn test_content_type_for_extension(#[case] file_extension: &str, #[case] expected: &str) {
+        assert_eq!(content_type_for_extension(file_extension), expected);
+    }
+
+    #[rstest]
+    #[case(".pom", Some("xml"))]
+    #[case(".module", Some("json"))]
+    #[case(".jar.asc", Some("text"))]
+    #[case(".jar", None)]
+    #[case(".tar.gz", None)]
+    fn test_preview_syntax(#[case] file_extension: &str, #[case] expected: Option<&str>) {
+        assert_eq!(preview_syntax(file_extension), expected);
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use hyper::{Body, Client, Method, Request};
+use hyper::header::CONTENT_TYPE;
+use hyper_tls::HttpsConnector;
+use serde::{Deserialize, Serialize};
+
+use crate::util::anonymization::AnonymizationPolicy;
+
+/// Deploy rates above these limits are reported as anomalies, e.g. a sudden mass re-deploy
+///  caused by a misconfigured CI job or a compromised account
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct DeployAlertThreshold {
+    pub window_secs: u64,
+    /// per repository and principal within the window
+    pub max_deploys: Option<u64>,
+    /// per repository and principal within the window
+    pub max_bytes: Option<u64>,
+}
+impl Default for DeployAlertThreshold {
+    fn default() -> DeployAlertThreshold {
+        DeployAlertThreshold {
+            window_secs: 600,
+            max_deploys: Some(1000),
+            max_bytes: None,
+        }
+    }
+}
+
+/// Deploy counters for a repository and principal
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+pub struct DeployStats {
+    pub repository: String,
+    pub principal: String,
+    pub total_deploys: u64,
+    pub total_bytes: u64,
+    /// within the alert threshold's window
+    pub recent_deploys: u64,
+    /// within the alert threshold's window
+    pub recent_bytes: u64,
+}
+
+/// Raised when a repository / principal pair exceeds the [DeployAlertThreshold]
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+pub struct DeployAlert {
+    pub repository: String,
+    pub principal: String,
+    pub window_secs: u64,
+    pub deploys: u64,
+    pub bytes: u64,
+}
+
+#[derive(Default)]
+struct PrincipalDeploys {
+    total_deploys: u64,
+    total_bytes: u64,
+    /// (time, size) of deploys within the window
+    recent: VecDeque<(Instant, u64)>,
+    /// to raise an alert once when the threshold is exceeded rather than for every deploy
+    is_alerting: bool,
+    last_deploy: Option<Instant>,
+}
+
+/// Tracks deploy frequency and size per repository and principal
+pub struct DeployMetrics {
+    threshold: DeployAlertThreshold,
+    deploys: Mutex<BTreeMap<(String, String), PrincipalDeploys>>,
+}
+impl DeployMetrics {
+    pub fn new(threshold: DeployAlertThreshold) -> DeployMetrics {
+        DeployMetrics {
+            threshold,
+            deploys: Default::default(),
+        }
+    }
+
+    /// Returns an alert if this deploy makes the principal exceed the threshold
+    pub fn record_deploy(&self, repository: &str, principal: &str, size: u64) -> Option<DeployAlert> {
+        self.record_deploy_at(Instant::now(), repository, principal, size)
+    }
+
+    fn record_deploy_at(&self, now: Instant, repository: &str, principal: &str, size: u64) -> Option<DeployAlert> {
+        let mut deploys = self.deploys.lock().unwrap();
+        let entry = deploys.entry((repository.to_string(), principal.to_string())).or_default();
+
+        entry.total_deploys += 1;
+        entry.total_bytes += size;
+        entry.recent.push_back((now, size));
+        entry.last_deploy = Some(now);
+        self.expire(entry, now);
+
+        let recent_deploys = entry.recent.len() as u64;
+        let recent_bytes = entry.recent.iter().map(|(_, s)| s).sum();
+
+        let exceeds_threshold = self.threshold.max_deploys.map(|max| recent_deploys > max).unwrap_or(false)
+            || self.threshold.max_bytes.map(|max| recent_bytes > max).unwrap_or(false);
+
+        let was_alerting = entry.is_alerting;
+        entry.is_alerting = exceeds_threshold;
+        if exceeds_threshold && !was_alerting {
+            Some(DeployAlert {
+                repository: repository.to_string(),
+                principal: principal.to_string(),
+                window_secs: self.threshold.window_secs,
+                deploys: recent_deploys,
+                bytes: recent_bytes,
+            })
+        }
+        else {
+            None
+        }
+    }
+
+    fn expire(&self, entry: &mut PrincipalDeploys, now: Instant) {
+        let window = Duration::from_secs(self.threshold.window_secs);
+        while let Some((t, _)) = entry.recent.front() {
+            if now.saturating_duration_since(*t) <= window {
+                break;
+            }
+            entry.recent.pop_front();
+        }
+    }
+
+    /// Anonymizes principals that have not deployed within the policy's retention period, merging
+    ///  their counters with those of other principals that anonymize the same. Returns the number
+    ///  of anonymized principals.
+    pub fn anonymize(&self, policy: &AnonymizationPolicy) -> usize {
+        self.anonymize_at(policy, Instant::now())
+    }
+
+    fn anonymize_at(&self, policy: &AnonymizationPolicy, now: Instant) -> usize {
+        let mut deploys = self.deploys.lock().unwrap();
+        let expired = deploys.iter()
+            .filter(|(_, entry)| entry.last_deploy
+                .map(|t| now.saturating_duration_since(t) >= policy.retention)
+                .unwrap_or(true))
+            .filter_map(|((repository, principal), _)| policy.anonymize(principal)
+                .map(|anonymized| ((repository.clone(), principal.clone()), anonymized)))
+            .collect::<Vec<_>>();
+
+        for ((repository, principal), anonymized) in &expired {
+            let entry = deploys.remove(&(repository.clone(), principal.clone())).unwrap();
+            let merged = deploys.entry((repository.clone(), anonymized.clone())).or_default();
+            merged.total_deploys += entry.total_deploys;
+            merged.total_bytes += entry.total_bytes;
+            merged.recent.extend(entry.recent);
+            merged.recent.make_contiguous().sort_by_key(|(t, _)| *t);
+            merged.is_alerting |= entry.is_alerting;
+            merged.last_deploy = merged.last_deploy.max(entry.last_deploy);
+        }
+        expired.len()
+    }
+
+    pub fn stats(&self) -> Vec<DeployStats> {
+        let now = Instant::now();
+        let mut deploys = self.deploys.lock().unwrap();
+        deploys.iter_mut()
+            .map(|((repository, principal), entry)| {
+                self.expire(entry, now);
+                DeployStats {
+                    repository: repository.clone(),
+                    principal: principal.clone(),
+                    total_deploys: entry.total_deploys,
+                    total_bytes: entry.total_bytes,
+                    recent_deploys: entry.recent.len() as u64,
+                    recent_bytes: entry.recent.iter().map(|(_, s)| s).sum(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Posts an alert as JSON to a webhook
+pub async fn send_alert_webhook(webhook_uri: &str, alert: &DeployAlert) -> anyhow::Result<()> {
+    let client = Client::builder().build::<_, Body>(HttpsConnector::new());
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(webhook_uri)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(serde_json::to_vec(alert)?))?;
+
+    let response = client.request(request).await?;
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!("webhook returned status {}", response.status()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::util::anonymization::{AnonymizationMode, ANONYMOUS};
+
+    use super::*;
+
+    #[test]
+    fn test_alert_once_per_burst() {
+        let metrics = DeployMetrics::new(DeployAlertThreshold {
+            window_secs: 60,
+            max_deploys: Some(2),
+            max_bytes: None,
+        });
+        let start = Instant::now();
+
+        assert_eq!(metrics.record_deploy_at(start, "internal", "ci", 10), None);
+        assert_eq!(metrics.record_deploy_at(start, "internal", "ci", 10), None);
+        // other principals are counted separately
+        assert_eq!(metrics.record_deploy_at(start, "internal", "alice", 10), None);
+
+        let alert = metrics.record_deploy_at(start + Duration::from_secs(1), "internal", "ci", 10).unwrap();
+        assert_eq!(alert.deploys, 3);
+        assert_eq!(alert.bytes, 30);
+        assert_eq!(metrics.record_deploy_at(start + Duration::from_secs(2), "internal", "ci", 10), None);
+
+        // after the window, the burst is over, and a new one raises a new alert
+        let later = start + Duration::from_secs(120);
+        assert_eq!(metrics.record_deploy_at(later, "internal", "ci", 10), None);
+        assert_eq!(metrics.record_deploy_at(later, "internal", "ci", 10), None);
+        assert!(metrics.record_deploy_at(later, "internal", "ci", 10).is_some());
+    }
+
+    #[test]
+    fn test_byte_threshold() {
+        let metrics = DeployMetrics::new(DeployAlertThreshold {
+            window_secs: 60,
+            max_deploys: None,
+            max_bytes: Some(100),
+        });
+        assert_eq!(metrics.record_deploy("internal", "ci", 60), None);
+        assert_eq!(metrics.record_deploy("internal", "ci", 60).unwrap().bytes, 120);
+
+        let stats = metrics.stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].total_deploys, 2);
+        assert_eq!(stats[0].total_bytes, 120);
+    }
+
+    #[test]
+    fn test_anonymize() {
+        let metrics = DeployMetrics::new(DeployAlertThreshold::default());
+        let start = Instant::now();
+        metrics.record_deploy_at(start, "internal", "alice", 10);
+        metrics.record_deploy_at(start, "internal", "bob", 20);
+        metrics.record_deploy_at(start + Duration::from_secs(30), "internal", "ci", 30);
+
+        let policy = AnonymizationPolicy::new(AnonymizationMode::Drop, Duration::from_secs(60));
+        assert_eq!(metrics.anonymize_at(&policy, start + Duration::from_secs(10)), 0);
+        assert_eq!(metrics.anonymize_at(&policy, start + Duration::from_secs(60)), 2);
+        assert_eq!(metrics.anonymize_at(&policy, start + Duration::from_secs(60)), 0);
+
+        let stats = metrics.stats();
+        let principals = stats.iter().map(|s| (s.principal.as_str(), s.total_deploys, s.total_bytes)).collect::<Vec<_>>();
+        assert_eq!(principals, vec![(ANONYMOUS, 2, 30), ("ci", 1, 30)]);
+    }
+}
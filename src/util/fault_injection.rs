@@ -0,0 +1,142 @@
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use anyhow::anyhow;
+use tracing::debug;
+
+use crate::util::uuid_generator::{random_fraction, RandomUuidGenerator, UuidGenerator};
+
+/// The operations that faults can be injected into
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum FaultOperation {
+    BlobInsert,
+    BlobGet,
+    BlobStat,
+    BlobDelete,
+    Download,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Fault {
+    Fail,
+    Delay(Duration),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct FaultRule {
+    /// `None` for all operations
+    pub operation: Option<FaultOperation>,
+    /// restricts the rule to repository paths starting with this prefix (e.g. 'org/apache/'),
+    ///  for operations that refer to a path
+    pub path_prefix: Option<String>,
+    /// between 0.0 (never) and 1.0 (always)
+    pub probability: f64,
+    pub fault: Fault,
+}
+impl FaultRule {
+    pub fn always(operation: FaultOperation, fault: Fault) -> FaultRule {
+        FaultRule {
+            operation: Some(operation),
+            path_prefix: None,
+            probability: 1.0,
+            fault,
+        }
+    }
+
+    fn applies_to(&self, operation: FaultOperation, path: Option<&str>) -> bool {
+        if self.operation.is_some_and(|o| o != operation) {
+            return false;
+        }
+        match (&self.path_prefix, path) {
+            (None, _) => true,
+            (Some(prefix), Some(path)) => path.starts_with(prefix.as_str()),
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// Makes storage and downloads fail or slow down on demand so that resilience features can be
+///  exercised in integration tests. This is for testing only, and it is not compiled into regular
+///  builds (see the 'fault-injection' cargo feature).
+pub struct FaultInjector {
+    rules: RwLock<Vec<FaultRule>>,
+    random: Arc<dyn UuidGenerator>,
+}
+impl Default for FaultInjector {
+    fn default() -> FaultInjector {
+        FaultInjector {
+            rules: Default::default(),
+            random: Arc::new(RandomUuidGenerator::default()),
+        }
+    }
+}
+impl FaultInjector {
+    pub fn new() -> FaultInjector {
+        Default::default()
+    }
+
+    pub fn with_random(self, random: Arc<dyn UuidGenerator>) -> FaultInjector {
+        FaultInjector {
+            random,
+            ..self
+        }
+    }
+
+    pub fn add_rule(&self, rule: FaultRule) {
+        self.rules.write().unwrap().push(rule);
+    }
+
+    pub fn clear(&self) {
+        self.rules.write().unwrap().clear();
+    }
+
+    /// Applies the first matching rule whose probability check succeeds: delays return after
+    ///  sleeping, failures return an error
+    pub async fn inject(&self, operation: FaultOperation, path: Option<&str>) -> anyhow::Result<()> {
+        let fault = self.rules.read().unwrap()
+            .iter()
+            .filter(|r| r.applies_to(operation, path))
+            .find(|r| random_fraction(self.random.as_ref()) < r.probability)
+            .map(|r| r.fault.clone());
+
+        match fault {
+            None => Ok(()),
+            Some(Fault::Fail) => {
+                debug!("injecting failure into {:?} {:?}", operation, path);
+                Err(anyhow!("injected fault in {:?}", operation))
+            }
+            Some(Fault::Delay(delay)) => {
+                debug!("injecting delay of {:?} into {:?} {:?}", delay, operation, path);
+                tokio::time::sleep(delay).await;
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_inject() {
+        let injector = FaultInjector::new();
+        assert!(injector.inject(FaultOperation::Download, Some("org/apache/x")).await.is_ok());
+
+        injector.add_rule(FaultRule {
+            operation: Some(FaultOperation::Download),
+            path_prefix: Some("org/apache/".to_string()),
+            probability: 1.0,
+            fault: Fault::Fail,
+        });
+        injector.add_rule(FaultRule::always(FaultOperation::BlobGet, Fault::Delay(Duration::from_millis(1))));
+
+        assert!(injector.inject(FaultOperation::Download, Some("org/apache/x")).await.is_err());
+        assert!(injector.inject(FaultOperation::Download, Some("com/example/x")).await.is_ok());
+        assert!(injector.inject(FaultOperation::BlobInsert, None).await.is_ok());
+        assert!(injector.inject(FaultOperation::BlobGet, None).await.is_ok());
+
+        injector.clear();
+        assert!(injector.inject(FaultOperation::Download, Some("org/apache/x")).await.is_ok());
+    }
+}
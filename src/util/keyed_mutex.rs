@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::OwnedMutexGuard;
+
+/// An async mutex per key, e.g. for coalescing concurrent downloads of the same artifact. Entries
+///  are removed when nobody holds or waits for them, so this does not grow with the number of
+///  keys ever used.
+pub struct KeyedMutex<K: Eq + Hash + Clone> {
+    locks: Mutex<HashMap<K, Arc<tokio::sync::Mutex<()>>>>,
+}
+impl<K: Eq + Hash + Clone> Default for KeyedMutex<K> {
+    fn default() -> KeyedMutex<K> {
+        KeyedMutex {
+            locks: Default::default(),
+        }
+    }
+}
+impl<K: Eq + Hash + Clone> KeyedMutex<K> {
+    pub fn new() -> KeyedMutex<K> {
+        Default::default()
+    }
+
+    pub async fn lock(&self, key: K) -> KeyedMutexGuard<'_, K> {
+        let lock = self.locks.lock().unwrap()
+            .entry(key.clone())
+            .or_default()
+            .clone();
+        KeyedMutexGuard {
+            owner: self,
+            key,
+            guard: Some(lock.lock_owned().await),
+        }
+    }
+
+    /// the number of keys that are currently locked or waited for
+    pub fn len(&self) -> usize {
+        self.locks.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+pub struct KeyedMutexGuard<'a, K: Eq + Hash + Clone> {
+    owner: &'a KeyedMutex<K>,
+    key: K,
+    guard: Option<OwnedMutexGuard<()>>,
+}
+impl<K: Eq + Hash + Clone> Drop for KeyedMutexGuard<'_, K> {
+    fn drop(&mut self) {
+        self.guard.take();
+
+        // waiters hold a reference to the entry, and they can only get one while the map is locked
+        let mut locks = self.owner.locks.lock().unwrap();
+        if let Some(lock) = locks.get(&self.key) {
+            if Arc::strong_count(lock) == 1 {
+                locks.remove(&self.key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_lock() {
+        let keyed_mutex = Arc::new(KeyedMutex::new());
+        let guard = keyed_mutex.lock("a").await;
+        let _other_key = keyed_mutex.lock("b").await;
+
+        let waiter = {
+            let keyed_mutex = keyed_mutex.clone();
+            tokio::spawn(async move {
+                let _guard = keyed_mutex.lock("a").await;
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiter.is_finished());
+
+        drop(guard);
+        waiter.await.unwrap();
+        assert_eq!(keyed_mutex.len(), 1);
+    }
+}
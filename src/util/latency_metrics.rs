@@ -0,0 +1,180 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use hdrhistogram::Histogram;
+use lazy_static::lazy_static;
+use serde::Serialize;
+
+/// the period the percentiles cover
+pub const LATENCY_WINDOW: Duration = Duration::from_secs(300);
+/// the window moves in steps of this, dropping the oldest slot's samples at once
+const SLOT_DURATION: Duration = Duration::from_secs(30);
+/// latencies above this are recorded as this
+const MAX_LATENCY_MICROS: u64 = 3_600_000_000;
+
+lazy_static! {
+    /// recorded by the access log, see [access_log](crate::util::access_log::access_log)
+    pub static ref REQUEST_LATENCIES: RequestLatencies = RequestLatencies::new();
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum LatencyKind {
+    /// an artifact served from a remote repository's cache
+    CacheHit,
+    /// an artifact downloaded from upstream
+    CacheMiss,
+    /// a 'maven-metadata.xml' file
+    Metadata,
+}
+
+/// Percentiles of the time until response headers were sent, in milliseconds - None if there were
+///  no requests in the window
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize)]
+pub struct LatencyPercentiles {
+    pub count: u64,
+    pub p50_ms: Option<f64>,
+    pub p95_ms: Option<f64>,
+    pub p99_ms: Option<f64>,
+    pub max_ms: Option<f64>,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize)]
+pub struct LatencyReport {
+    pub window_secs: u64,
+    pub cache_hit: LatencyPercentiles,
+    pub cache_miss: LatencyPercentiles,
+    pub metadata: LatencyPercentiles,
+}
+
+/// Rolling latency percentiles of the kinds of requests that SLOs are typically set for, kept in
+///  HDR histograms so that memory does not grow with the number of requests
+pub struct RequestLatencies {
+    cache_hit: RollingHistogram,
+    cache_miss: RollingHistogram,
+    metadata: RollingHistogram,
+}
+impl RequestLatencies {
+    pub fn new() -> RequestLatencies {
+        RequestLatencies {
+            cache_hit: RollingHistogram::new(),
+            cache_miss: RollingHistogram::new(),
+            metadata: RollingHistogram::new(),
+        }
+    }
+
+    pub fn record(&self, kind: LatencyKind, latency: Duration) {
+        let histogram = match kind {
+            LatencyKind::CacheHit => &self.cache_hit,
+            LatencyKind::CacheMiss => &self.cache_miss,
+            LatencyKind::Metadata => &self.metadata,
+        };
+        histogram.record(Instant::now(), latency);
+    }
+
+    pub fn report(&self) -> LatencyReport {
+        let now = Instant::now();
+        LatencyReport {
+            window_secs: LATENCY_WINDOW.as_secs(),
+            cache_hit: self.cache_hit.percentiles(now),
+            cache_miss: self.cache_miss.percentiles(now),
+            metadata: self.metadata.percentiles(now),
+        }
+    }
+}
+impl Default for RequestLatencies {
+    fn default() -> RequestLatencies {
+        RequestLatencies::new()
+    }
+}
+
+/// A histogram per slot of [SLOT_DURATION], newest last
+struct RollingHistogram {
+    slots: Mutex<VecDeque<(Instant, Histogram<u64>)>>,
+}
+impl RollingHistogram {
+    fn new() -> RollingHistogram {
+        RollingHistogram {
+            slots: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    fn record(&self, now: Instant, latency: Duration) {
+        let mut slots = self.slots.lock().unwrap();
+        Self::expire(&mut slots, now);
+        if !matches!(slots.back(), Some((started, _)) if now.duration_since(*started) < SLOT_DURATION) {
+            slots.push_back((now, new_histogram()));
+        }
+        let micros = u64::try_from(latency.as_micros()).unwrap_or(u64::MAX);
+        slots.back_mut().unwrap().1.saturating_record(micros.clamp(1, MAX_LATENCY_MICROS));
+    }
+
+    fn percentiles(&self, now: Instant) -> LatencyPercentiles {
+        let mut slots = self.slots.lock().unwrap();
+        Self::expire(&mut slots, now);
+
+        let mut merged = new_histogram();
+        for (_, histogram) in slots.iter() {
+            // both have the same bounds, so this can not fail
+            let _ = merged.add(histogram);
+        }
+        if merged.is_empty() {
+            return LatencyPercentiles::default();
+        }
+        let millis = |micros: u64| Some(micros as f64 / 1000.0);
+        LatencyPercentiles {
+            count: merged.len(),
+            p50_ms: millis(merged.value_at_quantile(0.5)),
+            p95_ms: millis(merged.value_at_quantile(0.95)),
+            p99_ms: millis(merged.value_at_quantile(0.99)),
+            max_ms: millis(merged.max()),
+        }
+    }
+
+    /// drops slots that started before the window
+    fn expire(slots: &mut VecDeque<(Instant, Histogram<u64>)>, now: Instant) {
+        while matches!(slots.front(), Some((started, _)) if now.duration_since(*started) >= LATENCY_WINDOW) {
+            slots.pop_front();
+        }
+    }
+}
+
+fn new_histogram() -> Histogram<u64> {
+    // three significant digits, i.e. percentiles are accurate to 0.1%
+    Histogram::new_with_bounds(1, MAX_LATENCY_MICROS, 3).unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_percentiles() {
+        let histogram = RollingHistogram::new();
+        let now = Instant::now();
+        for millis in 1..=100 {
+            histogram.record(now, Duration::from_millis(millis));
+        }
+
+        let percentiles = histogram.percentiles(now);
+        assert_eq!(percentiles.count, 100);
+        let p50 = percentiles.p50_ms.unwrap();
+        assert!((49.9..=50.1).contains(&p50), "{}", p50);
+        let p99 = percentiles.p99_ms.unwrap();
+        assert!((98.9..=99.1).contains(&p99), "{}", p99);
+        assert!(percentiles.max_ms.unwrap() >= 99.9);
+    }
+
+    #[test]
+    fn test_rolling_window() {
+        let histogram = RollingHistogram::new();
+        let start = Instant::now();
+        histogram.record(start, Duration::from_millis(10));
+        histogram.record(start + SLOT_DURATION, Duration::from_millis(20));
+
+        assert_eq!(histogram.percentiles(start + SLOT_DURATION).count, 2);
+        // the first slot left the window, the second one did not
+        assert_eq!(histogram.percentiles(start + LATENCY_WINDOW).count, 1);
+        assert_eq!(histogram.percentiles(start + LATENCY_WINDOW + SLOT_DURATION), LatencyPercentiles::default());
+    }
+}
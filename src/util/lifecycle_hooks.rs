@@ -0,0 +1,135 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use tracing::warn;
+
+/// Provisions and releases resources that belong to a repository created at runtime, e.g. a
+///  storage prefix, database rows or registrations in other systems
+#[async_trait]
+pub trait RepositoryLifecycleHook: Send + Sync {
+    /// for logging
+    fn name(&self) -> &str;
+
+    /// Called before a new repository becomes visible. An error aborts the repository's creation.
+    async fn on_create(&self, repository: &str) -> anyhow::Result<()>;
+
+    /// Called after a repository was removed, or when its creation was aborted after this hook's
+    ///  `on_create` succeeded. After a removal, this runs in the background, so it may take its
+    ///  time e.g. for deleting data.
+    async fn on_delete(&self, repository: &str) -> anyhow::Result<()>;
+}
+
+/// Hooks that run when repositories are created or deleted at runtime. Creation hooks run in the
+///  order the hooks were added, deletion hooks in reverse order.
+#[derive(Clone, Default)]
+pub struct RepositoryLifecycleHooks {
+    hooks: Vec<Arc<dyn RepositoryLifecycleHook>>,
+}
+impl Debug for RepositoryLifecycleHooks {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let names = self.hooks.iter()
+            .map(|h| h.name())
+            .collect::<Vec<_>>();
+        write!(f, "RepositoryLifecycleHooks{:?}", names)
+    }
+}
+impl RepositoryLifecycleHooks {
+    pub fn new() -> RepositoryLifecycleHooks {
+        Default::default()
+    }
+
+    pub fn with_hook(mut self, hook: Arc<dyn RepositoryLifecycleHook>) -> RepositoryLifecycleHooks {
+        self.hooks.push(hook);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// If a hook fails, the hooks that succeeded before it are undone so that nothing leaks
+    pub async fn on_create(&self, repository: &str) -> anyhow::Result<()> {
+        for (i, hook) in self.hooks.iter().enumerate() {
+            if let Err(e) = hook.on_create(repository).await {
+                warn!("lifecycle hook {} failed creating repository {}: {}", hook.name(), repository, e);
+                delete(&self.hooks[..i], repository).await;
+                return Err(anyhow!("lifecycle hook {} failed: {}", hook.name(), e));
+            }
+        }
+        Ok(())
+    }
+
+    /// Failures are logged, and do not keep the remaining hooks from running
+    pub async fn on_delete(&self, repository: &str) {
+        delete(&self.hooks, repository).await;
+    }
+}
+
+async fn delete(hooks: &[Arc<dyn RepositoryLifecycleHook>], repository: &str) {
+    for hook in hooks.iter().rev() {
+        if let Err(e) = hook.on_delete(repository).await {
+            warn!("lifecycle hook {} failed deleting repository {}: {}", hook.name(), repository, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// records calls as '<hook name> <create|delete> <repository>'
+    struct RecordingHook {
+        name: &'static str,
+        fail_on_create: bool,
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+    #[async_trait]
+    impl RepositoryLifecycleHook for RecordingHook {
+        fn name(&self) -> &str {
+            self.name
+        }
+        async fn on_create(&self, repository: &str) -> anyhow::Result<()> {
+            self.calls.lock().unwrap().push(format!("{} create {}", self.name, repository));
+            match self.fail_on_create {
+                true => Err(anyhow!("failing on purpose")),
+                false => Ok(()),
+            }
+        }
+        async fn on_delete(&self, repository: &str) -> anyhow::Result<()> {
+            self.calls.lock().unwrap().push(format!("{} delete {}", self.name, repository));
+            Err(anyhow!("failures are logged only"))
+        }
+    }
+
+    fn hooks(calls: &Arc<Mutex<Vec<String>>>, failing: &[bool]) -> RepositoryLifecycleHooks {
+        ["a", "b", "c"].iter().zip(failing)
+            .fold(RepositoryLifecycleHooks::new(), |hooks, (name, fail_on_create)| hooks.with_hook(Arc::new(RecordingHook {
+                name,
+                fail_on_create: *fail_on_create,
+                calls: calls.clone(),
+            })))
+    }
+
+    #[tokio::test]
+    async fn test_create_and_delete() {
+        let calls = Arc::new(Mutex::new(vec![]));
+        let hooks = hooks(&calls, &[false, false]);
+
+        hooks.on_create("x").await.unwrap();
+        hooks.on_delete("x").await;
+        assert_eq!(*calls.lock().unwrap(), vec!["a create x", "b create x", "b delete x", "a delete x"]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_create_is_undone() {
+        let calls = Arc::new(Mutex::new(vec![]));
+        let hooks = hooks(&calls, &[false, true, false]);
+
+        assert!(hooks.on_create("x").await.is_err());
+        assert_eq!(*calls.lock().unwrap(), vec!["a create x", "b create x", "a delete x"]);
+    }
+}
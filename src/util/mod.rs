@@ -1,4 +1,32 @@
+pub mod access_log;
+pub mod anonymization;
+pub mod audit_log;
 pub mod blob;
+pub mod byte_range;
+pub mod cache_control;
 pub mod change_kind;
+pub mod checksum_file;
+pub mod checksum_headers;
+pub mod circuit_breaker;
+pub mod conditional_request;
+pub mod content_hooks;
+pub mod content_type;
+pub mod deploy_metrics;
+pub mod keyed_mutex;
+pub mod latency_metrics;
+#[cfg(feature = "fault-injection")]
+pub mod fault_injection;
+pub mod lifecycle_hooks;
+pub mod pgp;
+pub mod prometheus_metrics;
+pub mod repo_error;
+pub mod retry_later;
+pub mod scheduler;
+pub mod signature_verification;
+pub mod slow_transfer;
+pub mod tasks;
+pub mod transfer_metrics;
+pub mod upstream_client;
+pub mod uuid_generator;
 pub mod validating_http_body;
 pub mod validating_http_downloader;
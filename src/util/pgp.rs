@@ -0,0 +1,505 @@
+use anyhow::anyhow;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use ring::signature::{ED25519, UnparsedPublicKey};
+use rsa::{BigUint, Pkcs1v15Sign, RsaPublicKey};
+use rsa::traits::PublicKeyParts;
+use sha1::Sha1;
+use sha2::{Sha224, Sha256, Sha384, Sha512};
+
+// the subset of OpenPGP (RFC 4880) needed for verifying detached artifact signatures as they are
+//  published to Maven repositories: v4 keys and signatures, with RSA or Ed25519 keys
+
+const TAG_SIGNATURE: u8 = 2;
+const TAG_PUBLIC_KEY: u8 = 6;
+const TAG_PUBLIC_SUBKEY: u8 = 14;
+
+const ALGORITHM_RSA: u8 = 1;
+const ALGORITHM_RSA_SIGN_ONLY: u8 = 3;
+const ALGORITHM_EDDSA: u8 = 22;
+
+const SUBPACKET_ISSUER: u8 = 16;
+const SUBPACKET_ISSUER_FINGERPRINT: u8 = 33;
+
+/// 1.3.6.1.4.1.11591.15.1
+const ED25519_OID: &[u8] = &[0x2b, 0x06, 0x01, 0x04, 0x01, 0xda, 0x47, 0x0f, 0x01];
+
+/// A v4 public key or subkey that can make signatures
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct PgpPublicKey {
+    pub fingerprint: [u8;20],
+    material: KeyMaterial,
+}
+impl PgpPublicKey {
+    /// the last 8 bytes of the fingerprint, which is how signatures refer to their key
+    pub fn key_id(&self) -> u64 {
+        u64::from_be_bytes(self.fingerprint[12..].try_into().unwrap())
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+enum KeyMaterial {
+    Rsa { n: Vec<u8>, e: Vec<u8> },
+    Ed25519(Vec<u8>),
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+enum HashAlgorithm {
+    Sha1,
+    Sha224,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+impl HashAlgorithm {
+    fn from_id(id: u8) -> anyhow::Result<HashAlgorithm> {
+        match id {
+            2 => Ok(HashAlgorithm::Sha1),
+            8 => Ok(HashAlgorithm::Sha256),
+            9 => Ok(HashAlgorithm::Sha384),
+            10 => Ok(HashAlgorithm::Sha512),
+            11 => Ok(HashAlgorithm::Sha224),
+            _ => Err(anyhow!("unsupported signature hash algorithm {}", id)),
+        }
+    }
+
+    /// the DER encoded 'DigestInfo' that precedes the digest in PKCS #1 v1.5 signatures
+    fn digest_info_prefix(&self) -> &'static [u8] {
+        match self {
+            HashAlgorithm::Sha1 => &[0x30, 0x21, 0x30, 0x09, 0x06, 0x05, 0x2b, 0x0e, 0x03, 0x02, 0x1a, 0x05, 0x00, 0x04, 0x14],
+            HashAlgorithm::Sha224 => &[0x30, 0x2d, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x04, 0x05, 0x00, 0x04, 0x1c],
+            HashAlgorithm::Sha256 => &[0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01, 0x05, 0x00, 0x04, 0x20],
+            HashAlgorithm::Sha384 => &[0x30, 0x41, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x02, 0x05, 0x00, 0x04, 0x30],
+            HashAlgorithm::Sha512 => &[0x30, 0x51, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x03, 0x05, 0x00, 0x04, 0x40],
+        }
+    }
+}
+
+/// Hashes signed data incrementally, so that artifacts can be verified while they are stored,
+///  see [PgpSignature::hasher]
+#[derive(Clone)]
+pub enum SignatureHasher {
+    Sha1(Sha1),
+    Sha224(Sha224),
+    Sha256(Sha256),
+    Sha384(Sha384),
+    Sha512(Sha512),
+}
+impl SignatureHasher {
+    fn new(hash_algorithm: HashAlgorithm) -> SignatureHasher {
+        use sha2::Digest;
+        match hash_algorithm {
+            HashAlgorithm::Sha1 => SignatureHasher::Sha1(sha1::Digest::new()),
+            HashAlgorithm::Sha224 => SignatureHasher::Sha224(Sha224::new()),
+            HashAlgorithm::Sha256 => SignatureHasher::Sha256(Sha256::new()),
+            HashAlgorithm::Sha384 => SignatureHasher::Sha384(Sha384::new()),
+            HashAlgorithm::Sha512 => SignatureHasher::Sha512(Sha512::new()),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        use sha2::Digest;
+        match self {
+            SignatureHasher::Sha1(h) => sha1::Digest::update(h, data),
+            SignatureHasher::Sha224(h) => h.update(data),
+            SignatureHasher::Sha256(h) => h.update(data),
+            SignatureHasher::Sha384(h) => h.update(data),
+            SignatureHasher::Sha512(h) => h.update(data),
+        }
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        use sha2::Digest;
+        match self {
+            SignatureHasher::Sha1(h) => sha1::Digest::finalize(h).to_vec(),
+            SignatureHasher::Sha224(h) => h.finalize().to_vec(),
+            SignatureHasher::Sha256(h) => h.finalize().to_vec(),
+            SignatureHasher::Sha384(h) => h.finalize().to_vec(),
+            SignatureHasher::Sha512(h) => h.finalize().to_vec(),
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+enum SignatureValue {
+    Rsa(Vec<u8>),
+    EdDsa { r: Vec<u8>, s: Vec<u8> },
+}
+
+/// A v4 signature of a binary document, i.e. a detached '.asc' file
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct PgpSignature {
+    /// the id of the key that made the signature, if the signature names it
+    pub issuer: Option<u64>,
+    hash_algorithm: HashAlgorithm,
+    /// the part of the signature packet that is hashed after the signed data, with its v4 trailer
+    hashed_trailer: Vec<u8>,
+    /// the first two bytes of the digest, for rejecting wrong data before the expensive check
+    digest_prefix: [u8;2],
+    value: SignatureValue,
+}
+impl PgpSignature {
+    pub fn hasher(&self) -> SignatureHasher {
+        SignatureHasher::new(self.hash_algorithm)
+    }
+
+    /// Checks the signature against a hasher that was fed the signed data
+    pub fn verify(&self, hasher: SignatureHasher, key: &PgpPublicKey) -> anyhow::Result<()> {
+        let mut hasher = hasher;
+        hasher.update(&self.hashed_trailer);
+        let digest = hasher.finalize();
+        if digest[..2] != self.digest_prefix {
+            return Err(anyhow!("signature does not match the data"));
+        }
+
+        match (&key.material, &self.value) {
+            (KeyMaterial::Rsa { n, e }, SignatureValue::Rsa(s)) => {
+                let public_key = RsaPublicKey::new(BigUint::from_bytes_be(n), BigUint::from_bytes_be(e))?;
+                let mut digest_info = self.hash_algorithm.digest_info_prefix().to_vec();
+                digest_info.extend_from_slice(&digest);
+                public_key.verify(Pkcs1v15Sign::new_unprefixed(), &digest_info, &left_pad(s, public_key.size())?)
+                    .map_err(|_| anyhow!("signature does not match the data"))
+            }
+            (KeyMaterial::Ed25519(point), SignatureValue::EdDsa { r, s }) => {
+                let mut signature = left_pad(r, 32)?;
+                signature.extend(left_pad(s, 32)?);
+                UnparsedPublicKey::new(&ED25519, point)
+                    .verify(&digest, &signature)
+                    .map_err(|_| anyhow!("signature does not match the data"))
+            }
+            _ => Err(anyhow!("signature algorithm does not match key {:016X}", key.key_id())),
+        }
+    }
+}
+
+/// MPIs are stored without leading zeros
+fn left_pad(data: &[u8], len: usize) -> anyhow::Result<Vec<u8>> {
+    if data.len() > len {
+        return Err(anyhow!("signature value is too long"));
+    }
+    let mut result = vec![0; len - data.len()];
+    result.extend_from_slice(data);
+    Ok(result)
+}
+
+/// The first signature in a (possibly ASCII armored) '.asc' file
+pub fn parse_signature(data: &[u8]) -> anyhow::Result<PgpSignature> {
+    let data = dearmor(data)?;
+    let body = parse_packets(&data)?.into_iter()
+        .find(|(tag, _)| *tag == TAG_SIGNATURE)
+        .map(|(_, body)| body)
+        .ok_or_else(|| anyhow!("no signature packet"))?;
+
+    let mut reader = Reader(body);
+    let version = reader.u8()?;
+    if version != 4 {
+        return Err(anyhow!("unsupported signature version {}", version));
+    }
+    let signature_type = reader.u8()?;
+    if signature_type != 0 {
+        return Err(anyhow!("unsupported signature type {}, expected a binary document signature", signature_type));
+    }
+    let algorithm = reader.u8()?;
+    let hash_algorithm = HashAlgorithm::from_id(reader.u8()?)?;
+    let hashed_len = reader.u16()?;
+    let hashed_subpackets = reader.take(hashed_len)?;
+    let unhashed_len = reader.u16()?;
+    let unhashed_subpackets = reader.take(unhashed_len)?;
+    let digest_prefix = reader.take(2)?.try_into().unwrap();
+    let value = match algorithm {
+        ALGORITHM_RSA | ALGORITHM_RSA_SIGN_ONLY => SignatureValue::Rsa(reader.mpi()?.to_vec()),
+        ALGORITHM_EDDSA => SignatureValue::EdDsa { r: reader.mpi()?.to_vec(), s: reader.mpi()?.to_vec() },
+        _ => return Err(anyhow!("unsupported signature algorithm {}", algorithm)),
+    };
+
+    // version, type, algorithms and hashed subpackets, followed by the v4 trailer
+    let hashed_header_len = 6 + hashed_len;
+    let mut hashed_trailer = body[..hashed_header_len].to_vec();
+    hashed_trailer.extend_from_slice(&[0x04, 0xff]);
+    hashed_trailer.extend_from_slice(&(hashed_header_len as u32).to_be_bytes());
+
+    Ok(PgpSignature {
+        issuer: find_issuer(hashed_subpackets)?.or(find_issuer(unhashed_subpackets)?),
+        hash_algorithm,
+        hashed_trailer,
+        digest_prefix,
+        value,
+    })
+}
+
+fn find_issuer(subpackets: &[u8]) -> anyhow::Result<Option<u64>> {
+    let mut reader = Reader(subpackets);
+    while !reader.is_empty() {
+        let len = match reader.u8()? as usize {
+            len @ 0..=191 => len,
+            first @ 192..=254 => ((first - 192) << 8) + reader.u8()? as usize + 192,
+            _ => reader.u32()?,
+        };
+        let subpacket = reader.take(len)?;
+        let (&subpacket_type, content) = subpacket.split_first()
+            .ok_or_else(|| anyhow!("empty signature subpacket"))?;
+        // the top bit marks subpackets as critical
+        match (subpacket_type & 0x7f, content) {
+            (SUBPACKET_ISSUER, key_id) if key_id.len() == 8 => return Ok(Some(u64::from_be_bytes(key_id.try_into().unwrap()))),
+            (SUBPACKET_ISSUER_FINGERPRINT, [4, fingerprint @ ..]) if fingerprint.len() == 20 => return Ok(Some(u64::from_be_bytes(fingerprint[12..].try_into().unwrap()))),
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+/// The signing keys in a (possibly ASCII armored) key file or keyring. Keys with unsupported
+///  versions or algorithms (e.g. encryption subkeys) are skipped.
+pub fn parse_public_keys(data: &[u8]) -> anyhow::Result<Vec<PgpPublicKey>> {
+    let data = dearmor(data)?;
+    let mut keys = Vec::new();
+    for (tag, body) in parse_packets(&data)? {
+        if tag == TAG_PUBLIC_KEY || tag == TAG_PUBLIC_SUBKEY {
+            keys.extend(parse_public_key(body)?);
+        }
+    }
+    Ok(keys)
+}
+
+fn parse_public_key(body: &[u8]) -> anyhow::Result<Option<PgpPublicKey>> {
+    let mut reader = Reader(body);
+    if reader.u8()? != 4 {
+        return Ok(None);
+    }
+    // creation time
+    reader.take(4)?;
+    let material = match reader.u8()? {
+        ALGORITHM_RSA | ALGORITHM_RSA_SIGN_ONLY => KeyMaterial::Rsa { n: reader.mpi()?.to_vec(), e: reader.mpi()?.to_vec() },
+        ALGORITHM_EDDSA => {
+            let oid_len = reader.u8()? as usize;
+            let oid = reader.take(oid_len)?;
+            // the point is prefixed with 0x40 for 'native' encoding
+            match (oid, reader.mpi()?) {
+                (ED25519_OID, [0x40, point @ ..]) if point.len() == 32 => KeyMaterial::Ed25519(point.to_vec()),
+                _ => return Ok(None),
+            }
+        }
+        _ => return Ok(None),
+    };
+
+    let mut hasher = SignatureHasher::new(HashAlgorithm::Sha1);
+    hasher.update(&[0x99]);
+    hasher.update(&(body.len() as u16).to_be_bytes());
+    hasher.update(body);
+    Ok(Some(PgpPublicKey {
+        fingerprint: hasher.finalize().try_into().unwrap(),
+        material,
+    }))
+}
+
+/// The binary content of ASCII armored data, or the data itself if it is not armored
+pub fn dearmor(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let text = match std::str::from_utf8(data) {
+        Ok(text) if text.trim_start().starts_with("-----BEGIN PGP") => text,
+        _ => return Ok(data.to_vec()),
+    };
+
+    let mut result = Vec::new();
+    let mut lines = text.lines().map(|line| line.trim());
+    while let Some(line) = lines.next() {
+        if !line.starts_with("-----BEGIN PGP") {
+            continue;
+        }
+        let mut base64 = String::new();
+        let mut in_headers = true;
+        for line in lines.by_ref() {
+            // the CRC line starts with '=', which base64 lines of the armor's length never do
+            if line.starts_with("-----END PGP") || line.starts_with('=') {
+                break;
+            }
+            // armor headers like 'Comment: ...' precede the data
+            if in_headers && line.contains(": ") {
+                continue;
+            }
+            in_headers = false;
+            base64.push_str(line);
+        }
+        result.extend(STANDARD.decode(&base64).map_err(|e| anyhow!("invalid ASCII armor: {}", e))?);
+    }
+    Ok(result)
+}
+
+/// (tag, body) of the packets in binary OpenPGP data
+fn parse_packets(data: &[u8]) -> anyhow::Result<Vec<(u8, &[u8])>> {
+    let mut packets = Vec::new();
+    let mut reader = Reader(data);
+    while !reader.is_empty() {
+        let header = reader.u8()?;
+        if header & 0x80 == 0 {
+            return Err(anyhow!("invalid OpenPGP packet header"));
+        }
+        let (tag, len) = if header & 0x40 != 0 {
+            let len = match reader.u8()? as usize {
+                len @ 0..=191 => len,
+                first @ 192..=223 => ((first - 192) << 8) + reader.u8()? as usize + 192,
+                255 => reader.u32()?,
+                _ => return Err(anyhow!("partial OpenPGP packet lengths are not supported")),
+            };
+            (header & 0x3f, len)
+        }
+        else {
+            let len = match header & 0x03 {
+                0 => reader.u8()? as usize,
+                1 => reader.u16()?,
+                2 => reader.u32()?,
+                // indeterminate length, i.e. the rest of the data
+                _ => reader.0.len(),
+            };
+            ((header >> 2) & 0x0f, len)
+        };
+        packets.push((tag, reader.take(len)?));
+    }
+    Ok(packets)
+}
+
+struct Reader<'a>(&'a [u8]);
+impl<'a> Reader<'a> {
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn take(&mut self, n: usize) -> anyhow::Result<&'a [u8]> {
+        if self.0.len() < n {
+            return Err(anyhow!("truncated OpenPGP data"));
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> anyhow::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> anyhow::Result<usize> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()) as usize)
+    }
+
+    fn u32(&mut self) -> anyhow::Result<usize> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()) as usize)
+    }
+
+    /// a multiprecision integer: its length in bits, followed by its big-endian bytes
+    fn mpi(&mut self) -> anyhow::Result<&'a [u8]> {
+        let bits = self.u16()?;
+        self.take(bits.div_ceil(8))
+    }
+}
+
+/// Keys and signatures made with gpg, for tests
+#[cfg(test)]
+pub(crate) mod test_keys {
+    /// made with 'gpg --detach-sign --armor', the signed data is 'jar'
+    pub(crate) const ED25519_KEY: &str = "-----BEGIN PGP PUBLIC KEY BLOCK-----
+
+mDMEatFjnBYJKwYBBAHaRw8BAQdA4Hw3j5l8R/JqHIKVVWMLh2OrGXFlpj9hzbE6
+NGyxRQW0GFRlc3QgRWQgPGVkQGV4YW1wbGUuY29tPoiQBBMWCAA4FiEERh+c8jtj
+8x9b+dSwKp2uMmJVF7YFAmrRY5wCGwMFCwkIBwIGFQoJCAsCBBYCAwECHgECF4AA
+CgkQKp2uMmJVF7YdJQEA/p7lPJP3PTo40AphmnjEqqz1iAxtk2DJlb3RyN6z1loA
+/2WGu2p2zXI8KJg+0Bieif3oECFX1+nvFVC9c/yApN8K
+=zu1J
+-----END PGP PUBLIC KEY BLOCK-----
+";
+    pub(crate) const ED25519_SIGNATURE: &str = "-----BEGIN PGP SIGNATURE-----
+
+iIUEABYKAC0WIQRGH5zyO2PzH1v51LAqna4yYlUXtgUCatFjnA8cZWRAZXhhbXBs
+ZS5jb20ACgkQKp2uMmJVF7aAdQD+MEKKzMAZYLyNOIsDnOsmOa7KdKVTb+TtcaoN
+chG4ZK4BAIAnUE1yfJgyiaBxC4f8hn1vRwjzF7g801dYeINpLPsJ
+=CD7h
+-----END PGP SIGNATURE-----
+";
+    pub(crate) const RSA_KEY: &str = "-----BEGIN PGP PUBLIC KEY BLOCK-----
+
+mQENBGrRY5wBCADP2zifk2WuCTrMm8VW25R0sniSAFe1FfdM9mjgNf9X7NtycW5c
+N3Tg6/boLqCnXioE1G5fx0/dSvOifT2XOA/ZCeNfMTO7gRltWq9NE95GLpDiTfKp
+cdw7stdxEKXqMnZyJNkLiQmrR264mzWxJMCEXSIeyjCDylIVdR4W12DkRc1RkszT
+kQ/mS5184lTwRx817uZatH11vpas43rC3GfzLRKQkQI9Gqt3fODNYpk6M87URS5a
+GChYsJOPAU/h+NAlY8bc4xM/Q3/4ZyjAW1KsCZYfAJHMhNJMoBDDFD5T2J/RsMbt
+ezOwWTperwRL4C1Pj/M59v2BVGhdm0Oz07F5ABEBAAG0GlRlc3QgUlNBIDxyc2FA
+ZXhhbXBsZS5jb20+iQFOBBMBCgA4FiEEanc6ftGzVxRckKxXL/89nRPVT+0FAmrR
+Y5wCGwMFCwkIBwIGFQoJCAsCBBYCAwECHgECF4AACgkQL/89nRPVT+3czggAkTM1
+Njnd7kwtiJLqltn+vkSIYQWyU+JsNMsHjfxcah9isXISsn9luyJqlPWN2irZ8iDG
+qHvgQMUUDye0hMG5Ye5GdM+cCOBB+sMyDI398fUgKpoR2oKFMOIWUU7MF8oCYT8j
+5AHbcmmBWVhhSzp1odsFUxNz81yHr+tM1EA+aUb3zAN74aL39lbKDOQNpZdurZdl
+X3pnqT2IO/gGUdKFzPGEB4/f29mK3Jm9yhd4c7OIL/Hp8eif6Rs+U8l6+nBlRoaM
+82QaUwcVD6C6MhE1U5RIXWnxrPv3Z5iyhPkZGzO7R26rNzgeOgf0EAP+2Bk6efuA
+32tW4omRwu0Rue2tWg==
+=Q5q3
+-----END PGP PUBLIC KEY BLOCK-----
+";
+    pub(crate) const RSA_SIGNATURE: &str = "-----BEGIN PGP SIGNATURE-----
+
+iQFEBAABCAAuFiEEanc6ftGzVxRckKxXL/89nRPVT+0FAmrRY5wQHHJzYUBleGFt
+cGxlLmNvbQAKCRAv/z2dE9VP7WudCADICTGJ1UY8hzf3nGaFPWEYj4yAR97amR2K
+GfpDB+Z/uijEE7Y2HB6sci2Hq4FQ2+fAOpxMQMXeZgsftPzQ8tBSMr1D02TsSHqc
+bhykTKvYavkwaKC/LrO+OuBrLnQCdn6/bxpN4prTFbmhqNoVXTi5HhPQe7TByY02
+AK0lgcNs9Boa6+yR5vixx1DW2O3gqBjmE6LRqPGPX/1ZcG2AHv7WAcXtGo6b9ylr
+udxIXuFBuzmLAYMR6V12tIQQP/NoKiojoRH6Ue7h/vWB/cHDDIp/fEyVaJL6G+Qc
+TcbwgI4CJErvGMSbafFMMev3m2k5yaESlIf+4x3eY+LysmDBf2vm
+=8Fkm
+-----END PGP SIGNATURE-----
+";
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::rstest;
+
+    use super::*;
+    use super::test_keys::*;
+
+    fn verify(key: &str, signature: &str, data: &[u8]) -> anyhow::Result<()> {
+        let key = parse_public_keys(key.as_bytes())?.into_iter().next().unwrap();
+        let signature = parse_signature(signature.as_bytes())?;
+        assert_eq!(signature.issuer, Some(key.key_id()));
+        let mut hasher = signature.hasher();
+        for chunk in data.chunks(2) {
+            hasher.update(chunk);
+        }
+        signature.verify(hasher, &key)
+    }
+
+    #[rstest]
+    #[case(ED25519_KEY, ED25519_SIGNATURE)]
+    #[case(RSA_KEY, RSA_SIGNATURE)]
+    fn test_verify(#[case] key: &str, #[case] signature: &str) {
+        verify(key, signature, b"jar").unwrap();
+        assert!(verify(key, signature, b"jaR").is_err());
+        assert!(verify(key, signature, b"").is_err());
+    }
+
+    #[test]
+    fn test_parse_public_keys() {
+        let keys = parse_public_keys(format!("{}{}", ED25519_KEY, RSA_KEY).as_bytes()).unwrap();
+        assert_eq!(keys.iter().map(|k| hex::encode_upper(k.fingerprint)).collect::<Vec<_>>(), vec![
+            "461F9CF23B63F31F5BF9D4B02A9DAE32625517B6",
+            "6A773A7ED1B357145C90AC572FFF3D9D13D54FED",
+        ]);
+        assert_eq!(keys[1].key_id(), 0x2FFF3D9D13D54FED);
+
+        // binary keyrings are read as well
+        assert_eq!(parse_public_keys(&dearmor(RSA_KEY.as_bytes()).unwrap()).unwrap(), vec![keys[1].clone()]);
+    }
+
+    #[test]
+    fn test_wrong_key() {
+        let ed25519_key = parse_public_keys(ED25519_KEY.as_bytes()).unwrap().remove(0);
+        let signature = parse_signature(RSA_SIGNATURE.as_bytes()).unwrap();
+        let mut hasher = signature.hasher();
+        hasher.update(b"jar");
+        assert!(signature.verify(hasher, &ed25519_key).is_err());
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("-----BEGIN PGP SIGNATURE-----\n\nnot base64\n-----END PGP SIGNATURE-----\n")]
+    #[case("-----BEGIN PGP SIGNATURE-----\n\niIUEABYKAC0WIQRGH5zy\n-----END PGP SIGNATURE-----\n")]
+    fn test_invalid_signature(#[case] signature: &str) {
+        assert!(parse_signature(signature.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,79 @@
+use lazy_static::lazy_static;
+use prometheus::{Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder};
+
+lazy_static! {
+    /// the metrics exported at '/metrics' - a registry of its own rather than the prometheus
+    ///  crate's default registry, so that embedders' metrics do not end up here by accident
+    pub static ref REGISTRY: Registry = Registry::new();
+
+    /// requests for artifacts of a remote repository, by 'result' ('hit' or 'miss')
+    pub static ref CACHE_REQUESTS: IntCounterVec = register(IntCounterVec::new(
+        Opts::new("arti_vault_cache_requests_total", "Artifact requests to remote repositories by whether they were cached"),
+        &["repository", "result"],
+    ).unwrap());
+    pub static ref UPSTREAM_REQUEST_DURATION: HistogramVec = register(HistogramVec::new(
+        HistogramOpts::new("arti_vault_upstream_request_duration_seconds", "Time until upstream responded with headers"),
+        &["upstream"],
+    ).unwrap());
+    /// by 'reason' ('not_found', 'status' or 'unavailable')
+    pub static ref UPSTREAM_FAILURES: IntCounterVec = register(IntCounterVec::new(
+        Opts::new("arti_vault_upstream_failures_total", "Upstream requests that did not return the file"),
+        &["upstream", "reason"],
+    ).unwrap());
+    pub static ref DOWNLOADS_IN_FLIGHT: IntGaugeVec = register(IntGaugeVec::new(
+        Opts::new("arti_vault_downloads_in_flight", "Artifact downloads from upstream that are in progress"),
+        &["repository"],
+    ).unwrap());
+    pub static ref BLOB_STORAGE_BYTES: IntCounterVec = register(IntCounterVec::new(
+        Opts::new("arti_vault_blob_storage_bytes_total", "Bytes written to or read from file system blob storage"),
+        &["direction"],
+    ).unwrap());
+    /// sampled when metrics are rendered
+    pub static ref NEGATIVE_CACHE_ENTRIES: IntGaugeVec = register(IntGaugeVec::new(
+        Opts::new("arti_vault_negative_cache_entries", "Remembered failed downloads"),
+        &["repository"],
+    ).unwrap());
+}
+
+fn register<M: prometheus::core::Collector + Clone + 'static>(metric: M) -> M {
+    REGISTRY.register(Box::new(metric.clone())).unwrap();
+    metric
+}
+
+/// Decrements a gauge when dropped, e.g. for counting operations in progress that can fail or be
+///  cancelled at any await point
+pub struct InFlightGuard(IntGauge);
+impl InFlightGuard {
+    pub fn new(gauge: IntGauge) -> InFlightGuard {
+        gauge.inc();
+        InFlightGuard(gauge)
+    }
+}
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0.dec();
+    }
+}
+
+/// All metrics in the Prometheus text format
+pub fn render() -> String {
+    let mut buffer = Vec::new();
+    TextEncoder::new().encode(&REGISTRY.gather(), &mut buffer).unwrap();
+    String::from_utf8(buffer).unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_in_flight_guard() {
+        let gauge = DOWNLOADS_IN_FLIGHT.with_label_values(&["test-in-flight"]);
+        {
+            let _guard = InFlightGuard::new(gauge.clone());
+            assert_eq!(gauge.get(), 1);
+            assert!(render().contains(r#"arti_vault_downloads_in_flight{repository="test-in-flight"} 1"#));
+        }
+        assert_eq!(gauge.get(), 0);
+    }
+}
@@ -0,0 +1,57 @@
+use std::fmt::{Display, Formatter};
+
+/// Why a repository could not serve an artifact. Repositories return these wrapped in
+///  `anyhow::Error` so that frontends can map them to status codes, see [RepoError::of].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum RepoError {
+    /// the artifact does not exist, i.e. upstream answered '404 Not Found' or the repository is
+    ///  offline and does not have it
+    NotFound(String),
+    /// upstream could not be reached, or answered with an error status
+    UpstreamUnavailable(String),
+    /// the content did not match the checksum that upstream sent with it
+    ChecksumMismatch(String),
+    /// the artifact's '.asc' signature is missing or does not verify, see
+    ///  [SignaturePolicy](crate::util::signature_verification::SignaturePolicy)
+    InvalidSignature(String),
+    /// local inconsistency, e.g. metadata referring to a blob that does not exist
+    Internal(String),
+}
+impl RepoError {
+    /// The first [RepoError] in an error's chain, if any
+    pub fn of(e: &anyhow::Error) -> Option<&RepoError> {
+        e.chain().find_map(|cause| cause.downcast_ref::<RepoError>())
+    }
+
+    pub fn is_not_found(e: &anyhow::Error) -> bool {
+        matches!(RepoError::of(e), Some(RepoError::NotFound(_)))
+    }
+}
+impl Display for RepoError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RepoError::NotFound(msg) => write!(f, "not found: {}", msg),
+            RepoError::UpstreamUnavailable(msg) => write!(f, "upstream unavailable: {}", msg),
+            RepoError::ChecksumMismatch(msg) => write!(f, "checksum mismatch: {}", msg),
+            RepoError::InvalidSignature(msg) => write!(f, "invalid signature: {}", msg),
+            RepoError::Internal(msg) => write!(f, "internal error: {}", msg),
+        }
+    }
+}
+impl std::error::Error for RepoError {}
+
+#[cfg(test)]
+mod test {
+    use anyhow::Context;
+
+    use super::*;
+
+    #[test]
+    fn test_of_finds_wrapped_error() {
+        let e = Err::<(), _>(anyhow::Error::from(RepoError::NotFound("org/a/a/1.0/a-1.0.jar".to_string())))
+            .context("error getting artifact")
+            .unwrap_err();
+        assert!(RepoError::is_not_found(&e));
+        assert_eq!(RepoError::of(&anyhow::anyhow!("untyped")), None);
+    }
+}
@@ -0,0 +1,81 @@
+use std::fmt::{Display, Formatter};
+use std::time::SystemTime;
+
+use serde::Serialize;
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryLaterReason {
+    /// the artifact's download failed recently, and it is not requested again before the retry
+    ///  interval is over
+    DownloadFailedRecently,
+    /// the upstream repository's circuit breaker is open
+    UpstreamUnavailable,
+}
+
+/// A request that was refused without contacting upstream, with the earliest time a retry can
+///  have a different outcome
+#[derive(Clone, Debug)]
+pub struct RetryLaterError {
+    pub reason: RetryLaterReason,
+    pub path: String,
+    pub retry_at: SystemTime,
+}
+impl RetryLaterError {
+    /// for 'Retry-After', rounded up so that clients do not retry too early
+    pub fn retry_after_secs(&self) -> u64 {
+        match self.retry_at.duration_since(SystemTime::now()) {
+            Ok(d) if d.subsec_nanos() > 0 => d.as_secs() + 1,
+            Ok(d) => d.as_secs(),
+            Err(_) => 0,
+        }
+    }
+
+    /// A machine-readable response body
+    pub fn as_json(&self) -> String {
+        serde_json::json!({
+            "error": self.reason,
+            "message": self.to_string(),
+            "retry_after_secs": self.retry_after_secs(),
+            "retry_at": httpdate::fmt_http_date(self.retry_at),
+        }).to_string()
+    }
+}
+impl Display for RetryLaterError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.reason {
+            RetryLaterReason::DownloadFailedRecently => write!(f, "downloading {} failed recently", self.path),
+            RetryLaterReason::UpstreamUnavailable => write!(f, "upstream repository is unavailable, not requesting {}", self.path),
+        }
+    }
+}
+impl std::error::Error for RetryLaterError {}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_as_json() {
+        let e = RetryLaterError {
+            reason: RetryLaterReason::UpstreamUnavailable,
+            path: "org/a/1.0/a-1.0.jar".to_string(),
+            retry_at: SystemTime::now() + Duration::from_millis(29_500),
+        };
+        let json: serde_json::Value = serde_json::from_str(&e.as_json()).unwrap();
+        assert_eq!(json["error"], "upstream_unavailable");
+        assert_eq!(json["retry_after_secs"], 30);
+    }
+
+    #[test]
+    fn test_retry_after_in_the_past() {
+        let e = RetryLaterError {
+            reason: RetryLaterReason::DownloadFailedRecently,
+            path: "org/a/1.0/a-1.0.jar".to_string(),
+            retry_at: SystemTime::now() - Duration::from_secs(1),
+        };
+        assert_eq!(e.retry_after_secs(), 0);
+    }
+}
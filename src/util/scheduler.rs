@@ -0,0 +1,111 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::{debug, warn};
+
+use crate::util::uuid_generator::{random_fraction, UuidGenerator};
+
+/// Runs periodic background jobs (cleanup, metadata refreshes etc.).
+///
+/// When many instances restart at the same time (e.g. a rolling deploy), their jobs would run in
+///  lockstep and hit upstreams and the metadata store all at once. The scheduler therefore
+///  spreads each job's first run randomly over a warm-up window, limits the number of jobs running
+///  concurrently during warm-up, and jitters all subsequent intervals.
+pub struct Scheduler {
+    started: Instant,
+    warm_up: Duration,
+    /// fraction of an interval by which individual runs deviate from it, e.g. 0.1 for +/- 10%
+    jitter: f64,
+    warm_up_permits: Arc<Semaphore>,
+    /// source of randomness, injectable for reproducibility like everything else using UUIDs
+    random: Arc<dyn UuidGenerator>,
+}
+impl Scheduler {
+    pub fn new(random: Arc<dyn UuidGenerator>) -> Scheduler {
+        Scheduler {
+            started: Instant::now(),
+            warm_up: Duration::from_secs(120),
+            jitter: 0.1,
+            warm_up_permits: Arc::new(Semaphore::new(2)),
+            random,
+        }
+    }
+
+    pub fn with_warm_up(self, warm_up: Duration, max_concurrent_jobs: usize) -> Scheduler {
+        Scheduler {
+            warm_up,
+            warm_up_permits: Arc::new(Semaphore::new(max_concurrent_jobs.max(1))),
+            ..self
+        }
+    }
+
+    pub fn with_jitter(self, jitter: f64) -> Scheduler {
+        Scheduler {
+            jitter: jitter.clamp(0.0, 1.0),
+            ..self
+        }
+    }
+
+    /// Runs a job periodically until the returned handle is aborted. Failures are logged, and the
+    ///  job is run again after the next interval.
+    pub fn schedule<F, Fut>(&self, name: &'static str, interval: Duration, job: F) -> JoinHandle<()>
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send,
+    {
+        let warm_up_end = self.started + self.warm_up;
+        let warm_up_permits = self.warm_up_permits.clone();
+        let random = self.random.clone();
+        let jitter = self.jitter;
+
+        let initial_delay = scale(self.warm_up.min(interval), random_fraction(random.as_ref()));
+
+        tokio::spawn(async move {
+            tokio::time::sleep(initial_delay).await;
+            loop {
+                let permit = if Instant::now() < warm_up_end {
+                    warm_up_permits.acquire().await.ok()
+                }
+                else {
+                    None
+                };
+
+                debug!("running scheduled job {}", name);
+                if let Err(e) = job().await {
+                    warn!("scheduled job {} failed: {}", name, e);
+                }
+                drop(permit);
+
+                tokio::time::sleep(jittered(interval, jitter, random_fraction(random.as_ref()))).await;
+            }
+        })
+    }
+}
+
+fn scale(duration: Duration, factor: f64) -> Duration {
+    Duration::from_secs_f64(duration.as_secs_f64() * factor)
+}
+
+/// Deviates from the base duration by at most `jitter` of its length in either direction, with
+///  `random` in [0, 1) selecting the deviation
+fn jittered(base: Duration, jitter: f64, random: f64) -> Duration {
+    scale(base, 1.0 + jitter * (2.0 * random - 1.0))
+}
+
+#[cfg(test)]
+mod test {
+    use rstest::*;
+    use super::*;
+
+    #[rstest]
+    #[case::lower_bound(0.0, 90)]
+    #[case::middle(0.5, 100)]
+    #[case::close_to_upper_bound(0.999, 109)]
+    fn test_jittered(#[case] random: f64, #[case] expected_secs: u64) {
+        assert_eq!(jittered(Duration::from_secs(100), 0.1, random).as_secs(), expected_secs);
+    }
+}
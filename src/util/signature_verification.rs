@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::RwLock;
+
+use anyhow::{anyhow, Context};
+use serde::Deserialize;
+
+use crate::util::pgp::{parse_public_keys, PgpPublicKey};
+use crate::util::validating_http_downloader::ValidatingHttpDownloader;
+
+/// '.asc' files are a few hundred bytes, anything much longer is not a signature
+pub const MAX_SIGNATURE_SIZE: usize = 64*1024;
+/// keyserver responses include all of a key's signatures, which can add up for popular keys
+const MAX_KEYSERVER_RESPONSE_SIZE: usize = 1024*1024;
+
+/// Whether remote repositories request an artifact's '.asc' signature from upstream and verify it
+///  against the [Keyring] before caching the artifact. Artifacts with a signature that does not
+///  match their content are rejected unless signatures are ignored.
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignaturePolicy {
+    /// artifacts are rejected unless they are signed by a known key
+    Require,
+    /// artifacts without a signature, or with a signature that can not be checked (unknown key
+    ///  or unsupported algorithm), are accepted
+    VerifyIfPresent,
+    /// signatures are not requested
+    #[default]
+    Ignore,
+}
+impl FromStr for SignaturePolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<SignaturePolicy> {
+        match s {
+            "require" => Ok(SignaturePolicy::Require),
+            "verify_if_present" => Ok(SignaturePolicy::VerifyIfPresent),
+            "ignore" => Ok(SignaturePolicy::Ignore),
+            _ => Err(anyhow!("invalid signature policy '{}', must be 'require', 'verify_if_present' or 'ignore'", s)),
+        }
+    }
+}
+
+/// The public keys that artifact signatures are verified against, by key id. Keys that are not in
+///  the keyring can be requested from a keyserver.
+#[derive(Default)]
+pub struct Keyring {
+    keys: RwLock<HashMap<u64, PgpPublicKey>>,
+    keyserver: Option<ValidatingHttpDownloader>,
+}
+impl Keyring {
+    pub fn new() -> Keyring {
+        Default::default()
+    }
+
+    /// Reads a key file in ASCII armor or binary, e.g. from 'gpg --export --armor <key ids>'
+    pub fn load(path: &Path) -> anyhow::Result<Keyring> {
+        let data = std::fs::read(path)
+            .with_context(|| format!("error reading keyring {:?}", path))?;
+        let keys = parse_public_keys(&data)
+            .with_context(|| format!("invalid keyring {:?}", path))?;
+        let keyring = Keyring::new();
+        keyring.add_keys(keys);
+        Ok(keyring)
+    }
+
+    /// Keys that are not in the keyring are requested from this HKP keyserver (e.g.
+    ///  'https://keyserver.ubuntu.com') and added to it. Anybody can upload keys to public
+    ///  keyservers, so this only proves that an artifact was not modified after it was signed.
+    ///  Only the requested key is taken from a response, and it never replaces a key that is in
+    ///  the keyring already.
+    pub fn with_keyserver(self, keyserver_uri: String) -> anyhow::Result<Keyring> {
+        Ok(Keyring {
+            keyserver: Some(ValidatingHttpDownloader::new(keyserver_uri)?),
+            ..self
+        })
+    }
+
+    pub fn add_keys(&self, keys: Vec<PgpPublicKey>) {
+        let mut by_id = self.keys.write().unwrap();
+        for key in keys {
+            by_id.insert(key.key_id(), key);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// whether there are keys or a keyserver, i.e. whether any signature can be verified
+    pub fn can_verify(&self) -> bool {
+        !self.is_empty() || self.keyserver.is_some()
+    }
+
+    /// None if the key is neither in the keyring nor on the keyserver
+    pub async fn get(&self, key_id: u64) -> anyhow::Result<Option<PgpPublicKey>> {
+        if let Some(key) = self.keys.read().unwrap().get(&key_id) {
+            return Ok(Some(key.clone()));
+        }
+        let Some(keyserver) = &self.keyserver else {
+            return Ok(None);
+        };
+
+        let response = keyserver.get_bounded(&format!("pks/lookup?op=get&options=mr&search=0x{:016X}", key_id), MAX_KEYSERVER_RESPONSE_SIZE)
+            .await
+            .with_context(|| format!("error requesting key {:016X} from keyserver", key_id))?;
+        Ok(self.add_fetched_key(key_id, parse_public_keys(&response)?))
+    }
+
+    /// Adds the key with 'key_id' from a keyserver response, ignoring any other keys in it. Keys
+    ///  that are in the keyring take precedence, including a different key with the same id.
+    fn add_fetched_key(&self, key_id: u64, keys: Vec<PgpPublicKey>) -> Option<PgpPublicKey> {
+        let fetched = keys.into_iter().find(|key| key.key_id() == key_id)?;
+        Some(self.keys.write().unwrap()
+            .entry(key_id)
+            .or_insert(fetched)
+            .clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hyper::{Body, Request, Response, Server, StatusCode};
+    use hyper::service::{make_service_fn, service_fn};
+
+    use crate::util::pgp::test_keys::{ED25519_KEY, RSA_KEY};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_keyserver() {
+        let make_service = make_service_fn(|_| async {
+            Ok::<_, hyper::Error>(service_fn(|request: Request<Body>| async move {
+                Ok::<_, hyper::Error>(match request.uri().query() {
+                    Some("op=get&options=mr&search=0x2A9DAE32625517B6") => Response::new(Body::from(ED25519_KEY)),
+                    // a response with keys other than the requested one
+                    Some("op=get&options=mr&search=0x1111111111111111") => Response::new(Body::from(ED25519_KEY)),
+                    _ => Response::builder().status(StatusCode::NOT_FOUND).body(Body::empty()).unwrap(),
+                })
+            }))
+        });
+        let server = Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(make_service);
+        let keyserver_uri = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+
+        let keyring = Keyring::new().with_keyserver(keyserver_uri).unwrap();
+        keyring.add_keys(parse_public_keys(RSA_KEY.as_bytes()).unwrap());
+        assert!(keyring.get(0x2FFF3D9D13D54FED).await.unwrap().is_some());
+        assert!(keyring.get(0x0123456789ABCDEF).await.is_err());
+
+        assert!(keyring.get(0x1111111111111111).await.unwrap().is_none());
+        assert_eq!(keyring.len(), 1);
+
+        assert!(keyring.get(0x2A9DAE32625517B6).await.unwrap().is_some());
+        assert_eq!(keyring.len(), 2);
+    }
+
+    #[test]
+    fn test_fetched_key_does_not_replace_keyring() {
+        let keyring = Keyring::new();
+        keyring.add_keys(parse_public_keys(RSA_KEY.as_bytes()).unwrap());
+        let configured = keyring.keys.read().unwrap().values().next().unwrap().clone();
+
+        // a different key with the same 64 bit id
+        let mut substitute = configured.clone();
+        substitute.fingerprint[0] ^= 1;
+        assert_eq!(substitute.key_id(), configured.key_id());
+
+        let ed25519 = parse_public_keys(ED25519_KEY.as_bytes()).unwrap();
+        assert_eq!(keyring.add_fetched_key(configured.key_id(), [ed25519, vec![substitute]].concat()), Some(configured.clone()));
+        assert_eq!(keyring.len(), 1);
+    }
+
+    #[test]
+    fn test_can_verify() {
+        assert!(!Keyring::new().can_verify());
+        assert!(Keyring::new().with_keyserver("https://keyserver.ubuntu.com".to_string()).unwrap().can_verify());
+    }
+}
@@ -0,0 +1,173 @@
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::Stream;
+use tokio::time::{Instant, Sleep};
+
+/// Upstream transfers that are slower than this after a grace period are aborted, so that a single
+///  stalled connection does not hang a build until some TCP timeout kicks in
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub struct SlowTransferPolicy {
+    /// average over the entire transfer so far
+    pub min_bytes_per_sec: u64,
+    /// connection setup and TCP slow start make the beginning of a transfer unrepresentative
+    pub grace_period: Duration,
+}
+impl Default for SlowTransferPolicy {
+    fn default() -> SlowTransferPolicy {
+        SlowTransferPolicy {
+            min_bytes_per_sec: 10*1024,
+            grace_period: Duration::from_secs(10),
+        }
+    }
+}
+impl SlowTransferPolicy {
+    pub fn guard<S: Stream<Item=anyhow::Result<Bytes>>>(&self, inner: S) -> SlowTransferGuard<S> {
+        SlowTransferGuard {
+            inner,
+            policy: *self,
+            start: Instant::now(),
+            num_bytes: 0,
+            timer: Box::pin(tokio::time::sleep(self.grace_period)),
+            is_failed: false,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SlowTransferError {
+    pub num_bytes: u64,
+    pub elapsed: Duration,
+}
+impl Display for SlowTransferError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "slow transfer: {} bytes in {} ms", self.num_bytes, self.elapsed.as_millis())
+    }
+}
+impl std::error::Error for SlowTransferError {}
+
+/// Checks if a download failed because it was aborted by a [SlowTransferGuard]
+pub fn is_slow_transfer(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<SlowTransferError>().is_some()
+}
+
+/// Passes data through, failing with a [SlowTransferError] if the transfer rate drops below the
+///  policy's minimum. This includes transfers that stall completely: a timer wakes the stream up
+///  to check the rate even if no data arrives.
+pub struct SlowTransferGuard<S> {
+    inner: S,
+    policy: SlowTransferPolicy,
+    start: Instant,
+    num_bytes: u64,
+    timer: Pin<Box<Sleep>>,
+    is_failed: bool,
+}
+impl<S> SlowTransferGuard<S> {
+    fn check_rate(&self, now: Instant) -> Option<SlowTransferError> {
+        let elapsed = now.duration_since(self.start);
+        if elapsed < self.policy.grace_period {
+            return None;
+        }
+        if (self.num_bytes as f64) / elapsed.as_secs_f64() >= self.policy.min_bytes_per_sec as f64 {
+            return None;
+        }
+        Some(SlowTransferError {
+            num_bytes: self.num_bytes,
+            elapsed,
+        })
+    }
+}
+impl<S: Stream<Item=anyhow::Result<Bytes>> + Unpin> Stream for SlowTransferGuard<S> {
+    type Item = anyhow::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.is_failed {
+            return Poll::Ready(None);
+        }
+
+        match Pin::new(&mut self.inner).poll_next(cx) {
+            Poll::Ready(Some(Ok(data))) => {
+                self.num_bytes += data.len() as u64;
+                if let Some(e) = self.check_rate(Instant::now()) {
+                    self.is_failed = true;
+                    return Poll::Ready(Some(Err(e.into())));
+                }
+                Poll::Ready(Some(Ok(data)))
+            }
+            Poll::Ready(other) => Poll::Ready(other),
+            Poll::Pending => {
+                if self.timer.as_mut().poll(cx).is_pending() {
+                    return Poll::Pending;
+                }
+
+                let now = Instant::now();
+                if let Some(e) = self.check_rate(now) {
+                    self.is_failed = true;
+                    return Poll::Ready(Some(Err(e.into())));
+                }
+
+                // check again when the transfer would drop below the minimum without further data
+                let deadline = self.start + Duration::from_secs_f64(self.num_bytes as f64 / self.policy.min_bytes_per_sec.max(1) as f64);
+                self.timer.as_mut().reset(deadline.max(now + Duration::from_millis(100)));
+                let _ = self.timer.as_mut().poll(cx);
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::StreamExt;
+
+    use super::*;
+
+    fn policy() -> SlowTransferPolicy {
+        SlowTransferPolicy {
+            min_bytes_per_sec: 100,
+            grace_period: Duration::from_secs(5),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stalled_transfer_fails() {
+        let (tx, rx) = futures::channel::mpsc::unbounded();
+        tx.unbounded_send(Ok(Bytes::from(vec![0u8; 1000]))).unwrap();
+
+        let mut guarded = policy().guard(rx);
+        assert_eq!(guarded.next().await.unwrap().unwrap().len(), 1000);
+
+        // 1000 bytes suffice for 10 seconds at 100 bytes/sec, after that the transfer is too slow
+        let start = Instant::now();
+        let e = guarded.next().await.unwrap().unwrap_err();
+        assert!(is_slow_transfer(&e));
+        assert!(start.elapsed() >= Duration::from_secs(10));
+        assert!(guarded.next().await.is_none());
+        drop(tx);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_fast_transfer_passes() {
+        let data = futures::stream::iter((0..10).map(|_| Ok(Bytes::from(vec![0u8; 1000]))));
+        let guarded = policy().guard(data);
+        assert_eq!(guarded.collect::<Vec<_>>().await.len(), 10);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_trickling_transfer_fails() {
+        let data = futures::stream::iter(0..100)
+            .then(|_| async {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                Ok(Bytes::from_static(b"x"))
+            });
+        let results = policy().guard(Box::pin(data)).collect::<Vec<_>>().await;
+
+        // fails as soon as the grace period is over
+        assert!(results.len() <= 6);
+        assert!(is_slow_transfer(results.last().unwrap().as_ref().unwrap_err()));
+    }
+}
@@ -0,0 +1,314 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use futures::Stream;
+use serde::Serialize;
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::util::uuid_generator::UuidGenerator;
+
+/// the number of log lines kept per task, older lines are dropped
+const MAX_LOG_LINES: usize = 100;
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskState {
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Serialize)]
+pub struct TaskProgress {
+    pub done: u64,
+    pub total: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TaskStatus {
+    pub id: Uuid,
+    /// the operation, e.g. 'warm-up'
+    pub kind: String,
+    pub state: TaskState,
+    /// seconds since the UNIX epoch
+    pub started: u64,
+    pub finished: Option<u64>,
+    /// None for operations that can not estimate their progress
+    pub progress: Option<TaskProgress>,
+    /// the most recent log lines
+    pub log: VecDeque<String>,
+    /// the operation's report, if it succeeded
+    pub result: Option<serde_json::Value>,
+    pub error: Option<String>,
+}
+
+/// Passed to a task's job for reporting progress and checking for cancellation
+#[derive(Clone)]
+pub struct TaskContext {
+    status: Arc<watch::Sender<TaskStatus>>,
+    cancellation: CancellationToken,
+}
+impl TaskContext {
+    /// A context that is not registered anywhere, for running a job directly
+    #[cfg(test)]
+    pub fn detached() -> TaskContext {
+        let (status, _) = watch::channel(new_status(Uuid::nil(), "detached"));
+        TaskContext {
+            status: Arc::new(status),
+            cancellation: CancellationToken::new(),
+        }
+    }
+
+    pub fn set_progress(&self, done: u64, total: u64) {
+        self.status.send_modify(|status| status.progress = Some(TaskProgress { done, total }));
+    }
+
+    pub fn log(&self, line: impl Into<String>) {
+        let line = line.into();
+        self.status.send_modify(|status| {
+            if status.log.len() >= MAX_LOG_LINES {
+                status.log.pop_front();
+            }
+            status.log.push_back(line);
+        });
+    }
+
+    /// Jobs are dropped at their next await point when they are cancelled, so checking this is
+    ///  only necessary for stopping at a consistent point
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+}
+
+struct Task {
+    status: Arc<watch::Sender<TaskStatus>>,
+    cancellation: CancellationToken,
+}
+
+/// Long-running admin operations (warm-up, garbage collection etc.) run as tasks: they are started
+///  in the background and identified by an id, through which clients poll or stream their progress
+///  and result, and cancel them. Finished tasks are kept for querying up to a capacity.
+pub struct TaskRegistry {
+    max_finished: usize,
+    /// in the order they were started
+    tasks: Mutex<Vec<Task>>,
+    uuid_generator: Arc<dyn UuidGenerator>,
+}
+impl TaskRegistry {
+    pub fn new(max_finished: usize, uuid_generator: Arc<dyn UuidGenerator>) -> TaskRegistry {
+        TaskRegistry {
+            max_finished,
+            tasks: Default::default(),
+            uuid_generator,
+        }
+    }
+
+    /// Starts a job as a task, returning its id. The job's result becomes the task's result.
+    pub fn spawn<F, Fut, R>(&self, kind: &str, job: F) -> Uuid
+    where
+        F: FnOnce(TaskContext) -> Fut,
+        Fut: Future<Output = anyhow::Result<R>> + Send + 'static,
+        R: Serialize,
+    {
+        let id = self.uuid_generator.new_uuid();
+        let (status, _) = watch::channel(new_status(id, kind));
+        let context = TaskContext {
+            status: Arc::new(status),
+            cancellation: CancellationToken::new(),
+        };
+        let status = context.status.clone();
+        let cancellation = context.cancellation.clone();
+        self.register(Task {
+            status: status.clone(),
+            cancellation: cancellation.clone(),
+        });
+
+        info!("starting task {} ({})", id, kind);
+        let job = job(context);
+        tokio::spawn(async move {
+            let outcome = tokio::select! {
+                result = job => Some(result),
+                _ = cancellation.cancelled() => None,
+            };
+            status.send_modify(|status| {
+                status.finished = Some(now_secs());
+                match outcome {
+                    Some(Ok(result)) => match serde_json::to_value(result) {
+                        Ok(result) => {
+                            status.state = TaskState::Succeeded;
+                            status.result = Some(result);
+                        }
+                        Err(e) => {
+                            status.state = TaskState::Failed;
+                            status.error = Some(format!("error serializing result: {}", e));
+                        }
+                    },
+                    Some(Err(e)) => {
+                        warn!("task {} ({}) failed: {}", status.id, status.kind, e);
+                        status.state = TaskState::Failed;
+                        status.error = Some(e.to_string());
+                    }
+                    None => {
+                        info!("task {} ({}) was cancelled", status.id, status.kind);
+                        status.state = TaskState::Cancelled;
+                    }
+                }
+            });
+        });
+        id
+    }
+
+    fn register(&self, task: Task) {
+        let mut tasks = self.tasks.lock().unwrap();
+        tasks.push(task);
+
+        let num_finished = tasks.iter().filter(|t| t.status.borrow().state != TaskState::Running).count();
+        let mut num_evicted = num_finished.saturating_sub(self.max_finished);
+        tasks.retain(|t| {
+            if num_evicted > 0 && t.status.borrow().state != TaskState::Running {
+                num_evicted -= 1;
+                false
+            }
+            else {
+                true
+            }
+        });
+    }
+
+    pub fn status(&self, id: &Uuid) -> Option<TaskStatus> {
+        self.tasks.lock().unwrap().iter()
+            .map(|t| t.status.borrow())
+            .find(|status| &status.id == id)
+            .map(|status| status.clone())
+    }
+
+    pub fn statuses(&self) -> Vec<TaskStatus> {
+        self.tasks.lock().unwrap().iter()
+            .map(|t| t.status.borrow().clone())
+            .collect()
+    }
+
+    /// Returns the task's state before cancelling it, i.e. only a running task is cancelled.
+    ///  None if there is no such task.
+    pub fn cancel(&self, id: &Uuid) -> Option<TaskState> {
+        let tasks = self.tasks.lock().unwrap();
+        let task = tasks.iter().find(|t| &t.status.borrow().id == id)?;
+        let state = task.status.borrow().state;
+        if state == TaskState::Running {
+            task.cancellation.cancel();
+        }
+        Some(state)
+    }
+
+    /// The task's current status followed by every change, ending when the task has finished
+    pub fn watch(&self, id: &Uuid) -> Option<impl Stream<Item = TaskStatus>> {
+        let receiver = self.tasks.lock().unwrap().iter()
+            .find(|t| &t.status.borrow().id == id)
+            .map(|t| t.status.subscribe())?;
+
+        Some(futures::stream::unfold((Some(receiver), true), |(receiver, is_first)| async move {
+            let mut receiver = receiver?;
+            if !is_first && receiver.changed().await.is_err() {
+                return None;
+            }
+            let status = receiver.borrow_and_update().clone();
+            let receiver = match status.state {
+                TaskState::Running => Some(receiver),
+                _ => None,
+            };
+            Some((status, (receiver, false)))
+        }))
+    }
+}
+
+fn new_status(id: Uuid, kind: &str) -> TaskStatus {
+    TaskStatus {
+        id,
+        kind: kind.to_string(),
+        state: TaskState::Running,
+        started: now_secs(),
+        finished: None,
+        progress: None,
+        log: VecDeque::new(),
+        result: None,
+        error: None,
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use futures::StreamExt;
+
+    use crate::util::uuid_generator::RandomUuidGenerator;
+
+    use super::*;
+
+    fn registry(max_finished: usize) -> TaskRegistry {
+        TaskRegistry::new(max_finished, Arc::new(RandomUuidGenerator::default()))
+    }
+
+    #[tokio::test]
+    async fn test_progress_and_result() {
+        let tasks = registry(10);
+        let id = tasks.spawn("test", |task| async move {
+            task.set_progress(1, 2);
+            task.log("halfway");
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            task.set_progress(2, 2);
+            Ok(42)
+        });
+
+        let statuses = tasks.watch(&id).unwrap().collect::<Vec<_>>().await;
+        let last = statuses.last().unwrap();
+        assert_eq!(last.state, TaskState::Succeeded);
+        assert_eq!(last.progress, Some(TaskProgress { done: 2, total: 2 }));
+        assert_eq!(last.log, vec!["halfway".to_string()]);
+        assert_eq!(last.result, Some(serde_json::json!(42)));
+        assert_eq!(tasks.status(&id).unwrap().state, TaskState::Succeeded);
+    }
+
+    #[tokio::test]
+    async fn test_cancel() {
+        let tasks = registry(10);
+        let id = tasks.spawn("test", |_| async move {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(())
+        });
+        assert_eq!(tasks.cancel(&id), Some(TaskState::Running));
+
+        let last = tasks.watch(&id).unwrap().collect::<Vec<_>>().await.pop().unwrap();
+        assert_eq!(last.state, TaskState::Cancelled);
+        assert_eq!(tasks.cancel(&id), Some(TaskState::Cancelled));
+        assert_eq!(tasks.cancel(&Uuid::nil()), None);
+    }
+
+    #[tokio::test]
+    async fn test_finished_tasks_are_evicted() {
+        let tasks = registry(1);
+        let first = tasks.spawn("test", |_| async { Err::<(), _>(anyhow::anyhow!("failed")) });
+        tasks.watch(&first).unwrap().collect::<Vec<_>>().await;
+        assert_eq!(tasks.status(&first).unwrap().error.as_deref(), Some("failed"));
+
+        let second = tasks.spawn("test", |_| async { Ok(()) });
+        tasks.watch(&second).unwrap().collect::<Vec<_>>().await;
+        let third = tasks.spawn("test", |_| async { Ok(()) });
+
+        assert!(tasks.status(&first).is_none());
+        assert!(tasks.status(&second).is_some());
+        assert!(tasks.status(&third).is_some());
+    }
+}
@@ -0,0 +1,143 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use futures_core::Stream;
+use serde::Serialize;
+use tracing::{debug, error};
+
+type DataStream = Pin<Box<dyn Stream<Item = anyhow::Result<Bytes>> + Send + 'static>>;
+
+/// Counts responses whose body did not have the blob's recorded size. Clients going away mid-way
+///  are common and harmless, while storage delivering a different number of bytes than it
+///  recorded points to data corruption, so they are counted separately.
+#[derive(Default, Debug)]
+pub struct TransferMetrics {
+    /// the client stopped reading before the body was complete
+    client_disconnects: AtomicU64,
+    /// storage ended the body early (including with an error), or delivered more than the size
+    storage_size_mismatches: AtomicU64,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Serialize)]
+pub struct TransferMetricsSnapshot {
+    pub client_disconnects: u64,
+    pub storage_size_mismatches: u64,
+}
+
+impl TransferMetrics {
+    pub fn new() -> TransferMetrics {
+        Default::default()
+    }
+
+    pub fn snapshot(&self) -> TransferMetricsSnapshot {
+        TransferMetricsSnapshot {
+            client_disconnects: self.client_disconnects.load(Ordering::Relaxed),
+            storage_size_mismatches: self.storage_size_mismatches.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Wraps a response body, checking the number of bytes served against the expected size.
+    ///  Bodies of unknown size are passed through unchanged.
+    pub fn measure(self: &Arc<Self>, path: String, expected_size: Option<u64>, data: DataStream) -> DataStream {
+        match expected_size {
+            None => data,
+            Some(expected_size) => Box::pin(SizeCheckingStream {
+                metrics: self.clone(),
+                path,
+                expected_size,
+                num_bytes: 0,
+                is_done: false,
+                data,
+            }),
+        }
+    }
+}
+
+struct SizeCheckingStream {
+    metrics: Arc<TransferMetrics>,
+    path: String,
+    expected_size: u64,
+    num_bytes: u64,
+    is_done: bool,
+    data: DataStream,
+}
+impl SizeCheckingStream {
+    fn storage_mismatch(&mut self, reason: &str) {
+        self.is_done = true;
+        self.metrics.storage_size_mismatches.fetch_add(1, Ordering::Relaxed);
+        error!("served {} bytes of {} instead of {}: {} - this may be a data integrity problem", self.num_bytes, self.path, self.expected_size, reason);
+    }
+}
+impl Stream for SizeCheckingStream {
+    type Item = anyhow::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let result = self.data.as_mut().poll_next(cx);
+        match &result {
+            Poll::Ready(Some(Ok(bytes))) => {
+                self.num_bytes += bytes.len() as u64;
+            }
+            Poll::Ready(Some(Err(e))) => {
+                let reason = format!("storage error {}", e);
+                self.storage_mismatch(&reason);
+            }
+            Poll::Ready(None) if !self.is_done && self.num_bytes != self.expected_size => {
+                self.storage_mismatch("unexpected end of data");
+            }
+            Poll::Ready(None) => {
+                self.is_done = true;
+            }
+            Poll::Pending => {}
+        }
+        result
+    }
+}
+impl Drop for SizeCheckingStream {
+    fn drop(&mut self) {
+        if !self.is_done {
+            self.metrics.client_disconnects.fetch_add(1, Ordering::Relaxed);
+            debug!("client disconnected after {} of {} bytes of {}", self.num_bytes, self.expected_size, self.path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::StreamExt;
+
+    use super::*;
+
+    fn data(chunks: Vec<anyhow::Result<&'static [u8]>>) -> DataStream {
+        Box::pin(futures::stream::iter(chunks.into_iter().map(|c| c.map(Bytes::from_static))))
+    }
+
+    #[tokio::test]
+    async fn test_complete() {
+        let metrics = Arc::new(TransferMetrics::new());
+        let stream = metrics.measure("a".to_string(), Some(3), data(vec![Ok(b"ab"), Ok(b"c")]));
+        assert_eq!(stream.collect::<Vec<_>>().await.len(), 2);
+        assert_eq!(metrics.snapshot(), TransferMetricsSnapshot { client_disconnects: 0, storage_size_mismatches: 0 });
+    }
+
+    #[tokio::test]
+    async fn test_client_disconnect() {
+        let metrics = Arc::new(TransferMetrics::new());
+        let mut stream = metrics.measure("a".to_string(), Some(3), data(vec![Ok(b"ab"), Ok(b"c")]));
+        stream.next().await;
+        drop(stream);
+        assert_eq!(metrics.snapshot(), TransferMetricsSnapshot { client_disconnects: 1, storage_size_mismatches: 0 });
+    }
+
+    #[tokio::test]
+    async fn test_storage_mismatch() {
+        let metrics = Arc::new(TransferMetrics::new());
+        let stream = metrics.measure("a".to_string(), Some(3), data(vec![Ok(b"ab")]));
+        stream.collect::<Vec<_>>().await;
+        let stream = metrics.measure("a".to_string(), Some(3), data(vec![Ok(b"ab"), Err(anyhow::anyhow!("read error"))]));
+        stream.collect::<Vec<_>>().await;
+        assert_eq!(metrics.snapshot(), TransferMetricsSnapshot { client_disconnects: 0, storage_size_mismatches: 2 });
+    }
+}
@@ -0,0 +1,253 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{anyhow, Context};
+use hyper::{Body, Client};
+use hyper::client::HttpConnector;
+use hyper_tls::HttpsConnector;
+use native_tls::{Certificate, Identity, TlsConnector};
+use serde::Deserialize;
+use tracing::{info, warn};
+
+pub type HttpsClient = Client<HttpsConnector<HttpConnector>, Body>;
+
+/// certificate files are checked for changes at most this often
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A client certificate for upstream servers that require mutual TLS. Both files are PEM encoded,
+///  the key in PKCS#8 format.
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize)]
+pub struct ClientCertificate {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
+
+/// TLS settings for connecting to an upstream repository
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize)]
+#[serde(default)]
+pub struct UpstreamTlsConfig {
+    pub client_certificate: Option<ClientCertificate>,
+    /// PEM files with CA certificates to trust, e.g. for a mirror with a certificate from an
+    ///  internal CA
+    pub ca_certificates: Vec<PathBuf>,
+    /// false trusts only `ca_certificates`, i.e. makes them a complete trust store
+    pub use_system_roots: bool,
+    /// accepts any server certificate - an escape hatch for lab environments, never for production
+    pub insecure_skip_verify: bool,
+}
+impl Default for UpstreamTlsConfig {
+    fn default() -> UpstreamTlsConfig {
+        UpstreamTlsConfig {
+            client_certificate: None,
+            ca_certificates: vec![],
+            use_system_roots: true,
+            insecure_skip_verify: false,
+        }
+    }
+}
+impl UpstreamTlsConfig {
+    fn files(&self) -> Vec<&Path> {
+        let mut result = self.client_certificate.iter()
+            .flat_map(|c| [c.cert_path.as_path(), c.key_path.as_path()])
+            .collect::<Vec<_>>();
+        result.extend(self.ca_certificates.iter().map(|p| p.as_path()));
+        result
+    }
+
+    /// None if a file does not exist (or can not be accessed)
+    fn modified(&self) -> Option<Vec<SystemTime>> {
+        self.files().into_iter()
+            .map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+            .collect()
+    }
+
+    fn load(&self) -> anyhow::Result<TlsMaterial> {
+        let identity = match &self.client_certificate {
+            None => None,
+            Some(c) => {
+                let cert = std::fs::read(&c.cert_path)
+                    .with_context(|| format!("error reading client certificate {}", c.cert_path.display()))?;
+                let key = std::fs::read(&c.key_path)
+                    .with_context(|| format!("error reading client key {}", c.key_path.display()))?;
+                Some(Identity::from_pkcs8(&cert, &key)
+                    .with_context(|| format!("invalid client certificate {} or key {}", c.cert_path.display(), c.key_path.display()))?)
+            }
+        };
+
+        let mut ca_certificates = vec![];
+        for path in &self.ca_certificates {
+            let pem = std::fs::read_to_string(path)
+                .with_context(|| format!("error reading CA certificates {}", path.display()))?;
+            let certificates = split_pem_certificates(&pem);
+            if certificates.is_empty() {
+                return Err(anyhow!("no PEM encoded certificates in {}", path.display()));
+            }
+            for certificate in certificates {
+                ca_certificates.push(Certificate::from_pem(certificate.as_bytes())
+                    .with_context(|| format!("invalid CA certificate in {}", path.display()))?);
+            }
+        }
+
+        if !self.use_system_roots && ca_certificates.is_empty() {
+            return Err(anyhow!("without system roots, at least one CA certificate must be configured"));
+        }
+
+        Ok(TlsMaterial {
+            identity,
+            ca_certificates,
+        })
+    }
+}
+
+/// The 'BEGIN CERTIFICATE' / 'END CERTIFICATE' blocks of a PEM file, e.g. a CA bundle
+fn split_pem_certificates(pem: &str) -> Vec<&str> {
+    const BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+    const END: &str = "-----END CERTIFICATE-----";
+
+    let mut result = vec![];
+    let mut rest = pem;
+    while let Some(start) = rest.find(BEGIN) {
+        match rest[start..].find(END) {
+            Some(len) => {
+                let end = start + len + END.len();
+                result.push(&rest[start..end]);
+                rest = &rest[end..];
+            }
+            None => break,
+        }
+    }
+    result
+}
+
+#[derive(Clone)]
+struct TlsMaterial {
+    identity: Option<Identity>,
+    ca_certificates: Vec<Certificate>,
+}
+
+struct LoadedClient {
+    client: HttpsClient,
+    material: TlsMaterial,
+    /// of the files the TLS material was loaded from
+    modified: Option<Vec<SystemTime>>,
+    last_check: Instant,
+}
+
+/// Provides the HTTP client for an upstream repository with its TLS settings. Certificates are
+///  reloaded when their files change, so that they can be rotated without a restart.
+pub struct UpstreamClient {
+    tls_config: UpstreamTlsConfig,
+    loaded: Mutex<LoadedClient>,
+}
+impl UpstreamClient {
+    /// Fails if certificates can not be loaded
+    pub fn new(tls_config: UpstreamTlsConfig) -> anyhow::Result<UpstreamClient> {
+        if tls_config.insecure_skip_verify {
+            warn!("TLS certificate verification is disabled for an upstream repository");
+        }
+
+        let modified = tls_config.modified();
+        let material = tls_config.load()?;
+        Ok(UpstreamClient {
+            loaded: Mutex::new(LoadedClient {
+                client: build_client(&tls_config, &material, true)?,
+                material,
+                modified,
+                last_check: Instant::now(),
+            }),
+            tls_config,
+        })
+    }
+
+    /// The pooled client, which is replaced if certificate files changed
+    pub fn client(&self) -> HttpsClient {
+        let mut loaded = self.loaded.lock().unwrap();
+        if !self.tls_config.files().is_empty() && loaded.last_check.elapsed() >= RELOAD_CHECK_INTERVAL {
+            loaded.last_check = Instant::now();
+            let modified = self.tls_config.modified();
+            if modified.is_some() && modified != loaded.modified {
+                // e.g. a rotation in progress that wrote only some of the files - keep using the
+                //  previous certificates and try again later
+                match self.tls_config.load().and_then(|material| Ok((build_client(&self.tls_config, &material, true)?, material))) {
+                    Ok((client, material)) => {
+                        info!("reloaded upstream TLS certificates");
+                        loaded.client = client;
+                        loaded.material = material;
+                        loaded.modified = modified;
+                    }
+                    Err(e) => warn!("error reloading upstream TLS certificates: {:#}", e),
+                }
+            }
+        }
+        loaded.client.clone()
+    }
+
+    /// A client that does not reuse pooled connections, e.g. for retrying after a transfer stalled
+    ///  on a pooled connection
+    pub fn fresh_client(&self) -> anyhow::Result<HttpsClient> {
+        let material = self.loaded.lock().unwrap().material.clone();
+        build_client(&self.tls_config, &material, false)
+    }
+}
+
+fn build_client(tls_config: &UpstreamTlsConfig, material: &TlsMaterial, pooled: bool) -> anyhow::Result<HttpsClient> {
+    let mut tls = TlsConnector::builder();
+    if let Some(identity) = &material.identity {
+        tls.identity(identity.clone());
+    }
+    for certificate in &material.ca_certificates {
+        tls.add_root_certificate(certificate.clone());
+    }
+    tls.disable_built_in_roots(!tls_config.use_system_roots);
+    if tls_config.insecure_skip_verify {
+        tls.danger_accept_invalid_certs(true);
+        tls.danger_accept_invalid_hostnames(true);
+    }
+
+    let mut http = HttpConnector::new();
+    http.enforce_http(false);
+    let connector = HttpsConnector::from((http, tls.build()?.into()));
+
+    let mut builder = Client::builder();
+    if !pooled {
+        builder.pool_max_idle_per_host(0);
+    }
+    Ok(builder.build(connector))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_missing_client_certificate_fails() {
+        let tls_config = UpstreamTlsConfig {
+            client_certificate: Some(ClientCertificate {
+                cert_path: "/nonexistent/client.pem".into(),
+                key_path: "/nonexistent/client.key".into(),
+            }),
+            ..Default::default()
+        };
+        let e = UpstreamClient::new(tls_config).err().unwrap();
+        assert!(format!("{:#}", e).contains("/nonexistent/client.pem"));
+    }
+
+    #[test]
+    fn test_trust_store_requires_ca_certificates() {
+        let tls_config = UpstreamTlsConfig {
+            use_system_roots: false,
+            ..Default::default()
+        };
+        assert!(UpstreamClient::new(tls_config).is_err());
+    }
+
+    #[test]
+    fn test_split_pem_certificates() {
+        let pem = "# bundle\n-----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----\n\n-----BEGIN CERTIFICATE-----\nBBBB\n-----END CERTIFICATE-----\n-----BEGIN CERTIFICATE-----\ntruncated";
+        assert_eq!(split_pem_certificates(pem), vec![
+            "-----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----",
+            "-----BEGIN CERTIFICATE-----\nBBBB\n-----END CERTIFICATE-----",
+        ]);
+    }
+}
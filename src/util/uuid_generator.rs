@@ -0,0 +1,108 @@
+use std::fmt::Debug;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use uuid::{Builder, Uuid};
+
+/// Source of UUIDs for blob keys and correlation ids. This is injectable so that storage layout
+///  and logs can be made reproducible in integration tests and bug reports.
+pub trait UuidGenerator: Send + Sync + Debug {
+    fn new_uuid(&self) -> Uuid;
+}
+
+/// The default: random UUIDs (v4)
+#[derive(Default, Debug)]
+pub struct RandomUuidGenerator {}
+impl UuidGenerator for RandomUuidGenerator {
+    fn new_uuid(&self) -> Uuid {
+        Uuid::new_v4()
+    }
+}
+
+/// Pseudo-random v4 UUIDs from a fixed seed, i.e. the same seed produces the same sequence of UUIDs
+#[derive(Debug)]
+pub struct SeededUuidGenerator {
+    state: Mutex<u64>,
+}
+impl SeededUuidGenerator {
+    pub fn new(seed: u64) -> SeededUuidGenerator {
+        SeededUuidGenerator {
+            state: Mutex::new(seed),
+        }
+    }
+
+    /// splitmix64 - not cryptographically secure, but good enough for reproducible test data
+    fn next_u64(state: &mut u64) -> u64 {
+        *state = state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = *state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+}
+impl UuidGenerator for SeededUuidGenerator {
+    fn new_uuid(&self) -> Uuid {
+        let mut state = self.state.lock().unwrap();
+        let high = Self::next_u64(&mut state);
+        let low = Self::next_u64(&mut state);
+
+        let mut bytes = [0u8; 16];
+        bytes[..8].copy_from_slice(&high.to_be_bytes());
+        bytes[8..].copy_from_slice(&low.to_be_bytes());
+        Builder::from_random_bytes(bytes).into_uuid()
+    }
+}
+
+/// UUIDs counting up from 00000000-0000-0000-0000-000000000001, for maximum readability in tests
+#[derive(Default, Debug)]
+pub struct SequentialUuidGenerator {
+    counter: AtomicU64,
+}
+impl UuidGenerator for SequentialUuidGenerator {
+    fn new_uuid(&self) -> Uuid {
+        Uuid::from_u64_pair(0, self.counter.fetch_add(1, Ordering::SeqCst) + 1)
+    }
+}
+
+/// A random number in [0, 1) taken from a generator's UUIDs. This allows code that needs
+///  randomness (e.g. for jitter) to be reproducible with a [SeededUuidGenerator].
+pub fn random_fraction(random: &dyn UuidGenerator) -> f64 {
+    (random.new_uuid().as_u64_pair().1 >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_seeded_is_reproducible() {
+        let a = SeededUuidGenerator::new(42);
+        let b = SeededUuidGenerator::new(42);
+        let c = SeededUuidGenerator::new(43);
+
+        let from_a: Vec<Uuid> = (0..3).map(|_| a.new_uuid()).collect();
+        let from_b: Vec<Uuid> = (0..3).map(|_| b.new_uuid()).collect();
+        let from_c: Vec<Uuid> = (0..3).map(|_| c.new_uuid()).collect();
+
+        assert_eq!(from_a, from_b);
+        assert_ne!(from_a, from_c);
+        assert_ne!(from_a[0], from_a[1]);
+        assert_eq!(from_a[0].get_version_num(), 4);
+    }
+
+    #[test]
+    fn test_random_fraction() {
+        let random = SeededUuidGenerator::new(1);
+        for _ in 0..100 {
+            let f = random_fraction(&random);
+            assert!((0.0..1.0).contains(&f));
+        }
+    }
+
+    #[test]
+    fn test_sequential() {
+        let generator = SequentialUuidGenerator::default();
+        assert_eq!(generator.new_uuid().as_hyphenated().to_string(), "00000000-0000-0000-0000-000000000001");
+        assert_eq!(generator.new_uuid().as_hyphenated().to_string(), "00000000-0000-0000-0000-000000000002");
+    }
+}
@@ -1,5 +1,6 @@
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::Instant;
 
 use bytes::Bytes;
 use futures_core::{ready, Stream};
@@ -8,35 +9,49 @@ use pin_project_lite::pin_project;
 use sha1::{Digest, Sha1};
 use sha1::digest::consts::U20;
 use sha1::digest::generic_array::GenericArray;
-use tracing::trace;
+use tracing::{debug, Span, trace};
 
-/// This struct wraps an HTTP body, allowing it to be consumed asynchronously without materializing
-///  it but at the same time performing validation that requires knowledge of the entire body's
-///  data (e.g. SHA1 checksum check).
+use crate::util::repo_error::RepoError;
+
+/// This struct wraps a stream of data (e.g. an HTTP body or a file), allowing it to be consumed
+///  asynchronously without materializing it but at the same time performing validation that
+///  requires knowledge of the entire data (e.g. SHA1 checksum check).
 ///
 /// The actual contract is to append an (empty) chunk of data to the stream with an error if the
 ///  validation fails. Once a stream chunk with an error was returned, this stream will stop
 ///  polling from upstream and always return an error
+///
+/// The number of bytes and the transfer duration are recorded as 'bytes' and 'duration_ms' fields
+///  in the span that is current when the stream is created, if that span declares them.
 pin_project! {
-    pub struct ValidatingHttpBody {
+    pub struct ValidatingStream<S> {
         #[pin]
-        http_body: Body,
+        inner: S,
         validators: Vec<Box<dyn HttpBodyValidator>>,
         is_failed: bool,
+        span: Span,
+        start: Instant,
+        num_bytes: u64,
     }
 }
-impl ValidatingHttpBody {
-    pub fn new(http_body: Body, validators: Vec<Box<dyn HttpBodyValidator>>) -> ValidatingHttpBody {
-        ValidatingHttpBody {
-            http_body,
+impl<S> ValidatingStream<S> {
+    pub fn new(inner: S, validators: Vec<Box<dyn HttpBodyValidator>>) -> ValidatingStream<S> {
+        ValidatingStream {
+            inner,
             validators,
             is_failed: false,
+            span: Span::current(),
+            start: Instant::now(),
+            num_bytes: 0,
         }
     }
 }
-unsafe impl Send for ValidatingHttpBody {}
 
-impl Stream for ValidatingHttpBody {
+pub type ValidatingHttpBody = ValidatingStream<Body>;
+
+impl<S, E> Stream for ValidatingStream<S>
+    where S: Stream<Item=Result<Bytes, E>>, E: Into<anyhow::Error>
+{
     type Item = anyhow::Result<Bytes>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
@@ -46,23 +61,28 @@ impl Stream for ValidatingHttpBody {
         }
 
         let this = self.project();
-        let inner = ready!(this.http_body.poll_next(cx));
+        let inner = ready!(this.inner.poll_next(cx));
         match inner {
             Some(Ok(data)) => {
-                // available data from the wrapped HTTP body -> pass this on
+                // available data from the wrapped stream -> pass this on
                 for v in this.validators {
                     v.add_data(&data);
                 }
+                *this.num_bytes += data.len() as u64;
                 Poll::Ready(Some(Ok(data)))
             }
             None => {
-                // wrapped HTTP body is fully drained -> finalize validation
+                // wrapped stream is fully drained -> finalize validation
+                this.span.record("bytes", *this.num_bytes);
+                this.span.record("duration_ms", this.start.elapsed().as_millis() as u64);
+                this.span.in_scope(|| debug!("finished receiving data"));
+
                 if this.validators.iter().all(|v| v.do_validate()) {
                     Poll::Ready(None)
                 }
                 else {
                     *this.is_failed = true;
-                    Poll::Ready(Some(Err(anyhow::Error::msg("failed validation"))))
+                    Poll::Ready(Some(Err(RepoError::ChecksumMismatch("failed validation".to_string()).into())))
                 }
             }
             Some(Err(e)) => {
@@ -73,7 +93,7 @@ impl Stream for ValidatingHttpBody {
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        self.http_body.size_hint()
+        self.inner.size_hint()
     }
 }
 
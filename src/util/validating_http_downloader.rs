@@ -1,10 +1,23 @@
-use hex::FromHex;
-use hyper::{Body, Client, Request, Uri};
-use hyper::client::HttpConnector;
-use hyper::header::USER_AGENT;
-use hyper_tls::HttpsConnector;
-use tracing::trace;
+use anyhow::anyhow;
+use bytes::{Bytes, BytesMut};
+use futures::StreamExt;
+use std::sync::Arc;
+use std::time::Instant;
+
+use hyper::{Body, Request, StatusCode, Uri};
+use hyper::header::{CONTENT_LENGTH, CONTENT_RANGE, HeaderName, LAST_MODIFIED, RANGE, USER_AGENT};
+use tracing::{debug, Span, trace};
+use crate::maven::paths::ChecksumKind;
 use crate::util::blob::Blob;
+use crate::util::byte_range::{BlobRange, ByteRange, parse_content_range, slice_stream};
+use crate::util::checksum_file::{ChecksumFilePolicy, MAX_CHECKSUM_FILE_SIZE, parse_md5_file, parse_sha1_file};
+use crate::util::checksum_headers::{ChecksumHeader, default_checksum_headers, find_checksum};
+use crate::util::prometheus_metrics::{UPSTREAM_FAILURES, UPSTREAM_REQUEST_DURATION};
+use crate::util::repo_error::RepoError;
+use crate::util::slow_transfer::SlowTransferPolicy;
+use crate::util::upstream_client::{HttpsClient, UpstreamClient, UpstreamTlsConfig};
+#[cfg(feature = "fault-injection")]
+use crate::util::fault_injection::{FaultInjector, FaultOperation};
 
 use crate::util::validating_http_body::{HttpBodyValidator, Md5HttpBodyValidator, Sha1HttpBodyValidator, ValidatingHttpBody};
 
@@ -12,9 +25,19 @@ use crate::util::validating_http_body::{HttpBodyValidator, Md5HttpBodyValidator,
 ///  if one is returned in a header.
 ///
 /// Instances do HTTP connection caching internally, so keeping them alive has performance benefits.
+#[derive(Clone)]
 pub struct ValidatingHttpDownloader {
-    client: Client<HttpsConnector<HttpConnector>>,
+    client: Arc<UpstreamClient>,
     base_uri: String, // with trailing '/'
+    /// the base URI's host, for labelling metrics
+    upstream_label: String,
+    captured_headers: Vec<HeaderName>,
+    /// response headers that downloads are validated against
+    checksum_headers: Vec<ChecksumHeader>,
+    checksum_file_policy: ChecksumFilePolicy,
+    slow_transfer_policy: Option<SlowTransferPolicy>,
+    #[cfg(feature = "fault-injection")]
+    fault_injector: Option<std::sync::Arc<FaultInjector>>,
 }
 impl ValidatingHttpDownloader {
     pub fn new(base_uri: String) -> anyhow::Result<ValidatingHttpDownloader> {
@@ -27,13 +50,103 @@ impl ValidatingHttpDownloader {
         Uri::try_from(base_uri.clone())?;
 
         Ok(ValidatingHttpDownloader {
-            client: Client::builder()
-                .build::<_, Body>(HttpsConnector::new()),
+            client: Arc::new(UpstreamClient::new(Default::default())?),
+            upstream_label: upstream_label(&base_uri),
             base_uri,
+            captured_headers: vec![],
+            checksum_headers: default_checksum_headers(),
+            checksum_file_policy: Default::default(),
+            slow_transfer_policy: None,
+            #[cfg(feature = "fault-injection")]
+            fault_injector: None,
+        })
+    }
+
+    /// Response headers with these names are returned as [Blob::upstream_headers] by [Self::get]
+    pub fn with_captured_headers(self, captured_headers: Vec<HeaderName>) -> ValidatingHttpDownloader {
+        ValidatingHttpDownloader {
+            captured_headers,
+            ..self
+        }
+    }
+
+    /// The response headers checked for checksums of downloaded files, in order, see
+    ///  [default_checksum_headers]
+    pub fn with_checksum_headers(self, checksum_headers: Vec<ChecksumHeader>) -> ValidatingHttpDownloader {
+        ValidatingHttpDownloader {
+            checksum_headers,
+            ..self
+        }
+    }
+
+    /// Whether [Self::get] validates downloads without a checksum header against upstream's
+    ///  checksum files, see [ChecksumFilePolicy]
+    pub fn with_checksum_file_policy(self, checksum_file_policy: ChecksumFilePolicy) -> ValidatingHttpDownloader {
+        ValidatingHttpDownloader {
+            checksum_file_policy,
+            ..self
+        }
+    }
+
+    /// Downloads by [Self::get] fail with a [SlowTransferError](crate::util::slow_transfer::SlowTransferError)
+    ///  if they are slower than the policy allows
+    pub fn with_slow_transfer_policy(self, slow_transfer_policy: Option<SlowTransferPolicy>) -> ValidatingHttpDownloader {
+        ValidatingHttpDownloader {
+            slow_transfer_policy,
+            ..self
+        }
+    }
+
+    /// e.g. for upstream servers requiring mutual TLS or using an internal CA. Fails if certificates
+    ///  can not be loaded.
+    pub fn with_tls_config(self, tls_config: UpstreamTlsConfig) -> anyhow::Result<ValidatingHttpDownloader> {
+        Ok(ValidatingHttpDownloader {
+            client: Arc::new(UpstreamClient::new(tls_config)?),
+            ..self
         })
     }
 
+    /// A downloader for a different base URI (e.g. a mirror of the same repository) with this
+    ///  one's client, TLS configuration and settings
+    pub fn for_base_uri(&self, base_uri: String) -> anyhow::Result<ValidatingHttpDownloader> {
+        let mut base_uri = base_uri;
+        if !base_uri.ends_with('/') {
+            base_uri.push('/');
+        }
+        Uri::try_from(base_uri.clone())?;
+
+        Ok(ValidatingHttpDownloader {
+            upstream_label: upstream_label(&base_uri),
+            base_uri,
+            ..self.clone()
+        })
+    }
+
+    #[cfg(feature = "fault-injection")]
+    pub fn with_fault_injector(self, fault_injector: std::sync::Arc<FaultInjector>) -> ValidatingHttpDownloader {
+        ValidatingHttpDownloader {
+            fault_injector: Some(fault_injector),
+            ..self
+        }
+    }
+
     pub async fn get(&self, path: &str) -> anyhow::Result<Blob> {
+        self.get_with_client(&self.client.client(), path).await
+    }
+
+    /// Like [Self::get], but bypassing the connection pool, e.g. for retrying after a transfer
+    ///  stalled on a pooled connection
+    pub async fn get_with_fresh_connection(&self, path: &str) -> anyhow::Result<Blob> {
+        self.get_with_client(&self.client.fresh_client()?, path).await
+    }
+
+    #[tracing::instrument(level = "debug", skip(self, client), fields(status, bytes, duration_ms))]
+    async fn get_with_client(&self, client: &HttpsClient, path: &str) -> anyhow::Result<Blob> {
+        #[cfg(feature = "fault-injection")]
+        if let Some(injector) = &self.fault_injector {
+            injector.inject(FaultOperation::Download, Some(path)).await?;
+        }
+
         let artifact_path = format!("{}{}", self.base_uri, path);
         let request = Request::builder()
             .method("GET")
@@ -43,40 +156,287 @@ impl ValidatingHttpDownloader {
 
         trace!("getting {:?}", request);
 
-        let artifact_response = self.client.request(request)
-            .await?;
+        // requested in parallel so that it does not add latency, even if it turns out to be unneeded
+        let checksum_file = async {
+            match self.checksum_file_policy {
+                ChecksumFilePolicy::Ignore => None,
+                _ if is_checksum_path(path) => None,
+                _ => Some(self.get_checksum_file(path).await),
+            }
+        };
+
+        let artifact_response = async {
+            let start = Instant::now();
+            let response = client.request(request).await;
+            if response.is_ok() {
+                UPSTREAM_REQUEST_DURATION.with_label_values(&[&self.upstream_label]).observe(start.elapsed().as_secs_f64());
+            }
+            response
+        };
+        let (artifact_response, checksum_file) = futures::join!(artifact_response, checksum_file);
+        let artifact_response = artifact_response
+            .map_err(|e| {
+                self.count_failure("unavailable");
+                RepoError::UpstreamUnavailable(format!("error requesting {}: {}", artifact_path, e))
+            })?;
+        Span::current().record("status", artifact_response.status().as_u16());
+        match artifact_response.status() {
+            StatusCode::NOT_FOUND | StatusCode::GONE => {
+                self.count_failure("not_found");
+                return Err(RepoError::NotFound(artifact_path).into());
+            }
+            status if !status.is_success() => {
+                self.count_failure("status");
+                return Err(RepoError::UpstreamUnavailable(format!("upstream returned status {} for {}", status, artifact_path)).into());
+            }
+            _ => {}
+        }
+
+        let mut sha1 = find_checksum(&self.checksum_headers, ChecksumKind::Sha1, artifact_response.headers())?;
+        let mut md5 = find_checksum(&self.checksum_headers, ChecksumKind::Md5, artifact_response.headers())?;
+        if let (None, None, Some(checksum_file)) = (&sha1, &md5, checksum_file) {
+            match checksum_file {
+                Ok((ChecksumKind::Sha1, checksum)) => sha1 = Some(checksum),
+                Ok((_, checksum)) => md5 = Some(checksum),
+                Err(e) if self.checksum_file_policy == ChecksumFilePolicy::Require => {
+                    self.count_failure("no_checksum");
+                    return Err(RepoError::UpstreamUnavailable(format!("no checksum for {}: {}", artifact_path, e)).into());
+                }
+                Err(e) => debug!("no checksum for {}: {}", artifact_path, e),
+            }
+        }
 
-        let sha1_hash_header = artifact_response.headers().get("x-checksum-sha1")
-            .or_else(|| artifact_response.headers().get("x-goog-meta-checksum-sha1"))
-            .or_else(|| artifact_response.headers().get("etag"))
-            ;
-        let sha1_string = sha1_hash_header
-            .map(|h| h.to_str().unwrap_or(""))
-            .map(|s| if s.len() == 42 { &s[1..41] } else { s } );
+        let size = artifact_response.headers().get(CONTENT_LENGTH)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|s| s.parse().ok());
 
-        let md5_string = artifact_response.headers().get("x-checksum-md5")
-            .or_else(|| artifact_response.headers().get("x-goog-meta-checksum-md5"))
-            .map(|h| h.to_str().unwrap_or(""))
-            ;
+        // an unparseable timestamp is not worth failing the download for
+        let last_modified = artifact_response.headers().get(LAST_MODIFIED)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|s| httpdate::parse_http_date(s).ok());
+
+        // header values that are not valid strings are skipped rather than passed on mangled
+        let upstream_headers = self.captured_headers.iter()
+            .flat_map(|name| artifact_response.headers().get_all(name).iter()
+                .filter_map(|value| value.to_str().ok())
+                .map(|value| (name.as_str().to_string(), value.to_string())))
+            .collect();
 
         let mut expected_sha1 = None;
         let mut expected_md5 = None;
 
         let mut validators: Vec<Box<dyn HttpBodyValidator>> = vec![];
-        if let Some(sha1) = sha1_string {
-            let expected_hash = <[u8;20]>::from_hex(sha1)?; //TODO how to handle invalid content in an sha1 tag? Reject? Fall-through to other hashes?
+        if let Some(sha1) = sha1 {
+            let expected_hash = <[u8;20]>::try_from(sha1.as_slice())?;
             expected_sha1 = Some(expected_hash.clone());
             validators.push(Box::new( Sha1HttpBodyValidator::new(expected_hash)));
         }
-        if let Some(md5) = md5_string {
-            let expected_hash = <[u8;16]>::from_hex(md5)?; //TODO how to handle invalid content in an sha1 tag? Reject? Fall-through to other hashes?
+        if let Some(md5) = md5 {
+            let expected_hash = <[u8;16]>::try_from(md5.as_slice())?;
             expected_md5 = Some(expected_hash.clone());
             validators.push(Box::new(Md5HttpBodyValidator::new(expected_hash)));
         }
+        let body = ValidatingHttpBody::new(artifact_response.into_body(), validators);
         Ok(Blob {
-            data: Box::pin(ValidatingHttpBody::new(artifact_response.into_body(), validators)),
+            data: match &self.slow_transfer_policy {
+                Some(policy) => Box::pin(policy.guard(body)),
+                None => Box::pin(body),
+            },
             md5: expected_md5,
             sha1: expected_sha1,
+            size,
+            last_modified,
+            upstream_headers,
         })
     }
+
+    /// The checksum from upstream's '.sha1' file for a path, or from its '.md5' file if there is
+    ///  no '.sha1' file
+    async fn get_checksum_file(&self, path: &str) -> anyhow::Result<(ChecksumKind, Vec<u8>)> {
+        let sha1_path = format!("{}.sha1", path);
+        let sha1_error = match self.get_bounded(&sha1_path, MAX_CHECKSUM_FILE_SIZE).await {
+            Ok(sha1_file) => return parse_sha1_file(&String::from_utf8_lossy(&sha1_file))
+                .map(|sha1| (ChecksumKind::Sha1, sha1.to_vec()))
+                .ok_or_else(|| anyhow!("invalid checksum file {}", sha1_path)),
+            Err(e) => e,
+        };
+        let md5_path = format!("{}.md5", path);
+        match self.get_bounded(&md5_path, MAX_CHECKSUM_FILE_SIZE).await {
+            Ok(md5_file) => parse_md5_file(&String::from_utf8_lossy(&md5_file))
+                .map(|md5| (ChecksumKind::Md5, md5.to_vec()))
+                .ok_or_else(|| anyhow!("invalid checksum file {}", md5_path)),
+            Err(_) => Err(sha1_error),
+        }
+    }
+
+    fn count_failure(&self, reason: &str) {
+        UPSTREAM_FAILURES.with_label_values(&[&self.upstream_label, reason]).inc();
+    }
+
+    /// Requests part of a file, e.g. for serving a range of an artifact without caching it. The
+    ///  data is not validated since checksums refer to the entire file. Upstreams that ignore
+    ///  the range are handled by skipping the data outside of it.
+    pub async fn get_range(&self, path: &str, range: &ByteRange) -> anyhow::Result<BlobRange> {
+        #[cfg(feature = "fault-injection")]
+        if let Some(injector) = &self.fault_injector {
+            injector.inject(FaultOperation::Download, Some(path)).await?;
+        }
+
+        let artifact_path = format!("{}{}", self.base_uri, path);
+        let request = Request::builder()
+            .method("GET")
+            .uri(Uri::try_from(artifact_path.clone())?)
+            .header(USER_AGENT, "curl/7.68.0" )
+            .header(RANGE, range.to_header())
+            .body(Body::empty())?;
+
+        trace!("getting {:?}", request);
+
+        let response = self.client.client().request(request)
+            .await
+            .map_err(|e| RepoError::UpstreamUnavailable(format!("error requesting {}: {}", artifact_path, e)))?;
+        let header = |name| response.headers().get(name).and_then(|h| h.to_str().ok());
+        let last_modified = header(LAST_MODIFIED)
+            .and_then(|s| httpdate::parse_http_date(s).ok());
+
+        let (range, total_size) = match response.status() {
+            StatusCode::NOT_FOUND | StatusCode::GONE => return Err(RepoError::NotFound(artifact_path).into()),
+            StatusCode::PARTIAL_CONTENT | StatusCode::RANGE_NOT_SATISFIABLE => header(CONTENT_RANGE)
+                .and_then(parse_content_range)
+                .ok_or_else(|| RepoError::UpstreamUnavailable(format!("invalid Content-Range from upstream for {}", artifact_path)))?,
+            StatusCode::OK => {
+                let total_size = header(CONTENT_LENGTH)
+                    .and_then(|s| s.parse().ok())
+                    .ok_or_else(|| RepoError::UpstreamUnavailable(format!("upstream ignored range and sent no Content-Length for {}", artifact_path)))?;
+                (range.resolve(total_size), total_size)
+            }
+            status => return Err(RepoError::UpstreamUnavailable(format!("upstream returned status {} for {}", status, artifact_path)).into()),
+        };
+        let range = match range {
+            Some(range) => range,
+            None => return Ok(BlobRange::Unsatisfiable { total_size }),
+        };
+
+        let is_partial = response.status() == StatusCode::PARTIAL_CONTENT;
+        let body = response.into_body()
+            .map(|chunk| chunk.map_err(anyhow::Error::from));
+        Ok(BlobRange::Partial {
+            blob: Blob {
+                data: match is_partial {
+                    true => Box::pin(body),
+                    false => Box::pin(slice_stream(body, range.clone())),
+                },
+                md5: None,
+                sha1: None,
+                size: Some(range.end - range.start),
+                last_modified,
+                upstream_headers: vec![],
+            },
+            range,
+            total_size,
+        })
+    }
+
+    /// Fetches a small resource (e.g. a directory index page) into memory, failing for responses
+    ///  that are not successful or exceed the given size. No validation is performed.
+    pub async fn get_bounded(&self, path: &str, max_len: usize) -> anyhow::Result<Bytes> {
+        #[cfg(feature = "fault-injection")]
+        if let Some(injector) = &self.fault_injector {
+            injector.inject(FaultOperation::Download, Some(path)).await?;
+        }
+
+        let uri = format!("{}{}", self.base_uri, path);
+        let request = Request::builder()
+            .method("GET")
+            .uri(Uri::try_from(uri.clone())?)
+            .header(USER_AGENT, "curl/7.68.0" )
+            .body(Body::empty())?;
+
+        trace!("getting {:?}", request);
+
+        let response = self.client.client().request(request)
+            .await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("upstream returned status {} for {}", response.status(), uri));
+        }
+
+        let mut body = response.into_body();
+        let mut result = BytesMut::new();
+        while let Some(chunk) = body.next().await {
+            result.extend_from_slice(&chunk?);
+            if result.len() > max_len {
+                return Err(anyhow!("response for {} exceeds {} bytes", uri, max_len));
+            }
+        }
+        Ok(result.freeze())
+    }
+}
+
+/// whether a path is a checksum file itself, which has no checksum file
+fn is_checksum_path(path: &str) -> bool {
+    path.rsplit_once('.')
+        .and_then(|(_, extension)| ChecksumKind::for_file_extension(&format!(".{}", extension)))
+        .is_some()
+}
+
+fn upstream_label(base_uri: &str) -> String {
+    Uri::try_from(base_uri).ok()
+        .and_then(|uri| uri.authority().map(|a| a.to_string()))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use futures::TryStreamExt;
+    use hyper::{Response, Server};
+    use hyper::service::{make_service_fn, service_fn};
+    use rstest::rstest;
+
+    use super::*;
+
+    // checksums of 'abc'
+    const ABC_SHA1: &str = "a9993e364706816aba3e25717850c26c9cd0d89d";
+    const ABC_MD5: &str = "900150983cd24fb0d6963f7d28e17f72";
+
+    fn serve(files: &'static [(&'static str, &'static str)]) -> String {
+        let make_service = make_service_fn(move |_| async move {
+            Ok::<_, hyper::Error>(service_fn(move |request: Request<Body>| async move {
+                Ok::<_, hyper::Error>(match files.iter().find(|(path, _)| *path == request.uri().path()) {
+                    Some((_, body)) => Response::new(Body::from(*body)),
+                    None => Response::builder().status(StatusCode::NOT_FOUND).body(Body::empty()).unwrap(),
+                })
+            }))
+        });
+        let server = Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(make_service);
+        let uri = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+        uri
+    }
+
+    async fn download(files: &'static [(&'static str, &'static str)], policy: ChecksumFilePolicy) -> anyhow::Result<Bytes> {
+        let downloader = ValidatingHttpDownloader::new(serve(files)).unwrap()
+            .with_checksum_file_policy(policy);
+        let blob = downloader.get("a/1.0/a-1.0.jar").await?;
+        Ok(blob.data.try_collect::<Vec<_>>().await?.concat().into())
+    }
+
+    #[rstest]
+    #[case::sha1(&[("/a/1.0/a-1.0.jar", "abc"), ("/a/1.0/a-1.0.jar.sha1", ABC_SHA1)], ChecksumFilePolicy::Require, true)]
+    #[case::md5(&[("/a/1.0/a-1.0.jar", "abc"), ("/a/1.0/a-1.0.jar.md5", ABC_MD5)], ChecksumFilePolicy::Require, true)]
+    #[case::mismatch(&[("/a/1.0/a-1.0.jar", "abd"), ("/a/1.0/a-1.0.jar.sha1", ABC_SHA1)], ChecksumFilePolicy::VerifyIfPresent, false)]
+    #[case::missing(&[("/a/1.0/a-1.0.jar", "abc")], ChecksumFilePolicy::VerifyIfPresent, true)]
+    #[case::missing_required(&[("/a/1.0/a-1.0.jar", "abc")], ChecksumFilePolicy::Require, false)]
+    #[case::ignored(&[("/a/1.0/a-1.0.jar", "abd"), ("/a/1.0/a-1.0.jar.sha1", ABC_SHA1)], ChecksumFilePolicy::Ignore, true)]
+    #[tokio::test]
+    async fn test_checksum_file_policy(#[case] files: &'static [(&'static str, &'static str)], #[case] policy: ChecksumFilePolicy, #[case] expected_ok: bool) {
+        assert_eq!(download(files, policy).await.is_ok(), expected_ok);
+    }
+
+    #[rstest]
+    #[case("a/1.0/a-1.0.jar.sha1", true)]
+    #[case("a/1.0/a-1.0.pom.sha512", true)]
+    #[case("a/1.0/a-1.0.jar", false)]
+    fn test_is_checksum_path(#[case] path: &str, #[case] expected: bool) {
+        assert_eq!(is_checksum_path(path), expected);
+    }
 }
@@ -0,0 +1,81 @@
+//! Uses the prelude the way embedders do. If this stops compiling, the change breaks embedders and
+//!  needs a new major version.
+
+use std::sync::Arc;
+
+use bytes::Bytes;
+use rstest::rstest;
+use sha1::{Digest, Sha1};
+
+use arti_vault::prelude::*;
+
+fn data(bytes: impl Into<Bytes>) -> ContentStream {
+    let bytes = bytes.into();
+    Box::pin(futures::stream::iter(vec![Ok(bytes)]))
+}
+
+fn sha1(bytes: &[u8]) -> String {
+    hex::encode(Sha1::digest(bytes))
+}
+
+#[rstest]
+#[case::plain("com/example/lib/1.0/lib-1.0.jar")]
+#[case::classifier("com/example/lib/1.0/lib-1.0-sources.jar")]
+#[case::snapshot("com/example/lib/1.0-SNAPSHOT/lib-1.0-SNAPSHOT-20240101.120000-1.pom")]
+fn test_coordinates_round_trip(#[case] path: &str) {
+    let artifact_ref: MavenArtifactRef = parse_maven_path(path).unwrap();
+    let MavenArtifactRef { coordinates: MavenCoordinates { group_id, artifact_id, version }, classifier, file_extension } = artifact_ref.clone();
+    let _: (MavenGroupId, MavenArtifactId, MavenVersion, MavenClassifier, String) = (group_id, artifact_id, version, classifier, file_extension);
+
+    assert_eq!(as_maven_path(&artifact_ref), path);
+    assert!(path.ends_with(&maven_file_name(&artifact_ref)));
+}
+
+#[tokio::test]
+async fn test_embedded_hosted_repo() {
+    let blob_storage: Arc<dyn BlobStorage<Uuid>> = Arc::new(TransientBlobStorage::new());
+    let metadata_store: Arc<dyn RemoteRepoMetadataStore> = Arc::new(DummyRemoteRepoMetadataStore::new());
+    let repo = HostedMavenRepo::new("internal".to_string(), blob_storage, metadata_store);
+
+    assert_eq!(repo.deploy("com/example/lib/1.0/lib-1.0.jar", data("jar")).await.unwrap(), DeployOutcome::Pending);
+    assert_eq!(repo.deploy("com/example/lib/1.0/lib-1.0.jar.sha1", data(sha1(b"jar"))).await.unwrap(), DeployOutcome::Staged);
+    assert_eq!(repo.deploy("com/example/lib/1.0/lib-1.0.pom", data("pom")).await.unwrap(), DeployOutcome::Pending);
+    assert_eq!(repo.deploy("com/example/lib/1.0/lib-1.0.pom.sha1", data(sha1(b"pom"))).await.unwrap(), DeployOutcome::Staged);
+    assert!(matches!(repo.deploy("com/example/lib/maven-metadata.xml", data("<metadata/>")).await.unwrap(), DeployOutcome::Committed(_)));
+
+    let jar = parse_maven_path("com/example/lib/1.0/lib-1.0.jar").unwrap();
+    let blob: Blob = repo.get_artifact(&jar).await.unwrap().unwrap();
+    assert_eq!(blob.sha1.map(hex::encode), Some(sha1(b"jar")));
+}
+
+#[test]
+fn test_signatures() {
+    let _: fn(&str) -> anyhow::Result<MavenArtifactRef> = parse_maven_path;
+    let _: fn(&MavenArtifactRef) -> String = as_maven_path;
+    let _: fn(&MavenArtifactRef) -> String = maven_file_name;
+    let _: fn(String, Arc<dyn BlobStorage<Uuid>>, Arc<dyn RemoteRepoMetadataStore>) -> HostedMavenRepo = HostedMavenRepo::new;
+    let _: fn(String, Arc<dyn BlobStorage<Uuid>>, Arc<dyn RemoteRepoMetadataStore>) -> anyhow::Result<RemoteMavenRepo> = RemoteMavenRepo::new;
+    let _: fn(RepositoryManagerConfig) -> anyhow::Result<RepositoryManager> = RepositoryManager::new;
+}
+
+#[test]
+fn test_traits_are_object_safe() {
+    fn assert_send_sync<T: Send + Sync + ?Sized>() {}
+    assert_send_sync::<dyn BlobStorage<Uuid>>();
+    assert_send_sync::<dyn RemoteRepoMetadataStore>();
+
+    let storage = TransientBlobStorage::new();
+    let _: &dyn BlobStorage<Uuid> = &storage;
+}
+
+#[test]
+fn test_error_and_checksum_types() {
+    let _: Option<&RepoError> = None;
+    let _: Option<DeleteOutcome> = None;
+    let _: Option<BlobStat> = None;
+    let _: Option<BlobStream> = None;
+    let _: Option<(RepositoryConfig, RepositoryKind)> = None;
+    for kind in [ChecksumKind::Sha1, ChecksumKind::Md5] {
+        assert!(!kind.checksum_of(b"abc").is_empty());
+    }
+}